@@ -3,9 +3,10 @@ use std::{collections::HashMap, ops::Bound};
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
 use syn::{
-    parenthesized,
+    bracketed, parenthesized,
     parse::{Parse, ParseStream},
     parse_macro_input,
+    punctuated::Punctuated,
     token::{Comma, Paren},
     Attribute, Expr, ExprLit, ExprRange, Ident, Lit, LitInt, Meta, MetaNameValue, Path,
     RangeLimits, Token,
@@ -82,8 +83,63 @@ enum Access {
     RW,
     RO,
     WO,
+    /// Read, write 1 to clear.
     RW1C,
+    /// Read, write 1 to activate (hardware clears it once done).
     RW1O,
+    /// Read, write 1 to set (writing 0 has no effect).
+    RW1S,
+    /// Write only, write 1 to trigger a one-shot action.
+    W1O,
+    /// Read only, reading clears the value.
+    RC,
+}
+
+impl Access {
+    /// If the field can be `(written, read)`.
+    fn write_read(&self) -> (bool, bool) {
+        match self {
+            Access::RO | Access::RC => (false, true),
+            Access::WO | Access::W1O => (true, false),
+            _ => (true, true),
+        }
+    }
+
+    /// Fields where writing a 1 has a side effect, so a read-modify-write of their register
+    /// must write them back as 0 unless they are the field being written.
+    fn is_write_one(&self) -> bool {
+        matches!(
+            self,
+            Access::RW1C | Access::RW1O | Access::RW1S | Access::W1O
+        )
+    }
+
+    /// Reading this field has a side effect, so getters take `&mut self`.
+    fn is_read_to_clear(&self) -> bool {
+        matches!(self, Access::RC)
+    }
+
+    /// The `(setter_prefix, setter_takes_no_value, getter_prefix, getter_suffix)` names used
+    /// for single bit fields.
+    fn single_names(&self) -> (&'static str, bool, &'static str, &'static str) {
+        match self {
+            Access::RW1C => ("clear", true, "is_", "_active"),
+            Access::RW1O => ("activate", true, "is_", "_pending"),
+            Access::RW1S => ("set", true, "is_", "_set"),
+            Access::W1O => ("trigger", true, "", ""),
+            Access::RC => ("", false, "take_", ""),
+            _ => ("set", false, "get_", ""),
+        }
+    }
+
+    /// The `(setter_prefix, getter_prefix)` names used for multi-bit fields.
+    fn range_names(&self) -> (&'static str, &'static str) {
+        match self {
+            Access::W1O => ("trigger", "get_"),
+            Access::RC => ("set", "take_"),
+            _ => ("set", "get_"),
+        }
+    }
 }
 
 mod access {
@@ -92,6 +148,9 @@ mod access {
     syn::custom_keyword!(WO);
     syn::custom_keyword!(RW1C);
     syn::custom_keyword!(RW1O);
+    syn::custom_keyword!(RW1S);
+    syn::custom_keyword!(W1O);
+    syn::custom_keyword!(RC);
 }
 
 impl Parse for Access {
@@ -111,8 +170,19 @@ impl Parse for Access {
         } else if input.peek(access::RW1O) {
             input.parse::<access::RW1O>()?;
             Ok(Access::RW1O)
+        } else if input.peek(access::RW1S) {
+            input.parse::<access::RW1S>()?;
+            Ok(Access::RW1S)
+        } else if input.peek(access::W1O) {
+            input.parse::<access::W1O>()?;
+            Ok(Access::W1O)
+        } else if input.peek(access::RC) {
+            input.parse::<access::RC>()?;
+            Ok(Access::RC)
         } else {
-            Err(input.error("Not a valid access type, please use 'RO', 'WO', 'RW1C', or 'RW1O'"))
+            Err(input.error(
+                "Not a valid access type, please use 'RW', 'RO', 'WO', 'RW1C', 'RW1O', 'RW1S', 'W1O', or 'RC'",
+            ))
         }
     }
 }
@@ -154,18 +224,186 @@ impl Parse for BitAttribute {
     }
 }
 
+#[derive(Debug)]
+struct ValueVariant {
+    name: Ident,
+    value: usize,
+}
+
+impl Parse for ValueVariant {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let value: LitInt = input.parse()?;
+
+        Ok(Self {
+            name,
+            value: value.base10_parse()?,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct ValuesAttribute {
+    enum_name: Option<Ident>,
+    variants: Vec<ValueVariant>,
+}
+
+impl Parse for ValuesAttribute {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let enum_name = if input.peek(Ident) && input.peek2(Token![:]) {
+            let name = input.parse()?;
+            input.parse::<Token![:]>()?;
+            Some(name)
+        } else {
+            None
+        };
+
+        let variants: Vec<ValueVariant> = input
+            .parse_terminated(ValueVariant::parse, Comma)?
+            .into_iter()
+            .collect();
+
+        if variants.is_empty() {
+            return Err(input.error("#[values(...)] requires at least one variant!"));
+        }
+
+        Ok(Self {
+            enum_name,
+            variants,
+        })
+    }
+}
+
+#[derive(Debug)]
+enum ArrayLayout {
+    /// Each element is a whole register, `stride` bytes after the last.
+    Stride(usize),
+    /// Each element is a whole register at the given byte offsets, for arrays with holes.
+    Offsets(Vec<usize>),
+    /// Each element is a field in the same register, `bit_stride` bits after the last.
+    BitStride(usize),
+}
+
+#[derive(Debug)]
+struct ArrayAttribute {
+    count: usize,
+    layout: ArrayLayout,
+}
+
+mod array {
+    syn::custom_keyword!(count);
+    syn::custom_keyword!(stride);
+    syn::custom_keyword!(bit_stride);
+    syn::custom_keyword!(offsets);
+}
+
+impl Parse for ArrayAttribute {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut count: Option<usize> = None;
+        let mut layout: Option<ArrayLayout> = None;
+
+        while !input.is_empty() {
+            let lookahead = input.lookahead1();
+            let new_layout = if lookahead.peek(array::count) {
+                input.parse::<array::count>()?;
+                input.parse::<Token![=]>()?;
+                count = Some(input.parse::<LitInt>()?.base10_parse()?);
+                None
+            } else if lookahead.peek(array::stride) {
+                input.parse::<array::stride>()?;
+                input.parse::<Token![=]>()?;
+                Some(ArrayLayout::Stride(
+                    input.parse::<LitInt>()?.base10_parse()?,
+                ))
+            } else if lookahead.peek(array::bit_stride) {
+                input.parse::<array::bit_stride>()?;
+                input.parse::<Token![=]>()?;
+                Some(ArrayLayout::BitStride(
+                    input.parse::<LitInt>()?.base10_parse()?,
+                ))
+            } else if lookahead.peek(array::offsets) {
+                input.parse::<array::offsets>()?;
+                input.parse::<Token![=]>()?;
+                let content;
+                let _ = bracketed!(content in input);
+                let offsets = content
+                    .parse_terminated(LitInt::parse, Comma)?
+                    .into_iter()
+                    .map(|offset| offset.base10_parse())
+                    .collect::<syn::Result<Vec<usize>>>()?;
+                Some(ArrayLayout::Offsets(offsets))
+            } else {
+                return Err(lookahead.error());
+            };
+
+            if let Some(new_layout) = new_layout {
+                if layout.is_some() {
+                    return Err(input
+                        .error("Only one of 'stride', 'bit_stride', or 'offsets' can be given!"));
+                }
+                layout = Some(new_layout);
+            }
+
+            if !input.is_empty() {
+                input.parse::<Comma>()?;
+            }
+        }
+
+        let layout = layout.ok_or(
+            input.error("#[array(...)] requires one of 'stride', 'bit_stride', or 'offsets'!"),
+        )?;
+
+        let count = match (&layout, count) {
+            (ArrayLayout::Offsets(offsets), None) => offsets.len(),
+            (ArrayLayout::Offsets(offsets), Some(count)) if count != offsets.len() => {
+                return Err(input.error("'count' does not match the number of 'offsets'!"));
+            }
+            (_, Some(count)) => count,
+            (_, None) => return Err(input.error("#[array(...)] requires a 'count'!")),
+        };
+
+        if count == 0 {
+            return Err(input.error("#[array(...)] requires at least one element!"));
+        }
+
+        Ok(Self { count, layout })
+    }
+}
+
 #[derive(Debug)]
 struct BitBlock {
     doc_attr: Vec<String>,
     bit_attr: BitAttribute,
+    values_attr: Option<ValuesAttribute>,
+    array_attr: Option<ArrayAttribute>,
+    instances_attr: Option<Vec<Path>>,
     name: Ident,
 }
 
+impl BitBlock {
+    /// The name of the enum generated for a `#[values(...)]` field. Either the name given
+    /// in the attribute, or the field name in `PascalCase`.
+    fn values_enum_name(&self) -> Option<Ident> {
+        let values = self.values_attr.as_ref()?;
+
+        Some(
+            values
+                .enum_name
+                .clone()
+                .unwrap_or_else(|| string_into_pascal(&self.name.to_string())),
+        )
+    }
+}
+
 impl Parse for BitBlock {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let attributes = input.call(Attribute::parse_outer)?;
         let mut doc_attr: Vec<String> = Vec::new();
         let mut bit_attr: Option<BitAttribute> = None;
+        let mut values_attr: Option<ValuesAttribute> = None;
+        let mut array_attr: Option<ArrayAttribute> = None;
+        let mut instances_attr: Option<Vec<Path>> = None;
 
         for attr in &attributes {
             if attr.path().is_ident("doc") {
@@ -184,15 +422,68 @@ impl Parse for BitBlock {
                 doc_attr.push(format!(" {}", string.value().trim_start()));
             } else if attr.path().is_ident("bit") {
                 bit_attr = Some(attr.parse_args()?);
+            } else if attr.path().is_ident("values") {
+                values_attr = Some(attr.parse_args()?);
+            } else if attr.path().is_ident("array") {
+                array_attr = Some(attr.parse_args()?);
+            } else if attr.path().is_ident("instances") {
+                instances_attr = Some(
+                    attr.parse_args_with(Punctuated::<Path, Comma>::parse_terminated)?
+                        .into_iter()
+                        .collect(),
+                );
             } else {
                 return Err(input.error("Unknown attribute"));
             }
         }
 
+        let bit_attr: BitAttribute = bit_attr
+            .ok_or(input.error("Reqires a #[bit(...)] attribute before a name (ie. Ident)."))?;
+
+        if let Some(values) = &values_attr {
+            let BitRange::Range(range) = bit_attr.bit else {
+                return Err(input
+                    .error("#[values(...)] can only be used on multi-bit fields (ie. '0..=3')!"));
+            };
+
+            let (start, end) = get_real_range(range);
+            let max_value = (1usize << (end - start + 1)) - 1;
+
+            if let Some(variant) = values.variants.iter().find(|v| v.value > max_value) {
+                return Err(input.error(format!(
+                    "Value {} of '{}' does not fit in bits {start}..={end}!",
+                    variant.value, variant.name
+                )));
+            }
+        }
+
+        if let Some(ArrayAttribute {
+            count,
+            layout: ArrayLayout::BitStride(bit_stride),
+        }) = &array_attr
+        {
+            let (start, end) = match bit_attr.bit {
+                BitRange::Range(range) => get_real_range(range),
+                BitRange::Single(single) => (single, single),
+            };
+
+            if *bit_stride <= end - start {
+                return Err(input.error("'bit_stride' would make array elements overlap!"));
+            }
+            if end + (count - 1) * bit_stride > 31 {
+                return Err(input.error(format!(
+                    "The last element of the array would end at bit {}, past the end of the register!",
+                    end + (count - 1) * bit_stride
+                )));
+            }
+        }
+
         Ok(Self {
             doc_attr,
-            bit_attr: bit_attr
-                .ok_or(input.error("Reqires a #[bit(...)] attribute before a name (ie. Ident)."))?,
+            bit_attr,
+            values_attr,
+            array_attr,
+            instances_attr,
             name: input.parse()?,
         })
     }
@@ -255,26 +546,171 @@ pub fn make_device(input: TokenStream) -> TokenStream {
 
     let register_fields = generate_reg_fields(&register_names);
     let registers_struct = generate_reg_struct(&register_fields);
-    let bit_impl: Vec<proc_macro2::TokenStream> =
-        parsed_scope.bits.iter().map(generate_bit).collect();
+    let (bit_consts, bit_impl): (Vec<_>, Vec<_>) =
+        parsed_scope.bits.iter().map(generate_bit).unzip();
 
     let set_masks = generate_set_masks(&parsed_scope.bits);
+    let instance_fn = generate_instance_constructer(
+        &register_fields,
+        &parsed_scope.device_ports,
+        generate_instance_addresses(&parsed_scope.bits),
+    );
     let new_fn = generate_new_constructer(&register_fields, parsed_scope.device_ports);
+    let value_enums = match generate_value_enums(&parsed_scope.bits) {
+        Ok(value_enums) => value_enums,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let (modify_proxies, modify_fns) = generate_modify(&parsed_scope.bits);
+    let (snapshot_struct, snapshot_fn) = generate_snapshot(&parsed_scope.bits);
+    let raw_fns = generate_raw_access(&register_fields);
+    let instance_checks = generate_instance_checks(&parsed_scope.bits);
 
     let emit = quote! {
         #registers_struct
+        #value_enums
+        #modify_proxies
+        #snapshot_struct
 
         impl Registers {
             #new_fn
 
             #set_masks
+            #(#bit_consts)*
+        }
+
+        #instance_fn
+
+        impl<B: hal_macros::BaseAddress> Registers<B> {
             #(#bit_impl)*
+            #modify_fns
+            #snapshot_fn
+            #raw_fns
+            #instance_checks
         }
     };
 
     emit.into()
 }
 
+fn generate_raw_access(register_fields: &[(Ident, Path)]) -> proc_macro2::TokenStream {
+    let Some((first_register, _)) = register_fields.first() else {
+        return quote!();
+    };
+
+    quote! {
+        /// # Base Address
+        /// The address of this device, every register offset is relative to it.
+        #[inline(always)]
+        pub fn base_address(&self) -> usize {
+            self.#first_register.base_address()
+        }
+
+        /// # As Ptr
+        /// A pointer to the 32-bit register at `offset` bytes from the base address of
+        /// this device. Meant for reaching registers the HAL has not modeled yet.
+        #[inline(always)]
+        pub fn as_ptr(&self, offset: usize) -> *const u32 {
+            debug_assert!(offset % 4 == 0, "Register offset {offset:#x} is not word aligned!");
+            hal_macros::mmio_ptr(self.base_address() + offset)
+        }
+
+        /// # As Mut Ptr
+        /// A mutable pointer to the 32-bit register at `offset` bytes from the base address
+        /// of this device. Meant for reaching registers the HAL has not modeled yet.
+        #[inline(always)]
+        pub fn as_mut_ptr(&mut self, offset: usize) -> *mut u32 {
+            debug_assert!(offset % 4 == 0, "Register offset {offset:#x} is not word aligned!");
+            hal_macros::mmio_ptr(self.base_address() + offset)
+        }
+
+        /// # Read Raw
+        /// Read the 32-bit register at `offset` bytes from the base address of this device,
+        /// without decoding any of its fields.
+        ///
+        /// # Safety
+        /// The caller must ensure `offset` is a readable register of this device, and that
+        /// reading it has no side effects (ie. read-to-clear fields) the HAL relies on.
+        ///
+        /// # Volatile
+        /// This function only preforms **1** volatile *read*.
+        ///
+        #[inline(always)]
+        pub unsafe fn read_raw(&self, offset: usize) -> u32 {
+            let ptr = self.as_ptr(offset);
+            let value = core::ptr::read_volatile(ptr);
+            hal_macros::trace::read(ptr, &value);
+            value
+        }
+
+        /// # Write Raw
+        /// Write the 32-bit register at `offset` bytes from the base address of this
+        /// device, replacing every field in it.
+        ///
+        /// # Safety
+        /// The caller must ensure `offset` is a writable register of this device. It is up
+        /// to the caller to verify that this register write will not cause any side
+        /// effects, or break the assumptions of the rest of the HAL.
+        ///
+        /// # Volatile
+        /// This function only preforms **1** volatile *write*.
+        ///
+        #[inline(always)]
+        pub unsafe fn write_raw(&mut self, offset: usize, value: u32) {
+            let ptr = self.as_mut_ptr(offset);
+            hal_macros::trace::write(ptr, &value);
+            core::ptr::write_volatile(ptr, value)
+        }
+    }
+}
+
+fn generate_instance_constructer(
+    register_fields: &[(Ident, Path)],
+    device_ports: &DevicePorts,
+    addresses: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let device_ports_vec = &device_ports.0;
+    let fields: Vec<_> = register_fields
+        .iter()
+        .map(|(ident, _)| quote!(#ident : RW::new_const()))
+        .collect();
+
+    quote!(
+        /// # Instance
+        /// A zero sized handle to the device at the base address `PORT`. Every register
+        /// address is known at compile time, so they fold into immediates and no runtime
+        /// port checks are needed.
+        pub type Instance<const PORT: usize> = Registers<hal_macros::ConstAddress<PORT>>;
+
+        impl<const PORT: usize> Registers<hal_macros::ConstAddress<PORT>> {
+            #[allow(dead_code)]
+            const PORT_IS_VALID: () = assert!(
+                false #( || #device_ports_vec == PORT)*,
+                "Register port is not one of this device's ports!"
+            );
+
+            /// # Instance
+            /// Make the zero sized handle to the device at the base address `PORT`.
+            ///
+            /// # Panics
+            /// The port is checked at compile time, using a port that is not one of the
+            /// `device_ports` of this device will fail to compile. Like `new`, checking
+            /// is disabled in testing mode.
+            #[inline(always)]
+            pub const fn instance() -> Self {
+                #[cfg(not(test))]
+                #[allow(clippy::let_unit_value)]
+                let () = Self::PORT_IS_VALID;
+
+                Self {
+                    #(#fields,)*
+                }
+            }
+
+            #addresses
+        }
+    )
+}
+
 fn generate_new_constructer(
     register_fields: &[(Ident, Path)],
     device_ports: DevicePorts,
@@ -340,34 +776,41 @@ fn generate_set_masks(bit: &[BitBlock]) -> proc_macro2::TokenStream {
 
     for b in bit.iter() {
         let key = b.bit_attr.register_name.to_string();
-        match b.bit_attr.access {
-            Access::RW1C | Access::RW1O => {
-                let bit_or_mask = {
-                    match b.bit_attr.bit {
-                        BitRange::Range(range) => {
-                            let (start, end) = get_real_range(range);
-
-                            let mut mask: u32 = 1;
-                            for _ in 0..(end - start) {
-                                mask <<= 1;
-                                mask |= 1;
-                            }
-
-                            mask << start
+        if b.bit_attr.access.is_write_one() {
+            let bit_or_mask = {
+                match b.bit_attr.bit {
+                    BitRange::Range(range) => {
+                        let (start, end) = get_real_range(range);
+
+                        let mut mask: u32 = 1;
+                        for _ in 0..(end - start) {
+                            mask <<= 1;
+                            mask |= 1;
                         }
-                        BitRange::Single(single) => 1 << (single as u32),
-                    }
-                };
 
-                if let Some(bit) = bit_map.get_mut(&key) {
-                    *bit |= bit_or_mask;
-                } else {
-                    bit_map.insert(key, bit_or_mask);
+                        mask << start
+                    }
+                    BitRange::Single(single) => 1 << (single as u32),
                 }
+            };
+
+            let bit_or_mask = match &b.array_attr {
+                Some(ArrayAttribute {
+                    count,
+                    layout: ArrayLayout::BitStride(bit_stride),
+                }) => (0..*count).fold(0, |mask, index| {
+                    mask | (bit_or_mask << (index * bit_stride) as u32)
+                }),
+                _ => bit_or_mask,
+            };
+
+            if let Some(bit) = bit_map.get_mut(&key) {
+                *bit |= bit_or_mask;
+            } else {
+                bit_map.insert(key, bit_or_mask);
             }
-            _ => {
-                bit_map.entry(key).or_insert(0);
-            }
+        } else {
+            bit_map.entry(key).or_insert(0);
         }
     }
 
@@ -386,13 +829,86 @@ fn generate_set_masks(bit: &[BitBlock]) -> proc_macro2::TokenStream {
     generating
 }
 
-fn generate_bit(bit: &BitBlock) -> proc_macro2::TokenStream {
-    match bit.bit_attr.bit {
-        BitRange::Range(range) => generate_bit_range(range, bit),
-        BitRange::Single(single) => generate_bit_single(single, bit),
+/// The `(consts, accessors)` of a field. Consts don't depend on the base address, so they
+/// are kept on the plain `Registers` type where they can be named without generics.
+fn generate_bit(bit: &BitBlock) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let (consts, accessors) = if bit.array_attr.is_some() {
+        generate_bit_array(bit)
+    } else {
+        match bit.bit_attr.bit {
+            BitRange::Range(range) => generate_bit_range(range, bit),
+            BitRange::Single(single) => generate_bit_single(single, bit),
+        }
+    };
+
+    let layout_consts = generate_layout_consts(bit);
+    (quote!(#consts #layout_consts), accessors)
+}
+
+/// The consts DMA descriptors, linker scripts, or inline assembly need to find a field
+/// without going through the accessors. Ranges already have a mask, so single bits get
+/// one too, and every field gets the offset of its register.
+fn generate_layout_consts(bit: &BitBlock) -> proc_macro2::TokenStream {
+    let const_name = bit.name.to_string().to_uppercase();
+    let path = &bit.bit_attr.path;
+    let offset_name = format_ident!("{}_OFFSET", const_name);
+    let offset_title = string_into_title(&format!("{} offset", bit.name));
+    let offset_doc = format!(
+        " The offset of the register holding `{}`, from the base address of the device.",
+        bit.name
+    );
+    let array_doc = if bit.array_attr.is_some() {
+        quote!(
+            /// For arrays this is the register of element `0`.
+        )
+    } else {
+        quote!()
+    };
+
+    let mask = if let BitRange::Single(single) = bit.bit_attr.bit {
+        let mask_name = format_ident!("{}_BIT_MASK", const_name);
+        let mask_title = string_into_title(&format!("{} bit mask", bit.name));
+        let mask_doc = format!(" The mask of `{}` in its register.", bit.name);
+        let mask: usize = 1 << single;
+        quote! {
+            #mask_title
+            #[doc = #mask_doc]
+            #array_doc
+            pub const #mask_name: usize = #mask;
+        }
+    } else {
+        quote!()
+    };
+
+    quote! {
+        #offset_title
+        #[doc = #offset_doc]
+        #array_doc
+        pub const #offset_name: usize = #path;
+
+        #mask
     }
 }
 
+/// `<FIELD>_ADDRESS` consts for a `Instance`, where the base address is known.
+fn generate_instance_addresses(bits: &[BitBlock]) -> proc_macro2::TokenStream {
+    let addresses = bits.iter().map(|bit| {
+        let const_name = bit.name.to_string().to_uppercase();
+        let offset_name = format_ident!("{}_OFFSET", const_name);
+        let address_name = format_ident!("{}_ADDRESS", const_name);
+        let title = string_into_title(&format!("{} address", bit.name));
+        let doc = format!(" The address of the register holding `{}`.", bit.name);
+
+        quote! {
+            #title
+            #[doc = #doc]
+            pub const #address_name: usize = PORT + Registers::#offset_name;
+        }
+    });
+
+    quote!(#(#addresses)*)
+}
+
 fn get_real_range(range: (Bound<usize>, Bound<usize>)) -> (usize, usize) {
     let start = match range.0 {
         Bound::Unbounded => 0,
@@ -408,6 +924,21 @@ fn get_real_range(range: (Bound<usize>, Bound<usize>)) -> (usize, usize) {
     (start, end)
 }
 
+fn string_into_pascal(name: &str) -> Ident {
+    let name: String = name
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect();
+
+    format_ident!("{}", name)
+}
+
 fn string_into_title(name: &str) -> proc_macro2::TokenStream {
     let name = format!(
         " # {}",
@@ -494,6 +1025,8 @@ fn generate_range_get(
     let self_shift = format_ident!("{}_BIT_START", const_name);
     let doc_title = string_into_title(name.to_string().as_str());
     let doc = generate_doc_strings(&bit.doc_attr);
+    let (receiver, read_doc) = generate_read_receiver(bit);
+    let trace = generate_field_prelude(bit);
     quote! {
         #doc_title
         #doc
@@ -510,10 +1043,12 @@ fn generate_range_get(
         /// This function only preforms **1** volatile *read* and immediately copies
         /// the value and extracts the bits to return the result.
         ///
+        #read_doc
         #[inline(always)]
-        pub fn #name(&self) -> #bit_type {
+        pub fn #name(#receiver) -> #bit_type {
             use hal_macros::VolatileRead;
-            (((self.#self_dot.read() as usize) & <Self>::#self_mask) >> <Self>::#self_shift) as #bit_type
+            #trace
+            (((self.#self_dot.read() as usize) & Registers::#self_mask) >> Registers::#self_shift) as #bit_type
         }
     }
 }
@@ -525,6 +1060,8 @@ fn generate_single_get(name: &str, bit: &BitBlock) -> proc_macro2::TokenStream {
     let self_shift = format_ident!("{}_BIT", const_name);
     let doc_title = string_into_title(name.to_string().as_str());
     let doc = generate_doc_strings(&bit.doc_attr);
+    let (receiver, read_doc) = generate_read_receiver(bit);
+    let trace = generate_field_prelude(bit);
     quote! {
         #doc_title
         #doc
@@ -541,10 +1078,12 @@ fn generate_single_get(name: &str, bit: &BitBlock) -> proc_macro2::TokenStream {
         /// This function only preforms **1** volatile *read* and immediately copies
         /// the value and extracts the bits to return the result.
         ///
+        #read_doc
         #[inline(always)]
-        pub fn #name(&self) -> bool {
+        pub fn #name(#receiver) -> bool {
             use hal_macros::VolatileRead;
-            (self.#self_dot.read() & (1u32 << <Self>::#self_shift)) != 0
+            #trace
+            (self.#self_dot.read() & (1u32 << Registers::#self_shift)) != 0
         }
     }
 }
@@ -574,6 +1113,7 @@ fn generate_single_set(name: &str, bit: &BitBlock, only_gen_one: bool) -> proc_m
     } else {
         quote!(flag)
     };
+    let trace = generate_field_prelude(bit);
     quote! {
         #doc_title
         #doc
@@ -603,8 +1143,9 @@ fn generate_single_set(name: &str, bit: &BitBlock, only_gen_one: bool) -> proc_m
         #[inline(always)]
         pub unsafe fn #name(&mut self #param) {
             use hal_macros::{VolatileRead, VolatileWrite};
-            let read_value: u32 = self.#self_dot.read() & (<Self>::#self_mask as u32);
-            let flag_value: u32 = 1 << (<Self>::#self_shift as u32);
+            #trace
+            let read_value: u32 = self.#self_dot.read() & (Registers::#self_mask as u32);
+            let flag_value: u32 = 1 << (Registers::#self_shift as u32);
             let write = if #flag_or_true {
                 read_value | flag_value
             } else {
@@ -631,6 +1172,7 @@ fn generate_range_set(
     let self_set_mask = format_ident!("{}_SET_MASK", const_reg_name);
     let doc_title = string_into_title(name.to_string().as_str());
     let doc = generate_doc_strings(&bit.doc_attr);
+    let trace = generate_field_prelude(bit);
     quote! {
         #doc_title
         #doc
@@ -667,14 +1209,734 @@ fn generate_range_set(
         #[inline(always)]
         pub unsafe fn #name(&mut self, flag: #bit_type) {
             use hal_macros::{VolatileRead, VolatileWrite};
-            debug_assert!((flag as usize) >> ((<Self>::#self_end) - <Self>::#self_shift) <= 1, "Provided flag {flag} is too large for provided setter range {}..={}!", #start, #end);
-            let flag_shift: u32 = (flag as u32) << (<Self>::#self_shift as u32);
-            let read_value: u32 = self.#self_dot.read() & (!<Self>::#self_mask as u32) & (<Self>::#self_set_mask as u32);
+            #trace
+            debug_assert!((flag as usize) >> ((Registers::#self_end) - Registers::#self_shift) <= 1, "Provided flag {flag} is too large for provided setter range {}..={}!", #start, #end);
+            let flag_shift: u32 = (flag as u32) << (Registers::#self_shift as u32);
+            let read_value: u32 = self.#self_dot.read() & (!Registers::#self_mask as u32) & (Registers::#self_set_mask as u32);
             self.#self_dot.write(read_value | flag_shift);
         }
     }
 }
 
+/// The return type of a `#[values(...)]` getter, and the conversion from `value` into it.
+/// Fields where every bit pattern has a enumerated value return the enum directly,
+/// otherwise `Option<Enum>`.
+fn generate_values_convert(
+    bit: &BitBlock,
+    (start, end): (usize, usize),
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let enum_name = bit.values_enum_name();
+    let is_exhaustive = bit
+        .values_attr
+        .as_ref()
+        .is_some_and(|values| values.variants.len() == 1 << (end - start + 1));
+
+    if is_exhaustive {
+        (
+            quote!(#enum_name),
+            quote!(match #enum_name::from_bits(value) {
+                Some(value) => value,
+                None => unreachable!(),
+            }),
+        )
+    } else {
+        (
+            quote!(Option<#enum_name>),
+            quote!(#enum_name::from_bits(value)),
+        )
+    }
+}
+
+fn generate_values_get(
+    name: &str,
+    bit: &BitBlock,
+    (start, end): (usize, usize),
+) -> proc_macro2::TokenStream {
+    let name = format_ident!("{}", name.to_lowercase().replace(' ', "_"));
+    let bit_type = min_type_for_range((start, end));
+    let self_dot = format_ident!("{}", bit.bit_attr.register_name);
+    let const_name = bit.name.to_string().to_uppercase().replace(' ', "_");
+    let self_mask = format_ident!("{}_BIT_MASK", const_name);
+    let self_shift = format_ident!("{}_BIT_START", const_name);
+    let doc_title = string_into_title(name.to_string().as_str());
+    let doc = generate_doc_strings(&bit.doc_attr);
+    let (receiver, read_doc) = generate_read_receiver(bit);
+    let (return_type, convert) = generate_values_convert(bit, (start, end));
+
+    let trace = generate_field_prelude(bit);
+    quote! {
+        #doc_title
+        #doc
+        ///
+        /// # Get
+        /// Gets the value from the given register as its enumerated value. If not every
+        /// bit pattern has a enumerated value, `None` is returned for the others.
+        ///
+        /// # Safety
+        /// It is ultimately up to the caller to ensure this function will
+        /// never cause any side effects. However, usually reading from
+        /// registers does not modify any processor state (just looks at it).
+        ///
+        /// # Volatile
+        /// This function only preforms **1** volatile *read* and immediately copies
+        /// the value and extracts the bits to return the result.
+        ///
+        #read_doc
+        #[inline(always)]
+        pub fn #name(#receiver) -> #return_type {
+            use hal_macros::VolatileRead;
+            #trace
+            let value = (((self.#self_dot.read() as usize) & Registers::#self_mask) >> Registers::#self_shift) as #bit_type;
+            #convert
+        }
+    }
+}
+
+fn generate_values_set(name: &str, bit: &BitBlock) -> proc_macro2::TokenStream {
+    let name = format_ident!("{}", name.to_lowercase().replace(' ', "_"));
+    let self_dot = format_ident!("{}", bit.bit_attr.register_name);
+    let const_name = bit.name.to_string().to_uppercase().replace(' ', "_");
+    let self_mask = format_ident!("{}_BIT_MASK", const_name);
+    let self_shift = format_ident!("{}_BIT_START", const_name);
+    let const_reg_name = bit.bit_attr.register_name.to_uppercase().replace(' ', "_");
+    let self_set_mask = format_ident!("{}_SET_MASK", const_reg_name);
+    let enum_name = bit.values_enum_name();
+    let doc_title = string_into_title(name.to_string().as_str());
+    let doc = generate_doc_strings(&bit.doc_attr);
+    let trace = generate_field_prelude(bit);
+    quote! {
+        #doc_title
+        #doc
+        ///
+        /// # Set
+        /// Set the enumerated value into the given register.
+        ///
+        /// # Safety
+        /// It is up to the caller to verify that this register write will not
+        /// cause any side effects. There could be an event that setting this
+        /// register could cause undefined behavior elsewhere in the program.
+        ///
+        /// This register will deference the given `ptr` + `offset`, so one
+        /// must verify at compile time that the given `ptr` falls within
+        /// acceptable memory ranges.
+        ///
+        /// ## Other Register State
+        /// In some examples it is true that ones register state depends on another
+        /// register's status. In these cases, it is up to the caller to properly
+        /// set this register to a valid (and ONLY valid value).
+        ///
+        /// # Volatile
+        /// This function only preforms **1** volatile *read*,
+        /// immediately modifies the flag and does **1** volatile *write* using
+        /// the internal provided function to register.
+        ///
+        #[inline(always)]
+        pub unsafe fn #name(&mut self, value: #enum_name) {
+            use hal_macros::{VolatileRead, VolatileWrite};
+            #trace
+            let flag_shift: u32 = (value.into_bits() as u32) << (Registers::#self_shift as u32);
+            let read_value: u32 = self.#self_dot.read() & (!Registers::#self_mask as u32) & (Registers::#self_set_mask as u32);
+            self.#self_dot.write(read_value | flag_shift);
+        }
+    }
+}
+
+fn generate_value_enums(bits: &[BitBlock]) -> syn::Result<proc_macro2::TokenStream> {
+    let mut generated: HashMap<String, (String, Vec<(String, usize)>)> = HashMap::new();
+    let mut enums: Vec<proc_macro2::TokenStream> = Vec::new();
+
+    for bit in bits.iter() {
+        let (Some(values), Some(enum_name), BitRange::Range(range)) =
+            (&bit.values_attr, bit.values_enum_name(), &bit.bit_attr.bit)
+        else {
+            continue;
+        };
+
+        let bit_type = min_type_for_range(get_real_range(*range));
+        let variant_list: Vec<(String, usize)> = values
+            .variants
+            .iter()
+            .map(|variant| (variant.name.to_string(), variant.value))
+            .collect();
+
+        // Fields that share a enum (ie. TimerA and TimerB modes) must agree on it.
+        if let Some((other_type, other_variants)) = generated.get(&enum_name.to_string()) {
+            if *other_type != bit_type.to_string() || *other_variants != variant_list {
+                return Err(syn::Error::new_spanned(
+                    &bit.name,
+                    format!("Enum '{enum_name}' was already defined with different values!"),
+                ));
+            }
+            continue;
+        }
+        generated.insert(enum_name.to_string(), (bit_type.to_string(), variant_list));
+
+        let doc_title = format!(" # {enum_name}");
+        let doc = generate_doc_strings(&bit.doc_attr);
+        let variant_names: Vec<&Ident> = values.variants.iter().map(|v| &v.name).collect();
+        let variant_values: Vec<proc_macro2::Literal> = values
+            .variants
+            .iter()
+            .map(|v| proc_macro2::Literal::usize_unsuffixed(v.value))
+            .collect();
+
+        enums.push(quote! {
+            #[doc = #doc_title]
+            #doc
+            ///
+            /// # Values
+            /// This enum was generated by `#[values(...)]` in the `make_device!` macro. Each
+            /// variant's discriminant is the raw bit pattern written into the register.
+            #[repr(#bit_type)]
+            #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+            #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+            pub enum #enum_name {
+                #(#variant_names = #variant_values,)*
+            }
+
+            impl #enum_name {
+                /// # From Bits
+                /// Convert the raw bit pattern of this field into its enumerated value,
+                /// `None` if the pattern has no enumerated value.
+                pub const fn from_bits(bits: #bit_type) -> Option<Self> {
+                    match bits {
+                        #(#variant_values => Some(Self::#variant_names),)*
+                        #[allow(unreachable_patterns)]
+                        _ => None,
+                    }
+                }
+
+                /// # Into Bits
+                /// Convert this value into the raw bit pattern written into the register.
+                pub const fn into_bits(self) -> #bit_type {
+                    self as #bit_type
+                }
+            }
+        });
+    }
+
+    Ok(quote!(#(#enums)*))
+}
+
+fn generate_modify_array_field(
+    bit: &BitBlock,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let name = &bit.name;
+    let const_name = bit.name.to_string().to_uppercase().replace(' ', "_");
+    let self_count = format_ident!("{}_COUNT", const_name);
+    let doc_title = string_into_title(&bit.name.to_string());
+    let doc_strings = generate_doc_strings(&bit.doc_attr);
+    let doc = quote!(#doc_title #doc_strings);
+    let panic_message = format!("Index {{index}} is out of range for '{}'!", bit.name);
+
+    let (start, end) = match bit.bit_attr.bit {
+        BitRange::Range(range) => get_real_range(range),
+        BitRange::Single(single) => (single, single),
+    };
+    let width_mask: u32 = (((1u64 << (end - start + 1)) - 1) & 0xFFFF_FFFF) as u32;
+    let (_, shift) = generate_array_location(bit, start);
+    let is_single = matches!(bit.bit_attr.bit, BitRange::Single(_));
+
+    let (value_type, into_raw, return_type, convert) = if is_single {
+        (
+            quote!(bool),
+            quote!(flag as u32),
+            quote!(bool),
+            quote!(value != 0),
+        )
+    } else if bit.values_attr.is_some() {
+        let enum_name = bit.values_enum_name();
+        let bit_type = min_type_for_range((start, end));
+        let (return_type, convert) = generate_values_convert(bit, (start, end));
+        (
+            quote!(#enum_name),
+            quote!(flag.into_bits() as u32),
+            return_type,
+            quote!(let value = value as #bit_type; #convert),
+        )
+    } else {
+        let bit_type = min_type_for_range((start, end));
+        (
+            quote!(#bit_type),
+            quote!(flag as u32),
+            quote!(#bit_type),
+            quote!(value as #bit_type),
+        )
+    };
+
+    let (write, read) = bit.bit_attr.access.write_read();
+
+    let reader = quote! {
+        #doc
+        #[inline(always)]
+        pub fn #name(&self, index: usize) -> #return_type {
+            assert!(index < Registers::#self_count, #panic_message);
+            let shift: usize = #shift;
+            let value = (self.0 >> shift) & #width_mask;
+            #convert
+        }
+    };
+
+    let (prefix, setter_one, _, _) = bit.bit_attr.access.single_names();
+    let (writer_name, param, flag) = if is_single && setter_one {
+        (
+            format_ident!("{}_{}", prefix, name),
+            quote!(),
+            quote!(let flag = true;),
+        )
+    } else {
+        (name.clone(), quote!(, flag: #value_type), quote!())
+    };
+
+    let writer = quote! {
+        #doc
+        #[inline(always)]
+        pub fn #writer_name(&mut self, index: usize #param) -> &mut Self {
+            assert!(index < Registers::#self_count, #panic_message);
+            #flag
+            let shift: usize = #shift;
+            let raw: u32 = #into_raw;
+            self.0 = (self.0 & !(#width_mask << shift)) | (raw << shift);
+            self
+        }
+    };
+
+    (
+        if read { reader } else { quote!() },
+        if write { writer } else { quote!() },
+    )
+}
+
+fn generate_modify_field(bit: &BitBlock) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    if bit.array_attr.is_some() {
+        return generate_modify_array_field(bit);
+    }
+
+    let name = &bit.name;
+    let const_name = bit.name.to_string().to_uppercase().replace(' ', "_");
+    let doc_title = string_into_title(&bit.name.to_string());
+    let doc_strings = generate_doc_strings(&bit.doc_attr);
+    let doc = quote!(#doc_title #doc_strings);
+
+    let (write, read) = bit.bit_attr.access.write_read();
+
+    let (reader, writer) = match bit.bit_attr.bit {
+        BitRange::Single(_) => {
+            let self_shift = format_ident!("{}_BIT", const_name);
+            let reader = quote! {
+                #doc
+                #[inline(always)]
+                pub fn #name(&self) -> bool {
+                    (self.0 & (1u32 << Registers::#self_shift)) != 0
+                }
+            };
+            let (prefix, setter_one, _, _) = bit.bit_attr.access.single_names();
+            let writer = if setter_one {
+                let setter_name = format_ident!("{}_{}", prefix, name);
+                quote! {
+                    #doc
+                    #[inline(always)]
+                    pub fn #setter_name(&mut self) -> &mut Self {
+                        self.0 |= 1u32 << Registers::#self_shift;
+                        self
+                    }
+                }
+            } else {
+                quote! {
+                    #doc
+                    #[inline(always)]
+                    pub fn #name(&mut self, flag: bool) -> &mut Self {
+                        if flag {
+                            self.0 |= 1u32 << Registers::#self_shift;
+                        } else {
+                            self.0 &= !(1u32 << Registers::#self_shift);
+                        }
+                        self
+                    }
+                }
+            };
+
+            (reader, writer)
+        }
+        BitRange::Range(range) => {
+            let (start, end) = get_real_range(range);
+            let bit_type = min_type_for_range((start, end));
+            let self_mask = format_ident!("{}_BIT_MASK", const_name);
+            let self_shift = format_ident!("{}_BIT_START", const_name);
+            let self_end = format_ident!("{}_BIT_END", const_name);
+
+            let (return_type, convert) = if bit.values_attr.is_some() {
+                generate_values_convert(bit, (start, end))
+            } else {
+                (quote!(#bit_type), quote!(value))
+            };
+            let reader = quote! {
+                #doc
+                #[inline(always)]
+                pub fn #name(&self) -> #return_type {
+                    let value = (((self.0 as usize) & Registers::#self_mask) >> Registers::#self_shift) as #bit_type;
+                    #convert
+                }
+            };
+
+            let writer = if bit.values_attr.is_some() {
+                let enum_name = bit.values_enum_name();
+                quote! {
+                    #doc
+                    #[inline(always)]
+                    pub fn #name(&mut self, value: #enum_name) -> &mut Self {
+                        self.0 = (self.0 & !(Registers::#self_mask as u32))
+                            | ((value.into_bits() as u32) << (Registers::#self_shift as u32));
+                        self
+                    }
+                }
+            } else {
+                quote! {
+                    #doc
+                    #[inline(always)]
+                    pub fn #name(&mut self, flag: #bit_type) -> &mut Self {
+                        debug_assert!((flag as usize) >> ((Registers::#self_end) - Registers::#self_shift) <= 1, "Provided flag {flag} is too large for provided setter range {}..={}!", #start, #end);
+                        self.0 = (self.0 & !(Registers::#self_mask as u32))
+                            | ((flag as u32) << (Registers::#self_shift as u32));
+                        self
+                    }
+                }
+            };
+
+            (reader, writer)
+        }
+    };
+
+    (
+        if read { reader } else { quote!() },
+        if write { writer } else { quote!() },
+    )
+}
+
+fn generate_modify(bits: &[BitBlock]) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    // Keep registers in the order they were first used, so the generated code is stable
+    let mut register_order: Vec<&str> = Vec::new();
+    for bit in bits.iter() {
+        if !register_order.contains(&bit.bit_attr.register_name.as_str()) {
+            register_order.push(bit.bit_attr.register_name.as_str());
+        }
+    }
+
+    let mut proxies: Vec<proc_macro2::TokenStream> = Vec::new();
+    let mut modify_fns: Vec<proc_macro2::TokenStream> = Vec::new();
+
+    for register in register_order {
+        // Register arrays span many registers, so they are modified one setter at a time
+        let is_register_array = bits.iter().any(|bit| {
+            bit.bit_attr.register_name == register
+                && bit.array_attr.as_ref().is_some_and(|array| {
+                    matches!(
+                        array.layout,
+                        ArrayLayout::Stride(_) | ArrayLayout::Offsets(_)
+                    )
+                })
+        });
+        if is_register_array {
+            continue;
+        }
+
+        let (readers, writers): (Vec<_>, Vec<_>) = bits
+            .iter()
+            .filter(|bit| bit.bit_attr.register_name == register)
+            .map(generate_modify_field)
+            .unzip();
+
+        let pascal = string_into_pascal(register).to_string();
+        let reader_name = format_ident!("{}Reader", pascal);
+        let writer_name = format_ident!("{}Writer", pascal);
+        let self_dot = format_ident!("{}", register);
+        let self_set_mask = format_ident!("{}_SET_MASK", register.to_uppercase());
+        let fn_name = format_ident!("modify_{}", register);
+        let doc_title = string_into_title(&format!("modify {register}"));
+        let reader_doc = format!(" # {reader_name}");
+        let writer_doc = format!(" # {writer_name}");
+        let modify_doc = format!(
+            " `{reader_name}`, and each field set on the `{writer_name}` is written back in"
+        );
+        let read_all_name = format_ident!("read_all_{}", register);
+        let read_all_title = string_into_title(&format!("read all {register}"));
+        let read_all_doc = format!(
+            " Read the register once, and decode any number of its fields from the `{reader_name}`."
+        );
+        // Reading the register clears any read-to-clear fields in it
+        let (read_all_receiver, read_all_clear_doc) = match bits.iter().find(|bit| {
+            bit.bit_attr.register_name == register && bit.bit_attr.access.is_read_to_clear()
+        }) {
+            Some(bit) => generate_read_receiver(bit),
+            None => (quote!(&self), quote!()),
+        };
+
+        proxies.push(quote! {
+            #[doc = #reader_doc]
+            /// A copy of the register value read by `modify` or `read_all`, with a getter for
+            /// every readable field.
+            pub struct #reader_name(u32);
+
+            impl #reader_name {
+                /// # Bits
+                /// The raw value of the register.
+                #[inline(always)]
+                pub fn bits(&self) -> u32 {
+                    self.0
+                }
+
+                #(#readers)*
+            }
+
+            #[doc = #writer_doc]
+            /// The value `modify` will write into the register, with a setter for every writable
+            /// field. Setters can be chained, ie. `w.first(true).second(2)`.
+            pub struct #writer_name(u32);
+
+            impl #writer_name {
+                /// # Bits
+                /// The raw value that will be written into the register.
+                #[inline(always)]
+                pub fn bits(&self) -> u32 {
+                    self.0
+                }
+
+                #(#writers)*
+            }
+        });
+
+        modify_fns.push(quote! {
+            #read_all_title
+            #[doc = #read_all_doc]
+            /// Checking many flags this way only costs one bus access, instead of one per
+            /// getter called.
+            ///
+            #read_all_clear_doc
+            /// # Volatile
+            /// This function only preforms **1** volatile *read*.
+            ///
+            #[inline(always)]
+            pub fn #read_all_name(#read_all_receiver) -> #reader_name {
+                use hal_macros::VolatileRead;
+                #reader_name(self.#self_dot.read())
+            }
+
+            #doc_title
+            /// Read the register once, then update any number of its fields before writing
+            /// it back once. The closure is given the value that was read as a
+            #[doc = #modify_doc]
+            /// a single volatile write. Unlike calling several setters, other fields can not
+            /// be observed by the hardware in a half updated state.
+            ///
+            /// Bits that are cleared or activated by writing a 1 (ie. `RW1C`) are only written
+            /// when explicitly set on the writer. Read-to-clear (`RC`) fields are cleared by
+            /// the read, their old value is only available from the reader.
+            ///
+            /// # Safety
+            /// It is up to the caller to verify that this register write will not
+            /// cause any side effects. There could be an event that setting this
+            /// register could cause undefined behavior elsewhere in the program.
+            ///
+            /// # Volatile
+            /// This function only preforms **1** volatile *read*, and **1** volatile *write*.
+            ///
+            #[inline(always)]
+            pub unsafe fn #fn_name<F>(&mut self, f: F)
+            where
+                F: for<'w> FnOnce(&#reader_name, &'w mut #writer_name) -> &'w mut #writer_name,
+            {
+                use hal_macros::{VolatileRead, VolatileWrite};
+                let read_value: u32 = self.#self_dot.read();
+                let mut writer = #writer_name(read_value & (Registers::#self_set_mask as u32));
+                f(&#reader_name(read_value), &mut writer);
+                self.#self_dot.write(writer.0);
+            }
+        });
+    }
+
+    (quote!(#(#proxies)*), quote!(#(#modify_fns)*))
+}
+
+/// The receiver of a getter, and the extra docs for it. Reading a read-to-clear field
+/// changes hardware state, so its getter must take `&mut self`.
+fn generate_read_receiver(bit: &BitBlock) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    if bit.bit_attr.access.is_read_to_clear() {
+        (
+            quote!(&mut self),
+            quote!(
+                /// # Read To Clear
+                /// Reading this field clears it in hardware, so the value can only be
+                /// taken once. This is why this function requires `&mut self`.
+                ///
+            ),
+        )
+    } else {
+        (quote!(&self), quote!())
+    }
+}
+
+fn generate_snapshot(bits: &[BitBlock]) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    // Reading any part of a register with read-to-clear fields would clear them
+    let registers_with_rc: Vec<&str> = bits
+        .iter()
+        .filter(|bit| bit.bit_attr.access.is_read_to_clear())
+        .map(|bit| bit.bit_attr.register_name.as_str())
+        .collect();
+
+    let (fields, reads): (Vec<_>, Vec<_>) = bits
+        .iter()
+        .filter(|bit| {
+            bit.bit_attr.access.write_read().1
+                && !registers_with_rc.contains(&bit.bit_attr.register_name.as_str())
+        })
+        .map(|bit| {
+            let name = &bit.name;
+            let doc_title = string_into_title(&bit.name.to_string());
+            let doc = generate_doc_strings(&bit.doc_attr);
+
+            let (getter, field_type) = match bit.bit_attr.bit {
+                BitRange::Single(_) => {
+                    let (_, _, getter_start, getter_end) = bit.bit_attr.access.single_names();
+                    (
+                        format_ident!("{}{}{}", getter_start, name, getter_end),
+                        quote!(bool),
+                    )
+                }
+                BitRange::Range(range) => {
+                    let (start, end) = get_real_range(range);
+                    let (_, getter_start) = bit.bit_attr.access.range_names();
+                    let field_type = if bit.values_attr.is_some() {
+                        generate_values_convert(bit, (start, end)).0
+                    } else {
+                        min_type_for_range((start, end))
+                    };
+                    (format_ident!("{}{}", getter_start, name), field_type)
+                }
+            };
+
+            // Named after the getter (without `get_`), since fields of different access
+            // types can share a name (ie. a `RW1C` flag and its `RW` threshold).
+            let getter_string = getter.to_string();
+            let field = format_ident!(
+                "{}",
+                getter_string.strip_prefix("get_").unwrap_or(&getter_string)
+            );
+
+            let (field_type, read) = match &bit.array_attr {
+                Some(array) => {
+                    let count = array.count;
+                    (
+                        quote!([#field_type; #count]),
+                        quote!(core::array::from_fn(|index| self.#getter(index))),
+                    )
+                }
+                None => (field_type, quote!(self.#getter())),
+            };
+
+            // Fields only some instances have are `None` on the others
+            if bit.instances_attr.is_some() {
+                let has_name = format_ident!("has_{}", name);
+                (
+                    quote!(#doc_title #doc pub #field: Option<#field_type>,),
+                    quote!(#field: self.#has_name().then(|| #read),),
+                )
+            } else {
+                (
+                    quote!(#doc_title #doc pub #field: #field_type,),
+                    quote!(#field: #read,),
+                )
+            }
+        })
+        .unzip();
+
+    (
+        quote! {
+            /// # Snapshot
+            /// Every readable field of the device, decoded, at the time `snapshot()` was
+            /// called. Made for dumping the full state of a device to a log during bring-up.
+            #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+            #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+            pub struct Snapshot {
+                #(#fields)*
+            }
+        },
+        quote! {
+            /// # Snapshot
+            /// Read and decode every readable field of the device into a `Snapshot`, which
+            /// implements `Debug` (and `defmt::Format` with the `defmt` feature).
+            ///
+            /// # Volatile
+            /// Every field is read with its own volatile *read*, so the snapshot is not
+            /// atomic. Registers with read-to-clear (`RC`) fields are skipped, so taking a
+            /// snapshot never changes the state of the device. Fields this instance does not
+            /// have (see `#[instances(...)]`) are `None`.
+            #[inline(never)]
+            pub fn snapshot(&self) -> Snapshot {
+                Snapshot {
+                    #(#reads)*
+                }
+            }
+        },
+    )
+}
+
+/// The first statements of every generated field accessor. Names the field for register
+/// access tracing, and checks the field exists on this instance.
+fn generate_field_prelude(bit: &BitBlock) -> proc_macro2::TokenStream {
+    let name = bit.name.to_string();
+    let trace = quote!(let _trace = hal_macros::trace::field(&#name););
+
+    let Some(instances) = &bit.instances_attr else {
+        return trace;
+    };
+
+    let has_name = format_ident!("has_{}", bit.name);
+    let message = format!("'{name}' does not exist on this instance of the device!");
+    quote! {
+        #trace
+        #[cfg(not(test))]
+        const {
+            assert!(
+                match B::CONST_ADDRESS {
+                    Some(address) => false #( || address == #instances)*,
+                    None => true,
+                },
+                #message
+            )
+        };
+        #[cfg(not(test))]
+        debug_assert!(self.#has_name(), #message);
+    }
+}
+
+/// `has_<field>()` for every field only some instances of the device have.
+fn generate_instance_checks(bits: &[BitBlock]) -> proc_macro2::TokenStream {
+    let checks = bits.iter().filter_map(|bit| {
+        let instances = bit.instances_attr.as_ref()?;
+        let has_name = format_ident!("has_{}", bit.name);
+        let doc_title = string_into_title(&has_name.to_string());
+        let doc = format!(
+            " Check if this instance of the device has `{}`. Only some instances do,",
+            bit.name
+        );
+
+        Some(quote! {
+            #doc_title
+            #[doc = #doc]
+            /// so using it on the others is checked at compile time for `Instance`, and
+            /// with a `debug_assert!` otherwise. Like `new`, checking is disabled in
+            /// testing mode.
+            #[inline(always)]
+            pub fn #has_name(&self) -> bool {
+                let address = self.base_address();
+                false #( || address == #instances)*
+            }
+        })
+    });
+
+    quote!(#(#checks)*)
+}
+
 fn generate_doc_strings(strings: &Vec<String>) -> proc_macro2::TokenStream {
     quote!(
         #(#[doc = #strings])*
@@ -684,7 +1946,7 @@ fn generate_doc_strings(strings: &Vec<String>) -> proc_macro2::TokenStream {
 fn generate_bit_range(
     range: (Bound<usize>, Bound<usize>),
     bit: &BitBlock,
-) -> proc_macro2::TokenStream {
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
     let (start, end) = get_real_range(range);
 
     let doc_string = generate_doc_strings(&bit.doc_attr);
@@ -709,49 +1971,53 @@ fn generate_bit_range(
         doc_string.clone(),
     );
 
-    let (write, read) = match bit.bit_attr.access {
-        Access::RO => (false, true),
-        Access::WO => (true, false),
-        _ => (true, true),
-    };
+    let (write, read) = bit.bit_attr.access.write_read();
 
-    let getter = if read {
-        generate_range_get(format!("get_{}", bit.name).as_str(), bit, (start, end))
+    let has_values = bit.values_attr.is_some();
+
+    let (setter_start, getter_start) = bit.bit_attr.access.range_names();
+    let getter_name = format!("{}{}", getter_start, bit.name);
+    let setter_name = format!("{}_{}", setter_start, bit.name);
+
+    let getter = if read && has_values {
+        generate_values_get(getter_name.as_str(), bit, (start, end))
+    } else if read {
+        generate_range_get(getter_name.as_str(), bit, (start, end))
     } else {
         quote!()
     };
-    let setter = if write {
-        generate_range_set(format!("set_{}", bit.name).as_str(), bit, (start, end))
+    let setter = if write && has_values {
+        generate_values_set(setter_name.as_str(), bit)
+    } else if write {
+        generate_range_set(setter_name.as_str(), bit, (start, end))
     } else {
         quote!()
     };
 
-    quote!(
-        #const_start
-        #const_end
-        #const_mask
-
-        #getter
-        #setter
+    (
+        quote!(
+            #const_start
+            #const_end
+            #const_mask
+        ),
+        quote!(
+            #getter
+            #setter
+        ),
     )
 }
 
-fn generate_bit_single(single: usize, bit: &BitBlock) -> proc_macro2::TokenStream {
+fn generate_bit_single(
+    single: usize,
+    bit: &BitBlock,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
     let doc_string = generate_doc_strings(&bit.doc_attr);
 
-    let (setter_name, setter_one, getter_start, getter_name) = match bit.bit_attr.access {
-        Access::RW1C => ("clear", true, "is_", "_active"),
-        Access::RW1O => ("activate", true, "is_", "_pending"),
-        _ => ("set", false, "get_", ""),
-    };
+    let (setter_name, setter_one, getter_start, getter_name) = bit.bit_attr.access.single_names();
 
     let const_start = generate_const(format!("{}_BIT", bit.name).as_str(), single, doc_string);
 
-    let (write, read) = match bit.bit_attr.access {
-        Access::RO => (false, true),
-        Access::WO => (true, false),
-        _ => (true, true),
-    };
+    let (write, read) = bit.bit_attr.access.write_read();
 
     let getter = if read {
         generate_single_get(
@@ -770,11 +2036,228 @@ fn generate_bit_single(single: usize, bit: &BitBlock) -> proc_macro2::TokenStrea
     } else {
         quote!()
     };
-    quote!(
-        #const_start
+    (
+        quote!(#const_start),
+        quote!(
+            #getter
+            #setter
+        ),
+    )
+}
 
-        #getter
-        #setter
+/// The byte offset and bit shift of element `index` of a `#[array(...)]` field.
+fn generate_array_location(
+    bit: &BitBlock,
+    start: usize,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    match bit.array_attr.as_ref().map(|array| &array.layout) {
+        Some(ArrayLayout::Stride(stride)) => (quote!(index * #stride), quote!(#start)),
+        Some(ArrayLayout::Offsets(offsets)) => (quote!([#(#offsets),*][index]), quote!(#start)),
+        Some(ArrayLayout::BitStride(bit_stride)) => {
+            (quote!(0usize), quote!(#start + index * #bit_stride))
+        }
+        None => (quote!(0usize), quote!(#start)),
+    }
+}
+
+fn generate_bit_array(bit: &BitBlock) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let Some(array) = &bit.array_attr else {
+        return (quote!(), quote!());
+    };
+
+    let doc_string = generate_doc_strings(&bit.doc_attr);
+    let const_name = bit.name.to_string().to_uppercase().replace(' ', "_");
+    let self_count = format_ident!("{}_COUNT", const_name);
+    let self_dot = format_ident!("{}", bit.bit_attr.register_name);
+    let reg_const_name = bit.bit_attr.register_name.to_uppercase().replace(' ', "_");
+    let self_set_mask = format_ident!("{}_SET_MASK", reg_const_name);
+    let count = array.count;
+
+    let (start, end, consts) = match bit.bit_attr.bit {
+        BitRange::Range(range) => {
+            let (start, end) = get_real_range(range);
+            let mut mask: u32 = 1;
+            for _ in 0..(end - start) {
+                mask <<= 1;
+                mask |= 1;
+            }
+            mask <<= start;
+
+            let const_start = generate_const(
+                &format!("{}_BIT_START", bit.name),
+                start,
+                doc_string.clone(),
+            );
+            let const_end =
+                generate_const(&format!("{}_BIT_END", bit.name), end, doc_string.clone());
+            let const_mask = generate_const(
+                &format!("{}_BIT_MASK", bit.name),
+                mask as usize,
+                doc_string.clone(),
+            );
+
+            (start, end, quote!(#const_start #const_end #const_mask))
+        }
+        BitRange::Single(single) => (
+            single,
+            single,
+            generate_const(&format!("{}_BIT", bit.name), single, doc_string.clone()),
+        ),
+    };
+    let const_count = generate_const(&format!("{}_COUNT", bit.name), count, doc_string.clone());
+
+    let width_mask: u32 = (((1u64 << (end - start + 1)) - 1) & 0xFFFF_FFFF) as u32;
+    let (offset, shift) = generate_array_location(bit, start);
+    let is_single = matches!(bit.bit_attr.bit, BitRange::Single(_));
+
+    let (setter_name, setter_one, getter_start, getter_name) = if is_single {
+        bit.bit_attr.access.single_names()
+    } else {
+        let (setter_name, getter_start) = bit.bit_attr.access.range_names();
+        (setter_name, false, getter_start, "")
+    };
+    let getter_ident = format_ident!("{}{}{}", getter_start, bit.name, getter_name);
+    let setter_ident = format_ident!("{}_{}", setter_name, bit.name);
+
+    let (value_type, into_raw, return_type, convert) = if is_single {
+        (
+            quote!(bool),
+            quote!(flag as u32),
+            quote!(bool),
+            quote!(value != 0),
+        )
+    } else if bit.values_attr.is_some() {
+        let enum_name = bit.values_enum_name();
+        let bit_type = min_type_for_range((start, end));
+        let (return_type, convert) = generate_values_convert(bit, (start, end));
+        (
+            quote!(#enum_name),
+            quote!(flag.into_bits() as u32),
+            return_type,
+            quote!(let value = value as #bit_type; #convert),
+        )
+    } else {
+        let bit_type = min_type_for_range((start, end));
+        (
+            quote!(#bit_type),
+            quote!(flag as u32),
+            quote!(#bit_type),
+            quote!(value as #bit_type),
+        )
+    };
+
+    let doc_title_get = string_into_title(&getter_ident.to_string());
+    let doc_title_set = string_into_title(&setter_ident.to_string());
+    let panic_message = format!("Index {{index}} is out of range for '{}'!", bit.name);
+    let (receiver, read_doc) = generate_read_receiver(bit);
+
+    let (write, read) = bit.bit_attr.access.write_read();
+
+    let trace = generate_field_prelude(bit);
+    let getter = if read {
+        quote! {
+            #doc_title_get
+            #doc_string
+            ///
+            /// # Get
+            /// Gets the value of element `index` of this array from the given register.
+            ///
+            /// # Panics
+            /// This function will panic if `index` is not less than the number of elements
+            /// in this array. Unlike range checks, this is also checked in release since
+            /// a bad index would access a different register.
+            ///
+            /// # Volatile
+            /// This function only preforms **1** volatile *read* and immediately copies
+            /// the value and extracts the bits to return the result.
+            ///
+            #read_doc
+            #[inline(always)]
+            pub fn #getter_ident(#receiver, index: usize) -> #return_type {
+                use hal_macros::VolatileWrap;
+                #trace
+                assert!(index < Registers::#self_count, #panic_message);
+                let offset: usize = #offset;
+                let shift: usize = #shift;
+                let value: u32 = self.#self_dot.action_immutable(|ptr| unsafe {
+                    let ptr = ptr.byte_add(offset);
+                    let value = core::ptr::read_volatile(ptr);
+                    hal_macros::trace::read(ptr, &value);
+                    value
+                });
+                let value = (value >> shift) & #width_mask;
+                #convert
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    let (param, flag) = if setter_one {
+        (quote!(), quote!(let flag = true;))
+    } else {
+        (quote!(, flag: #value_type), quote!())
+    };
+
+    let setter = if write {
+        quote! {
+            #doc_title_set
+            #doc_string
+            ///
+            /// # Set
+            /// Set the value of element `index` of this array into the given register.
+            ///
+            /// # Safety
+            /// It is up to the caller to verify that this register write will not
+            /// cause any side effects. There could be an event that setting this
+            /// register could cause undefined behavior elsewhere in the program.
+            ///
+            /// # Panics
+            /// This function will panic if `index` is not less than the number of elements
+            /// in this array. Unlike range checks, this is also checked in release since
+            /// a bad index would access a different register.
+            ///
+            /// # Volatile
+            /// This function only preforms **1** volatile *read*,
+            /// immediately modifies the flag and does **1** volatile *write* using
+            /// the internal provided function to register.
+            ///
+            #[inline(always)]
+            pub unsafe fn #setter_ident(&mut self, index: usize #param) {
+                use hal_macros::VolatileWrap;
+                #trace
+                assert!(index < Registers::#self_count, #panic_message);
+                #flag
+                let offset: usize = #offset;
+                let shift: usize = #shift;
+                let raw: u32 = #into_raw;
+                debug_assert!(raw <= #width_mask, "Provided flag {raw} is too large for provided setter range {}..={}!", #start, #end);
+                self.#self_dot.action_mutable(|ptr| unsafe {
+                    let ptr = ptr.byte_add(offset);
+                    let read_value: u32 = core::ptr::read_volatile(ptr);
+                    hal_macros::trace::read(ptr, &read_value);
+                    let write_value: u32 = (read_value
+                        & !(#width_mask << shift)
+                        & (Registers::#self_set_mask as u32))
+                        | (raw << shift);
+                    hal_macros::trace::write(ptr, &write_value);
+                    core::ptr::write_volatile(ptr, write_value);
+                });
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    (
+        quote!(
+            #consts
+            #const_count
+        ),
+        quote!(
+            #getter
+            #setter
+        ),
     )
 }
 
@@ -796,7 +2279,7 @@ fn generate_reg_fields(all_register_names: &[(String, Path)]) -> Vec<(Ident, Pat
 fn generate_reg_struct(reg_names: &[(Ident, Path)]) -> proc_macro2::TokenStream {
     let properties: Vec<proc_macro2::TokenStream> = reg_names
         .iter()
-        .map(|(ident, path)| quote!(#ident: RW<{#path}, u32>))
+        .map(|(ident, path)| quote!(#ident: RW<{#path}, u32, B>))
         .collect();
 
     quote! {
@@ -825,11 +2308,33 @@ fn generate_reg_struct(reg_names: &[(Ident, Path)]) -> proc_macro2::TokenStream
         ///
         ///    #[bit(2..=10, WO, MY_REGISTER_OFFSET)]
         ///    my_reg_range_write_only,
+        ///
+        ///    #[bit(11..=12, RW, MY_REGISTER_OFFSET)]
+        ///    #[values(Off = 0, Slow = 1, Fast = 2)]
+        ///    my_reg_speed,
+        ///
+        ///    #[bit(16, RW, MY_REGISTER_OFFSET)]
+        ///    #[array(count = 8, bit_stride = 1)]
+        ///    my_reg_per_pin_enable,
+        ///
+        ///    #[bit(24, RW, MY_REGISTER_OFFSET)]
+        ///    #[instances(MY_DEVICE_PORT1)]
+        ///    my_reg_port1_only,
         /// }
         ///
         /// ```
+        ///
+        /// # Differently Shaped Instances
+        /// Fields marked with `#[instances(...)]` only exist on the listed ports. Use
+        /// `has_<field>()` to check for them, using them on a `Instance` of another port
+        /// fails to compile.
+        ///
+        /// # Instances
+        /// By default the base address is given at runtime with `Registers::new(port)`. Use
+        /// `Instance<PORT>` (ie. `Instance<{ MY_DEVICE_PORT0 }>`) for a zero sized handle
+        /// with the base address known at compile time.
         #[allow(unused)]
-        pub struct Registers {
+        pub struct Registers<B: hal_macros::BaseAddress = hal_macros::RuntimeAddress> {
            #(#properties,)*
         }
     }
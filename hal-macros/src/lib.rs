@@ -1,9 +1,89 @@
 #![no_std]
-use core::ptr::NonNull;
+use core::marker::PhantomData;
+
+#[cfg(all(feature = "mock-mmio", not(target_os = "none")))]
+pub mod mock;
+pub mod trace;
+
+/// # MMIO Pointer
+/// Turn a hardware address into the pointer used to access it. On hardware this is
+/// just a cast, with the `mock-mmio` feature on a host target, peripheral addresses are
+/// redirected into a in-process register file (see `mock`).
+#[inline(always)]
+pub fn mmio_ptr<T>(address: usize) -> *mut T {
+    #[cfg(all(feature = "mock-mmio", not(target_os = "none")))]
+    {
+        mock::translate(address) as *mut T
+    }
+    #[cfg(not(all(feature = "mock-mmio", not(target_os = "none"))))]
+    {
+        address as *mut T
+    }
+}
+
+/// # MMIO Write
+/// Write the 32-bit register at `address`. With the `mock-mmio` feature on a host
+/// target, the write also has the side effects the mock models, see `mock::written`.
+///
+/// # Safety
+/// `address` must be a writable register, and writing `value` to it must be sound.
+#[inline(always)]
+pub unsafe fn mmio_write(address: usize, value: u32) {
+    core::ptr::write_volatile(mmio_ptr::<u32>(address), value);
+
+    #[cfg(all(feature = "mock-mmio", not(target_os = "none")))]
+    mock::written(address, value);
+}
+
+/// # Base Address
+/// Where a register block lives in memory. Registers add their own `OFFSET` on top.
+pub trait BaseAddress: Copy {
+    /// # Const Address
+    /// The base address when it is known at compile time, used for compile time checks.
+    const CONST_ADDRESS: Option<usize> = None;
+
+    fn address(&self) -> usize;
+}
+
+/// # Runtime Address
+/// A base address only known at runtime (ie. fake registers when testing).
+#[derive(Clone, Copy)]
+pub struct RuntimeAddress(usize);
+
+/// # Const Address
+/// A base address known at compile time. This is zero sized, so the compiler can fold
+/// every register address into an immediate.
+#[derive(Clone, Copy)]
+pub struct ConstAddress<const ADDRESS: usize>;
+
+impl BaseAddress for RuntimeAddress {
+    #[inline(always)]
+    fn address(&self) -> usize {
+        self.0
+    }
+}
 
-pub struct RO<const OFFSET: usize, T: Sized + Copy>(NonNull<T>);
-pub struct WO<const OFFSET: usize, T: Sized + Copy>(NonNull<T>);
-pub struct RW<const OFFSET: usize, T: Sized + Copy>(NonNull<T>);
+impl<const ADDRESS: usize> BaseAddress for ConstAddress<ADDRESS> {
+    const CONST_ADDRESS: Option<usize> = Some(ADDRESS);
+
+    #[inline(always)]
+    fn address(&self) -> usize {
+        ADDRESS
+    }
+}
+
+pub struct RO<const OFFSET: usize, T: Sized + Copy, B: BaseAddress = RuntimeAddress>(
+    B,
+    PhantomData<*mut T>,
+);
+pub struct WO<const OFFSET: usize, T: Sized + Copy, B: BaseAddress = RuntimeAddress>(
+    B,
+    PhantomData<*mut T>,
+);
+pub struct RW<const OFFSET: usize, T: Sized + Copy, B: BaseAddress = RuntimeAddress>(
+    B,
+    PhantomData<*mut T>,
+);
 
 pub trait VolatileWrap<T: Sized> {
     fn action_immutable<Return, F>(&self, func: F) -> Return
@@ -18,24 +98,31 @@ pub trait VolatileWrap<T: Sized> {
 pub trait VolatileRead<T>: VolatileWrap<T> {
     #[inline(always)]
     fn read(&self) -> T {
-        self.action_immutable(|ptr| unsafe { core::ptr::read_volatile(ptr) })
+        self.action_immutable(|ptr| {
+            let value = unsafe { core::ptr::read_volatile(ptr) };
+            trace::read(ptr, &value);
+            value
+        })
     }
 }
 
 pub trait VolatileWrite<T>: VolatileWrap<T> {
     #[inline(always)]
     fn write(&mut self, value: T) {
-        self.action_mutable(|ptr| unsafe { core::ptr::write_volatile(ptr, value) })
+        self.action_mutable(|ptr| {
+            trace::write(ptr, &value);
+            unsafe { core::ptr::write_volatile(ptr, value) }
+        })
     }
 }
 
-impl<T: Sized + Copy, const OFFSET: usize> VolatileWrap<T> for RO<OFFSET, T> {
+impl<T: Sized + Copy, const OFFSET: usize, B: BaseAddress> VolatileWrap<T> for RO<OFFSET, T, B> {
     #[inline(always)]
     fn action_immutable<Return, F>(&self, func: F) -> Return
     where
         F: FnOnce(*const T) -> Return,
     {
-        func(self.0.as_ptr())
+        func(mmio_ptr(self.0.address() + OFFSET))
     }
 
     #[inline(always)]
@@ -47,7 +134,7 @@ impl<T: Sized + Copy, const OFFSET: usize> VolatileWrap<T> for RO<OFFSET, T> {
     }
 }
 
-impl<T: Sized + Copy, const OFFSET: usize> VolatileWrap<T> for WO<OFFSET, T> {
+impl<T: Sized + Copy, const OFFSET: usize, B: BaseAddress> VolatileWrap<T> for WO<OFFSET, T, B> {
     #[inline(always)]
     fn action_immutable<Return, F>(&self, _func: F) -> Return
     where
@@ -61,17 +148,17 @@ impl<T: Sized + Copy, const OFFSET: usize> VolatileWrap<T> for WO<OFFSET, T> {
     where
         F: FnOnce(*mut T) -> Return,
     {
-        func(self.0.as_ptr())
+        func(mmio_ptr(self.0.address() + OFFSET))
     }
 }
 
-impl<T: Sized + Copy, const OFFSET: usize> VolatileWrap<T> for RW<OFFSET, T> {
+impl<T: Sized + Copy, const OFFSET: usize, B: BaseAddress> VolatileWrap<T> for RW<OFFSET, T, B> {
     #[inline(always)]
     fn action_immutable<Return, F>(&self, func: F) -> Return
     where
         F: FnOnce(*const T) -> Return,
     {
-        func(self.0.as_ptr())
+        func(mmio_ptr(self.0.address() + OFFSET))
     }
 
     #[inline(always)]
@@ -79,30 +166,84 @@ impl<T: Sized + Copy, const OFFSET: usize> VolatileWrap<T> for RW<OFFSET, T> {
     where
         F: FnOnce(*mut T) -> Return,
     {
-        func(self.0.as_ptr())
+        func(mmio_ptr(self.0.address() + OFFSET))
     }
 }
 
-impl<T: Sized + Copy, const OFFSET: usize> VolatileRead<T> for RO<OFFSET, T> {}
-impl<T: Sized + Copy, const OFFSET: usize> VolatileRead<T> for RW<OFFSET, T> {}
+impl<T: Sized + Copy, const OFFSET: usize, B: BaseAddress> VolatileRead<T> for RO<OFFSET, T, B> {}
+impl<T: Sized + Copy, const OFFSET: usize, B: BaseAddress> VolatileRead<T> for RW<OFFSET, T, B> {}
+
+impl<T: Sized + Copy, const OFFSET: usize, B: BaseAddress> VolatileWrite<T> for WO<OFFSET, T, B> {}
+impl<T: Sized + Copy, const OFFSET: usize, B: BaseAddress> VolatileWrite<T> for RW<OFFSET, T, B> {}
+
+macro_rules! impl_base_address {
+    ($($wrap:ident),*) => {
+        $(
+            impl<T: Sized + Copy, const OFFSET: usize, B: BaseAddress> $wrap<OFFSET, T, B> {
+                /// # Base Address
+                /// The address of the register block this register belongs to, ie. without
+                /// `OFFSET` added.
+                #[inline(always)]
+                pub fn base_address(&self) -> usize {
+                    self.0.address()
+                }
+            }
+        )*
+    };
+}
 
-impl<T: Sized + Copy, const OFFSET: usize> VolatileWrite<T> for WO<OFFSET, T> {}
-impl<T: Sized + Copy, const OFFSET: usize> VolatileWrite<T> for RW<OFFSET, T> {}
+impl_base_address!(RO, WO, RW);
 
 impl<T: Sized + Copy, const OFFSET: usize> RO<OFFSET, T> {
     pub fn new(base_ptr: usize) -> Option<Self> {
-        Some(Self(NonNull::new((base_ptr + OFFSET) as *mut T)?))
+        if base_ptr + OFFSET == 0 {
+            None
+        } else {
+            Some(Self(RuntimeAddress(base_ptr), PhantomData))
+        }
+    }
+}
+
+impl<T: Sized + Copy, const OFFSET: usize, const ADDRESS: usize>
+    RO<OFFSET, T, ConstAddress<ADDRESS>>
+{
+    pub const fn new_const() -> Self {
+        Self(ConstAddress, PhantomData)
     }
 }
 
 impl<T: Sized + Copy, const OFFSET: usize> WO<OFFSET, T> {
     pub fn new(base_ptr: usize) -> Option<Self> {
-        Some(Self(NonNull::new((base_ptr + OFFSET) as *mut T)?))
+        if base_ptr + OFFSET == 0 {
+            None
+        } else {
+            Some(Self(RuntimeAddress(base_ptr), PhantomData))
+        }
+    }
+}
+
+impl<T: Sized + Copy, const OFFSET: usize, const ADDRESS: usize>
+    WO<OFFSET, T, ConstAddress<ADDRESS>>
+{
+    pub const fn new_const() -> Self {
+        Self(ConstAddress, PhantomData)
     }
 }
 
 impl<T: Sized + Copy, const OFFSET: usize> RW<OFFSET, T> {
     pub fn new(base_ptr: usize) -> Option<Self> {
-        Some(Self(NonNull::new((base_ptr + OFFSET) as *mut T)?))
+        if base_ptr + OFFSET == 0 {
+            None
+        } else {
+            Some(Self(RuntimeAddress(base_ptr), PhantomData))
+        }
+    }
+}
+
+impl<T: Sized + Copy, const OFFSET: usize, const ADDRESS: usize>
+    RW<OFFSET, T, ConstAddress<ADDRESS>>
+{
+    pub const fn new_const() -> Self {
+        Self(ConstAddress, PhantomData)
     }
 }
@@ -0,0 +1,130 @@
+//! # Mock MMIO
+//! A in-process register file that stands in for the MAX78000's peripherals when
+//! running on the host, so driver logic can be tested with `cargo test`.
+//!
+//! Every test thread gets its own register file, starting zeroed. Memory outside the
+//! mocked regions (ie. fake registers made from a array) is accessed directly.
+//!
+//! The register file is plain memory, it has none of the hardware's side effects. Bits
+//! the hardware would set or clear (ie. a reset finishing) have to be written by the
+//! test with `write`, otherwise a driver waiting on them will spin forever. The one
+//! exception are atomic set and clear registers added with `register_atomic`, whose
+//! writes through `mmio_write` also land in the register they set or clear bits of.
+extern crate std;
+
+use core::cell::{Cell, RefCell};
+use std::boxed::Box;
+use std::vec;
+use std::vec::Vec;
+
+/// # Mocked Regions
+/// The `(start, length)` of every address range the register file covers.
+const REGIONS: [(usize, usize); 4] = [
+    // APB peripherals
+    (0x4000_0000, 0x0010_0000),
+    // CNN global control
+    (0x5000_0000, 0x0000_1000),
+    // Data watchpoint and trace (DWT)
+    (0xE000_1000, 0x0000_1000),
+    // System control space (NVIC, SCB)
+    (0xE000_E000, 0x0000_1000),
+];
+
+const FILE_WORDS: usize = {
+    let mut words = 0;
+    let mut i = 0;
+    while i < REGIONS.len() {
+        words += REGIONS[i].1 / 4;
+        i += 1;
+    }
+    words
+};
+
+std::thread_local! {
+    static REGISTER_FILE: Box<[Cell<u32>]> = vec![0u32; FILE_WORDS]
+        .into_iter()
+        .map(Cell::new)
+        .collect();
+
+    /// The `(set, clear, target)` addresses of the atomic registers modeled, see
+    /// `register_atomic`.
+    static ATOMIC_REGISTERS: RefCell<Vec<(usize, usize, usize)>> =
+        const { RefCell::new(Vec::new()) };
+}
+
+/// The byte offset of `address` in the register file, if it is mocked.
+fn file_offset(address: usize) -> Option<usize> {
+    let mut region_offset = 0;
+    for (start, length) in REGIONS {
+        if (start..start + length).contains(&address) {
+            return Some(region_offset + (address - start));
+        }
+        region_offset += length;
+    }
+
+    None
+}
+
+/// # Translate
+/// Redirect a peripheral address into this thread's register file. Addresses that
+/// are not mocked are returned as is.
+pub fn translate(address: usize) -> usize {
+    match file_offset(address) {
+        Some(offset) => REGISTER_FILE.with(|file| file.as_ptr() as usize + offset),
+        None => address,
+    }
+}
+
+/// # Read
+/// Read the 32-bit register at `address`, like the hardware would see it.
+pub fn read(address: usize) -> u32 {
+    unsafe { core::ptr::read_volatile(translate(address) as *const u32) }
+}
+
+/// # Write
+/// Write the 32-bit register at `address`, ie. to set a status flag the driver is
+/// waiting on.
+pub fn write(address: usize, value: u32) {
+    unsafe { core::ptr::write_volatile(translate(address) as *mut u32, value) }
+}
+
+/// # Reset
+/// Zero every register in this thread's register file.
+pub fn reset() {
+    REGISTER_FILE.with(|file| file.iter().for_each(|register| register.set(0)));
+}
+
+/// # Register Atomic
+/// Model a pair of atomic registers on this thread: writing a mask to `set` (`clear`)
+/// through `mmio_write` sets (clears) those bits of `target`. The registers outlive
+/// `reset`, registering the same pair again does nothing.
+pub fn register_atomic(set: usize, clear: usize, target: usize) {
+    ATOMIC_REGISTERS.with_borrow_mut(|registers| {
+        if !registers.contains(&(set, clear, target)) {
+            registers.push((set, clear, target));
+        }
+    });
+}
+
+/// # Written
+/// Model the side effects of writing `value` to the register at `address`, see
+/// `register_atomic`.
+pub fn written(address: usize, value: u32) {
+    let target = ATOMIC_REGISTERS.with_borrow(|registers| {
+        registers.iter().find_map(|&(set, clear, target)| {
+            if address == set {
+                Some((target, true))
+            } else if address == clear {
+                Some((target, false))
+            } else {
+                None
+            }
+        })
+    });
+
+    match target {
+        Some((target, true)) => write(target, read(target) | value),
+        Some((target, false)) => write(target, read(target) & !value),
+        None => {}
+    }
+}
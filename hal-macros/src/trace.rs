@@ -0,0 +1,132 @@
+//! # Register Access Tracing
+//! With the `trace` feature enabled, every volatile register read and write made through
+//! this crate (and the `make_device!` accessors) is reported to a hook, along with the
+//! name of the field being accessed. Handy for diffing a driver against the C SDK during
+//! bring-up.
+//!
+//! Without the feature every function here is a empty `#[inline(always)]` stub, so the
+//! calls in generated code cost nothing.
+#[cfg(feature = "trace")]
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// # Access Kind
+/// If a traced access was a read or a write.
+#[cfg(feature = "trace")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// # Trace Event
+/// A single traced register access.
+#[cfg(feature = "trace")]
+#[derive(Clone, Copy, Debug)]
+pub struct Event {
+    /// If the register was read or written.
+    pub kind: AccessKind,
+    /// The address that was accessed.
+    pub address: usize,
+    /// The value that was read or written.
+    pub value: u64,
+    /// The field being accessed, `None` for whole register accesses (ie. `read_raw` or
+    /// `modify_*`).
+    pub field: Option<&'static str>,
+}
+
+/// # Trace Hook
+/// The function called for every traced access.
+#[cfg(feature = "trace")]
+pub type Hook = fn(&Event);
+
+#[cfg(feature = "trace")]
+static HOOK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+#[cfg(feature = "trace")]
+static FIELD: AtomicPtr<&'static str> = AtomicPtr::new(core::ptr::null_mut());
+
+/// # Set Hook
+/// Set (or remove with `None`) the function every traced access is reported to.
+///
+/// The hook is called from whatever context made the access, including interrupt
+/// handlers, so it should be short and must not access registers itself.
+#[cfg(feature = "trace")]
+pub fn set_hook(hook: Option<Hook>) {
+    let ptr = hook.map_or(core::ptr::null_mut(), |hook| hook as *mut ());
+    HOOK.store(ptr, Ordering::Release);
+}
+
+/// # Field Guard
+/// Names the field being accessed until it is dropped. See `field`.
+pub struct FieldGuard {
+    #[cfg(feature = "trace")]
+    previous: *mut &'static str,
+}
+
+#[cfg(feature = "trace")]
+impl Drop for FieldGuard {
+    #[inline(always)]
+    fn drop(&mut self) {
+        FIELD.store(self.previous, Ordering::Relaxed);
+    }
+}
+
+/// # Field
+/// Name the field every access is for, until the returned guard is dropped. Generated
+/// accessors call this first, ie. `let _trace = field(&"my_field");`.
+#[inline(always)]
+pub fn field(_name: &'static &'static str) -> FieldGuard {
+    FieldGuard {
+        #[cfg(feature = "trace")]
+        previous: FIELD.swap(
+            _name as *const &'static str as *mut &'static str,
+            Ordering::Relaxed,
+        ),
+    }
+}
+
+/// # Read
+/// Report a volatile read of `value` from `ptr`.
+#[inline(always)]
+pub fn read<T>(_ptr: *const T, _value: &T) {
+    #[cfg(feature = "trace")]
+    emit(AccessKind::Read, _ptr as usize, _value);
+}
+
+/// # Write
+/// Report a volatile write of `value` into `ptr`.
+#[inline(always)]
+pub fn write<T>(_ptr: *const T, _value: &T) {
+    #[cfg(feature = "trace")]
+    emit(AccessKind::Write, _ptr as usize, _value);
+}
+
+#[cfg(feature = "trace")]
+fn emit<T>(kind: AccessKind, address: usize, value: &T) {
+    let hook = HOOK.load(Ordering::Acquire);
+    if hook.is_null() {
+        return;
+    }
+
+    // Registers are at most 64 bits, widen whatever was accessed into a `u64`
+    let mut bytes = [0u8; 8];
+    let size = core::mem::size_of::<T>().min(bytes.len());
+    unsafe {
+        core::ptr::copy_nonoverlapping(value as *const T as *const u8, bytes.as_mut_ptr(), size)
+    };
+
+    let field = FIELD.load(Ordering::Relaxed);
+    let event = Event {
+        kind,
+        address,
+        value: u64::from_le_bytes(bytes),
+        field: if field.is_null() {
+            None
+        } else {
+            Some(unsafe { *field })
+        },
+    };
+
+    let hook: Hook = unsafe { core::mem::transmute::<*mut (), Hook>(hook) };
+    hook(&event);
+}
@@ -44,6 +44,18 @@ impl AES {
         }
     }
 
+    /// Conjure a handle to AES without resetting it or enabling its clock. Meant for
+    /// interrupt handlers and panic paths that need AES after it was moved elsewhere.
+    ///
+    /// # Safety
+    /// AES must already have been initialized with `init`, and the caller must ensure
+    /// the stolen handle does not race with the original one.
+    pub unsafe fn steal() -> Self {
+        Self {
+            registers: Registers::new(mmio::AES),
+        }
+    }
+
     /// Writes the given key to the beginning of the AES keys register. Before setting
     /// the key it will wipe all 1024 bytes of the register and after setting the key
     /// it will run a dummy encryption to assure that the first decryption will always work.
@@ -56,7 +68,10 @@ impl AES {
         #[cfg(not(test))]
         unsafe {
             for i in 0..256 {
-                core::ptr::write_volatile((mmio::AES_KEYS + (i * 4)) as *mut u32, 0u32);
+                core::ptr::write_volatile(
+                    hal_macros::mmio_ptr::<u32>(mmio::AES_KEYS + (i * 4)),
+                    0u32,
+                );
             }
             core::ptr::copy_nonoverlapping(key_ptr, mmio::AES_KEYS as *mut u8, key_len);
             [0; 16]
@@ -81,12 +96,11 @@ impl AES {
 
     /// Reads a block from the AES FIFO Register.
     pub fn read_back_fifo(&self) -> [u8; 16] {
-        let mut data = [0u32; 4];
-        for word in data.iter_mut() {
-            *word = self.registers.get_aes_fifo();
+        let mut block = [0u8; 16];
+        for bytes in block.chunks_exact_mut(4) {
+            bytes.copy_from_slice(&self.registers.get_aes_fifo().to_le_bytes());
         }
-        let block = unsafe { &*(data.as_ptr() as *const u32 as *const u128) };
-        block.to_le_bytes()
+        block
     }
 }
 
@@ -102,7 +116,7 @@ impl<I: Iterator> AESIterExt for I {}
 
 pub trait AESIterExt: Iterator {
     /// Initializes a new AES cipher operation. Returns an iterator over the ciphered bytes.
-    fn cipher<'a>(self, aes: &'a mut AES, cipher_type: CipherType) -> AESIter<Self>
+    fn cipher(self, aes: &mut AES, cipher_type: CipherType) -> AESIter<'_, Self>
     where
         Self::Item: Into<u8>,
         Self: Sized,
@@ -152,6 +166,21 @@ where
     }
 }
 
+impl crate::pac::FromPac for AES {
+    fn from_pac<P: crate::pac::PacPeripheral>(peripheral: P) -> crate::error::Result<Self> {
+        crate::pac::check_address::<P>(mmio::AES)?;
+        drop(peripheral);
+
+        Ok(Self::init())
+    }
+}
+
+impl crate::pac::IntoPac for AES {
+    fn free<P: crate::pac::PacPeripheral>(self) -> crate::error::Result<P> {
+        crate::pac::free_into(self, mmio::AES)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -0,0 +1,89 @@
+/// # Asm
+/// Thin wrappers around the Cortex-M4 instructions the HAL needs. When building for
+/// the host (ie. `cargo test`) these become no-ops so driver logic can still be tested.
+macro_rules! instruction {
+    ($($(#[$meta:meta])* $name:ident => $asm:literal;)*) => {
+        $(
+            $(#[$meta])*
+            #[inline(always)]
+            pub fn $name() {
+                #[cfg(target_arch = "arm")]
+                unsafe {
+                    core::arch::asm!($asm, options(nostack, preserves_flags))
+                };
+            }
+        )*
+    };
+}
+
+instruction! {
+    /// # Nop
+    /// No operation.
+    nop => "nop";
+
+    /// # Data Synchronization Barrier
+    /// Wait for every outstanding memory access to complete.
+    dsb => "dsb";
+
+    /// # Instruction Synchronization Barrier
+    /// Flush the pipeline so following instructions see prior context changes.
+    isb => "isb";
+
+    /// # Wait For Interrupt
+    /// Sleep until a interrupt (or debug event) wakes the core.
+    wfi => "wfi";
+
+    /// # Wait For Event
+    /// Sleep until a event, or a interrupt, wakes the core.
+    wfe => "wfe";
+
+    /// # Send Event
+    /// Signal a event to every core waiting in `wfe`.
+    sev => "sev";
+
+    /// # Disable Interrupts
+    /// Mask every configurable priority exception (`PRIMASK = 1`).
+    cpsid => "cpsid i";
+
+    /// # Enable Interrupts
+    /// Unmask the configurable priority exceptions (`PRIMASK = 0`).
+    cpsie => "cpsie i";
+}
+
+/// # Interrupts Masked
+/// Whether `PRIMASK` is set, ie. after `cpsid`. Always `false` on the host.
+#[inline(always)]
+pub fn primask() -> bool {
+    #[cfg(target_arch = "arm")]
+    {
+        let value: u32;
+        unsafe {
+            core::arch::asm!("mrs {}, PRIMASK", out(reg) value, options(nomem, nostack, preserves_flags))
+        };
+        value & 1 == 1
+    }
+
+    #[cfg(not(target_arch = "arm"))]
+    false
+}
+
+/// # Bootstrap
+/// Load the main stack pointer with `stack`, then branch to `entry`, never returning.
+///
+/// # Safety
+/// `stack` must be the top of a valid stack, and `entry` a Thumb function that never
+/// returns. Everything on the current stack is abandoned.
+#[inline(always)]
+pub unsafe fn bootstrap(stack: u32, entry: u32) -> ! {
+    #[cfg(target_arch = "arm")]
+    core::arch::asm!(
+        "msr msp, {stack}",
+        "bx {entry}",
+        stack = in(reg) stack,
+        entry = in(reg) entry,
+        options(noreturn, nomem, nostack),
+    );
+
+    #[cfg(not(target_arch = "arm"))]
+    unreachable!("can not jump to 0x{entry:08x} (sp 0x{stack:08x}) on the host");
+}
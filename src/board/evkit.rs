@@ -0,0 +1,264 @@
+//! # MAX78000EVKIT
+//! Resources of the MAX78000 evaluation kit. See the MAX78000EVKIT schematic.
+//!
+//! `Board::init` takes everything at once as `GpioPin`s. For typed pins (see
+//! `gpio::pin`) the pin bundles (ie. `TftPins`) take the board's pins out of the taken
+//! ports instead, and only accept the right pins.
+//!
+//! With the `display` feature, `display::Display::evkit` drives the TFT from `SpiBus`
+//! and `Tft`.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::board::evkit::TftPins;
+//! use max78000_hal::gpio::pin::Gpio0;
+//!
+//! let port0 = Gpio0::take().unwrap();
+//! let mut tft = TftPins::new(
+//!     port0.p0_7, port0.p0_5, port0.p0_6, port0.p0_11, port0.p0_19, port0.p0_9,
+//! );
+//! tft.backlight.set_high();
+//! ```
+use super::{console, take, take_alternate, take_output, Button, Camera, Led};
+use crate::error::Result;
+use crate::gpio::pin::{Af1, Alternate, Input, Output, Pin, PullUp};
+use crate::gpio::{GpioPin, GpioSelect, ResistorStrength, VoltageSelect};
+use crate::uart::{UART, UART0};
+
+/// # SPI 0 Bus
+/// The SPI 0 pins shared by the TFT and the SD card, switched to the SPI function.
+pub struct SpiBus {
+    /// Serial clock, `P0.7`.
+    pub sck: GpioPin,
+    /// Controller out, `P0.5`.
+    pub mosi: GpioPin,
+    /// Controller in, `P0.6`.
+    pub miso: GpioPin,
+}
+
+/// # TFT
+/// The control pins of the 2.4" TFT display, which sits on `SpiBus`.
+pub struct Tft {
+    /// Chip select (SPI 0 SS1), `P0.11`.
+    pub cs: GpioPin,
+    /// Reset, active low, `P0.19`. Starts released (high).
+    pub reset: GpioPin,
+    /// Backlight enable, `P0.9`. Starts off.
+    pub backlight: GpioPin,
+}
+
+/// # SD Card
+/// The control pins of the micro SD card socket, which sits on `SpiBus`.
+pub struct SdCard {
+    /// Chip select (SPI 0 SS0), `P0.4`.
+    pub cs: GpioPin,
+}
+
+/// # Board
+/// Every resource of the MAX78000EVKIT, configured and ready to use.
+pub struct Board {
+    /// LED1, `P0.2`.
+    pub led_1: Led,
+    /// LED2, `P0.3`.
+    pub led_2: Led,
+    /// Push button SW2, `P2.6`.
+    pub button_1: Button,
+    /// Push button SW3, `P2.7`.
+    pub button_2: Button,
+    /// The SPI 0 bus.
+    pub spi: SpiBus,
+    /// The TFT display.
+    pub tft: Tft,
+    /// The SD card socket.
+    pub sd_card: SdCard,
+    /// The camera connector.
+    pub camera: Camera,
+    /// The console, UART 0 routed to the debug adapter's virtual COM port at
+    /// `super::CONSOLE_BAUD_RATE`, 8N1.
+    pub console: UART<UART0>,
+}
+
+impl Board {
+    /// # Init
+    /// Take and configure every resource on the board. The LEDs and backlight start
+    /// off, and both chip selects start deasserted.
+    ///
+    /// # Errors
+    /// `ErrorKind::Busy` when any of the pins (or peripherals) are already owned, ie.
+    /// when `init` is called twice.
+    pub fn init() -> Result<Self> {
+        Ok(Self {
+            led_1: led(2)?,
+            led_2: led(3)?,
+            button_1: button(6)?,
+            button_2: button(7)?,
+            spi: SpiBus {
+                sck: take_alternate(GpioSelect::Gpio0, 7)?,
+                mosi: take_alternate(GpioSelect::Gpio0, 5)?,
+                miso: take_alternate(GpioSelect::Gpio0, 6)?,
+            },
+            tft: Tft {
+                cs: take_output(GpioSelect::Gpio0, 11, true)?,
+                reset: take_output(GpioSelect::Gpio0, 19, true)?,
+                backlight: take_output(GpioSelect::Gpio0, 9, false)?,
+            },
+            sd_card: SdCard {
+                cs: take_output(GpioSelect::Gpio0, 4, true)?,
+            },
+            camera: Camera::init()?,
+            console: console()?,
+        })
+    }
+}
+
+/// The LEDs are tied to VDDIOH through their resistors, and sink into the pin.
+fn led(pin: usize) -> Result<Led> {
+    Ok(Led::new(
+        take(GpioSelect::Gpio0, pin)?,
+        VoltageSelect::VddIOH,
+        true,
+    ))
+}
+
+/// The buttons short to ground, and rely on the internal pull-up.
+fn button(pin: usize) -> Result<Button> {
+    Ok(Button::new(
+        take(GpioSelect::Gpio2, pin)?,
+        ResistorStrength::WeakPullup,
+        true,
+    ))
+}
+
+/// # LED Pins
+/// LED1 and LED2, outputs sinking the LED current, so low turns them on. They start
+/// off.
+pub struct LedPins {
+    /// LED1, `P0.2`.
+    pub led_1: Pin<0, 2, Output>,
+    /// LED2, `P0.3`.
+    pub led_2: Pin<0, 3, Output>,
+}
+
+impl LedPins {
+    /// # New
+    /// Configure the LED pins.
+    pub fn new<M1, M2>(led_1: Pin<0, 2, M1>, led_2: Pin<0, 3, M2>) -> Self {
+        Self {
+            led_1: led_1
+                .into_push_pull_output_in_state(true)
+                .with_voltage(VoltageSelect::VddIOH),
+            led_2: led_2
+                .into_push_pull_output_in_state(true)
+                .with_voltage(VoltageSelect::VddIOH),
+        }
+    }
+}
+
+/// # Button Pins
+/// The push buttons, shorting to ground, so low is pressed.
+pub struct ButtonPins {
+    /// Push button SW2, `P2.6`.
+    pub sw2: Pin<2, 6, Input<PullUp>>,
+    /// Push button SW3, `P2.7`.
+    pub sw3: Pin<2, 7, Input<PullUp>>,
+}
+
+impl ButtonPins {
+    /// # New
+    /// Configure the button pins.
+    pub fn new<M1, M2>(sw2: Pin<2, 6, M1>, sw3: Pin<2, 7, M2>) -> Self {
+        Self {
+            sw2: sw2.into_pull_up_input(),
+            sw3: sw3.into_pull_up_input(),
+        }
+    }
+}
+
+/// # TFT Pins
+/// The TFT display's SPI 0 pins and control pins. The chip select starts deasserted,
+/// the reset released and the backlight off.
+pub struct TftPins {
+    /// Serial clock, `P0.7`.
+    pub sck: Pin<0, 7, Alternate<Af1>>,
+    /// Controller out, `P0.5`.
+    pub mosi: Pin<0, 5, Alternate<Af1>>,
+    /// Controller in, `P0.6`.
+    pub miso: Pin<0, 6, Alternate<Af1>>,
+    /// Chip select (SPI 0 SS1), `P0.11`.
+    pub cs: Pin<0, 11, Output>,
+    /// Reset, active low, `P0.19`.
+    pub reset: Pin<0, 19, Output>,
+    /// Backlight enable, `P0.9`.
+    pub backlight: Pin<0, 9, Output>,
+}
+
+impl TftPins {
+    /// # New
+    /// Configure the TFT pins.
+    pub fn new<M1, M2, M3, M4, M5, M6>(
+        sck: Pin<0, 7, M1>,
+        mosi: Pin<0, 5, M2>,
+        miso: Pin<0, 6, M3>,
+        cs: Pin<0, 11, M4>,
+        reset: Pin<0, 19, M5>,
+        backlight: Pin<0, 9, M6>,
+    ) -> Self {
+        Self {
+            sck: sck.into_alternate(),
+            mosi: mosi.into_alternate(),
+            miso: miso.into_alternate(),
+            cs: cs.into_push_pull_output_in_state(true),
+            reset: reset.into_push_pull_output_in_state(true),
+            backlight: backlight.into_push_pull_output(),
+        }
+    }
+}
+
+/// # Camera Pins
+/// The camera connector's SCCB bus, I2C 1.
+pub struct CameraPins {
+    /// Clock, `P0.16`.
+    pub scl: Pin<0, 16, Alternate<Af1>>,
+    /// Data, `P0.17`.
+    pub sda: Pin<0, 17, Alternate<Af1>>,
+}
+
+impl CameraPins {
+    /// # New
+    /// Configure the camera pins.
+    pub fn new<M1, M2>(scl: Pin<0, 16, M1>, sda: Pin<0, 17, M2>) -> Self {
+        Self {
+            scl: scl.into_alternate(),
+            sda: sda.into_alternate(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::gpio::pin::{Gpio0, Gpio2};
+
+    #[test]
+    fn typed_bundles_configure_their_pins() {
+        crate::mock::reset();
+        let port0 = Gpio0::take().unwrap();
+        let port2 = Gpio2::take().unwrap();
+
+        let leds = LedPins::new(port0.p0_2, port0.p0_3);
+        assert!(leds.led_1.is_set_high());
+
+        let _buttons = ButtonPins::new(port2.p2_6, port2.p2_7);
+
+        let tft = TftPins::new(
+            port0.p0_7,
+            port0.p0_5,
+            port0.p0_6,
+            port0.p0_11,
+            port0.p0_19,
+            port0.p0_9,
+        );
+        assert!(tft.cs.is_set_high());
+        assert!(tft.backlight.is_set_low());
+    }
+}
@@ -0,0 +1,213 @@
+//! # MAX78000FTHR
+//! Resources of the MAX78000FTHR feather board. See the MAX78000FTHR schematic.
+//!
+//! `Board::init` takes everything at once, the named constructors (ie. `red_led`) take
+//! one resource each, leaving the other pins free for something else.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::board::fthr;
+//!
+//! let mut led = fthr::red_led()?;
+//! let button = fthr::button_1()?;
+//! if button.is_pressed() {
+//!     led.on();
+//! }
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::{console, take, take_alternate, Button, Camera, Led};
+use crate::error::Result;
+use crate::gpio::{GpioPin, GpioSelect, ResistorStrength, VoltageSelect};
+use crate::uart::{UART, UART0};
+
+/// # I2S Microphone
+/// The pins of the on-board I2S MEMS microphone, switched to the I2S function.
+pub struct Microphone {
+    /// Bit clock, `P1.2`.
+    pub sck: GpioPin,
+    /// Word select, `P1.3`.
+    pub ws: GpioPin,
+    /// Serial data from the microphone, `P1.4`.
+    pub sd: GpioPin,
+}
+
+/// # Audio Codec
+/// The I2S pins of the MAX9867 audio codec, switched to the I2S function. The codec
+/// shares the bit clock and word select with the microphone, so take one or the other.
+pub struct AudioCodec {
+    /// Bit clock, `P1.2`.
+    pub sck: GpioPin,
+    /// Word select, `P1.3`.
+    pub ws: GpioPin,
+    /// Serial data from the codec, `P1.4`.
+    pub sdi: GpioPin,
+    /// Serial data to the codec, `P1.5`.
+    pub sdo: GpioPin,
+}
+
+/// # Board
+/// Every resource of the MAX78000FTHR, configured and ready to use.
+pub struct Board {
+    /// The red channel of the RGB LED (LED1), `P2.0`.
+    pub led_red: Led,
+    /// The green channel of the RGB LED (LED1), `P2.1`.
+    pub led_green: Led,
+    /// The blue channel of the RGB LED (LED1), `P2.2`.
+    pub led_blue: Led,
+    /// Push button SW1, `P0.2`.
+    pub button_1: Button,
+    /// Push button SW2, `P1.7`.
+    pub button_2: Button,
+    /// The I2S microphone.
+    pub microphone: Microphone,
+    /// The camera connector.
+    pub camera: Camera,
+    /// The console, UART 0 routed to the debug adapter's virtual COM port at
+    /// `super::CONSOLE_BAUD_RATE`, 8N1.
+    pub console: UART<UART0>,
+}
+
+impl Board {
+    /// # Init
+    /// Take and configure every resource on the board. The LEDs start off.
+    ///
+    /// # Errors
+    /// `ErrorKind::Busy` when any of the pins (or peripherals) are already owned, ie.
+    /// when `init` is called twice.
+    pub fn init() -> Result<Self> {
+        Ok(Self {
+            led_red: red_led()?,
+            led_green: green_led()?,
+            led_blue: blue_led()?,
+            button_1: button_1()?,
+            button_2: button_2()?,
+            microphone: microphone()?,
+            camera: camera()?,
+            console: console()?,
+        })
+    }
+}
+
+/// # Red LED
+/// The red channel of the RGB LED, `P2.0`, off.
+///
+/// # Errors
+/// `ErrorKind::Busy` when the pin is already owned.
+pub fn red_led() -> Result<Led> {
+    led(0)
+}
+
+/// # Green LED
+/// The green channel of the RGB LED, `P2.1`, off.
+///
+/// # Errors
+/// `ErrorKind::Busy` when the pin is already owned.
+pub fn green_led() -> Result<Led> {
+    led(1)
+}
+
+/// # Blue LED
+/// The blue channel of the RGB LED, `P2.2`, off.
+///
+/// # Errors
+/// `ErrorKind::Busy` when the pin is already owned.
+pub fn blue_led() -> Result<Led> {
+    led(2)
+}
+
+/// # Button 1
+/// Push button SW1, `P0.2`.
+///
+/// # Errors
+/// `ErrorKind::Busy` when the pin is already owned.
+pub fn button_1() -> Result<Button> {
+    button(GpioSelect::Gpio0, 2)
+}
+
+/// # Button 2
+/// Push button SW2, `P1.7`.
+///
+/// # Errors
+/// `ErrorKind::Busy` when the pin is already owned.
+pub fn button_2() -> Result<Button> {
+    button(GpioSelect::Gpio1, 7)
+}
+
+/// # Microphone
+/// The I2S microphone's pins.
+///
+/// # Errors
+/// `ErrorKind::Busy` when any of the pins is already owned, ie. by `audio_codec`.
+pub fn microphone() -> Result<Microphone> {
+    Ok(Microphone {
+        sck: i2s_pin(2)?,
+        ws: i2s_pin(3)?,
+        sd: i2s_pin(4)?,
+    })
+}
+
+/// # Audio Codec
+/// The audio codec's I2S pins.
+///
+/// # Errors
+/// `ErrorKind::Busy` when any of the pins is already owned, ie. by `microphone`.
+pub fn audio_codec() -> Result<AudioCodec> {
+    Ok(AudioCodec {
+        sck: i2s_pin(2)?,
+        ws: i2s_pin(3)?,
+        sdi: i2s_pin(4)?,
+        sdo: i2s_pin(5)?,
+    })
+}
+
+/// # Camera
+/// The camera connector's SCCB bus.
+///
+/// # Errors
+/// `ErrorKind::Busy` when I2C 1 or its pins are already owned.
+pub fn camera() -> Result<Camera> {
+    Camera::init()
+}
+
+/// The RGB LED is common anode, tied to VDDIOH.
+fn led(pin: usize) -> Result<Led> {
+    Ok(Led::new(
+        take(GpioSelect::Gpio2, pin)?,
+        VoltageSelect::VddIOH,
+        true,
+    ))
+}
+
+/// The buttons short to ground, and rely on the internal pull-up.
+fn button(port: GpioSelect, pin: usize) -> Result<Button> {
+    Ok(Button::new(
+        take(port, pin)?,
+        ResistorStrength::WeakPullup,
+        true,
+    ))
+}
+
+fn i2s_pin(pin: usize) -> Result<GpioPin> {
+    take_alternate(GpioSelect::Gpio1, pin)
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn named_resources_are_taken_once() {
+        crate::mock::reset();
+
+        let mut led = red_led().unwrap();
+        assert_eq!(red_led().err(), Some(ErrorKind::Busy));
+        led.on();
+        assert!(led.is_on());
+
+        let _microphone = microphone().unwrap();
+        assert_eq!(audio_codec().err(), Some(ErrorKind::Busy));
+        // The failed constructor took nothing
+        assert!(GpioPin::new(GpioSelect::Gpio1, 5).is_some());
+    }
+}
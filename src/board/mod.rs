@@ -0,0 +1,185 @@
+//! # Board Support
+//! Named, pre-configured resources for the MAX78000 development boards. Each board lives
+//! behind its own feature, ie. `board-fthr` or `board-evkit`, and is brought up with one `Board::init()`.
+use crate::error::{ErrorKind, Result};
+use crate::gpio::{
+    GpioPin, GpioSelect, OutputDriveStrength, PinFunction, ResistorStrength, VoltageSelect,
+};
+use crate::i2c::{I2CPort1, I2C};
+use crate::uart::{BaudRates, CharacterLength, Parity, ParityValueSelect, StopBits, UART, UART0};
+
+#[cfg(feature = "board-evkit")]
+pub mod evkit;
+#[cfg(feature = "board-fthr")]
+pub mod fthr;
+
+/// # Console Baud Rate
+/// The baud rate the console UART is set up with.
+pub const CONSOLE_BAUD_RATE: BaudRates = BaudRates::Baud115200;
+
+/// # Camera
+/// The camera connector's control bus. The camera is configured over SCCB, which the
+/// I2C 1 master can talk.
+pub struct Camera {
+    /// The SCCB bus, I2C 1 on `P0.16` (SCL) and `P0.17` (SDA).
+    pub bus: I2C<I2CPort1>,
+}
+
+impl Camera {
+    pub(crate) fn init() -> Result<Self> {
+        Ok(Self {
+            bus: I2C::init_port_1_master()?,
+        })
+    }
+}
+
+/// # Led
+/// A on-board LED, hiding which level turns it on.
+pub struct Led {
+    pin: GpioPin,
+    active_low: bool,
+}
+
+impl Led {
+    /// # New
+    /// Configure `pin` as a output driving a LED, and turn the LED off.
+    pub(crate) fn new(pin: GpioPin, voltage: VoltageSelect, active_low: bool) -> Self {
+        pin.configure_output(OutputDriveStrength::Strength0(voltage), PinFunction::IO);
+
+        let mut led = Self { pin, active_low };
+        led.off();
+        led
+    }
+
+    /// # Set
+    /// Turn the LED on (`true`) or off (`false`).
+    pub fn set(&mut self, on: bool) {
+        self.pin.set_output(on != self.active_low);
+    }
+
+    /// # On
+    /// Turn the LED on.
+    pub fn on(&mut self) {
+        self.set(true);
+    }
+
+    /// # Off
+    /// Turn the LED off.
+    pub fn off(&mut self) {
+        self.set(false);
+    }
+
+    /// # Is On
+    /// Check if the LED is currently being driven on.
+    pub fn is_on(&self) -> bool {
+        self.pin.get_output() != self.active_low
+    }
+
+    /// # Toggle
+    /// Turn the LED on if it was off, and off if it was on.
+    pub fn toggle(&mut self) {
+        let on = self.is_on();
+        self.set(!on);
+    }
+
+    /// # Free
+    /// Give up the LED, returning the pin it was using.
+    pub fn free(self) -> GpioPin {
+        self.pin
+    }
+}
+
+/// # Button
+/// A on-board push button, hiding which level means pressed.
+pub struct Button {
+    pin: GpioPin,
+    active_low: bool,
+}
+
+impl Button {
+    /// # New
+    /// Configure `pin` as a input reading a button, with the given pull resistor.
+    pub(crate) fn new(pin: GpioPin, pull: ResistorStrength, active_low: bool) -> Self {
+        pin.configure_input(pull, PinFunction::IO);
+
+        Self { pin, active_low }
+    }
+
+    /// # Is Pressed
+    /// Check if the button is currently held down. No debouncing is done.
+    pub fn is_pressed(&self) -> bool {
+        self.pin.get_input() != self.active_low
+    }
+
+    /// # Free
+    /// Give up the button, returning the pin it was using.
+    pub fn free(self) -> GpioPin {
+        self.pin
+    }
+}
+
+/// # Console
+/// Set up UART 0, which both boards route to the debug adapter's virtual COM port, at
+/// `CONSOLE_BAUD_RATE` 8N1.
+pub(crate) fn console() -> Result<UART<UART0>> {
+    UART::port_0_init(
+        CONSOLE_BAUD_RATE,
+        CharacterLength::EightBits,
+        StopBits::OneBit,
+        false,
+        Parity::Odd,
+        ParityValueSelect::OneBased,
+        false,
+    )
+}
+
+/// # Take
+/// Take ownership of a pin.
+pub(crate) fn take(port: GpioSelect, pin: usize) -> Result<GpioPin> {
+    GpioPin::new(port, pin).ok_or(ErrorKind::Busy)
+}
+
+/// # Take Alternate
+/// Take ownership of a pin, and switch it to alternate function 1.
+pub(crate) fn take_alternate(port: GpioSelect, pin: usize) -> Result<GpioPin> {
+    let pin = take(port, pin)?;
+    pin.configure_input(ResistorStrength::None, PinFunction::AF1);
+    Ok(pin)
+}
+
+/// # Take Output
+/// Take ownership of a pin, and make it a output driven to `level`.
+#[cfg_attr(not(feature = "board-evkit"), allow(unused))]
+pub(crate) fn take_output(port: GpioSelect, pin: usize, level: bool) -> Result<GpioPin> {
+    let pin = take(port, pin)?;
+    pin.set_output(level);
+    pin.configure_output(
+        OutputDriveStrength::Strength0(VoltageSelect::VddIO),
+        PinFunction::IO,
+    );
+    Ok(pin)
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::gpio::GpioSelect;
+    use crate::mock;
+
+    #[test]
+    fn active_low_led() {
+        mock::reset();
+
+        let pin = GpioPin::new(GpioSelect::Gpio2, 5).unwrap();
+        let mut led = Led::new(pin, VoltageSelect::VddIOH, true);
+        assert!(!led.is_on());
+
+        led.on();
+        assert!(led.is_on());
+        // Active low, so the output is driven low to turn it on
+        assert!(!led.pin.get_output());
+
+        led.toggle();
+        assert!(!led.is_on());
+    }
+}
@@ -0,0 +1,98 @@
+//! # ROM Bootloader
+//! Re-enter the Maxim ROM bootloader from application code, so a device in the field
+//! can be reflashed over UART without a debugger.
+//!
+//! There are two ways in:
+//! - `enter()` jumps straight into the ROM, after putting the core back into a reset
+//!   like state.
+//! - `enter_on_reset()` sets a flag in `GCR_GPR0` and soft resets. The application
+//!   then calls `check_request()` first thing in `main`, and enters the ROM with every
+//!   peripheral still in its reset state. Prefer this when the application has
+//!   configured DMA, the CNN or the RISC-V core, which `enter()` does not undo.
+use crate::bits::BitManipulation;
+use crate::interrupt::INTERRUPT_COUNT;
+use crate::memory_map::{memory, scs};
+
+/// # Request Flag
+/// The value `enter_on_reset` leaves in `GCR_GPR0` ("BOOT" in ASCII).
+pub const REQUEST_FLAG: u32 = 0x424F_4F54;
+
+/// # SysTick Enable
+/// The `ENABLE` bit of `SYST_CSR`. See ARMv7-M Reference, B3.3.3.
+const SYST_CSR_ENABLE: u8 = 0;
+
+/// # Enter
+/// Jump into the ROM bootloader. The sequence is:
+/// 1. Mask interrupts, and stop SysTick.
+/// 2. Disable, and clear the pending state of, every NVIC line.
+/// 3. Point the VTOR at the ROM vector table.
+/// 4. Load the main stack pointer from the ROM vector table, and jump to its reset
+///    handler.
+///
+/// Peripherals are left as they are, see `enter_on_reset` for a cleaner entry.
+pub fn enter() -> ! {
+    crate::asm::cpsid();
+
+    unsafe {
+        let mut csr = core::ptr::read_volatile(hal_macros::mmio_ptr::<u32>(scs::SYST_CSR));
+        csr.set_bit(SYST_CSR_ENABLE, false);
+        core::ptr::write_volatile(hal_macros::mmio_ptr::<u32>(scs::SYST_CSR), csr);
+
+        for bank in 0..(INTERRUPT_COUNT as usize).div_ceil(32) {
+            core::ptr::write_volatile(
+                hal_macros::mmio_ptr::<u32>(scs::NVIC_ICER + bank * 4),
+                u32::MAX,
+            );
+            core::ptr::write_volatile(
+                hal_macros::mmio_ptr::<u32>(scs::NVIC_ICPR + bank * 4),
+                u32::MAX,
+            );
+        }
+
+        core::ptr::write_volatile(
+            hal_macros::mmio_ptr::<u32>(scs::SCB_VTOR),
+            memory::ROM.0 as u32,
+        );
+        crate::asm::dsb();
+        crate::asm::isb();
+
+        let stack = core::ptr::read_volatile(hal_macros::mmio_ptr::<u32>(memory::ROM.0));
+        let entry = core::ptr::read_volatile(hal_macros::mmio_ptr::<u32>(memory::ROM.0 + 4));
+        crate::asm::bootstrap(stack, entry)
+    }
+}
+
+/// # Enter On Reset
+/// Leave `REQUEST_FLAG` in `GCR_GPR0` and soft reset. `check_request()` must run early
+/// in the application for the ROM to be entered.
+pub fn enter_on_reset() -> ! {
+    crate::gcr::set_general_purpose_register(REQUEST_FLAG);
+    crate::gcr::soft_reset()
+}
+
+/// # Check Request
+/// If `enter_on_reset` left its flag, clear it and enter the ROM bootloader. Call this
+/// before setting up any peripherals.
+pub fn check_request() {
+    if crate::gcr::get_general_purpose_register() == REQUEST_FLAG {
+        crate::gcr::set_general_purpose_register(0);
+        enter();
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::memory_map::mmio;
+    use crate::mock;
+
+    #[test]
+    fn check_request_without_flag() {
+        mock::reset();
+        mock::write(mmio::GLOBAL_CONTROL + 0x80, 0x1234);
+
+        // No flag, so this must return (instead of jumping) and leave GPR0 alone
+        check_request();
+        assert_eq!(mock::read(mmio::GLOBAL_CONTROL + 0x80), 0x1234);
+    }
+}
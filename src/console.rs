@@ -0,0 +1,386 @@
+//! # Console
+//! A single global byte sink that `uprint!`/`uprintln!` (or `print!`/`println!`), the
+//! panic handler (with the `console-panic` feature) and the logger (with the `log`
+//! feature) all write into.
+//! Anything implementing `embedded_io::Write` can be installed, ie. a UART, a buffered
+//! UART, a RTT channel or a semihosting adapter, so call sites never need to know
+//! where output ends up.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::uart::{UART, UART0};
+//! use max78000_hal::{console, uprintln};
+//! # use max78000_hal::uart::{BaudRates, CharacterLength, Parity, ParityValueSelect, StopBits};
+//!
+//! static mut SERIAL: Option<UART<UART0>> = None;
+//!
+//! let uart = UART::port_0_init(
+//!     // ...
+//! #     BaudRates::Baud115200,
+//! #     CharacterLength::EightBits,
+//! #     StopBits::OneBit,
+//! #     false,
+//! #     Parity::Odd,
+//! #     ParityValueSelect::OneBased,
+//! #     false,
+//! )?;
+//! // Only ever borrowed here, so the console holds the one reference to it
+//! console::install(unsafe { (*core::ptr::addr_of_mut!(SERIAL)).insert(uart) });
+//!
+//! uprintln!("Hello from the console!");
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use core::fmt;
+
+use crate::uart::private::UARTPortCompatable;
+use crate::uart::{BaudClock, UART};
+
+/// # Sink
+/// `embedded_io::Write` has a associated error type, so it can not be made into a
+/// trait object. This erases it.
+trait Sink {
+    fn write_bytes(&mut self, bytes: &[u8]) -> fmt::Result;
+    fn flush(&mut self) -> fmt::Result;
+}
+
+impl<W: embedded_io::Write> Sink for W {
+    fn write_bytes(&mut self, bytes: &[u8]) -> fmt::Result {
+        self.write_all(bytes).map_err(|_| fmt::Error)
+    }
+
+    fn flush(&mut self) -> fmt::Result {
+        embedded_io::Write::flush(self).map_err(|_| fmt::Error)
+    }
+}
+
+/// # Sleep Aware
+/// A console that needs to prepare for deep sleep, ie. by flushing and switching to a
+/// clock that keeps running. See `install_sleep_aware` and `power::deep_sleep`.
+pub trait SleepAware {
+    /// # Before Sleep
+    /// Called right before the core enters deep sleep.
+    fn before_sleep(&mut self);
+
+    /// # After Sleep
+    /// Called right after the core woke up from deep sleep.
+    fn after_sleep(&mut self);
+}
+
+// Both point at the same installed writer, so they are kept as raw pointers and only
+// turned into a reference for the duration of a single call
+static mut CONSOLE: Option<*mut dyn Sink> = None;
+static mut SLEEP: Option<*mut dyn SleepAware> = None;
+
+/// # Install
+/// Make `writer` the global console, replacing the one installed before.
+///
+/// Install the console before enabling any interrupt that prints, since installing is
+/// not atomic with respect to a interrupt printing at the same time.
+pub fn install<W: embedded_io::Write + 'static>(writer: &'static mut W) {
+    unsafe {
+        SLEEP = None;
+        CONSOLE = Some(writer as *mut W as *mut dyn Sink);
+    }
+}
+
+/// # Install Sleep Aware
+/// Like `install`, but `writer` is also told about deep sleep transitions made with
+/// `power::deep_sleep`, ie. a `LowPowerConsole`.
+pub fn install_sleep_aware<W: embedded_io::Write + SleepAware + 'static>(writer: &'static mut W) {
+    let writer = writer as *mut W;
+    unsafe {
+        CONSOLE = Some(writer as *mut dyn Sink);
+        SLEEP = Some(writer as *mut dyn SleepAware);
+    }
+}
+
+/// # Remove
+/// Remove the global console, output is discarded until a new one is installed.
+pub fn remove() {
+    unsafe {
+        SLEEP = None;
+        CONSOLE = None;
+    }
+}
+
+/// # Before Sleep
+/// Let a sleep aware console prepare for deep sleep. Called by `power::deep_sleep`.
+pub fn before_sleep() {
+    if let Some(sleep) = unsafe { SLEEP } {
+        unsafe { (*sleep).before_sleep() };
+    }
+}
+
+/// # After Sleep
+/// Let a sleep aware console restore itself after deep sleep. Called by
+/// `power::deep_sleep`.
+pub fn after_sleep() {
+    if let Some(sleep) = unsafe { SLEEP } {
+        unsafe { (*sleep).after_sleep() };
+    }
+}
+
+/// # Is Installed
+/// Check if there is a console to write into.
+pub fn is_installed() -> bool {
+    unsafe { (*core::ptr::addr_of!(CONSOLE)).is_some() }
+}
+
+/// # Write Bytes
+/// Write raw bytes into the console. Without a console the bytes are discarded.
+pub fn write_bytes(bytes: &[u8]) -> fmt::Result {
+    match unsafe { CONSOLE } {
+        Some(sink) => unsafe { (*sink).write_bytes(bytes) },
+        None => Ok(()),
+    }
+}
+
+/// # Flush
+/// Wait until everything written so far has left the console.
+pub fn flush() -> fmt::Result {
+    match unsafe { CONSOLE } {
+        Some(sink) => unsafe { (*sink).flush() },
+        None => Ok(()),
+    }
+}
+
+/// # Low Power Console
+/// A UART console that survives deep sleep. Before sleeping it flushes the transmit
+/// FIFO and moves the baud generator to the IBRO, after waking it restores the
+/// previous (full speed) clocking. Install it with `install_sleep_aware`.
+pub struct LowPowerConsole<Port> {
+    uart: UART<Port>,
+    saved: Option<BaudClock>,
+}
+
+impl<Port: UARTPortCompatable> LowPowerConsole<Port> {
+    /// # New
+    /// Wrap a initialized UART.
+    pub fn new(uart: UART<Port>) -> Self {
+        Self { uart, saved: None }
+    }
+
+    /// # Free
+    /// Give back the UART, restoring its clocking if it is still set up for sleep.
+    pub fn free(mut self) -> UART<Port> {
+        self.after_sleep();
+        self.uart
+    }
+}
+
+impl<Port: UARTPortCompatable> embedded_io::ErrorType for LowPowerConsole<Port> {
+    type Error = crate::Error;
+}
+
+impl<Port: UARTPortCompatable> embedded_io::Write for LowPowerConsole<Port> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.uart.write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        embedded_io::Write::flush(&mut self.uart)
+    }
+}
+
+impl<Port: UARTPortCompatable> SleepAware for LowPowerConsole<Port> {
+    fn before_sleep(&mut self) {
+        if self.saved.is_none() {
+            self.saved = Some(self.uart.switch_to_ibro());
+        }
+    }
+
+    fn after_sleep(&mut self) {
+        if let Some(clock) = self.saved.take() {
+            self.uart.set_baud_clock(clock);
+        }
+    }
+}
+
+/// # Console
+/// A `core::fmt::Write` handle to the global console.
+pub struct Console;
+
+impl fmt::Write for Console {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        write_bytes(s.as_bytes())
+    }
+}
+
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    use core::fmt::Write;
+    // Nowhere to report a failed print to, so it is dropped
+    let _ = Console.write_fmt(args);
+}
+
+/// # Console Print
+/// Print into the global console, see `console::install`.
+#[macro_export]
+macro_rules! uprint {
+    ($($arg:tt)*) => {
+        $crate::console::_print(format_args!($($arg)*))
+    };
+}
+
+/// # Console Print Line
+/// Print a line into the global console, see `console::install`.
+#[macro_export]
+macro_rules! uprintln {
+    () => {
+        $crate::uprint!("\n")
+    };
+    ($($arg:tt)*) => {{
+        $crate::console::_print(format_args!($($arg)*));
+        $crate::uprint!("\n");
+    }};
+}
+
+/// # Print
+/// Print into the global console, like `uprint!`. Register a `uart::Console` (or
+/// `console::install` any writer) first, output is discarded until then.
+#[macro_export]
+macro_rules! print {
+    ($($arg:tt)*) => {
+        $crate::console::_print(format_args!($($arg)*))
+    };
+}
+
+/// # Print Line
+/// Print a line into the global console, like `uprintln!`.
+#[macro_export]
+macro_rules! println {
+    () => {
+        $crate::print!("\n")
+    };
+    ($($arg:tt)*) => {{
+        $crate::console::_print(format_args!($($arg)*));
+        $crate::print!("\n");
+    }};
+}
+
+/// # Panic Handler
+/// Print the panic message into the console, flush it, and halt.
+#[cfg(all(feature = "console-panic", target_os = "none"))]
+#[panic_handler]
+fn panic(info: &core::panic::PanicInfo) -> ! {
+    crate::uprintln!("{}", info);
+    let _ = flush();
+
+    loop {
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+/// # Logger
+/// A `log` backend writing every record into the console as `[LEVEL] message`.
+#[cfg(feature = "log")]
+pub struct Logger;
+
+#[cfg(feature = "log")]
+static LOGGER: Logger = Logger;
+
+#[cfg(feature = "log")]
+impl log::Log for Logger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        is_installed()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            crate::uprintln!("[{}] {}", record.level(), record.args());
+        }
+    }
+
+    fn flush(&self) {
+        let _ = flush();
+    }
+}
+
+/// # Init Logger
+/// Make the console the `log` backend, showing records up to `level`.
+///
+/// # Errors
+/// When a other logger was already set.
+#[cfg(feature = "log")]
+pub fn init_logger(level: log::LevelFilter) -> Result<(), log::SetLoggerError> {
+    log::set_logger(&LOGGER)?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Buffer {
+        bytes: [u8; 64],
+        len: usize,
+        sleeping: bool,
+    }
+
+    impl SleepAware for Buffer {
+        fn before_sleep(&mut self) {
+            self.sleeping = true;
+        }
+
+        fn after_sleep(&mut self) {
+            self.sleeping = false;
+        }
+    }
+
+    impl embedded_io::ErrorType for Buffer {
+        type Error = core::convert::Infallible;
+    }
+
+    impl embedded_io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            let count = buf.len().min(self.bytes.len() - self.len);
+            self.bytes[self.len..self.len + count].copy_from_slice(&buf[..count]);
+            self.len += count;
+            Ok(count)
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    static mut BUFFER: Buffer = Buffer {
+        bytes: [0; 64],
+        len: 0,
+        sleeping: false,
+    };
+
+    #[test]
+    fn prints_into_installed_sink() {
+        // Nothing installed, output is dropped
+        crate::uprintln!("lost");
+
+        install(unsafe { &mut *core::ptr::addr_of_mut!(BUFFER) });
+        assert!(is_installed());
+        crate::uprint!("a = {}", 1);
+        crate::uprintln!(", b = {}", 2);
+        crate::print!("c");
+        crate::println!();
+        remove();
+
+        crate::uprintln!("lost");
+        let buffer = unsafe { &*core::ptr::addr_of!(BUFFER) };
+        assert_eq!(&buffer.bytes[..buffer.len], b"a = 1, b = 2\nc\n");
+
+        // Only a sleep aware install is told about sleep
+        install(unsafe { &mut *core::ptr::addr_of_mut!(BUFFER) });
+        before_sleep();
+        assert!(!unsafe { BUFFER.sleeping });
+
+        install_sleep_aware(unsafe { &mut *core::ptr::addr_of_mut!(BUFFER) });
+        before_sleep();
+        assert!(unsafe { BUFFER.sleeping });
+        after_sleep();
+        assert!(!unsafe { BUFFER.sleeping });
+
+        remove();
+        before_sleep();
+        assert!(!unsafe { BUFFER.sleeping });
+    }
+}
@@ -1,8 +1,8 @@
 static mut DEBUG_OUTPUT_STREAM: DebugStream = DebugStream(None);
 
-pub struct DebugStream(Option<&'static mut (dyn core::fmt::Write)>);
+pub struct DebugStream(Option<&'static mut dyn core::fmt::Write>);
 
-pub fn attach_debug(stream: &'static mut (dyn core::fmt::Write)) {
+pub fn attach_debug(stream: &'static mut dyn core::fmt::Write) {
     unsafe {
         DEBUG_OUTPUT_STREAM.0 = Some(stream);
     }
@@ -34,7 +34,11 @@ impl core::fmt::Write for DebugStream {
 #[doc(hidden)]
 pub fn _print(args: ::core::fmt::Arguments) {
     use core::fmt::Write;
-    unsafe { DEBUG_OUTPUT_STREAM.write_fmt(args).unwrap() };
+    unsafe {
+        (*core::ptr::addr_of_mut!(DEBUG_OUTPUT_STREAM))
+            .write_fmt(args)
+            .unwrap()
+    };
 }
 
 #[macro_export]
@@ -54,3 +58,22 @@ macro_rules! debug_println {
         $crate::debug_print!("\n");
     }};
 }
+
+/// # Defmt Trace Hook
+/// A register access tracing hook that logs every access with `defmt::trace!`. Install
+/// it with `trace::set_hook(Some(defmt_trace_hook))`.
+#[cfg(all(feature = "trace-mmio", feature = "defmt"))]
+pub fn defmt_trace_hook(event: &crate::trace::Event) {
+    let kind = match event.kind {
+        crate::trace::AccessKind::Read => "R",
+        crate::trace::AccessKind::Write => "W",
+    };
+
+    defmt::trace!(
+        "{=str} {=usize:#010x} = {=u64:#010x} ({=str})",
+        kind,
+        event.address,
+        event.value,
+        event.field.unwrap_or("-"),
+    );
+}
@@ -0,0 +1,96 @@
+//! # Delay
+//! Busy wait delays, counted with the DWT cycle counter (see `profiling`). Implements
+//! the embedded-hal delay traits with the `eh0` and `eh1` features, for drivers that
+//! need to be handed a delay provider.
+use crate::profiling;
+use crate::time::Micros;
+
+/// # Delay
+/// A busy wait delay provider. Every instance shares the one cycle counter, so any
+/// number of them can be made.
+#[derive(Clone, Copy)]
+pub struct Delay {
+    cycles_per_micro: u32,
+}
+
+impl Delay {
+    /// # New
+    /// Make a delay provider for the current core clock, starting the cycle counter if
+    /// it is not running yet.
+    pub fn new() -> Self {
+        if !profiling::is_enabled() {
+            profiling::enable();
+        }
+
+        Self {
+            cycles_per_micro: crate::core_clock().to_MHz().max(1),
+        }
+    }
+
+    /// # Delay Cycles
+    /// Busy wait for at least `cycles` core clock cycles.
+    pub fn delay_cycles(&self, cycles: u32) {
+        let start = profiling::now();
+        while profiling::now().wrapping_sub(start) < cycles {}
+    }
+
+    /// # Delay
+    /// Busy wait for at least `duration`.
+    pub fn delay(&self, duration: Micros) {
+        // Split long delays so the cycle count never wraps
+        let max_us = u32::MAX / 2 / self.cycles_per_micro;
+        let mut left = duration.to_micros();
+        while left > 0 {
+            let step = left.min(max_us);
+            self.delay_cycles(step * self.cycles_per_micro);
+            left -= step;
+        }
+    }
+}
+
+impl Default for Delay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "eh0")]
+macro_rules! impl_eh0_delay {
+    ($($ty:ty),*) => {
+        $(
+            impl embedded_hal_0_2::blocking::delay::DelayUs<$ty> for Delay {
+                fn delay_us(&mut self, us: $ty) {
+                    self.delay(Micros::micros(us as u32));
+                }
+            }
+
+            impl embedded_hal_0_2::blocking::delay::DelayMs<$ty> for Delay {
+                fn delay_ms(&mut self, ms: $ty) {
+                    for _ in 0..ms {
+                        self.delay(Micros::millis(1));
+                    }
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "eh0")]
+impl_eh0_delay!(u8, u16, u32);
+
+#[cfg(feature = "eh1")]
+impl embedded_hal::delay::DelayNs for Delay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.delay(Micros::micros(ns.div_ceil(1_000)));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        self.delay(Micros::micros(us));
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        for _ in 0..ms {
+            self.delay(Micros::millis(1));
+        }
+    }
+}
@@ -0,0 +1,496 @@
+//! # TFT Display
+//! A driver for ILI9341 compatible TFT controllers on a SPI bus, like the 2.4" display
+//! of the MAX78000EVKIT. The panel is driven in landscape, `WIDTH` by `HEIGHT`, with
+//! 16 bit RGB565 pixels.
+//!
+//! `Display` implements `embedded_graphics_core::draw_target::DrawTarget`, so
+//! embedded-graphics can draw on it directly, each drawing streamed over the bus. Whole
+//! frames (or any area) already in memory are sent with `Display::blit` instead, moved
+//! by DMA from a buffer laid out by `encode`.
+//!
+//! The controller tells commands from data with its D/C line. When it is wired to a pin
+//! the characters are 8 bits, otherwise the display is in its 9 bit serial mode, where
+//! the first bit of each character carries D/C (see `DataCommand`). The EVKIT does not
+//! route a D/C pin, so `Display::evkit` uses the latter.
+//!
+//! # Example
+//! ```no_run
+//! use embedded_graphics_core::draw_target::DrawTarget;
+//! use embedded_graphics_core::geometry::{Point, Size};
+//! use embedded_graphics_core::pixelcolor::{Rgb565, RgbColor};
+//! use embedded_graphics_core::primitives::Rectangle;
+//! use max78000_hal::delay::Delay;
+//! use max78000_hal::display::{DataCommand, Display};
+//! use max78000_hal::dma::DmaChannel;
+//! use max78000_hal::spi::{SpiMode, SPI};
+//! use max78000_hal::time::Hertz;
+//! # use max78000_hal::gpio::{GpioPin, GpioSelect};
+//! # let [cs, reset] = [11, 19].map(|pin| GpioPin::new(GpioSelect::Gpio0, pin).unwrap());
+//! # let backlight = GpioPin::new(GpioSelect::Gpio0, 9).unwrap();
+//! # let colors = core::iter::repeat(Rgb565::RED);
+//!
+//! let spi = SPI::port_0_init(SpiMode::Mode0, 9, Hertz::MHz(10))?;
+//! let mut display = Display::new(spi, cs, reset, DataCommand::NinthBit, Some(backlight))?;
+//! display.init(&mut Delay::new())?;
+//! display.clear(Rgb565::BLACK)?;
+//!
+//! // A 32 by 32 sprite, 4 bytes a pixel in the 9 bit mode
+//! let mut sprite = [0; 32 * 32 * 4];
+//! for (color, bytes) in colors.zip(sprite.chunks_exact_mut(display.pixel_bytes())) {
+//!     display.encode(color, bytes);
+//! }
+//! let (mut tx, mut rx) = (DmaChannel::new(0)?, DmaChannel::new(1)?);
+//! let area = Rectangle::new(Point::new(144, 104), Size::new(32, 32));
+//! display.blit(&mut tx, &mut rx, &area, &sprite)?;
+//! # Ok::<(), max78000_hal::Error>(())
+//! ```
+use crate::dma::DmaChannel;
+use crate::error::{ErrorKind, Peripheral, Result};
+use crate::gpio::GpioPin;
+use crate::spi::{private, SPI};
+use embedded_graphics_core::draw_target::DrawTarget;
+use embedded_graphics_core::geometry::{Dimensions, OriginDimensions, Point, Size};
+use embedded_graphics_core::pixelcolor::{IntoStorage, Rgb565};
+use embedded_graphics_core::primitives::Rectangle;
+use embedded_graphics_core::Pixel;
+use embedded_hal::delay::DelayNs;
+
+/// # Width
+/// The horizontal pixels of the panel, in landscape.
+pub const WIDTH: usize = 320;
+
+/// # Height
+/// The vertical pixels of the panel, in landscape.
+pub const HEIGHT: usize = 240;
+
+/// Leave sleep mode.
+const SLEEP_OUT: u8 = 0x11;
+/// Turn the panel on.
+const DISPLAY_ON: u8 = 0x29;
+/// Set the first and last column written.
+const COLUMN_ADDRESS: u8 = 0x2A;
+/// Set the first and last row written.
+const PAGE_ADDRESS: u8 = 0x2B;
+/// Write pixels into the window set, left to right then top to bottom.
+const MEMORY_WRITE: u8 = 0x2C;
+/// Set the scan order, see `LANDSCAPE`.
+const MEMORY_ACCESS: u8 = 0x36;
+/// Set the pixel format, see `RGB565`.
+const PIXEL_FORMAT: u8 = 0x3A;
+
+/// Rows and columns exchanged, and BGR ordered subpixels.
+const LANDSCAPE: u8 = 0x28;
+/// 16 bits a pixel.
+const RGB565: u8 = 0x55;
+
+/// Bytes buffered on the stack while streaming pixels.
+const STREAM_BYTES: usize = 32;
+
+/// The most pixels a single DMA transfer moves, each pixel is two characters.
+const BLIT_PIXELS: usize = 0xFFFF / 2;
+
+/// # Data Command
+/// How the controller is told whether a character is a command or data.
+pub enum DataCommand {
+    /// A pin driven high for data and low for commands.
+    Pin(GpioPin),
+    /// The 9 bit serial mode, the first bit of each character is set for data.
+    NinthBit,
+}
+
+/// # Display
+/// A TFT display on a SPI port, selected by a GPIO pin.
+pub struct Display<Port: private::SPIPortCompatable> {
+    spi: SPI<Port>,
+    cs: GpioPin,
+    reset: GpioPin,
+    dc: DataCommand,
+    backlight: Option<GpioPin>,
+}
+
+impl<Port: private::SPIPortCompatable> Display<Port> {
+    /// # New
+    /// Take the display on `spi`. The pins must already be outputs: `cs`, the chip
+    /// select, is driven low while selected, `reset` low to reset the controller and
+    /// `backlight` (when there is one) high to light the panel.
+    ///
+    /// The word size is set to 8 bits, or 9 for `DataCommand::NinthBit`, and the
+    /// hardware slave selects are deselected. The controller reads at up to 10 MHz.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadState` when `spi` is in a dual or quad data width.
+    pub fn new(
+        mut spi: SPI<Port>,
+        cs: GpioPin,
+        reset: GpioPin,
+        dc: DataCommand,
+        backlight: Option<GpioPin>,
+    ) -> Result<Self> {
+        if spi.data_width() != crate::spi::DataWidth::Single {
+            return Err(ErrorKind::BadState);
+        }
+        spi.set_word_size(match dc {
+            DataCommand::Pin(_) => 8,
+            DataCommand::NinthBit => 9,
+        })?;
+        spi.deselect();
+
+        cs.set_output(true);
+        reset.set_output(true);
+        if let Some(backlight) = &backlight {
+            backlight.set_output(false);
+        }
+        Ok(Self {
+            spi,
+            cs,
+            reset,
+            dc,
+            backlight,
+        })
+    }
+
+    /// # Init
+    /// Reset the controller and bring it up in landscape with RGB565 pixels, then turn
+    /// on the backlight. The panel holds noise until it is first drawn on. Takes about
+    /// 250 ms.
+    pub fn init(&mut self, delay: &mut impl DelayNs) -> Result<()> {
+        self.reset.set_output(false);
+        delay.delay_ms(10);
+        self.reset.set_output(true);
+        delay.delay_ms(120);
+
+        self.command(SLEEP_OUT, &[])?;
+        delay.delay_ms(120);
+        self.command(PIXEL_FORMAT, &[RGB565])?;
+        self.command(MEMORY_ACCESS, &[LANDSCAPE])?;
+        self.command(DISPLAY_ON, &[])?;
+
+        self.set_backlight(true);
+        Ok(())
+    }
+
+    /// # Set Backlight
+    /// Turn the backlight on (`true`) or off, when there is one.
+    pub fn set_backlight(&mut self, on: bool) {
+        if let Some(backlight) = &self.backlight {
+            backlight.set_output(on);
+        }
+    }
+
+    /// # Command
+    /// Send `command` to the controller, followed by its `parameters`.
+    pub fn command(&mut self, command: u8, parameters: &[u8]) -> Result<()> {
+        self.selected(|display| {
+            display.send(false, &[command])?;
+            display.send(true, parameters)
+        })
+    }
+
+    /// # Pixel Bytes
+    /// The bytes a pixel takes in the buffers of `blit`, 2 or 4 in the 9 bit mode.
+    pub fn pixel_bytes(&self) -> usize {
+        match self.dc {
+            DataCommand::Pin(_) => 2,
+            DataCommand::NinthBit => 4,
+        }
+    }
+
+    /// # Encode
+    /// Lay `color` out in `bytes`, `pixel_bytes` long, for `blit`. The pixel is sent as
+    /// two characters, the high byte first, each little endian in the 9 bit mode.
+    ///
+    /// # Panics
+    /// When `bytes` is shorter than `pixel_bytes`.
+    pub fn encode(&self, color: Rgb565, bytes: &mut [u8]) {
+        let [high, low] = color.into_storage().to_be_bytes();
+        match self.dc {
+            DataCommand::Pin(_) => bytes[..2].copy_from_slice(&[high, low]),
+            DataCommand::NinthBit => bytes[..4].copy_from_slice(&[high, 1, low, 1]),
+        }
+    }
+
+    /// # Fill Pixels
+    /// Write the pixels of `area`, which must lie on the panel, left to right then top
+    /// to bottom from `colors`. Pixels past the end of `colors` are left alone.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `area` does not lie on the panel.
+    pub fn fill_pixels(
+        &mut self,
+        area: &Rectangle,
+        colors: impl IntoIterator<Item = Rgb565>,
+    ) -> Result<()> {
+        let pixels = Self::pixels(area)?;
+        if pixels == 0 {
+            return Ok(());
+        }
+
+        self.selected(|display| {
+            display.start_write(area)?;
+            let mut buffer = [0; STREAM_BYTES];
+            let mut length = 0;
+            for color in colors.into_iter().take(pixels) {
+                let [high, low] = color.into_storage().to_be_bytes();
+                buffer[length..length + 2].copy_from_slice(&[high, low]);
+                length += 2;
+                if length == STREAM_BYTES {
+                    display.send(true, &buffer)?;
+                    length = 0;
+                }
+            }
+            display.send(true, &buffer[..length])
+        })
+    }
+
+    /// # Blit
+    /// Write the pixels of `area`, which must lie on the panel, from `data` laid out by
+    /// `encode`, moved by the `tx` and `rx` DMA channels. Returns once it is sent.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` when `area` does not lie on the panel, or `data` is not
+    ///   `pixel_bytes` for each of its pixels.
+    /// - The errors of `DmaTransfer::wait`.
+    pub fn blit(
+        &mut self,
+        tx: &mut DmaChannel,
+        rx: &mut DmaChannel,
+        area: &Rectangle,
+        data: &[u8],
+    ) -> Result<()> {
+        let bytes = self.pixel_bytes();
+        if data.len() != Self::pixels(area)? * bytes {
+            return Err(ErrorKind::BadParam);
+        }
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        self.selected(|display| {
+            display.start_write(area)?;
+            if let DataCommand::Pin(dc) = &display.dc {
+                dc.set_output(true);
+            }
+            for chunk in data.chunks(BLIT_PIXELS * bytes) {
+                // Waited on right away, so never leaked
+                unsafe { display.spi.transfer_dma(tx, rx, &mut [], &[chunk])? }.wait()?;
+            }
+            Ok(())
+        })
+    }
+
+    /// # Free
+    /// Release the SPI port and the pins.
+    pub fn free(self) -> (SPI<Port>, GpioPin, GpioPin, DataCommand, Option<GpioPin>) {
+        (self.spi, self.cs, self.reset, self.dc, self.backlight)
+    }
+
+    /// The pixels in `area`, when it lies on the panel.
+    fn pixels(area: &Rectangle) -> Result<usize> {
+        let screen = Rectangle::new(Point::zero(), Size::new(WIDTH as u32, HEIGHT as u32));
+        if area.is_zero_sized() {
+            return Ok(0);
+        }
+        if screen.intersection(area) != *area {
+            return Err(ErrorKind::BadParam);
+        }
+        Ok(area.size.width as usize * area.size.height as usize)
+    }
+
+    /// Set the window to `area`, which is not empty and lies on the panel, and start
+    /// writing pixels into it.
+    fn start_write(&mut self, area: &Rectangle) -> Result<()> {
+        let start = area.top_left;
+        let end = area.bottom_right().unwrap_or(start);
+
+        self.send(false, &[COLUMN_ADDRESS])?;
+        self.send(true, &span(start.x, end.x))?;
+        self.send(false, &[PAGE_ADDRESS])?;
+        self.send(true, &span(start.y, end.y))?;
+        self.send(false, &[MEMORY_WRITE])
+    }
+
+    /// Run `f` with the display selected.
+    fn selected<R>(&mut self, f: impl FnOnce(&mut Self) -> Result<R>) -> Result<R> {
+        self.cs.set_output(false);
+        let result = f(self);
+        self.cs.set_output(true);
+        result
+    }
+
+    /// Send `bytes`, as data or commands.
+    fn send(&mut self, data: bool, bytes: &[u8]) -> Result<()> {
+        match &self.dc {
+            DataCommand::Pin(dc) => {
+                dc.set_output(data);
+                self.spi.write(bytes)
+            }
+            DataCommand::NinthBit => {
+                let flag = if data { 0x100 } else { 0 };
+                for chunk in bytes.chunks(STREAM_BYTES) {
+                    let mut characters = [0_u16; STREAM_BYTES];
+                    for (character, byte) in characters.iter_mut().zip(chunk) {
+                        *character = flag | *byte as u16;
+                    }
+                    self.spi.write(&characters[..chunk.len()])?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The 4 bytes of a address command, from the first address `start` to the last `end`.
+fn span(start: i32, end: i32) -> [u8; 4] {
+    let [start_high, start_low] = (start as u16).to_be_bytes();
+    let [end_high, end_low] = (end as u16).to_be_bytes();
+    [start_high, start_low, end_high, end_low]
+}
+
+#[cfg(feature = "board-evkit")]
+impl Display<crate::spi::SPI0> {
+    /// # EVKIT
+    /// Bring up SPI 0 at `frequency` (up to 10 MHz) for the MAX78000EVKIT's TFT, in
+    /// the 9 bit mode, as its D/C line is not routed. The pins of `bus` are released to
+    /// the SPI port, so the SD card can not be used while the display is.
+    ///
+    /// # Errors
+    /// The errors of `SPI::port_0_init`.
+    pub fn evkit(
+        bus: crate::board::evkit::SpiBus,
+        tft: crate::board::evkit::Tft,
+        frequency: crate::time::Hertz,
+    ) -> Result<Self> {
+        drop(bus);
+        let spi = SPI::port_0_init(crate::spi::SpiMode::Mode0, 9, frequency)?;
+        Self::new(
+            spi,
+            tft.cs,
+            tft.reset,
+            DataCommand::NinthBit,
+            Some(tft.backlight),
+        )
+    }
+}
+
+impl<Port: private::SPIPortCompatable> OriginDimensions for Display<Port> {
+    fn size(&self) -> Size {
+        Size::new(WIDTH as u32, HEIGHT as u32)
+    }
+}
+
+impl<Port: private::SPIPortCompatable> DrawTarget for Display<Port> {
+    type Color = Rgb565;
+    type Error = crate::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> core::result::Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let screen = self.bounding_box();
+        for Pixel(point, color) in pixels {
+            if screen.contains(point) {
+                self.fill_pixels(&Rectangle::new(point, Size::new(1, 1)), [color])
+                    .map_err(|kind| kind.within(Peripheral::Spi))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(
+        &mut self,
+        area: &Rectangle,
+        colors: I,
+    ) -> core::result::Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        if self.bounding_box().intersection(area) == *area {
+            return self
+                .fill_pixels(area, colors)
+                .map_err(|kind| kind.within(Peripheral::Spi));
+        }
+
+        // Partly off the panel, so drawn a pixel at a time
+        let width = area.size.width as i32;
+        let points = area
+            .rows()
+            .flat_map(move |y| (0..width).map(move |x| Point::new(area.top_left.x + x, y)));
+        self.draw_iter(points.zip(colors).map(|(point, color)| Pixel(point, color)))
+    }
+
+    fn fill_solid(
+        &mut self,
+        area: &Rectangle,
+        color: Self::Color,
+    ) -> core::result::Result<(), Self::Error> {
+        let area = self.bounding_box().intersection(area);
+        let pixels = area.size.width as usize * area.size.height as usize;
+        self.fill_pixels(&area, core::iter::repeat_n(color, pixels))
+            .map_err(|kind| kind.within(Peripheral::Spi))
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::gpio::GpioSelect;
+    use crate::memory_map::mmio;
+    use crate::spi::SPI0;
+    use core::mem::ManuallyDrop;
+
+    fn setup(dc: DataCommand) -> Display<SPI0> {
+        crate::mock::reset();
+        crate::mock::write(mmio::SPI_0 + 0x1C, 2 << 24);
+        crate::mock::write(mmio::SPI_0 + 0x20, 1 << 11);
+        let spi = ManuallyDrop::into_inner(unsafe { SPI::<SPI0>::steal() });
+        let cs = GpioPin::new(GpioSelect::Gpio1, 0).unwrap();
+        let reset = GpioPin::new(GpioSelect::Gpio1, 1).unwrap();
+        Display::new(spi, cs, reset, dc, None).unwrap()
+    }
+
+    #[test]
+    fn pixels_are_encoded_for_the_mode() {
+        let color = Rgb565::new(0x1F, 0, 0x01);
+        let dc = GpioPin::new(GpioSelect::Gpio1, 2).unwrap();
+        let display = setup(DataCommand::Pin(dc));
+        let mut bytes = [0; 4];
+        display.encode(color, &mut bytes);
+        assert_eq!(display.pixel_bytes(), 2);
+        assert_eq!(bytes[..2], [0xF8, 0x01]);
+        drop(display);
+
+        let display = setup(DataCommand::NinthBit);
+        display.encode(color, &mut bytes);
+        assert_eq!(display.pixel_bytes(), 4);
+        assert_eq!(bytes, [0xF8, 0x01, 0x01, 0x01]);
+        assert_eq!(span(10, 319), [0x00, 0x0A, 0x01, 0x3F]);
+    }
+
+    #[test]
+    fn drawing_streams_over_the_bus() {
+        let ctrl1 = mmio::SPI_0 + 0x08;
+        let dc = GpioPin::new(GpioSelect::Gpio1, 2).unwrap();
+        let mut display = setup(DataCommand::Pin(dc));
+
+        // Clipped to the two pixels on the panel, sent as one run of data
+        let area = Rectangle::new(Point::new(318, 239), Size::new(4, 4));
+        display.fill_solid(&area, Rgb565::new(0, 0x3F, 0)).unwrap();
+        assert_eq!(crate::mock::read(ctrl1) & 0xFFFF, 4);
+        assert_eq!(crate::mock::read(mmio::SPI_0) & 0xFF, 0xE0);
+
+        assert_eq!(
+            display.fill_pixels(&area, [Rgb565::new(0, 0, 0)]),
+            Err(ErrorKind::BadParam)
+        );
+        let (_, cs, _, dc, _) = display.free();
+        assert!(cs.get_output());
+        assert!(matches!(&dc, DataCommand::Pin(pin) if pin.get_output()));
+        drop((cs, dc));
+
+        // The ninth bit marks the data
+        let mut display = setup(DataCommand::NinthBit);
+        display.command(MEMORY_ACCESS, &[LANDSCAPE]).unwrap();
+        assert_eq!(crate::mock::read(ctrl1) & 0xFFFF, 1);
+        assert_eq!(crate::mock::read(mmio::SPI_0) & 0x1FF, 0x128);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::memory_map::mmio;
+use hal_macros::RW;
+use hal_macros_derive::make_device;
+
+/// # Channel 0
+/// Every channel has a block of registers, `0x20` bytes apart.
+pub const CHANNEL_0: usize = mmio::STANDARD_DMA + 0x100;
+/// # Channel 1
+pub const CHANNEL_1: usize = CHANNEL_0 + 0x20;
+/// # Channel 2
+pub const CHANNEL_2: usize = CHANNEL_0 + 0x40;
+/// # Channel 3
+pub const CHANNEL_3: usize = CHANNEL_0 + 0x60;
+
+/// # DMA Channel Register Offsets
+/// See Max 78000 User Guide, Standard DMA Channel Registers.
+pub(super) mod rro {
+    /// # DMA Channel Control Register
+    pub const DMA_CTRL: usize = 0x0000;
+    /// # DMA Channel Status Register
+    pub const DMA_STATUS: usize = 0x0004;
+    /// # DMA Channel Source Register
+    pub const DMA_SRC: usize = 0x0008;
+    /// # DMA Channel Destination Register
+    pub const DMA_DST: usize = 0x000C;
+    /// # DMA Channel Count Register
+    pub const DMA_CNT: usize = 0x0010;
+    /// # DMA Channel Source Reload Register
+    pub const DMA_SRCRLD: usize = 0x0014;
+    /// # DMA Channel Destination Reload Register
+    pub const DMA_DSTRLD: usize = 0x0018;
+    /// # DMA Channel Count Reload Register
+    pub const DMA_CNTRLD: usize = 0x001C;
+}
+
+make_device! {
+    device_ports(CHANNEL_0, CHANNEL_1, CHANNEL_2, CHANNEL_3);
+
+    /// Count to Zero Interrupt Enable.
+    /// Interrupt when the count reaches zero.
+    #[bit(31, RW, rro::DMA_CTRL)]
+    count_to_zero_interrupt_enable,
+
+    /// Channel Disable Interrupt Enable.
+    /// Interrupt when the channel is disabled, ie. by a bus error.
+    #[bit(30, RW, rro::DMA_CTRL)]
+    disable_interrupt_enable,
+
+    /// Burst Size.
+    /// The bytes moved for every request, minus one.
+    #[bit(24..=28, RW, rro::DMA_CTRL)]
+    burst_size,
+
+    /// Destination Increment.
+    /// Step the destination address after every write.
+    #[bit(22, RW, rro::DMA_CTRL)]
+    destination_increment,
+
+    /// Destination Width.
+    /// - 0: Byte
+    /// - 1: Half word
+    /// - 2: Word
+    #[bit(20..=21, RW, rro::DMA_CTRL)]
+    destination_width,
+
+    /// Source Increment.
+    /// Step the source address after every read.
+    #[bit(18, RW, rro::DMA_CTRL)]
+    source_increment,
+
+    /// Source Width.
+    /// - 0: Byte
+    /// - 1: Half word
+    /// - 2: Word
+    #[bit(16..=17, RW, rro::DMA_CTRL)]
+    source_width,
+
+    /// Request Select.
+    /// The peripheral request line pacing the channel, see `Request`.
+    #[bit(4..=9, RW, rro::DMA_CTRL)]
+    request,
+
+    /// Priority.
+    /// - 0: Highest
+    /// - 3: Lowest
+    #[bit(2..=3, RW, rro::DMA_CTRL)]
+    priority,
+
+    /// Reload Enable.
+    /// Load the reload registers once the count reaches zero, and carry on.
+    #[bit(1, RW, rro::DMA_CTRL)]
+    reload_enable,
+
+    /// Channel Enable.
+    /// - 0: Disabled
+    /// - 1: Enabled
+    #[bit(0, RW, rro::DMA_CTRL)]
+    enable,
+
+    /// Timeout Flag.
+    #[bit(6, RW1C, rro::DMA_STATUS)]
+    timeout_flag,
+
+    /// Bus Error Flag.
+    /// A address could not be accessed, the channel was disabled.
+    #[bit(4, RW1C, rro::DMA_STATUS)]
+    bus_error_flag,
+
+    /// Reload Flag.
+    /// The reload registers were loaded.
+    #[bit(3, RW1C, rro::DMA_STATUS)]
+    reload_flag,
+
+    /// Count to Zero Flag.
+    /// The count reached zero.
+    #[bit(2, RW1C, rro::DMA_STATUS)]
+    count_to_zero_flag,
+
+    /// Interrupt Pending.
+    #[bit(1, RO, rro::DMA_STATUS)]
+    interrupt_pending,
+
+    /// Channel Status.
+    /// - 0: Idle
+    /// - 1: Running
+    #[bit(0, RO, rro::DMA_STATUS)]
+    channel_status,
+
+    /// Source Address.
+    #[bit(0..=31, RW, rro::DMA_SRC)]
+    source,
+
+    /// Destination Address.
+    #[bit(0..=31, RW, rro::DMA_DST)]
+    destination,
+
+    /// Count.
+    /// The bytes left to move.
+    #[bit(0..=23, RW, rro::DMA_CNT)]
+    count,
+
+    /// Source Reload Address.
+    #[bit(0..=30, RW, rro::DMA_SRCRLD)]
+    source_reload,
+
+    /// Destination Reload Address.
+    #[bit(0..=30, RW, rro::DMA_DSTRLD)]
+    destination_reload,
+
+    /// Count Reload Enable.
+    /// Set once the reload registers are written, cleared by the hardware when it loads
+    /// them.
+    #[bit(31, RW, rro::DMA_CNTRLD)]
+    count_reload_enable,
+
+    /// Count Reload.
+    #[bit(0..=23, RW, rro::DMA_CNTRLD)]
+    count_reload,
+}
@@ -0,0 +1,362 @@
+//! # DMA
+//! The standard DMA controller's four channels, moving bytes between memory and the
+//! peripherals' FIFOs without the CPU. A channel is paced by a peripheral's request
+//! line (see `Request`), and can carry on with a second block loaded into its reload
+//! registers without stopping, which drivers use to chain buffers.
+//!
+//! Drivers take the channels they need as `DmaChannel`s, ie. `SPI::transfer_dma`.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::dma::DmaChannel;
+//! use max78000_hal::spi::{SpiMode, SPI};
+//! use max78000_hal::time::Hertz;
+//! # let mut spi = SPI::port_0_init(SpiMode::Mode0, 8, Hertz::MHz(4))?;
+//! # let mut buffer = [0_u8; 256];
+//! # let command = [0x03_u8, 0, 0, 0];
+//!
+//! let mut tx = DmaChannel::new(0)?;
+//! let mut rx = DmaChannel::new(1)?;
+//! unsafe { spi.transfer_dma(&mut tx, &mut rx, &mut [&mut buffer], &[&command])? }.wait()?;
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use crate::error::{ErrorKind, Result};
+use crate::gcr::{system_clock_enable, HardwareSource};
+use crate::interrupt::Interrupt;
+
+pub mod channel;
+pub mod registers;
+
+/// # Channels
+/// The number of DMA channels.
+pub const CHANNELS: usize = 4;
+
+/// The bitmap of owned channels, changed atomically so two owners can't both see a
+/// channel free and take it.
+#[cfg(not(test))]
+static CHANNELS_OWNED: AtomicU8 = AtomicU8::new(0);
+
+// Like the pins, every test thread owns its own channels
+#[cfg(test)]
+extern crate std;
+
+#[cfg(test)]
+std::thread_local! {
+    static CHANNELS_OWNED: AtomicU8 = const { AtomicU8::new(0) };
+}
+
+/// Run `f` on the bitmap of owned channels.
+#[cfg(not(test))]
+fn with_owned<R>(f: impl FnOnce(&AtomicU8) -> R) -> R {
+    f(&CHANNELS_OWNED)
+}
+
+/// Run `f` on the bitmap of owned channels.
+#[cfg(test)]
+fn with_owned<R>(f: impl FnOnce(&AtomicU8) -> R) -> R {
+    CHANNELS_OWNED.with(f)
+}
+
+/// # Request
+/// The request lines pacing a channel, see Max 78000 User Guide, DMA Request Select.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum Request {
+    /// Not paced, for memory to memory copies.
+    Memory = 0x00,
+    Spi1Receive = 0x01,
+    Uart0Receive = 0x04,
+    Uart1Receive = 0x05,
+    I2c0Receive = 0x07,
+    I2c1Receive = 0x08,
+    I2c2Receive = 0x0A,
+    Uart2Receive = 0x0E,
+    Spi0Receive = 0x0F,
+    Uart3Receive = 0x1C,
+    Spi1Transmit = 0x21,
+    Uart0Transmit = 0x24,
+    Uart1Transmit = 0x25,
+    I2c0Transmit = 0x27,
+    I2c1Transmit = 0x28,
+    I2c2Transmit = 0x2A,
+    Uart2Transmit = 0x2E,
+    Spi0Transmit = 0x2F,
+    Uart3Transmit = 0x3C,
+}
+
+/// # Block
+/// A block of bytes for a channel to move. Addresses not incremented stay on a
+/// peripheral's FIFO.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Block {
+    pub source: usize,
+    pub source_increment: bool,
+    pub destination: usize,
+    pub destination_increment: bool,
+    /// The bytes to move, up to `MAX_COUNT`.
+    pub count: usize,
+}
+
+/// # Max Count
+/// The most bytes a single block can move.
+pub const MAX_COUNT: usize = 0xFF_FFFF;
+
+/// # Acknowledge
+/// Clear the flags of channel `index`, and with them its interrupt, from a interrupt
+/// handler that can not reach the `DmaChannel`. Does nothing for a channel that does not
+/// exist.
+pub fn acknowledge(index: usize) {
+    if index < CHANNELS {
+        let mut reg = channel::Registers::new(channel::CHANNEL_0 + index * 0x20);
+        unsafe {
+            reg.clear_count_to_zero_flag();
+            reg.clear_reload_flag();
+            reg.clear_bus_error_flag();
+            reg.clear_timeout_flag();
+        }
+    }
+}
+
+/// # DMA Channel
+/// A owned DMA channel, stopped and released when dropped.
+pub struct DmaChannel {
+    reg: channel::Registers,
+    index: usize,
+}
+
+impl DmaChannel {
+    /// # New
+    /// Take channel `index`, clocking the DMA controller.
+    ///
+    /// # Errors
+    /// - `ErrorKind::NoDevice` when there is no channel `index`.
+    /// - `ErrorKind::Busy` when the channel is owned.
+    pub fn new(index: usize) -> Result<Self> {
+        if index >= CHANNELS {
+            return Err(ErrorKind::NoDevice);
+        }
+
+        with_owned(|owned| {
+            owned.fetch_update(Ordering::Acquire, Ordering::Relaxed, |owned| {
+                (owned & (1 << index) == 0).then_some(owned | (1 << index))
+            })
+        })
+        .map_err(|_| ErrorKind::Busy)?;
+
+        system_clock_enable(HardwareSource::DMA, true);
+        Ok(Self {
+            reg: channel::Registers::new(channel::CHANNEL_0 + index * 0x20),
+            index,
+        })
+    }
+
+    /// # Index
+    /// The channel's number.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// # Interrupt
+    /// The channel's interrupt, for binding a handler.
+    pub fn interrupt(&self) -> Interrupt {
+        match self.index {
+            0 => Interrupt::DMA0,
+            1 => Interrupt::DMA1,
+            2 => Interrupt::DMA2,
+            _ => Interrupt::DMA3,
+        }
+    }
+
+    /// # Start
+    /// Move `block` a byte at a time, `burst` bytes (1 to 32) for every request of
+    /// `request`, stopping the channel first. The reload is cleared.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when the count or burst is out of range.
+    ///
+    /// # Safety
+    /// The addresses of `block` must stay valid for the channel to access until it
+    /// stops, see `is_busy`.
+    pub unsafe fn start(&mut self, request: Request, block: Block, burst: usize) -> Result<()> {
+        if !(1..=MAX_COUNT).contains(&block.count) || !(1..=32).contains(&burst) {
+            return Err(ErrorKind::BadParam);
+        }
+
+        self.stop();
+        self.clear_flags();
+        self.reg.set_request(request as u8);
+        self.reg.set_source_width(0);
+        self.reg.set_destination_width(0);
+        self.reg.set_source_increment(block.source_increment);
+        self.reg
+            .set_destination_increment(block.destination_increment);
+        self.reg.set_burst_size((burst - 1) as u8);
+        self.reg.set_source(block.source as u32);
+        self.reg.set_destination(block.destination as u32);
+        self.reg.set_count(block.count as u32);
+        self.reg.set_count_reload_enable(false);
+        self.reg.set_reload_enable(true);
+        self.reg.set_enable(true);
+        Ok(())
+    }
+
+    /// # Reload
+    /// Carry on with `block` once the current one is done, keeping the request, burst
+    /// and address increments of `start`. Only one block waits at a time, see
+    /// `is_reload_pending`.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` when the count is out of range.
+    /// - `ErrorKind::Busy` when a block is waiting already.
+    ///
+    /// # Safety
+    /// See `start`.
+    pub unsafe fn reload(&mut self, block: Block) -> Result<()> {
+        if !(1..=MAX_COUNT).contains(&block.count) {
+            return Err(ErrorKind::BadParam);
+        }
+        if self.is_reload_pending() {
+            return Err(ErrorKind::Busy);
+        }
+
+        // The whole address goes to the reload registers, the hardware ignores their
+        // reserved bit 31, which no memory or peripheral address has set anyway
+        self.reg
+            .write_raw(channel::rro::DMA_SRCRLD, block.source as u32);
+        self.reg
+            .write_raw(channel::rro::DMA_DSTRLD, block.destination as u32);
+        self.reg.set_count_reload(block.count as u32);
+        self.reg.set_count_reload_enable(true);
+        Ok(())
+    }
+
+    /// # Is Reload Pending
+    /// Check if a block waits in the reload registers.
+    pub fn is_reload_pending(&self) -> bool {
+        self.reg.get_count_reload_enable()
+    }
+
+    /// # Is Busy
+    /// Check if the channel is moving bytes, or waiting for requests to.
+    pub fn is_busy(&self) -> bool {
+        self.reg.get_channel_status()
+    }
+
+    /// # Remaining
+    /// The bytes the current block has left to move.
+    pub fn remaining(&self) -> usize {
+        self.reg.get_count() as usize
+    }
+
+    /// # Is Complete
+    /// Check if a block was completed since the flags were cleared.
+    pub fn is_complete(&self) -> bool {
+        self.reg.is_count_to_zero_flag_active()
+    }
+
+    /// # Has Bus Error
+    /// Check if the channel stopped on a address it could not access.
+    pub fn has_bus_error(&self) -> bool {
+        self.reg.is_bus_error_flag_active()
+    }
+
+    /// # Clear Flags
+    /// Clear the channel's flags, and with them its interrupt.
+    pub fn clear_flags(&mut self) {
+        acknowledge(self.index);
+    }
+
+    /// # Set Interrupt
+    /// Interrupt on every completed block (`true`), or a bus error. The interrupt must be
+    /// bound to a handler clearing the flags (see `acknowledge`), and enabled in the
+    /// NVIC.
+    pub fn set_interrupt(&mut self, enable: bool) {
+        let mut shared = registers::Registers::new(crate::memory_map::mmio::STANDARD_DMA);
+        let mask = shared.get_channel_interrupt_enable() & !(1 << self.index);
+
+        unsafe {
+            self.reg.set_count_to_zero_interrupt_enable(enable);
+            self.reg.set_disable_interrupt_enable(enable);
+            shared.set_channel_interrupt_enable(mask | (enable as u8) << self.index);
+        }
+    }
+
+    /// # Stop
+    /// Disable the channel, dropping the rest of its block and the reload, and wait
+    /// until it is idle.
+    pub fn stop(&mut self) {
+        unsafe {
+            self.reg.set_enable(false);
+            self.reg.set_count_reload_enable(false);
+        }
+        while self.reg.get_channel_status() {}
+    }
+}
+
+impl Drop for DmaChannel {
+    fn drop(&mut self) {
+        self.set_interrupt(false);
+        self.stop();
+        with_owned(|owned| owned.fetch_and(!(1 << self.index), Ordering::Release));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn channels_are_owned() {
+        assert_eq!(DmaChannel::new(CHANNELS).err(), Some(ErrorKind::NoDevice));
+
+        #[cfg(feature = "mock-mmio")]
+        {
+            crate::mock::reset();
+            let channel = DmaChannel::new(2).unwrap();
+            assert!(channel.interrupt() == Interrupt::DMA2);
+            assert_eq!(DmaChannel::new(2).err(), Some(ErrorKind::Busy));
+            drop(channel);
+            assert!(DmaChannel::new(2).is_ok());
+        }
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn blocks_chain_through_the_reload() {
+        crate::mock::reset();
+        let base = channel::CHANNEL_1;
+        let mut channel = DmaChannel::new(1).unwrap();
+        let block = Block {
+            source: 0x2000_0000,
+            source_increment: true,
+            destination: 0x2000_1000,
+            destination_increment: true,
+            count: 16,
+        };
+
+        unsafe {
+            assert_eq!(
+                channel.start(Request::Memory, Block { count: 0, ..block }, 4),
+                Err(ErrorKind::BadParam)
+            );
+            channel.start(Request::Spi0Transmit, block, 4).unwrap();
+            assert_eq!(crate::mock::read(base) >> 4 & 0x3F, 0x2F);
+            assert_eq!(crate::mock::read(base + 0x10), 16);
+
+            channel.reload(Block { count: 8, ..block }).unwrap();
+            assert!(channel.is_reload_pending());
+            assert_eq!(channel.reload(block), Err(ErrorKind::Busy));
+            assert_eq!(crate::mock::read(base + 0x1C), (1 << 31) | 8);
+        }
+
+        channel.set_interrupt(true);
+        assert_eq!(
+            crate::mock::read(crate::memory_map::mmio::STANDARD_DMA),
+            0b10
+        );
+        channel.stop();
+        assert!(!channel.is_reload_pending());
+    }
+}
@@ -0,0 +1,27 @@
+use crate::memory_map::mmio;
+use hal_macros::RW;
+use hal_macros_derive::make_device;
+
+/// # DMA Register Offsets
+/// The offsets of the registers shared by all DMA channels, see Max 78000 User Guide,
+/// Standard DMA Registers.
+mod rro {
+    /// # DMA Interrupt Enable Register
+    pub const DMA_INTEN: usize = 0x0000;
+    /// # DMA Interrupt Flag Register
+    pub const DMA_INTFL: usize = 0x0004;
+}
+
+make_device! {
+    device_ports(mmio::STANDARD_DMA);
+
+    /// Channel Interrupt Enable.
+    /// One bit per channel, letting its interrupt reach the NVIC.
+    #[bit(0..=3, RW, rro::DMA_INTEN)]
+    channel_interrupt_enable,
+
+    /// Channel Interrupt Flags.
+    /// One bit per channel, set while the channel has a interrupt pending.
+    #[bit(0..=3, RO, rro::DMA_INTFL)]
+    channel_interrupt_flags,
+}
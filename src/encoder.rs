@@ -0,0 +1,451 @@
+//! # Encoder
+//! Position and velocity from rotary encoders, ie. knobs and motor shafts.
+//!
+//! - `CounterEncoder` counts the pulses of a single channel encoder on a timer input, in
+//!   hardware, with the timer in counter mode. The timers have no direction input, so
+//!   the direction is set by software (ie. from the motor's drive direction).
+//! - `QuadratureEncoder` decodes the A and B channels of a quadrature encoder from the
+//!   GPIO interrupts of any two pins, counting every edge of both channels.
+//! - `TimerQuadratureEncoder` timestamps the rising edges of A and B with two timers in
+//!   capture mode, on the timer input pins. It counts once per period of A, with the
+//!   direction from which channel rose last, and only interrupts once per period.
+//!
+//! # Example
+//! ```no_run
+//! use core::cell::RefCell;
+//! use critical_section::Mutex;
+//! use max78000_hal::bind_interrupts;
+//! use max78000_hal::encoder::QuadratureEncoder;
+//! use max78000_hal::interrupt::typelevel::{Handler, GPIO0};
+//! use max78000_hal::time::Micros;
+//! # use max78000_hal::gpio::{GpioPin, GpioSelect};
+//! # let [a, b] = [10, 11].map(|pin| GpioPin::new(GpioSelect::Gpio0, pin).unwrap());
+//!
+//! static KNOB: Mutex<RefCell<Option<QuadratureEncoder>>> = Mutex::new(RefCell::new(None));
+//!
+//! struct Edge;
+//! impl Handler<GPIO0> for Edge {
+//!     unsafe fn on_interrupt() {
+//!         critical_section::with(|cs| {
+//!             if let Some(knob) = KNOB.borrow_ref_mut(cs).as_mut() {
+//!                 knob.on_interrupt();
+//!             }
+//!         });
+//!     }
+//! }
+//!
+//! bind_interrupts!(struct Irqs { GPIO0 => Edge; });
+//!
+//! let knob = QuadratureEncoder::new(a, b);
+//! critical_section::with(|cs| KNOB.replace(cs, Some(knob)));
+//! // ...
+//! let speed = critical_section::with(|cs| {
+//!     KNOB.borrow_ref_mut(cs)
+//!         .as_mut()
+//!         .map(|knob| knob.velocity(Micros::millis(100)))
+//! });
+//! ```
+use crate::error::Result;
+use crate::gcr::{peripheral_reset, system_clock_enable};
+use crate::gpio::wait::Trigger;
+use crate::gpio::GpioPin;
+use crate::time::Micros;
+use crate::timer::capture::{Edge, InputCapture};
+use crate::timer::registers::{TimerMode, TimerPrescaler};
+use crate::timer::{hardware_source, mode, Timer, TimerInstance};
+
+/// The step between two `(A << 1) | B` states, indexed by `(old << 2) | new`. Invalid
+/// transitions (both channels changed) count as no step.
+const QUADRATURE_STEPS: [i8; 16] = [0, -1, 1, 0, 1, 0, 0, -1, -1, 0, 0, 1, 0, 1, -1, 0];
+
+/// # Quadrature
+/// The state machine of a quadrature decoder.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quadrature {
+    state: u8,
+}
+
+impl Quadrature {
+    /// # New
+    /// Start from the current level of the A and B channels.
+    pub const fn new(a: bool, b: bool) -> Self {
+        Self {
+            state: ((a as u8) << 1) | b as u8,
+        }
+    }
+
+    /// # Update
+    /// Feed the new levels of the channels, returning the step taken: `1` when A leads
+    /// B, `-1` when B leads A, or `0`.
+    pub fn update(&mut self, a: bool, b: bool) -> i8 {
+        let state = ((a as u8) << 1) | b as u8;
+        let step = QUADRATURE_STEPS[((self.state << 2) | state) as usize];
+        self.state = state;
+        step
+    }
+}
+
+/// # Velocity
+/// The change in a position since the last sample.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Velocity {
+    last: i32,
+}
+
+impl Velocity {
+    /// Counts per second since the last sample, `elapsed` ago.
+    fn sample(&mut self, position: i32, elapsed: Micros) -> i32 {
+        let delta = position.wrapping_sub(self.last) as i64;
+        self.last = position;
+
+        match elapsed.ticks() {
+            0 => 0,
+            micros => (delta * 1_000_000 / micros as i64) as i32,
+        }
+    }
+}
+
+/// # Counter Encoder
+/// A single channel encoder on the input pin of the timer at `PORT`, counted on every
+/// rising edge.
+pub struct CounterEncoder<const PORT: usize> {
+    timer: TimerInstance<PORT>,
+    count: u32,
+    position: i32,
+    reverse: bool,
+    velocity: Velocity,
+}
+
+impl<const PORT: usize> CounterEncoder<PORT> {
+    /// # New
+    /// Set up the timer to count input edges, and start it. The input pin must already
+    /// be routed to the timer. The encoder starts at position `0`.
+    ///
+    /// # Errors
+    /// `ErrorKind::NoDevice` when `PORT` is a low power timer.
+    pub fn new(timer: TimerInstance<PORT>) -> Result<Self> {
+        let source = hardware_source(PORT)?;
+
+        peripheral_reset(source);
+        system_clock_enable(source, true);
+
+        let mut encoder = Self {
+            timer,
+            count: 0,
+            position: 0,
+            reverse: false,
+            velocity: Velocity::default(),
+        };
+
+        unsafe {
+            encoder.timer.set_timera_enable(false);
+            encoder.timer.set_timera_clock_source(0);
+            encoder
+                .timer
+                .set_timera_prescaler_select(TimerPrescaler::Div1);
+            encoder.timer.set_timera_mode_select(TimerMode::Counter);
+            encoder.timer.set_timera_polarity(false);
+            encoder.timer.set_timer_count(0);
+            encoder.timer.set_timer_compare_value(u32::MAX);
+            encoder.timer.set_timera_clock_enable(true);
+        }
+        while !encoder.timer.get_timera_clock_ready() {}
+        unsafe { encoder.timer.set_timera_enable(true) };
+
+        Ok(encoder)
+    }
+
+    /// # Set Reverse
+    /// Count the pulses from now on down (`true`) or up (`false`).
+    pub fn set_reverse(&mut self, reverse: bool) {
+        self.position();
+        self.reverse = reverse;
+    }
+
+    /// # Position
+    /// The number of pulses counted, read from the timer.
+    pub fn position(&mut self) -> i32 {
+        let count = self.timer.get_timer_count();
+        let delta = count.wrapping_sub(self.count) as i32;
+        self.count = count;
+
+        self.position = match self.reverse {
+            false => self.position.wrapping_add(delta),
+            true => self.position.wrapping_sub(delta),
+        };
+        self.position
+    }
+
+    /// # Set Position
+    /// Redefine the current position, ie. after homing.
+    pub fn set_position(&mut self, position: i32) {
+        self.position();
+        self.position = position;
+        self.velocity.last = position;
+    }
+
+    /// # Velocity
+    /// The pulses per second since the last call, made `elapsed` ago.
+    pub fn velocity(&mut self, elapsed: Micros) -> i32 {
+        let position = self.position();
+        self.velocity.sample(position, elapsed)
+    }
+
+    /// # Free
+    /// Stop the timer, and give it back.
+    pub fn free(mut self) -> TimerInstance<PORT> {
+        unsafe { self.timer.set_timera_enable(false) };
+        self.timer
+    }
+}
+
+/// # Quadrature Encoder
+/// A quadrature encoder on two GPIO input pins, decoded in software from the pins'
+/// interrupts.
+pub struct QuadratureEncoder {
+    a: GpioPin,
+    b: GpioPin,
+    decoder: Quadrature,
+    position: i32,
+    velocity: Velocity,
+}
+
+impl QuadratureEncoder {
+    /// # New
+    /// Enable the interrupts of both pins on any edge. The pins must already be
+    /// configured as inputs, and the interrupt of their port(s) bound to a handler
+    /// calling `on_interrupt`. The encoder starts at position `0`.
+    pub fn new(a: GpioPin, b: GpioPin) -> Self {
+        let decoder = Quadrature::new(a.get_input(), b.get_input());
+
+        a.listen(Trigger::AnyEdge);
+        b.listen(Trigger::AnyEdge);
+
+        Self {
+            a,
+            b,
+            decoder,
+            position: 0,
+            velocity: Velocity::default(),
+        }
+    }
+
+    /// # On Interrupt
+    /// Clear the interrupts of the pins, and decode their new levels. Call this from
+    /// the GPIO interrupt handler, it is harmless when another pin of the port
+    /// interrupted.
+    pub fn on_interrupt(&mut self) {
+        let fired = self.a.take_interrupt() | self.b.take_interrupt();
+        if fired {
+            self.poll();
+        }
+    }
+
+    /// # Poll
+    /// Decode the current levels of the pins, without interrupts.
+    pub fn poll(&mut self) {
+        let step = self.decoder.update(self.a.get_input(), self.b.get_input());
+        self.position = self.position.wrapping_add(step as i32);
+    }
+
+    /// # Position
+    /// The number of edges counted, positive when A leads B.
+    pub fn position(&self) -> i32 {
+        self.position
+    }
+
+    /// # Set Position
+    /// Redefine the current position, ie. after homing.
+    pub fn set_position(&mut self, position: i32) {
+        self.position = position;
+        self.velocity.last = position;
+    }
+
+    /// # Velocity
+    /// The edges per second since the last call, made `elapsed` ago.
+    pub fn velocity(&mut self, elapsed: Micros) -> i32 {
+        self.velocity.sample(self.position, elapsed)
+    }
+
+    /// # Free
+    /// Disable the pin interrupts, and give back the pins.
+    pub fn free(self) -> (GpioPin, GpioPin) {
+        self.a.unlisten();
+        self.b.unlisten();
+        (self.a, self.b)
+    }
+}
+
+/// # Timer Quadrature Encoder
+/// A quadrature encoder with its A channel on the input pin of the timer at `A`, and
+/// its B channel on the input pin of the timer at `B`.
+pub struct TimerQuadratureEncoder<const A: usize, const B: usize> {
+    a: InputCapture<A>,
+    b: InputCapture<B>,
+    last_a: Option<u32>,
+    last_b: Option<u32>,
+    position: i32,
+    velocity: Velocity,
+}
+
+impl<const A: usize, const B: usize> TimerQuadratureEncoder<A, B> {
+    /// # New
+    /// Start both timers capturing rising edges, with the same prescaler so their counts
+    /// line up. The input pins must already be routed to the timers (their alternate
+    /// function), and the interrupt of timer `A` bound to a handler calling
+    /// `on_interrupt`. The encoder starts at position `0`.
+    pub fn new(a: Timer<A, mode::Disabled>, b: Timer<B, mode::Disabled>) -> Self {
+        let mut a = a.into_capture(TimerPrescaler::Div1, Edge::Rising);
+        let mut b = b.into_capture(TimerPrescaler::Div1, Edge::Rising);
+
+        a.enable_interrupt(true);
+        b.start();
+        a.start();
+
+        Self {
+            a,
+            b,
+            last_a: None,
+            last_b: None,
+            position: 0,
+            velocity: Velocity::default(),
+        }
+    }
+
+    /// # On Interrupt
+    /// Count a rising edge of A, if there was one. Call this from the interrupt handler
+    /// of timer `A`.
+    pub fn on_interrupt(&mut self) {
+        let now = match self.a.try_capture() {
+            Some(now) => now,
+            None => return,
+        };
+        if let Some(b) = self.b.try_capture() {
+            self.last_b = Some(b);
+        }
+
+        if let (Some(previous), Some(b)) = (self.last_a, self.last_b) {
+            let step = phase_step(now, previous, b);
+            self.position = self.position.wrapping_add(step as i32);
+        }
+        self.last_a = Some(now);
+    }
+
+    /// # Position
+    /// The number of periods of A counted, positive when A leads B.
+    pub fn position(&self) -> i32 {
+        self.position
+    }
+
+    /// # Set Position
+    /// Redefine the current position, ie. after homing.
+    pub fn set_position(&mut self, position: i32) {
+        self.position = position;
+        self.velocity.last = position;
+    }
+
+    /// # Velocity
+    /// The periods per second since the last call, made `elapsed` ago.
+    pub fn velocity(&mut self, elapsed: Micros) -> i32 {
+        self.velocity.sample(self.position, elapsed)
+    }
+
+    /// # Free
+    /// Stop both timers, and give them back disabled.
+    pub fn free(self) -> (Timer<A, mode::Disabled>, Timer<B, mode::Disabled>) {
+        (self.a.free(), self.b.free())
+    }
+}
+
+/// # Phase Step
+/// The step at a rising edge of A at `now`, after the last one at `previous` and the
+/// last rising edge of B at `b`. When A leads, B rose three quarters of a period
+/// before A (or just after it), when B leads it rose a quarter period before.
+fn phase_step(now: u32, previous: u32, b: u32) -> i8 {
+    let period = now.wrapping_sub(previous);
+    match now.wrapping_sub(b) < period / 2 {
+        true => -1,
+        false => 1,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn quadrature_decodes_both_directions() {
+        // A leads B: 00 -> 10 -> 11 -> 01 -> 00
+        let forward = [(true, false), (true, true), (false, true), (false, false)];
+        let mut decoder = Quadrature::new(false, false);
+
+        let total: i32 = forward
+            .iter()
+            .map(|(a, b)| decoder.update(*a, *b) as i32)
+            .sum();
+        assert_eq!(total, 4);
+
+        let total: i32 = forward
+            .iter()
+            .rev()
+            .skip(1)
+            .chain([(false, false)].iter())
+            .map(|(a, b)| decoder.update(*a, *b) as i32)
+            .sum();
+        assert_eq!(total, -4);
+
+        // No change, and a skipped state, are not steps
+        assert_eq!(decoder.update(false, false), 0);
+        assert_eq!(decoder.update(true, true), 0);
+    }
+
+    #[test]
+    fn phase_gives_direction() {
+        // A leads: B rose 3/4 of a period before A
+        assert_eq!(phase_step(1_000, 0, 250), 1);
+        // B leads: B rose 1/4 of a period before A
+        assert_eq!(phase_step(1_000, 0, 750), -1);
+        // A leads, and B already rose again before the interrupt ran
+        assert_eq!(phase_step(1_000, 0, 1_250), 1);
+        // Across the wrap of the timer count
+        assert_eq!(phase_step(100, u32::MAX - 899, u32::MAX - 149), -1);
+    }
+
+    #[test]
+    fn velocity_per_second() {
+        let mut velocity = Velocity::default();
+        assert_eq!(velocity.sample(100, Micros::millis(100)), 1_000);
+        assert_eq!(velocity.sample(50, Micros::millis(500)), -100);
+        assert_eq!(velocity.sample(60, Micros::micros(0)), 0);
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn counter_tracks_direction() {
+        use crate::memory_map::mmio;
+
+        crate::mock::reset();
+
+        let mut encoder = CounterEncoder {
+            timer: TimerInstance::<{ mmio::TIMER_0 }>::instance(),
+            count: 0,
+            position: 0,
+            reverse: false,
+            velocity: Velocity::default(),
+        };
+
+        unsafe { encoder.timer.set_timer_count(10) };
+        assert_eq!(encoder.position(), 10);
+
+        encoder.set_reverse(true);
+        unsafe { encoder.timer.set_timer_count(14) };
+        assert_eq!(encoder.position(), 6);
+
+        // Wrapping of the timer count is not a jump
+        encoder.set_reverse(false);
+        unsafe { encoder.timer.set_timer_count(u32::MAX - 1) };
+        encoder.count = u32::MAX - 1;
+        unsafe { encoder.timer.set_timer_count(2) };
+        assert_eq!(encoder.position(), 10);
+    }
+}
@@ -2,7 +2,8 @@
 /// Errors for states internal to the MAX78000 microcontroller.
 #[non_exhaustive]
 #[repr(u8)]
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ErrorKind {
     /// # Null Ptr
     /// Some critical ptr was found to be null, and could not continue.
@@ -102,3 +103,211 @@ impl core::fmt::Debug for ErrorKind {
 /// # Result
 /// Result type that includes the `ErrorKind` enum as error.
 pub type Result<T> = core::result::Result<T, ErrorKind>;
+
+/// # Peripheral
+/// The peripheral a `Error` came from.
+#[non_exhaustive]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Peripheral {
+    Aes,
+    Gcr,
+    Gpio,
+    I2c,
+    Spi,
+    Timer,
+    Trng,
+    Uart,
+    /// # Unknown
+    /// The error was converted from a bare `ErrorKind`, so the source is not known.
+    Unknown,
+}
+
+/// # Error Category
+/// What went wrong, grouped the same way for every peripheral so applications can handle
+/// failures without knowing which driver they came from.
+#[non_exhaustive]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Category {
+    /// # Timeout
+    /// The hardware did not finish in time.
+    Timeout,
+    /// # Nack
+    /// The other side of the bus did not acknowledge (or respond at all).
+    Nack,
+    /// # Overrun
+    /// Data arrived faster than it was read, or did not fit, and was lost.
+    Overrun,
+    /// # Bus Error
+    /// The bus itself failed, ie. lost arbitration or a framing error.
+    BusError,
+    /// # Invalid Config
+    /// A argument, or the current state of the driver, does not allow the operation.
+    InvalidConfig,
+    /// # Busy
+    /// The hardware (or a pin it needs) is in use, trying again later may work.
+    Busy,
+    /// # Hardware Fault
+    /// The hardware failed in a way not covered above.
+    HardwareFault,
+}
+
+impl ErrorKind {
+    /// # Category
+    /// The `Category` this kind of error belongs to.
+    pub const fn category(self) -> Category {
+        match self {
+            Self::TimeOut => Category::Timeout,
+            Self::NoResponse => Category::Nack,
+            Self::Overflow | Self::Underflow => Category::Overrun,
+            Self::ComError | Self::Abort => Category::BusError,
+            Self::BadParam
+            | Self::Invalid
+            | Self::BadState
+            | Self::Uninitialized
+            | Self::NotSupported => Category::InvalidConfig,
+            Self::Busy | Self::NoneAvailable => Category::Busy,
+            Self::NullPtr | Self::NoDevice | Self::Unknown | Self::Shutdown | Self::Fail => {
+                Category::HardwareFault
+            }
+        }
+    }
+
+    /// # Within
+    /// Attach the peripheral this error came from, making a `Error`.
+    pub const fn within(self, peripheral: Peripheral) -> Error {
+        Error {
+            peripheral,
+            kind: self,
+        }
+    }
+}
+
+/// # Error
+/// A crate-wide error, the `ErrorKind` together with the peripheral it came from.
+/// Implements the `embedded-hal` (and `embedded-hal-nb`) error traits with the `eh1`
+/// feature, and `embedded_io::Error` with the `embedded-io` feature.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Error {
+    peripheral: Peripheral,
+    kind: ErrorKind,
+}
+
+impl Error {
+    /// # New
+    /// Make a error for the given peripheral.
+    pub const fn new(peripheral: Peripheral, kind: ErrorKind) -> Self {
+        Self { peripheral, kind }
+    }
+
+    /// # Peripheral
+    /// The peripheral this error came from.
+    pub const fn peripheral(&self) -> Peripheral {
+        self.peripheral
+    }
+
+    /// # Kind
+    /// The exact kind of error.
+    pub const fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// # Category
+    /// What went wrong, see `Category`.
+    pub const fn category(&self) -> Category {
+        self.kind.category()
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        kind.within(Peripheral::Unknown)
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl embedded_hal::i2c::Error for Error {
+    fn kind(&self) -> embedded_hal::i2c::ErrorKind {
+        use embedded_hal::i2c::{ErrorKind as I2cKind, NoAcknowledgeSource};
+
+        match (self.kind, self.category()) {
+            (ErrorKind::Abort, _) => I2cKind::ArbitrationLoss,
+            (_, Category::Nack) => I2cKind::NoAcknowledge(NoAcknowledgeSource::Unknown),
+            (_, Category::Overrun) => I2cKind::Overrun,
+            (_, Category::BusError) => I2cKind::Bus,
+            _ => I2cKind::Other,
+        }
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl embedded_hal::spi::Error for Error {
+    fn kind(&self) -> embedded_hal::spi::ErrorKind {
+        use embedded_hal::spi::ErrorKind as SpiKind;
+
+        match self.category() {
+            Category::Overrun => SpiKind::Overrun,
+            Category::BusError => SpiKind::ModeFault,
+            _ => SpiKind::Other,
+        }
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl embedded_hal::digital::Error for Error {
+    fn kind(&self) -> embedded_hal::digital::ErrorKind {
+        embedded_hal::digital::ErrorKind::Other
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl embedded_hal_nb::serial::Error for Error {
+    fn kind(&self) -> embedded_hal_nb::serial::ErrorKind {
+        use embedded_hal_nb::serial::ErrorKind as SerialKind;
+
+        match self.category() {
+            Category::Overrun => SerialKind::Overrun,
+            Category::BusError => SerialKind::FrameFormat,
+            _ => SerialKind::Other,
+        }
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Error for Error {
+    fn kind(&self) -> embedded_io::ErrorKind {
+        use embedded_io::ErrorKind as IoKind;
+
+        match self.category() {
+            Category::Timeout => IoKind::TimedOut,
+            Category::Nack => IoKind::NotConnected,
+            Category::Overrun => IoKind::OutOfMemory,
+            Category::BusError => IoKind::Interrupted,
+            Category::InvalidConfig => IoKind::InvalidInput,
+            Category::Busy => IoKind::AddrInUse,
+            Category::HardwareFault => IoKind::Other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn categories() {
+        assert_eq!(ErrorKind::TimeOut.category(), Category::Timeout);
+        assert_eq!(ErrorKind::NoResponse.category(), Category::Nack);
+        assert_eq!(ErrorKind::BadParam.category(), Category::InvalidConfig);
+
+        let error = ErrorKind::Overflow.within(Peripheral::Uart);
+        assert_eq!(error.peripheral(), Peripheral::Uart);
+        assert_eq!(error.category(), Category::Overrun);
+        assert_eq!(
+            Error::from(ErrorKind::Busy).peripheral(),
+            Peripheral::Unknown
+        );
+    }
+}
@@ -0,0 +1,265 @@
+//! # Fault Handling
+//! Capture what the core was doing when it faulted, keep it across the reset that
+//! follows, and report it after reboot with `last_crash()`.
+//!
+//! With the `fault-handler` feature, this module provides the `HardFault_Handler`,
+//! `MemManage_Handler`, `BusFault_Handler` and `UsageFault_Handler` vectors (overriding
+//! the weak ones of the Maxim startup file). They record a `CrashRecord` and reset the
+//! chip.
+//!
+//! The record is kept in RAM the startup code never initializes (the `.uninit`
+//! section), guarded by a magic and checksum. It survives every reset besides a power
+//! cycle.
+use crate::bits::BitManipulation;
+use crate::memory_map::{mmio, scs};
+
+/// # Record Magic
+/// Marks a valid record ("CRSH" in ASCII).
+const RECORD_MAGIC: u32 = 0x4352_5348;
+
+/// # Fault Enable Bits
+/// The `MEMFAULTENA`, `BUSFAULTENA` and `USGFAULTENA` bits of `SCB_SHCSR`. See
+/// ARMv7-M Reference, B3.2.13.
+const SHCSR_FAULT_ENABLES: [u8; 3] = [16, 17, 18];
+
+/// # Exception Frame
+/// The registers the core pushed onto the stack when the fault was taken.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ExceptionFrame {
+    pub r0: u32,
+    pub r1: u32,
+    pub r2: u32,
+    pub r3: u32,
+    pub r12: u32,
+    /// The link register, where the faulting function was going to return to.
+    pub lr: u32,
+    /// The address of the instruction that faulted (or the one after it, for
+    /// imprecise bus faults).
+    pub pc: u32,
+    pub xpsr: u32,
+}
+
+/// # Fault Kind
+/// Which fault handler ran.
+#[repr(u32)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FaultKind {
+    HardFault = 3,
+    MemManage = 4,
+    BusFault = 5,
+    UsageFault = 6,
+    Unknown = 0,
+}
+
+impl From<u32> for FaultKind {
+    fn from(exception: u32) -> Self {
+        match exception {
+            3 => FaultKind::HardFault,
+            4 => FaultKind::MemManage,
+            5 => FaultKind::BusFault,
+            6 => FaultKind::UsageFault,
+            _ => FaultKind::Unknown,
+        }
+    }
+}
+
+/// # Crash Record
+/// Everything captured about a fault.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CrashRecord {
+    /// Which fault handler ran.
+    pub kind: FaultKind,
+    /// The stacked registers.
+    pub frame: ExceptionFrame,
+    /// `SCB_CFSR`, the MemManage, BusFault and UsageFault status.
+    pub cfsr: u32,
+    /// `SCB_HFSR`, the HardFault status.
+    pub hfsr: u32,
+    /// `SCB_MMFAR`, the faulting address when `cfsr` bit 7 (`MMARVALID`) is set.
+    pub mmfar: u32,
+    /// `SCB_BFAR`, the faulting address when `cfsr` bit 15 (`BFARVALID`) is set.
+    pub bfar: u32,
+    /// The RTC seconds counter when the fault happened, `0` if the RTC was not running.
+    pub timestamp: u32,
+}
+
+/// # Record Words
+/// The size of a `CrashRecord` in words.
+const RECORD_WORDS: usize = core::mem::size_of::<CrashRecord>() / 4;
+
+/// # Stored Record
+/// A `CrashRecord` as it is kept across the reset. It is kept as plain words, since
+/// the RAM may hold anything after a power cycle.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Stored {
+    magic: u32,
+    words: [u32; RECORD_WORDS],
+    checksum: u32,
+}
+
+#[cfg_attr(target_os = "none", link_section = ".uninit.max78000_hal.crash")]
+static mut STORED: core::mem::MaybeUninit<Stored> = core::mem::MaybeUninit::uninit();
+
+/// # Checksum
+/// A simple rotate-xor over the words of a record.
+fn checksum(words: &[u32; RECORD_WORDS]) -> u32 {
+    words
+        .iter()
+        .fold(RECORD_MAGIC, |sum, word| sum.rotate_left(5) ^ word)
+}
+
+#[inline(always)]
+fn read_scs(address: usize) -> u32 {
+    unsafe { core::ptr::read_volatile(hal_macros::mmio_ptr::<u32>(address)) }
+}
+
+/// # Enable Fault Handlers
+/// Enable the MemManage, BusFault and UsageFault exceptions. Without this every fault
+/// escalates to a HardFault, which loses some detail.
+pub fn enable_fault_handlers() {
+    unsafe {
+        let mut shcsr = core::ptr::read_volatile(hal_macros::mmio_ptr::<u32>(scs::SCB_SHCSR));
+        for bit in SHCSR_FAULT_ENABLES {
+            shcsr.set_bit(bit, true);
+        }
+        core::ptr::write_volatile(hal_macros::mmio_ptr::<u32>(scs::SCB_SHCSR), shcsr);
+    }
+}
+
+/// # Capture
+/// Read the fault status registers, and build the record of the fault that stacked
+/// `frame`.
+pub fn capture(kind: FaultKind, frame: &ExceptionFrame) -> CrashRecord {
+    CrashRecord {
+        kind,
+        frame: *frame,
+        cfsr: read_scs(scs::SCB_CFSR),
+        hfsr: read_scs(scs::SCB_HFSR),
+        mmfar: read_scs(scs::SCB_MMFAR),
+        bfar: read_scs(scs::SCB_BFAR),
+        // `RTC_SEC` is the first register of the RTC
+        timestamp: unsafe {
+            core::ptr::read_volatile(hal_macros::mmio_ptr::<u32>(mmio::REAL_TIME_CLOCK))
+        },
+    }
+}
+
+/// # Store
+/// Keep `record` across the next reset, replacing any record already kept.
+pub fn store(record: &CrashRecord) {
+    let words = unsafe { core::mem::transmute::<CrashRecord, [u32; RECORD_WORDS]>(*record) };
+
+    unsafe {
+        core::ptr::addr_of_mut!(STORED)
+            .cast::<Stored>()
+            .write_volatile(Stored {
+                magic: RECORD_MAGIC,
+                words,
+                checksum: checksum(&words),
+            });
+    }
+}
+
+/// # Last Crash
+/// The record of the fault that caused the last reset, if there was one.
+pub fn last_crash() -> Option<CrashRecord> {
+    let stored = unsafe { core::ptr::addr_of!(STORED).cast::<Stored>().read_volatile() };
+
+    // The kind is the first word, and must be a valid `FaultKind` before transmuting
+    let kind = stored.words[0];
+    if stored.magic == RECORD_MAGIC
+        && FaultKind::from(kind) as u32 == kind
+        && checksum(&stored.words) == stored.checksum
+    {
+        Some(unsafe { core::mem::transmute::<[u32; RECORD_WORDS], CrashRecord>(stored.words) })
+    } else {
+        None
+    }
+}
+
+/// # Clear
+/// Forget the kept record, ie. after it was reported.
+pub fn clear() {
+    unsafe {
+        core::ptr::addr_of_mut!((*core::ptr::addr_of_mut!(STORED).cast::<Stored>()).magic)
+            .write_volatile(0);
+    }
+}
+
+/// # Fault Entry
+/// Called by the fault vectors, with the stacked frame.
+#[cfg(all(feature = "fault-handler", target_arch = "arm"))]
+#[no_mangle]
+unsafe extern "C" fn __max78000_hal_fault(frame: &ExceptionFrame) -> ! {
+    // The active exception number is the low 9 bits of `ICSR` (`VECTACTIVE`)
+    let exception = read_scs(scs::SCB_ICSR) & 0x1FF;
+    store(&capture(FaultKind::from(exception), frame));
+
+    crate::gcr::controller_reset()
+}
+
+// Pick the stack the frame was pushed onto from `EXC_RETURN`, then hand it to Rust
+#[cfg(all(feature = "fault-handler", target_arch = "arm"))]
+core::arch::global_asm!(
+    ".section .text.__max78000_hal_fault_vector",
+    ".global HardFault_Handler",
+    ".global MemManage_Handler",
+    ".global BusFault_Handler",
+    ".global UsageFault_Handler",
+    ".thumb_func",
+    "HardFault_Handler:",
+    ".thumb_func",
+    "MemManage_Handler:",
+    ".thumb_func",
+    "BusFault_Handler:",
+    ".thumb_func",
+    "UsageFault_Handler:",
+    "tst lr, #4",
+    "ite eq",
+    "mrseq r0, msp",
+    "mrsne r0, psp",
+    "b __max78000_hal_fault",
+);
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn record_round_trip() {
+        crate::mock::reset();
+        crate::mock::write(scs::SCB_CFSR, 1 << 16);
+        crate::mock::write(mmio::REAL_TIME_CLOCK, 1234);
+
+        let frame = ExceptionFrame {
+            r0: 0,
+            r1: 1,
+            r2: 2,
+            r3: 3,
+            r12: 12,
+            lr: 0x1000_0101,
+            pc: 0x1000_0200,
+            xpsr: 0x0100_0000,
+        };
+        let record = capture(FaultKind::UsageFault, &frame);
+        assert_eq!(record.cfsr, 1 << 16);
+        assert_eq!(record.timestamp, 1234);
+
+        store(&record);
+        assert_eq!(last_crash(), Some(record));
+
+        // A corrupted record is not reported
+        unsafe { (*core::ptr::addr_of_mut!(STORED).cast::<Stored>()).words[7] ^= 1 };
+        assert_eq!(last_crash(), None);
+
+        store(&record);
+        clear();
+        assert_eq!(last_crash(), None);
+    }
+}
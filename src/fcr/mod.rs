@@ -0,0 +1,62 @@
+use crate::memory_map::mmio;
+
+pub mod registers;
+
+type Fcr = registers::Instance<{ mmio::FUNCTION_CONTROL }>;
+
+/// # I2C Glitch Filter
+/// Enable/Disable the SDA and SCL glitch filters of I2C `port` (0, 1, or 2). The filters
+/// reject pulses shorter than a few system clocks, which helps on long or noisy buses.
+///
+/// # Panics
+/// Panics if `port` is higher then 2.
+pub fn set_i2c_glitch_filter(port: usize, enable: bool) {
+    let mut fcr = Fcr::instance();
+    unsafe {
+        fcr.set_i2c_sda_glitch_filter_enable(port, enable);
+        fcr.set_i2c_scl_glitch_filter_enable(port, enable);
+    }
+}
+
+/// # Is I2C Glitch Filter Enabled
+/// Check if both glitch filters of I2C `port` (0, 1, or 2) are enabled.
+///
+/// # Panics
+/// Panics if `port` is higher then 2.
+pub fn is_i2c_glitch_filter_enabled(port: usize) -> bool {
+    let fcr = Fcr::instance();
+    fcr.get_i2c_sda_glitch_filter_enable(port) && fcr.get_i2c_scl_glitch_filter_enable(port)
+}
+
+/// # Set RISC-V Boot Address
+/// Set the address the RISC-V core (CPU1) starts executing from when it is released from
+/// reset.
+///
+/// # Safety
+/// The caller must ensure `address` points at valid RISC-V code before releasing CPU1,
+/// and must not change it while CPU1 is running.
+pub unsafe fn set_riscv_boot_address(address: u32) {
+    Fcr::instance().set_riscv_boot_address(address);
+}
+
+/// # RISC-V Boot Address
+/// Get the address the RISC-V core (CPU1) starts executing from.
+pub fn riscv_boot_address() -> u32 {
+    Fcr::instance().get_riscv_boot_address()
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::mock;
+
+    #[test]
+    fn glitch_filter_bits() {
+        mock::reset();
+
+        set_i2c_glitch_filter(1, true);
+        assert!(is_i2c_glitch_filter_enabled(1));
+        assert!(!is_i2c_glitch_filter_enabled(0));
+        assert_eq!(mock::read(mmio::FUNCTION_CONTROL), 0b11 << 22);
+    }
+}
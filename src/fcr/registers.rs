@@ -0,0 +1,99 @@
+use hal_macros::RW;
+use hal_macros_derive::make_device;
+
+use crate::memory_map::mmio;
+
+/// # Relative Register Offsets
+/// These are the offsets for the FCR registers that the
+/// Maxim Integrated - spec shows. Found on page 89.
+mod rro {
+    /// # Function Control 0 Register
+    pub const FCR_FCTRL0: usize = 0x0000;
+    /// # Automatic Calibration 0 Register
+    pub const FCR_AUTOCAL0: usize = 0x0004;
+    /// # Automatic Calibration 1 Register
+    pub const FCR_AUTOCAL1: usize = 0x0008;
+    /// # Automatic Calibration 2 Register
+    pub const FCR_AUTOCAL2: usize = 0x000C;
+    /// # RISC-V Boot Address Register
+    pub const FCR_URVBOOTADDR: usize = 0x0010;
+    /// # RISC-V Control Register
+    pub const FCR_URVCTRL: usize = 0x0014;
+}
+
+make_device! {
+    device_ports(mmio::FUNCTION_CONTROL);
+
+    /// I2C SDA Glitch Filter Enable, one per I2C port.
+    /// - 0: Disabled
+    /// - 1: Enabled
+    #[bit(20, RW, rro::FCR_FCTRL0)]
+    #[array(count = 3, bit_stride = 2)]
+    i2c_sda_glitch_filter_enable,
+
+    /// I2C SCL Glitch Filter Enable, one per I2C port.
+    /// - 0: Disabled
+    /// - 1: Enabled
+    #[bit(21, RW, rro::FCR_FCTRL0)]
+    #[array(count = 3, bit_stride = 2)]
+    i2c_scl_glitch_filter_enable,
+
+    /// IPO Automatic Calibration Enable.
+    /// Continuously trim the internal primary oscillator against the 32 kHz clock.
+    #[bit(0, RW, rro::FCR_AUTOCAL0)]
+    ipo_auto_calibration_enable,
+
+    /// IPO Automatic Calibration Run.
+    /// Hold to keep the calibration running, clear to stop it.
+    #[bit(1, RW, rro::FCR_AUTOCAL0)]
+    ipo_auto_calibration_run,
+
+    /// IPO Automatic Calibration Load Trim.
+    /// Load `ipo_initial_trim` into the calibration.
+    #[bit(2, RW1O, rro::FCR_AUTOCAL0)]
+    ipo_load_trim,
+
+    /// IPO Automatic Calibration Gain Invert.
+    #[bit(3, RW, rro::FCR_AUTOCAL0)]
+    ipo_gain_invert,
+
+    /// IPO Automatic Calibration Atomic Run.
+    /// Run the calibration for `ipo_done_count` iterations, then stop.
+    #[bit(4, RW1O, rro::FCR_AUTOCAL0)]
+    ipo_atomic_run,
+
+    /// IPO Automatic Calibration Gain (mu).
+    #[bit(8..=19, RW, rro::FCR_AUTOCAL0)]
+    ipo_calibration_gain,
+
+    /// IPO Trim Value.
+    /// The current trim the calibration settled on.
+    #[bit(23..=31, RO, rro::FCR_AUTOCAL0)]
+    ipo_trim_value,
+
+    /// IPO Initial Trim.
+    /// The trim the calibration starts from.
+    #[bit(0..=8, RW, rro::FCR_AUTOCAL1)]
+    ipo_initial_trim,
+
+    /// IPO Automatic Calibration Done Count.
+    /// The number of iterations a atomic run takes.
+    #[bit(0..=7, RW, rro::FCR_AUTOCAL2)]
+    ipo_done_count,
+
+    /// IPO Automatic Calibration Divider.
+    /// The IPO is divided by this before being compared against the 32 kHz clock.
+    #[bit(8..=20, RW, rro::FCR_AUTOCAL2)]
+    ipo_calibration_divider,
+
+    /// RISC-V Boot Address.
+    /// The address the RISC-V core (CPU1) starts executing from when it is released
+    /// from reset.
+    #[bit(0..=31, RW, rro::FCR_URVBOOTADDR)]
+    riscv_boot_address,
+
+    /// RISC-V Memory Select.
+    /// Select which memory the RISC-V core (CPU1) uses for its data.
+    #[bit(0, RW, rro::FCR_URVCTRL)]
+    riscv_memory_select,
+}
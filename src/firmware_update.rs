@@ -0,0 +1,323 @@
+//! # Firmware Update
+//! A small block transfer protocol served over I2C in slave mode, so a host processor
+//! can update the MAX78000's firmware over a existing I2C link.
+//!
+//! Every I2C write from the host is one command, every I2C read returns a single
+//! `Status` byte describing the last command. All values are little endian.
+//!
+//! | Command  | Byte   | Arguments                                    |
+//! |----------|--------|----------------------------------------------|
+//! | Erase    | `0x01` | `address: u32`                               |
+//! | Write    | `0x02` | `address: u32`, `data` (`WRITE_SIZE` aligned) |
+//! | Verify   | `0x03` | `address: u32`, `len: u32`, `crc32: u32`     |
+//! | Commit   | `0x04` |                                              |
+//!
+//! The checksum `Verify` expects is `flc::crc32`. Addresses must fall inside the image area given to `UpdateService::new`, so the
+//! running firmware can not be overwritten. What a commit means (ie. resetting into the
+//! new image, or marking it valid for a bootloader) is up to the application.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::firmware_update::UpdateService;
+//! use max78000_hal::flc::Flc;
+//! use max78000_hal::gcr;
+//! use max78000_hal::i2c::I2C;
+//!
+//! // The upper half of the flash, the running firmware is in the lower one
+//! const IMAGE_START: usize = 0x1004_0000;
+//! const IMAGE_SIZE: usize = 256 * 1024;
+//!
+//! # fn main() -> max78000_hal::error::Result<()> {
+//! let i2c = I2C::init_port_1_slave(0x42)?;
+//! let mut update = unsafe { UpdateService::new(i2c, Flc::init(), IMAGE_START, IMAGE_SIZE)? };
+//!
+//! update.serve()?;
+//! gcr::controller_reset()
+//! # }
+//! ```
+use crate::error::{ErrorKind, Result};
+use crate::flc::{self, crc32, Flc, PAGE_SIZE, WRITE_SIZE};
+use crate::i2c::private::I2CPortCompatable;
+use crate::i2c::I2C;
+
+/// # Max Block
+/// The most data bytes a single `Write` command can carry.
+pub const MAX_BLOCK: usize = 128;
+
+/// # Header Length
+/// The command byte and the address.
+const HEADER_LEN: usize = 5;
+
+/// # Status
+/// The result of the last command, returned to the host on every I2C read.
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Status {
+    /// No command was received yet.
+    Idle = 0x00,
+    /// The last command succeeded.
+    Ok = 0x01,
+    /// The last command was unknown, or had the wrong length.
+    BadCommand = 0x02,
+    /// The last command touched flash outside the image area, or was misaligned.
+    BadAddress = 0x03,
+    /// The flash controller rejected a erase or write.
+    FlashError = 0x04,
+    /// The CRC of the verified range did not match.
+    VerifyFailed = 0x05,
+    /// The image was committed, no more commands are accepted.
+    Committed = 0x06,
+}
+
+/// # Command
+/// A parsed command sent by the host.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command<'a> {
+    /// Erase the page holding `address`.
+    Erase { address: usize },
+    /// Write `data` into erased flash at `address`.
+    Write { address: usize, data: &'a [u8] },
+    /// Check the CRC-32 of `len` bytes at `address`.
+    Verify {
+        address: usize,
+        len: usize,
+        crc32: u32,
+    },
+    /// Finish the update.
+    Commit,
+}
+
+impl<'a> Command<'a> {
+    /// # Parse
+    /// Parse a command packet received from the host.
+    ///
+    /// # Errors
+    /// Returns `Status::BadCommand` if the packet is unknown, or has the wrong length.
+    pub fn parse(packet: &'a [u8]) -> core::result::Result<Self, Status> {
+        let word = |at: usize| {
+            packet
+                .get(at..at + 4)
+                .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .ok_or(Status::BadCommand)
+        };
+
+        match (packet.first(), packet.len()) {
+            (Some(0x01), HEADER_LEN) => Ok(Command::Erase {
+                address: word(1)? as usize,
+            }),
+            (Some(0x02), len) if len > HEADER_LEN && len - HEADER_LEN <= MAX_BLOCK => {
+                Ok(Command::Write {
+                    address: word(1)? as usize,
+                    data: &packet[HEADER_LEN..],
+                })
+            }
+            (Some(0x03), 13) => Ok(Command::Verify {
+                address: word(1)? as usize,
+                len: word(5)? as usize,
+                crc32: word(9)?,
+            }),
+            (Some(0x04), 1) => Ok(Command::Commit),
+            _ => Err(Status::BadCommand),
+        }
+    }
+}
+
+/// # Update Service
+/// Serve the firmware update protocol on a I2C port configured as a slave.
+pub struct UpdateService<Port> {
+    i2c: I2C<Port>,
+    flc: Flc,
+    start: usize,
+    size: usize,
+    status: Status,
+}
+
+impl<Port: I2CPortCompatable> UpdateService<Port> {
+    /// # New
+    /// Serve updates into the `size` bytes of flash at `start`, the image area.
+    ///
+    /// # Errors
+    /// Returns `ErrorKind::BadParam` if the image area is not inside the flash, or not
+    /// made of whole pages.
+    ///
+    /// # Safety
+    /// The image area must not hold any code or data in use while the service runs,
+    /// ie. the running firmware, since the host can erase and write all of it.
+    pub unsafe fn new(i2c: I2C<Port>, flc: Flc, start: usize, size: usize) -> Result<Self> {
+        flc::check_range(start, size)?;
+        if flc::page_start(start) != start || !size.is_multiple_of(PAGE_SIZE) {
+            return Err(ErrorKind::BadParam);
+        }
+
+        Ok(Self {
+            i2c,
+            flc,
+            start,
+            size,
+            status: Status::Idle,
+        })
+    }
+
+    /// # Status
+    /// Get the result of the last command.
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// # Poll
+    /// Wait for one I2C transaction. If it was a command it is run, and its result
+    /// returned.
+    ///
+    /// # Errors
+    /// Returns the I2C error if the transaction failed.
+    pub fn poll(&mut self) -> Result<Option<Status>> {
+        let mut packet = [0u8; HEADER_LEN + MAX_BLOCK];
+        let mut len = 0;
+        let mut overflow = false;
+        let status = self.status as u8;
+
+        self.i2c.slave_transaction(
+            |byte| {
+                match packet.get_mut(len) {
+                    Some(slot) => *slot = byte,
+                    None => overflow = true,
+                }
+                len += 1;
+                Ok(())
+            },
+            || Ok(status),
+        )?;
+
+        if len == 0 {
+            return Ok(None);
+        }
+
+        self.status = match (overflow, self.status) {
+            (_, Status::Committed) => Status::Committed,
+            (true, _) => Status::BadCommand,
+            (false, _) => match Command::parse(&packet[..len]) {
+                Ok(command) => self.run(command),
+                Err(status) => status,
+            },
+        };
+
+        Ok(Some(self.status))
+    }
+
+    /// # Serve
+    /// Keep running commands until the host commits the image.
+    ///
+    /// # Errors
+    /// Returns the I2C error if a transaction failed.
+    pub fn serve(&mut self) -> Result<()> {
+        while self.poll()? != Some(Status::Committed) {}
+        Ok(())
+    }
+
+    /// # Free
+    /// Give back the I2C port and the flash controller.
+    pub fn free(self) -> (I2C<Port>, Flc) {
+        (self.i2c, self.flc)
+    }
+
+    /// Make sure `len` bytes at `address` are inside the image area.
+    fn in_image(&self, address: usize, len: usize) -> bool {
+        address >= self.start
+            && address
+                .checked_add(len)
+                .is_some_and(|end| end <= self.start + self.size)
+    }
+
+    fn run(&mut self, command: Command) -> Status {
+        match command {
+            Command::Erase { address } => {
+                if !self.in_image(address, 1) {
+                    return Status::BadAddress;
+                }
+
+                match unsafe { self.flc.erase_page(address) } {
+                    Ok(()) => Status::Ok,
+                    Err(_) => Status::FlashError,
+                }
+            }
+            Command::Write { address, data } => {
+                if !self.in_image(address, data.len())
+                    || !address.is_multiple_of(WRITE_SIZE)
+                    || !data.len().is_multiple_of(WRITE_SIZE)
+                {
+                    return Status::BadAddress;
+                }
+
+                match unsafe { self.flc.write(address, data) } {
+                    Ok(()) => Status::Ok,
+                    Err(_) => Status::FlashError,
+                }
+            }
+            Command::Verify {
+                address,
+                len,
+                crc32: expected,
+            } => {
+                if !self.in_image(address, len) {
+                    return Status::BadAddress;
+                }
+
+                let image = unsafe { core::slice::from_raw_parts(address as *const u8, len) };
+                if crc32(image) == expected {
+                    Status::Ok
+                } else {
+                    Status::VerifyFailed
+                }
+            }
+            Command::Commit => Status::Committed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(
+            Command::parse(&[0x01, 0x00, 0x20, 0x04, 0x10]),
+            Ok(Command::Erase {
+                address: 0x1004_2000
+            })
+        );
+
+        let write = [0x02, 0x10, 0x00, 0x04, 0x10, 0xAA, 0xBB];
+        assert_eq!(
+            Command::parse(&write),
+            Ok(Command::Write {
+                address: 0x1004_0010,
+                data: &[0xAA, 0xBB]
+            })
+        );
+
+        assert_eq!(
+            Command::parse(&[0x03, 0, 0, 4, 0x10, 0x80, 0, 0, 0, 0x26, 0x39, 0xF4, 0xCB]),
+            Ok(Command::Verify {
+                address: 0x1004_0000,
+                len: 0x80,
+                crc32: 0xCBF4_3926
+            })
+        );
+
+        assert_eq!(Command::parse(&[0x04]), Ok(Command::Commit));
+    }
+
+    #[test]
+    fn rejects_bad_packets() {
+        assert_eq!(Command::parse(&[]), Err(Status::BadCommand));
+        assert_eq!(Command::parse(&[0x01, 0, 0]), Err(Status::BadCommand));
+        assert_eq!(Command::parse(&[0x02, 0, 0, 0, 0]), Err(Status::BadCommand));
+        assert_eq!(Command::parse(&[0x04, 0]), Err(Status::BadCommand));
+        assert_eq!(Command::parse(&[0x7F]), Err(Status::BadCommand));
+
+        let too_long = [0x02; HEADER_LEN + MAX_BLOCK + 1];
+        assert_eq!(Command::parse(&too_long), Err(Status::BadCommand));
+    }
+}
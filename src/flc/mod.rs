@@ -0,0 +1,302 @@
+use crate::error::{ErrorKind, Result};
+use crate::memory_map::{memory, mmio};
+use crate::time::Hertz;
+use crate::watchdog::{Feed, NoFeed};
+use registers::{EraseCode, Instance};
+
+pub mod registers;
+
+/// # Page Size
+/// The smallest erasable unit of flash, in bytes.
+pub const PAGE_SIZE: usize = 8 * 1024;
+
+/// # Write Size
+/// The flash is written 128 bits at a time, writes must be aligned to this.
+pub const WRITE_SIZE: usize = 16;
+
+/// # Feed Interval
+/// How many bytes `write_feeding` writes between feeds, one page.
+pub const FEED_INTERVAL: usize = PAGE_SIZE;
+
+/// # Unlock Key
+/// Written into `FLC_CTRL.unlock` to allow writes and erases.
+const UNLOCK_KEY: u8 = 0x2;
+
+/// # Flash Clock
+/// The clock the flash controller must be run at.
+const FLASH_CLOCK: Hertz = Hertz::MHz(1);
+
+/// # Page Start
+/// Get the address of the page holding `address`.
+pub const fn page_start(address: usize) -> usize {
+    address - (address - memory::FLASH.0) % PAGE_SIZE
+}
+
+/// # Check Range
+/// Make sure `len` bytes at `address` are inside the flash.
+///
+/// # Errors
+/// Returns `ErrorKind::BadParam` if any part of the range is outside the flash.
+pub fn check_range(address: usize, len: usize) -> Result<()> {
+    let (start, size) = memory::FLASH;
+    match address.checked_add(len) {
+        Some(end) if address >= start && end <= start + size => Ok(()),
+        _ => Err(ErrorKind::BadParam),
+    }
+}
+
+/// # CRC-32
+/// The IEEE 802.3 CRC-32 (as used by zlib) of `data`, ie. to check a image
+/// written to flash.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+/// # Flash Controller
+/// A wrapper around the flash controller (FLC), used to erase and write the internal
+/// flash.
+///
+/// The flash can not be read while it is being written or erased, so code running
+/// from the same flash is stalled for the duration of each operation.
+pub struct Flc {
+    registers: Instance<{ mmio::FLASH_CONTROLLER_0 }>,
+}
+
+impl Flc {
+    /// # Init
+    /// Set the flash clock divisor for the current core clock, and lock the flash.
+    pub fn init() -> Self {
+        let mut registers = Instance::<{ mmio::FLASH_CONTROLLER_0 }>::instance();
+        let divisor = (crate::core_clock() / FLASH_CLOCK).clamp(1, u8::MAX as u32);
+
+        unsafe {
+            registers.set_clock_divisor(divisor as u8);
+            registers.set_unlock(0);
+        }
+
+        Self { registers }
+    }
+
+    /// # Steal
+    /// Conjure a handle to the flash controller without setting it up. Meant for
+    /// interrupt handlers and panic paths that need it after it was moved elsewhere.
+    ///
+    /// # Safety
+    /// The flash controller must already have been initialized with `init`, and the
+    /// caller must ensure the stolen handle does not race with the original one.
+    pub unsafe fn steal() -> Self {
+        Self {
+            registers: Instance::<{ mmio::FLASH_CONTROLLER_0 }>::instance(),
+        }
+    }
+
+    /// # Is Busy
+    /// Check if a write or erase is in progress.
+    pub fn is_busy(&self) -> bool {
+        self.registers.get_flash_busy()
+            || self.registers.is_write_pending()
+            || self.registers.is_page_erase_pending()
+            || self.registers.is_mass_erase_pending()
+    }
+
+    /// # Erase Page
+    /// Erase the page holding `address`, setting all of its bytes to `0xFF`.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` if `address` is not inside the flash.
+    /// - `ErrorKind::Fail` if the flash controller rejected the erase.
+    ///
+    /// # Safety
+    /// The caller must ensure the page does not hold any code or data still in use,
+    /// ie. the running firmware.
+    pub unsafe fn erase_page(&mut self, address: usize) -> Result<()> {
+        check_range(address, 1)?;
+
+        self.start(page_start(address), |registers| unsafe {
+            registers.set_erase_code(EraseCode::Page);
+            registers.activate_page_erase();
+        })
+    }
+
+    /// # Write 128
+    /// Write one 128-bit line of flash, which must have been erased before.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` if `address` is not inside the flash, or not aligned to
+    ///   `WRITE_SIZE`.
+    /// - `ErrorKind::Fail` if the flash controller rejected the write.
+    ///
+    /// # Safety
+    /// The caller must ensure the line does not hold any code or data still in use.
+    pub unsafe fn write_128(&mut self, address: usize, words: [u32; 4]) -> Result<()> {
+        check_range(address, WRITE_SIZE)?;
+        if !address.is_multiple_of(WRITE_SIZE) {
+            return Err(ErrorKind::BadParam);
+        }
+
+        self.start(address, |registers| unsafe {
+            registers.set_data_0(words[0]);
+            registers.set_data_1(words[1]);
+            registers.set_data_2(words[2]);
+            registers.set_data_3(words[3]);
+            registers.activate_write();
+        })
+    }
+
+    /// # Erase Range
+    /// Erase every page holding a byte of the `len` bytes at `address`, calling `feed`
+    /// after each page, while the flash controller is idle.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` if the range is not inside the flash.
+    /// - `ErrorKind::Fail` if the flash controller rejected a erase.
+    ///
+    /// # Safety
+    /// The caller must ensure the pages do not hold any code or data still in use.
+    pub unsafe fn erase_range(
+        &mut self,
+        address: usize,
+        len: usize,
+        feed: &mut dyn Feed,
+    ) -> Result<()> {
+        check_range(address, len)?;
+
+        let mut page = page_start(address);
+        while page < address + len {
+            self.erase_page(page)?;
+            feed.feed();
+            page += PAGE_SIZE;
+        }
+
+        Ok(())
+    }
+
+    /// # Write
+    /// Write `data` into erased flash starting at `address`, one 128-bit line at a
+    /// time. See `write_feeding` for large writes while a watchdog is running.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` if the range is not inside the flash, or `address` and
+    ///   `data.len()` are not multiples of `WRITE_SIZE`.
+    /// - `ErrorKind::Fail` if the flash controller rejected a write.
+    ///
+    /// # Safety
+    /// The caller must ensure the range does not hold any code or data still in use.
+    pub unsafe fn write(&mut self, address: usize, data: &[u8]) -> Result<()> {
+        self.write_feeding(address, data, &mut NoFeed)
+    }
+
+    /// # Write Feeding
+    /// Like `write`, but calls `feed` after every `FEED_INTERVAL` bytes written, while
+    /// the flash controller is idle.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` if the range is not inside the flash, or `address` and
+    ///   `data.len()` are not multiples of `WRITE_SIZE`.
+    /// - `ErrorKind::Fail` if the flash controller rejected a write.
+    ///
+    /// # Safety
+    /// The caller must ensure the range does not hold any code or data still in use.
+    pub unsafe fn write_feeding(
+        &mut self,
+        address: usize,
+        data: &[u8],
+        feed: &mut dyn Feed,
+    ) -> Result<()> {
+        check_range(address, data.len())?;
+        if !data.len().is_multiple_of(WRITE_SIZE) {
+            return Err(ErrorKind::BadParam);
+        }
+
+        for (index, line) in data.chunks_exact(WRITE_SIZE).enumerate() {
+            let mut words = [0u32; 4];
+            for (word, bytes) in words.iter_mut().zip(line.chunks_exact(4)) {
+                *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            }
+
+            self.write_128(address + index * WRITE_SIZE, words)?;
+
+            if ((index + 1) * WRITE_SIZE).is_multiple_of(FEED_INTERVAL) {
+                feed.feed();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Unlock the flash, set the address, start the operation and wait for it to
+    /// finish, then lock the flash again.
+    fn start<Func>(&mut self, address: usize, func: Func) -> Result<()>
+    where
+        Func: FnOnce(&mut Instance<{ mmio::FLASH_CONTROLLER_0 }>),
+    {
+        while self.is_busy() {}
+
+        unsafe {
+            self.registers.set_done_flag(false);
+            self.registers.set_access_fail_flag(false);
+            self.registers
+                .set_flash_address((address - memory::FLASH.0) as u32);
+            self.registers.set_unlock(UNLOCK_KEY);
+        }
+
+        func(&mut self.registers);
+        while self.is_busy() {}
+
+        let failed = self.registers.get_access_fail_flag();
+        unsafe {
+            self.registers.set_erase_code(EraseCode::Nop);
+            self.registers.set_unlock(0);
+        }
+
+        // The instruction cache may still hold the old contents
+        crate::gcr::flush_instruction_cache();
+
+        if failed {
+            Err(ErrorKind::Fail)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn crc32_matches_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(&[]), 0);
+    }
+
+    #[test]
+    fn page_start_rounds_down() {
+        let (start, _) = memory::FLASH;
+        assert_eq!(page_start(start), start);
+        assert_eq!(page_start(start + PAGE_SIZE - 1), start);
+        assert_eq!(page_start(start + PAGE_SIZE + 0x10), start + PAGE_SIZE);
+    }
+
+    #[test]
+    fn check_range_stays_in_flash() {
+        let (start, size) = memory::FLASH;
+        assert!(check_range(start, size).is_ok());
+        assert!(check_range(start + size - WRITE_SIZE, WRITE_SIZE).is_ok());
+        assert_eq!(check_range(start - 1, 1), Err(ErrorKind::BadParam));
+        assert_eq!(check_range(start + size, 1), Err(ErrorKind::BadParam));
+        assert_eq!(check_range(usize::MAX, 2), Err(ErrorKind::BadParam));
+    }
+}
@@ -0,0 +1,110 @@
+use hal_macros::RW;
+use hal_macros_derive::make_device;
+
+use crate::memory_map::mmio;
+
+/// # Relative Register Offsets
+/// These are the offsets for the FLC registers that the
+/// Maxim Integrated - spec shows. See MAX78000 User Guide, Table 7-5.
+mod rro {
+    /// # Flash Write Address Register
+    pub const FLC_ADDR: usize = 0x0000;
+    /// # Flash Clock Divisor Register
+    pub const FLC_CLKDIV: usize = 0x0004;
+    /// # Flash Control Register
+    pub const FLC_CTRL: usize = 0x0008;
+    /// # Flash Interrupt Register
+    pub const FLC_INTR: usize = 0x0024;
+    /// # Flash Write Data 0 Register
+    pub const FLC_DATA0: usize = 0x0030;
+    /// # Flash Write Data 1 Register
+    pub const FLC_DATA1: usize = 0x0034;
+    /// # Flash Write Data 2 Register
+    pub const FLC_DATA2: usize = 0x0038;
+    /// # Flash Write Data 3 Register
+    pub const FLC_DATA3: usize = 0x003C;
+}
+
+make_device! {
+    device_ports(mmio::FLASH_CONTROLLER_0);
+
+    /// Flash Address.
+    /// The physical address (ie. offset from the start of flash) to write or erase.
+    #[bit(0..=31, RW, rro::FLC_ADDR)]
+    flash_address,
+
+    /// Flash Clock Divisor.
+    /// The system clock is divided by this to get the 1 MHz flash clock.
+    #[bit(0..=7, RW, rro::FLC_CLKDIV)]
+    clock_divisor,
+
+    /// Write.
+    /// Start writing the data registers to `flash_address`, clears when done.
+    #[bit(0, RW1O, rro::FLC_CTRL)]
+    write,
+
+    /// Mass Erase.
+    /// Start erasing the whole flash, clears when done.
+    #[bit(1, RW1O, rro::FLC_CTRL)]
+    mass_erase,
+
+    /// Page Erase.
+    /// Start erasing the page holding `flash_address`, clears when done.
+    #[bit(2, RW1O, rro::FLC_CTRL)]
+    page_erase,
+
+    /// Erase Code.
+    /// Must be set to the matching code before starting a erase.
+    #[bit(8..=15, RW, rro::FLC_CTRL)]
+    #[values(EraseCode: Nop = 0x00, Page = 0x55, Mass = 0xAA)]
+    erase_code,
+
+    /// Flash Busy.
+    /// Set while a write or erase is in progress.
+    #[bit(24, RO, rro::FLC_CTRL)]
+    flash_busy,
+
+    /// Low Voltage Enable.
+    #[bit(25, RW, rro::FLC_CTRL)]
+    low_voltage_enable,
+
+    /// Flash Unlock.
+    /// Must be set to `0x2` for writes and erases to be accepted.
+    #[bit(28..=31, RW, rro::FLC_CTRL)]
+    unlock,
+
+    /// Done Flag.
+    /// Set when a write or erase finished, write 0 to clear.
+    #[bit(0, RW, rro::FLC_INTR)]
+    done_flag,
+
+    /// Access Fail Flag.
+    /// Set when a operation was attempted while the flash was locked or busy, write 0
+    /// to clear.
+    #[bit(1, RW, rro::FLC_INTR)]
+    access_fail_flag,
+
+    /// Done Interrupt Enable.
+    #[bit(8, RW, rro::FLC_INTR)]
+    done_interrupt_enable,
+
+    /// Access Fail Interrupt Enable.
+    #[bit(9, RW, rro::FLC_INTR)]
+    access_fail_interrupt_enable,
+
+    /// Flash Write Data 0.
+    #[bit(0..=31, RW, rro::FLC_DATA0)]
+    data_0,
+
+    /// Flash Write Data 1.
+    #[bit(0..=31, RW, rro::FLC_DATA1)]
+    data_1,
+
+    /// Flash Write Data 2.
+    #[bit(0..=31, RW, rro::FLC_DATA2)]
+    data_2,
+
+    /// Flash Write Data 3.
+    #[bit(0..=31, RW, rro::FLC_DATA3)]
+    data_3,
+}
@@ -0,0 +1,54 @@
+use hal_macros::RW;
+use hal_macros_derive::make_device;
+
+use crate::memory_map::mmio;
+
+/// # Relative Register Offsets
+/// The offsets of the Low Power Global Control (LPGCR) registers, which reset and
+/// clock the peripherals on the low power bus.
+mod rro {
+    /// # Low Power Reset Register
+    pub const LPGCR_RST: usize = 0x0008;
+    /// # Low Power Peripheral Clock Disable Register
+    pub const LPGCR_PCLKDIS: usize = 0x000C;
+}
+
+make_device! {
+    device_ports(mmio::LOW_POWER_CONTROL);
+
+    /// Low Power UART 0 (UART3) Reset.
+    /// - 0: Reset complete
+    /// - 1: Starts reset
+    #[bit(4, RW1O, rro::LPGCR_RST)]
+    uart3_reset,
+
+    /// Low Power Timer 1 (TMR5) Reset.
+    /// - 0: Reset complete
+    /// - 1: Starts reset
+    #[bit(3, RW1O, rro::LPGCR_RST)]
+    timer5_reset,
+
+    /// Low Power Timer 0 (TMR4) Reset.
+    /// - 0: Reset complete
+    /// - 1: Starts reset
+    #[bit(2, RW1O, rro::LPGCR_RST)]
+    timer4_reset,
+
+    /// Low Power UART 0 (UART3) Clock Disable.
+    /// - 0: Enabled
+    /// - 1: Disabled
+    #[bit(4, RW, rro::LPGCR_PCLKDIS)]
+    uart3_clock_disable,
+
+    /// Low Power Timer 1 (TMR5) Clock Disable.
+    /// - 0: Enabled
+    /// - 1: Disabled
+    #[bit(3, RW, rro::LPGCR_PCLKDIS)]
+    timer5_clock_disable,
+
+    /// Low Power Timer 0 (TMR4) Clock Disable.
+    /// - 0: Enabled
+    /// - 1: Disabled
+    #[bit(2, RW, rro::LPGCR_PCLKDIS)]
+    timer4_clock_disable,
+}
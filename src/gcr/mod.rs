@@ -1,16 +1,20 @@
 use crate::memory_map::mmio;
 
+pub mod low_power;
 pub mod registers;
 
-static mut GLOBAL_CONTROL_REGISTER: Option<registers::Registers> = None;
+static mut GLOBAL_CONTROL_REGISTER: Option<registers::Instance<{ mmio::GLOBAL_CONTROL }>> = None;
 
 /// # Setup Global Control Register
 /// Init the global control register, allows you to call other functions on the global
 /// control register.
+///
+/// The first call also applies the factory clock trims, see `trim::apply`.
 #[inline(always)]
 pub fn init_global_control_register() {
-    if unsafe { GLOBAL_CONTROL_REGISTER.is_none() } {
-        unsafe { GLOBAL_CONTROL_REGISTER = Some(registers::Registers::new(mmio::GLOBAL_CONTROL)) };
+    if unsafe { (*core::ptr::addr_of!(GLOBAL_CONTROL_REGISTER)).is_none() } {
+        crate::trim::apply();
+        unsafe { GLOBAL_CONTROL_REGISTER = Some(registers::Instance::instance()) };
     }
 }
 
@@ -23,6 +27,7 @@ fn ensure_gcr() {
 /// Possible hardware devices on the MAX78000 Chip set. Use this enum
 /// to select which hardware device to use when enabling/disabling clock
 /// or other hardware features.
+#[derive(Clone, Copy)]
 pub enum HardwareSource {
     GPIO0,
     GPIO1,
@@ -35,11 +40,14 @@ pub enum HardwareSource {
     TMR1,
     TMR2,
     TMR3,
+    TMR4,
+    TMR5,
     ADC,
     CNN,
     I2C1,
     PT,
     UART2,
+    UART3,
     TRNG,
     SMPHR,
     OWIRE,
@@ -59,12 +67,68 @@ pub enum HardwareSource {
 pub fn controller_reset() -> ! {
     ensure_gcr();
     unsafe {
-        GLOBAL_CONTROL_REGISTER
+        (*core::ptr::addr_of_mut!(GLOBAL_CONTROL_REGISTER))
             .as_mut()
             .unwrap()
             .activate_system_reset()
     };
-    loop {}
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+/// # Soft Reset
+/// Reset the CPU and peripherals, keeping the GCR registers (ie. `GCR_GPR0`), GPIO and
+/// RAM as they are.
+pub fn soft_reset() -> ! {
+    ensure_gcr();
+    unsafe {
+        (*core::ptr::addr_of_mut!(GLOBAL_CONTROL_REGISTER))
+            .as_mut()
+            .unwrap()
+            .activate_soft_reset()
+    };
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+/// # Set General Purpose Register
+/// Write `GCR_GPR0`, a scratch register that survives a `soft_reset`.
+pub fn set_general_purpose_register(value: u32) {
+    ensure_gcr();
+    unsafe {
+        (*core::ptr::addr_of_mut!(GLOBAL_CONTROL_REGISTER))
+            .as_mut()
+            .unwrap()
+            .set_general_purpose_register(value)
+    };
+}
+
+/// # Get General Purpose Register
+/// Read `GCR_GPR0`.
+pub fn get_general_purpose_register() -> u32 {
+    ensure_gcr();
+    unsafe {
+        (*core::ptr::addr_of!(GLOBAL_CONTROL_REGISTER))
+            .as_ref()
+            .unwrap()
+            .get_general_purpose_register()
+    }
+}
+
+/// # Flush Instruction Cache
+/// Invalidate the CM4 instruction cache (ICC0), ie. after the flash was written.
+pub fn flush_instruction_cache() {
+    ensure_gcr();
+
+    let gcr = unsafe {
+        (*core::ptr::addr_of_mut!(GLOBAL_CONTROL_REGISTER))
+            .as_mut()
+            .unwrap()
+    };
+    unsafe { gcr.set_icc0_cache_flush(true) };
+    while gcr.get_icc0_cache_flush() {}
 }
 
 /// # System Clock Enable
@@ -72,7 +136,12 @@ pub fn controller_reset() -> ! {
 pub fn system_clock_enable(clock: HardwareSource, enable: bool) {
     ensure_gcr();
 
-    let gcr = unsafe { GLOBAL_CONTROL_REGISTER.as_mut().unwrap() };
+    let gcr = unsafe {
+        (*core::ptr::addr_of_mut!(GLOBAL_CONTROL_REGISTER))
+            .as_mut()
+            .unwrap()
+    };
+    let mut lpgcr = low_power_control();
     unsafe {
         match clock {
             HardwareSource::GPIO0 => gcr.set_gpio0_port_and_pad_logic_clock_disable(!enable),
@@ -87,11 +156,14 @@ pub fn system_clock_enable(clock: HardwareSource, enable: bool) {
             HardwareSource::TMR1 => gcr.set_timer1_clock_disable(!enable),
             HardwareSource::TMR2 => gcr.set_timer2_clock_disable(!enable),
             HardwareSource::TMR3 => gcr.set_timer3_clock_disable(!enable),
+            HardwareSource::TMR4 => lpgcr.set_timer4_clock_disable(!enable),
+            HardwareSource::TMR5 => lpgcr.set_timer5_clock_disable(!enable),
             HardwareSource::ADC => gcr.set_adc_clock_disable(!enable),
             HardwareSource::CNN => gcr.set_cnn_clock_disable(!enable),
             HardwareSource::I2C1 => gcr.set_i2c1_clock_disable(!enable),
             HardwareSource::PT => gcr.set_pulse_train_clock_disable(!enable),
             HardwareSource::UART2 => gcr.set_uart2_clock_disable(!enable),
+            HardwareSource::UART3 => lpgcr.set_uart3_clock_disable(!enable),
             HardwareSource::TRNG => gcr.set_trng_clock_disable(!enable),
             HardwareSource::SMPHR => gcr.set_semaphore_block_clock_disable(!enable),
             HardwareSource::OWIRE => gcr.set_one_wire_clock_disable(!enable),
@@ -112,7 +184,12 @@ pub fn system_clock_enable(clock: HardwareSource, enable: bool) {
 pub fn peripheral_reset(device: HardwareSource) {
     ensure_gcr();
 
-    let gcr = unsafe { GLOBAL_CONTROL_REGISTER.as_mut().unwrap() };
+    let gcr = unsafe {
+        (*core::ptr::addr_of_mut!(GLOBAL_CONTROL_REGISTER))
+            .as_mut()
+            .unwrap()
+    };
+    let mut lpgcr = low_power_control();
     unsafe {
         match device {
             HardwareSource::GPIO0 => gcr.activate_gpio0_reset(),
@@ -127,11 +204,14 @@ pub fn peripheral_reset(device: HardwareSource) {
             HardwareSource::TMR1 => gcr.activate_timer1_reset(),
             HardwareSource::TMR2 => gcr.activate_timer2_reset(),
             HardwareSource::TMR3 => gcr.activate_timer3_reset(),
+            HardwareSource::TMR4 => lpgcr.activate_timer4_reset(),
+            HardwareSource::TMR5 => lpgcr.activate_timer5_reset(),
             HardwareSource::ADC => gcr.activate_adc_reset(),
             HardwareSource::CNN => gcr.activate_cnn_reset(),
             HardwareSource::I2C1 => gcr.activate_i2c1_reset(),
             HardwareSource::PT => gcr.activate_pulse_train_reset(),
             HardwareSource::UART2 => gcr.activate_uart2_reset(),
+            HardwareSource::UART3 => lpgcr.activate_uart3_reset(),
             HardwareSource::TRNG => gcr.activate_trng_reset(),
             HardwareSource::SMPHR => gcr.activate_semaphore_block_reset(),
             HardwareSource::OWIRE => gcr.activate_one_wire_reset(),
@@ -148,4 +228,14 @@ pub fn peripheral_reset(device: HardwareSource) {
 
     // Wait until reset is complete
     while gcr.get_reset_status0() | gcr.get_reset_status1() != 0 {}
+    while lpgcr.is_timer4_reset_pending()
+        || lpgcr.is_timer5_reset_pending()
+        || lpgcr.is_uart3_reset_pending()
+    {}
+}
+
+/// The low power global control registers, which reset and clock the peripherals on
+/// the low power bus (ie. TMR4 and TMR5).
+fn low_power_control() -> low_power::Instance<{ mmio::LOW_POWER_CONTROL }> {
+    low_power::Instance::instance()
 }
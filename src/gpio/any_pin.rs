@@ -0,0 +1,263 @@
+//! # Any Pin
+//! Typed pins with their port and number moved from the type to a runtime value, so
+//! pins in the same mode have the same type. A LED matrix's rows can then be kept in
+//! one array, and iterated over.
+//!
+//! Like a typed pin, a `AnyPin` keeps its pin owned when dropped.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::gpio::any_pin::AnyPin;
+//! use max78000_hal::gpio::pin::{Gpio0, Output};
+//!
+//! let pins = Gpio0::take().unwrap();
+//! let mut rows = [
+//!     pins.p0_5.into_push_pull_output().degrade(),
+//!     pins.p0_6.into_push_pull_output().degrade(),
+//!     pins.p0_19.into_push_pull_output().degrade(),
+//! ];
+//! rows.iter_mut().for_each(AnyPin::<Output>::set_high);
+//! ```
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+
+use super::pin::{Input, OpenDrain, Output, Pin};
+use super::{GpioPin, GpioSelect};
+
+/// # Any Pin
+/// A pin in `MODE`, on any port.
+pub struct AnyPin<MODE> {
+    pin: ManuallyDrop<GpioPin>,
+    _mode: PhantomData<MODE>,
+}
+
+impl<const PORT: usize, const N: usize, MODE> Pin<PORT, N, MODE> {
+    /// # Degrade
+    /// Move the port and number of the pin out of its type, keeping the mode.
+    pub fn degrade(self) -> AnyPin<MODE> {
+        AnyPin {
+            pin: self.raw(),
+            _mode: PhantomData,
+        }
+    }
+}
+
+impl<MODE> AnyPin<MODE> {
+    /// # Port
+    /// The port of the pin.
+    pub fn port(&self) -> GpioSelect {
+        self.pin.get_port()
+    }
+
+    /// # Pin
+    /// The number of the pin in its port.
+    pub fn pin(&self) -> usize {
+        self.pin.get_pin()
+    }
+
+    /// # Erase
+    /// Give up the mode too, for a `GpioPin` owning the pin.
+    pub fn erase(self) -> GpioPin {
+        ManuallyDrop::into_inner(self.pin)
+    }
+}
+
+impl<PULL> AnyPin<Input<PULL>> {
+    /// # Is High
+    /// Check if the pin reads high.
+    pub fn is_high(&self) -> bool {
+        self.pin.get_input()
+    }
+
+    /// # Is Low
+    /// Check if the pin reads low.
+    pub fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+}
+
+impl AnyPin<Output> {
+    /// # Set High
+    /// Drive the pin high.
+    pub fn set_high(&mut self) {
+        self.pin.set_output(true);
+    }
+
+    /// # Set Low
+    /// Drive the pin low.
+    pub fn set_low(&mut self) {
+        self.pin.set_output(false);
+    }
+
+    /// # Toggle
+    /// Drive the pin to the other level.
+    pub fn toggle(&mut self) {
+        self.pin.toggle_output();
+    }
+
+    /// # Is Set High
+    /// Check if the pin is driven high.
+    pub fn is_set_high(&self) -> bool {
+        self.pin.get_output()
+    }
+
+    /// # Is Set Low
+    /// Check if the pin is driven low.
+    pub fn is_set_low(&self) -> bool {
+        !self.is_set_high()
+    }
+}
+
+impl<PULL> AnyPin<OpenDrain<PULL>> {
+    /// # Set High
+    /// Release the pin.
+    pub fn set_high(&mut self) {
+        self.pin.set_open_drain(true);
+    }
+
+    /// # Set Low
+    /// Pull the pin low.
+    pub fn set_low(&mut self) {
+        self.pin.set_open_drain(false);
+    }
+
+    /// # Is Set High
+    /// Check if the pin is released.
+    pub fn is_set_high(&self) -> bool {
+        self.pin.is_open_drain_released()
+    }
+
+    /// # Is High
+    /// Check if the line reads high.
+    pub fn is_high(&self) -> bool {
+        self.pin.get_input()
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl embedded_hal_0_2::digital::v2::OutputPin for AnyPin<Output> {
+    type Error = core::convert::Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Self::set_low(self);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Self::set_high(self);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl<PULL> embedded_hal_0_2::digital::v2::InputPin for AnyPin<Input<PULL>> {
+    type Error = core::convert::Infallible;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(Self::is_high(self))
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(Self::is_low(self))
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<MODE> embedded_hal::digital::ErrorType for AnyPin<MODE> {
+    type Error = core::convert::Infallible;
+}
+
+#[cfg(feature = "eh1")]
+impl embedded_hal::digital::OutputPin for AnyPin<Output> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Self::set_low(self);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Self::set_high(self);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl embedded_hal::digital::StatefulOutputPin for AnyPin<Output> {
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(Self::is_set_high(self))
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(Self::is_set_low(self))
+    }
+
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        Self::toggle(self);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<PULL> embedded_hal::digital::InputPin for AnyPin<Input<PULL>> {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(Self::is_high(self))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(Self::is_low(self))
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<PULL> embedded_hal::digital::OutputPin for AnyPin<OpenDrain<PULL>> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Self::set_low(self);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Self::set_high(self);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<PULL> embedded_hal::digital::InputPin for AnyPin<OpenDrain<PULL>> {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(Self::is_high(self))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!Self::is_high(self))
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::gpio::pin::{Gpio0, Gpio2};
+    use crate::gpio::registers::{self, rro};
+
+    #[test]
+    fn mixed_ports_in_one_array() {
+        crate::gpio::reset_mock();
+        let port0 = Gpio0::take().unwrap();
+        let port2 = Gpio2::take().unwrap();
+
+        let mut rows = [
+            port0.p0_5.into_push_pull_output().degrade(),
+            port2.p2_2.into_push_pull_output().degrade(),
+        ];
+        assert_eq!(rows[1].port(), GpioSelect::Gpio2);
+        assert_eq!(rows[1].pin(), 2);
+
+        rows.iter_mut().for_each(AnyPin::<Output>::set_high);
+        assert_eq!(crate::mock::read(rro::GPIO_OUT + registers::GPIO_0), 1 << 5);
+        assert_eq!(crate::mock::read(rro::GPIO_OUT + registers::GPIO_2), 1 << 2);
+
+        // Still owned when dropped, erased it is given back
+        let [row, _] = rows;
+        drop(row.erase());
+        assert!(GpioPin::new(GpioSelect::Gpio0, 5).is_some());
+        assert!(GpioPin::new(GpioSelect::Gpio2, 2).is_none());
+    }
+}
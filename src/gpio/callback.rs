@@ -0,0 +1,181 @@
+//! # GPIO Callbacks
+//! Run a function when a pin's interrupt fires, without writing the interrupt handler.
+//! All the pins of a port share one NVIC line, so `InterruptHandler` reads the port's
+//! interrupt flags once, clears them in one write, and calls the function registered
+//! for every pin that interrupted. Register one with `GpioPin::set_callback`, or
+//! `set_callback` on a typed input pin.
+//!
+//! Bind `InterruptHandler` to the interrupts of the ports you register callbacks on,
+//! the NVIC line is enabled by the first registration on that port. It is the handler
+//! of `wait` too, so the other pins of a port can still be waited on.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::bind_interrupts;
+//! use max78000_hal::gpio::callback;
+//! use max78000_hal::gpio::pin::Gpio2;
+//! use max78000_hal::gpio::wait::Trigger;
+//! # let pins = Gpio2::take().unwrap();
+//!
+//! bind_interrupts!(struct Irqs {
+//!     GPIO2 => callback::InterruptHandler;
+//! });
+//!
+//! fn pressed() {
+//!     // ...
+//! }
+//!
+//! let mut button = pins.p2_6.into_pull_up_input();
+//! button.set_callback(Trigger::FallingEdge, Some(pressed));
+//! ```
+use super::wait::Trigger;
+use super::{GpioPin, GpioSelect};
+
+pub use super::wait::InterruptHandler;
+
+/// The callback of a pin, if it has one.
+type Slot = Option<fn()>;
+
+/// The callback of every pin. A slot is only written while its pin's interrupt is
+/// disabled, and only read by the interrupt handler while it is enabled.
+static mut CALLBACKS: [[Slot; 32]; 3] = [[None; 32]; 3];
+
+/// The callback of `pin` of `port`, for the interrupt handler.
+pub(super) unsafe fn callback(port: GpioSelect, pin: usize) -> Slot {
+    CALLBACKS[port as usize][pin]
+}
+
+impl GpioPin {
+    /// # Set Callback
+    /// Call `callback` from `InterruptHandler` every time the pin sees `trigger`,
+    /// enabling the pin's interrupt. `None` disables the interrupt.
+    ///
+    /// A level `trigger` (`High` or `Low`) calls back once: the pin's interrupt is
+    /// disabled before the callback runs, as the level would otherwise keep firing it.
+    /// Call `listen` (or `set_callback` again) to re-arm it once the level is gone.
+    pub fn set_callback(&self, trigger: Trigger, callback: Option<fn()>) {
+        self.unlisten();
+        unsafe { CALLBACKS[self.get_port() as usize][self.get_pin()] = callback };
+
+        if callback.is_some() {
+            self.listen(trigger);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::gpio::registers::{self, rro};
+    use crate::interrupt::typelevel::{Handler, GPIO0, GPIO1, GPIO2};
+    use core::future::Future;
+    use core::sync::atomic::{AtomicU32, Ordering};
+    use core::task::{Context, Poll, Waker};
+
+    static FIRST: AtomicU32 = AtomicU32::new(0);
+    static SECOND: AtomicU32 = AtomicU32::new(0);
+
+    fn first() {
+        FIRST.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn second() {
+        SECOND.fetch_add(1, Ordering::Relaxed);
+    }
+
+    static MIXED: AtomicU32 = AtomicU32::new(0);
+
+    fn mixed() {
+        MIXED.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn calls_every_pin_that_fired() {
+        crate::gpio::reset_mock();
+        let base = registers::GPIO_1;
+        let a = unsafe { GpioPin::steal(GpioSelect::Gpio1, 2) };
+        let b = unsafe { GpioPin::steal(GpioSelect::Gpio1, 7) };
+
+        a.set_callback(Trigger::RisingEdge, Some(first));
+        b.set_callback(Trigger::AnyEdge, Some(second));
+        assert_eq!(crate::mock::read(rro::GPIO_DUALEDGE + base), 1 << 7);
+
+        assert_eq!(
+            crate::mock::read(rro::GPIO_INTEN + base),
+            (1 << 2) | (1 << 7)
+        );
+        // Pin 3 has no interrupt enabled, its flag is left alone
+        crate::mock::write(rro::GPIO_INTFL + base, (1 << 2) | (1 << 3) | (1 << 7));
+        unsafe { <InterruptHandler as Handler<GPIO1>>::on_interrupt() };
+
+        assert_eq!(FIRST.load(Ordering::Relaxed), 1);
+        assert_eq!(SECOND.load(Ordering::Relaxed), 1);
+        assert_eq!(
+            crate::mock::read(rro::GPIO_INTFL_CLR + base),
+            (1 << 2) | (1 << 7)
+        );
+    }
+
+    #[test]
+    fn callbacks_and_waits_share_a_port() {
+        crate::gpio::reset_mock();
+        let base = registers::GPIO_2;
+        let inten = rro::GPIO_INTEN + base;
+        let button = unsafe { GpioPin::steal(GpioSelect::Gpio2, 4) };
+        let mut waited = unsafe { GpioPin::steal(GpioSelect::Gpio2, 5) };
+
+        button.set_callback(Trigger::FallingEdge, Some(mixed));
+        let mut future = waited.wait_for(Trigger::RisingEdge);
+        let mut cx = Context::from_waker(Waker::noop());
+        assert_eq!(
+            core::pin::Pin::new(&mut future).poll(&mut cx),
+            Poll::Pending
+        );
+
+        assert_eq!(crate::mock::read(inten), (1 << 4) | (1 << 5));
+        crate::mock::write(rro::GPIO_INTFL + base, (1 << 4) | (1 << 5));
+        unsafe { <InterruptHandler as Handler<GPIO2>>::on_interrupt() };
+
+        // The callback pin stays enabled, only the waited pin is disabled
+        assert_eq!(MIXED.load(Ordering::Relaxed), 1);
+        assert_eq!(crate::mock::read(rro::GPIO_INTEN_CLR + base), 1 << 5);
+        assert_eq!(crate::mock::read(inten), 1 << 4);
+        assert_eq!(
+            crate::mock::read(rro::GPIO_INTFL_CLR + base),
+            (1 << 4) | (1 << 5)
+        );
+
+        crate::mock::write(inten, 1 << 4);
+        crate::mock::write(rro::GPIO_INTFL + base, 0);
+        assert_eq!(
+            core::pin::Pin::new(&mut future).poll(&mut cx),
+            Poll::Ready(())
+        );
+        button.set_callback(Trigger::FallingEdge, None);
+    }
+
+    static LEVEL: AtomicU32 = AtomicU32::new(0);
+
+    fn level() {
+        LEVEL.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn level_callback_disables_its_pin() {
+        crate::gpio::reset_mock();
+        let base = registers::GPIO_0;
+        let inten = rro::GPIO_INTEN + base;
+        let alarm = unsafe { GpioPin::steal(GpioSelect::Gpio0, 9) };
+
+        alarm.set_callback(Trigger::Low, Some(level));
+        assert_eq!(crate::mock::read(inten), 1 << 9);
+
+        crate::mock::write(rro::GPIO_INTFL + base, 1 << 9);
+        unsafe { <InterruptHandler as Handler<GPIO0>>::on_interrupt() };
+
+        // Still low, so left enabled it would fire again right away
+        assert_eq!(LEVEL.load(Ordering::Relaxed), 1);
+        assert_eq!(crate::mock::read(inten), 0);
+        alarm.set_callback(Trigger::Low, None);
+    }
+}
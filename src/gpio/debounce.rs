@@ -0,0 +1,184 @@
+//! # Debounced Input
+//! A input (ie. a button or switch) sampled at a steady rate, only changing state once
+//! it read the same level for a number of samples in a row. Sample it from a timer,
+//! either a timer callback or a `SoftTimerQueue` timer, and read the settled state by
+//! polling, or have a callback run on every transition.
+//!
+//! # Example
+//! ```no_run
+//! use core::cell::RefCell;
+//! use critical_section::Mutex;
+//! use max78000_hal::gpio::debounce::DebouncedInput;
+//! use max78000_hal::gpio::pin::{Gpio2, PullUp};
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::time::ExtU32;
+//! use max78000_hal::timer::registers::TimerPrescaler;
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//! # let pins = Gpio2::take().unwrap();
+//! # let timer = Timer::new(TimerInstance::<{ mmio::TIMER_0 }>::instance())?;
+//!
+//! static BUTTON: Mutex<RefCell<Option<DebouncedInput<PullUp>>>> =
+//!     Mutex::new(RefCell::new(None));
+//!
+//! fn tick() {
+//!     critical_section::with(|cs| {
+//!         if let Some(button) = BUTTON.borrow_ref_mut(cs).as_mut() {
+//!             button.sample();
+//!         }
+//!     });
+//! }
+//!
+//! let mut button = DebouncedInput::new(pins.p2_6.into_pull_up_input().degrade(), 5);
+//! button.set_callback(Some(|transition| { /* ... */ }));
+//! critical_section::with(|cs| BUTTON.replace(cs, Some(button)));
+//!
+//! let mut timer = timer.into_continuous(TimerPrescaler::Div1024, 5.millis())?;
+//! timer.set_callback(Some(tick))?;
+//! timer.start();
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::any_pin::AnyPin;
+use super::pin::Input;
+
+/// # Transition
+/// A settled change of a debounced input.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Transition {
+    /// From low to high.
+    Rising,
+    /// From high to low.
+    Falling,
+}
+
+/// # Debounced Input
+/// A input pulled by `PULL`, debounced over `samples` samples.
+pub struct DebouncedInput<PULL> {
+    pin: AnyPin<Input<PULL>>,
+    samples: u8,
+    /// The samples read differently from `high`, in a row.
+    changing: u8,
+    /// The settled level.
+    high: bool,
+    /// The last transition not taken yet.
+    pending: Option<Transition>,
+    callback: Option<fn(Transition)>,
+}
+
+impl<PULL> DebouncedInput<PULL> {
+    /// # New
+    /// Debounce `pin`, a change settling after `samples` (at least one) equal samples.
+    /// The settled level starts as the pin reads now.
+    pub fn new(pin: AnyPin<Input<PULL>>, samples: u8) -> Self {
+        let high = pin.is_high();
+
+        Self {
+            pin,
+            samples: samples.max(1),
+            changing: 0,
+            high,
+            pending: None,
+            callback: None,
+        }
+    }
+
+    /// # Set Callback
+    /// Call `callback` from `sample` on every transition, or stop with `None`.
+    pub fn set_callback(&mut self, callback: Option<fn(Transition)>) {
+        self.callback = callback;
+    }
+
+    /// # Sample
+    /// Read the pin once, returning the transition when the input settled at a new
+    /// level. Call this at a steady rate, ie. from a timer callback.
+    pub fn sample(&mut self) -> Option<Transition> {
+        if self.pin.is_high() == self.high {
+            self.changing = 0;
+            return None;
+        }
+
+        self.changing += 1;
+        if self.changing < self.samples {
+            return None;
+        }
+
+        self.changing = 0;
+        self.high = !self.high;
+        let transition = match self.high {
+            true => Transition::Rising,
+            false => Transition::Falling,
+        };
+
+        self.pending = Some(transition);
+        if let Some(callback) = self.callback {
+            callback(transition);
+        }
+        Some(transition)
+    }
+
+    /// # Is High
+    /// Check if the input settled high.
+    pub fn is_high(&self) -> bool {
+        self.high
+    }
+
+    /// # Is Low
+    /// Check if the input settled low.
+    pub fn is_low(&self) -> bool {
+        !self.high
+    }
+
+    /// # Take Transition
+    /// Take the last transition since this was called, for polling.
+    pub fn take_transition(&mut self) -> Option<Transition> {
+        self.pending.take()
+    }
+
+    /// # Free
+    /// Give back the pin.
+    pub fn free(self) -> AnyPin<Input<PULL>> {
+        self.pin
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::gpio::pin::Gpio2;
+    use crate::gpio::registers::{self, rro};
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    static FALLING: AtomicU32 = AtomicU32::new(0);
+
+    fn on_transition(transition: Transition) {
+        if transition == Transition::Falling {
+            FALLING.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn settles_after_samples() {
+        crate::gpio::reset_mock();
+        let pins = Gpio2::take().unwrap();
+        let input = rro::GPIO_IN + registers::GPIO_2;
+
+        crate::mock::write(input, 1 << 6);
+        let mut button = DebouncedInput::new(pins.p2_6.into_pull_up_input().degrade(), 3);
+        button.set_callback(Some(on_transition));
+        assert!(button.is_high());
+
+        // Pressed, bouncing once
+        crate::mock::write(input, 0);
+        assert_eq!(button.sample(), None);
+        crate::mock::write(input, 1 << 6);
+        assert_eq!(button.sample(), None);
+        crate::mock::write(input, 0);
+        assert_eq!(button.sample(), None);
+        assert_eq!(button.sample(), None);
+        assert_eq!(button.sample(), Some(Transition::Falling));
+        assert!(button.is_low());
+        assert_eq!(FALLING.load(Ordering::Relaxed), 1);
+
+        assert_eq!(button.take_transition(), Some(Transition::Falling));
+        assert_eq!(button.take_transition(), None);
+    }
+}
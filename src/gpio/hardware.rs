@@ -50,13 +50,7 @@ MXC_GPIO_PAD_NONE, MXC_GPIO_VSSEL_VDDIO, MXC_GPIO_DRVSTR_0 };
 /// # I2C (n)
 /// Get the i2c GPIO pins for port n.
 pub fn i2c_n(port: usize) -> Option<[GpioPin; 2]> {
-    let pins = match port {
-        0 => (10, 11),
-        1 => (16, 17),
-        2 => (30, 31),
-
-        _ => panic!("Cannot have a port higher then 2"),
-    };
+    let pins = i2c_pins(port);
 
     let gpio_0 = GpioPin::new(super::GpioSelect::Gpio0, pins.0)?;
     let gpio_1 = GpioPin::new(super::GpioSelect::Gpio0, pins.1)?;
@@ -67,6 +61,31 @@ pub fn i2c_n(port: usize) -> Option<[GpioPin; 2]> {
     Some([gpio_0, gpio_1])
 }
 
+/// # Steal I2C (n)
+/// Get the i2c GPIO pins for port n, without taking ownership or configuring them.
+///
+/// # Safety
+/// The pins must already be owned by a I2C handle for port n. Dropping the returned
+/// pins releases that ownership.
+pub(crate) unsafe fn steal_i2c_n(port: usize) -> [GpioPin; 2] {
+    let pins = i2c_pins(port);
+
+    [
+        GpioPin::from_parts(super::GpioSelect::Gpio0, pins.0),
+        GpioPin::from_parts(super::GpioSelect::Gpio0, pins.1),
+    ]
+}
+
+fn i2c_pins(port: usize) -> (usize, usize) {
+    match port {
+        0 => (10, 11),
+        1 => (16, 17),
+        2 => (30, 31),
+
+        _ => panic!("Cannot have a port higher then 2"),
+    }
+}
+
 // UART 0 P0_0 Rx P0_1 Tx
 // UART 1 P0_12 Rx P0_13 Tx
 // UART 2 P1_0 Rx P1_1 Tx
@@ -75,15 +94,7 @@ pub fn i2c_n(port: usize) -> Option<[GpioPin; 2]> {
 /// # UART (n)
 /// Get the UART GPIO pins for port n.
 pub fn uart_n(port: usize) -> Option<[GpioPin; 2]> {
-    // (Rx, Tx, GPIO_port)
-    let pins = match port {
-        0 => (0, 1, super::GpioSelect::Gpio0),
-        1 => (12, 13, super::GpioSelect::Gpio0),
-        2 => (0, 1, super::GpioSelect::Gpio1),
-        3 => (6, 7, super::GpioSelect::Gpio2),
-
-        _ => panic!("Cannot have a port higher than 3"),
-    };
+    let pins = uart_pins(port);
 
     let gpio_rx = GpioPin::new(pins.2, pins.0)?;
     let gpio_tx = GpioPin::new(pins.2, pins.1)?;
@@ -93,3 +104,132 @@ pub fn uart_n(port: usize) -> Option<[GpioPin; 2]> {
 
     Some([gpio_rx, gpio_tx])
 }
+
+/// # Steal UART (n)
+/// Get the UART GPIO pins for port n, without taking ownership or configuring them.
+///
+/// # Safety
+/// The pins must already be owned by a UART handle for port n. Dropping the returned
+/// pins releases that ownership.
+pub(crate) unsafe fn steal_uart_n(port: usize) -> [GpioPin; 2] {
+    let pins = uart_pins(port);
+
+    [
+        GpioPin::from_parts(pins.2, pins.0),
+        GpioPin::from_parts(pins.2, pins.1),
+    ]
+}
+
+// UART 0 P0_2 CTS P0_3 RTS
+// UART 1 P0_14 CTS P0_15 RTS
+
+/// # UART Flow (n)
+/// Get the CTS and RTS GPIO pins for port n, `None` when they are owned or the port
+/// has none.
+pub fn uart_flow_n(port: usize) -> Option<[GpioPin; 2]> {
+    let (cts, rts) = match port {
+        0 => (2, 3),
+        1 => (14, 15),
+        _ => return None,
+    };
+
+    let gpio_cts = GpioPin::new(super::GpioSelect::Gpio0, cts)?;
+    let gpio_rts = GpioPin::new(super::GpioSelect::Gpio0, rts)?;
+
+    gpio_cts.configure_input(super::ResistorStrength::None, super::PinFunction::AF1);
+    gpio_rts.configure_input(super::ResistorStrength::None, super::PinFunction::AF1);
+
+    Some([gpio_cts, gpio_rts])
+}
+
+// SPI 0 P0_7 SCK P0_5 MOSI P0_6 MISO, SS0 P0_4 AF1, SS1 P0_11 AF2, SS2 P0_10 AF2
+//       P0_8 SDIO2 P0_9 SDIO3 AF1
+// SPI 1 P0_23 SCK P0_21 MOSI P0_22 MISO, SS0 P0_20 AF1
+//       P0_24 SDIO2 P0_25 SDIO3 AF1
+
+/// # SPI (n)
+/// Get the SCK, MOSI and MISO GPIO pins for SPI port n.
+pub fn spi_n(port: usize) -> Option<[GpioPin; 3]> {
+    let (sck, mosi, miso) = spi_pins(port);
+
+    let gpio_sck = GpioPin::new(super::GpioSelect::Gpio0, sck)?;
+    let gpio_mosi = GpioPin::new(super::GpioSelect::Gpio0, mosi)?;
+    let gpio_miso = GpioPin::new(super::GpioSelect::Gpio0, miso)?;
+
+    for pin in [&gpio_sck, &gpio_mosi, &gpio_miso] {
+        pin.configure_input(super::ResistorStrength::None, super::PinFunction::AF1);
+    }
+
+    Some([gpio_sck, gpio_mosi, gpio_miso])
+}
+
+/// # Steal SPI (n)
+/// Get the SCK, MOSI and MISO GPIO pins for SPI port n, without taking ownership or
+/// configuring them.
+///
+/// # Safety
+/// The pins must already be owned by a SPI handle for port n. Dropping the returned
+/// pins releases that ownership.
+pub(crate) unsafe fn steal_spi_n(port: usize) -> [GpioPin; 3] {
+    let (sck, mosi, miso) = spi_pins(port);
+
+    [
+        GpioPin::from_parts(super::GpioSelect::Gpio0, sck),
+        GpioPin::from_parts(super::GpioSelect::Gpio0, mosi),
+        GpioPin::from_parts(super::GpioSelect::Gpio0, miso),
+    ]
+}
+
+fn spi_pins(port: usize) -> (usize, usize, usize) {
+    // (SCK, MOSI, MISO)
+    match port {
+        0 => (7, 5, 6),
+        1 => (23, 21, 22),
+
+        _ => panic!("Cannot have a port higher than 1"),
+    }
+}
+
+/// # SPI Quad (n)
+/// Get the SDIO2 and SDIO3 GPIO pins for SPI port n, the data lines quad mode adds to
+/// MOSI and MISO.
+pub fn spi_quad_n(port: usize) -> Option<[GpioPin; 2]> {
+    let (sdio2, sdio3) = match port {
+        0 => (8, 9),
+        1 => (24, 25),
+        _ => panic!("Cannot have a port higher than 1"),
+    };
+
+    let gpio_sdio2 = GpioPin::new(super::GpioSelect::Gpio0, sdio2)?;
+    let gpio_sdio3 = GpioPin::new(super::GpioSelect::Gpio0, sdio3)?;
+
+    for pin in [&gpio_sdio2, &gpio_sdio3] {
+        pin.configure_input(super::ResistorStrength::None, super::PinFunction::AF1);
+    }
+
+    Some([gpio_sdio2, gpio_sdio3])
+}
+
+/// # SPI Slave Select Pin
+/// The pin and alternate function of slave select `ss` of SPI port n, if it has one.
+pub fn spi_slave_select_pin(port: usize, ss: usize) -> Option<(usize, super::PinFunction)> {
+    match (port, ss) {
+        (0, 0) => Some((4, super::PinFunction::AF1)),
+        (0, 1) => Some((11, super::PinFunction::AF2)),
+        (0, 2) => Some((10, super::PinFunction::AF2)),
+        (1, 0) => Some((20, super::PinFunction::AF1)),
+        _ => None,
+    }
+}
+
+fn uart_pins(port: usize) -> (usize, usize, super::GpioSelect) {
+    // (Rx, Tx, GPIO_port)
+    match port {
+        0 => (0, 1, super::GpioSelect::Gpio0),
+        1 => (12, 13, super::GpioSelect::Gpio0),
+        2 => (0, 1, super::GpioSelect::Gpio1),
+        3 => (6, 7, super::GpioSelect::Gpio2),
+
+        _ => panic!("Cannot have a port higher than 3"),
+    }
+}
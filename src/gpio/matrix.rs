@@ -0,0 +1,216 @@
+//! # Keypad Matrix
+//! Scan a matrix of keys, with a key at every crossing of a row and a column. The rows
+//! are open-drain outputs and the columns pulled up inputs, so every scan pulls one row
+//! low at a time and reads which columns it pulls low through a pressed key. Open-drain
+//! rows keep two keys pressed in the same column from shorting two driven rows.
+//!
+//! A key only changes state once it read the same for `debounce` scans in a row, then a
+//! `KeyEvent` is queued. Call `scan` at a steady rate (ie. every 5 ms from a timer
+//! interrupt), and take the events with `next_event`.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::gpio::matrix::{KeyEvent, KeypadScanner};
+//! use max78000_hal::gpio::pin::{Floating, Gpio0};
+//!
+//! let pins = Gpio0::take().unwrap();
+//! let rows = [
+//!     pins.p0_5.into_open_drain_output::<Floating>().degrade(),
+//!     pins.p0_6.into_open_drain_output::<Floating>().degrade(),
+//! ];
+//! let columns = [
+//!     pins.p0_8.into_pull_up_input().degrade(),
+//!     pins.p0_9.into_pull_up_input().degrade(),
+//! ];
+//! let mut keypad: KeypadScanner<2, 2> = KeypadScanner::new(rows, columns, 4);
+//!
+//! keypad.scan();
+//! while let Some(KeyEvent::Pressed(key)) = keypad.next_event() {
+//!     // ...
+//! }
+//! ```
+use super::any_pin::AnyPin;
+use super::pin::{Floating, Input, OpenDrain, PullUp};
+
+/// # Row
+/// A row pin, a open-drain output without pull resistor.
+pub type Row = AnyPin<OpenDrain<Floating>>;
+
+/// # Column
+/// A column pin, a input pulled up.
+pub type Column = AnyPin<Input<PullUp>>;
+
+/// # Key
+/// A key of the matrix, by its row and column.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Key {
+    pub row: usize,
+    pub column: usize,
+}
+
+/// # Key Event
+/// A key changing state, after debouncing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KeyEvent {
+    Pressed(Key),
+    Released(Key),
+}
+
+/// # Keypad Scanner
+/// A matrix of `ROWS` by `COLUMNS` keys, queueing up to `QUEUE` events.
+pub struct KeypadScanner<const ROWS: usize, const COLUMNS: usize, const QUEUE: usize = 16> {
+    rows: [Row; ROWS],
+    columns: [Column; COLUMNS],
+    debounce: u8,
+    /// The debounced state of every key.
+    pressed: [[bool; COLUMNS]; ROWS],
+    /// The scans every key has read differently from its state, in a row.
+    changing: [[u8; COLUMNS]; ROWS],
+    events: [Option<KeyEvent>; QUEUE],
+    head: usize,
+    len: usize,
+    dropped: usize,
+}
+
+impl<const ROWS: usize, const COLUMNS: usize, const QUEUE: usize>
+    KeypadScanner<ROWS, COLUMNS, QUEUE>
+{
+    /// # New
+    /// Scan the keys between `rows` and `columns`, a key changing after `debounce` (at
+    /// least one) equal scans. Every row is released.
+    pub fn new(mut rows: [Row; ROWS], columns: [Column; COLUMNS], debounce: u8) -> Self {
+        rows.iter_mut().for_each(Row::set_high);
+
+        Self {
+            rows,
+            columns,
+            debounce: debounce.max(1),
+            pressed: [[false; COLUMNS]; ROWS],
+            changing: [[0; COLUMNS]; ROWS],
+            events: [None; QUEUE],
+            head: 0,
+            len: 0,
+            dropped: 0,
+        }
+    }
+
+    /// # Scan
+    /// Read every key once, queueing a event for every key that settled in a new state.
+    pub fn scan(&mut self) {
+        for row in 0..ROWS {
+            self.rows[row].set_low();
+            for column in 0..COLUMNS {
+                let down = self.columns[column].is_low();
+                self.sample(Key { row, column }, down);
+            }
+            self.rows[row].set_high();
+        }
+    }
+
+    /// # Next Event
+    /// Take the oldest queued event.
+    pub fn next_event(&mut self) -> Option<KeyEvent> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let event = self.events[self.head].take();
+        self.head = (self.head + 1) % QUEUE;
+        self.len -= 1;
+        event
+    }
+
+    /// # Is Pressed
+    /// Check if `key` is pressed, after debouncing.
+    pub fn is_pressed(&self, key: Key) -> bool {
+        self.pressed[key.row][key.column]
+    }
+
+    /// # Dropped
+    /// The events lost since the queue was full, take them more often when this grows.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// # Free
+    /// Give back the row and column pins, the rows released.
+    pub fn free(self) -> ([Row; ROWS], [Column; COLUMNS]) {
+        (self.rows, self.columns)
+    }
+
+    /// # Sample
+    /// Debounce one reading of `key`.
+    fn sample(&mut self, key: Key, down: bool) {
+        let changing = &mut self.changing[key.row][key.column];
+        if down == self.pressed[key.row][key.column] {
+            *changing = 0;
+            return;
+        }
+
+        *changing += 1;
+        if *changing < self.debounce {
+            return;
+        }
+
+        *changing = 0;
+        self.pressed[key.row][key.column] = down;
+        self.push(match down {
+            true => KeyEvent::Pressed(key),
+            false => KeyEvent::Released(key),
+        });
+    }
+
+    /// # Push
+    /// Queue `event`, dropping it when the queue is full.
+    fn push(&mut self, event: KeyEvent) {
+        if self.len == QUEUE {
+            self.dropped += 1;
+            return;
+        }
+
+        self.events[(self.head + self.len) % QUEUE] = Some(event);
+        self.len += 1;
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::gpio::pin::Gpio1;
+    use crate::gpio::registers::{self, rro};
+
+    #[test]
+    fn debounces_into_events() {
+        crate::gpio::reset_mock();
+        let pins = Gpio1::take().unwrap();
+        let input = rro::GPIO_IN + registers::GPIO_1;
+
+        let rows = [pins.p1_0.into_open_drain_output::<Floating>().degrade()];
+        let columns = [
+            pins.p1_4.into_pull_up_input().degrade(),
+            pins.p1_5.into_pull_up_input().degrade(),
+        ];
+        let mut keypad: KeypadScanner<1, 2, 2> = KeypadScanner::new(rows, columns, 3);
+        let key = Key { row: 0, column: 1 };
+
+        // Column 1 pulled low, with a bounce
+        crate::mock::write(input, 1 << 4);
+        keypad.scan();
+        keypad.scan();
+        crate::mock::write(input, (1 << 4) | (1 << 5));
+        keypad.scan();
+        crate::mock::write(input, 1 << 4);
+        keypad.scan();
+        keypad.scan();
+        assert_eq!(keypad.next_event(), None);
+        keypad.scan();
+        assert!(keypad.is_pressed(key));
+        assert_eq!(keypad.next_event(), Some(KeyEvent::Pressed(key)));
+
+        crate::mock::write(input, (1 << 4) | (1 << 5));
+        (0..3).for_each(|_| keypad.scan());
+        assert_eq!(keypad.next_event(), Some(KeyEvent::Released(key)));
+        assert_eq!(keypad.next_event(), None);
+        assert_eq!(keypad.dropped(), 0);
+    }
+}
@@ -1,11 +1,21 @@
+use core::mem::ManuallyDrop;
+
+pub mod any_pin;
+pub mod callback;
+pub mod debounce;
 pub mod hardware;
+pub mod matrix;
 mod ownership;
+pub mod pin;
+pub mod port;
 pub mod registers;
+pub mod wait;
+pub mod wakeup;
 
 /// # GPIO Select
 /// Select a GPIO port.
 #[repr(u8)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum GpioSelect {
     Gpio0 = 0,
     Gpio1 = 1,
@@ -42,6 +52,28 @@ pub enum OutputDriveStrength {
     Strength2(VoltageSelect),
 }
 
+/// # Slew Rate
+/// How fast a output switches, slow limits the ringing and emissions of a pin.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SlewRate {
+    Fast,
+    Slow,
+}
+
+impl OutputDriveStrength {
+    /// The bits of `GPIO_DS1`, `GPIO_DS0` and `GPIO_VSSEL` for the strength.
+    fn bits(&self) -> (bool, bool, bool) {
+        let (ds_ctrl1, ds_ctrl0, v_sel) = match self {
+            OutputDriveStrength::Strength0(setting) => (false, false, setting),
+            OutputDriveStrength::Strength1(setting) => (false, true, setting),
+            OutputDriveStrength::Strength2(setting) => (true, true, setting),
+        };
+
+        (ds_ctrl1, ds_ctrl0, matches!(v_sel, VoltageSelect::VddIOH))
+    }
+}
+
+#[derive(Copy, Clone)]
 pub enum PinFunction {
     AF1,
     AF2,
@@ -52,21 +84,38 @@ pub struct GpioPin(u8);
 
 impl GpioPin {
     pub fn new(port: GpioSelect, pin: usize) -> Option<Self> {
-        let port_number = port as u8;
-        let pin_number = pin as u8;
-        let combined_number = (port_number << 6) | (pin_number & 0x3F);
-
-        let gpio = Self(combined_number);
+        let gpio = Self::from_parts(port, pin);
 
-        if ownership::is_owned(&gpio) {
-            None
-        } else {
-            ownership::set_owned(&gpio);
+        if ownership::claim_pin(&gpio) {
             unsafe { gpio.set_bit(registers::rro::GPIO_INEN, true) };
             Some(gpio)
+        } else {
+            None
         }
     }
 
+    /// # Steal
+    /// Conjure a handle to a pin without checking, or taking, ownership of it. Meant for
+    /// interrupt handlers and panic paths that need a pin which was already moved
+    /// elsewhere.
+    ///
+    /// The handle is wrapped in `ManuallyDrop`, so dropping it never releases the
+    /// ownership held by the real owner.
+    ///
+    /// # Safety
+    /// The caller must ensure the stolen handle does not race with the real owner, ie.
+    /// the owner is preempted by (or will never run again after) the code using it.
+    pub unsafe fn steal(port: GpioSelect, pin: usize) -> ManuallyDrop<Self> {
+        ManuallyDrop::new(Self::from_parts(port, pin))
+    }
+
+    fn from_parts(port: GpioSelect, pin: usize) -> Self {
+        let port_number = port as u8;
+        let pin_number = pin as u8;
+
+        Self((port_number << 6) | (pin_number & 0x3F))
+    }
+
     #[inline]
     pub fn get_port(&self) -> GpioSelect {
         let number = self.0 >> 6;
@@ -127,8 +176,30 @@ impl GpioPin {
         }
     }
 
+    /// # Set Output
+    /// Drive this pin high (`true`) or low, with a single write that leaves the other
+    /// pins of the port alone.
     pub fn set_output(&self, output_enable: bool) {
-        unsafe { self.set_bit(registers::rro::GPIO_OUT, output_enable) };
+        unsafe {
+            registers::write_pins(self.get_port().into(), 1 << self.get_pin(), output_enable)
+        };
+    }
+
+    /// # Toggle Output
+    /// Drive this pin to the other level. There is no toggle register, so this reads
+    /// the level first, it is only atomic against writes to other pins.
+    pub fn toggle_output(&self) {
+        self.set_output(!self.get_output());
+    }
+
+    /// # Get Output
+    /// Read back the level this pin is being driven to.
+    pub fn get_output(&self) -> bool {
+        unsafe {
+            (registers::read_gpio(registers::rro::GPIO_OUT, self.get_port().into())
+                & (1 << self.get_pin()))
+                != 0
+        }
     }
 
     pub fn get_input(&self) -> bool {
@@ -159,16 +230,7 @@ impl GpioPin {
     }
 
     pub fn configure_output(&self, strength: OutputDriveStrength, function: PinFunction) {
-        let (ds_ctrl1, ds_ctrl0, v_sel) = match strength {
-            OutputDriveStrength::Strength0(setting) => (false, false, setting),
-            OutputDriveStrength::Strength1(setting) => (false, true, setting),
-            OutputDriveStrength::Strength2(setting) => (true, true, setting),
-        };
-
-        let v_sel = match v_sel {
-            VoltageSelect::VddIO => false,
-            VoltageSelect::VddIOH => true,
-        };
+        let (ds_ctrl1, ds_ctrl0, v_sel) = strength.bits();
 
         self.switch_function(function, || unsafe {
             self.set_bit(registers::rro::GPIO_DS1, ds_ctrl1);
@@ -179,10 +241,83 @@ impl GpioPin {
         });
     }
 
+    /// # Configure Open Drain
+    /// Make the pin a open-drain output, with `res` pulling it up (or not). There is no
+    /// open-drain driver, so the pin drives low with its output enabled, and floats
+    /// with it disabled. It starts released, reading as the line is.
+    pub fn configure_open_drain(&self, res: ResistorStrength) {
+        self.set_output(false);
+        self.configure_input(res, PinFunction::IO);
+    }
+
+    /// # Set Open Drain
+    /// Release the open-drain pin (`true`), or pull it low (`false`).
+    pub fn set_open_drain(&self, high: bool) {
+        let register = if high {
+            registers::rro::GPIO_OUTEN_CLR
+        } else {
+            registers::rro::GPIO_OUTEN_SET
+        };
+        unsafe { registers::write_gpio(register, self.get_port().into(), 1 << self.get_pin()) };
+    }
+
+    /// # Is Open Drain Released
+    /// Check if the open-drain pin is released, rather than pulled low.
+    pub fn is_open_drain_released(&self) -> bool {
+        unsafe {
+            (registers::read_gpio(registers::rro::GPIO_OUTEN, self.get_port().into())
+                & (1 << self.get_pin()))
+                == 0
+        }
+    }
+
+    /// # Set Drive Strength
+    /// Change the drive strength and supply of the pin, without reconfiguring it.
+    pub fn set_drive_strength(&self, strength: OutputDriveStrength) {
+        let (ds_ctrl1, ds_ctrl0, v_sel) = strength.bits();
+
+        unsafe {
+            self.set_bit(registers::rro::GPIO_DS1, ds_ctrl1);
+            self.set_bit(registers::rro::GPIO_DS0, ds_ctrl0);
+            self.set_bit(registers::rro::GPIO_VSSEL, v_sel);
+        }
+    }
+
+    /// # Set Voltage
+    /// Power the pin from VDDIO or VDDIOH, setting the levels it drives and reads at.
+    pub fn set_voltage(&self, voltage: VoltageSelect) {
+        let v_sel = matches!(voltage, VoltageSelect::VddIOH);
+        unsafe { self.set_bit(registers::rro::GPIO_VSSEL, v_sel) };
+    }
+
+    /// # Set Slew Rate
+    /// Change how fast the pin switches when driven.
+    pub fn set_slew_rate(&self, rate: SlewRate) {
+        unsafe { self.set_bit(registers::rro::GPIO_SRSEL, rate == SlewRate::Slow) };
+    }
+
+    /// # Set Hysteresis
+    /// Enable the input's Schmitt trigger, so a slow or noisy edge reads as one change.
+    pub fn set_hysteresis(&self, enable: bool) {
+        unsafe { self.set_bit(registers::rro::GPIO_HYSEN, enable) };
+    }
+
+    /// # Raw Output Enable
+    /// Enable the output driver of this pin without touching any other pin configuration.
+    ///
+    /// # Safety
+    /// The caller must ensure the pin's function and pad configuration are valid for
+    /// driving an output, since no other registers are updated.
     pub unsafe fn raw_output_enable(&self) {
         self.set_bit(registers::rro::GPIO_OUTEN_SET, true);
     }
 
+    /// # Raw Input Enable
+    /// Enable the input buffer of this pin without touching any other pin configuration.
+    ///
+    /// # Safety
+    /// The caller must ensure the pin's function and pad configuration are valid for
+    /// reading an input, since no other registers are updated.
     pub unsafe fn raw_input_enable(&self) {
         self.set_bit(registers::rro::GPIO_INEN, true);
     }
@@ -193,3 +328,108 @@ impl Drop for GpioPin {
         ownership::disown_pin(self);
     }
 }
+
+#[cfg(feature = "eh0")]
+impl embedded_hal_0_2::digital::v2::OutputPin for GpioPin {
+    type Error = core::convert::Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_output(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_output(true);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl embedded_hal_0_2::digital::v2::StatefulOutputPin for GpioPin {
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.get_output())
+    }
+
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+        Ok(!self.get_output())
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl embedded_hal_0_2::digital::v2::toggleable::Default for GpioPin {}
+
+#[cfg(feature = "eh0")]
+impl embedded_hal_0_2::digital::v2::InputPin for GpioPin {
+    type Error = core::convert::Infallible;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.get_input())
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(!self.get_input())
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl embedded_hal::digital::ErrorType for GpioPin {
+    type Error = core::convert::Infallible;
+}
+
+#[cfg(feature = "eh1")]
+impl embedded_hal::digital::OutputPin for GpioPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set_output(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set_output(true);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl embedded_hal::digital::StatefulOutputPin for GpioPin {
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.get_output())
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.get_output())
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl embedded_hal::digital::InputPin for GpioPin {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.get_input())
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.get_input())
+    }
+}
+
+/// # Reset Mock
+/// Reset the mock, and model the atomic set and clear registers of every port in it, so
+/// writing `GPIO_OUT_SET` (and the like) lands in `GPIO_OUT` like on the hardware.
+#[cfg(all(test, feature = "mock-mmio"))]
+pub(crate) fn reset_mock() {
+    use registers::rro;
+
+    crate::mock::reset();
+    for port in [registers::GPIO_0, registers::GPIO_1, registers::GPIO_2] {
+        for (set, clear, target) in [
+            (rro::GPIO_EN0_SET, rro::GPIO_EN0_CLR, rro::GPIO_EN0),
+            (rro::GPIO_OUTEN_SET, rro::GPIO_OUTEN_CLR, rro::GPIO_OUTEN),
+            (rro::GPIO_OUT_SET, rro::GPIO_OUT_CLR, rro::GPIO_OUT),
+            (rro::GPIO_INTEN_SET, rro::GPIO_INTEN_CLR, rro::GPIO_INTEN),
+            (rro::GPIO_WKEN_SET, rro::GPIO_WKEN_CLR, rro::GPIO_WKEN),
+            (rro::GPIO_EN1_SET, rro::GPIO_EN1_CLR, rro::GPIO_EN1),
+            (rro::GPIO_EN2_SET, rro::GPIO_EN2_CLR, rro::GPIO_EN2),
+        ] {
+            crate::mock::register_atomic(set + port, clear + port, target + port);
+        }
+    }
+}
@@ -1,24 +1,64 @@
-use crate::bits::BitManipulation;
+use core::sync::atomic::{AtomicU32, Ordering};
 
-use super::GpioPin;
+use super::{GpioPin, GpioSelect};
 
-static mut PINS_OWNED: [u32; 4] = [0_u32; 4];
+/// One bitmap of owned pins per port, changed atomically so two owners can't both
+/// see a pin free and take it.
+#[cfg(not(test))]
+static PINS_OWNED: [AtomicU32; 4] = [const { AtomicU32::new(0) }; 4];
+
+// Tests run in parallel threads, like the mock register file every test thread owns
+// its own pins, so taking a whole port in one test does not fail another.
+#[cfg(test)]
+extern crate std;
+
+#[cfg(test)]
+std::thread_local! {
+    static PINS_OWNED: [AtomicU32; 4] = const { [const { AtomicU32::new(0) }; 4] };
+}
+
+/// Run `f` on the bitmap of owned pins of `port`.
+#[cfg(not(test))]
+fn with_owned<R>(port: usize, f: impl FnOnce(&AtomicU32) -> R) -> R {
+    f(&PINS_OWNED[port])
+}
+
+/// Run `f` on the bitmap of owned pins of `port`.
+#[cfg(test)]
+fn with_owned<R>(port: usize, f: impl FnOnce(&AtomicU32) -> R) -> R {
+    PINS_OWNED.with(|owned| f(&owned[port]))
+}
 
 fn pin_mode(pin: &GpioPin) -> (usize, usize) {
     (pin.get_port() as u8 as usize, pin.get_pin())
 }
 
-pub fn is_owned(pin: &GpioPin) -> bool {
-    let (port, pin) = pin_mode(pin);
-    unsafe { PINS_OWNED[port].get_bit(pin as u8) }
+/// Take `pin`, if it is not owned yet.
+pub fn claim_pin(pin: &GpioPin) -> bool {
+    claim_mask(pin.get_port(), 1 << pin.get_pin())
 }
 
-pub fn set_owned(pin: &GpioPin) {
+pub fn disown_pin(pin: &GpioPin) {
     let (port, pin) = pin_mode(pin);
-    unsafe { PINS_OWNED[port].set_bit(pin as u8, true) };
+    with_owned(port, |owned| {
+        owned.fetch_and(!(1 << pin), Ordering::Release)
+    });
 }
 
-pub fn disown_pin(pin: &GpioPin) {
-    let (port, pin) = pin_mode(pin);
-    unsafe { PINS_OWNED[port].set_bit(pin as u8, false) };
+/// Take the pins in `mask` of `port`, if none of them are owned yet.
+pub fn claim_mask(port: GpioSelect, mask: u32) -> bool {
+    with_owned(port as usize, |owned| {
+        owned
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |owned| {
+                (owned & mask == 0).then_some(owned | mask)
+            })
+            .is_ok()
+    })
+}
+
+/// Give back the pins in `mask` of `port`.
+pub fn disown_mask(port: GpioSelect, mask: u32) {
+    with_owned(port as usize, |owned| {
+        owned.fetch_and(!mask, Ordering::Release)
+    });
 }
@@ -0,0 +1,923 @@
+//! # Typed Pins
+//! Pins with their port, number and mode in their type, ie. `Pin<0, 5, Output>` is
+//! P0.5 driven as a output. A port's pins are taken all at once with `Gpio0::take`
+//! (and `Gpio1`, `Gpio2`), which claims them from the same ownership as `GpioPin`. After
+//! that every pin exists once, and changing its mode consumes it, so the same pin can
+//! not be configured twice.
+//!
+//! P3.0 and P3.1 are not on a GPIO port, they are controlled by the MCR.
+//!
+//! `into_alternate` hands a pin to a peripheral through its pin mux. The pins each
+//! peripheral can use implement its pin trait (ie. `TimerOutput<{ mmio::TIMER_0 }>`)
+//! once on the right alternate function, so peripheral constructors can take them.
+//!
+//! The electrical settings (drive strength, supply, slew rate and hysteresis) chain on
+//! after the mode, with the `with_` methods.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::gpio::pin::{Af2, Gpio0, Gpio2};
+//! use max78000_hal::gpio::SlewRate;
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::time::RateExtU32;
+//! use max78000_hal::timer::registers::TimerPrescaler;
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//! # let timer = Timer::new(TimerInstance::<{ mmio::TIMER_0 }>::instance())?;
+//!
+//! let pins = Gpio2::take().unwrap();
+//! let mut led = pins
+//!     .p2_0
+//!     .into_push_pull_output()
+//!     .with_slew_rate(SlewRate::Slow);
+//! let button = pins.p2_6.into_pull_up_input();
+//!
+//! if button.is_low() {
+//!     led.set_high();
+//! }
+//!
+//! let pins = Gpio0::take().unwrap();
+//! let output = pins.p0_2.into_alternate::<Af2>();
+//! let (pwm, output) = timer.into_pwm_with_pin(TimerPrescaler::Div1, 50.kHz(), output)?;
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+
+use super::wait::{Trigger, WaitFuture};
+use super::{
+    ownership, GpioPin, GpioSelect, OutputDriveStrength, PinFunction, ResistorStrength, SlewRate,
+    VoltageSelect,
+};
+use crate::i2c::{I2CPort0, I2CPort1, I2CPort2};
+use crate::memory_map::mmio;
+use crate::uart::{UART0, UART1, UART2, UART3};
+
+/// # Unconfigured
+/// A pin as it was when taken, in whatever mode it was left in.
+pub struct Unconfigured;
+
+/// # Input
+/// A input, with `PULL` resistor.
+pub struct Input<PULL> {
+    _pull: PhantomData<PULL>,
+}
+
+/// # Floating
+/// No pull resistor.
+pub struct Floating;
+
+/// # Pull Up
+/// The weak pull-up resistor.
+pub struct PullUp;
+
+/// # Pull Down
+/// The weak pull-down resistor.
+pub struct PullDown;
+
+/// # Strong Pull Up
+/// The strong pull-up resistor.
+pub struct StrongPullUp;
+
+/// # Output
+/// A push-pull output.
+pub struct Output;
+
+/// # Open Drain
+/// A output only ever pulling low, released it floats or is pulled up by `PULL`. It
+/// reads the level of the line, so another device can pull it low too.
+pub struct OpenDrain<PULL> {
+    _pull: PhantomData<PULL>,
+}
+
+/// # Open Drain Pull
+/// The pull-up resistors of a open-drain output.
+pub trait OpenDrainPull: private::Sealed {
+    /// The resistor to configure the pin with.
+    const RESISTOR: ResistorStrength;
+}
+
+impl OpenDrainPull for Floating {
+    const RESISTOR: ResistorStrength = ResistorStrength::None;
+}
+
+impl OpenDrainPull for PullUp {
+    const RESISTOR: ResistorStrength = ResistorStrength::WeakPullup;
+}
+
+impl OpenDrainPull for StrongPullUp {
+    const RESISTOR: ResistorStrength = ResistorStrength::StrongPullup;
+}
+
+/// # Alternate
+/// Connected to a peripheral through alternate function `AF`.
+pub struct Alternate<AF> {
+    _function: PhantomData<AF>,
+}
+
+/// # Alternate Function 1
+pub struct Af1;
+
+/// # Alternate Function 2
+pub struct Af2;
+
+/// # Alternate Function
+/// A alternate function of the pin mux.
+pub trait AlternateFunction: private::Sealed {
+    /// The function to configure the pin for.
+    const FUNCTION: PinFunction;
+}
+
+impl AlternateFunction for Af1 {
+    const FUNCTION: PinFunction = PinFunction::AF1;
+}
+
+impl AlternateFunction for Af2 {
+    const FUNCTION: PinFunction = PinFunction::AF2;
+}
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for super::Floating {}
+    impl Sealed for super::PullUp {}
+    impl Sealed for super::StrongPullUp {}
+    impl Sealed for super::Af1 {}
+    impl Sealed for super::Af2 {}
+}
+
+/// # Pin
+/// Pin `N` of GPIO port `PORT`, in `MODE`.
+pub struct Pin<const PORT: usize, const N: usize, MODE = Unconfigured> {
+    _mode: PhantomData<MODE>,
+}
+
+impl<const PORT: usize, const N: usize, MODE> Pin<PORT, N, MODE> {
+    const VALID: () = assert!(PORT < 3 && N < 32, "Not a GPIO pin");
+
+    /// Make a pin, only done once per pin, by `take`.
+    const fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::VALID;
+        Self { _mode: PhantomData }
+    }
+
+    /// The untyped pin, without its ownership.
+    pub(super) fn raw(&self) -> ManuallyDrop<GpioPin> {
+        let port = match PORT {
+            0 => GpioSelect::Gpio0,
+            1 => GpioSelect::Gpio1,
+            _ => GpioSelect::Gpio2,
+        };
+        ManuallyDrop::new(GpioPin::from_parts(port, N))
+    }
+
+    /// Change the type to `NEW`, after the pin was configured for it.
+    fn into_mode<NEW>(self) -> Pin<PORT, N, NEW> {
+        Pin::new()
+    }
+
+    /// # Into Floating Input
+    /// Make the pin a input without a pull resistor.
+    pub fn into_floating_input(self) -> Pin<PORT, N, Input<Floating>> {
+        self.raw()
+            .configure_input(ResistorStrength::None, PinFunction::IO);
+        self.into_mode()
+    }
+
+    /// # Into Pull Up Input
+    /// Make the pin a input pulled up.
+    pub fn into_pull_up_input(self) -> Pin<PORT, N, Input<PullUp>> {
+        self.raw()
+            .configure_input(ResistorStrength::WeakPullup, PinFunction::IO);
+        self.into_mode()
+    }
+
+    /// # Into Pull Down Input
+    /// Make the pin a input pulled down.
+    pub fn into_pull_down_input(self) -> Pin<PORT, N, Input<PullDown>> {
+        self.raw()
+            .configure_input(ResistorStrength::WeakPulldown, PinFunction::IO);
+        self.into_mode()
+    }
+
+    /// # Into Push Pull Output
+    /// Make the pin a output, driven low.
+    pub fn into_push_pull_output(self) -> Pin<PORT, N, Output> {
+        self.into_push_pull_output_in_state(false)
+    }
+
+    /// # Into Push Pull Output In State
+    /// Make the pin a output, driven high (`true`) or low (`false`) from the start.
+    pub fn into_push_pull_output_in_state(self, high: bool) -> Pin<PORT, N, Output> {
+        let raw = self.raw();
+        raw.set_output(high);
+        raw.configure_output(
+            OutputDriveStrength::Strength0(VoltageSelect::VddIO),
+            PinFunction::IO,
+        );
+        self.into_mode()
+    }
+
+    /// # Into Open Drain Output
+    /// Make the pin a open-drain output pulled up by `PULL` (`Floating` for a external
+    /// pull-up), released.
+    pub fn into_open_drain_output<PULL: OpenDrainPull>(self) -> Pin<PORT, N, OpenDrain<PULL>> {
+        self.raw().configure_open_drain(PULL::RESISTOR);
+        self.into_mode()
+    }
+
+    /// # Into Alternate
+    /// Connect the pin to the peripheral on alternate function `AF`, which drives it.
+    pub fn into_alternate<AF: AlternateFunction>(self) -> Pin<PORT, N, Alternate<AF>> {
+        self.raw()
+            .configure_input(ResistorStrength::None, AF::FUNCTION);
+        self.into_mode()
+    }
+
+    /// # With Drive Strength
+    /// Set the drive strength and supply of the pin.
+    pub fn with_drive_strength(self, strength: OutputDriveStrength) -> Self {
+        self.raw().set_drive_strength(strength);
+        self
+    }
+
+    /// # With Voltage
+    /// Power the pin from VDDIO or VDDIOH.
+    pub fn with_voltage(self, voltage: VoltageSelect) -> Self {
+        self.raw().set_voltage(voltage);
+        self
+    }
+
+    /// # With Slew Rate
+    /// Set how fast the pin switches when driven.
+    pub fn with_slew_rate(self, rate: SlewRate) -> Self {
+        self.raw().set_slew_rate(rate);
+        self
+    }
+
+    /// # With Hysteresis
+    /// Enable or disable the input's Schmitt trigger.
+    pub fn with_hysteresis(self, enable: bool) -> Self {
+        self.raw().set_hysteresis(enable);
+        self
+    }
+
+    /// # Erase
+    /// Give up the type, for a `GpioPin` owning the pin. Dropping that gives the pin
+    /// back to the GPIO ownership.
+    pub fn erase(self) -> GpioPin {
+        ManuallyDrop::into_inner(self.raw())
+    }
+}
+
+impl<const PORT: usize, const N: usize, PULL> Pin<PORT, N, Input<PULL>> {
+    /// # Is High
+    /// Check if the pin reads high.
+    pub fn is_high(&self) -> bool {
+        self.raw().get_input()
+    }
+
+    /// # Is Low
+    /// Check if the pin reads low.
+    pub fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+
+    /// # Listen
+    /// Enable the pin's interrupt on `trigger`, and the NVIC line of its port.
+    pub fn listen(&mut self, trigger: Trigger) {
+        self.raw().listen(trigger);
+    }
+
+    /// # Unlisten
+    /// Disable the pin's interrupt.
+    pub fn unlisten(&mut self) {
+        self.raw().unlisten();
+    }
+
+    /// # Is Interrupt Pending
+    /// Check if the pin's interrupt fired.
+    pub fn is_interrupt_pending(&self) -> bool {
+        self.raw().is_interrupt_pending()
+    }
+
+    /// # Clear Interrupt
+    /// Clear the pin's interrupt flag.
+    pub fn clear_interrupt(&mut self) {
+        self.raw().clear_interrupt();
+    }
+
+    /// # Set Callback
+    /// Call `callback` from `callback::InterruptHandler` every time the pin sees
+    /// `trigger`. `None` disables the pin's interrupt. A level `trigger` calls back once,
+    /// see `GpioPin::set_callback`.
+    pub fn set_callback(&mut self, trigger: Trigger, callback: Option<fn()>) {
+        self.raw().set_callback(trigger, callback);
+    }
+
+    /// # Enable Wake-Up
+    /// Arm/Disarm the pin as a wake-up source, on the trigger it listens for.
+    pub fn enable_wakeup(&mut self, enable: bool) {
+        self.raw().enable_wakeup(enable);
+    }
+
+    /// # Is Wake-Up Pending
+    /// Check if the pin flagged a wake-up event.
+    pub fn is_wakeup_pending(&self) -> bool {
+        self.raw().is_wakeup_pending()
+    }
+
+    /// # Clear Wake-Up
+    /// Clear the pin's wake-up event flag.
+    pub fn clear_wakeup(&mut self) {
+        self.raw().clear_wakeup();
+    }
+
+    /// # Wait For
+    /// Wait until the pin sees `trigger`, with `wait::InterruptHandler` bound to its
+    /// port's interrupt.
+    pub fn wait_for(&mut self, trigger: Trigger) -> WaitFuture<'_> {
+        WaitFuture::new(self.raw(), trigger)
+    }
+}
+
+impl<const PORT: usize, const N: usize> Pin<PORT, N, Output> {
+    /// # Set High
+    /// Drive the pin high.
+    pub fn set_high(&mut self) {
+        self.raw().set_output(true);
+    }
+
+    /// # Set Low
+    /// Drive the pin low.
+    pub fn set_low(&mut self) {
+        self.raw().set_output(false);
+    }
+
+    /// # Toggle
+    /// Drive the pin to the other level.
+    pub fn toggle(&mut self) {
+        self.raw().toggle_output();
+    }
+
+    /// # Is Set High
+    /// Check if the pin is driven high.
+    pub fn is_set_high(&self) -> bool {
+        self.raw().get_output()
+    }
+
+    /// # Is Set Low
+    /// Check if the pin is driven low.
+    pub fn is_set_low(&self) -> bool {
+        !self.is_set_high()
+    }
+}
+
+impl<const PORT: usize, const N: usize, PULL> Pin<PORT, N, OpenDrain<PULL>> {
+    /// # Set High
+    /// Release the pin, it goes high unless another device pulls it low.
+    pub fn set_high(&mut self) {
+        self.raw().set_open_drain(true);
+    }
+
+    /// # Set Low
+    /// Pull the pin low.
+    pub fn set_low(&mut self) {
+        self.raw().set_open_drain(false);
+    }
+
+    /// # Toggle
+    /// Release the pin when pulled low, and the other way around.
+    pub fn toggle(&mut self) {
+        let raw = self.raw();
+        raw.set_open_drain(!raw.is_open_drain_released());
+    }
+
+    /// # Is Set High
+    /// Check if the pin is released.
+    pub fn is_set_high(&self) -> bool {
+        self.raw().is_open_drain_released()
+    }
+
+    /// # Is Set Low
+    /// Check if the pin is pulled low.
+    pub fn is_set_low(&self) -> bool {
+        !self.is_set_high()
+    }
+
+    /// # Is High
+    /// Check if the line reads high.
+    pub fn is_high(&self) -> bool {
+        self.raw().get_input()
+    }
+
+    /// # Is Low
+    /// Check if the line reads low, ie. pulled low by this or another device.
+    pub fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+}
+
+/// # Timer Output
+/// A pin carrying output A of the timer at `TIMER` (ie. `mmio::TIMER_0`).
+pub trait TimerOutput<const TIMER: usize> {}
+
+/// # Timer Output B
+/// A pin carrying output B of the timer at `TIMER`.
+pub trait TimerOutputB<const TIMER: usize> {}
+
+/// # UART Receive
+/// A pin carrying the RX line of `UART`.
+pub trait UartRx<UART> {}
+
+/// # UART Transmit
+/// A pin carrying the TX line of `UART`.
+pub trait UartTx<UART> {}
+
+/// # UART Clear To Send
+/// A pin carrying the CTS line of `UART`.
+pub trait UartCts<UART> {}
+
+/// # UART Request To Send
+/// A pin carrying the RTS line of `UART`.
+pub trait UartRts<UART> {}
+
+/// # I2C Clock
+/// A pin carrying the SCL line of `I2C`.
+pub trait I2cScl<I2C> {}
+
+/// # I2C Data
+/// A pin carrying the SDA line of `I2C`.
+pub trait I2cSda<I2C> {}
+
+macro_rules! peripheral_pins {
+    ($($function:ident: $trait:ident<$peripheral:tt> for Pin<$port:literal, $n:literal>),* $(,)?) => {
+        $(impl $trait<$peripheral> for Pin<$port, $n, Alternate<$function>> {})*
+    };
+}
+
+peripheral_pins!(
+    Af2: TimerOutput<{ mmio::TIMER_0 }> for Pin<0, 2>,
+    Af2: TimerOutput<{ mmio::TIMER_1 }> for Pin<0, 14>,
+    Af2: TimerOutput<{ mmio::TIMER_2 }> for Pin<0, 26>,
+    Af2: TimerOutput<{ mmio::TIMER_3 }> for Pin<1, 6>,
+    Af1: TimerOutput<{ mmio::LOW_POWER_TIMER_0 }> for Pin<2, 4>,
+    Af1: TimerOutput<{ mmio::LOW_POWER_TIMER_1 }> for Pin<2, 5>,
+    Af2: TimerOutputB<{ mmio::TIMER_0 }> for Pin<0, 3>,
+    Af2: TimerOutputB<{ mmio::TIMER_1 }> for Pin<0, 15>,
+    Af2: TimerOutputB<{ mmio::TIMER_2 }> for Pin<0, 27>,
+    Af2: TimerOutputB<{ mmio::TIMER_3 }> for Pin<1, 7>,
+);
+
+peripheral_pins!(
+    Af1: UartRx<UART0> for Pin<0, 0>,
+    Af1: UartTx<UART0> for Pin<0, 1>,
+    Af1: UartRx<UART1> for Pin<0, 12>,
+    Af1: UartTx<UART1> for Pin<0, 13>,
+    Af1: UartRx<UART2> for Pin<1, 0>,
+    Af1: UartTx<UART2> for Pin<1, 1>,
+    Af1: UartRx<UART3> for Pin<2, 6>,
+    Af1: UartTx<UART3> for Pin<2, 7>,
+    Af1: UartCts<UART0> for Pin<0, 2>,
+    Af1: UartRts<UART0> for Pin<0, 3>,
+    Af1: UartCts<UART1> for Pin<0, 14>,
+    Af1: UartRts<UART1> for Pin<0, 15>,
+    Af1: I2cScl<I2CPort0> for Pin<0, 10>,
+    Af1: I2cSda<I2CPort0> for Pin<0, 11>,
+    Af1: I2cScl<I2CPort1> for Pin<0, 16>,
+    Af1: I2cSda<I2CPort1> for Pin<0, 17>,
+    Af1: I2cScl<I2CPort2> for Pin<0, 30>,
+    Af1: I2cSda<I2CPort2> for Pin<0, 31>,
+);
+
+/// # Claim Port
+/// Take ownership of pins `0..count` of `port`, if none of them are owned yet.
+fn claim_port(port: GpioSelect, count: usize) -> bool {
+    ownership::claim_mask(port, u32::MAX >> (32 - count))
+}
+
+macro_rules! gpio_port {
+    ($port:ident, $select:expr, $index:literal, [$($pin:ident: $n:literal),* $(,)?]) => {
+        /// # GPIO Port
+        /// Every pin of the port, see `take`.
+        pub struct $port {
+            $(
+                #[doc = concat!("Pin ", stringify!($n), ".")]
+                pub $pin: Pin<$index, $n>,
+            )*
+        }
+
+        impl $port {
+            /// # Take
+            /// Take every pin of the port, `None` when any of them is already owned
+            /// (ie. the port was already taken).
+            pub fn take() -> Option<Self> {
+                const COUNT: usize = [$($n),*].len();
+                if !claim_port($select, COUNT) {
+                    return None;
+                }
+
+                Some(Self {
+                    $($pin: Pin::new(),)*
+                })
+            }
+        }
+    };
+}
+
+gpio_port!(Gpio0, GpioSelect::Gpio0, 0, [
+    p0_0: 0, p0_1: 1, p0_2: 2, p0_3: 3, p0_4: 4, p0_5: 5, p0_6: 6, p0_7: 7,
+    p0_8: 8, p0_9: 9, p0_10: 10, p0_11: 11, p0_12: 12, p0_13: 13, p0_14: 14, p0_15: 15,
+    p0_16: 16, p0_17: 17, p0_18: 18, p0_19: 19, p0_20: 20, p0_21: 21, p0_22: 22,
+    p0_23: 23, p0_24: 24, p0_25: 25, p0_26: 26, p0_27: 27, p0_28: 28, p0_29: 29,
+    p0_30: 30, p0_31: 31,
+]);
+
+gpio_port!(Gpio1, GpioSelect::Gpio1, 1, [
+    p1_0: 0, p1_1: 1, p1_2: 2, p1_3: 3, p1_4: 4, p1_5: 5, p1_6: 6, p1_7: 7, p1_8: 8,
+    p1_9: 9,
+]);
+
+gpio_port!(Gpio2, GpioSelect::Gpio2, 2, [
+    p2_0: 0, p2_1: 1, p2_2: 2, p2_3: 3, p2_4: 4, p2_5: 5, p2_6: 6, p2_7: 7,
+]);
+
+#[cfg(feature = "eh0")]
+impl<const PORT: usize, const N: usize> embedded_hal_0_2::digital::v2::OutputPin
+    for Pin<PORT, N, Output>
+{
+    type Error = core::convert::Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Self::set_low(self);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Self::set_high(self);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl<const PORT: usize, const N: usize> embedded_hal_0_2::digital::v2::StatefulOutputPin
+    for Pin<PORT, N, Output>
+{
+    fn is_set_high(&self) -> Result<bool, Self::Error> {
+        Ok(Self::is_set_high(self))
+    }
+
+    fn is_set_low(&self) -> Result<bool, Self::Error> {
+        Ok(Self::is_set_low(self))
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl<const PORT: usize, const N: usize> embedded_hal_0_2::digital::v2::ToggleableOutputPin
+    for Pin<PORT, N, Output>
+{
+    type Error = core::convert::Infallible;
+
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        Self::toggle(self);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl<const PORT: usize, const N: usize, PULL> embedded_hal_0_2::digital::v2::InputPin
+    for Pin<PORT, N, Input<PULL>>
+{
+    type Error = core::convert::Infallible;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(Self::is_high(self))
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(Self::is_low(self))
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<const PORT: usize, const N: usize, MODE> embedded_hal::digital::ErrorType
+    for Pin<PORT, N, MODE>
+{
+    type Error = core::convert::Infallible;
+}
+
+#[cfg(feature = "eh1")]
+impl<const PORT: usize, const N: usize> embedded_hal::digital::OutputPin for Pin<PORT, N, Output> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Self::set_low(self);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Self::set_high(self);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<const PORT: usize, const N: usize> embedded_hal::digital::StatefulOutputPin
+    for Pin<PORT, N, Output>
+{
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(Self::is_set_high(self))
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(Self::is_set_low(self))
+    }
+
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        Self::toggle(self);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<const PORT: usize, const N: usize, PULL> embedded_hal::digital::InputPin
+    for Pin<PORT, N, Input<PULL>>
+{
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(Self::is_high(self))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(Self::is_low(self))
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl<const PORT: usize, const N: usize, PULL> embedded_hal_0_2::digital::v2::OutputPin
+    for Pin<PORT, N, OpenDrain<PULL>>
+{
+    type Error = core::convert::Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Self::set_low(self);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Self::set_high(self);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl<const PORT: usize, const N: usize, PULL> embedded_hal_0_2::digital::v2::InputPin
+    for Pin<PORT, N, OpenDrain<PULL>>
+{
+    type Error = core::convert::Infallible;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(Self::is_high(self))
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(Self::is_low(self))
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<const PORT: usize, const N: usize, PULL> embedded_hal::digital::OutputPin
+    for Pin<PORT, N, OpenDrain<PULL>>
+{
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Self::set_low(self);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Self::set_high(self);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<const PORT: usize, const N: usize, PULL> embedded_hal::digital::StatefulOutputPin
+    for Pin<PORT, N, OpenDrain<PULL>>
+{
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(Self::is_set_high(self))
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(Self::is_set_low(self))
+    }
+
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        Self::toggle(self);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<const PORT: usize, const N: usize, PULL> embedded_hal::digital::InputPin
+    for Pin<PORT, N, OpenDrain<PULL>>
+{
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(Self::is_high(self))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(Self::is_low(self))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<const PORT: usize, const N: usize, PULL> embedded_hal_async::digital::Wait
+    for Pin<PORT, N, Input<PULL>>
+{
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(Trigger::High).await;
+        Ok(())
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(Trigger::Low).await;
+        Ok(())
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(Trigger::RisingEdge).await;
+        Ok(())
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(Trigger::FallingEdge).await;
+        Ok(())
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(Trigger::AnyEdge).await;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::gpio::registers::{self, rro};
+    use crate::interrupt::{Interrupt, InterruptExt};
+
+    #[test]
+    fn port_is_taken_once() {
+        crate::gpio::reset_mock();
+
+        let pins = Gpio1::take().unwrap();
+        assert!(Gpio1::take().is_none());
+        assert!(GpioPin::new(GpioSelect::Gpio1, 3).is_none());
+
+        // Erased, the pin can be taken on its own again
+        drop(pins.p1_3.erase());
+        assert!(GpioPin::new(GpioSelect::Gpio1, 3).is_some());
+
+        // A port with one pin owned is not taken, nor are any of its other pins
+        let _pin = GpioPin::new(GpioSelect::Gpio2, 5).unwrap();
+        assert!(Gpio2::take().is_none());
+        assert!(GpioPin::new(GpioSelect::Gpio2, 6).is_some());
+    }
+
+    #[test]
+    fn modes_configure_the_pin() {
+        crate::gpio::reset_mock();
+        let pins = Gpio2::take().unwrap();
+
+        let mut led = pins.p2_1.into_push_pull_output_in_state(true);
+        assert!(led.is_set_high());
+        led.toggle();
+        assert!(led.is_set_low());
+        assert_eq!(
+            crate::mock::read(rro::GPIO_OUT + registers::GPIO_2) & (1 << 1),
+            0
+        );
+
+        let button = pins.p2_6.into_pull_up_input();
+        crate::mock::write(rro::GPIO_IN + registers::GPIO_2, 1 << 6);
+        assert!(button.is_high());
+        assert_ne!(
+            crate::mock::read(rro::GPIO_PADCTRL0 + registers::GPIO_2) & (1 << 6),
+            0
+        );
+    }
+
+    #[test]
+    fn outputs_write_atomically() {
+        crate::gpio::reset_mock();
+        let pins = Gpio0::take().unwrap();
+        let base = registers::GPIO_0;
+
+        let mut led = pins.p0_20.into_push_pull_output();
+        led.set_high();
+        assert_eq!(crate::mock::read(rro::GPIO_OUT_SET + base), 1 << 20);
+        led.toggle();
+        assert_eq!(crate::mock::read(rro::GPIO_OUT_CLR + base), 1 << 20);
+        assert!(led.is_set_low());
+    }
+
+    #[test]
+    fn open_drain_pulls_low_or_releases() {
+        crate::gpio::reset_mock();
+        let pins = Gpio0::take().unwrap();
+        let base = registers::GPIO_0;
+
+        let mut line = pins.p0_8.into_open_drain_output::<StrongPullUp>();
+        assert!(line.is_set_high());
+        assert_eq!(crate::mock::read(rro::GPIO_OUT_CLR + base), 1 << 8);
+        assert_eq!(crate::mock::read(rro::GPIO_PS + base), 1 << 8);
+
+        line.set_low();
+        assert_eq!(crate::mock::read(rro::GPIO_OUTEN_SET + base), 1 << 8);
+        assert!(line.is_set_low());
+        assert!(line.is_low());
+
+        line.toggle();
+        assert_eq!(crate::mock::read(rro::GPIO_OUTEN_CLR + base), 1 << 8);
+        crate::mock::write(rro::GPIO_IN + base, 1 << 8);
+        assert!(line.is_high());
+    }
+
+    #[test]
+    fn electrical_settings() {
+        crate::gpio::reset_mock();
+        let pins = Gpio2::take().unwrap();
+        let base = registers::GPIO_2;
+
+        let _output = pins
+            .p2_3
+            .into_push_pull_output()
+            .with_drive_strength(OutputDriveStrength::Strength2(VoltageSelect::VddIOH))
+            .with_slew_rate(SlewRate::Slow);
+        assert_eq!(crate::mock::read(rro::GPIO_DS0 + base), 1 << 3);
+        assert_eq!(crate::mock::read(rro::GPIO_DS1 + base), 1 << 3);
+        assert_eq!(crate::mock::read(rro::GPIO_VSSEL + base), 1 << 3);
+        assert_eq!(crate::mock::read(rro::GPIO_SRSEL + base), 1 << 3);
+
+        let _input = pins
+            .p2_7
+            .into_floating_input()
+            .with_voltage(VoltageSelect::VddIOH)
+            .with_hysteresis(true);
+        assert_eq!(
+            crate::mock::read(rro::GPIO_VSSEL + base),
+            (1 << 3) | (1 << 7)
+        );
+        assert_eq!(crate::mock::read(rro::GPIO_HYSEN + base), 1 << 7);
+    }
+
+    #[test]
+    fn input_interrupts() {
+        crate::gpio::reset_mock();
+        let pins = Gpio0::take().unwrap();
+        let base = registers::GPIO_0;
+
+        let mut button = pins.p0_9.into_pull_up_input();
+        button.listen(Trigger::FallingEdge);
+        assert_eq!(crate::mock::read(rro::GPIO_INTMODE + base), 1 << 9);
+        assert_eq!(crate::mock::read(rro::GPIO_INTPOL + base), 0);
+        assert_eq!(crate::mock::read(rro::GPIO_INTEN_SET + base), 1 << 9);
+        assert!(Interrupt::GPIO0.is_enabled());
+
+        assert!(!button.is_interrupt_pending());
+        crate::mock::write(rro::GPIO_INTFL + base, 1 << 9);
+        assert!(button.is_interrupt_pending());
+        button.clear_interrupt();
+        assert_eq!(crate::mock::read(rro::GPIO_INTFL_CLR + base), 1 << 9);
+
+        button.unlisten();
+        assert_eq!(crate::mock::read(rro::GPIO_INTEN_CLR + base), 1 << 9);
+    }
+
+    #[cfg(feature = "eh1")]
+    #[test]
+    fn drivers_use_typed_pins() {
+        use embedded_hal::digital::{InputPin, OutputPin, StatefulOutputPin};
+
+        /// A driver only knowing the embedded-hal traits.
+        fn follow(input: &mut impl InputPin, output: &mut impl StatefulOutputPin) -> bool {
+            if input.is_high().unwrap() {
+                output.set_high().unwrap();
+            } else {
+                output.toggle().unwrap();
+            }
+            output.is_set_high().unwrap()
+        }
+
+        crate::gpio::reset_mock();
+        let pins = Gpio1::take().unwrap();
+        let mut input = pins.p1_2.into_floating_input();
+        let mut output = pins.p1_3.into_push_pull_output();
+
+        assert!(follow(&mut input, &mut output));
+        assert!(!follow(&mut input, &mut output));
+
+        crate::mock::write(rro::GPIO_IN + registers::GPIO_1, 1 << 2);
+        assert!(follow(&mut input, &mut output));
+        OutputPin::set_low(&mut output).unwrap();
+        assert!(output.is_set_low());
+    }
+}
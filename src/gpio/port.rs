@@ -0,0 +1,172 @@
+//! # GPIO Port
+//! Many pins of one GPIO port at once, for bit-banged parallel buses. A `Port` owns the
+//! pins in its mask, so the other pins of the port can still be taken one by one (as
+//! `GpioPin` or typed pins), and reads and writes only ever touch its own pins.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::gpio::port::Port;
+//! use max78000_hal::gpio::GpioSelect;
+//! # let byte = 0x5Au8;
+//!
+//! // An 8-bit data bus on P0.16 to P0.23
+//! let mut bus = Port::new(GpioSelect::Gpio0, 0xFF << 16)?;
+//! bus.set_outputs(0xFF << 16);
+//! bus.write((byte as u32) << 16);
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::registers::{self, rro};
+use super::{ownership, GpioSelect};
+use crate::error::{ErrorKind, Result};
+
+/// # Port
+/// The pins in `mask` of a GPIO port.
+pub struct Port {
+    port: GpioSelect,
+    mask: u32,
+}
+
+/// The pins `port` has, as a mask.
+fn port_pins(port: GpioSelect) -> u32 {
+    match port {
+        GpioSelect::Gpio0 => u32::MAX,
+        GpioSelect::Gpio1 => 0x3FF,
+        GpioSelect::Gpio2 => 0xFF,
+    }
+}
+
+impl Port {
+    /// # New
+    /// Take the pins in `mask` of `port`, and make them inputs on their GPIO function.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` when `mask` is empty, or has pins the port does not have.
+    /// - `ErrorKind::Busy` when any of the pins is already owned.
+    pub fn new(port: GpioSelect, mask: u32) -> Result<Self> {
+        if mask == 0 || mask & !port_pins(port) != 0 {
+            return Err(ErrorKind::BadParam);
+        }
+        if !ownership::claim_mask(port, mask) {
+            return Err(ErrorKind::Busy);
+        }
+
+        let base = port.into();
+        unsafe {
+            registers::write_gpio(rro::GPIO_OUTEN_CLR, base, mask);
+            registers::write_gpio(rro::GPIO_EN0_SET, base, mask);
+            registers::write_gpio(rro::GPIO_EN1_CLR, base, mask);
+            registers::write_gpio(rro::GPIO_EN2_CLR, base, mask);
+            registers::enable_mask(rro::GPIO_INEN, base, mask);
+        }
+
+        Ok(Self { port, mask })
+    }
+
+    /// # Mask
+    /// The pins owned.
+    pub fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    /// # Set Outputs
+    /// Drive the pins in `outputs`, the other pins owned become inputs. Pins outside
+    /// the mask are ignored.
+    pub fn set_outputs(&mut self, outputs: u32) {
+        let base = self.port.into();
+        let outputs = outputs & self.mask;
+
+        unsafe {
+            registers::write_gpio(rro::GPIO_OUTEN_CLR, base, self.mask & !outputs);
+            registers::write_gpio(rro::GPIO_OUTEN_SET, base, outputs);
+        }
+    }
+
+    /// # Read
+    /// The level of every pin owned, in one read. The other bits are zero.
+    pub fn read(&self) -> u32 {
+        unsafe { registers::read_gpio(rro::GPIO_IN, self.port.into()) & self.mask }
+    }
+
+    /// # Read Output
+    /// The level every pin owned is driven to.
+    pub fn read_output(&self) -> u32 {
+        unsafe { registers::read_gpio(rro::GPIO_OUT, self.port.into()) & self.mask }
+    }
+
+    /// # Write
+    /// Drive every pin owned to its bit of `value`, all switching together in one write
+    /// of `GPIO_OUT`. The other pins of the port are read and written back unchanged, so
+    /// this must not race a interrupt writing them, use `set_bits`/`clear_bits` then.
+    pub fn write(&mut self, value: u32) {
+        let base = self.port.into();
+
+        unsafe {
+            let out = registers::read_gpio(rro::GPIO_OUT, base);
+            registers::write_gpio(
+                rro::GPIO_OUT,
+                base,
+                (out & !self.mask) | (value & self.mask),
+            );
+        }
+    }
+
+    /// # Set Bits
+    /// Drive the pins in `bits` high, in one atomic write.
+    pub fn set_bits(&mut self, bits: u32) {
+        unsafe { registers::write_pins(self.port.into(), bits & self.mask, true) };
+    }
+
+    /// # Clear Bits
+    /// Drive the pins in `bits` low, in one atomic write.
+    pub fn clear_bits(&mut self, bits: u32) {
+        unsafe { registers::write_pins(self.port.into(), bits & self.mask, false) };
+    }
+}
+
+impl Drop for Port {
+    fn drop(&mut self) {
+        ownership::disown_mask(self.port, self.mask);
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::gpio::GpioPin;
+
+    #[test]
+    fn shares_port_with_pins() {
+        crate::gpio::reset_mock();
+        let base = registers::GPIO_1;
+
+        let pin = GpioPin::new(GpioSelect::Gpio1, 0).unwrap();
+        assert_eq!(
+            Port::new(GpioSelect::Gpio1, 0x3).err(),
+            Some(ErrorKind::Busy)
+        );
+        assert_eq!(
+            Port::new(GpioSelect::Gpio1, 1 << 10).err(),
+            Some(ErrorKind::BadParam)
+        );
+
+        let mut bus = Port::new(GpioSelect::Gpio1, 0xF0).unwrap();
+        assert!(GpioPin::new(GpioSelect::Gpio1, 4).is_none());
+        bus.set_outputs(0x30);
+        assert_eq!(crate::mock::read(rro::GPIO_OUTEN_SET + base), 0x30);
+        assert_eq!(crate::mock::read(rro::GPIO_OUTEN_CLR + base), 0xC0);
+
+        pin.set_output(true);
+        bus.write(0xFFFF_FFA0);
+        assert_eq!(crate::mock::read(rro::GPIO_OUT + base), 0xA1);
+        assert_eq!(bus.read_output(), 0xA0);
+
+        bus.clear_bits(0x21);
+        assert_eq!(crate::mock::read(rro::GPIO_OUT + base), 0x81);
+
+        crate::mock::write(rro::GPIO_IN + base, 0x3C);
+        assert_eq!(bus.read(), 0x30);
+
+        drop(bus);
+        assert!(GpioPin::new(GpioSelect::Gpio1, 4).is_some());
+    }
+}
@@ -91,19 +91,30 @@ pub(super) mod rro {
 /// # Write GPIO
 /// Write to a GPIO register and port.
 pub(crate) unsafe fn write_gpio(base: BaseOffset, port: PortOffset, value: u32) {
-    let ptr = (base + port) as *mut u32;
-
-    core::ptr::write_volatile(ptr, value);
+    hal_macros::mmio_write(base + port, value);
 }
 
 /// # Read GPIO
 /// Read from the GPIO register and port.
 pub(crate) unsafe fn read_gpio(base: BaseOffset, port: PortOffset) -> u32 {
-    let ptr = (base + port) as *const u32;
+    let ptr = hal_macros::mmio_ptr::<u32>(base + port);
 
     core::ptr::read_volatile(ptr)
 }
 
+/// # Write Pins
+/// Drive the pins in `mask` of `port` high or low, through the atomic set and clear
+/// registers. The other pins of the port are not touched, so this needs no read of
+/// `GPIO_OUT` and can not race a interrupt writing another pin.
+pub(crate) unsafe fn write_pins(port: PortOffset, mask: u32, high: bool) {
+    let register = if high {
+        rro::GPIO_OUT_SET
+    } else {
+        rro::GPIO_OUT_CLR
+    };
+    write_gpio(register, port, mask);
+}
+
 /// # Enable Bit
 /// Enable the bit for the given GPIO port and register.
 pub(crate) unsafe fn enable_bit(base: BaseOffset, port: PortOffset, bit: usize) {
@@ -119,3 +130,10 @@ pub(crate) unsafe fn disable_bit(base: BaseOffset, port: PortOffset, bit: usize)
     let bit = 1 << bit;
     write_gpio(base, port, read & (!bit));
 }
+
+/// # Enable Mask
+/// Enable the bits in `mask` for the given GPIO port and register.
+pub(crate) unsafe fn enable_mask(base: BaseOffset, port: PortOffset, mask: u32) {
+    let read = read_gpio(base, port);
+    write_gpio(base, port, mask | read);
+}
@@ -0,0 +1,364 @@
+//! # GPIO Wait
+//! Wait for a pin to change without spinning, by letting the GPIO port interrupts wake
+//! the waiting task. With the `async` feature `GpioPin` and the typed input pins also
+//! implement `embedded_hal_async::digital::Wait`.
+//!
+//! Bind `InterruptHandler` to the port interrupts of the pins you wait on, the NVIC
+//! line is enabled by the first wait on that port. The same handler runs the callbacks
+//! of `callback`, so a port can have pins waited on and pins with callbacks. A single
+//! pin has one or the other.
+//!
+//! For a handler of your own, `listen` configures a pin's interrupt and enables the
+//! port's NVIC line, and `take_interrupt` checks and clears its flag in the handler.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::bind_interrupts;
+//! use max78000_hal::gpio::wait::{self, Trigger};
+//! use max78000_hal::gpio::GpioPin;
+//!
+//! bind_interrupts!(struct Irqs {
+//!     GPIO0 => wait::InterruptHandler;
+//! });
+//!
+//! async fn wait_for_press(button: &mut GpioPin) {
+//!     button.wait_for(Trigger::FallingEdge).await;
+//! }
+//! ```
+use core::future::Future;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use super::registers::{self, rro};
+use super::{GpioPin, GpioSelect};
+use crate::interrupt::typelevel::{Handler, GPIO0, GPIO1, GPIO2};
+use crate::interrupt::{Interrupt, InterruptExt};
+
+/// # Trigger
+/// What a pin waits for.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Trigger {
+    /// The pin is high, done at once if it already is.
+    High,
+    /// The pin is low, done at once if it already is.
+    Low,
+    /// The pin goes from low to high.
+    RisingEdge,
+    /// The pin goes from high to low.
+    FallingEdge,
+    /// The pin changes either way.
+    AnyEdge,
+}
+
+/// The waker of the task waiting on every pin. A slot is only written while its pin's
+/// interrupt is disabled, and only taken by the interrupt handler while it is enabled.
+static mut WAKERS: [[Option<Waker>; 32]; 3] = [const { [const { None }; 32] }; 3];
+
+/// # Interrupt Handler
+/// Calls the callbacks of the pins that interrupted (see `callback`), and wakes the
+/// tasks waiting on the others. Bind it to `GPIO0`, `GPIO1` and/or `GPIO2`.
+pub struct InterruptHandler;
+
+impl Handler<GPIO0> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch(GpioSelect::Gpio0);
+    }
+}
+
+impl Handler<GPIO1> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch(GpioSelect::Gpio1);
+    }
+}
+
+impl Handler<GPIO2> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch(GpioSelect::Gpio2);
+    }
+}
+
+/// Clear every pin of `port` that interrupted, then call back the pins with a callback
+/// and wake the tasks waiting on the others. Pins with a callback on an edge stay
+/// enabled, the others are disabled until waited on (or listened to) again.
+unsafe fn dispatch(port: GpioSelect) {
+    let base = port.into();
+    let fired =
+        registers::read_gpio(rro::GPIO_INTFL, base) & registers::read_gpio(rro::GPIO_INTEN, base);
+    let called = (0..32)
+        .filter(|pin| fired & (1 << pin) != 0 && super::callback::callback(port, *pin).is_some())
+        .fold(0, |called, pin| called | (1 << pin));
+    // A level is still there after its flag is cleared, and would interrupt again as soon
+    // as the handler returns
+    let kept = called & registers::read_gpio(rro::GPIO_INTMODE, base);
+
+    registers::write_gpio(rro::GPIO_INTEN_CLR, base, fired & !kept);
+    registers::write_gpio(rro::GPIO_INTFL_CLR, base, fired);
+
+    for pin in (0..32).filter(|pin| fired & (1 << pin) != 0) {
+        if let Some(callback) = super::callback::callback(port, pin) {
+            callback();
+        } else if let Some(waker) = WAKERS[port as usize][pin].take() {
+            waker.wake();
+        }
+    }
+}
+
+fn port_interrupt(port: GpioSelect) -> Interrupt {
+    match port {
+        GpioSelect::Gpio0 => Interrupt::GPIO0,
+        GpioSelect::Gpio1 => Interrupt::GPIO1,
+        GpioSelect::Gpio2 => Interrupt::GPIO2,
+    }
+}
+
+/// # Wait Future
+/// Completes once the pin saw its `Trigger`, see `GpioPin::wait_for`.
+#[must_use = "futures do nothing unless polled"]
+pub struct WaitFuture<'a> {
+    pin: ManuallyDrop<GpioPin>,
+    trigger: Trigger,
+    armed: bool,
+    _pin: PhantomData<&'a mut GpioPin>,
+}
+
+impl WaitFuture<'_> {
+    /// Wait on `pin` for `trigger`, without taking its ownership.
+    pub(super) fn new(pin: ManuallyDrop<GpioPin>, trigger: Trigger) -> Self {
+        Self {
+            pin,
+            trigger,
+            armed: false,
+            _pin: PhantomData,
+        }
+    }
+
+    fn mask(&self) -> u32 {
+        1 << self.pin.get_pin()
+    }
+
+    fn base(&self) -> registers::PortOffset {
+        self.pin.get_port().into()
+    }
+
+    /// Configure the pin's interrupt for the trigger, and clear any old event.
+    unsafe fn arm(&self) {
+        self.pin.set_trigger(self.trigger);
+        registers::write_gpio(rro::GPIO_INTFL_CLR, self.base(), self.mask());
+    }
+}
+
+impl Future for WaitFuture<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let level = match self.trigger {
+            Trigger::High => Some(true),
+            Trigger::Low => Some(false),
+            _ => None,
+        };
+        if !self.armed && level.is_some_and(|level| self.pin.get_input() == level) {
+            return Poll::Ready(());
+        }
+
+        let base = self.base();
+        let mask = self.mask();
+        let port = self.pin.get_port();
+        let slot = self.pin.get_pin();
+
+        unsafe {
+            if self.armed {
+                // Stop the handler from touching the waker while it is replaced
+                let enabled = registers::read_gpio(rro::GPIO_INTEN, base) & mask != 0;
+                registers::write_gpio(rro::GPIO_INTEN_CLR, base, mask);
+
+                let flagged = registers::read_gpio(rro::GPIO_INTFL, base) & mask != 0;
+                if !enabled || flagged {
+                    registers::write_gpio(rro::GPIO_INTFL_CLR, base, mask);
+                    self.armed = false;
+                    return Poll::Ready(());
+                }
+            } else {
+                self.arm();
+                port_interrupt(port).enable();
+                self.armed = true;
+            }
+
+            WAKERS[port as usize][slot] = Some(cx.waker().clone());
+            registers::write_gpio(rro::GPIO_INTEN_SET, base, mask);
+        }
+
+        Poll::Pending
+    }
+}
+
+impl Drop for WaitFuture<'_> {
+    fn drop(&mut self) {
+        if self.armed {
+            unsafe {
+                registers::write_gpio(rro::GPIO_INTEN_CLR, self.base(), self.mask());
+                WAKERS[self.pin.get_port() as usize][self.pin.get_pin()] = None;
+            }
+        }
+    }
+}
+
+impl GpioPin {
+    /// Configure what the pin's interrupt fires on.
+    unsafe fn set_trigger(&self, trigger: Trigger) {
+        let (edge, high, dual) = match trigger {
+            Trigger::High => (false, true, false),
+            Trigger::Low => (false, false, false),
+            Trigger::RisingEdge => (true, true, false),
+            Trigger::FallingEdge => (true, false, false),
+            Trigger::AnyEdge => (true, false, true),
+        };
+
+        self.set_bit(rro::GPIO_INTMODE, edge);
+        self.set_bit(rro::GPIO_INTPOL, high);
+        self.set_bit(rro::GPIO_DUALEDGE, dual);
+    }
+
+    /// # Listen
+    /// Enable the pin's interrupt on `trigger`, and the NVIC line of its port. For
+    /// handling the port interrupt yourself, see `take_interrupt`.
+    pub fn listen(&self, trigger: Trigger) {
+        let base = self.get_port().into();
+        let mask = 1 << self.get_pin();
+
+        unsafe {
+            registers::write_gpio(rro::GPIO_INTEN_CLR, base, mask);
+            self.set_trigger(trigger);
+            registers::write_gpio(rro::GPIO_INTFL_CLR, base, mask);
+            registers::write_gpio(rro::GPIO_INTEN_SET, base, mask);
+            port_interrupt(self.get_port()).enable();
+        }
+    }
+
+    /// # Unlisten
+    /// Disable the pin's interrupt.
+    pub fn unlisten(&self) {
+        unsafe {
+            registers::write_gpio(
+                rro::GPIO_INTEN_CLR,
+                self.get_port().into(),
+                1 << self.get_pin(),
+            )
+        };
+    }
+
+    /// # Is Interrupt Pending
+    /// Check if the pin's interrupt fired. The flag is set on the trigger even while the
+    /// interrupt is disabled.
+    pub fn is_interrupt_pending(&self) -> bool {
+        let flags = unsafe { registers::read_gpio(rro::GPIO_INTFL, self.get_port().into()) };
+        flags & (1 << self.get_pin()) != 0
+    }
+
+    /// # Clear Interrupt
+    /// Clear the pin's interrupt flag.
+    pub fn clear_interrupt(&self) {
+        unsafe {
+            registers::write_gpio(
+                rro::GPIO_INTFL_CLR,
+                self.get_port().into(),
+                1 << self.get_pin(),
+            )
+        };
+    }
+
+    /// # Take Interrupt
+    /// Check if the pin's interrupt fired, and clear it.
+    pub fn take_interrupt(&self) -> bool {
+        let fired = self.is_interrupt_pending();
+        if fired {
+            self.clear_interrupt();
+        }
+        fired
+    }
+
+    /// # Wait For
+    /// Wait until the pin sees `trigger`. The pin must be configured as a input, and
+    /// `InterruptHandler` bound to its port's interrupt. The pin stays borrowed while
+    /// the future lives, so a second wait can't take over its waker slot.
+    pub fn wait_for(&mut self, trigger: Trigger) -> WaitFuture<'_> {
+        WaitFuture::new(ManuallyDrop::new(GpioPin(self.0)), trigger)
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::digital::Wait for GpioPin {
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(Trigger::High).await;
+        Ok(())
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(Trigger::Low).await;
+        Ok(())
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(Trigger::RisingEdge).await;
+        Ok(())
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(Trigger::FallingEdge).await;
+        Ok(())
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait_for(Trigger::AnyEdge).await;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+
+    fn poll(future: &mut WaitFuture<'_>) -> Poll<()> {
+        let mut cx = Context::from_waker(Waker::noop());
+        Pin::new(future).poll(&mut cx)
+    }
+
+    #[test]
+    fn level_already_met() {
+        crate::gpio::reset_mock();
+        let mut pin = unsafe { GpioPin::steal(GpioSelect::Gpio1, 6) };
+
+        crate::mock::write(rro::GPIO_IN + registers::GPIO_1, 1 << 6);
+        assert_eq!(poll(&mut pin.wait_for(Trigger::High)), Poll::Ready(()));
+        assert_eq!(crate::mock::read(rro::GPIO_INTEN + registers::GPIO_1), 0);
+    }
+
+    #[test]
+    fn edge_wakes_through_dispatch() {
+        crate::gpio::reset_mock();
+        let mut pin = unsafe { GpioPin::steal(GpioSelect::Gpio0, 3) };
+        let inten = rro::GPIO_INTEN + registers::GPIO_0;
+
+        let mut future = pin.wait_for(Trigger::RisingEdge);
+        assert_eq!(poll(&mut future), Poll::Pending);
+        assert_eq!(
+            crate::mock::read(rro::GPIO_INTMODE + registers::GPIO_0),
+            1 << 3
+        );
+        assert_eq!(
+            crate::mock::read(rro::GPIO_INTPOL + registers::GPIO_0),
+            1 << 3
+        );
+        assert!(Interrupt::GPIO0.is_enabled());
+
+        assert_eq!(crate::mock::read(inten), 1 << 3);
+        assert_eq!(poll(&mut future), Poll::Pending);
+
+        crate::mock::write(rro::GPIO_INTFL + registers::GPIO_0, 1 << 3);
+        unsafe { dispatch(GpioSelect::Gpio0) };
+        assert_eq!(crate::mock::read(inten), 0);
+        assert_eq!(poll(&mut future), Poll::Ready(()));
+    }
+}
@@ -0,0 +1,139 @@
+//! # GPIO Wake-Up
+//! Pins as wake-up sources for the low power modes. A pin armed with `enable_wakeup`
+//! wakes the chip when its interrupt trigger (see `listen`) is seen while it sleeps,
+//! and the power sequencer flags it in `PWRSEQ_LPWKSTn`. Arming a pin also allows GPIO
+//! wake-ups in `GCR_PM`, see `power::enable_wakeup_source`. After waking, `woken_by`
+//! tells which pin it was.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::gpio::pin::Gpio2;
+//! use max78000_hal::gpio::wait::Trigger;
+//! use max78000_hal::power;
+//! # let pins = Gpio2::take().unwrap();
+//!
+//! let mut button = pins.p2_6.into_pull_up_input();
+//! button.listen(Trigger::FallingEdge);
+//! button.enable_wakeup(true);
+//!
+//! power::deep_sleep();
+//! if button.is_wakeup_pending() {
+//!     button.clear_wakeup();
+//! }
+//! ```
+use super::registers::{self, rro};
+use super::{GpioPin, GpioSelect};
+use crate::memory_map::mmio;
+use crate::power::{self, WakeUpSource};
+
+/// # Power Sequencer Offsets
+/// The GPIO wake-up registers of the power sequencer, one pair per port.
+mod pwrseq {
+    /// # GPIO0 Low Power Wake-Up Status Register
+    pub const PWRSEQ_LPWKST0: usize = 0x0004;
+    /// # GPIO0 Low Power Wake-Up Enable Register
+    pub const PWRSEQ_LPWKEN0: usize = 0x0008;
+    /// # The distance from one port's pair of registers to the next.
+    pub const PORT_STRIDE: usize = 0x0008;
+}
+
+/// The address of the wake-up register at `offset`, for `port`.
+fn pwrseq_register(offset: usize, port: GpioSelect) -> *mut u32 {
+    hal_macros::mmio_ptr(mmio::POWER_SEQUENCER + offset + port as usize * pwrseq::PORT_STRIDE)
+}
+
+/// The wake-up flags of `port`.
+fn wakeup_flags(port: GpioSelect) -> u32 {
+    unsafe { core::ptr::read_volatile(pwrseq_register(pwrseq::PWRSEQ_LPWKST0, port)) }
+}
+
+/// Clear the wake-up flags in `mask` of `port`, they are write one to clear.
+fn clear_wakeup_mask(port: GpioSelect, mask: u32) {
+    unsafe { core::ptr::write_volatile(pwrseq_register(pwrseq::PWRSEQ_LPWKST0, port), mask) };
+}
+
+impl GpioPin {
+    /// # Enable Wake-Up
+    /// Arm/Disarm the pin as a wake-up source from low power modes. Arming also allows
+    /// GPIO wake-ups for the whole chip, which are left allowed when disarming.
+    pub fn enable_wakeup(&self, enable: bool) {
+        let port = self.get_port();
+        let mask = 1 << self.get_pin();
+
+        unsafe {
+            let wken = if enable {
+                rro::GPIO_WKEN_SET
+            } else {
+                rro::GPIO_WKEN_CLR
+            };
+            registers::write_gpio(wken, port.into(), mask);
+
+            let lpwken = pwrseq_register(pwrseq::PWRSEQ_LPWKEN0, port);
+            let armed = core::ptr::read_volatile(lpwken);
+            let armed = if enable { armed | mask } else { armed & !mask };
+            core::ptr::write_volatile(lpwken, armed);
+        }
+
+        if enable {
+            power::enable_wakeup_source(WakeUpSource::Gpio, true);
+        }
+    }
+
+    /// # Is Wake-Up Pending
+    /// Check if the pin flagged a wake-up event since it was last cleared.
+    pub fn is_wakeup_pending(&self) -> bool {
+        wakeup_flags(self.get_port()) & (1 << self.get_pin()) != 0
+    }
+
+    /// # Clear Wake-Up
+    /// Clear the pin's wake-up event flag.
+    pub fn clear_wakeup(&self) {
+        clear_wakeup_mask(self.get_port(), 1 << self.get_pin());
+    }
+}
+
+/// # Woken By
+/// The port and number of the first pin with a wake-up event flagged.
+pub fn woken_by() -> Option<(GpioSelect, usize)> {
+    [GpioSelect::Gpio0, GpioSelect::Gpio1, GpioSelect::Gpio2]
+        .into_iter()
+        .find_map(|port| match wakeup_flags(port) {
+            0 => None,
+            flags => Some((port, flags.trailing_zeros() as usize)),
+        })
+}
+
+/// # Clear Wake-Up Flags
+/// Clear the wake-up event flags of every pin.
+pub fn clear_wakeup_flags() {
+    for port in [GpioSelect::Gpio0, GpioSelect::Gpio1, GpioSelect::Gpio2] {
+        clear_wakeup_mask(port, u32::MAX);
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn arms_and_finds_woken_pin() {
+        crate::gpio::reset_mock();
+        let pin = unsafe { GpioPin::steal(GpioSelect::Gpio1, 4) };
+
+        pin.enable_wakeup(true);
+        assert_eq!(
+            crate::mock::read(rro::GPIO_WKEN_SET + registers::GPIO_1),
+            1 << 4
+        );
+        assert_eq!(crate::mock::read(mmio::POWER_SEQUENCER + 0x10), 1 << 4);
+        assert!(power::is_wakeup_source(WakeUpSource::Gpio));
+
+        assert_eq!(woken_by(), None);
+        crate::mock::write(mmio::POWER_SEQUENCER + 0x0C, 1 << 4);
+        assert!(pin.is_wakeup_pending());
+        assert_eq!(woken_by(), Some((GpioSelect::Gpio1, 4)));
+
+        pin.enable_wakeup(false);
+        assert_eq!(crate::mock::read(mmio::POWER_SEQUENCER + 0x10), 0);
+    }
+}
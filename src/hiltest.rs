@@ -0,0 +1,249 @@
+//! # Hardware In The Loop Tests
+//! A tiny on-target test runner, so the drivers can be regression tested on real
+//! silicon. Test cases are plain functions registered with `hil_tests!`, and `serve`
+//! runs them as a host asks over a UART.
+//!
+//! # Protocol
+//! Every message is a line of ASCII ending in `\n`, a trailing `\r` is ignored.
+//!
+//! | Host sends   | Target replies                                                   |
+//! |--------------|------------------------------------------------------------------|
+//! |              | `READY <count>` once, when `serve` starts                        |
+//! | `LIST`       | `CASE <name>` for every test, then `END <count>`                 |
+//! | `RUN <name>` | `PASS <name>` or `FAIL <name> <message>`, then `END 1`            |
+//! | `ALL`        | `PASS`/`FAIL` for every test, then `END <passed> <failed>`        |
+//! | anything     | `ERROR <line>`                                                   |
+//!
+//! A test that panics never reports back, the host should treat a missing reply as a
+//! failure (with `console-panic`, the panic message is printed first).
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::hiltest::{self, TestResult};
+//! use max78000_hal::trng::TRNG;
+//! use max78000_hal::{hil_assert, hil_tests};
+//! # use max78000_hal::uart::{BaudRates, CharacterLength, Parity, ParityValueSelect, StopBits, UART};
+//!
+//! fn trng_is_ready() -> TestResult {
+//!     let mut trng = TRNG::init();
+//!     hil_assert!(trng.get_trng_data() != 0);
+//!     Ok(())
+//! }
+//!
+//! hil_tests! {
+//!     static TESTS = [trng_is_ready];
+//! }
+//!
+//! # let mut uart = UART::port_0_init(
+//! #     BaudRates::Baud115200,
+//! #     CharacterLength::EightBits,
+//! #     StopBits::OneBit,
+//! #     false,
+//! #     Parity::Odd,
+//! #     ParityValueSelect::OneBased,
+//! #     false,
+//! # ).unwrap();
+//! hiltest::serve(&mut uart, TESTS);
+//! ```
+use core::fmt::{self, Write};
+
+use crate::uart::private::UARTPortCompatable;
+use crate::uart::UART;
+
+/// # Max Line
+/// The longest command line `serve` accepts, longer lines are reported as errors.
+const MAX_LINE: usize = 64;
+
+/// # Test Result
+/// What a test case returns, the error is a message describing the failure.
+pub type TestResult = core::result::Result<(), &'static str>;
+
+/// # Test Case
+/// A registered test, see `hil_tests!`.
+#[derive(Clone, Copy)]
+pub struct TestCase {
+    /// The name the host uses for the test.
+    pub name: &'static str,
+    /// The test itself.
+    pub run: fn() -> TestResult,
+}
+
+/// # HIL Tests
+/// Register test functions, making a `&'static [TestCase]` named after the function
+/// paths.
+#[macro_export]
+macro_rules! hil_tests {
+    ($vis:vis static $name:ident = [$($test:path),* $(,)?];) => {
+        $vis static $name: &[$crate::hiltest::TestCase] = &[
+            $($crate::hiltest::TestCase {
+                name: stringify!($test),
+                run: $test,
+            }),*
+        ];
+    };
+}
+
+/// # HIL Assert
+/// Fail the current test case when the condition is false, reporting where.
+#[macro_export]
+macro_rules! hil_assert {
+    ($cond:expr) => {
+        if !$cond {
+            return Err(concat!(file!(), ":", line!(), ": ", stringify!($cond)));
+        }
+    };
+    ($cond:expr, $msg:literal) => {
+        if !$cond {
+            return Err(concat!(file!(), ":", line!(), ": ", $msg));
+        }
+    };
+}
+
+/// # Serve
+/// Announce the tests on `uart`, then run commands from the host forever.
+pub fn serve<Port: UARTPortCompatable>(uart: &mut UART<Port>, tests: &[TestCase]) -> ! {
+    let _ = writeln!(uart, "READY {}", tests.len());
+
+    let mut line = [0u8; MAX_LINE];
+    loop {
+        let mut len = 0;
+        let mut overflow = false;
+        loop {
+            match uart.read_blocking_receive_fifo() {
+                b'\n' => break,
+                byte => match line.get_mut(len) {
+                    Some(slot) => {
+                        *slot = byte;
+                        len += 1;
+                    }
+                    None => overflow = true,
+                },
+            }
+        }
+
+        let command = match core::str::from_utf8(&line[..len]) {
+            Ok(command) if !overflow => command,
+            _ => "",
+        };
+        let _ = handle(command, tests, uart);
+    }
+}
+
+/// # Handle
+/// Run one command line, writing the replies to `out`.
+pub fn handle<W: Write>(line: &str, tests: &[TestCase], out: &mut W) -> fmt::Result {
+    let line = line.trim_end_matches('\r');
+    let mut words = line.split(' ');
+
+    match (words.next(), words.next(), words.next()) {
+        (Some("LIST"), None, _) => {
+            for test in tests {
+                writeln!(out, "CASE {}", test.name)?;
+            }
+            writeln!(out, "END {}", tests.len())
+        }
+        (Some("RUN"), Some(name), None) => match tests.iter().find(|test| test.name == name) {
+            Some(test) => {
+                report(test, out)?;
+                writeln!(out, "END 1")
+            }
+            None => writeln!(out, "ERROR {}", line),
+        },
+        (Some("ALL"), None, _) => {
+            let mut passed = 0;
+            for test in tests {
+                if report(test, out)? {
+                    passed += 1;
+                }
+            }
+            writeln!(out, "END {} {}", passed, tests.len() - passed)
+        }
+        _ => writeln!(out, "ERROR {}", line),
+    }
+}
+
+/// Run `test`, writing its `PASS`/`FAIL` line, and return if it passed.
+fn report<W: Write>(test: &TestCase, out: &mut W) -> core::result::Result<bool, fmt::Error> {
+    match (test.run)() {
+        Ok(()) => writeln!(out, "PASS {}", test.name).map(|_| true),
+        Err(message) => writeln!(out, "FAIL {} {}", test.name, message).map(|_| false),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Buffer {
+        bytes: [u8; 256],
+        len: usize,
+    }
+
+    impl Buffer {
+        fn new() -> Self {
+            Self {
+                bytes: [0; 256],
+                len: 0,
+            }
+        }
+
+        fn as_str(&self) -> &str {
+            core::str::from_utf8(&self.bytes[..self.len]).unwrap()
+        }
+    }
+
+    impl Write for Buffer {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.len + s.len();
+            self.bytes
+                .get_mut(self.len..end)
+                .ok_or(fmt::Error)?
+                .copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    fn passes() -> TestResult {
+        hil_assert!(TESTS.len() == 2);
+        Ok(())
+    }
+
+    fn fails() -> TestResult {
+        hil_assert!(false, "always fails");
+        Ok(())
+    }
+
+    hil_tests! {
+        static TESTS = [passes, fails];
+    }
+
+    #[test]
+    fn lists_tests() {
+        let mut out = Buffer::new();
+        handle("LIST\r", TESTS, &mut out).unwrap();
+        assert_eq!(out.as_str(), "CASE passes\nCASE fails\nEND 2\n");
+    }
+
+    #[test]
+    fn runs_tests() {
+        let mut out = Buffer::new();
+        handle("RUN passes", TESTS, &mut out).unwrap();
+        assert_eq!(out.as_str(), "PASS passes\nEND 1\n");
+
+        let mut out = Buffer::new();
+        handle("ALL", TESTS, &mut out).unwrap();
+        assert!(out
+            .as_str()
+            .starts_with("PASS passes\nFAIL fails src/hiltest.rs:"));
+        assert!(out.as_str().ends_with(": always fails\nEND 1 1\n"));
+    }
+
+    #[test]
+    fn rejects_unknown_commands() {
+        let mut out = Buffer::new();
+        handle("RUN missing", TESTS, &mut out).unwrap();
+        handle("JUMP", TESTS, &mut out).unwrap();
+        assert_eq!(out.as_str(), "ERROR RUN missing\nERROR JUMP\n");
+    }
+}
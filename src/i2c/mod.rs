@@ -2,14 +2,16 @@ use crate::error::{ErrorKind, Result};
 use crate::gcr::{peripheral_reset, system_clock_enable};
 use crate::gpio::GpioPin;
 use crate::memory_map::mmio;
+use crate::time::Hertz;
 use crate::{core_peripheral_clock, debug_print, debug_println};
 use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
 
 use self::registers::Registers;
 
 pub mod registers;
 
-mod private {
+pub(crate) mod private {
     pub trait I2CPortCompatable {
         const PORT_PTR: usize;
         const PORT_NUM: usize;
@@ -116,11 +118,11 @@ const MAX_I2C_SLAVE_ADDRESS_7_BIT: usize = 0b1111111;
 const MAX_I2C_SLAVE_ADDRESS_10_BIT: usize = 0b1111111111;
 
 #[allow(unused)]
-const MAX_I2C_NORMAL_CLOCK_HZ: usize = 100000;
+const MAX_I2C_NORMAL_CLOCK_HZ: Hertz = Hertz::kHz(100);
 #[allow(unused)]
-const MAX_I2C_FAST_CLOCK_HZ: usize = 400000;
-const MAX_I2C_FASTPLUS_CLOCK_TIME: usize = 1000000;
-const MAX_I2C_HIGHSPEED_CLOCK_TIME: usize = 3400000;
+const MAX_I2C_FAST_CLOCK_HZ: Hertz = Hertz::kHz(400);
+const MAX_I2C_FASTPLUS_CLOCK_TIME: Hertz = Hertz::MHz(1);
+const MAX_I2C_HIGHSPEED_CLOCK_TIME: Hertz = Hertz::kHz(3_400);
 
 const MAX_TRANSMIT_FIFO_LEN: usize = 8;
 
@@ -170,6 +172,31 @@ impl I2C<NoPort> {
 
 #[allow(unused)]
 impl<Port: private::I2CPortCompatable> I2C<Port> {
+    /// # Steal
+    /// Conjure a handle to a already initialized I2C port, without resetting it or
+    /// taking ownership of its pins. Master/slave mode and the slave address are read
+    /// back from the hardware.
+    ///
+    /// The handle is wrapped in `ManuallyDrop`, so dropping it never releases the pins
+    /// owned by the original handle.
+    ///
+    /// # Safety
+    /// The port must have been initialized with one of the `init_port_n_*` functions,
+    /// and the caller must ensure the stolen handle does not race with the original
+    /// one, ie. it is only used from a interrupt handler or panic path that preempts it.
+    pub unsafe fn steal() -> ManuallyDrop<Self> {
+        let reg = Registers::new(Port::PORT_PTR);
+
+        ManuallyDrop::new(Self {
+            master_enabled: reg.get_master_mode_enable(),
+            slave_address: reg.get_slave_mode_address() as usize,
+            reg,
+            gpio: crate::gpio::hardware::steal_i2c_n(Port::PORT_NUM),
+            slave_underflow: false,
+            _ph: PhantomData,
+        })
+    }
+
     fn init(master_enabled: bool, slave_address: usize) -> Result<Self> {
         let mut i2c = Self {
             reg: Registers::new(Port::PORT_PTR),
@@ -249,36 +276,39 @@ impl<Port: private::I2CPortCompatable> I2C<Port> {
             return Err(ErrorKind::BadState);
         }
 
-        if self.reg.get_error_condition() != 0 {
+        // Triage every flag from a single read of the interrupt flags
+        let flags = self.reg.read_all_i2c_intfl0();
+
+        if flags.error_condition() != 0 {
             return Err(ErrorKind::ComError);
         }
 
-        if self.reg.is_receive_fifo_threshold_level_active() {
+        if flags.receive_fifo_threshold_level_flag() {
             return Ok(SlaveStatus::ReadRequested);
         }
 
-        if self.reg.is_slave_mode_stop_condition_active() {
+        if flags.slave_mode_stop_condition() {
             return Ok(SlaveStatus::Stop);
         }
 
-        if self.reg.is_transfer_complete_flag_active() {
+        if flags.transfer_complete_flag() {
             return Ok(SlaveStatus::TransferDone);
         }
 
-        if self.reg.is_slave_incoming_address_match_status_active()
-            || self.reg.is_slave_write_addr_match_interrupt_active()
-            || self.reg.is_slave_read_addr_match_interrupt_active()
+        if flags.slave_incoming_address_match_status()
+            || flags.slave_write_addr_match_interrupt()
+            || flags.slave_read_addr_match_interrupt()
         {
-            let is_write = self.reg.get_read_write_bit_status()
-                && !self.reg.is_slave_read_addr_match_interrupt_active();
+            let is_write =
+                self.reg.get_read_write_bit_status() && !flags.slave_read_addr_match_interrupt();
             return Ok(SlaveStatus::IncomingRequest { is_write });
         }
 
-        if self.reg.is_transmit_fifo_locked_active() {
+        if flags.transmit_fifo_locked() {
             return Ok(SlaveStatus::TransmitFIFOLocked);
         }
 
-        if self.reg.is_transmit_fifo_threshold_level_active() {
+        if flags.transmit_fifo_threshold_level_flag() {
             return Ok(SlaveStatus::WriteRequested);
         }
 
@@ -363,7 +393,7 @@ impl<Port: private::I2CPortCompatable> I2C<Port> {
                         recv_buffer[recv_buffer_index] = data;
                         recv_buffer_index += 1;
 
-                        unsafe { self.reg.clear_receive_fifo_threshold_level() };
+                        unsafe { self.reg.clear_receive_fifo_threshold_level_flag() };
                     }
                 }
                 Ok(SlaveStatus::WriteRequested) if tx_state => {
@@ -373,7 +403,7 @@ impl<Port: private::I2CPortCompatable> I2C<Port> {
                         .inspect_err(|_err| self.slave_underflow = true)?;
                     unsafe { self.reg.clear_slave_mode_transmit_fifo_underflow_flag() };
                     unsafe { self.reg.set_fifo_data(data) };
-                    unsafe { self.reg.clear_transmit_fifo_threshold_level() };
+                    unsafe { self.reg.clear_transmit_fifo_threshold_level_flag() };
                 }
                 Ok(SlaveStatus::TransferDone) => {
                     unsafe { self.reg.clear_transfer_complete_flag() };
@@ -447,13 +477,13 @@ impl<Port: private::I2CPortCompatable> I2C<Port> {
                     while !self.reg.get_receive_fifo_empty() {
                         rx(self.reg.get_fifo_data())?;
                     }
-                    // unsafe { self.reg.clear_receive_fifo_threshold_level() };
+                    // unsafe { self.reg.clear_receive_fifo_threshold_level_flag() };
                 }
                 Ok(SlaveStatus::WriteRequested) if tx_state => {
                     unsafe { self.reg.clear_slave_mode_transmit_fifo_underflow_flag() };
                     let data = tx()?;
                     unsafe { self.reg.set_fifo_data(data) };
-                    unsafe { self.reg.clear_transmit_fifo_threshold_level() };
+                    unsafe { self.reg.clear_transmit_fifo_threshold_level_flag() };
                 }
                 Ok(SlaveStatus::TransferDone) => {
                     unsafe { self.reg.clear_transfer_complete_flag() };
@@ -461,7 +491,7 @@ impl<Port: private::I2CPortCompatable> I2C<Port> {
                     // while !self.reg.get_receive_fifo_empty() {
                     // rx(self.reg.get_fifo_data())?;
                     // }
-                    // unsafe { self.reg.clear_receive_fifo_threshold_level() };
+                    // unsafe { self.reg.clear_receive_fifo_threshold_level_flag() };
                     debug_println!("Transfer Done");
                     tx_state = false;
                 }
@@ -508,8 +538,8 @@ impl<Port: private::I2CPortCompatable> I2C<Port> {
             self.reg
                 .is_slave_general_call_address_match_received_active(),
             self.reg.is_slave_incoming_address_match_status_active(),
-            self.reg.is_receive_fifo_threshold_level_active(),
-            self.reg.is_transmit_fifo_threshold_level_active(),
+            self.reg.is_receive_fifo_threshold_level_flag_active(),
+            self.reg.is_transmit_fifo_threshold_level_flag_active(),
             self.reg.is_slave_mode_stop_condition_active(),
             self.reg.is_master_ack_from_external_slave_active(),
             self.reg.is_master_mode_arbitration_lost_active(),
@@ -533,7 +563,7 @@ impl<Port: private::I2CPortCompatable> I2C<Port> {
             return Ok(MasterStatus::SlaveAck);
         }
 
-        if self.reg.is_receive_fifo_threshold_level_active() {
+        if self.reg.is_receive_fifo_threshold_level_flag_active() {
             return Ok(MasterStatus::ReadRequested);
         }
 
@@ -553,7 +583,7 @@ impl<Port: private::I2CPortCompatable> I2C<Port> {
             return Ok(MasterStatus::TransferDone);
         }
 
-        if self.reg.is_transmit_fifo_threshold_level_active() {
+        if self.reg.is_transmit_fifo_threshold_level_flag_active() {
             return Ok(MasterStatus::WriteRequested);
         }
 
@@ -643,7 +673,7 @@ impl<Port: private::I2CPortCompatable> I2C<Port> {
                         if self.write_fifo(&mut tx_iter).is_err() {
                             break;
                         }
-                        unsafe { self.reg.clear_transmit_fifo_threshold_level() };
+                        unsafe { self.reg.clear_transmit_fifo_threshold_level_flag() };
                     }
                     Ok(MasterStatus::TransferDone) => self.handle_i2c_master_error(
                         ErrorKind::Abort,
@@ -691,7 +721,7 @@ impl<Port: private::I2CPortCompatable> I2C<Port> {
                         while !self.reg.get_receive_fifo_empty() {
                             bytes_written += self.read_fifo(&mut rx[bytes_written..]);
                         }
-                        unsafe { self.reg.clear_receive_fifo_threshold_level() };
+                        unsafe { self.reg.clear_receive_fifo_threshold_level_flag() };
 
                         if bytes_written < rx.len() {
                             let read_amount = rx.len() - bytes_written;
@@ -712,7 +742,7 @@ impl<Port: private::I2CPortCompatable> I2C<Port> {
                         while !self.reg.get_receive_fifo_empty() {
                             bytes_written += self.read_fifo(&mut rx[bytes_written..]);
                         }
-                        unsafe { self.reg.clear_receive_fifo_threshold_level() };
+                        unsafe { self.reg.clear_receive_fifo_threshold_level_flag() };
                     }
                     Ok(_) => (),
                     Err(err) => self.handle_i2c_master_error(err, "COMM ERROR")?,
@@ -732,17 +762,28 @@ impl<Port: private::I2CPortCompatable> I2C<Port> {
         Ok(())
     }
 
-    fn set_freq(&mut self, hz: usize) -> Result<usize> {
-        if hz > MAX_I2C_HIGHSPEED_CLOCK_TIME {
+    /// # Set Freq
+    /// Set the bus clock, returning the closest clock the hardware could make.
+    ///
+    /// # Errors
+    /// Returns `ErrorKind::BadParam` if `freq` is faster than high speed mode, or too
+    /// fast for the peripheral clock.
+    ///
+    /// # Panics
+    /// High speed mode (over 1 MHz) is not supported yet.
+    pub fn set_freq(&mut self, freq: Hertz) -> Result<Hertz> {
+        if freq > MAX_I2C_HIGHSPEED_CLOCK_TIME || freq.raw() == 0 {
             return Err(ErrorKind::BadParam);
         }
 
-        if hz <= MAX_I2C_HIGHSPEED_CLOCK_TIME && hz > MAX_I2C_FASTPLUS_CLOCK_TIME {
+        if freq <= MAX_I2C_HIGHSPEED_CLOCK_TIME && freq > MAX_I2C_FASTPLUS_CLOCK_TIME {
             todo!("Highspeed I2C Mode is currently not supported");
         }
 
-        let peripheral_clock = core_peripheral_clock() as usize;
-        let ticks_total = peripheral_clock / hz;
+        let ticks_total = (core_peripheral_clock() / freq) as usize;
+        if ticks_total < 4 {
+            return Err(ErrorKind::BadParam);
+        }
         let high_clock_time = (ticks_total >> 1) - 1;
         let low_clock_time = (ticks_total >> 1) - 1;
 
@@ -762,7 +803,12 @@ impl<Port: private::I2CPortCompatable> I2C<Port> {
         Ok(self.get_freq())
     }
 
-    fn get_freq(&self) -> usize {
+    /// # Get Freq
+    /// Get the bus clock.
+    ///
+    /// # Panics
+    /// High speed mode is not supported yet.
+    pub fn get_freq(&self) -> Hertz {
         if self.reg.get_high_speed_mode() {
             todo!("Highspeed I2C Mode is currently not supported");
         }
@@ -775,7 +821,7 @@ impl<Port: private::I2CPortCompatable> I2C<Port> {
 
         let cycles_total = cycles_low + cycles_high;
 
-        (core_peripheral_clock() as usize) / (cycles_total as usize)
+        core_peripheral_clock() / cycles_total as u32
     }
 
     fn write_fifo<Bytes>(&mut self, tx: &mut Bytes) -> Result<usize>
@@ -1017,3 +1063,83 @@ impl<Port: private::I2CPortCompatable> I2C<Port> {
         Ok(())
     }
 }
+
+impl<Port: private::I2CPortCompatable> crate::pac::IntoPac for I2C<Port> {
+    fn free<P: crate::pac::PacPeripheral>(self) -> Result<P> {
+        crate::pac::free_into(self, Port::PORT_PTR)
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl<Port: private::I2CPortCompatable> embedded_hal_0_2::blocking::i2c::Write for I2C<Port> {
+    type Error = crate::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> core::result::Result<(), Self::Error> {
+        self.master_transaction(address as usize, None, Some(bytes))
+            .map_err(|kind| kind.within(crate::error::Peripheral::I2c))
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl<Port: private::I2CPortCompatable> embedded_hal_0_2::blocking::i2c::Read for I2C<Port> {
+    type Error = crate::Error;
+
+    fn read(&mut self, address: u8, buffer: &mut [u8]) -> core::result::Result<(), Self::Error> {
+        self.master_transaction(address as usize, Some(buffer), None)
+            .map_err(|kind| kind.within(crate::error::Peripheral::I2c))
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl<Port: private::I2CPortCompatable> embedded_hal_0_2::blocking::i2c::WriteRead for I2C<Port> {
+    type Error = crate::Error;
+
+    fn write_read(
+        &mut self,
+        address: u8,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> core::result::Result<(), Self::Error> {
+        self.master_transaction(address as usize, Some(buffer), Some(bytes))
+            .map_err(|kind| kind.within(crate::error::Peripheral::I2c))
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<Port: private::I2CPortCompatable> embedded_hal::i2c::ErrorType for I2C<Port> {
+    type Error = crate::Error;
+}
+
+/// A write followed by a read is sent with a repeated start, every other operation
+/// gets a transfer of its own.
+#[cfg(feature = "eh1")]
+impl<Port: private::I2CPortCompatable> embedded_hal::i2c::I2c for I2C<Port> {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> core::result::Result<(), Self::Error> {
+        use embedded_hal::i2c::Operation;
+
+        let address = address as usize;
+        let mut operations = operations.iter_mut().peekable();
+
+        while let Some(operation) = operations.next() {
+            let result = match operation {
+                Operation::Write(bytes) => match operations.peek_mut() {
+                    Some(Operation::Read(buffer)) => {
+                        let result = self.master_transaction(address, Some(buffer), Some(bytes));
+                        operations.next();
+                        result
+                    }
+                    _ => self.master_transaction(address, None, Some(bytes)),
+                },
+                Operation::Read(buffer) => self.master_transaction(address, Some(buffer), None),
+            };
+
+            result.map_err(|kind| kind.within(crate::error::Peripheral::I2c))?;
+        }
+
+        Ok(())
+    }
+}
@@ -353,7 +353,7 @@ make_device! {
     /// - 0: Transmit FIFO contains more bytes than the transmit threshold level.
     /// - 1: Transmit FIFO contains less bytes than the transmit threshold level.
     #[bit(5, RW1C, rro::I2C_INTFL0)]
-    transmit_fifo_threshold_level,
+    transmit_fifo_threshold_level_flag,
 
     /// Receive FIFO Threshold Level
     /// (MAYBE ERROR IN DOCUMENTATION PAGE 228 MAX78000 USER GUIDE)
@@ -364,7 +364,7 @@ make_device! {
     /// - 0: Receive FIFO contains more bytes than the transmit threshold level.
     /// - 1: Receive FIFO contains less bytes than the transmit threshold level.
     #[bit(4, RW1C, rro::I2C_INTFL0)]
-    receive_fifo_threshold_level,
+    receive_fifo_threshold_level_flag,
 
     /// Slave mode Incoming Address Match Status
     /// If the controller is configured for Slave mode, the hardware will set this flag is the incoming address
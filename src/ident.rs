@@ -0,0 +1,129 @@
+use crate::gcr::registers::Instance as GcrInstance;
+use crate::memory_map::{memory, mmio};
+
+/// # USN Length
+/// The number of bytes in the unique serial number.
+pub const USN_LEN: usize = 13;
+
+/// # Flash Controller Access Control
+/// The `FLC_ACTRL` register, writing the unlock sequence into it opens the info block.
+/// See MAX78000 User Guide, Table 7-5.
+const FLC_ACTRL: usize = mmio::FLASH_CONTROLLER_0 + 0x0040;
+
+/// # Info Block Unlock Sequence
+/// Written into `FLC_ACTRL` in order to unlock the info block, any other write locks it.
+const INFO_BLOCK_UNLOCK: [u32; 3] = [0x3a7f_5ca3, 0xa1e3_4f20, 0x9608_b2c1];
+
+/// # Info Block USN Words
+/// The number of info block words the USN is spread over.
+const USN_WORDS: usize = 6;
+
+/// # Part Info
+/// What kind of chip this is, and how much memory it has.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PartInfo {
+    /// The silicon revision, ie. `0xA1` for revision A1.
+    pub revision: u16,
+    /// The size of the internal flash in bytes.
+    pub flash_size: usize,
+    /// The size of the system RAM in bytes.
+    pub sram_size: usize,
+}
+
+/// # Revision
+/// Read the silicon revision from the `GCR_REVISION` register, ie. `0xA1` for A1.
+pub fn revision() -> u16 {
+    GcrInstance::<{ mmio::GLOBAL_CONTROL }>::instance().get_device_revision()
+}
+
+/// # Part Info
+/// Get the silicon revision, along with the flash and RAM sizes. The MAX78000 only
+/// comes in one memory configuration, so the sizes are not read from the chip.
+pub fn part_info() -> PartInfo {
+    PartInfo {
+        revision: revision(),
+        flash_size: memory::FLASH.1,
+        sram_size: memory::SRAM.1,
+    }
+}
+
+/// # Unique Serial Number
+/// Read the factory programmed unique serial number (USN) from the flash info block.
+///
+/// The info block is unlocked for the duration of the read, and locked again after.
+///
+/// # Safety
+/// The caller must ensure no flash operation is in progress, and that nothing else
+/// (ie. a interrupt handler) uses the flash controller until this returns.
+pub unsafe fn usn() -> [u8; USN_LEN] {
+    for key in INFO_BLOCK_UNLOCK {
+        core::ptr::write_volatile(hal_macros::mmio_ptr::<u32>(FLC_ACTRL), key);
+    }
+
+    let mut words = [0u32; USN_WORDS];
+    for (index, word) in words.iter_mut().enumerate() {
+        *word = core::ptr::read_volatile(hal_macros::mmio_ptr::<u32>(
+            memory::INFO_BLOCK.0 + index * 4,
+        ));
+    }
+
+    core::ptr::write_volatile(hal_macros::mmio_ptr::<u32>(FLC_ACTRL), 0);
+
+    decode_usn(&words)
+}
+
+/// # Decode USN
+/// The USN is stored in bits `15..=62` of each pair of info block words, skipping
+/// the low bits (used for the checksum).
+fn decode_usn(words: &[u32; USN_WORDS]) -> [u8; USN_LEN] {
+    let mut usn = [0u8; USN_LEN];
+
+    for (pair, bytes) in usn.chunks_mut(6).enumerate() {
+        let low = words[pair * 2] as u64;
+        let high = words[pair * 2 + 1] as u64;
+        let bits = ((high << 32) | low) >> 15;
+
+        for (index, byte) in bytes.iter_mut().enumerate() {
+            *byte = (bits >> (index * 8)) as u8;
+        }
+    }
+
+    usn
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_usn_layout() {
+        // Each USN byte is its own index, packed like the info block would be
+        let mut words = [0u32; USN_WORDS];
+        for pair in 0..3 {
+            let mut bits: u64 = 0;
+            for index in 0..6 {
+                bits |= ((pair * 6 + index) as u64) << (index * 8);
+            }
+            bits <<= 15;
+            words[pair * 2] = bits as u32;
+            words[pair * 2 + 1] = (bits >> 32) as u32;
+        }
+
+        let usn = decode_usn(&words);
+        for (index, byte) in usn.iter().enumerate() {
+            assert_eq!(*byte as usize, index);
+        }
+    }
+
+    #[test]
+    fn decode_usn_known_bits() {
+        let mut words = [0u32; USN_WORDS];
+        words[0] = (0xAB << 15) | (1 << 31);
+        words[1] = 0x7F;
+
+        let usn = decode_usn(&words);
+        assert_eq!(usn[0], 0xAB);
+        assert_eq!(usn[2], 0xFF);
+    }
+}
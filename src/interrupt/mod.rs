@@ -0,0 +1,435 @@
+pub mod priorities;
+pub mod registers;
+pub mod software;
+
+use priorities::Priority;
+use registers::rro;
+
+/// # Declare Interrupts
+/// Generate the `Interrupt` enum, and one zero sized token per interrupt in
+/// `typelevel`, from a single table so both always stay in sync.
+macro_rules! declare_interrupts {
+    ($($(#[$meta:meta])* $name:ident = $number:literal,)*) => {
+        /// # Interrupt
+        /// Every external interrupt line of the MAX78000 Cortex-M4 NVIC. The discriminant
+        /// is the IRQ number (exception number - 16). Reserved lines are not listed.
+        /// The numbers are the `IRQn_Type` of the MSDK's `max78000.h`, whose comments
+        /// give the vector table entry (IRQ number + 16). The `Interrupt` of the
+        /// `max78000-pac` crate, generated from the SVD, agrees on every line it lists.
+        #[non_exhaustive]
+        #[repr(u16)]
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+        pub enum Interrupt {
+            $($(#[$meta])* $name = $number,)*
+        }
+
+        impl TryFrom<u16> for Interrupt {
+            type Error = crate::error::ErrorKind;
+
+            fn try_from(value: u16) -> crate::error::Result<Self> {
+                match value {
+                    $($number => Ok(Self::$name),)*
+                    _ => Err(crate::error::ErrorKind::NoDevice),
+                }
+            }
+        }
+
+        /// # Type Level Interrupts
+        /// Zero sized tokens for every interrupt. Drivers name these in their
+        /// constructors (ie. `impl Binding<typelevel::TMR0, Handler>`) so the
+        /// compiler can check that the correct handler was bound.
+        pub mod typelevel {
+            mod sealed {
+                pub trait Sealed {}
+            }
+
+            $(
+                $(#[$meta])*
+                #[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+                pub enum $name {}
+                impl sealed::Sealed for $name {}
+                impl Interrupt for $name {
+                    const IRQ: super::Interrupt = super::Interrupt::$name;
+                }
+            )*
+
+            /// # Type Level Interrupt
+            /// A interrupt line known at compile time.
+            pub trait Interrupt: sealed::Sealed + 'static {
+                /// # IRQ
+                /// The runtime interrupt this type represents.
+                const IRQ: super::Interrupt;
+
+                /// # Enable
+                /// Enable this interrupt in the NVIC.
+                ///
+                /// # Safety
+                /// Enabling a interrupt can break critical sections that depend on it
+                /// being masked.
+                #[inline(always)]
+                unsafe fn enable() {
+                    super::InterruptExt::enable(Self::IRQ)
+                }
+
+                /// # Disable
+                /// Disable this interrupt in the NVIC.
+                #[inline(always)]
+                fn disable() {
+                    super::InterruptExt::disable(Self::IRQ)
+                }
+
+                /// # Is Enabled
+                /// Check if this interrupt is enabled in the NVIC.
+                #[inline(always)]
+                fn is_enabled() -> bool {
+                    super::InterruptExt::is_enabled(Self::IRQ)
+                }
+
+                /// # Pend
+                /// Set this interrupt pending.
+                #[inline(always)]
+                fn pend() {
+                    super::InterruptExt::pend(Self::IRQ)
+                }
+
+                /// # Unpend
+                /// Clear the pending state of this interrupt.
+                #[inline(always)]
+                fn unpend() {
+                    super::InterruptExt::unpend(Self::IRQ)
+                }
+
+                /// # Is Pending
+                /// Check if this interrupt is pending.
+                #[inline(always)]
+                fn is_pending() -> bool {
+                    super::InterruptExt::is_pending(Self::IRQ)
+                }
+
+                /// # Set Priority
+                /// Set the NVIC priority of this interrupt.
+                ///
+                /// # Safety
+                /// See `InterruptExt::set_priority`.
+                #[inline(always)]
+                unsafe fn set_priority(priority: super::Priority) {
+                    super::InterruptExt::set_priority(Self::IRQ, priority)
+                }
+
+                /// # Get Priority
+                /// Get the NVIC priority of this interrupt.
+                #[inline(always)]
+                fn get_priority() -> super::Priority {
+                    super::InterruptExt::get_priority(Self::IRQ)
+                }
+            }
+
+            /// # Handler
+            /// Interrupt handler trait. Drivers implement this for their interrupt
+            /// handler types, and `bind_interrupts!` calls `on_interrupt` from the
+            /// vector table entry.
+            pub trait Handler<I: Interrupt> {
+                /// # On Interrupt
+                /// Called from the interrupt vector.
+                ///
+                /// # Safety
+                /// This function must only be called by the interrupt vector of `I`.
+                unsafe fn on_interrupt();
+            }
+
+            /// # Binding
+            /// Proof that the interrupt `I` was bound to the handler `H`. Only
+            /// `bind_interrupts!` should implement this.
+            ///
+            /// # Safety
+            /// Implementing this without actually routing the vector of `I` to `H`
+            /// would leave a driver waiting for interrupts that are never serviced.
+            pub unsafe trait Binding<I: Interrupt, H: Handler<I>> {}
+        }
+    };
+}
+
+declare_interrupts! {
+    /// Power Fail
+    PF = 0,
+    /// Watchdog Timer 0
+    WDT0 = 1,
+    /// Real-Time Clock
+    RTC = 3,
+    /// True Random Number Generator
+    TRNG = 4,
+    /// Timer 0
+    TMR0 = 5,
+    /// Timer 1
+    TMR1 = 6,
+    /// Timer 2
+    TMR2 = 7,
+    /// Timer 3
+    TMR3 = 8,
+    /// Low Power Timer 0 (TMR4)
+    TMR4 = 9,
+    /// Low Power Timer 1 (TMR5)
+    TMR5 = 10,
+    /// I2C Port 0
+    I2C0 = 13,
+    /// UART 0
+    UART0 = 14,
+    /// UART 1
+    UART1 = 15,
+    /// SPI 1
+    SPI1 = 16,
+    /// ADC
+    ADC = 20,
+    /// Flash Controller 0
+    FLC0 = 23,
+    /// GPIO Port 0
+    GPIO0 = 24,
+    /// GPIO Port 1
+    GPIO1 = 25,
+    /// GPIO Port 2
+    GPIO2 = 26,
+    /// DMA Channel 0
+    DMA0 = 28,
+    /// DMA Channel 1
+    DMA1 = 29,
+    /// DMA Channel 2
+    DMA2 = 30,
+    /// DMA Channel 3
+    DMA3 = 31,
+    /// UART 2
+    UART2 = 34,
+    /// I2C Port 1
+    I2C1 = 36,
+    /// Wakeup Timer
+    WUT = 53,
+    /// GPIO Wakeup
+    GPIOWAKE = 54,
+    /// SPI 0
+    SPI0 = 56,
+    /// Low Power Watchdog Timer (WDT1)
+    WDT1 = 57,
+    /// Pulse Train Engine
+    PT = 59,
+    /// I2C Port 2
+    I2C2 = 62,
+    /// RISC-V Core
+    RISCV = 63,
+    /// 1-Wire Master
+    OWM = 67,
+    /// Error Correction Coding
+    ECC = 82,
+    /// Dynamic Voltage Scaling
+    DVS = 83,
+    /// Single Input Multiple Output
+    SIMO = 84,
+    /// Low Power UART 0 (UART3)
+    UART3 = 88,
+    /// Parallel Camera Interface
+    PCIF = 91,
+    /// AES
+    AES = 97,
+    /// I2S
+    I2S = 99,
+    /// CNN FIFO
+    CNN_FIFO = 100,
+    /// CNN
+    CNN = 101,
+    /// Low Power Comparators
+    LPCMP = 103,
+}
+
+/// # Interrupt Count
+/// The number of external interrupt lines (including reserved lines) the NVIC has,
+/// `MXC_IRQ_EXT_COUNT` in `max78000.h`.
+pub const INTERRUPT_COUNT: u16 = 104;
+
+/// # Interrupt Ext
+/// NVIC control of a interrupt line.
+pub trait InterruptExt: Copy {
+    /// # Number
+    /// The IRQ number of this interrupt.
+    fn number(self) -> u16;
+
+    /// # Enable
+    /// Enable the interrupt in the NVIC.
+    ///
+    /// # Safety
+    /// Enabling a interrupt can break critical sections that depend on it
+    /// being masked.
+    #[inline(always)]
+    unsafe fn enable(self) {
+        registers::write_bit(rro::NVIC_ISER, self.number());
+    }
+
+    /// # Disable
+    /// Disable the interrupt in the NVIC.
+    #[inline(always)]
+    fn disable(self) {
+        unsafe { registers::write_bit(rro::NVIC_ICER, self.number()) };
+    }
+
+    /// # Is Enabled
+    /// Check if the interrupt is enabled in the NVIC.
+    #[inline(always)]
+    fn is_enabled(self) -> bool {
+        unsafe { registers::read_bit(rro::NVIC_ISER, self.number()) }
+    }
+
+    /// # Pend
+    /// Set the interrupt pending, the handler will run once its priority allows.
+    #[inline(always)]
+    fn pend(self) {
+        unsafe { registers::write_bit(rro::NVIC_ISPR, self.number()) };
+    }
+
+    /// # Unpend
+    /// Clear the pending state of the interrupt.
+    #[inline(always)]
+    fn unpend(self) {
+        unsafe { registers::write_bit(rro::NVIC_ICPR, self.number()) };
+    }
+
+    /// # Is Pending
+    /// Check if the interrupt is pending.
+    #[inline(always)]
+    fn is_pending(self) -> bool {
+        unsafe { registers::read_bit(rro::NVIC_ISPR, self.number()) }
+    }
+
+    /// # Is Active
+    /// Check if the interrupt handler is currently running (or was preempted).
+    #[inline(always)]
+    fn is_active(self) -> bool {
+        unsafe { registers::read_bit(rro::NVIC_IABR, self.number()) }
+    }
+
+    /// # Set Priority
+    /// Set the NVIC priority of the interrupt.
+    ///
+    /// # Safety
+    /// Changing priorities can break priority based critical sections.
+    #[inline(always)]
+    unsafe fn set_priority(self, priority: Priority) {
+        priorities::set_priority(self, priority)
+    }
+
+    /// # Get Priority
+    /// Get the NVIC priority of the interrupt.
+    #[inline(always)]
+    fn get_priority(self) -> Priority {
+        priorities::get_priority(self)
+    }
+}
+
+impl InterruptExt for Interrupt {
+    #[inline(always)]
+    fn number(self) -> u16 {
+        self as u16
+    }
+}
+
+/// # Bind Interrupts
+/// Bind interrupt vectors to driver interrupt handlers, and generate a zero sized
+/// struct proving the binding exists. Drivers then take this struct in their
+/// constructors as `impl Binding<typelevel::IRQ, Handler>`.
+///
+/// The vectors are exported with the `<IRQ>_IRQHandler` names used by the Maxim
+/// startup file, so they override its weak default handlers.
+///
+/// # Example
+/// ```no_run
+/// use max78000_hal::bind_interrupts;
+/// use max78000_hal::interrupt::typelevel::{Handler, TMR0};
+///
+/// struct MyHandler;
+/// impl Handler<TMR0> for MyHandler {
+///     unsafe fn on_interrupt() {}
+/// }
+///
+/// bind_interrupts!(struct Irqs {
+///     TMR0 => MyHandler;
+/// });
+/// ```
+#[macro_export]
+macro_rules! bind_interrupts {
+    ($vis:vis struct $name:ident { $($irq:ident => $($handler:ty),*;)* }) => {
+        #[derive(Copy, Clone)]
+        $vis struct $name;
+
+        $(
+            #[allow(non_snake_case)]
+            #[export_name = concat!(stringify!($irq), "_IRQHandler")]
+            unsafe extern "C" fn $irq() {
+                $(
+                    <$handler as $crate::interrupt::typelevel::Handler<
+                        $crate::interrupt::typelevel::$irq,
+                    >>::on_interrupt();
+                )*
+            }
+
+            $(
+                unsafe impl $crate::interrupt::typelevel::Binding<
+                    $crate::interrupt::typelevel::$irq,
+                    $handler,
+                > for $name {}
+            )*
+        )*
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn interrupt_number_round_trip() {
+        for number in 0..INTERRUPT_COUNT {
+            if let Ok(irq) = Interrupt::try_from(number) {
+                assert_eq!(irq.number(), number);
+            }
+        }
+    }
+
+    #[test]
+    fn reserved_lines_are_not_interrupts() {
+        assert!(Interrupt::try_from(2).is_err());
+        assert!(Interrupt::try_from(INTERRUPT_COUNT).is_err());
+        assert!(Interrupt::try_from(5).is_ok_and(|irq| irq == Interrupt::TMR0));
+        for line in software::SOFTWARE_EVENT_LINES {
+            assert!(Interrupt::try_from(line).is_err());
+        }
+    }
+
+    #[test]
+    fn numbers_match_the_sdk() {
+        // Vector table entries from the comments of `IRQn_Type` in `max78000.h`
+        let entries = [
+            (Interrupt::I2C1, 52),
+            (Interrupt::GPIOWAKE, 70),
+            (Interrupt::SPI0, 72),
+            (Interrupt::UART3, 104),
+            (Interrupt::LPCMP, 119),
+        ];
+        for (irq, entry) in entries {
+            assert_eq!(irq.number() + 16, entry);
+        }
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn mock_enable_and_priority() {
+        crate::mock::reset();
+
+        unsafe {
+            Interrupt::UART2.enable();
+            Interrupt::UART2.set_priority(Priority::P3);
+        }
+
+        assert!(Interrupt::UART2.is_enabled());
+        assert!(!Interrupt::UART1.is_enabled());
+        assert_eq!(Interrupt::UART2.get_priority(), Priority::P3);
+        // UART2 is IRQ 34, so it lives in bit 2 of the second ISER bank
+        assert_eq!(crate::mock::read(rro::NVIC_ISER + 4), 1 << 2);
+    }
+}
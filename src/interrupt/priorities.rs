@@ -0,0 +1,183 @@
+use super::{registers, InterruptExt};
+
+/// # Priority Bits
+/// The number of priority bits the MAX78000's Cortex-M4 implements. Only the upper
+/// `PRIORITY_BITS` of every 8-bit NVIC priority field are stored, the rest read as zero.
+pub const PRIORITY_BITS: u8 = 3;
+
+/// # Priority Levels
+/// The number of distinct priority levels available.
+pub const PRIORITY_LEVELS: u8 = 1 << PRIORITY_BITS;
+
+/// # Priority
+/// A NVIC priority level that the hardware can actually represent. `P0` is the most
+/// urgent level and will preempt every other level, `P7` is the least urgent.
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Priority {
+    P0 = 0,
+    P1 = 1,
+    P2 = 2,
+    P3 = 3,
+    P4 = 4,
+    P5 = 5,
+    P6 = 6,
+    P7 = 7,
+}
+
+impl Priority {
+    /// # Highest
+    /// The most urgent priority level.
+    pub const HIGHEST: Self = Self::P0;
+
+    /// # Lowest
+    /// The least urgent priority level, and the level every interrupt resets to.
+    pub const LOWEST: Self = Self::P7;
+
+    /// # New
+    /// Make a priority from its level, `None` if the level is not implemented.
+    pub const fn new(level: u8) -> Option<Self> {
+        match level {
+            0 => Some(Self::P0),
+            1 => Some(Self::P1),
+            2 => Some(Self::P2),
+            3 => Some(Self::P3),
+            4 => Some(Self::P4),
+            5 => Some(Self::P5),
+            6 => Some(Self::P6),
+            7 => Some(Self::P7),
+            _ => None,
+        }
+    }
+
+    /// # Level
+    /// The priority level, where `0` is the most urgent.
+    pub const fn level(self) -> u8 {
+        self as u8
+    }
+
+    /// # From Raw
+    /// Decode a raw 8-bit NVIC priority field, ignoring the unimplemented bits.
+    pub const fn from_raw(raw: u8) -> Self {
+        match Self::new(raw >> (8 - PRIORITY_BITS)) {
+            Some(priority) => priority,
+            None => unreachable!(),
+        }
+    }
+
+    /// # Into Raw
+    /// Encode this priority as a raw 8-bit NVIC priority field.
+    pub const fn into_raw(self) -> u8 {
+        (self as u8) << (8 - PRIORITY_BITS)
+    }
+
+    /// # Is Higher Than
+    /// Check if this priority preempts `other`.
+    pub const fn is_higher_than(self, other: Self) -> bool {
+        (self as u8) < (other as u8)
+    }
+
+    /// # From Logical
+    /// Make a priority from a RTIC style logical priority, where `1` is the least
+    /// urgent and `PRIORITY_LEVELS` is the most urgent. `None` if out of range.
+    pub const fn from_logical(logical: u8) -> Option<Self> {
+        if logical == 0 || logical > PRIORITY_LEVELS {
+            None
+        } else {
+            Self::new(PRIORITY_LEVELS - logical)
+        }
+    }
+
+    /// # Into Logical
+    /// The RTIC style logical priority of this level.
+    pub const fn into_logical(self) -> u8 {
+        PRIORITY_LEVELS - self as u8
+    }
+}
+
+/// # Set Priority
+/// Set the priority of the given interrupt.
+///
+/// # Safety
+/// Changing priorities can break priority based critical sections (ie. RTIC resources,
+/// or a embassy executor running at a fixed priority).
+pub unsafe fn set_priority(irq: impl InterruptExt, priority: Priority) {
+    registers::write_priority(irq.number(), priority.into_raw());
+}
+
+/// # Get Priority
+/// Get the priority of the given interrupt.
+pub fn get_priority(irq: impl InterruptExt) -> Priority {
+    Priority::from_raw(unsafe { registers::read_priority(irq.number()) })
+}
+
+/// # Priority Layout
+/// Name the priority bands of a application once, and check at compile time that they
+/// are listed from most to least urgent without sharing a level. The generated
+/// module holds one `Priority` const per band, ready to hand to executors or
+/// `set_priority`.
+///
+/// # Example
+/// ```
+/// use max78000_hal::priority_layout;
+/// use max78000_hal::interrupt::priorities::Priority;
+///
+/// priority_layout! {
+///     pub mod prio {
+///         DRIVERS = P1,
+///         EXECUTOR_HIGH = P3,
+///         EXECUTOR_LOW = P6,
+///     }
+/// }
+///
+/// assert!(prio::DRIVERS.is_higher_than(prio::EXECUTOR_HIGH));
+/// assert_eq!(prio::EXECUTOR_LOW, Priority::P6);
+/// ```
+#[macro_export]
+macro_rules! priority_layout {
+    ($vis:vis mod $name:ident { $($band:ident = $level:ident),+ $(,)? }) => {
+        $vis mod $name {
+            $(
+                pub const $band: $crate::interrupt::priorities::Priority =
+                    $crate::interrupt::priorities::Priority::$level;
+            )+
+
+            const _: () = {
+                let bands = [$($band),+];
+                let mut i = 1;
+                while i < bands.len() {
+                    assert!(
+                        bands[i - 1].is_higher_than(bands[i]),
+                        "Priority bands must be listed from most to least urgent, without sharing a level!"
+                    );
+                    i += 1;
+                }
+            };
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn raw_round_trip() {
+        for level in 0..PRIORITY_LEVELS {
+            let priority = Priority::new(level).unwrap();
+            assert_eq!(Priority::from_raw(priority.into_raw()), priority);
+            assert_eq!(priority.into_raw() & 0x1F, 0);
+        }
+        assert_eq!(Priority::from_raw(0xFF), Priority::P7);
+        assert_eq!(Priority::new(PRIORITY_LEVELS), None);
+    }
+
+    #[test]
+    fn logical_priorities() {
+        assert_eq!(Priority::from_logical(1), Some(Priority::LOWEST));
+        assert_eq!(Priority::from_logical(8), Some(Priority::HIGHEST));
+        assert_eq!(Priority::from_logical(0), None);
+        assert_eq!(Priority::from_logical(9), None);
+        assert_eq!(Priority::P5.into_logical(), 3);
+    }
+}
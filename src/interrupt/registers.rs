@@ -0,0 +1,62 @@
+pub(crate) type BaseOffset = usize;
+pub(crate) type IrqNumber = u16;
+
+/// # NVIC Register Bases
+/// The NVIC keeps one bit per interrupt line, packed 32 lines per register.
+/// These are the first register of every bank. See ARMv7-M Reference, B3.4.3.
+#[allow(unused)]
+pub(crate) mod rro {
+    use super::BaseOffset;
+    use crate::memory_map::scs;
+
+    /// # Interrupt Set-Enable Registers
+    pub const NVIC_ISER: BaseOffset = scs::NVIC_ISER;
+    /// # Interrupt Clear-Enable Registers
+    pub const NVIC_ICER: BaseOffset = scs::NVIC_ICER;
+    /// # Interrupt Set-Pending Registers
+    pub const NVIC_ISPR: BaseOffset = scs::NVIC_ISPR;
+    /// # Interrupt Clear-Pending Registers
+    pub const NVIC_ICPR: BaseOffset = scs::NVIC_ICPR;
+    /// # Interrupt Active Bit Registers
+    pub const NVIC_IABR: BaseOffset = scs::NVIC_IABR;
+    /// # Interrupt Priority Registers (one byte per line)
+    pub const NVIC_IPR: BaseOffset = scs::NVIC_IPR;
+}
+
+#[inline(always)]
+fn bank_ptr(base: BaseOffset, irq: IrqNumber) -> *mut u32 {
+    hal_macros::mmio_ptr(base + ((irq as usize / 32) * 4))
+}
+
+#[inline(always)]
+fn bank_bit(irq: IrqNumber) -> u32 {
+    1 << (irq as u32 % 32)
+}
+
+/// # Write Bit
+/// Write a single `1` for the given interrupt line into a NVIC set/clear bank.
+/// These banks ignore `0` writes, so no read is required.
+pub(crate) unsafe fn write_bit(base: BaseOffset, irq: IrqNumber) {
+    core::ptr::write_volatile(bank_ptr(base, irq), bank_bit(irq));
+}
+
+/// # Read Bit
+/// Read the state of the given interrupt line from a NVIC bank.
+pub(crate) unsafe fn read_bit(base: BaseOffset, irq: IrqNumber) -> bool {
+    core::ptr::read_volatile(bank_ptr(base, irq)) & bank_bit(irq) != 0
+}
+
+/// # Write Priority
+/// Write the raw 8-bit priority field of the given interrupt line.
+pub(crate) unsafe fn write_priority(irq: IrqNumber, value: u8) {
+    core::ptr::write_volatile(
+        hal_macros::mmio_ptr::<u8>(rro::NVIC_IPR + irq as usize),
+        value,
+    );
+}
+
+/// # Read Priority
+/// Read the raw 8-bit priority field of the given interrupt line.
+pub(crate) unsafe fn read_priority(irq: IrqNumber) -> u8 {
+    core::ptr::read_volatile(hal_macros::mmio_ptr::<u8>(rro::NVIC_IPR + irq as usize))
+}
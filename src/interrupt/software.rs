@@ -0,0 +1,196 @@
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use super::{InterruptExt, INTERRUPT_COUNT};
+use crate::memory_map::scs;
+
+/// # Software Event Lines
+/// NVIC lines that are reserved on the MAX78000 (no peripheral drives them), so they
+/// can only ever be pended by software. These are `RSV12_IRQn` to `RSV19_IRQn` in the
+/// `IRQn_Type` of the MSDK's `max78000.h`, between I2C1 (36) and GPIOWAKE (54).
+pub const SOFTWARE_EVENT_LINES: [u16; 8] = [37, 38, 39, 40, 41, 42, 43, 44];
+
+/// # Vector Count
+/// The number of vectors in the table, the 16 core exceptions plus every IRQ line.
+const VECTOR_COUNT: usize = 16 + INTERRUPT_COUNT as usize;
+
+/// # RAM Vector Table
+/// The VTOR requires the table be aligned to the next power of two of its size.
+#[repr(C, align(512))]
+struct VectorTable([usize; VECTOR_COUNT]);
+
+static mut RAM_VECTORS: VectorTable = VectorTable([0; VECTOR_COUNT]);
+static VECTORS_RELOCATED: AtomicBool = AtomicBool::new(false);
+/// One bit per line in `SOFTWARE_EVENT_LINES`, set while a `SwEvent` owns it.
+static LINES_OWNED: AtomicU8 = AtomicU8::new(0);
+
+/// # Relocate Vector Table
+/// Copy the active vector table into RAM and point the VTOR at it, so handlers for
+/// software event lines can be installed at runtime. Every existing handler is kept.
+fn relocate_vector_table() {
+    if VECTORS_RELOCATED.load(Ordering::Acquire) {
+        return;
+    }
+
+    // Masked, so a handler making its own event can't copy over a table half set up
+    let masked = crate::asm::primask();
+    crate::asm::cpsid();
+    if !VECTORS_RELOCATED.load(Ordering::Relaxed) {
+        unsafe { copy_vector_table() };
+        VECTORS_RELOCATED.store(true, Ordering::Release);
+    }
+    if !masked {
+        crate::asm::cpsie();
+    }
+}
+
+/// # Copy Vector Table
+/// Copy the active vector table into `RAM_VECTORS`, and point the VTOR at it.
+///
+/// # Safety
+/// Only once, with interrupts masked, see `relocate_vector_table`.
+unsafe fn copy_vector_table() {
+    unsafe {
+        let vectors = &mut *core::ptr::addr_of_mut!(RAM_VECTORS);
+        let flash_table =
+            core::ptr::read_volatile(hal_macros::mmio_ptr::<u32>(scs::SCB_VTOR)) as *const usize;
+        for (index, vector) in vectors.0.iter_mut().enumerate() {
+            *vector = core::ptr::read_volatile(flash_table.add(index));
+        }
+
+        crate::asm::dsb();
+        core::ptr::write_volatile(
+            hal_macros::mmio_ptr::<u32>(scs::SCB_VTOR),
+            vectors.0.as_ptr() as u32,
+        );
+        crate::asm::dsb();
+        crate::asm::isb();
+    }
+}
+
+/// # Software Event
+/// A reserved interrupt line used as a cheap way to defer work. Calling `pend()` from
+/// a high priority context will run the registered handler once the CPU drops below
+/// the event's priority, without needing a RTOS.
+///
+/// # Example
+/// ```no_run
+/// use max78000_hal::interrupt::software::SwEvent;
+/// use max78000_hal::interrupt::InterruptExt;
+/// use max78000_hal::interrupt::priorities::Priority;
+///
+/// unsafe extern "C" fn process_samples() {
+///     // Lower priority work goes here
+/// }
+///
+/// let event = SwEvent::new(process_samples).unwrap();
+/// unsafe { (&event).set_priority(Priority::P6) };
+/// event.pend();
+/// ```
+pub struct SwEvent {
+    irq: u16,
+    slot: u8,
+}
+
+impl SwEvent {
+    /// # New
+    /// Reserve a free software event line, install `handler` as its vector and enable it.
+    /// `None` if every line in `SOFTWARE_EVENT_LINES` is already in use.
+    pub fn new(handler: unsafe extern "C" fn()) -> Option<Self> {
+        // The first free line is the lowest clear bit of what was owned before
+        let owned = LINES_OWNED
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |owned| {
+                (owned != u8::MAX).then(|| owned | (1 << owned.trailing_ones()))
+            })
+            .ok()?;
+        let slot = owned.trailing_ones() as u8;
+
+        let mut event = Self {
+            irq: SOFTWARE_EVENT_LINES[slot as usize],
+            slot,
+        };
+
+        relocate_vector_table();
+        event.set_handler(handler);
+        (&event).unpend();
+        unsafe { (&event).enable() };
+
+        Some(event)
+    }
+
+    /// # Set Handler
+    /// Replace the handler that runs when this event is serviced.
+    pub fn set_handler(&mut self, handler: unsafe extern "C" fn()) {
+        unsafe {
+            let vectors = core::ptr::addr_of_mut!(RAM_VECTORS.0);
+            core::ptr::write_volatile(&mut (*vectors)[16 + self.irq as usize], handler as usize);
+        }
+        crate::asm::dsb();
+    }
+
+    /// # Pend
+    /// Signal the event, its handler will run once its priority allows.
+    #[inline(always)]
+    pub fn pend(&self) {
+        InterruptExt::pend(self);
+    }
+}
+
+impl InterruptExt for &SwEvent {
+    #[inline(always)]
+    fn number(self) -> u16 {
+        self.irq
+    }
+}
+
+impl Drop for SwEvent {
+    fn drop(&mut self) {
+        InterruptExt::disable(&*self);
+        InterruptExt::unpend(&*self);
+        LINES_OWNED.fetch_and(!(1 << self.slot), Ordering::Release);
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::interrupt::registers::rro;
+
+    unsafe extern "C" fn first() {}
+    unsafe extern "C" fn second() {}
+
+    fn address(handler: unsafe extern "C" fn()) -> usize {
+        handler as usize
+    }
+
+    fn vector(irq: u16) -> usize {
+        unsafe { (*core::ptr::addr_of!(RAM_VECTORS)).0[16 + irq as usize] }
+    }
+
+    #[test]
+    fn events_claim_free_lines() {
+        crate::mock::reset();
+        // The host has no vector table in flash to copy
+        VECTORS_RELOCATED.store(true, Ordering::Relaxed);
+
+        let mut events: [Option<SwEvent>; 8] = Default::default();
+        for (event, line) in events.iter_mut().zip(SOFTWARE_EVENT_LINES) {
+            let claimed = SwEvent::new(first).unwrap();
+            assert_eq!((&claimed).number(), line);
+            assert!((&claimed).is_enabled());
+            assert_eq!(vector(line), address(first));
+            *event = Some(claimed);
+        }
+        assert!(SwEvent::new(first).is_none());
+
+        // Dropped, its line is the one handed out next
+        events[3] = None;
+        // IRQ 40 is bit 8 of the second bank
+        assert_eq!(crate::mock::read(rro::NVIC_ICER + 4), 1 << 8);
+        let mut event = SwEvent::new(second).unwrap();
+        assert_eq!((&event).number(), 40);
+        assert_eq!(vector(40), address(second));
+
+        event.set_handler(first);
+        assert_eq!(vector(40), address(first));
+    }
+}
@@ -1,28 +1,83 @@
 #![no_std]
 pub mod aes;
+pub mod asm;
 pub mod bits;
+#[cfg(any(feature = "board-fthr", feature = "board-evkit"))]
+pub mod board;
+pub mod bootloader;
+#[cfg(feature = "console")]
+pub mod console;
 pub mod debug;
+pub mod delay;
+#[cfg(feature = "display")]
+pub mod display;
+pub mod dma;
+pub mod encoder;
 pub mod error;
+pub mod fault;
+pub mod fcr;
+#[cfg(feature = "firmware-update")]
+pub mod firmware_update;
+pub mod flc;
 pub mod gcr;
 pub mod gpio;
+#[cfg(feature = "hiltest")]
+pub mod hiltest;
 pub mod i2c;
+pub mod ident;
+pub mod interrupt;
 pub mod memory_map;
+pub mod motion;
+pub mod mpu;
+pub mod pac;
+pub mod power;
+pub mod profiling;
+pub mod pwm_dac;
+pub mod ram;
+pub mod selftest;
+pub mod soft_pwm;
+pub mod spi;
+pub mod time;
 pub mod timer;
+pub mod touch;
+pub mod trim;
 pub mod trng;
 pub mod uart;
+pub mod watchdog;
 
 #[cfg(test)]
 pub mod tests;
 
+pub use error::Error;
+
+#[cfg(all(feature = "mock-mmio", not(target_os = "none")))]
+pub use hal_macros::mock;
+
+#[cfg(feature = "trace-mmio")]
+pub use hal_macros::trace;
+
 extern "C" {
     #[link_name = "SystemCoreClock"]
     pub static SYSTEM_CORE_CLOCK: u32;
 }
 
+/// The host has no startup code to define it, so the tests run at the MAX78000's reset
+/// clock, the 100 MHz IPO.
+#[cfg(test)]
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+static SystemCoreClock: u32 = 100_000_000;
+
+/// # Core Clock
+/// Get the clock the CPU runs at.
+pub fn core_clock() -> time::Hertz {
+    time::Hertz::from_raw(unsafe { SYSTEM_CORE_CLOCK })
+}
+
 /// # Core Peripheral Clock
 /// Get the peripheral clock used for timing things like I2C and UART for the CPU.
-pub fn core_peripheral_clock() -> u32 {
-    unsafe { SYSTEM_CORE_CLOCK / 2 }
+pub fn core_peripheral_clock() -> time::Hertz {
+    core_clock() / 2
 }
 
 /// # Const Assert
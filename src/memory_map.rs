@@ -159,3 +159,117 @@ pub mod mmio {
     /// The CNNx16 Quadrant 3 ptr.
     pub const CNNX16_QUADRANT_3: usize = 0x50d0_0000;
 }
+
+/// # Memory
+/// The on-chip memories, as `(start, size)` in bytes.
+/// Found at: MAX78000 Pg 31
+pub mod memory {
+    /// # ROM (ROM)
+    /// The boot ROM, holding the Maxim bootloader. Its vector table sits at the start.
+    pub const ROM: (usize, usize) = (0x0000_0000, 64 * 1024);
+    /// # Flash (FLASH)
+    /// The internal flash.
+    pub const FLASH: (usize, usize) = (0x1000_0000, 512 * 1024);
+    /// # Information Block (INFO)
+    /// The flash information block, holding factory data such as the USN.
+    pub const INFO_BLOCK: (usize, usize) = (0x1080_0000, 16 * 1024);
+    /// # System RAM (SRAM)
+    /// The system RAM.
+    pub const SRAM: (usize, usize) = (0x2000_0000, 128 * 1024);
+    /// # System RAM Bank 0 (SYSRAM0)
+    pub const SYSRAM_0: (usize, usize) = (0x2000_0000, 32 * 1024);
+    /// # System RAM Bank 1 (SYSRAM1)
+    pub const SYSRAM_1: (usize, usize) = (0x2000_8000, 32 * 1024);
+    /// # System RAM Bank 2 (SYSRAM2)
+    pub const SYSRAM_2: (usize, usize) = (0x2001_0000, 48 * 1024);
+    /// # System RAM Bank 3 (SYSRAM3)
+    pub const SYSRAM_3: (usize, usize) = (0x2001_C000, 16 * 1024);
+    /// # CNN Data RAM Quadrant 0 (CNNx16_0 SRAM)
+    /// Only 32-bit accesses are allowed.
+    pub const CNN_DATA_0: (usize, usize) = (0x5040_0000, 128 * 1024);
+    /// # CNN Data RAM Quadrant 1 (CNNx16_1 SRAM)
+    /// Only 32-bit accesses are allowed.
+    pub const CNN_DATA_1: (usize, usize) = (0x5080_0000, 128 * 1024);
+    /// # CNN Data RAM Quadrant 2 (CNNx16_2 SRAM)
+    /// Only 32-bit accesses are allowed.
+    pub const CNN_DATA_2: (usize, usize) = (0x50C0_0000, 128 * 1024);
+    /// # CNN Data RAM Quadrant 3 (CNNx16_3 SRAM)
+    /// Only 32-bit accesses are allowed.
+    pub const CNN_DATA_3: (usize, usize) = (0x5100_0000, 128 * 1024);
+}
+
+/// # System Control Space (SCS)
+/// The Cortex-M4 private peripheral space used for the core's own registers
+/// (NVIC, SCB, SysTick, ...). These sit outside the MAX78000 peripheral space.
+/// Found at: ARMv7-M Architecture Reference Manual, B3.2.2
+pub mod scs {
+    /// # DWT Control (DWT_CTRL)
+    /// The Data Watchpoint and Trace control register ptr.
+    pub const DWT_CTRL: usize = 0xE000_1000;
+    /// # DWT Cycle Count (DWT_CYCCNT)
+    /// The Data Watchpoint and Trace cycle count register ptr.
+    pub const DWT_CYCCNT: usize = 0xE000_1004;
+    /// # SysTick Control and Status (SYST_CSR)
+    /// The SysTick control and status register ptr.
+    pub const SYST_CSR: usize = 0xE000_E010;
+    /// # Interrupt Set-Enable (NVIC_ISER)
+    /// The first of the NVIC interrupt set-enable registers ptr.
+    pub const NVIC_ISER: usize = 0xE000_E100;
+    /// # Interrupt Clear-Enable (NVIC_ICER)
+    /// The first of the NVIC interrupt clear-enable registers ptr.
+    pub const NVIC_ICER: usize = 0xE000_E180;
+    /// # Interrupt Set-Pending (NVIC_ISPR)
+    /// The first of the NVIC interrupt set-pending registers ptr.
+    pub const NVIC_ISPR: usize = 0xE000_E200;
+    /// # Interrupt Clear-Pending (NVIC_ICPR)
+    /// The first of the NVIC interrupt clear-pending registers ptr.
+    pub const NVIC_ICPR: usize = 0xE000_E280;
+    /// # Interrupt Active Bit (NVIC_IABR)
+    /// The first of the NVIC interrupt active bit registers ptr.
+    pub const NVIC_IABR: usize = 0xE000_E300;
+    /// # Interrupt Priority (NVIC_IPR)
+    /// The first of the NVIC interrupt priority registers ptr.
+    pub const NVIC_IPR: usize = 0xE000_E400;
+    /// # Interrupt Control and State (SCB_ICSR)
+    /// The System Control Block interrupt control and state register ptr.
+    pub const SCB_ICSR: usize = 0xE000_ED04;
+    /// # Vector Table Offset (SCB_VTOR)
+    /// The System Control Block vector table offset register ptr.
+    pub const SCB_VTOR: usize = 0xE000_ED08;
+    /// # System Control (SCB_SCR)
+    /// The System Control Block system control register ptr.
+    pub const SCB_SCR: usize = 0xE000_ED10;
+    /// # System Handler Control and State (SCB_SHCSR)
+    /// The System Control Block system handler control and state register ptr.
+    pub const SCB_SHCSR: usize = 0xE000_ED24;
+    /// # Configurable Fault Status (SCB_CFSR)
+    /// The System Control Block configurable fault status register ptr.
+    pub const SCB_CFSR: usize = 0xE000_ED28;
+    /// # HardFault Status (SCB_HFSR)
+    /// The System Control Block HardFault status register ptr.
+    pub const SCB_HFSR: usize = 0xE000_ED2C;
+    /// # MemManage Fault Address (SCB_MMFAR)
+    /// The System Control Block MemManage fault address register ptr.
+    pub const SCB_MMFAR: usize = 0xE000_ED34;
+    /// # BusFault Address (SCB_BFAR)
+    /// The System Control Block BusFault address register ptr.
+    pub const SCB_BFAR: usize = 0xE000_ED38;
+    /// # MPU Type (MPU_TYPE)
+    /// The MPU type register ptr.
+    pub const MPU_TYPE: usize = 0xE000_ED90;
+    /// # MPU Control (MPU_CTRL)
+    /// The MPU control register ptr.
+    pub const MPU_CTRL: usize = 0xE000_ED94;
+    /// # MPU Region Number (MPU_RNR)
+    /// The MPU region number register ptr.
+    pub const MPU_RNR: usize = 0xE000_ED98;
+    /// # MPU Region Base Address (MPU_RBAR)
+    /// The MPU region base address register ptr.
+    pub const MPU_RBAR: usize = 0xE000_ED9C;
+    /// # MPU Region Attribute and Size (MPU_RASR)
+    /// The MPU region attribute and size register ptr.
+    pub const MPU_RASR: usize = 0xE000_EDA0;
+    /// # Debug Exception and Monitor Control (DEMCR)
+    /// The debug exception and monitor control register ptr.
+    pub const DEMCR: usize = 0xE000_EDFC;
+}
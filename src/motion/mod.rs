@@ -0,0 +1,9 @@
+//! # Motion
+//! Helpers for driving motors from the timers: hobby servos (`Servo`) from a timer in
+//! PWM mode, and step/direction stepper drivers (`Stepper`) from a timer interrupt with
+//! acceleration ramps.
+mod servo;
+mod stepper;
+
+pub use servo::Servo;
+pub use stepper::{Ramp, Stepper};
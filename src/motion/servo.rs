@@ -0,0 +1,180 @@
+use crate::error::{ErrorKind, Result};
+use crate::gcr::{peripheral_reset, system_clock_enable};
+use crate::time::{Hertz, Micros};
+use crate::timer::registers::{TimerMode, TimerPrescaler};
+use crate::timer::{hardware_source, pwm_match, TimerInstance};
+
+/// # Frame Rate
+/// Hobby servos expect a pulse every 20 ms.
+const FRAME_RATE: Hertz = Hertz::Hz(50);
+
+/// # Servo
+/// A hobby servo on the output of the timer at `PORT`. The position is set by the
+/// width of a pulse sent 50 times a second, from `min` (one end) to `max` (the other).
+///
+/// # Example
+/// ```no_run
+/// use max78000_hal::memory_map::mmio;
+/// use max78000_hal::motion::Servo;
+/// use max78000_hal::time::Micros;
+/// use max78000_hal::timer::TimerInstance;
+///
+/// let mut servo = Servo::new(TimerInstance::<{ mmio::TIMER_0 }>::instance())?;
+/// servo.set_pulse(Micros::micros(1_250));
+/// servo.set_angle(90);
+/// # Ok::<(), max78000_hal::error::ErrorKind>(())
+/// ```
+pub struct Servo<const PORT: usize> {
+    timer: TimerInstance<PORT>,
+    clocks_per_micro: u32,
+    min: Micros,
+    max: Micros,
+    pulse: Micros,
+}
+
+impl<const PORT: usize> Servo<PORT> {
+    /// # New
+    /// Set up the timer for 50 Hz PWM from the peripheral clock, with the usual pulse
+    /// range of 1000 to 2000 us, and start it at the center. The timer output pin must
+    /// already be routed to the timer.
+    ///
+    /// # Errors
+    /// - `ErrorKind::NoDevice` when `PORT` is a low power timer.
+    /// - `ErrorKind::BadParam` when the peripheral clock is slower than 1 MHz.
+    pub fn new(timer: TimerInstance<PORT>) -> Result<Self> {
+        Self::with_range(timer, Micros::micros(1_000), Micros::micros(2_000))
+    }
+
+    /// # With Range
+    /// Like `new`, with the pulse widths of the two ends of the servo.
+    ///
+    /// # Errors
+    /// - `ErrorKind::NoDevice` when `PORT` is a low power timer.
+    /// - `ErrorKind::BadParam` when the peripheral clock is slower than 1 MHz, `min` is
+    ///   above `max`, or `max` does not fit in the 20 ms frame.
+    pub fn with_range(timer: TimerInstance<PORT>, min: Micros, max: Micros) -> Result<Self> {
+        let source = hardware_source(PORT)?;
+        let clock = crate::core_peripheral_clock();
+        let clocks_per_micro = clock.raw() / 1_000_000;
+
+        if clocks_per_micro == 0 || min > max || max.ticks() >= 1_000_000 / FRAME_RATE.raw() {
+            return Err(ErrorKind::BadParam);
+        }
+
+        peripheral_reset(source);
+        system_clock_enable(source, true);
+
+        let center = Micros::micros((min.ticks() + max.ticks()) / 2);
+        let mut servo = Self {
+            timer,
+            clocks_per_micro,
+            min,
+            max,
+            pulse: center,
+        };
+
+        unsafe {
+            servo.timer.set_timera_enable(false);
+            servo.timer.set_timera_clock_source(0);
+            servo
+                .timer
+                .set_timera_prescaler_select(TimerPrescaler::Div1);
+            servo.timer.set_timera_mode_select(TimerMode::Pwm);
+            servo.timer.set_timera_polarity(false);
+            servo.timer.set_timer_count(1);
+            let frame = clock.raw() / FRAME_RATE.raw();
+            servo.timer.set_timer_compare_value(frame);
+            servo
+                .timer
+                .set_pwm(pwm_match(center.ticks() * clocks_per_micro, frame));
+            servo.timer.set_output_enable(true);
+            servo.timer.set_timera_clock_enable(true);
+        }
+        while !servo.timer.get_timera_clock_ready() {}
+        unsafe { servo.timer.set_timera_enable(true) };
+
+        Ok(servo)
+    }
+
+    /// # Set Pulse
+    /// Set the pulse width, clamped to the range of the servo. Takes effect from the
+    /// next frame.
+    pub fn set_pulse(&mut self, pulse: Micros) {
+        self.pulse = pulse.max(self.min).min(self.max);
+        let frame = self.timer.get_timer_compare_value();
+        unsafe {
+            self.timer
+                .set_pwm(pwm_match(self.pulse.ticks() * self.clocks_per_micro, frame))
+        };
+    }
+
+    /// # Get Pulse
+    /// Get the pulse width.
+    pub fn get_pulse(&self) -> Micros {
+        self.pulse
+    }
+
+    /// # Set Angle
+    /// Set the position in degrees, from `0` (`min`) to `180` (`max`). Angles past `180`
+    /// are clamped.
+    pub fn set_angle(&mut self, degrees: u8) {
+        self.set_pulse(angle_pulse(self.min, self.max, degrees));
+    }
+
+    /// # Free
+    /// Stop the timer, and give it back.
+    pub fn free(mut self) -> TimerInstance<PORT> {
+        unsafe {
+            self.timer.set_timera_enable(false);
+            self.timer.set_output_enable(false);
+        }
+        self.timer
+    }
+}
+
+/// # Angle Pulse
+/// The pulse width of `degrees` on a servo going from `min` to `max` over 180 degrees.
+fn angle_pulse(min: Micros, max: Micros, degrees: u8) -> Micros {
+    let span = max.ticks() - min.ticks();
+    Micros::micros(min.ticks() + span * degrees.min(180) as u32 / 180)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn angle_pulse_values() {
+        let (min, max) = (Micros::micros(1_000), Micros::micros(2_000));
+        assert_eq!(angle_pulse(min, max, 0), min);
+        assert_eq!(angle_pulse(min, max, 90), Micros::micros(1_500));
+        assert_eq!(angle_pulse(min, max, 180), max);
+        assert_eq!(angle_pulse(min, max, 255), max);
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn pulse_is_clamped() {
+        use crate::memory_map::mmio;
+
+        crate::mock::reset();
+
+        let mut servo = Servo {
+            timer: TimerInstance::<{ mmio::TIMER_0 }>::instance(),
+            clocks_per_micro: 50,
+            min: Micros::micros(1_000),
+            max: Micros::micros(2_000),
+            pulse: Micros::micros(1_500),
+        };
+        // A 20 ms frame at 50 MHz
+        crate::mock::write(mmio::TIMER_0 + 0x04, 1_000_000);
+
+        // The pulse is high, at the end of the frame after the PWM match
+        servo.set_pulse(Micros::micros(1_200));
+        assert_eq!(servo.timer.get_pwm(), 1_000_000 - 60_000);
+        servo.set_pulse(Micros::micros(500));
+        assert_eq!(servo.get_pulse(), Micros::micros(1_000));
+        servo.set_angle(180);
+        assert_eq!(servo.timer.get_pwm(), 1_000_000 - 100_000);
+    }
+}
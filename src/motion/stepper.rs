@@ -0,0 +1,403 @@
+use crate::error::{ErrorKind, Result};
+use crate::gcr::{peripheral_reset, system_clock_enable};
+use crate::gpio::GpioPin;
+use crate::time::Hertz;
+use crate::timer::registers::{TimerMode, TimerPrescaler};
+use crate::timer::{hardware_source, TimerInstance};
+
+/// # Ramp
+/// A trapezoidal speed profile, in steps per second. The speed goes up by
+/// `acceleration` steps per second every second until `max_speed`, and back down in
+/// time to reach the last step at the start speed.
+///
+/// Every step works out its speed from the one before it, with `v^2 = u^2 + 2a`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ramp {
+    max_speed: u32,
+    acceleration: u32,
+    speed: u32,
+    remaining: u32,
+}
+
+impl Ramp {
+    /// # New
+    /// A ramp with no steps to go.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `max_speed` or `acceleration` is zero.
+    pub fn new(max_speed: u32, acceleration: u32) -> Result<Self> {
+        if max_speed == 0 || acceleration == 0 {
+            return Err(ErrorKind::BadParam);
+        }
+
+        Ok(Self {
+            max_speed,
+            acceleration,
+            speed: 0,
+            remaining: 0,
+        })
+    }
+
+    /// # Start
+    /// Start a new move of `steps` steps from standstill.
+    pub fn start(&mut self, steps: u32) {
+        self.speed = 0;
+        self.remaining = steps;
+    }
+
+    /// # Brake
+    /// Cut the move short, only leaving the steps needed to slow down.
+    pub fn brake(&mut self) {
+        self.remaining = self.remaining.min(self.braking_steps());
+    }
+
+    /// # Remaining
+    /// The number of steps left in the move.
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+
+    /// # Speed
+    /// The speed of the last step, in steps per second.
+    pub fn speed(&self) -> u32 {
+        self.speed
+    }
+
+    /// # Next Step
+    /// Move to the next step, returning its speed, or `None` when the move is done.
+    pub fn next_step(&mut self) -> Option<u32> {
+        if self.remaining == 0 {
+            self.speed = 0;
+            return None;
+        }
+
+        let start_squared = 2 * self.acceleration as u64;
+        let squared = self.speed as u64 * self.speed as u64;
+
+        self.speed = if self.braking_steps() >= self.remaining {
+            isqrt(squared.saturating_sub(start_squared).max(start_squared))
+        } else {
+            isqrt(squared + start_squared).min(self.max_speed)
+        };
+        self.remaining -= 1;
+
+        Some(self.speed)
+    }
+
+    /// The number of steps it takes to slow down from the current speed, rounded up.
+    fn braking_steps(&self) -> u32 {
+        let squared = self.speed as u64 * self.speed as u64;
+        squared
+            .div_ceil(2 * self.acceleration as u64)
+            .min(u32::MAX as u64) as u32
+    }
+}
+
+/// Integer square root, rounded down.
+fn isqrt(value: u64) -> u32 {
+    if value < 2 {
+        return value as u32;
+    }
+
+    // Newton's method, from a guess that is never too low
+    let mut root = 1u64 << (64 - value.leading_zeros()).div_ceil(2);
+    loop {
+        let next = (root + value / root) / 2;
+        if next >= root {
+            return root as u32;
+        }
+        root = next;
+    }
+}
+
+/// # Stepper
+/// A step/direction stepper motor driver, stepped by the interrupt of the timer at
+/// `PORT`. Every step is a pulse on `step`, high for half of the step interval, with
+/// `direction` high for positive steps.
+///
+/// # Example
+/// ```no_run
+/// use core::cell::RefCell;
+/// use critical_section::Mutex;
+/// use max78000_hal::bind_interrupts;
+/// use max78000_hal::interrupt::typelevel::{Handler, TMR1};
+/// use max78000_hal::memory_map::mmio;
+/// use max78000_hal::motion::Stepper;
+/// use max78000_hal::timer::TimerInstance;
+/// # use max78000_hal::gpio::{GpioPin, GpioSelect};
+/// # let [step, direction] = [0, 1].map(|pin| GpioPin::new(GpioSelect::Gpio1, pin).unwrap());
+///
+/// static STEPPER: Mutex<RefCell<Option<Stepper<{ mmio::TIMER_1 }>>>> =
+///     Mutex::new(RefCell::new(None));
+///
+/// struct Step;
+/// impl Handler<TMR1> for Step {
+///     unsafe fn on_interrupt() {
+///         critical_section::with(|cs| {
+///             if let Some(stepper) = STEPPER.borrow_ref_mut(cs).as_mut() {
+///                 stepper.on_interrupt();
+///             }
+///         });
+///     }
+/// }
+///
+/// bind_interrupts!(struct Irqs { TMR1 => Step; });
+///
+/// let mut stepper = Stepper::new(TimerInstance::instance(), step, direction, 2_000, 8_000)?;
+/// stepper.move_to(1_600)?;
+/// critical_section::with(|cs| STEPPER.replace(cs, Some(stepper)));
+/// # Ok::<(), max78000_hal::error::ErrorKind>(())
+/// ```
+pub struct Stepper<const PORT: usize> {
+    timer: TimerInstance<PORT>,
+    step: GpioPin,
+    direction: GpioPin,
+    clock: Hertz,
+    ramp: Ramp,
+    position: i32,
+    forward: bool,
+}
+
+// The pins and timer belong to the stepper alone, and its interrupt steps it through
+// `&mut self`, so it can be moved to wherever that interrupt finds it
+unsafe impl<const PORT: usize> Send for Stepper<PORT> {}
+
+impl<const PORT: usize> Stepper<PORT> {
+    /// # New
+    /// Set up the timer to count from the peripheral clock, with moves going up to
+    /// `max_speed` steps per second at `acceleration` steps per second squared. The pins
+    /// must already be configured as outputs. The stepper starts at position `0`.
+    ///
+    /// # Errors
+    /// - `ErrorKind::NoDevice` when `PORT` is a low power timer.
+    /// - `ErrorKind::BadParam` when `max_speed` or `acceleration` is zero, or
+    ///   `max_speed` is faster than the timer can pulse.
+    pub fn new(
+        timer: TimerInstance<PORT>,
+        step: GpioPin,
+        direction: GpioPin,
+        max_speed: u32,
+        acceleration: u32,
+    ) -> Result<Self> {
+        let source = hardware_source(PORT)?;
+        let clock = crate::core_peripheral_clock();
+        let ramp = Ramp::new(max_speed, acceleration)?;
+
+        if clock.raw() / max_speed < 2 {
+            return Err(ErrorKind::BadParam);
+        }
+
+        peripheral_reset(source);
+        system_clock_enable(source, true);
+
+        let mut stepper = Self {
+            timer,
+            step,
+            direction,
+            clock,
+            ramp,
+            position: 0,
+            forward: true,
+        };
+
+        unsafe {
+            stepper.timer.set_timera_enable(false);
+            stepper.timer.set_timera_clock_source(0);
+            stepper
+                .timer
+                .set_timera_prescaler_select(TimerPrescaler::Div1);
+            stepper.timer.set_timera_mode_select(TimerMode::Continuous);
+            stepper.timer.set_timera_interrupt_enable(true);
+            stepper.timer.set_timera_clock_enable(true);
+        }
+        while !stepper.timer.get_timera_clock_ready() {}
+
+        stepper.step.set_output(false);
+        Ok(stepper)
+    }
+
+    /// # Position
+    /// The position of the motor, in steps from where it started.
+    pub fn position(&self) -> i32 {
+        self.position
+    }
+
+    /// # Set Position
+    /// Redefine the current position, ie. after homing.
+    pub fn set_position(&mut self, position: i32) {
+        self.position = position;
+    }
+
+    /// # Is Moving
+    /// Check if a move is still going.
+    pub fn is_moving(&self) -> bool {
+        self.ramp.remaining() != 0 || self.step.get_output()
+    }
+
+    /// # Move To
+    /// Start moving to `target`. The interrupt of the timer must be bound to a handler
+    /// calling `on_interrupt`, and enabled in the NVIC.
+    ///
+    /// # Errors
+    /// `ErrorKind::Busy` when the last move is still going, see `stop`.
+    pub fn move_to(&mut self, target: i32) -> Result<()> {
+        self.move_by(target.wrapping_sub(self.position))
+    }
+
+    /// # Move By
+    /// Start moving `steps` steps from the current position, see `move_to`.
+    ///
+    /// # Errors
+    /// `ErrorKind::Busy` when the last move is still going, see `stop`.
+    pub fn move_by(&mut self, steps: i32) -> Result<()> {
+        if self.is_moving() {
+            return Err(ErrorKind::Busy);
+        }
+        if steps == 0 {
+            return Ok(());
+        }
+
+        self.forward = steps > 0;
+        self.direction.set_output(self.forward);
+        self.ramp.start(steps.unsigned_abs());
+
+        unsafe {
+            self.timer.set_timer_count(1);
+            // The first interrupt comes right away, and starts the first step
+            self.timer.set_timer_compare_value(2);
+            self.timer.set_timera_enable(true);
+        }
+
+        Ok(())
+    }
+
+    /// # Stop
+    /// Slow down to a stop as fast as the acceleration allows.
+    pub fn stop(&mut self) {
+        self.ramp.brake();
+    }
+
+    /// # On Interrupt
+    /// Drive the step pin. Call this from the timer's interrupt handler.
+    pub fn on_interrupt(&mut self) {
+        unsafe { self.timer.clear_timera_interrupt_event() };
+
+        // The second half of a step, end the pulse
+        if self.step.get_output() {
+            self.step.set_output(false);
+            if self.ramp.remaining() == 0 {
+                unsafe { self.timer.set_timera_enable(false) };
+                return;
+            }
+
+            let interval = self.clock.raw() / self.ramp.speed().max(1);
+            unsafe { self.timer.set_timer_compare_value(interval - interval / 2) };
+            return;
+        }
+
+        match self.ramp.next_step() {
+            Some(speed) => {
+                self.step.set_output(true);
+                self.position = match self.forward {
+                    true => self.position.wrapping_add(1),
+                    false => self.position.wrapping_sub(1),
+                };
+
+                let interval = self.clock.raw() / speed;
+                unsafe { self.timer.set_timer_compare_value(interval / 2) };
+            }
+            None => unsafe { self.timer.set_timera_enable(false) },
+        }
+    }
+
+    /// # Free
+    /// Stop the timer right away, and give back the timer and pins.
+    pub fn free(mut self) -> (TimerInstance<PORT>, GpioPin, GpioPin) {
+        unsafe { self.timer.set_timera_enable(false) };
+        self.step.set_output(false);
+        (self.timer, self.step, self.direction)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn isqrt_values() {
+        for value in [0u64, 1, 2, 3, 4, 15, 16, 17, 1 << 40, u64::MAX] {
+            let root = isqrt(value) as u64;
+            assert!(root * root <= value);
+            assert!((root + 1)
+                .checked_mul(root + 1)
+                .is_none_or(|next| next > value));
+        }
+    }
+
+    #[test]
+    fn ramp_is_trapezoidal() {
+        assert_eq!(Ramp::new(0, 100), Err(ErrorKind::BadParam));
+
+        let mut ramp = Ramp::new(100, 1_000).unwrap();
+        ramp.start(200);
+
+        let mut speeds = [0; 200];
+        for speed in speeds.iter_mut() {
+            *speed = ramp.next_step().unwrap();
+        }
+        assert_eq!(ramp.next_step(), None);
+
+        // Accelerates to full speed, cruises, and slows back down
+        assert_eq!(speeds[0], 44);
+        assert!(speeds.windows(2).take(4).all(|pair| pair[0] < pair[1]));
+        assert!(speeds[10..190].iter().all(|speed| *speed == 100));
+        assert!(speeds[194..].windows(2).all(|pair| pair[0] >= pair[1]));
+        assert_eq!(speeds[194..198], [100, 89, 76, 61]);
+        assert_eq!(speeds[199], 44);
+    }
+
+    #[test]
+    fn ramp_brakes() {
+        let mut ramp = Ramp::new(100, 1_000).unwrap();
+        ramp.start(1_000);
+        for _ in 0..20 {
+            ramp.next_step();
+        }
+
+        ramp.brake();
+        assert_eq!(ramp.remaining(), 5);
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn steps_and_counts_position() {
+        use crate::gpio::GpioSelect;
+        use crate::memory_map::mmio;
+
+        crate::gpio::reset_mock();
+
+        let mut stepper = Stepper {
+            timer: TimerInstance::<{ mmio::TIMER_1 }>::instance(),
+            step: GpioPin::new(GpioSelect::Gpio1, 6).unwrap(),
+            direction: GpioPin::new(GpioSelect::Gpio1, 7).unwrap(),
+            clock: Hertz::MHz(50),
+            ramp: Ramp::new(100, 1_000).unwrap(),
+            position: 0,
+            forward: true,
+        };
+
+        stepper.move_by(-3).unwrap();
+        assert!(!stepper.direction.get_output());
+        assert_eq!(stepper.move_by(1), Err(ErrorKind::Busy));
+
+        let mut pulses = 0;
+        while stepper.is_moving() {
+            stepper.on_interrupt();
+            pulses += stepper.step.get_output() as u32;
+        }
+
+        assert_eq!(pulses, 3);
+        assert_eq!(stepper.position(), -3);
+        assert!(!stepper.timer.get_timera_enable());
+    }
+}
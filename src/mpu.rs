@@ -0,0 +1,372 @@
+//! # Memory Protection Unit
+//! A small builder for the Cortex-M4 MPU, covering the common layouts (a stack guard,
+//! no-execute RAM, device memory for the peripherals) without hand-coding `MPU_RASR`
+//! values. See ARMv7-M Reference, B3.5.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::mpu::Mpu;
+//! use max78000_hal::ram::regions;
+//!
+//! Mpu::builder()
+//!     .no_execute_ram(regions::SYSRAM0.start(), 128 * 1024)
+//!     .device_peripherals()
+//!     .stack_guard(0x2001_F000, 32)
+//!     .apply()
+//!     .unwrap();
+//! ```
+use crate::bits::BitManipulation;
+use crate::error::{ErrorKind, Result};
+use crate::memory_map::scs;
+
+/// # Region Count
+/// The number of regions the MAX78000's MPU has.
+pub const REGION_COUNT: usize = 8;
+
+/// # MPU Control Bits
+/// See ARMv7-M Reference, B3.5.5.
+mod ctrl {
+    /// # Enable
+    pub const ENABLE: u8 = 0;
+    /// # Privileged Default
+    /// Use the default memory map for privileged accesses no region covers.
+    pub const PRIVDEFENA: u8 = 2;
+}
+
+/// # MemManage Fault Enable
+/// The `MEMFAULTENA` bit of `SCB_SHCSR`. See ARMv7-M Reference, B3.2.13.
+const SHCSR_MEMFAULTENA: u8 = 16;
+
+/// # Access
+/// Who may read and write a region.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Access {
+    /// Every access faults.
+    NoAccess,
+    /// Only privileged code may read or write.
+    PrivilegedReadWrite,
+    /// Privileged code may read or write, unprivileged code may only read.
+    PrivilegedReadWriteUserReadOnly,
+    /// Everyone may read or write.
+    ReadWrite,
+    /// Only privileged code may read.
+    PrivilegedReadOnly,
+    /// Everyone may read, no one may write.
+    ReadOnly,
+}
+
+impl Access {
+    /// The `AP` field encoding. See ARMv7-M Reference, B3.5.9, Table B3-15.
+    const fn bits(self) -> u32 {
+        match self {
+            Access::NoAccess => 0b000,
+            Access::PrivilegedReadWrite => 0b001,
+            Access::PrivilegedReadWriteUserReadOnly => 0b010,
+            Access::ReadWrite => 0b011,
+            Access::PrivilegedReadOnly => 0b101,
+            Access::ReadOnly => 0b110,
+        }
+    }
+}
+
+/// # Memory Type
+/// How the core may order and buffer accesses to a region.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MemoryType {
+    /// Every access happens in order, and completes before the next one.
+    StronglyOrdered,
+    /// Peripheral registers, accesses are never merged or repeated.
+    Device,
+    /// RAM and flash.
+    Normal,
+}
+
+impl MemoryType {
+    /// The `TEX`, `S`, `C`, `B` fields, already shifted into place. See ARMv7-M
+    /// Reference, B3.5.9, Table B3-13.
+    const fn bits(self) -> u32 {
+        match self {
+            // TEX = 0, C = 0, B = 0
+            MemoryType::StronglyOrdered => 0,
+            // TEX = 0, S = 1, C = 0, B = 1
+            MemoryType::Device => (1 << 18) | (1 << 16),
+            // TEX = 0, S = 1, C = 1, B = 0 (write-through)
+            MemoryType::Normal => (1 << 18) | (1 << 17),
+        }
+    }
+}
+
+/// # Region
+/// One MPU region. The size must be a power of two of at least 32 bytes, and the base
+/// must be aligned to it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Region {
+    /// The first address of the region.
+    pub base: usize,
+    /// The size of the region in bytes.
+    pub size: usize,
+    /// Who may read and write the region.
+    pub access: Access,
+    /// How accesses to the region behave.
+    pub memory_type: MemoryType,
+    /// If instructions may be fetched from the region.
+    pub execute: bool,
+    /// Each set bit disables one eighth of the region, regions of 256 bytes or more only.
+    pub subregion_disable: u8,
+}
+
+impl Region {
+    /// # RBAR
+    /// The value of `MPU_RBAR` for this region, without the `VALID`/`REGION` fields.
+    fn rbar(&self) -> Result<u32> {
+        self.check()?;
+        Ok(self.base as u32)
+    }
+
+    /// # RASR
+    /// The value of `MPU_RASR` for this region, enabled.
+    fn rasr(&self) -> Result<u32> {
+        self.check()?;
+
+        // SIZE encodes a region of 2^(SIZE + 1) bytes
+        let size = self.size.trailing_zeros() - 1;
+        let mut rasr = self.memory_type.bits() | (self.access.bits() << 24) | (size << 1) | 1;
+        rasr |= (self.subregion_disable as u32) << 8;
+        rasr.set_bit(28, !self.execute);
+
+        Ok(rasr)
+    }
+
+    fn check(&self) -> Result<()> {
+        let sized = self.size.is_power_of_two() && self.size >= 32;
+        let aligned = self.base.is_multiple_of(self.size);
+        let subregions = self.subregion_disable == 0 || self.size >= 256;
+
+        if sized && aligned && subregions {
+            Ok(())
+        } else {
+            Err(ErrorKind::BadParam)
+        }
+    }
+}
+
+/// # MPU
+/// Entry point of the builder, see `Mpu::builder`.
+pub struct Mpu;
+
+impl Mpu {
+    /// # Builder
+    /// Start a new layout. Applying it replaces every region set before.
+    pub const fn builder() -> MpuBuilder {
+        MpuBuilder {
+            regions: [None; REGION_COUNT],
+            count: 0,
+            default_map: true,
+            overflowed: false,
+        }
+    }
+
+    /// # Disable
+    /// Turn the MPU off, every access is checked against the default memory map again.
+    pub fn disable() {
+        crate::asm::dsb();
+        unsafe { core::ptr::write_volatile(hal_macros::mmio_ptr::<u32>(scs::MPU_CTRL), 0) };
+        crate::asm::dsb();
+        crate::asm::isb();
+    }
+
+    /// # Is Enabled
+    /// Check if the MPU is on.
+    pub fn is_enabled() -> bool {
+        unsafe {
+            core::ptr::read_volatile(hal_macros::mmio_ptr::<u32>(scs::MPU_CTRL))
+                .get_bit(ctrl::ENABLE)
+        }
+    }
+}
+
+/// # MPU Builder
+/// Collects up to `REGION_COUNT` regions. When regions overlap, the one added last
+/// wins, so add the broad regions first and the exceptions (ie. the stack guard) last.
+#[derive(Clone, Copy)]
+pub struct MpuBuilder {
+    regions: [Option<Region>; REGION_COUNT],
+    count: usize,
+    default_map: bool,
+    overflowed: bool,
+}
+
+impl MpuBuilder {
+    /// # Region
+    /// Add a region.
+    pub fn region(mut self, region: Region) -> Self {
+        match self.regions.get_mut(self.count) {
+            Some(slot) => {
+                *slot = Some(region);
+                self.count += 1;
+            }
+            None => self.overflowed = true,
+        }
+        self
+    }
+
+    /// # Stack Guard
+    /// Make `size` bytes at `bottom` (the lowest address of a stack) inaccessible, so a
+    /// stack overflow faults instead of silently corrupting whatever is below.
+    pub fn stack_guard(self, bottom: usize, size: usize) -> Self {
+        self.region(Region {
+            base: bottom,
+            size,
+            access: Access::NoAccess,
+            memory_type: MemoryType::Normal,
+            execute: false,
+            subregion_disable: 0,
+        })
+    }
+
+    /// # No Execute RAM
+    /// Make `size` bytes of RAM at `base` readable and writable, but never executable.
+    pub fn no_execute_ram(self, base: usize, size: usize) -> Self {
+        self.region(Region {
+            base,
+            size,
+            access: Access::ReadWrite,
+            memory_type: MemoryType::Normal,
+            execute: false,
+            subregion_disable: 0,
+        })
+    }
+
+    /// # Device Peripherals
+    /// Mark the peripheral space (`0x4000_0000` to `0x5FFF_FFFF`, including the CNN) as
+    /// device memory, never executable.
+    pub fn device_peripherals(self) -> Self {
+        self.region(Region {
+            base: 0x4000_0000,
+            size: 0x2000_0000,
+            access: Access::ReadWrite,
+            memory_type: MemoryType::Device,
+            execute: false,
+            subregion_disable: 0,
+        })
+    }
+
+    /// # Default Map
+    /// Select if privileged code may access memory no region covers, using the default
+    /// memory map (the default), or if that should fault.
+    pub fn default_map(mut self, enable: bool) -> Self {
+        self.default_map = enable;
+        self
+    }
+
+    /// # Apply
+    /// Program every region, clear the unused ones, and enable the MPU along with the
+    /// MemManage fault.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when a region has a invalid size or alignment, or more
+    /// than `REGION_COUNT` regions were added. The MPU is left untouched.
+    pub fn apply(self) -> Result<()> {
+        if self.overflowed {
+            return Err(ErrorKind::BadParam);
+        }
+
+        let mut values = [(0u32, 0u32); REGION_COUNT];
+        for (value, region) in values.iter_mut().zip(self.regions.iter()) {
+            if let Some(region) = region {
+                *value = (region.rbar()?, region.rasr()?);
+            }
+        }
+
+        Mpu::disable();
+
+        unsafe {
+            for (number, (rbar, rasr)) in values.into_iter().enumerate() {
+                core::ptr::write_volatile(hal_macros::mmio_ptr::<u32>(scs::MPU_RNR), number as u32);
+                core::ptr::write_volatile(hal_macros::mmio_ptr::<u32>(scs::MPU_RBAR), rbar);
+                core::ptr::write_volatile(hal_macros::mmio_ptr::<u32>(scs::MPU_RASR), rasr);
+            }
+
+            let mut shcsr = core::ptr::read_volatile(hal_macros::mmio_ptr::<u32>(scs::SCB_SHCSR));
+            shcsr.set_bit(SHCSR_MEMFAULTENA, true);
+            core::ptr::write_volatile(hal_macros::mmio_ptr::<u32>(scs::SCB_SHCSR), shcsr);
+
+            let mut control = 0u32;
+            control.set_bit(ctrl::ENABLE, true);
+            control.set_bit(ctrl::PRIVDEFENA, self.default_map);
+            core::ptr::write_volatile(hal_macros::mmio_ptr::<u32>(scs::MPU_CTRL), control);
+        }
+
+        crate::asm::dsb();
+        crate::asm::isb();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rasr_encoding() {
+        let guard = Region {
+            base: 0x2001_F000,
+            size: 32,
+            access: Access::NoAccess,
+            memory_type: MemoryType::Normal,
+            execute: false,
+            subregion_disable: 0,
+        };
+        // XN, S, C, SIZE = 4, ENABLE
+        assert_eq!(
+            guard.rasr(),
+            Ok((1 << 28) | (1 << 18) | (1 << 17) | (4 << 1) | 1)
+        );
+
+        let peripherals = Mpu::builder().device_peripherals().regions[0].unwrap();
+        // XN, AP = full access, S, B, SIZE = 28
+        assert_eq!(
+            peripherals.rasr(),
+            Ok((1 << 28) | (0b011 << 24) | (1 << 18) | (1 << 16) | (28 << 1) | 1)
+        );
+    }
+
+    #[test]
+    fn invalid_regions() {
+        let misaligned = Mpu::builder().stack_guard(0x2000_0010, 32);
+        assert_eq!(
+            misaligned.regions[0].unwrap().rasr(),
+            Err(ErrorKind::BadParam)
+        );
+
+        let odd_size = Mpu::builder().no_execute_ram(0x2000_0000, 48);
+        assert_eq!(odd_size.apply(), Err(ErrorKind::BadParam));
+
+        let mut too_many = Mpu::builder();
+        for _ in 0..=REGION_COUNT {
+            too_many = too_many.stack_guard(0x2000_0000, 32);
+        }
+        assert_eq!(too_many.apply(), Err(ErrorKind::BadParam));
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn apply_enables() {
+        crate::mock::reset();
+
+        Mpu::builder()
+            .stack_guard(0x2000_0000, 1024)
+            .default_map(false)
+            .apply()
+            .unwrap();
+
+        assert!(Mpu::is_enabled());
+        assert_eq!(crate::mock::read(scs::MPU_CTRL), 1);
+        // The last region written is the unused region 7, which must be disabled
+        assert_eq!(crate::mock::read(scs::MPU_RNR), 7);
+        assert_eq!(crate::mock::read(scs::MPU_RASR), 0);
+    }
+}
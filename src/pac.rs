@@ -0,0 +1,157 @@
+use crate::error::{ErrorKind, Result};
+
+/// # PAC Peripheral
+/// A register block from a svd2rust generated PAC (ie. `max78000-pac`). Implementing this
+/// lets a PAC peripheral be turned into a handle of this HAL, and back, so a project can
+/// move between the two one peripheral at a time.
+///
+/// svd2rust peripherals all look the same, so the `impl_pac_peripheral!` macro can
+/// implement this for newtypes around them.
+///
+/// # Safety
+/// `address` must be the base address of the peripheral's register block, and `steal`
+/// must not do anything besides making the zero sized PAC handle.
+pub unsafe trait PacPeripheral: Sized {
+    /// # Address
+    /// The base address of the register block.
+    fn address() -> usize;
+
+    /// # Steal
+    /// Make the PAC handle, without checking if it is already owned.
+    ///
+    /// # Safety
+    /// The caller must make sure there is no other owner of the peripheral.
+    unsafe fn steal() -> Self;
+}
+
+/// # From PAC
+/// Take ownership of a PAC peripheral, and make the matching HAL handle out of it.
+pub trait FromPac: Sized {
+    /// # From PAC
+    /// Initialize the HAL handle from the PAC peripheral.
+    ///
+    /// # Errors
+    /// `ErrorKind::NoDevice` when `P` is not the peripheral this handle is for.
+    fn from_pac<P: PacPeripheral>(peripheral: P) -> Result<Self>;
+}
+
+/// # Into PAC
+/// Give up a HAL handle, and get the matching PAC peripheral back.
+pub trait IntoPac: Sized {
+    /// # Free
+    /// Release the HAL handle, returning the PAC peripheral. The hardware is left as it
+    /// is, so the PAC can pick up where the HAL stopped.
+    ///
+    /// # Errors
+    /// `ErrorKind::NoDevice` when `P` is not the peripheral this handle is for.
+    fn free<P: PacPeripheral>(self) -> Result<P>;
+}
+
+/// # Check Address
+/// Make sure the PAC peripheral `P` lives at `address`.
+pub(crate) fn check_address<P: PacPeripheral>(address: usize) -> Result<()> {
+    if P::address() == address {
+        Ok(())
+    } else {
+        Err(ErrorKind::NoDevice)
+    }
+}
+
+/// # Into PAC (Unchecked Free)
+/// Drop the HAL handle, then steal the PAC peripheral it was using.
+pub(crate) fn free_into<P: PacPeripheral, T>(handle: T, address: usize) -> Result<P> {
+    check_address::<P>(address)?;
+    drop(handle);
+
+    Ok(unsafe { P::steal() })
+}
+
+/// # Impl PAC Peripheral
+/// Declare a newtype around one or more svd2rust peripherals, implementing
+/// `PacPeripheral` for it. The PAC's types are foreign to both this crate and yours, so
+/// the trait can not be implemented for them directly, the newtype lives in your crate
+/// instead. Its field is the PAC peripheral.
+///
+/// # Example
+/// ```no_run
+/// # mod max78000_pac {
+/// #     pub struct TRNG(());
+/// #     impl TRNG {
+/// #         pub const PTR: *const u32 = 0x4004_D000 as *const u32;
+/// #         pub unsafe fn steal() -> Self { Self(()) }
+/// #     }
+/// # }
+/// use max78000_hal::pac::{FromPac, IntoPac};
+/// use max78000_hal::trng::TRNG;
+///
+/// max78000_hal::impl_pac_peripheral! {
+///     /// The TRNG of the PAC.
+///     pub struct PacTrng(max78000_pac::TRNG);
+/// }
+///
+/// let peripheral = unsafe { max78000_pac::TRNG::steal() };
+/// let mut trng = TRNG::from_pac(PacTrng(peripheral)).unwrap();
+/// let _ = trng.get_trng_data();
+/// let PacTrng(_peripheral) = trng.free::<PacTrng>().unwrap();
+/// ```
+#[macro_export]
+macro_rules! impl_pac_peripheral {
+    ($($(#[$meta:meta])* $vis:vis struct $name:ident($peripheral:ty);)*) => {
+        $(
+            $(#[$meta])*
+            $vis struct $name(pub $peripheral);
+
+            unsafe impl $crate::pac::PacPeripheral for $name {
+                #[inline(always)]
+                fn address() -> usize {
+                    <$peripheral>::PTR as usize
+                }
+
+                #[inline(always)]
+                unsafe fn steal() -> Self {
+                    Self(<$peripheral>::steal())
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::memory_map::mmio;
+    use crate::trng::TRNG;
+
+    struct FakeTrng;
+    struct FakeAes;
+
+    impl FakeTrng {
+        const PTR: *const u32 = mmio::TRNG as *const u32;
+
+        unsafe fn steal() -> Self {
+            Self
+        }
+    }
+
+    impl FakeAes {
+        const PTR: *const u32 = mmio::AES as *const u32;
+
+        unsafe fn steal() -> Self {
+            Self
+        }
+    }
+
+    crate::impl_pac_peripheral! {
+        struct PacTrng(FakeTrng);
+        struct PacAes(FakeAes);
+    }
+
+    #[test]
+    fn free_checks_address() {
+        assert!(unsafe { TRNG::steal() }.free::<PacTrng>().is_ok());
+        assert!(matches!(
+            unsafe { TRNG::steal() }.free::<PacAes>(),
+            Err(ErrorKind::NoDevice)
+        ));
+    }
+}
@@ -0,0 +1,218 @@
+use crate::bits::BitManipulation;
+use crate::gcr::registers::Instance as GcrInstance;
+use crate::memory_map::{mmio, scs};
+
+/// # System Control Register Bits
+/// See ARMv7-M Reference, B3.2.7.
+mod scr {
+    /// # Sleep On Exit
+    /// Enter sleep when returning from a handler into thread mode.
+    pub const SLEEPONEXIT: u8 = 1;
+    /// # Sleep Deep
+    /// Use the processor's deep sleep state instead of sleep.
+    pub const SLEEPDEEP: u8 = 2;
+    /// # Send Event On Pend
+    /// Pending interrupts (even disabled ones) count as a `wfe` wake up event.
+    pub const SEVONPEND: u8 = 4;
+}
+
+/// # Vector Pending
+/// The `VECTPENDING` field of the SCB `ICSR` register, the highest priority pending
+/// exception, or zero for none. See ARMv7-M Reference, B3.2.4.
+#[cfg(feature = "debug-sleep")]
+const ICSR_VECTPENDING: core::ops::RangeInclusive<u32> = 12..=20;
+
+#[inline(always)]
+fn modify_scr(bit: u8, flag: bool) {
+    unsafe {
+        let mut value = core::ptr::read_volatile(hal_macros::mmio_ptr::<u32>(scs::SCB_SCR));
+        value.set_bit(bit, flag);
+        core::ptr::write_volatile(hal_macros::mmio_ptr::<u32>(scs::SCB_SCR), value);
+    }
+}
+
+#[inline(always)]
+fn read_scr(bit: u8) -> bool {
+    unsafe { core::ptr::read_volatile(hal_macros::mmio_ptr::<u32>(scs::SCB_SCR)).get_bit(bit) }
+}
+
+/// # Debug Spin
+/// Busy wait until a interrupt (or any other exception, ie. SysTick) is pending. Used
+/// instead of sleeping when the `debug-sleep` feature is enabled, so the core (and the
+/// SWD port) stays clocked.
+///
+/// Like `wfi`, the check runs with interrupts masked: unmasked, the handler would take
+/// (and clear) the pending state before the loop could see it. Once one is pending
+/// the mask is restored, and the handler runs before this returns.
+#[cfg(feature = "debug-sleep")]
+#[inline(always)]
+fn debug_spin() {
+    let masked = crate::asm::primask();
+    crate::asm::cpsid();
+    while unsafe {
+        core::ptr::read_volatile(hal_macros::mmio_ptr::<u32>(scs::SCB_ICSR))
+            .get_bit_range(ICSR_VECTPENDING)
+            == 0
+    } {
+        crate::asm::nop();
+    }
+    if !masked {
+        crate::asm::cpsie();
+    }
+}
+
+/// # Wait For Interrupt
+/// Put the core to sleep until a interrupt arrives.
+///
+/// # Debug Sleep
+/// With the `debug-sleep` feature enabled the core never stops its clock, instead it
+/// spins until a interrupt is pending. This keeps a attached debugger alive, at the cost
+/// of power.
+#[inline(always)]
+pub fn wfi() {
+    #[cfg(feature = "debug-sleep")]
+    debug_spin();
+    #[cfg(not(feature = "debug-sleep"))]
+    crate::asm::wfi();
+}
+
+/// # Wait For Event
+/// Put the core to sleep until a event, or a interrupt, arrives. When a event was
+/// already signaled this returns immediately and clears it.
+///
+/// # Debug Sleep
+/// With the `debug-sleep` feature enabled this behaves like `wfi()`, spinning until a
+/// interrupt is pending. Events signaled with `sev` alone will not wake it.
+#[inline(always)]
+pub fn wfe() {
+    #[cfg(feature = "debug-sleep")]
+    debug_spin();
+    #[cfg(not(feature = "debug-sleep"))]
+    crate::asm::wfe();
+}
+
+/// # Set Sleep On Exit
+/// When enabled, the core goes straight back to sleep after every interrupt handler
+/// returns into thread mode. Useful for fully interrupt driven firmware, where `main`
+/// only sets things up and then calls `wfi()` once.
+///
+/// # Debug Sleep
+/// With the `debug-sleep` feature enabled sleep-on-exit is never enabled, since the
+/// core would stop its clock without going through `wfi()`.
+pub fn set_sleep_on_exit(enable: bool) {
+    modify_scr(
+        scr::SLEEPONEXIT,
+        enable && cfg!(not(feature = "debug-sleep")),
+    );
+}
+
+/// # Is Sleep On Exit
+/// Check if sleep-on-exit is enabled.
+pub fn is_sleep_on_exit() -> bool {
+    read_scr(scr::SLEEPONEXIT)
+}
+
+/// # Set Deep Sleep
+/// Select if `wfi()`/`wfe()` should use the processor's deep sleep state.
+///
+/// # Debug Sleep
+/// With the `debug-sleep` feature enabled deep sleep is never enabled.
+pub fn set_deep_sleep(enable: bool) {
+    modify_scr(scr::SLEEPDEEP, enable && cfg!(not(feature = "debug-sleep")));
+}
+
+/// # Deep Sleep
+/// Put the core into deep sleep until a interrupt arrives, then return to normal sleep.
+///
+/// With the `console` feature enabled a console installed with
+/// `console::install_sleep_aware` is prepared before sleeping and restored after waking,
+/// so logging keeps working across the transition.
+pub fn deep_sleep() {
+    #[cfg(feature = "console")]
+    crate::console::before_sleep();
+
+    set_deep_sleep(true);
+    wfi();
+    set_deep_sleep(false);
+
+    #[cfg(feature = "console")]
+    crate::console::after_sleep();
+}
+
+/// # Set Send Event On Pend
+/// When enabled, any interrupt becoming pending (even disabled ones) wakes a `wfe()`.
+pub fn set_send_event_on_pend(enable: bool) {
+    modify_scr(scr::SEVONPEND, enable);
+}
+
+/// # Wake-Up Source
+/// A peripheral that can wake the chip from the low power modes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WakeUpSource {
+    /// The pins armed with `GpioPin::enable_wakeup`.
+    Gpio,
+    /// The RTC alarm.
+    RealTimeClock,
+    /// The wake-up timer (WUT).
+    WakeUpTimer,
+    /// The analog comparators.
+    Comparator,
+}
+
+/// # Enable Wake-Up Source
+/// Allow/Stop `source` waking the chip from the low power modes, in `GCR_PM`. The
+/// peripheral still has to be armed on its own, ie. the pins with `enable_wakeup`.
+pub fn enable_wakeup_source(source: WakeUpSource, enable: bool) {
+    let mut gcr = GcrInstance::<{ mmio::GLOBAL_CONTROL }>::instance();
+
+    unsafe {
+        match source {
+            WakeUpSource::Gpio => gcr.set_gpio_wakeup_enable(enable),
+            WakeUpSource::RealTimeClock => gcr.set_rtc_alarm_wakeup_enable(enable),
+            WakeUpSource::WakeUpTimer => gcr.set_wake_up_timer_enable(enable),
+            WakeUpSource::Comparator => gcr.set_analog_input_comparator_wakeup_enable(enable),
+        }
+    };
+}
+
+/// # Is Wake-Up Source
+/// Check if `source` is allowed to wake the chip.
+pub fn is_wakeup_source(source: WakeUpSource) -> bool {
+    let gcr = GcrInstance::<{ mmio::GLOBAL_CONTROL }>::instance();
+
+    match source {
+        WakeUpSource::Gpio => gcr.get_gpio_wakeup_enable(),
+        WakeUpSource::RealTimeClock => gcr.get_rtc_alarm_wakeup_enable(),
+        WakeUpSource::WakeUpTimer => gcr.get_wake_up_timer_enable(),
+        WakeUpSource::Comparator => gcr.get_analog_input_comparator_wakeup_enable(),
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio", feature = "debug-sleep"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn debug_sleep_wakes_on_pending() {
+        crate::mock::reset();
+        // A NVIC interrupt (IRQ 4, exception 20) also sets ISRPENDING
+        let interrupt = (20 << 12) | (1 << 22);
+        let systick = 15 << 12;
+        let pendsv = 14 << 12;
+
+        crate::mock::write(scs::SCB_ICSR, interrupt);
+        wfi();
+        // The handler ran, clearing it, then something else is pending
+        crate::mock::write(scs::SCB_ICSR, 0);
+        crate::mock::write(scs::SCB_ICSR, interrupt);
+        wfe();
+        assert!(!crate::asm::primask());
+
+        // The system exceptions wake the core too, without ISRPENDING
+        crate::mock::write(scs::SCB_ICSR, systick);
+        wfi();
+        crate::mock::write(scs::SCB_ICSR, pendsv);
+        wfe();
+        assert!(!crate::asm::primask());
+    }
+}
@@ -0,0 +1,164 @@
+//! # Profiling
+//! Cycle accurate timing with the DWT cycle counter (`DWT_CYCCNT`), for measuring hot
+//! paths like CNN preprocessing or a driver's transfer loop.
+//!
+//! The counter is 32 bits, so at 100 MHz it wraps after about 42 seconds. Every
+//! measurement here uses wrapping math, so anything shorter than that is exact.
+use crate::bits::BitManipulation;
+use crate::memory_map::scs;
+use crate::time::{Hertz, Nanos};
+
+/// # Trace Enable
+/// The `TRCENA` bit of `DEMCR`, which powers the DWT. See ARMv7-M Reference, C1.6.5.
+const DEMCR_TRCENA: u8 = 24;
+
+/// # Cycle Count Enable
+/// The `CYCCNTENA` bit of `DWT_CTRL`. See ARMv7-M Reference, C1.8.7.
+const DWT_CTRL_CYCCNTENA: u8 = 0;
+
+#[inline(always)]
+fn modify(address: usize, bit: u8, flag: bool) {
+    unsafe {
+        let mut value = core::ptr::read_volatile(hal_macros::mmio_ptr::<u32>(address));
+        value.set_bit(bit, flag);
+        core::ptr::write_volatile(hal_macros::mmio_ptr::<u32>(address), value);
+    }
+}
+
+/// # Enable
+/// Power the DWT, and start the cycle counter. A attached debugger may also do this.
+pub fn enable() {
+    modify(scs::DEMCR, DEMCR_TRCENA, true);
+    modify(scs::DWT_CTRL, DWT_CTRL_CYCCNTENA, true);
+}
+
+/// # Disable
+/// Stop the cycle counter. The DWT stays powered, since a debugger may be using it.
+pub fn disable() {
+    modify(scs::DWT_CTRL, DWT_CTRL_CYCCNTENA, false);
+}
+
+/// # Is Enabled
+/// Check if the cycle counter is running.
+pub fn is_enabled() -> bool {
+    unsafe {
+        core::ptr::read_volatile(hal_macros::mmio_ptr::<u32>(scs::DWT_CTRL))
+            .get_bit(DWT_CTRL_CYCCNTENA)
+    }
+}
+
+/// # Now
+/// Read the cycle counter.
+#[inline(always)]
+pub fn now() -> u32 {
+    unsafe { core::ptr::read_volatile(hal_macros::mmio_ptr::<u32>(scs::DWT_CYCCNT)) }
+}
+
+/// # Cycles
+/// Run `f`, and return how many cycles it took. Includes the few cycles needed to read
+/// the counter.
+///
+/// # Example
+/// ```no_run
+/// use max78000_hal::profiling;
+///
+/// profiling::enable();
+/// let cycles = profiling::cycles(|| {
+///     // Code to measure
+/// });
+/// ```
+#[inline(always)]
+pub fn cycles<F: FnOnce()>(f: F) -> u32 {
+    let start = now();
+    f();
+    now().wrapping_sub(start)
+}
+
+/// # Cycles To Duration
+/// Convert `cycles` of `clock` into the time they took.
+pub const fn cycles_to_duration(cycles: u32, clock: Hertz) -> Nanos {
+    Nanos::from_ticks((cycles as u64 * 1_000_000_000) / clock.raw() as u64)
+}
+
+/// # Stopwatch
+/// Measures the cycles since it was started, or since the last `lap`.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Stopwatch {
+    start: u32,
+}
+
+impl Stopwatch {
+    /// # Start
+    /// Start a new stopwatch now. The cycle counter must be enabled, see `enable`.
+    #[inline(always)]
+    pub fn start() -> Self {
+        Self { start: now() }
+    }
+
+    /// # Restart
+    /// Start counting from now again.
+    #[inline(always)]
+    pub fn restart(&mut self) {
+        self.start = now();
+    }
+
+    /// # Elapsed Cycles
+    /// The cycles since the stopwatch was started.
+    #[inline(always)]
+    pub fn elapsed_cycles(&self) -> u32 {
+        now().wrapping_sub(self.start)
+    }
+
+    /// # Lap
+    /// The cycles since the stopwatch was started, restarting it.
+    #[inline(always)]
+    pub fn lap(&mut self) -> u32 {
+        let now = now();
+        let elapsed = now.wrapping_sub(self.start);
+        self.start = now;
+        elapsed
+    }
+
+    /// # Elapsed
+    /// The time since the stopwatch was started, at the current core clock.
+    pub fn elapsed(&self) -> Nanos {
+        cycles_to_duration(self.elapsed_cycles(), crate::core_clock())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        assert_eq!(cycles_to_duration(100, Hertz::MHz(100)).ticks(), 1_000);
+        assert_eq!(
+            cycles_to_duration(100_000_000, Hertz::MHz(100)).to_micros(),
+            1_000_000
+        );
+        // The widest count must not overflow
+        assert_eq!(
+            cycles_to_duration(u32::MAX, Hertz::MHz(1_000)).ticks(),
+            u32::MAX as u64
+        );
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn stopwatch_wraps() {
+        crate::mock::reset();
+
+        enable();
+        assert!(is_enabled());
+
+        crate::mock::write(scs::DWT_CYCCNT, u32::MAX - 9);
+        let mut stopwatch = Stopwatch::start();
+        crate::mock::write(scs::DWT_CYCCNT, 20);
+        assert_eq!(stopwatch.lap(), 30);
+
+        crate::mock::write(scs::DWT_CYCCNT, 25);
+        assert_eq!(stopwatch.elapsed_cycles(), 5);
+    }
+}
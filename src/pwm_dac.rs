@@ -0,0 +1,254 @@
+//! # PWM DAC
+//! Cheap analog output for tones and waveforms on a chip without a DAC. A timer runs in
+//! PWM mode with one PWM period per sample, and the duty of every period is the next
+//! sample of a buffer. Low pass filter the timer output (ie. a RC filter, or a speaker)
+//! to get the waveform back.
+//!
+//! None of the MAX78000's DMA request lines are driven by the timers, so a DMA channel
+//! can not be paced by the sample rate. Instead the timer's own interrupt, which fires
+//! at the end of every period, moves the next sample into `TMR_PWM`. The duty is only
+//! ever written at a period boundary, so every period is a whole sample.
+//!
+//! # Example
+//! ```no_run
+//! use core::cell::RefCell;
+//! use critical_section::Mutex;
+//! use max78000_hal::bind_interrupts;
+//! use max78000_hal::interrupt::typelevel::{Handler, TMR2};
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::pwm_dac::PwmDac;
+//! use max78000_hal::time::RateExtU32;
+//! use max78000_hal::timer::TimerInstance;
+//!
+//! static SINE: [u8; 32] = [
+//!     128, 153, 177, 199, 218, 234, 245, 253, 255, 253, 245, 234, 218, 199, 177, 153, 128,
+//!     103, 79, 57, 38, 22, 11, 3, 1, 3, 11, 22, 38, 57, 79, 103,
+//! ];
+//! static DAC: Mutex<RefCell<Option<PwmDac<{ mmio::TIMER_2 }>>>> =
+//!     Mutex::new(RefCell::new(None));
+//!
+//! struct Sample;
+//! impl Handler<TMR2> for Sample {
+//!     unsafe fn on_interrupt() {
+//!         critical_section::with(|cs| {
+//!             if let Some(dac) = DAC.borrow_ref_mut(cs).as_mut() {
+//!                 dac.on_interrupt();
+//!             }
+//!         });
+//!     }
+//! }
+//!
+//! bind_interrupts!(struct Irqs { TMR2 => Sample; });
+//!
+//! let mut dac = PwmDac::new(TimerInstance::instance(), 32.kHz())?;
+//! dac.play(&SINE, true);
+//! critical_section::with(|cs| DAC.replace(cs, Some(dac)));
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use crate::error::{ErrorKind, Result};
+use crate::gcr::{peripheral_reset, system_clock_enable};
+use crate::time::Hertz;
+use crate::timer::registers::{TimerMode, TimerPrescaler};
+use crate::timer::{hardware_source, pwm_match, TimerInstance};
+
+/// # Silence
+/// The sample output while nothing is playing, the middle of the range.
+pub const SILENCE: u8 = 0x80;
+
+/// # PWM DAC
+/// Plays 8-bit unsigned samples on the output of the timer at `PORT`.
+pub struct PwmDac<const PORT: usize> {
+    timer: TimerInstance<PORT>,
+    period: u32,
+    samples: &'static [u8],
+    position: usize,
+    looping: bool,
+}
+
+// Only the timer registers and a `'static` sample buffer are held, and every access to
+// them is through `&mut self`, so the DAC can move into the cell its interrupt reads
+unsafe impl<const PORT: usize> Send for PwmDac<PORT> {}
+
+impl<const PORT: usize> PwmDac<PORT> {
+    /// # New
+    /// Set up the timer for PWM at `sample_rate` from the peripheral clock, and start
+    /// it outputting `SILENCE`. The timer output pin must already be routed to the
+    /// timer, and the interrupt of the timer must be bound to a handler calling
+    /// `on_interrupt` and enabled in the NVIC.
+    ///
+    /// # Errors
+    /// - `ErrorKind::NoDevice` when `PORT` is a low power timer.
+    /// - `ErrorKind::BadParam` when the sample rate is zero, or too fast to leave a
+    ///   period of at least 256 clocks (one per sample step).
+    pub fn new(timer: TimerInstance<PORT>, sample_rate: Hertz) -> Result<Self> {
+        let source = hardware_source(PORT)?;
+        let period = sample_period(crate::core_peripheral_clock(), sample_rate)?;
+
+        peripheral_reset(source);
+        system_clock_enable(source, true);
+
+        let mut dac = Self {
+            timer,
+            period,
+            samples: &[],
+            position: 0,
+            looping: false,
+        };
+
+        unsafe {
+            dac.timer.set_timera_enable(false);
+            dac.timer.set_timera_clock_source(0);
+            dac.timer.set_timera_prescaler_select(TimerPrescaler::Div1);
+            dac.timer.set_timera_mode_select(TimerMode::Pwm);
+            dac.timer.set_timer_count(1);
+            dac.timer.set_timer_compare_value(period);
+            dac.timer.set_pwm(duty(SILENCE, period));
+            dac.timer.set_output_enable(true);
+            dac.timer.set_timera_interrupt_enable(true);
+            dac.timer.set_timera_clock_enable(true);
+        }
+        while !dac.timer.get_timera_clock_ready() {}
+        unsafe { dac.timer.set_timera_enable(true) };
+
+        Ok(dac)
+    }
+
+    /// # Period
+    /// The number of timer clocks in one sample.
+    pub fn period(&self) -> u32 {
+        self.period
+    }
+
+    /// # Play
+    /// Start playing `samples` from the next period boundary, replacing what was
+    /// playing. With `looping` the buffer repeats until `stop`, otherwise the output
+    /// goes back to `SILENCE` after the last sample.
+    pub fn play(&mut self, samples: &'static [u8], looping: bool) {
+        self.samples = samples;
+        self.position = 0;
+        self.looping = looping;
+    }
+
+    /// # Stop
+    /// Stop playing, the output goes back to `SILENCE` at the next period boundary.
+    pub fn stop(&mut self) {
+        self.samples = &[];
+        self.position = 0;
+    }
+
+    /// # Is Playing
+    /// Check if there are samples left to play.
+    pub fn is_playing(&self) -> bool {
+        self.position < self.samples.len()
+    }
+
+    /// # On Interrupt
+    /// Load the duty of the next sample. Call this from the timer's interrupt handler.
+    pub fn on_interrupt(&mut self) {
+        unsafe { self.timer.clear_timera_interrupt_event() };
+
+        let sample = match self.samples.get(self.position) {
+            Some(sample) => {
+                self.position += 1;
+                if self.looping && self.position == self.samples.len() {
+                    self.position = 0;
+                }
+                *sample
+            }
+            None => SILENCE,
+        };
+
+        unsafe { self.timer.set_pwm(duty(sample, self.period)) };
+    }
+
+    /// # Free
+    /// Stop the timer, and give it back.
+    pub fn free(mut self) -> TimerInstance<PORT> {
+        unsafe {
+            self.timer.set_timera_enable(false);
+            self.timer.set_timera_interrupt_enable(false);
+            self.timer.set_output_enable(false);
+        }
+        self.timer
+    }
+}
+
+/// # Sample Period
+/// The compare value of a PWM timer counting at `clock`, so a period lasts one sample.
+fn sample_period(clock: Hertz, sample_rate: Hertz) -> Result<u32> {
+    match clock.raw().checked_div(sample_rate.raw()) {
+        Some(period) if period >= 256 => Ok(period),
+        _ => Err(ErrorKind::BadParam),
+    }
+}
+
+/// # Duty
+/// The PWM match value keeping the output high for `sample` 256ths of a period of
+/// `period` clocks.
+fn duty(sample: u8, period: u32) -> u32 {
+    pwm_match(((sample as u64 * period as u64) >> 8) as u32, period)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sample_period_values() {
+        // 50 MHz, 32 kHz audio
+        let clock = Hertz::MHz(50);
+        assert_eq!(sample_period(clock, Hertz::kHz(32)), Ok(1_562));
+        assert_eq!(sample_period(clock, Hertz::Hz(0)), Err(ErrorKind::BadParam));
+        assert_eq!(
+            sample_period(clock, Hertz::kHz(200)),
+            Err(ErrorKind::BadParam)
+        );
+    }
+
+    #[test]
+    fn duty_values() {
+        // The output is high after the match, so louder samples match earlier
+        assert_eq!(duty(0, 1_024), 1_024);
+        assert_eq!(duty(SILENCE, 1_024), 512);
+        assert_eq!(duty(0xFF, 1_024), 4);
+        assert_eq!(duty(0xFF, u32::MAX), 0x0100_0000);
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn plays_samples_at_period_boundaries() {
+        use crate::memory_map::mmio;
+
+        crate::mock::reset();
+
+        static SAMPLES: [u8; 3] = [0x00, 0x40, 0xFF];
+        let mut dac = PwmDac {
+            timer: TimerInstance::<{ mmio::TIMER_2 }>::instance(),
+            period: 256,
+            samples: &[],
+            position: 0,
+            looping: false,
+        };
+
+        let mut played = [0; 4];
+        dac.play(&SAMPLES, false);
+        for duty in played.iter_mut() {
+            dac.on_interrupt();
+            *duty = dac.timer.get_pwm();
+        }
+        assert_eq!(played, [256, 0xC0, 0x01, SILENCE as u32]);
+        assert!(!dac.is_playing());
+
+        dac.play(&SAMPLES, true);
+        for duty in played.iter_mut() {
+            dac.on_interrupt();
+            *duty = dac.timer.get_pwm();
+        }
+        assert_eq!(played, [256, 0xC0, 0x01, 256]);
+        assert!(dac.is_playing());
+
+        dac.stop();
+        dac.on_interrupt();
+        assert_eq!(dac.timer.get_pwm(), SILENCE as u32);
+    }
+}
@@ -0,0 +1,301 @@
+//! # RAM Regions
+//! The MAX78000's RAM is split into banks that can be powered (and retained in low
+//! power modes) separately, and the CNN has its own data RAM per quadrant. Large
+//! buffers, ie. the input of a inference, should land in a bank on purpose, which
+//! this module helps with in two ways:
+//! - `in_region!` places a `static` into a linker section named after the region.
+//! - `RegionAllocator` hands out buffers from a region at runtime.
+use core::mem::{align_of, size_of, MaybeUninit};
+
+use crate::error::{ErrorKind, Result};
+use crate::memory_map::memory;
+
+/// # Region
+/// A contiguous range of RAM.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Region {
+    name: &'static str,
+    start: usize,
+    size: usize,
+    word_access_only: bool,
+}
+
+impl Region {
+    /// # New
+    /// Describe the `size` bytes of RAM at `start`.
+    pub const fn new(name: &'static str, start: usize, size: usize) -> Self {
+        Self {
+            name,
+            start,
+            size,
+            word_access_only: false,
+        }
+    }
+
+    const fn words_only(mut self) -> Self {
+        self.word_access_only = true;
+        self
+    }
+
+    /// # Name
+    /// The name of the region, which is also its linker section (ie. `.sysram2`).
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// # Start
+    /// The first address of the region.
+    pub const fn start(&self) -> usize {
+        self.start
+    }
+
+    /// # Size
+    /// The size of the region in bytes.
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    /// # End
+    /// The first address after the region.
+    pub const fn end(&self) -> usize {
+        self.start + self.size
+    }
+
+    /// # Is Word Access Only
+    /// If the region must only be accessed 32 bits at a time (the CNN data RAM).
+    pub const fn is_word_access_only(&self) -> bool {
+        self.word_access_only
+    }
+
+    /// # Contains
+    /// Check if `address` is inside the region.
+    pub const fn contains(&self, address: usize) -> bool {
+        address >= self.start && address < self.end()
+    }
+
+    /// # Contains Slice
+    /// Check if every byte of `slice` is inside the region.
+    pub fn contains_slice<T>(&self, slice: &[T]) -> bool {
+        let start = slice.as_ptr() as usize;
+        start >= self.start && start + core::mem::size_of_val(slice) <= self.end()
+    }
+
+    /// # Sub Region
+    /// The `size` bytes at `offset` into this region.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when the range does not fit in this region.
+    pub const fn sub_region(&self, offset: usize, size: usize) -> Result<Self> {
+        match offset.checked_add(size) {
+            Some(end) if end <= self.size => Ok(Self {
+                name: self.name,
+                start: self.start + offset,
+                size,
+                word_access_only: self.word_access_only,
+            }),
+            _ => Err(ErrorKind::BadParam),
+        }
+    }
+}
+
+/// # Regions
+/// Every RAM region of the MAX78000. The names match the linker sections `in_region!`
+/// uses.
+pub mod regions {
+    use super::{memory, Region};
+
+    /// # System RAM Bank 0
+    pub const SYSRAM0: Region = Region::new("sysram0", memory::SYSRAM_0.0, memory::SYSRAM_0.1);
+    /// # System RAM Bank 1
+    pub const SYSRAM1: Region = Region::new("sysram1", memory::SYSRAM_1.0, memory::SYSRAM_1.1);
+    /// # System RAM Bank 2
+    pub const SYSRAM2: Region = Region::new("sysram2", memory::SYSRAM_2.0, memory::SYSRAM_2.1);
+    /// # System RAM Bank 3
+    pub const SYSRAM3: Region = Region::new("sysram3", memory::SYSRAM_3.0, memory::SYSRAM_3.1);
+    /// # CNN Data RAM Quadrant 0
+    pub const CNN_DATA0: Region =
+        Region::new("cnn_data0", memory::CNN_DATA_0.0, memory::CNN_DATA_0.1).words_only();
+    /// # CNN Data RAM Quadrant 1
+    pub const CNN_DATA1: Region =
+        Region::new("cnn_data1", memory::CNN_DATA_1.0, memory::CNN_DATA_1.1).words_only();
+    /// # CNN Data RAM Quadrant 2
+    pub const CNN_DATA2: Region =
+        Region::new("cnn_data2", memory::CNN_DATA_2.0, memory::CNN_DATA_2.1).words_only();
+    /// # CNN Data RAM Quadrant 3
+    pub const CNN_DATA3: Region =
+        Region::new("cnn_data3", memory::CNN_DATA_3.0, memory::CNN_DATA_3.1).words_only();
+
+    /// # All
+    /// Every region, in address order.
+    pub const ALL: [Region; 8] = [
+        SYSRAM0, SYSRAM1, SYSRAM2, SYSRAM3, CNN_DATA0, CNN_DATA1, CNN_DATA2, CNN_DATA3,
+    ];
+}
+
+/// # Region Of
+/// Find the region `address` is in.
+pub fn region_of(address: usize) -> Option<Region> {
+    regions::ALL
+        .into_iter()
+        .find(|region| region.contains(address))
+}
+
+/// # In Region
+/// Place `static` items into the linker section of a region, ie. `.sysram2`. The
+/// linker script (`memory.x`) must map that section into the region, and should mark
+/// it `NOLOAD`, in which case the initial value is never written and the static must be
+/// initialized at runtime (ie. by being a `MaybeUninit`).
+///
+/// # Example
+/// ```no_run
+/// use core::mem::MaybeUninit;
+///
+/// max78000_hal::in_region!(sysram2,
+///     static mut INPUT: MaybeUninit<[u32; 8192]> = MaybeUninit::uninit();
+/// );
+/// ```
+#[macro_export]
+macro_rules! in_region {
+    ($region:ident, $($item:item)*) => {
+        $(
+            #[link_section = concat!(".", stringify!($region))]
+            $item
+        )*
+    };
+}
+
+/// # Region Allocator
+/// A bump allocator handing out buffers from a region. Buffers live forever, the only
+/// way to get memory back is `reset`.
+pub struct RegionAllocator {
+    region: Region,
+    next: usize,
+}
+
+impl RegionAllocator {
+    /// # New
+    /// Make a allocator owning `region`.
+    ///
+    /// # Safety
+    /// Nothing else (including the linker, or a other allocator) may use any of the
+    /// region while the allocator, or a buffer from it, is alive.
+    pub const unsafe fn new(region: Region) -> Self {
+        Self {
+            region,
+            next: region.start,
+        }
+    }
+
+    /// # Region
+    /// The region this allocator hands out buffers from.
+    pub const fn region(&self) -> Region {
+        self.region
+    }
+
+    /// # Used
+    /// The number of bytes handed out so far, including alignment padding.
+    pub const fn used(&self) -> usize {
+        self.next - self.region.start
+    }
+
+    /// # Remaining
+    /// The number of bytes left.
+    pub const fn remaining(&self) -> usize {
+        self.region.end() - self.next
+    }
+
+    /// # Alloc Uninit
+    /// Hand out room for `count` values of `T`.
+    ///
+    /// # Errors
+    /// - `ErrorKind::Overflow` when the region does not have enough room left.
+    /// - `ErrorKind::BadParam` when the region only allows 32-bit accesses, and `T` is
+    ///   not made of whole 32-bit words.
+    pub fn alloc_uninit<T>(&mut self, count: usize) -> Result<&'static mut [MaybeUninit<T>]> {
+        if self.region.word_access_only
+            && (!size_of::<T>().is_multiple_of(4) || align_of::<T>() < 4)
+        {
+            return Err(ErrorKind::BadParam);
+        }
+
+        let start = self.next.next_multiple_of(align_of::<T>());
+        let end = size_of::<T>()
+            .checked_mul(count)
+            .and_then(|size| start.checked_add(size))
+            .filter(|end| *end <= self.region.end())
+            .ok_or(ErrorKind::Overflow)?;
+
+        self.next = end;
+        Ok(unsafe { core::slice::from_raw_parts_mut(start as *mut MaybeUninit<T>, count) })
+    }
+
+    /// # Alloc
+    /// Hand out `count` values of `T`, every one set to `value`.
+    ///
+    /// # Errors
+    /// See `alloc_uninit`.
+    pub fn alloc<T: Copy>(&mut self, count: usize, value: T) -> Result<&'static mut [T]> {
+        let buffer = self.alloc_uninit::<T>(count)?;
+        for slot in buffer.iter_mut() {
+            slot.write(value);
+        }
+
+        Ok(unsafe { &mut *(buffer as *mut [MaybeUninit<T>] as *mut [T]) })
+    }
+
+    /// # Reset
+    /// Take back every buffer handed out.
+    ///
+    /// # Safety
+    /// Every buffer from this allocator must no longer be used.
+    pub unsafe fn reset(&mut self) {
+        self.next = self.region.start;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lookup_regions() {
+        assert_eq!(region_of(0x2000_8000), Some(regions::SYSRAM1));
+        assert_eq!(region_of(0x2001_FFFF), Some(regions::SYSRAM3));
+        assert_eq!(region_of(0x2002_0000), None);
+        assert!(regions::CNN_DATA2.is_word_access_only());
+
+        // The banks cover all of the system RAM, without gaps
+        assert_eq!(regions::SYSRAM0.start(), memory::SRAM.0);
+        for pair in regions::ALL[..4].windows(2) {
+            assert_eq!(pair[0].end(), pair[1].start());
+        }
+        assert_eq!(regions::SYSRAM3.end(), memory::SRAM.0 + memory::SRAM.1);
+    }
+
+    #[test]
+    fn allocator_bounds() {
+        static mut BACKING: [u32; 16] = [0; 16];
+
+        let start = core::ptr::addr_of_mut!(BACKING) as usize;
+        let region = Region::new("test", start, 64);
+        let mut allocator = unsafe { RegionAllocator::new(region) };
+
+        let bytes = allocator.alloc(3, 0xAAu8).unwrap();
+        assert_eq!(bytes, [0xAA; 3]);
+        assert!(region.contains_slice(bytes));
+
+        // Padded up to the alignment of `u32`
+        let words = allocator.alloc(4, 7u32).unwrap();
+        assert_eq!(words.as_ptr() as usize, start + 4);
+        assert_eq!(allocator.used(), 20);
+
+        assert_eq!(allocator.alloc::<u32>(12, 0), Err(ErrorKind::Overflow));
+        assert_eq!(allocator.alloc::<u32>(11, 0).map(|b| b.len()), Ok(11));
+        assert_eq!(allocator.remaining(), 0);
+
+        let words_only = region.words_only();
+        let mut allocator = unsafe { RegionAllocator::new(words_only) };
+        assert_eq!(allocator.alloc(1, 0u8), Err(ErrorKind::BadParam));
+    }
+}
@@ -0,0 +1,397 @@
+//! # Self Test
+//! Opt-in power-on self tests (POST), for production test and field diagnostics. Pick
+//! the checks to run with `SelfTest`, then `run` them to get a `Report`.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::ram::regions;
+//! use max78000_hal::selftest::SelfTest;
+//! use max78000_hal::trng::TRNG;
+//! # use max78000_hal::uart::{BaudRates, CharacterLength, Parity, ParityValueSelect, StopBits, UART};
+//! # const IMAGE_START: usize = 0x1000_0000;
+//! # const IMAGE_LEN: usize = 64 * 1024;
+//! # const IMAGE_CRC: u32 = 0;
+//! # let mut uart = UART::port_0_init(
+//! #     BaudRates::Baud115200,
+//! #     CharacterLength::EightBits,
+//! #     StopBits::OneBit,
+//! #     false,
+//! #     Parity::Odd,
+//! #     ParityValueSelect::OneBased,
+//! #     false,
+//! # )?;
+//!
+//! // Nothing is kept in the last KiB of SYSRAM3
+//! let scratch = regions::SYSRAM3.sub_region(regions::SYSRAM3.size() - 1024, 1024)?;
+//! let mut trng = TRNG::init();
+//!
+//! let report = unsafe { SelfTest::new().sram(scratch) }
+//!     .flash_crc(IMAGE_START, IMAGE_LEN, IMAGE_CRC)
+//!     .trng(&mut trng)
+//!     .uart_loopback(&mut uart)
+//!     .cnn()
+//!     .run();
+//!
+//! if !report.passed() {
+//!     panic!("POST failed: {:?}", report);
+//! }
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use crate::flc::crc32;
+use crate::gcr::{system_clock_enable, HardwareSource};
+use crate::ram::{regions, Region};
+use crate::trng::TRNG;
+use crate::uart::private::UARTPortCompatable;
+use crate::uart::UART;
+
+/// # Sram Patterns
+/// Every word of the scratch region is written and read back with each of these,
+/// catching stuck bits and shorted neighbours.
+const SRAM_PATTERNS: [u32; 4] = [0x0000_0000, 0xFFFF_FFFF, 0x5555_5555, 0xAAAA_AAAA];
+
+/// # TRNG Samples
+/// The number of words the TRNG health check looks at.
+const TRNG_SAMPLES: usize = 64;
+
+/// # Loopback Pattern
+/// The bytes sent through a loopback, chosen so every bit toggles.
+const LOOPBACK_PATTERN: [u8; 4] = [0x55, 0xAA, 0x00, 0xFF];
+
+/// # Loopback Timeout
+/// How many times a loopback polls for the echoed byte before giving up.
+const LOOPBACK_TIMEOUT: usize = 100_000;
+
+/// # Outcome
+/// The result of a single check.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Outcome {
+    /// The check was not selected.
+    Skipped,
+    /// The check ran and passed.
+    Passed,
+    /// The check ran and failed.
+    Failed,
+}
+
+impl From<bool> for Outcome {
+    fn from(passed: bool) -> Self {
+        if passed {
+            Outcome::Passed
+        } else {
+            Outcome::Failed
+        }
+    }
+}
+
+/// # Report
+/// The outcome of every check, as returned by `SelfTest::run`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Report {
+    /// The SRAM pattern test on the scratch region.
+    pub sram: Outcome,
+    /// The CRC of the flash image.
+    pub flash: Outcome,
+    /// The TRNG health checks.
+    pub trng: Outcome,
+    /// The UART loopback.
+    pub uart: Outcome,
+    /// The SPI loopback.
+    pub spi: Outcome,
+    /// The presence of every CNN quadrant's data RAM.
+    pub cnn: [Outcome; 4],
+}
+
+impl Report {
+    /// # Outcomes
+    /// Every outcome in the report.
+    pub fn outcomes(&self) -> [Outcome; 9] {
+        [
+            self.sram,
+            self.flash,
+            self.trng,
+            self.uart,
+            self.spi,
+            self.cnn[0],
+            self.cnn[1],
+            self.cnn[2],
+            self.cnn[3],
+        ]
+    }
+
+    /// # Passed
+    /// Check that no selected check failed.
+    pub fn passed(&self) -> bool {
+        self.failures() == 0
+    }
+
+    /// # Failures
+    /// The number of checks that failed.
+    pub fn failures(&self) -> usize {
+        self.outcomes()
+            .iter()
+            .filter(|outcome| **outcome == Outcome::Failed)
+            .count()
+    }
+}
+
+/// # Loopback
+/// A link whose output is wired back into its input, ie. a UART with TX jumpered to
+/// RX, or a SPI with MOSI jumpered to MISO.
+pub trait Loopback {
+    /// # Echo
+    /// Send `byte`, and return what came back, or `None` if nothing did.
+    fn echo(&mut self, byte: u8) -> Option<u8>;
+}
+
+impl<Port: UARTPortCompatable> Loopback for UART<Port> {
+    fn echo(&mut self, byte: u8) -> Option<u8> {
+        self.write_blocking_transmit_fifo(byte);
+        (0..LOOPBACK_TIMEOUT).find_map(|_| self.read_receive_fifo().ok())
+    }
+}
+
+/// Lets a SPI transfer (or anything else) be used as a loopback with a closure.
+impl<F: FnMut(u8) -> Option<u8>> Loopback for F {
+    fn echo(&mut self, byte: u8) -> Option<u8> {
+        self(byte)
+    }
+}
+
+/// # Self Test
+/// The checks to run, each one is skipped unless selected.
+#[derive(Default)]
+pub struct SelfTest<'a> {
+    sram: Option<Region>,
+    flash: Option<(usize, usize, u32)>,
+    trng: Option<&'a mut TRNG>,
+    uart: Option<&'a mut dyn Loopback>,
+    spi: Option<&'a mut dyn Loopback>,
+    cnn: bool,
+}
+
+impl<'a> SelfTest<'a> {
+    /// # New
+    /// Start with no checks selected.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # SRAM
+    /// Pattern test every word of `scratch`. Its contents are lost.
+    ///
+    /// # Safety
+    /// `scratch` must not hold anything in use, ie. the stack or statics.
+    pub unsafe fn sram(mut self, scratch: Region) -> Self {
+        self.sram = Some(scratch);
+        self
+    }
+
+    /// # Flash CRC
+    /// Check the `flc::crc32` of `len` bytes of flash at `start` is `expected`.
+    pub fn flash_crc(mut self, start: usize, len: usize, expected: u32) -> Self {
+        self.flash = Some((start, len, expected));
+        self
+    }
+
+    /// # TRNG
+    /// Run health checks on the TRNG's output.
+    pub fn trng(mut self, trng: &'a mut TRNG) -> Self {
+        self.trng = Some(trng);
+        self
+    }
+
+    /// # UART Loopback
+    /// Check bytes sent on `uart` come back, TX must be wired to RX.
+    pub fn uart_loopback(mut self, uart: &'a mut dyn Loopback) -> Self {
+        self.uart = Some(uart);
+        self
+    }
+
+    /// # SPI Loopback
+    /// Check bytes sent on a SPI come back, MOSI must be wired to MISO.
+    pub fn spi_loopback(mut self, spi: &'a mut dyn Loopback) -> Self {
+        self.spi = Some(spi);
+        self
+    }
+
+    /// # CNN
+    /// Check the data RAM of every CNN quadrant responds. This enables the CNN clock,
+    /// the CNN must already be powered up.
+    pub fn cnn(mut self) -> Self {
+        self.cnn = true;
+        self
+    }
+
+    /// # Run
+    /// Run the selected checks.
+    pub fn run(self) -> Report {
+        let mut report = Report {
+            sram: Outcome::Skipped,
+            flash: Outcome::Skipped,
+            trng: Outcome::Skipped,
+            uart: Outcome::Skipped,
+            spi: Outcome::Skipped,
+            cnn: [Outcome::Skipped; 4],
+        };
+
+        if let Some(scratch) = self.sram {
+            report.sram = unsafe { sram_pattern(scratch) }.into();
+        }
+
+        if let Some((start, len, expected)) = self.flash {
+            let image = unsafe { core::slice::from_raw_parts(start as *const u8, len) };
+            report.flash = (crc32(image) == expected).into();
+        }
+
+        if let Some(trng) = self.trng {
+            let mut samples = [0u32; TRNG_SAMPLES];
+            samples
+                .iter_mut()
+                .for_each(|sample| *sample = trng.get_trng_data());
+            report.trng = trng_healthy(&samples).into();
+        }
+
+        if let Some(uart) = self.uart {
+            report.uart = loopback(uart).into();
+        }
+
+        if let Some(spi) = self.spi {
+            report.spi = loopback(spi).into();
+        }
+
+        if self.cnn {
+            system_clock_enable(HardwareSource::CNN, true);
+            let quadrants = [
+                regions::CNN_DATA0,
+                regions::CNN_DATA1,
+                regions::CNN_DATA2,
+                regions::CNN_DATA3,
+            ];
+            for (outcome, quadrant) in report.cnn.iter_mut().zip(quadrants) {
+                *outcome = unsafe { cnn_present(quadrant) }.into();
+            }
+        }
+
+        report
+    }
+}
+
+/// Write and read back every pattern, then every word's own address.
+unsafe fn sram_pattern(scratch: Region) -> bool {
+    let words = || (scratch.start()..scratch.end() - 3).step_by(4);
+    let write = |address: usize, value: u32| unsafe {
+        core::ptr::write_volatile(address as *mut u32, value)
+    };
+    let read = |address: usize| unsafe { core::ptr::read_volatile(address as *const u32) };
+
+    for pattern in SRAM_PATTERNS {
+        words().for_each(|address| write(address, pattern));
+        if !words().all(|address| read(address) == pattern) {
+            return false;
+        }
+    }
+
+    words().for_each(|address| write(address, address as u32));
+    words().all(|address| read(address) == address as u32)
+}
+
+/// A stuck or biased TRNG fails at least one of these: repeated words, words that are
+/// all zeros or all ones, or a overall share of ones far from half.
+fn trng_healthy(samples: &[u32]) -> bool {
+    let repeated = samples.windows(2).any(|pair| pair[0] == pair[1]);
+    let stuck = samples
+        .iter()
+        .any(|sample| *sample == 0 || *sample == u32::MAX);
+
+    let ones: usize = samples
+        .iter()
+        .map(|sample| sample.count_ones() as usize)
+        .sum();
+    let bits = samples.len() * 32;
+    let balanced = (bits * 4 / 10..=bits * 6 / 10).contains(&ones);
+
+    !repeated && !stuck && balanced
+}
+
+fn loopback(link: &mut dyn Loopback) -> bool {
+    LOOPBACK_PATTERN
+        .iter()
+        .all(|byte| link.echo(*byte) == Some(*byte))
+}
+
+/// Check the first and last word of a quadrant's data RAM hold what is written, the
+/// previous contents are restored after.
+unsafe fn cnn_present(quadrant: Region) -> bool {
+    [quadrant.start(), quadrant.end() - 4]
+        .iter()
+        .all(|address| {
+            let word = *address as *mut u32;
+            let saved = core::ptr::read_volatile(word);
+
+            let present = SRAM_PATTERNS[2..].iter().all(|pattern| {
+                core::ptr::write_volatile(word, *pattern);
+                core::ptr::read_volatile(word) == *pattern
+            });
+
+            core::ptr::write_volatile(word, saved);
+            present
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sram_pattern_passes_on_ram() {
+        let mut scratch = [0u32; 64];
+        let region = Region::new("scratch", scratch.as_mut_ptr() as usize, 64 * 4);
+
+        assert!(unsafe { sram_pattern(region) });
+        assert_eq!(scratch[5], region.start() as u32 + 20);
+    }
+
+    #[test]
+    fn trng_health_checks() {
+        let mut state = 0x1234_5678u32;
+        let mut samples = [0u32; TRNG_SAMPLES];
+        for sample in samples.iter_mut() {
+            // xorshift32, a stand in for a healthy TRNG
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            *sample = state;
+        }
+        assert!(trng_healthy(&samples));
+
+        let mut repeated = samples;
+        repeated[10] = repeated[9];
+        assert!(!trng_healthy(&repeated));
+
+        let mut stuck = samples;
+        stuck[3] = 0;
+        assert!(!trng_healthy(&stuck));
+
+        let biased = samples.map(|sample| sample | 0xFFFF_0000);
+        assert!(!trng_healthy(&biased));
+    }
+
+    #[test]
+    fn loopback_and_report() {
+        let mut wired = |byte: u8| Some(byte);
+        let mut open = |_: u8| None;
+
+        let report = SelfTest::new()
+            .uart_loopback(&mut wired)
+            .spi_loopback(&mut open)
+            .run();
+
+        assert_eq!(report.uart, Outcome::Passed);
+        assert_eq!(report.spi, Outcome::Failed);
+        assert_eq!(report.sram, Outcome::Skipped);
+        assert_eq!(report.failures(), 1);
+        assert!(!report.passed());
+    }
+}
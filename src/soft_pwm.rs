@@ -0,0 +1,253 @@
+//! # Software PWM
+//! Drive PWM on any GPIO pin, for boards where the pins that need it are not routed to
+//! a timer output. One timer interrupts `frame_rate * resolution` times a second, and
+//! every interrupt moves all the pins one step through the frame.
+//!
+//! # Example
+//! ```no_run
+//! use core::cell::RefCell;
+//! use critical_section::Mutex;
+//! use max78000_hal::bind_interrupts;
+//! use max78000_hal::interrupt::typelevel::{Handler, TMR1};
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::soft_pwm::SoftPwm;
+//! use max78000_hal::time::RateExtU32;
+//! use max78000_hal::timer::TimerInstance;
+//! # use max78000_hal::gpio::{GpioPin, GpioSelect};
+//! # let [red, green, blue] = [0, 1, 2].map(|pin| GpioPin::new(GpioSelect::Gpio2, pin).unwrap());
+//!
+//! static PWM: Mutex<RefCell<Option<SoftPwm<{ mmio::TIMER_1 }, 3>>>> =
+//!     Mutex::new(RefCell::new(None));
+//!
+//! struct Tick;
+//! impl Handler<TMR1> for Tick {
+//!     unsafe fn on_interrupt() {
+//!         critical_section::with(|cs| {
+//!             if let Some(pwm) = PWM.borrow_ref_mut(cs).as_mut() {
+//!                 pwm.on_interrupt();
+//!             }
+//!         });
+//!     }
+//! }
+//!
+//! bind_interrupts!(struct Irqs { TMR1 => Tick; });
+//!
+//! let mut pwm = SoftPwm::new(TimerInstance::instance(), [red, green, blue], 200.Hz(), 100)?;
+//! pwm.set_duty(0, 25);
+//! pwm.start();
+//! critical_section::with(|cs| PWM.replace(cs, Some(pwm)));
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use crate::error::{ErrorKind, Result};
+use crate::gcr::{peripheral_reset, system_clock_enable};
+use crate::gpio::GpioPin;
+use crate::time::Hertz;
+use crate::timer::registers::{TimerMode, TimerPrescaler};
+use crate::timer::{hardware_source, TimerInstance};
+
+/// # Software PWM
+/// `N` GPIO pins pulsed by the interrupt of the timer at `PORT`. Every pin has its own
+/// duty, from `0` (always low) to `resolution` (always high).
+pub struct SoftPwm<const PORT: usize, const N: usize> {
+    timer: TimerInstance<PORT>,
+    pins: [GpioPin; N],
+    duty: [u8; N],
+    resolution: u8,
+    step: u8,
+}
+
+// The timer and pins are owned, and the interrupt only steps them through `&mut self`,
+// so the driver can be handed over to the context its interrupt reaches it from
+unsafe impl<const PORT: usize, const N: usize> Send for SoftPwm<PORT, N> {}
+
+impl<const PORT: usize, const N: usize> SoftPwm<PORT, N> {
+    /// # New
+    /// Set up the timer to tick `frame_rate * resolution` times a second, from the
+    /// peripheral clock. The pins must already be configured as outputs, and start low.
+    /// The timer is not started, see `start`.
+    ///
+    /// # Errors
+    /// - `ErrorKind::NoDevice` when `PORT` is a low power timer.
+    /// - `ErrorKind::BadParam` when the tick rate is zero, or faster than the timer can
+    ///   count.
+    pub fn new(
+        timer: TimerInstance<PORT>,
+        pins: [GpioPin; N],
+        frame_rate: Hertz,
+        resolution: u8,
+    ) -> Result<Self> {
+        let source = hardware_source(PORT)?;
+        let compare = tick_compare(crate::core_peripheral_clock(), frame_rate, resolution)?;
+
+        peripheral_reset(source);
+        system_clock_enable(source, true);
+
+        let mut pwm = Self {
+            timer,
+            pins,
+            duty: [0; N],
+            resolution,
+            step: 0,
+        };
+
+        unsafe {
+            pwm.timer.set_timera_enable(false);
+            pwm.timer.set_timera_clock_source(0);
+            pwm.timer.set_timera_prescaler_select(TimerPrescaler::Div1);
+            pwm.timer.set_timera_mode_select(TimerMode::Continuous);
+            pwm.timer.set_timer_count(1);
+            pwm.timer.set_timer_compare_value(compare);
+            pwm.timer.set_timera_interrupt_enable(true);
+            pwm.timer.set_timera_clock_enable(true);
+        }
+        while !pwm.timer.get_timera_clock_ready() {}
+
+        for pin in pwm.pins.iter() {
+            pin.set_output(false);
+        }
+
+        Ok(pwm)
+    }
+
+    /// # Start
+    /// Start the timer. The interrupt of the timer must be bound to a handler calling
+    /// `on_interrupt`, and enabled in the NVIC.
+    pub fn start(&mut self) {
+        unsafe { self.timer.set_timera_enable(true) };
+    }
+
+    /// # Stop
+    /// Stop the timer, and drive every pin low.
+    pub fn stop(&mut self) {
+        unsafe { self.timer.set_timera_enable(false) };
+
+        self.step = 0;
+        for pin in self.pins.iter() {
+            pin.set_output(false);
+        }
+    }
+
+    /// # Resolution
+    /// The number of steps in a frame, and the duty of a always high pin.
+    pub fn resolution(&self) -> u8 {
+        self.resolution
+    }
+
+    /// # Set Duty
+    /// Set the number of steps `channel` is high for in every frame, clamped to
+    /// `resolution`. Takes effect from the next frame.
+    ///
+    /// # Panics
+    /// Panics if `channel` is `N` or higher.
+    pub fn set_duty(&mut self, channel: usize, duty: u8) {
+        self.duty[channel] = duty.min(self.resolution);
+    }
+
+    /// # Get Duty
+    /// Get the duty of `channel`.
+    ///
+    /// # Panics
+    /// Panics if `channel` is `N` or higher.
+    pub fn get_duty(&self, channel: usize) -> u8 {
+        self.duty[channel]
+    }
+
+    /// # On Interrupt
+    /// Move every pin one step through the frame. Call this from the timer's interrupt
+    /// handler.
+    pub fn on_interrupt(&mut self) {
+        unsafe { self.timer.clear_timera_interrupt_event() };
+
+        for (pin, duty) in self.pins.iter().zip(self.duty.iter()) {
+            if self.step == 0 {
+                pin.set_output(*duty != 0);
+            } else if self.step == *duty {
+                pin.set_output(false);
+            }
+        }
+
+        self.step += 1;
+        if self.step >= self.resolution {
+            self.step = 0;
+        }
+    }
+
+    /// # Free
+    /// Stop the timer, and give back the timer and pins.
+    pub fn free(mut self) -> (TimerInstance<PORT>, [GpioPin; N]) {
+        self.stop();
+        (self.timer, self.pins)
+    }
+}
+
+/// # Tick Compare
+/// The compare value of a continuous timer counting at `clock`, so it interrupts
+/// `frame_rate * resolution` times a second.
+fn tick_compare(clock: Hertz, frame_rate: Hertz, resolution: u8) -> Result<u32> {
+    let tick_rate = frame_rate
+        .raw()
+        .checked_mul(resolution as u32)
+        .filter(|rate| *rate != 0)
+        .ok_or(ErrorKind::BadParam)?;
+
+    match clock.raw() / tick_rate {
+        0 => Err(ErrorKind::BadParam),
+        compare => Ok(compare),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tick_compare_values() {
+        // 50 MHz, 100 Hz frames of 100 steps
+        let clock = Hertz::MHz(50);
+        assert_eq!(tick_compare(clock, Hertz::Hz(100), 100), Ok(5_000));
+        assert_eq!(
+            tick_compare(clock, Hertz::Hz(0), 100),
+            Err(ErrorKind::BadParam)
+        );
+        assert_eq!(
+            tick_compare(clock, Hertz::Hz(100), 0),
+            Err(ErrorKind::BadParam)
+        );
+        assert_eq!(
+            tick_compare(Hertz::kHz(1), Hertz::Hz(100), 100),
+            Err(ErrorKind::BadParam)
+        );
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn frame_steps() {
+        use crate::gpio::GpioSelect;
+        use crate::memory_map::mmio;
+
+        crate::gpio::reset_mock();
+
+        let pins = [
+            GpioPin::new(GpioSelect::Gpio1, 20).unwrap(),
+            GpioPin::new(GpioSelect::Gpio1, 21).unwrap(),
+        ];
+        let mut pwm = SoftPwm {
+            timer: TimerInstance::<{ mmio::TIMER_3 }>::instance(),
+            pins,
+            duty: [2, 0],
+            resolution: 4,
+            step: 0,
+        };
+
+        let mut high = [0; 2];
+        for _ in 0..4 {
+            pwm.on_interrupt();
+            for (count, pin) in high.iter_mut().zip(pwm.pins.iter()) {
+                *count += pin.get_output() as u8;
+            }
+        }
+
+        assert_eq!(high, [2, 0]);
+        assert_eq!(pwm.step, 0);
+    }
+}
@@ -0,0 +1,330 @@
+//! # SPI Devices
+//! `embedded_hal::spi::SpiDevice` implementations, for the many driver crates (displays,
+//! flash chips, sensors) that want a device and its chip select rather than the bus.
+//! `ExclusiveDevice` owns the bus, `SharedDevice` borrows it from a `RefCell` so
+//! several devices can sit on it.
+//!
+//! The chip select is either a hardware slave select of the controller, kept asserted
+//! by the hardware across the operations of a transaction, or any GPIO pin, which is
+//! driven low to select. The delays drivers put between operations are waited out with
+//! the delay provider given, usually a `Delay`.
+//!
+//! # Example
+//! ```no_run
+//! use core::cell::RefCell;
+//! use max78000_hal::delay::Delay;
+//! use max78000_hal::error::ErrorKind;
+//! use max78000_hal::gpio::{GpioPin, GpioSelect};
+//! use max78000_hal::spi::device::{ChipSelect, SharedDevice};
+//! use max78000_hal::spi::{SpiMode, SPI};
+//! use max78000_hal::time::Hertz;
+//!
+//! let bus = RefCell::new(SPI::port_0_init(SpiMode::Mode0, 8, Hertz::MHz(8))?);
+//! let flash: ChipSelect = ChipSelect::Hardware { ss: 0, active_high: false };
+//! let flash = SharedDevice::new(&bus, flash, Delay::new())?;
+//! let cs = GpioPin::new(GpioSelect::Gpio1, 6).ok_or(ErrorKind::Busy)?;
+//! let display = SharedDevice::new(&bus, ChipSelect::Gpio(cs), Delay::new())?;
+//! # Ok::<(), max78000_hal::Error>(())
+//! ```
+use super::{private, SPI};
+use crate::delay::Delay;
+use crate::error::{ErrorKind, Peripheral};
+use crate::gpio::GpioPin;
+use core::cell::RefCell;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::OutputPin;
+use embedded_hal::spi::Operation;
+
+/// # Chip Select
+/// How a device is selected.
+pub enum ChipSelect<CS = GpioPin> {
+    /// A slave select of the controller, see `SPI::enable_slave_select`.
+    Hardware { ss: usize, active_high: bool },
+    /// A GPIO pin, low while selected.
+    Gpio(CS),
+}
+
+impl<CS: OutputPin> ChipSelect<CS> {
+    /// # Setup
+    /// Take the pin of a hardware slave select, or deselect a GPIO one.
+    fn setup<Port: private::SPIPortCompatable>(
+        &mut self,
+        bus: &mut SPI<Port>,
+    ) -> Result<(), crate::Error> {
+        match self {
+            ChipSelect::Hardware { ss, active_high } => {
+                bus.enable_slave_select(*ss, *active_high)
+                    .map_err(|kind| kind.within(Peripheral::Spi))?;
+                bus.deselect();
+                Ok(())
+            }
+            ChipSelect::Gpio(pin) => pin
+                .set_high()
+                .map_err(|_| ErrorKind::Fail.within(Peripheral::Spi)),
+        }
+    }
+
+    /// # Transaction
+    /// Run `operations` on `bus` with the device selected throughout, each run of
+    /// operations between delays through `SPI::transaction`.
+    ///
+    /// A hardware slave select is released as the last operation moving words ends, so
+    /// delays after it are run with the device deselected.
+    fn transaction<Port: private::SPIPortCompatable, W: private::Word>(
+        &mut self,
+        bus: &mut SPI<Port>,
+        delay: &mut impl DelayNs,
+        operations: &mut [Operation<'_, W>],
+    ) -> Result<(), crate::Error> {
+        let last = operations.iter().rposition(moves_words);
+
+        match self {
+            ChipSelect::Hardware { ss, active_high } => {
+                bus.enable_slave_select(*ss, *active_high)
+                    .map_err(|kind| kind.within(Peripheral::Spi))?;
+            }
+            ChipSelect::Gpio(pin) => {
+                bus.deselect();
+                pin.set_low()
+                    .map_err(|_| ErrorKind::Fail.within(Peripheral::Spi))?;
+            }
+        }
+
+        let mut result = Ok(());
+        let mut start = 0;
+        while start < operations.len() {
+            let end = operations[start..]
+                .iter()
+                .position(|operation| matches!(operation, Operation::DelayNs(_)))
+                .map_or(operations.len(), |delay| start + delay);
+
+            let hardware = matches!(self, ChipSelect::Hardware { .. });
+            bus.set_keep_selected(hardware && last.is_some_and(|last| last >= end));
+            result = bus.transaction(&mut operations[start..end]);
+            if result.is_err() {
+                break;
+            }
+
+            if let Some(Operation::DelayNs(ns)) = operations.get(end) {
+                delay.delay_ns(*ns);
+            }
+            start = end + 1;
+        }
+
+        match self {
+            ChipSelect::Hardware { .. } => {
+                bus.set_keep_selected(false);
+                bus.deselect();
+            }
+            ChipSelect::Gpio(pin) => pin
+                .set_high()
+                .map_err(|_| ErrorKind::Fail.within(Peripheral::Spi))?,
+        }
+        result.map_err(|kind| kind.within(Peripheral::Spi))
+    }
+}
+
+/// # Moves Words
+/// Check if `operation` clocks any words, so ends with the slave select deasserting.
+fn moves_words<W>(operation: &Operation<'_, W>) -> bool {
+    match operation {
+        Operation::Read(words) => !words.is_empty(),
+        Operation::Write(words) => !words.is_empty(),
+        Operation::Transfer(read, write) => !read.is_empty() || !write.is_empty(),
+        Operation::TransferInPlace(words) => !words.is_empty(),
+        Operation::DelayNs(_) => false,
+    }
+}
+
+/// # Exclusive Device
+/// A device with the bus all to itself.
+pub struct ExclusiveDevice<Port, CS = GpioPin, D = Delay> {
+    bus: SPI<Port>,
+    cs: ChipSelect<CS>,
+    delay: D,
+}
+
+impl<Port: private::SPIPortCompatable, CS: OutputPin, D: DelayNs> ExclusiveDevice<Port, CS, D> {
+    /// # New
+    /// Make the device, leaving it deselected.
+    ///
+    /// # Errors
+    /// The errors of `SPI::enable_slave_select` for a hardware slave select, or
+    /// `ErrorKind::Fail` when a GPIO one can not be driven.
+    pub fn new(mut bus: SPI<Port>, mut cs: ChipSelect<CS>, delay: D) -> Result<Self, crate::Error> {
+        cs.setup(&mut bus)?;
+        Ok(Self { bus, cs, delay })
+    }
+
+    /// # Bus
+    /// The bus, ie. to change its frequency.
+    pub fn bus(&mut self) -> &mut SPI<Port> {
+        &mut self.bus
+    }
+
+    /// # Free
+    /// Give back the bus and the chip select.
+    pub fn free(self) -> (SPI<Port>, ChipSelect<CS>) {
+        (self.bus, self.cs)
+    }
+}
+
+impl<Port: private::SPIPortCompatable, CS: OutputPin, D: DelayNs> embedded_hal::spi::ErrorType
+    for ExclusiveDevice<Port, CS, D>
+{
+    type Error = crate::Error;
+}
+
+impl<Port: private::SPIPortCompatable, CS: OutputPin, D: DelayNs, W: private::Word + 'static>
+    embedded_hal::spi::SpiDevice<W> for ExclusiveDevice<Port, CS, D>
+{
+    fn transaction(
+        &mut self,
+        operations: &mut [Operation<'_, W>],
+    ) -> core::result::Result<(), Self::Error> {
+        self.cs
+            .transaction(&mut self.bus, &mut self.delay, operations)
+    }
+}
+
+/// # Shared Device
+/// A device on a bus shared through a `RefCell`. Transactions borrow the bus, so the
+/// devices on it must not be used from interrupts that can preempt each other.
+pub struct SharedDevice<'a, Port, CS = GpioPin, D = Delay> {
+    bus: &'a RefCell<SPI<Port>>,
+    cs: ChipSelect<CS>,
+    delay: D,
+}
+
+impl<'a, Port: private::SPIPortCompatable, CS: OutputPin, D: DelayNs>
+    SharedDevice<'a, Port, CS, D>
+{
+    /// # New
+    /// Make the device, leaving it deselected.
+    ///
+    /// # Errors
+    /// - `ErrorKind::Busy` when the bus is borrowed.
+    /// - The errors of `SPI::enable_slave_select` for a hardware slave select, or
+    ///   `ErrorKind::Fail` when a GPIO one can not be driven.
+    pub fn new(
+        bus: &'a RefCell<SPI<Port>>,
+        mut cs: ChipSelect<CS>,
+        delay: D,
+    ) -> Result<Self, crate::Error> {
+        cs.setup(&mut *borrow(bus)?)?;
+        Ok(Self { bus, cs, delay })
+    }
+
+    /// # Free
+    /// Give back the chip select.
+    pub fn free(self) -> ChipSelect<CS> {
+        self.cs
+    }
+}
+
+/// # Borrow
+/// Borrow a shared bus, without panicking when it is borrowed already.
+fn borrow<Port>(
+    bus: &RefCell<SPI<Port>>,
+) -> Result<core::cell::RefMut<'_, SPI<Port>>, crate::Error> {
+    bus.try_borrow_mut()
+        .map_err(|_| ErrorKind::Busy.within(Peripheral::Spi))
+}
+
+impl<Port: private::SPIPortCompatable, CS: OutputPin, D: DelayNs> embedded_hal::spi::ErrorType
+    for SharedDevice<'_, Port, CS, D>
+{
+    type Error = crate::Error;
+}
+
+impl<Port: private::SPIPortCompatable, CS: OutputPin, D: DelayNs, W: private::Word + 'static>
+    embedded_hal::spi::SpiDevice<W> for SharedDevice<'_, Port, CS, D>
+{
+    fn transaction(
+        &mut self,
+        operations: &mut [Operation<'_, W>],
+    ) -> core::result::Result<(), Self::Error> {
+        self.cs
+            .transaction(&mut *borrow(self.bus)?, &mut self.delay, operations)
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::gpio::GpioSelect;
+    use crate::memory_map::mmio;
+    use crate::spi::SPI1;
+    use core::mem::ManuallyDrop;
+    use embedded_hal::spi::SpiDevice;
+
+    /// Bits 16 to 19 of `SPI_CTRL0`, the slave selects asserted.
+    const SLAVE_SELECT_ACTIVE: u32 = 0xF << 16;
+    /// Bit 8 of `SPI_CTRL0`, keep the slave select asserted.
+    const STAY_ASSERTED: u32 = 1 << 8;
+
+    /// `Delay` needs the core clock, which only exists on the target.
+    struct NoDelay;
+
+    impl DelayNs for NoDelay {
+        fn delay_ns(&mut self, _ns: u32) {}
+    }
+
+    #[test]
+    fn devices_select_around_transactions() {
+        crate::mock::reset();
+        let ctrl0 = mmio::SPI_1 + 0x04;
+        // The receive FIFO always holds two bytes, the transaction is done right away
+        crate::mock::write(mmio::SPI_1 + 0x1C, 2 << 24);
+        crate::mock::write(mmio::SPI_1 + 0x20, 1 << 11);
+        let mut spi = ManuallyDrop::into_inner(unsafe { SPI::<SPI1>::steal() });
+        spi.set_word_size(8).unwrap();
+        let bus = RefCell::new(spi);
+
+        let pin = GpioPin::new(GpioSelect::Gpio0, 24).unwrap();
+        let mut display = SharedDevice::new(&bus, ChipSelect::Gpio(pin), NoDelay).unwrap();
+        let mut flash: SharedDevice<'_, SPI1, GpioPin, NoDelay> = SharedDevice::new(
+            &bus,
+            ChipSelect::Hardware {
+                ss: 0,
+                active_high: false,
+            },
+            NoDelay,
+        )
+        .unwrap();
+        assert_eq!(crate::mock::read(ctrl0) & SLAVE_SELECT_ACTIVE, 0);
+
+        let mut id = [0x9F_u8, 0, 0];
+        flash
+            .transaction(&mut [
+                Operation::Write(&[0x9F]),
+                Operation::DelayNs(100),
+                Operation::Read(&mut id),
+            ])
+            .unwrap();
+        // The mock FIFO reads back the last byte written, reads send nothing
+        assert_eq!(id, [0x9F; 3]);
+        assert_eq!(
+            crate::mock::read(ctrl0) & (SLAVE_SELECT_ACTIVE | STAY_ASSERTED),
+            0
+        );
+
+        display.write(&[0x2C_u8, 1]).unwrap();
+        let ChipSelect::Gpio(pin) = display.free() else {
+            unreachable!()
+        };
+        assert!(pin.get_output());
+
+        // Only SS0 exists on SPI 1
+        let hardware = ChipSelect::<GpioPin>::Hardware {
+            ss: 1,
+            active_high: false,
+        };
+        assert!(SharedDevice::new(&bus, hardware, NoDelay).is_err());
+
+        // Stolen pins, never release them
+        let mut spi = bus.into_inner();
+        spi.disable_slave_select();
+        core::mem::forget(spi);
+    }
+}
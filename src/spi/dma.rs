@@ -0,0 +1,378 @@
+//! # SPI DMA
+//! Full duplex SPI transfers moved by two DMA channels, one feeding the transmit FIFO
+//! and one emptying the receive FIFO, so the CPU is free until the transfer is done.
+//!
+//! Both directions take a list of segments, gathered into (and scattered from) one
+//! transaction. Each channel holds two segments at once, the one it moves and the next
+//! in its reload registers, so the rest are handed over by `DmaTransfer::service`.
+//! Call it often enough, ie. from the channels' interrupts (see
+//! `DmaChannel::set_interrupt`), when there are more than two segments.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::asm::wfi;
+//! use max78000_hal::dma::DmaChannel;
+//! use max78000_hal::spi::{SpiMode, SPI};
+//! use max78000_hal::time::Hertz;
+//! # let mut spi = SPI::port_0_init(SpiMode::Mode0, 8, Hertz::MHz(4))?;
+//! # const READ_PAGE: u8 = 0x13;
+//!
+//! let (mut tx, mut rx) = (DmaChannel::new(0)?, DmaChannel::new(1)?);
+//! rx.set_interrupt(true);
+//!
+//! let mut header = [0; 4];
+//! let mut payload = [0; 512];
+//! let mut read: [&mut [u8]; 2] = [&mut header, &mut payload];
+//! // Never leaked, it is waited on (or dropped) before the buffers go
+//! let mut transfer = unsafe {
+//!     spi.transfer_dma(
+//!         &mut tx,
+//!         &mut rx,
+//!         &mut read,
+//!         &[&[READ_PAGE, 0, 0, 0]],
+//!     )?
+//! };
+//!
+//! // Do something else, woken by the DMA interrupt
+//! while !transfer.is_done() {
+//!     wfi();
+//! }
+//! transfer.wait()?;
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::{private, MAX_CHARACTERS, SPI};
+use crate::dma::{Block, DmaChannel, Request};
+use crate::error::{ErrorKind, Result};
+
+/// # DMA Transmit Threshold
+/// The transmit FIFO level at or below which the transmit channel moves a burst.
+const DMA_TRANSMIT_THRESHOLD: usize = 16;
+
+/// # Lane
+/// A direction of a transfer, with its channel.
+struct Lane<'a> {
+    channel: &'a mut DmaChannel,
+    request: Request,
+    burst: usize,
+    /// The next segment to hand to the channel.
+    next: usize,
+    /// The block in the reload registers, until the channel takes it.
+    pending: Option<Block>,
+}
+
+impl Lane<'_> {
+    /// # Take
+    /// The next non-empty block of `blocks`.
+    fn take(&mut self, blocks: &mut impl FnMut(usize) -> Option<Block>) -> Option<Block> {
+        while let Some(block) = blocks(self.next) {
+            self.next += 1;
+            if block.count != 0 {
+                return Some(block);
+            }
+        }
+        None
+    }
+
+    /// # Feed
+    /// Keep the channel running, and its reload registers filled, while blocks are
+    /// left. A channel that stopped before taking its reload is started with it.
+    fn feed(&mut self, mut blocks: impl FnMut(usize) -> Option<Block>) {
+        if !self.channel.is_reload_pending() {
+            self.pending = None;
+        }
+        self.channel.clear_flags();
+
+        // Segments are never longer than `MAX_CHARACTERS`, and the burst is in range,
+        // so starting and reloading never fail
+        if !self.channel.is_busy() {
+            match self.pending.take().or_else(|| self.take(&mut blocks)) {
+                Some(block) => {
+                    let _ = unsafe { self.channel.start(self.request, block, self.burst) };
+                }
+                None => return,
+            }
+        }
+
+        if !self.channel.is_reload_pending() {
+            if let Some(block) = self.take(&mut blocks) {
+                let _ = unsafe { self.channel.reload(block) };
+                self.pending = Some(block);
+            }
+        }
+    }
+
+    /// # Is Idle
+    /// Check if the channel moved every block, when no more are left to take.
+    fn is_idle(&self, exhausted: bool) -> bool {
+        exhausted && !self.channel.is_busy() && !self.channel.is_reload_pending()
+    }
+}
+
+/// # DMA Transfer
+/// A SPI transfer in progress, see `SPI::transfer_dma`. Dropping it aborts the
+/// transfer.
+pub struct DmaTransfer<'a, Port: private::SPIPortCompatable> {
+    spi: &'a mut SPI<Port>,
+    tx: Lane<'a>,
+    rx: Lane<'a>,
+    write: &'a [&'a [u8]],
+    read: &'a mut [&'a mut [u8]],
+    /// Nothing to move, so no transaction was started.
+    empty: bool,
+    finished: bool,
+}
+
+impl<Port: private::SPIPortCompatable> SPI<Port> {
+    /// # Transfer DMA
+    /// Start a transaction sending the segments of `write` one after the other, while
+    /// receiving into the segments of `read`, moved by the `tx` and `rx` channels. When
+    /// `read` is shorter the rest of the received bytes are dropped, when `write` is
+    /// shorter the rest of the bytes sent are the hardware's default.
+    ///
+    /// Characters of more than 8 bits take two bytes, least significant first (ie. a
+    /// `u16` buffer's bytes), so both directions must be a even number of bytes.
+    ///
+    /// Dual and quad transfers are half duplex, so only one of them may hold bytes.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` when either direction is longer than 65535 characters,
+    ///   or not a whole number of them.
+    /// - `ErrorKind::BadState` when sending and receiving in a dual or quad transfer.
+    ///
+    /// # Safety
+    /// The returned `DmaTransfer` must not be leaked (ie. with `mem::forget`) while in
+    /// progress. Only dropping it stops the channels, which would otherwise keep on
+    /// accessing the buffers after they are gone.
+    pub unsafe fn transfer_dma<'a>(
+        &'a mut self,
+        tx: &'a mut DmaChannel,
+        rx: &'a mut DmaChannel,
+        read: &'a mut [&'a mut [u8]],
+        write: &'a [&'a [u8]],
+    ) -> Result<DmaTransfer<'a, Port>> {
+        let bytes = if self.word_size > 8 { 2 } else { 1 };
+        let sending: usize = write.iter().map(|segment| segment.len()).sum();
+        let receiving: usize = read.iter().map(|segment| segment.len()).sum();
+        if !sending.is_multiple_of(bytes)
+            || !receiving.is_multiple_of(bytes)
+            || sending / bytes > MAX_CHARACTERS
+            || receiving / bytes > MAX_CHARACTERS
+        {
+            return Err(ErrorKind::BadParam);
+        }
+        if sending != 0 && receiving != 0 && self.data_width() != super::DataWidth::Single {
+            return Err(ErrorKind::BadState);
+        }
+
+        self.prepare(sending / bytes, receiving / bytes);
+        unsafe {
+            self.reg.clear_receive_overrun_flag();
+            self.reg
+                .set_transmit_fifo_threshold(DMA_TRANSMIT_THRESHOLD as u8);
+            self.reg.set_receive_fifo_threshold(0);
+        }
+
+        let mut transfer = DmaTransfer {
+            spi: self,
+            tx: Lane {
+                channel: tx,
+                request: Port::DMA_TRANSMIT,
+                burst: super::FIFO_DEPTH - DMA_TRANSMIT_THRESHOLD,
+                next: 0,
+                pending: None,
+            },
+            rx: Lane {
+                channel: rx,
+                request: Port::DMA_RECEIVE,
+                burst: 1,
+                next: 0,
+                pending: None,
+            },
+            write,
+            read,
+            empty: sending + receiving == 0,
+            finished: false,
+        };
+
+        transfer.service();
+        unsafe {
+            let reg = &mut transfer.spi.reg;
+            reg.set_transmit_dma_enable(sending != 0);
+            reg.set_receive_dma_enable(receiving != 0);
+            if !transfer.empty {
+                reg.activate_start_transaction();
+            }
+        }
+        Ok(transfer)
+    }
+}
+
+impl<Port: private::SPIPortCompatable> DmaTransfer<'_, Port> {
+    /// # Service
+    /// Hand the next segments to the channels, and clear their flags. Call this before
+    /// the segments a channel holds are done, see the module documentation.
+    pub fn service(&mut self) {
+        let write = self.write;
+        let fifo = Port::PORT_PTR;
+        self.tx.feed(|index| {
+            write.get(index).map(|segment| Block {
+                source: segment.as_ptr() as usize,
+                source_increment: true,
+                destination: fifo,
+                destination_increment: false,
+                count: segment.len(),
+            })
+        });
+
+        let read = &mut *self.read;
+        self.rx.feed(|index| {
+            read.get_mut(index).map(|segment| Block {
+                source: fifo,
+                source_increment: false,
+                destination: segment.as_mut_ptr() as usize,
+                destination_increment: true,
+                count: segment.len(),
+            })
+        });
+    }
+
+    /// # Is Done
+    /// Service the transfer, and check if it is done.
+    pub fn is_done(&mut self) -> bool {
+        self.service();
+
+        let sent = self.write.iter().skip(self.tx.next).all(|s| s.is_empty());
+        let received = self.read.iter().skip(self.rx.next).all(|s| s.is_empty());
+
+        self.tx.is_idle(sent)
+            && self.rx.is_idle(received)
+            && (self.empty || self.spi.reg.is_master_done_flag_active())
+    }
+
+    /// # Wait
+    /// Wait until the transfer is done, servicing it meanwhile.
+    ///
+    /// # Errors
+    /// - `ErrorKind::ComError` when a channel could not access a buffer.
+    /// - `ErrorKind::Overflow` when received bytes were lost.
+    pub fn wait(mut self) -> Result<()> {
+        while !self.is_done() {}
+
+        let bus_error = self.tx.channel.has_bus_error() || self.rx.channel.has_bus_error();
+        let overrun = self.spi.reg.is_receive_overrun_flag_active();
+        self.finish();
+
+        if bus_error {
+            return Err(ErrorKind::ComError);
+        }
+        if overrun {
+            return Err(ErrorKind::Overflow);
+        }
+        Ok(())
+    }
+
+    /// # Finish
+    /// Stop the channels, and give the FIFOs back to the CPU.
+    fn finish(&mut self) {
+        self.finished = true;
+        self.tx.channel.stop();
+        self.rx.channel.stop();
+
+        unsafe {
+            let reg = &mut self.spi.reg;
+            reg.set_transmit_dma_enable(false);
+            reg.set_receive_dma_enable(false);
+            reg.clear_master_done_flag();
+            reg.clear_receive_overrun_flag();
+        }
+    }
+}
+
+impl<Port: private::SPIPortCompatable> Drop for DmaTransfer<'_, Port> {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+
+        // Disabling the SPI ends the transaction
+        self.finish();
+        unsafe {
+            let reg = &mut self.spi.reg;
+            reg.set_spi_enable(false);
+            reg.activate_transmit_fifo_flush();
+            reg.activate_receive_fifo_flush();
+            reg.set_spi_enable(true);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::dma::channel::{CHANNEL_0, CHANNEL_1};
+    use crate::memory_map::mmio;
+    use crate::spi::SPI0;
+    use core::mem::ManuallyDrop;
+
+    #[test]
+    fn segments_chain_through_the_channels() {
+        crate::mock::reset();
+        let dma = mmio::SPI_0 + 0x1C;
+        let flags = mmio::SPI_0 + 0x20;
+        let mut spi = ManuallyDrop::into_inner(unsafe { SPI::<SPI0>::steal() });
+        spi.set_word_size(8).unwrap();
+        let mut tx = DmaChannel::new(0).unwrap();
+        let mut rx = DmaChannel::new(1).unwrap();
+
+        let (a, b, c) = ([1_u8; 3], [2_u8; 4], [3_u8; 5]);
+        let mut buffer = [0_u8; 6];
+        let destination = buffer.as_ptr() as u32;
+        let write: [&[u8]; 4] = [&a, &[], &b, &c];
+        let mut read: [&mut [u8]; 1] = [&mut buffer];
+        let mut transfer =
+            unsafe { spi.transfer_dma(&mut tx, &mut rx, &mut read, &write) }.unwrap();
+
+        // Both FIFOs moved by the DMA, twelve bytes out and six in
+        assert_eq!(crate::mock::read(dma) >> 15 & 1, 1);
+        assert_eq!(crate::mock::read(dma) >> 31, 1);
+        assert_eq!(crate::mock::read(mmio::SPI_0 + 0x08), (6 << 16) | 12);
+
+        // The first segment running, the next one (skipping the empty one) waiting
+        assert_eq!(crate::mock::read(CHANNEL_0 + 0x08), a.as_ptr() as u32);
+        assert_eq!(crate::mock::read(CHANNEL_0 + 0x14), b.as_ptr() as u32);
+        assert_eq!(crate::mock::read(CHANNEL_1 + 0x0C), destination);
+        assert_eq!(crate::mock::read(CHANNEL_1 + 0x1C), 0);
+
+        // The channel running, it takes its reload and gets the last segment
+        crate::mock::write(CHANNEL_0 + 0x04, 1);
+        assert!(!transfer.is_done());
+        crate::mock::write(CHANNEL_0 + 0x1C, 0);
+        assert!(!transfer.is_done());
+        assert_eq!(crate::mock::read(CHANNEL_0 + 0x14), c.as_ptr() as u32);
+        assert_eq!(crate::mock::read(CHANNEL_0 + 0x1C), (1 << 31) | 5);
+
+        // Stopped before taking it, the channel is started with it
+        crate::mock::write(CHANNEL_0 + 0x04, 0);
+        assert!(!transfer.is_done());
+        assert_eq!(crate::mock::read(CHANNEL_0 + 0x08), c.as_ptr() as u32);
+
+        crate::mock::write(flags, 1 << 11);
+        assert!(transfer.is_done());
+        transfer.wait().unwrap();
+        assert_eq!(crate::mock::read(dma) >> 15 & 1, 0);
+
+        // Nine bit characters take two bytes each
+        spi.set_word_size(9).unwrap();
+        let words = [0x1AB_u16, 0x1CD];
+        let bytes = [0_u8; 3];
+        let halves: [&[u8]; 1] = [&bytes];
+        assert!(unsafe { spi.transfer_dma(&mut tx, &mut rx, &mut [], &halves) }.is_err());
+        let write: [&[u8]; 1] = [unsafe { core::slice::from_raw_parts(words.as_ptr().cast(), 4) }];
+        let transfer = unsafe { spi.transfer_dma(&mut tx, &mut rx, &mut [], &write) }.unwrap();
+        assert_eq!(crate::mock::read(mmio::SPI_0 + 0x08), 2);
+        drop(transfer);
+
+        // Stolen pins, never release them
+        core::mem::forget(spi);
+    }
+}
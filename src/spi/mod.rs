@@ -0,0 +1,1081 @@
+//! # SPI
+//! The SPI controllers, SPI0 and SPI1, as masters. Transfers are full duplex: every
+//! character sent clocks one in, so a transfer sends and receives the same number of
+//! characters. Characters are 1 to 16 bits, those of up to 8 bits are moved as `u8`,
+//! longer ones as `u16`.
+//!
+//! The slave select is either one of the controller's SS pins, asserted by the
+//! hardware for every transfer (see `enable_slave_select`), or any GPIO pin driven by
+//! the caller. Each SS pin can have its own polarity and timing (see
+//! `configure_slave_select`), so several devices share the bus with the hardware
+//! switching between them, see `with_target`.
+//!
+//! For QSPI flash chips and displays the data can also move over two (MOSI and MISO)
+//! or four (adding SDIO2 and SDIO3) lines at once, see `set_data_width`. Those
+//! transfers are half duplex, the lines either send or receive, so only `write` and
+//! `read` work then. Chips taking their command on one line and the data on four are
+//! driven with the slave select kept asserted while switching widths.
+//!
+//! With the `eh1` feature the driver implements `embedded_hal::spi::SpiBus`,
+//! `transaction` runs a list of `embedded_hal::spi::Operation`s under one slave select,
+//! and `device` wraps it with a chip select into a `SpiDevice`.
+//! With the `eh0` feature it implements the embedded-hal 0.2 `blocking::spi::Transfer`
+//! and `Write` traits, and `spi::FullDuplex`.
+//!
+//! For pins the controllers can not reach, `soft` clocks SPI on any GPIO pins.
+//!
+//! `slave` turns a initialized port into a slave, serviced by its interrupt,
+//! `transfer_dma` moves bulk transfers through DMA channels, and `wait` has async
+//! transfers woken by the FIFO interrupts.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::spi::{SpiMode, SPI};
+//! use max78000_hal::time::Hertz;
+//!
+//! let mut spi = SPI::port_0_init(SpiMode::Mode0, 8, Hertz::MHz(4))?;
+//! spi.enable_slave_select(0, false)?;
+//!
+//! // Read a flash chip's JEDEC ID
+//! let mut id = [0x9F_u8, 0, 0, 0];
+//! spi.transfer_in_place(&mut id)?;
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use crate::core_peripheral_clock;
+use crate::error::{ErrorKind, Result};
+use crate::gcr::{peripheral_reset, system_clock_enable, HardwareSource};
+use crate::gpio::{GpioPin, GpioSelect, OutputDriveStrength, VoltageSelect};
+use crate::memory_map::mmio;
+use crate::time::Hertz;
+use core::cell::Cell;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+
+#[cfg(feature = "eh1")]
+pub mod device;
+pub mod dma;
+pub mod registers;
+pub mod slave;
+pub mod soft;
+#[cfg(feature = "eh1")]
+pub mod transaction;
+pub mod wait;
+
+pub(crate) mod private {
+    use super::registers::Registers;
+
+    pub trait SPIPortCompatable {
+        const PORT_PTR: usize;
+        const PORT_NUM: usize;
+        const DMA_TRANSMIT: crate::dma::Request;
+        const DMA_RECEIVE: crate::dma::Request;
+    }
+
+    /// # Word
+    /// A character moved through the FIFOs, `u8` for up to 8 bits and `u16` above.
+    pub trait Word: Copy + Default {
+        /// The bytes the character takes in the FIFOs.
+        const BYTES: usize;
+
+        /// Push into the transmit FIFO, which must have room.
+        unsafe fn push(self, reg: &mut Registers);
+
+        /// Pop from the receive FIFO, which must hold a character.
+        fn pop(reg: &mut Registers) -> Self;
+
+        /// The character's bits.
+        fn to_bits(self) -> u16;
+
+        /// The character of `bits`, cut to the word.
+        fn from_bits(bits: u16) -> Self;
+    }
+
+    impl Word for u8 {
+        const BYTES: usize = 1;
+
+        unsafe fn push(self, reg: &mut Registers) {
+            reg.write_fifo_8(self);
+        }
+
+        fn pop(reg: &mut Registers) -> Self {
+            reg.read_fifo_8()
+        }
+
+        fn to_bits(self) -> u16 {
+            self as u16
+        }
+
+        fn from_bits(bits: u16) -> Self {
+            bits as u8
+        }
+    }
+
+    impl Word for u16 {
+        const BYTES: usize = 2;
+
+        unsafe fn push(self, reg: &mut Registers) {
+            reg.write_fifo_16(self);
+        }
+
+        fn pop(reg: &mut Registers) -> Self {
+            reg.read_fifo_16()
+        }
+
+        fn to_bits(self) -> u16 {
+            self
+        }
+
+        fn from_bits(bits: u16) -> Self {
+            bits
+        }
+    }
+}
+
+pub struct NoPort {}
+pub struct SPI0 {}
+pub struct SPI1 {}
+
+impl private::SPIPortCompatable for SPI0 {
+    const PORT_PTR: usize = mmio::SPI_0;
+    const PORT_NUM: usize = 0;
+    const DMA_TRANSMIT: crate::dma::Request = crate::dma::Request::Spi0Transmit;
+    const DMA_RECEIVE: crate::dma::Request = crate::dma::Request::Spi0Receive;
+}
+impl private::SPIPortCompatable for SPI1 {
+    const PORT_PTR: usize = mmio::SPI_1;
+    const PORT_NUM: usize = 1;
+    const DMA_TRANSMIT: crate::dma::Request = crate::dma::Request::Spi1Transmit;
+    const DMA_RECEIVE: crate::dma::Request = crate::dma::Request::Spi1Receive;
+}
+
+/// # FIFO Depth
+/// The bytes the transmit and receive FIFOs each hold.
+const FIFO_DEPTH: usize = 32;
+
+/// # Max Characters
+/// The most characters a single transaction can move.
+const MAX_CHARACTERS: usize = 0xFFFF;
+
+/// # Max Clock Scale
+/// The largest power of two the peripheral clock can be divided by before SCK.
+const MAX_CLOCK_SCALE: u8 = 8;
+
+/// # Data Width
+/// The data lines a transfer moves characters over.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DataWidth {
+    /// MOSI out and MISO in, full duplex.
+    Single,
+    /// MOSI and MISO together, half duplex.
+    Dual,
+    /// MOSI, MISO, SDIO2 and SDIO3 together, half duplex.
+    Quad,
+}
+
+/// # Slave Select Timing
+/// The SCK periods around a hardware slave select, 1 to 256 with 0 counting as 256.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SlaveSelectTiming {
+    /// Between the slave select asserting and the first SCK edge.
+    pub pre: u8,
+    /// Between the last SCK edge and the slave select deasserting.
+    pub post: u8,
+    /// The slave select stays deasserted between transactions.
+    pub inactive: u8,
+}
+
+impl Default for SlaveSelectTiming {
+    fn default() -> Self {
+        Self {
+            pre: 1,
+            post: 1,
+            inactive: 1,
+        }
+    }
+}
+
+/// # SPI Mode
+/// The clock polarity (CPOL) and phase (CPHA).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SpiMode {
+    /// SCK idles low, data is sampled on the rising edge.
+    Mode0,
+    /// SCK idles low, data is sampled on the falling edge.
+    Mode1,
+    /// SCK idles high, data is sampled on the falling edge.
+    Mode2,
+    /// SCK idles high, data is sampled on the rising edge.
+    Mode3,
+}
+
+impl SpiMode {
+    /// # Polarity
+    /// Check if SCK idles high (CPOL = 1).
+    pub const fn polarity(self) -> bool {
+        matches!(self, SpiMode::Mode2 | SpiMode::Mode3)
+    }
+
+    /// # Phase
+    /// Check if data is sampled on the trailing edge (CPHA = 1).
+    pub const fn phase(self) -> bool {
+        matches!(self, SpiMode::Mode1 | SpiMode::Mode3)
+    }
+}
+
+pub struct SPI<Port = NoPort> {
+    reg: registers::Registers,
+    _ph: PhantomData<Port>,
+    _gpio: [GpioPin; 3],
+    /// The hardware slave select pins taken, by slave select.
+    slave_selects: [Option<GpioPin>; 3],
+    /// The timing of each slave select, applied when it is selected.
+    timings: [SlaveSelectTiming; 3],
+    /// SDIO2 and SDIO3, once quad transfers were enabled.
+    _quad: Option<[GpioPin; 2]>,
+    word_size: u8,
+}
+
+impl SPI<NoPort> {
+    /// # Port 0 Init
+    /// Initializes SPI 0 as a master, on `P0.7` (SCK), `P0.5` (MOSI) and `P0.6` (MISO).
+    /// # Arguments
+    /// * `mode` - The clock polarity and phase
+    /// * `word_size` - The bits in a character, 1 to 16
+    /// * `frequency` - The highest SCK frequency to use, see `set_frequency`
+    ///
+    /// # Errors
+    /// - `ErrorKind::Busy` when the pins are already owned.
+    /// - `ErrorKind::BadParam` when `word_size` or `frequency` can not be used.
+    pub fn port_0_init(mode: SpiMode, word_size: u8, frequency: Hertz) -> Result<SPI<SPI0>> {
+        peripheral_reset(HardwareSource::SPI0);
+        system_clock_enable(HardwareSource::SPI0, true);
+        SPI::<SPI0>::init(mode, word_size, frequency)
+    }
+
+    /// # Port 1 Init
+    /// Initializes SPI 1 as a master, on `P0.23` (SCK), `P0.21` (MOSI) and `P0.22`
+    /// (MISO). See `port_0_init`.
+    ///
+    /// # Errors
+    /// - `ErrorKind::Busy` when the pins are already owned.
+    /// - `ErrorKind::BadParam` when `word_size` or `frequency` can not be used.
+    pub fn port_1_init(mode: SpiMode, word_size: u8, frequency: Hertz) -> Result<SPI<SPI1>> {
+        peripheral_reset(HardwareSource::SPI1);
+        system_clock_enable(HardwareSource::SPI1, true);
+        SPI::<SPI1>::init(mode, word_size, frequency)
+    }
+}
+
+impl<Port: private::SPIPortCompatable> SPI<Port> {
+    /// # Steal
+    /// Conjure a handle to a already initialized SPI port, without resetting it or
+    /// taking ownership of its pins.
+    ///
+    /// The handle is wrapped in `ManuallyDrop`, so dropping it never releases the pins
+    /// owned by the original handle.
+    ///
+    /// # Safety
+    /// The port must have been initialized with one of the `port_n_init` functions,
+    /// and the caller must ensure the stolen handle does not race with the original
+    /// one.
+    pub unsafe fn steal() -> ManuallyDrop<Self> {
+        let reg = registers::Registers::new(Port::PORT_PTR);
+        let word_size = match reg.get_bits_per_character() {
+            0 => 16,
+            bits => bits,
+        };
+
+        ManuallyDrop::new(Self {
+            reg,
+            _ph: PhantomData,
+            _gpio: crate::gpio::hardware::steal_spi_n(Port::PORT_NUM),
+            slave_selects: [None, None, None],
+            timings: [SlaveSelectTiming::default(); 3],
+            _quad: None,
+            word_size,
+        })
+    }
+
+    fn init(mode: SpiMode, word_size: u8, frequency: Hertz) -> Result<Self> {
+        let mut spi = Self {
+            reg: registers::Registers::new(Port::PORT_PTR),
+            _ph: PhantomData,
+            _gpio: crate::gpio::hardware::spi_n(Port::PORT_NUM).ok_or(ErrorKind::Busy)?,
+            slave_selects: [None, None, None],
+            timings: [SlaveSelectTiming::default(); 3],
+            _quad: None,
+            word_size: 8,
+        };
+
+        unsafe {
+            spi.reg.set_spi_enable(false);
+            spi.reg.set_master_mode_enable(true);
+            // SS0 drives, no slave select until one is enabled
+            spi.reg.set_slave_select_input(false);
+            spi.reg.set_slave_select_active(0);
+            // Single data line each way
+            spi.reg.set_data_width(0);
+            spi.reg.set_three_wire_mode(false);
+            spi.reg.set_transmit_fifo_enable(true);
+            spi.reg.set_receive_fifo_enable(true);
+        }
+
+        spi.set_mode(mode);
+        spi.set_word_size(word_size)?;
+        spi.set_frequency(frequency)?;
+
+        unsafe { spi.reg.set_spi_enable(true) };
+        Ok(spi)
+    }
+
+    /// # Set Mode
+    /// Change the clock polarity and phase.
+    pub fn set_mode(&mut self, mode: SpiMode) {
+        unsafe {
+            self.reg.set_clock_polarity(mode.polarity());
+            self.reg.set_clock_phase(mode.phase());
+        }
+    }
+
+    /// # Set Word Size
+    /// Change the bits in a character, 1 to 16.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `bits` is out of range.
+    pub fn set_word_size(&mut self, bits: u8) -> Result<()> {
+        if !(1..=16).contains(&bits) {
+            return Err(ErrorKind::BadParam);
+        }
+
+        // 16 bits is written as 0
+        unsafe { self.reg.set_bits_per_character(bits & 0xF) };
+        self.word_size = bits;
+        Ok(())
+    }
+
+    /// # Word Size
+    /// The bits in a character.
+    pub fn word_size(&self) -> u8 {
+        self.word_size
+    }
+
+    /// # Set Data Width
+    /// Move characters over one, two or four data lines, taking SDIO2 and SDIO3 for
+    /// quad transfers. SPI 0 has them on `P0.8` and `P0.9`, SPI 1 on `P0.24` and `P0.25`.
+    /// They are kept once taken.
+    ///
+    /// # Errors
+    /// `ErrorKind::Busy` when SDIO2 or SDIO3 is owned.
+    pub fn set_data_width(&mut self, width: DataWidth) -> Result<()> {
+        if width == DataWidth::Quad && self._quad.is_none() {
+            self._quad =
+                Some(crate::gpio::hardware::spi_quad_n(Port::PORT_NUM).ok_or(ErrorKind::Busy)?);
+        }
+
+        let value = match width {
+            DataWidth::Single => 0,
+            DataWidth::Dual => 1,
+            DataWidth::Quad => 2,
+        };
+        unsafe { self.reg.set_data_width(value) };
+        Ok(())
+    }
+
+    /// # Data Width
+    /// The data lines transfers move characters over.
+    pub fn data_width(&self) -> DataWidth {
+        match self.reg.get_data_width() {
+            0 => DataWidth::Single,
+            1 => DataWidth::Dual,
+            _ => DataWidth::Quad,
+        }
+    }
+
+    /// # Set Frequency
+    /// Run SCK at `frequency`, or the closest slower frequency the peripheral clock
+    /// divides down to. Returns the frequency used.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `frequency` is zero, or too slow to divide down to.
+    pub fn set_frequency(&mut self, frequency: Hertz) -> Result<Hertz> {
+        let clock = core_peripheral_clock();
+        let (scale, half) = clock_dividers(clock, frequency)?;
+
+        unsafe {
+            self.reg.set_clock_scale(scale);
+            // 256 is written as 0
+            self.reg.set_clock_high_time(half as u8);
+            self.reg.set_clock_low_time(half as u8);
+        }
+        Ok(Hertz::from_raw((clock.raw() >> scale) / (2 * half as u32)))
+    }
+
+    /// # Enable Slave Select
+    /// Take slave select pin `ss`, if not taken yet, and assert it for every transfer
+    /// instead of the one asserted before. With `active_high` it is driven high to
+    /// select. SPI 0 has SS0 (`P0.4`), SS1 (`P0.11`) and SS2 (`P0.10`), SPI 1 only SS0
+    /// (`P0.20`).
+    ///
+    /// The other slave selects taken keep their pins, deasserted, so each device on a
+    /// shared bus can have one of its own. The timing given to `configure_slave_select`
+    /// is kept.
+    ///
+    /// # Errors
+    /// - `ErrorKind::NoDevice` when this port has no slave select `ss`.
+    /// - `ErrorKind::Busy` when its pin is owned.
+    pub fn enable_slave_select(&mut self, ss: usize, active_high: bool) -> Result<()> {
+        let timing = self.timings.get(ss).copied().unwrap_or_default();
+        self.configure_slave_select(ss, active_high, timing)?;
+        self.select(ss)
+    }
+
+    /// # Configure Slave Select
+    /// Take slave select pin `ss`, if not taken yet, without asserting it. With
+    /// `active_high` it is driven high to select, and `timing` is applied whenever it
+    /// is selected. See `enable_slave_select` for the pins.
+    ///
+    /// # Errors
+    /// - `ErrorKind::NoDevice` when this port has no slave select `ss`.
+    /// - `ErrorKind::Busy` when its pin is owned.
+    pub fn configure_slave_select(
+        &mut self,
+        ss: usize,
+        active_high: bool,
+        timing: SlaveSelectTiming,
+    ) -> Result<()> {
+        let (pin, function) = crate::gpio::hardware::spi_slave_select_pin(Port::PORT_NUM, ss)
+            .ok_or(ErrorKind::NoDevice)?;
+
+        if self.slave_selects[ss].is_none() {
+            let gpio = GpioPin::new(GpioSelect::Gpio0, pin).ok_or(ErrorKind::Busy)?;
+            gpio.configure_output(
+                OutputDriveStrength::Strength0(VoltageSelect::VddIO),
+                function,
+            );
+            self.slave_selects[ss] = Some(gpio);
+        }
+
+        // Every polarity is set up front, so the slave selects not asserted idle
+        // deasserted
+        let polarity = self.reg.get_slave_select_polarity() & !(1 << ss);
+        unsafe {
+            self.reg
+                .set_slave_select_polarity(polarity | (active_high as u8) << ss);
+        }
+
+        self.timings[ss] = timing;
+        if self.selected() == Some(ss) {
+            self.apply_timing(timing);
+        }
+        Ok(())
+    }
+
+    /// # Select
+    /// Assert slave select `ss`, taken by `configure_slave_select` or
+    /// `enable_slave_select`, for every transfer instead of the one asserted before.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadState` when slave select `ss` was not taken.
+    pub fn select(&mut self, ss: usize) -> Result<()> {
+        if self.slave_selects.get(ss).is_none_or(Option::is_none) {
+            return Err(ErrorKind::BadState);
+        }
+
+        self.apply_timing(self.timings[ss]);
+        unsafe { self.reg.set_slave_select_active(1 << ss) };
+        Ok(())
+    }
+
+    /// # Selected
+    /// The slave select asserted for every transfer, if any.
+    pub fn selected(&self) -> Option<usize> {
+        match self.reg.get_slave_select_active() {
+            0 => None,
+            active => Some(active.trailing_zeros() as usize),
+        }
+    }
+
+    /// # With Target
+    /// Run `f` with slave select `ss` asserted for its transfers, then go back to the
+    /// slave select asserted before (or none).
+    ///
+    /// # Errors
+    /// `ErrorKind::BadState` when slave select `ss` was not taken.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use max78000_hal::spi::{SlaveSelectTiming, SpiMode, SPI};
+    /// use max78000_hal::time::Hertz;
+    /// # let mut spi = SPI::port_0_init(SpiMode::Mode0, 8, Hertz::MHz(4))?;
+    ///
+    /// spi.configure_slave_select(0, false, SlaveSelectTiming::default())?;
+    /// spi.configure_slave_select(1, true, SlaveSelectTiming { pre: 4, post: 4, inactive: 8 })?;
+    ///
+    /// spi.with_target(0, |spi| spi.write(&[0x06_u8]))??;
+    /// let temperature = spi.with_target(1, |spi| {
+    ///     let mut reading = [0_u8; 2];
+    ///     spi.read(&mut reading).map(|_| reading)
+    /// })??;
+    /// # Ok::<(), max78000_hal::error::ErrorKind>(())
+    /// ```
+    pub fn with_target<R>(&mut self, ss: usize, f: impl FnOnce(&mut Self) -> R) -> Result<R> {
+        let previous = self.selected();
+        self.select(ss)?;
+        let result = f(self);
+
+        match previous {
+            // It was selected already, so is still taken
+            Some(previous) => {
+                let _ = self.select(previous);
+            }
+            None => self.deselect(),
+        }
+        Ok(result)
+    }
+
+    /// # Apply Timing
+    /// Write `timing` to the controller.
+    fn apply_timing(&mut self, timing: SlaveSelectTiming) {
+        unsafe {
+            self.reg.set_slave_select_pre_time(timing.pre);
+            self.reg.set_slave_select_post_time(timing.post);
+            self.reg.set_slave_select_inactive_time(timing.inactive);
+        }
+    }
+
+    /// # Deselect
+    /// Stop asserting the slave select, keeping its pin. Transfers then need a GPIO chip
+    /// select driven by the caller, until `enable_slave_select` is called again.
+    pub fn deselect(&mut self) {
+        unsafe { self.reg.set_slave_select_active(0) };
+    }
+
+    /// # Disable Slave Select
+    /// Stop asserting the slave select, and give up every slave select pin taken,
+    /// forgetting their timing.
+    pub fn disable_slave_select(&mut self) {
+        self.deselect();
+        self.slave_selects = [None, None, None];
+        self.timings = [SlaveSelectTiming::default(); 3];
+    }
+
+    /// # Set Keep Selected
+    /// Keep the slave select asserted after transfers (`true`), so several transfers
+    /// make up one transaction. The first transfer after setting it back to `false`
+    /// deasserts it when it ends.
+    pub fn set_keep_selected(&mut self, keep: bool) {
+        unsafe { self.reg.set_slave_select_stay_asserted(keep) };
+    }
+
+    /// # Is Busy
+    /// Check if a transfer is in progress.
+    pub fn is_busy(&self) -> bool {
+        self.reg.get_busy()
+    }
+
+    /// # Transfer
+    /// Send `write` while receiving into `read`. The longer of the two sets the
+    /// characters moved: missing characters of `write` are sent as zero, those received
+    /// past the end of `read` are dropped.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` when `W` does not fit the word size, see `Word`.
+    /// - `ErrorKind::BadState` for dual and quad transfers, which are half duplex.
+    pub fn transfer<W: private::Word>(&mut self, read: &mut [W], write: &[W]) -> Result<()> {
+        let count = read.len().max(write.len());
+        self.exchange(
+            count,
+            (true, true),
+            |index| write.get(index).copied().unwrap_or_default(),
+            |index, word| {
+                if let Some(slot) = read.get_mut(index) {
+                    *slot = word;
+                }
+            },
+        )
+    }
+
+    /// # Transfer In Place
+    /// Send `words`, replacing each of them by the character received with it.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` when `W` does not fit the word size.
+    /// - `ErrorKind::BadState` for dual and quad transfers.
+    pub fn transfer_in_place<W: private::Word>(&mut self, words: &mut [W]) -> Result<()> {
+        let words = Cell::from_mut(words).as_slice_of_cells();
+        self.exchange(
+            words.len(),
+            (true, true),
+            |index| words[index].get(),
+            |index, word| words[index].set(word),
+        )
+    }
+
+    /// # Write
+    /// Send `words`, dropping what is received.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `W` does not fit the word size.
+    pub fn write<W: private::Word>(&mut self, words: &[W]) -> Result<()> {
+        let lines = self.half_duplex(true, false);
+        self.exchange(words.len(), lines, |index| words[index], |_, _| {})
+    }
+
+    /// # Read
+    /// Receive into `words`, sending zeros (unless half duplex).
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `W` does not fit the word size.
+    pub fn read<W: private::Word>(&mut self, words: &mut [W]) -> Result<()> {
+        let lines = self.half_duplex(false, true);
+        self.exchange(
+            words.len(),
+            lines,
+            |_| W::default(),
+            |index, word| words[index] = word,
+        )
+    }
+
+    /// # Half Duplex
+    /// The directions a `write` or `read` moves characters in: both for single line
+    /// transfers, which are always full duplex, only the one asked for otherwise.
+    fn half_duplex(&self, sending: bool, receiving: bool) -> (bool, bool) {
+        match self.data_width() {
+            DataWidth::Single => (true, true),
+            _ => (sending, receiving),
+        }
+    }
+
+    /// # Exchange
+    /// Move `count` characters, sending `next(index)` and handing each received one to
+    /// `store`, in the directions of `lines` (sending, receiving). Transactions are
+    /// limited to `MAX_CHARACTERS`, so longer exchanges are split, keeping the slave
+    /// select asserted in between so they still make up one transaction.
+    fn exchange<W: private::Word>(
+        &mut self,
+        count: usize,
+        lines: (bool, bool),
+        mut next: impl FnMut(usize) -> W,
+        mut store: impl FnMut(usize, W),
+    ) -> Result<()> {
+        self.check::<W>(lines)?;
+
+        let keep = self.reg.get_slave_select_stay_asserted();
+        let mut done = 0;
+        while done < count {
+            let chunk = (count - done).min(MAX_CHARACTERS);
+            let transmit = if lines.0 { chunk } else { 0 };
+            let receive = if lines.1 { chunk } else { 0 };
+            self.set_keep_selected(keep || done + chunk < count);
+            self.move_characters(
+                transmit,
+                receive,
+                |index| next(done + index),
+                |index, word| store(done + index, word),
+            );
+            done += chunk;
+        }
+
+        self.set_keep_selected(keep);
+        Ok(())
+    }
+
+    /// # Move Characters
+    /// Run one transaction sending `transmit` characters, `next(index)`, and handing
+    /// the `receive` characters received to `store`. Both are up to `MAX_CHARACTERS`,
+    /// and `W` must fit the word size.
+    fn move_characters<W: private::Word>(
+        &mut self,
+        transmit: usize,
+        receive: usize,
+        mut next: impl FnMut(usize) -> W,
+        mut store: impl FnMut(usize, W),
+    ) {
+        self.prepare(transmit, receive);
+
+        let (mut sent, mut received) = (0, 0);
+        let mut started = false;
+        while !started || sent < transmit || received < receive {
+            while sent < transmit
+                && self.reg.get_transmit_fifo_level() as usize + W::BYTES <= FIFO_DEPTH
+            {
+                unsafe { next(sent).push(&mut self.reg) };
+                sent += 1;
+            }
+
+            // Start with the transmit FIFO filled, so it does not run dry right away
+            if !started {
+                unsafe { self.reg.activate_start_transaction() };
+                started = true;
+            }
+
+            while received < receive && self.reg.get_receive_fifo_level() as usize >= W::BYTES {
+                store(received, W::pop(&mut self.reg));
+                received += 1;
+            }
+        }
+
+        while !self.reg.is_master_done_flag_active() {}
+        unsafe { self.reg.clear_master_done_flag() };
+    }
+
+    /// # Check
+    /// Check if `W` fits the word size, and the directions of `lines` the data width.
+    fn check<W: private::Word>(&self, lines: (bool, bool)) -> Result<()> {
+        if (W::BYTES == 1) != (self.word_size <= 8) {
+            return Err(ErrorKind::BadParam);
+        }
+        if lines == (true, true) && self.data_width() != DataWidth::Single {
+            return Err(ErrorKind::BadState);
+        }
+        Ok(())
+    }
+
+    /// # Prepare
+    /// Empty the FIFOs, and set up a transaction sending `transmit` and receiving
+    /// `receive` characters.
+    fn prepare(&mut self, transmit: usize, receive: usize) {
+        unsafe {
+            self.reg.activate_transmit_fifo_flush();
+            self.reg.activate_receive_fifo_flush();
+            self.reg.set_transmit_character_count(transmit as u16);
+            self.reg.set_receive_character_count(receive as u16);
+            self.reg.clear_master_done_flag();
+        }
+    }
+}
+
+impl<Port: private::SPIPortCompatable> crate::pac::IntoPac for SPI<Port> {
+    fn free<P: crate::pac::PacPeripheral>(self) -> Result<P> {
+        crate::pac::free_into(self, Port::PORT_PTR)
+    }
+}
+
+/// The blocking traits, for each word type. A blanket impl over `Word` would overlap
+/// with the `Default` impls of embedded-hal 0.2.
+#[cfg(feature = "eh0")]
+macro_rules! blocking_eh0 {
+    ($($word:ty),*) => {
+        $(
+            impl<Port: private::SPIPortCompatable> embedded_hal_0_2::blocking::spi::Transfer<$word>
+                for SPI<Port>
+            {
+                type Error = crate::Error;
+
+                fn transfer<'w>(
+                    &mut self,
+                    words: &'w mut [$word],
+                ) -> core::result::Result<&'w [$word], Self::Error> {
+                    SPI::transfer_in_place(self, words)
+                        .map_err(|kind| kind.within(crate::error::Peripheral::Spi))?;
+                    Ok(words)
+                }
+            }
+
+            impl<Port: private::SPIPortCompatable> embedded_hal_0_2::blocking::spi::Write<$word>
+                for SPI<Port>
+            {
+                type Error = crate::Error;
+
+                fn write(&mut self, words: &[$word]) -> core::result::Result<(), Self::Error> {
+                    SPI::write(self, words).map_err(|kind| kind.within(crate::error::Peripheral::Spi))
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "eh0")]
+blocking_eh0!(u8, u16);
+
+/// Every `send` is a transaction of one character, whose received character is left
+/// in the receive FIFO for `read`.
+#[cfg(feature = "eh0")]
+impl<Port: private::SPIPortCompatable, W: private::Word> embedded_hal_0_2::spi::FullDuplex<W>
+    for SPI<Port>
+{
+    type Error = crate::Error;
+
+    fn read(&mut self) -> nb::Result<W, Self::Error> {
+        if (self.reg.get_receive_fifo_level() as usize) < W::BYTES {
+            return Err(nb::Error::WouldBlock);
+        }
+        Ok(W::pop(&mut self.reg))
+    }
+
+    fn send(&mut self, word: W) -> nb::Result<(), Self::Error> {
+        self.check::<W>((true, true))
+            .map_err(|kind| nb::Error::Other(kind.within(crate::error::Peripheral::Spi)))?;
+        if self.is_busy() {
+            return Err(nb::Error::WouldBlock);
+        }
+
+        unsafe {
+            self.reg.set_transmit_character_count(1);
+            self.reg.set_receive_character_count(1);
+            self.reg.clear_master_done_flag();
+            word.push(&mut self.reg);
+            self.reg.activate_start_transaction();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<Port: private::SPIPortCompatable> embedded_hal::spi::ErrorType for SPI<Port> {
+    type Error = crate::Error;
+}
+
+/// `u8` words for sizes of up to 8 bits, `u16` words above, see `Word`.
+#[cfg(feature = "eh1")]
+impl<Port: private::SPIPortCompatable, W: private::Word + 'static> embedded_hal::spi::SpiBus<W>
+    for SPI<Port>
+{
+    fn read(&mut self, words: &mut [W]) -> core::result::Result<(), Self::Error> {
+        SPI::read(self, words).map_err(|kind| kind.within(crate::error::Peripheral::Spi))
+    }
+
+    fn write(&mut self, words: &[W]) -> core::result::Result<(), Self::Error> {
+        SPI::write(self, words).map_err(|kind| kind.within(crate::error::Peripheral::Spi))
+    }
+
+    fn transfer(&mut self, read: &mut [W], write: &[W]) -> core::result::Result<(), Self::Error> {
+        SPI::transfer(self, read, write).map_err(|kind| kind.within(crate::error::Peripheral::Spi))
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [W]) -> core::result::Result<(), Self::Error> {
+        SPI::transfer_in_place(self, words)
+            .map_err(|kind| kind.within(crate::error::Peripheral::Spi))
+    }
+
+    fn flush(&mut self) -> core::result::Result<(), Self::Error> {
+        while self.is_busy() {}
+        Ok(())
+    }
+}
+
+/// # Clock Dividers
+/// The clock scale, and the SCK half period in scaled clocks (1 to 256), that divide
+/// `clock` down to `frequency` or the closest slower frequency.
+fn clock_dividers(clock: Hertz, frequency: Hertz) -> Result<(u8, u16)> {
+    if frequency.raw() == 0 {
+        return Err(ErrorKind::BadParam);
+    }
+
+    // Round the half period up, so SCK never runs faster than asked
+    let period = 2 * frequency.raw() as u64;
+    let half = |scale: u8| ((clock.raw() >> scale) as u64).div_ceil(period).max(1);
+
+    let mut scale = 0;
+    while half(scale) > 256 && scale < MAX_CLOCK_SCALE {
+        scale += 1;
+    }
+
+    match half(scale) {
+        half if half > 256 => Err(ErrorKind::BadParam),
+        half => Ok((scale, half as u16)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clock_divisions() {
+        let clock = Hertz::MHz(50);
+        assert_eq!(clock_dividers(clock, Hertz::MHz(25)), Ok((0, 1)));
+        // Never faster than asked
+        assert_eq!(clock_dividers(clock, Hertz::MHz(100)), Ok((0, 1)));
+        assert_eq!(clock_dividers(clock, Hertz::MHz(4)), Ok((0, 7)));
+        assert_eq!(clock_dividers(clock, Hertz::kHz(100)), Ok((0, 250)));
+        assert_eq!(clock_dividers(clock, Hertz::kHz(10)), Ok((4, 157)));
+        assert_eq!(
+            clock_dividers(clock, Hertz::Hz(100)),
+            Err(ErrorKind::BadParam)
+        );
+        assert_eq!(
+            clock_dividers(clock, Hertz::Hz(0)),
+            Err(ErrorKind::BadParam)
+        );
+
+        assert!(!SpiMode::Mode1.polarity() && SpiMode::Mode1.phase());
+        assert!(SpiMode::Mode2.polarity() && !SpiMode::Mode2.phase());
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn transfers_through_the_fifos() {
+        crate::mock::reset();
+        let dma = mmio::SPI_0 + 0x1C;
+        let flags = mmio::SPI_0 + 0x20;
+        let fifo = mmio::SPI_0;
+        let mut spi = unsafe { SPI::<SPI0>::steal() };
+        assert_eq!(spi.word_size(), 16);
+
+        // u8 needs up to 8 bits
+        let mut bytes = [1_u8, 2, 3];
+        assert_eq!(spi.transfer_in_place(&mut bytes), Err(ErrorKind::BadParam));
+        spi.set_word_size(8).unwrap();
+        assert_eq!(spi.set_word_size(17), Err(ErrorKind::BadParam));
+
+        // The mock FIFO reads back the last byte written, and the receive FIFO always
+        // holds two bytes. The transaction is done right away.
+        crate::mock::write(dma, 2 << 24);
+        crate::mock::write(flags, 1 << 11);
+        spi.transfer_in_place(&mut bytes).unwrap();
+        assert_eq!(bytes, [3, 3, 3]);
+        assert_eq!(crate::mock::read(mmio::SPI_0 + 0x08), (3 << 16) | 3);
+
+        let mut read = [0_u8; 4];
+        spi.transfer(&mut read, &[7, 8]).unwrap();
+        // The mock transmit FIFO never fills, so all four went out before the first
+        // read, the last two as zero
+        assert_eq!(read, [0; 4]);
+        assert_eq!(crate::mock::read(fifo) & 0xFF, 0);
+
+        spi.set_word_size(12).unwrap();
+        spi.write(&[0xABC_u16]).unwrap();
+        assert_eq!(crate::mock::read(fifo) & 0xFFFF, 0xABC);
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn long_exchanges_stay_selected() {
+        crate::mock::reset();
+        let ctrl0 = mmio::SPI_0 + 0x04;
+        let mut spi = unsafe { SPI::<SPI0>::steal() };
+        spi.set_word_size(8).unwrap();
+        // The receive FIFO always holds two bytes, and the transaction is done right away
+        crate::mock::write(mmio::SPI_0 + 0x1C, 2 << 24);
+        crate::mock::write(mmio::SPI_0 + 0x20, 1 << 11);
+
+        // Every transaction but the last keeps the slave select asserted
+        let count = MAX_CHARACTERS + 2;
+        let mut kept = [None; 2];
+        spi.exchange(
+            count,
+            (true, false),
+            |index| {
+                kept[index / MAX_CHARACTERS] = Some(crate::mock::read(ctrl0) & (1 << 8) != 0);
+                0_u8
+            },
+            |_, _| {},
+        )
+        .unwrap();
+        assert_eq!(kept, [Some(true), Some(false)]);
+        assert_eq!(crate::mock::read(ctrl0) & (1 << 8), 0);
+        // The last transaction moved the two characters left over
+        assert_eq!(crate::mock::read(mmio::SPI_0 + 0x08) & 0xFFFF, 2);
+
+        // Kept selected by the caller, it stays that way
+        spi.set_keep_selected(true);
+        spi.write(&[0_u8; MAX_CHARACTERS + 1]).unwrap();
+        assert_ne!(crate::mock::read(ctrl0) & (1 << 8), 0);
+    }
+
+    #[cfg(all(feature = "mock-mmio", feature = "eh0"))]
+    #[test]
+    fn embedded_hal_0_2_moves_words() {
+        use embedded_hal_0_2::blocking::spi::{Transfer, Write};
+        use embedded_hal_0_2::spi::FullDuplex;
+
+        crate::mock::reset();
+        let ctrl1 = mmio::SPI_0 + 0x08;
+        crate::mock::write(mmio::SPI_0 + 0x20, 1 << 11);
+        let mut spi = unsafe { SPI::<SPI0>::steal() };
+        spi.set_word_size(8).unwrap();
+
+        // Nothing received yet
+        assert!(matches!(
+            FullDuplex::<u8>::read(&mut *spi),
+            Err(nb::Error::WouldBlock)
+        ));
+        FullDuplex::send(&mut *spi, 0x5A_u8).unwrap();
+        assert_eq!(crate::mock::read(ctrl1), (1 << 16) | 1);
+        crate::mock::write(mmio::SPI_0 + 0x1C, 1 << 24);
+        assert_eq!(FullDuplex::<u8>::read(&mut *spi).unwrap(), 0x5A);
+        assert!(FullDuplex::send(&mut *spi, 0x5A_u16).is_err());
+
+        crate::mock::write(mmio::SPI_0 + 0x1C, 2 << 24);
+        let mut words = [1_u8, 2];
+        assert_eq!(Transfer::transfer(&mut *spi, &mut words).unwrap(), [2, 2]);
+        Write::write(&mut *spi, &[7_u8, 8, 9]).unwrap();
+        assert_eq!(crate::mock::read(ctrl1) & 0xFFFF, 3);
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn wide_transfers_are_half_duplex() {
+        crate::mock::reset();
+        let ctrl1 = mmio::SPI_0 + 0x08;
+        let ctrl2 = mmio::SPI_0 + 0x0C;
+        crate::mock::write(mmio::SPI_0 + 0x1C, 2 << 24);
+        crate::mock::write(mmio::SPI_0 + 0x20, 1 << 11);
+        let mut spi = unsafe { SPI::<SPI0>::steal() };
+        spi.set_word_size(8).unwrap();
+
+        spi.set_data_width(DataWidth::Quad).unwrap();
+        assert_eq!(spi.data_width(), DataWidth::Quad);
+        assert_eq!(crate::mock::read(ctrl2) >> 12 & 0b11, 2);
+        // SDIO2 and SDIO3 are taken
+        assert!(GpioPin::new(crate::gpio::GpioSelect::Gpio0, 8).is_none());
+
+        // Only sending, then only receiving
+        spi.write(&[0xEB_u8, 0, 0, 0]).unwrap();
+        assert_eq!(crate::mock::read(ctrl1), 4);
+        let mut read = [0_u8; 3];
+        spi.read(&mut read).unwrap();
+        assert_eq!(crate::mock::read(ctrl1), 3 << 16);
+        assert_eq!(spi.transfer_in_place(&mut read), Err(ErrorKind::BadState));
+
+        spi.set_data_width(DataWidth::Single).unwrap();
+        spi.write(&[0x9F_u8]).unwrap();
+        assert_eq!(crate::mock::read(ctrl1), (1 << 16) | 1);
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn targets_share_the_bus() {
+        crate::mock::reset();
+        let ctrl0 = mmio::SPI_0 + 0x04;
+        let ctrl2 = mmio::SPI_0 + 0x0C;
+        let sstime = mmio::SPI_0 + 0x10;
+        crate::mock::write(mmio::SPI_0 + 0x1C, 2 << 24);
+        crate::mock::write(mmio::SPI_0 + 0x20, 1 << 11);
+        let mut spi = unsafe { SPI::<SPI0>::steal() };
+        spi.set_word_size(8).unwrap();
+
+        let sensor = SlaveSelectTiming {
+            pre: 4,
+            post: 2,
+            inactive: 8,
+        };
+        spi.enable_slave_select(0, false).unwrap();
+        spi.configure_slave_select(2, true, sensor).unwrap();
+        assert_eq!(spi.selected(), Some(0));
+        assert_eq!(crate::mock::read(sstime), 0x01_01_01);
+        // Both polarities are set, only SS2 is active high
+        assert_eq!(crate::mock::read(ctrl2) >> 16 & 0xFF, 0b100);
+
+        let active = spi
+            .with_target(2, |spi| {
+                spi.write(&[0xA5_u8]).unwrap();
+                crate::mock::read(ctrl0) >> 16 & 0xF
+            })
+            .unwrap();
+        assert_eq!(active, 0b100);
+        assert_eq!(spi.selected(), Some(0));
+        assert_eq!(crate::mock::read(sstime), 0x01_01_01);
+
+        assert_eq!(spi.with_target(1, |_| ()), Err(ErrorKind::BadState));
+        assert_eq!(spi.select(3), Err(ErrorKind::BadState));
+        spi.select(2).unwrap();
+        assert_eq!(crate::mock::read(sstime), 0x08_02_04);
+
+        spi.deselect();
+        spi.with_target(0, |_| ()).unwrap();
+        assert_eq!(spi.selected(), None);
+        spi.disable_slave_select();
+    }
+}
@@ -0,0 +1,311 @@
+use crate::memory_map::mmio;
+use hal_macros::RW;
+use hal_macros_derive::make_device;
+
+/// # SPI Register Offsets
+/// See Max 78000 User Guide, SPI Registers.
+mod rro {
+    /// # SPI FIFO Data Register
+    pub const SPI_FIFO: usize = 0x0000;
+    /// # SPI Control 0 Register
+    pub const SPI_CTRL0: usize = 0x0004;
+    /// # SPI Control 1 Register (transaction size)
+    pub const SPI_CTRL1: usize = 0x0008;
+    /// # SPI Control 2 Register (frame format)
+    pub const SPI_CTRL2: usize = 0x000C;
+    /// # SPI Slave Select Timing Register
+    pub const SPI_SSTIME: usize = 0x0010;
+    /// # SPI Clock Control Register
+    pub const SPI_CLKCTRL: usize = 0x0014;
+    /// # SPI DMA Control Register
+    pub const SPI_DMA: usize = 0x001C;
+    /// # SPI Interrupt Flag Register
+    pub const SPI_INTFL: usize = 0x0020;
+    /// # SPI Interrupt Enable Register
+    pub const SPI_INTEN: usize = 0x0024;
+    /// # SPI Status Register
+    pub const SPI_STAT: usize = 0x0030;
+}
+
+make_device! {
+    device_ports(mmio::SPI_0, mmio::SPI_1);
+
+    /// Slave Select Active.
+    /// One bit per slave select pin, the pins asserted during a transaction.
+    /// - Bit 0: SS0
+    /// - Bit 1: SS1
+    /// - Bit 2: SS2
+    #[bit(16..=19, RW, rro::SPI_CTRL0)]
+    slave_select_active,
+
+    /// Slave Select Control.
+    /// What happens to the slave select at the end of a transaction.
+    /// - 0: Deassert
+    /// - 1: Stay asserted
+    #[bit(8, RW, rro::SPI_CTRL0)]
+    slave_select_stay_asserted,
+
+    /// Start Transaction.
+    /// Write a 1 to start a transaction as the master, cleared by the hardware.
+    #[bit(5, RW1O, rro::SPI_CTRL0)]
+    start_transaction,
+
+    /// Slave Select IO.
+    /// The direction of the SS0 pin.
+    /// - 0: Output (master)
+    /// - 1: Input (slave)
+    #[bit(4, RW, rro::SPI_CTRL0)]
+    slave_select_input,
+
+    /// Master Mode Enable.
+    /// - 0: Slave
+    /// - 1: Master
+    #[bit(1, RW, rro::SPI_CTRL0)]
+    master_mode_enable,
+
+    /// SPI Enable.
+    /// - 0: Disabled
+    /// - 1: Enabled
+    #[bit(0, RW, rro::SPI_CTRL0)]
+    spi_enable,
+
+    /// Number of Characters to Receive.
+    /// The characters a transaction receives, zero for a transmit only transaction.
+    #[bit(16..=31, RW, rro::SPI_CTRL1)]
+    receive_character_count,
+
+    /// Number of Characters to Transmit.
+    /// The characters a transaction transmits, zero for a receive only transaction.
+    #[bit(0..=15, RW, rro::SPI_CTRL1)]
+    transmit_character_count,
+
+    /// Slave Select Polarity.
+    /// One bit per slave select pin.
+    /// - 0: Active low
+    /// - 1: Active high
+    #[bit(16..=23, RW, rro::SPI_CTRL2)]
+    slave_select_polarity,
+
+    /// Three Wire Mode.
+    /// - 0: Four wire, separate MOSI and MISO
+    /// - 1: Three wire, data on MOSI both ways
+    #[bit(15, RW, rro::SPI_CTRL2)]
+    three_wire_mode,
+
+    /// Data Width.
+    /// The data lines used.
+    /// - 0: Single (MOSI and MISO)
+    /// - 1: Dual
+    /// - 2: Quad
+    #[bit(12..=13, RW, rro::SPI_CTRL2)]
+    data_width,
+
+    /// Number of Bits per Character.
+    /// - 0: 16 bits
+    /// - 1-15: 1 to 15 bits
+    #[bit(8..=11, RW, rro::SPI_CTRL2)]
+    bits_per_character,
+
+    /// Clock Polarity.
+    /// The level of SCK while idle.
+    /// - 0: Low
+    /// - 1: High
+    #[bit(1, RW, rro::SPI_CTRL2)]
+    clock_polarity,
+
+    /// Clock Phase.
+    /// The SCK edge data is sampled on.
+    /// - 0: Leading edge
+    /// - 1: Trailing edge
+    #[bit(0, RW, rro::SPI_CTRL2)]
+    clock_phase,
+
+    /// Slave Select Inactive Time.
+    /// The SCK periods the slave select stays deasserted between transactions.
+    #[bit(16..=23, RW, rro::SPI_SSTIME)]
+    slave_select_inactive_time,
+
+    /// Slave Select Post Time.
+    /// The SCK periods between the last SCK edge and the slave select deasserting.
+    #[bit(8..=15, RW, rro::SPI_SSTIME)]
+    slave_select_post_time,
+
+    /// Slave Select Pre Time.
+    /// The SCK periods between the slave select asserting and the first SCK edge.
+    #[bit(0..=7, RW, rro::SPI_SSTIME)]
+    slave_select_pre_time,
+
+    /// Clock Scale.
+    /// The peripheral clock is divided by 2^scale before generating SCK.
+    #[bit(16..=19, RW, rro::SPI_CLKCTRL)]
+    clock_scale,
+
+    /// Clock High Time.
+    /// The scaled clock periods SCK is high for, 0 means 256.
+    #[bit(8..=15, RW, rro::SPI_CLKCTRL)]
+    clock_high_time,
+
+    /// Clock Low Time.
+    /// The scaled clock periods SCK is low for, 0 means 256.
+    #[bit(0..=7, RW, rro::SPI_CLKCTRL)]
+    clock_low_time,
+
+    /// Receive DMA Enable.
+    /// Request the DMA while the receive FIFO is above its threshold.
+    #[bit(31, RW, rro::SPI_DMA)]
+    receive_dma_enable,
+
+    /// Receive FIFO Level.
+    /// The bytes waiting in the receive FIFO.
+    #[bit(24..=29, RO, rro::SPI_DMA)]
+    receive_fifo_level,
+
+    /// Receive FIFO Flush.
+    /// Write a 1 to empty the receive FIFO.
+    #[bit(23, RW1O, rro::SPI_DMA)]
+    receive_fifo_flush,
+
+    /// Receive FIFO Enable.
+    /// - 0: Disabled
+    /// - 1: Enabled
+    #[bit(22, RW, rro::SPI_DMA)]
+    receive_fifo_enable,
+
+    /// Receive FIFO Threshold.
+    /// The receive FIFO level that raises the receive threshold flag.
+    #[bit(16..=20, RW, rro::SPI_DMA)]
+    receive_fifo_threshold,
+
+    /// Transmit DMA Enable.
+    /// Request the DMA while the transmit FIFO is at or below its threshold.
+    #[bit(15, RW, rro::SPI_DMA)]
+    transmit_dma_enable,
+
+    /// Transmit FIFO Level.
+    /// The bytes waiting in the transmit FIFO.
+    #[bit(8..=13, RO, rro::SPI_DMA)]
+    transmit_fifo_level,
+
+    /// Transmit FIFO Flush.
+    /// Write a 1 to empty the transmit FIFO.
+    #[bit(7, RW1O, rro::SPI_DMA)]
+    transmit_fifo_flush,
+
+    /// Transmit FIFO Enable.
+    /// - 0: Disabled
+    /// - 1: Enabled
+    #[bit(6, RW, rro::SPI_DMA)]
+    transmit_fifo_enable,
+
+    /// Transmit FIFO Threshold.
+    /// The transmit FIFO level (or below) that raises the transmit threshold flag.
+    #[bit(0..=4, RW, rro::SPI_DMA)]
+    transmit_fifo_threshold,
+
+    /// Receive Overrun Flag.
+    /// A character was received with the receive FIFO full.
+    #[bit(14, RW1C, rro::SPI_INTFL)]
+    receive_overrun_flag,
+
+    /// Transmit Underrun Flag.
+    /// The slave had to send a character with the transmit FIFO empty.
+    #[bit(13, RW1C, rro::SPI_INTFL)]
+    transmit_underrun_flag,
+
+    /// Master Done Flag.
+    /// The master finished a transaction.
+    #[bit(11, RW1C, rro::SPI_INTFL)]
+    master_done_flag,
+
+    /// Slave Select Deasserted Flag.
+    /// The slave select input was deasserted, ending a transaction as the slave.
+    #[bit(5, RW1C, rro::SPI_INTFL)]
+    slave_select_deasserted_flag,
+
+    /// Slave Select Asserted Flag.
+    /// The slave select input was asserted, starting a transaction as the slave.
+    #[bit(4, RW1C, rro::SPI_INTFL)]
+    slave_select_asserted_flag,
+
+    /// Receive Threshold Flag.
+    /// The receive FIFO level reached its threshold.
+    #[bit(2, RW1C, rro::SPI_INTFL)]
+    receive_threshold_flag,
+
+    /// Transmit Empty Flag.
+    /// The transmit FIFO is empty.
+    #[bit(1, RW1C, rro::SPI_INTFL)]
+    transmit_empty_flag,
+
+    /// Transmit Threshold Flag.
+    /// The transmit FIFO level dropped to its threshold.
+    #[bit(0, RW1C, rro::SPI_INTFL)]
+    transmit_threshold_flag,
+
+    /// Receive Overrun Interrupt Enable.
+    #[bit(14, RW, rro::SPI_INTEN)]
+    receive_overrun_interrupt_enable,
+
+    /// Transmit Underrun Interrupt Enable.
+    #[bit(13, RW, rro::SPI_INTEN)]
+    transmit_underrun_interrupt_enable,
+
+    /// Master Done Interrupt Enable.
+    #[bit(11, RW, rro::SPI_INTEN)]
+    master_done_interrupt_enable,
+
+    /// Slave Select Deasserted Interrupt Enable.
+    #[bit(5, RW, rro::SPI_INTEN)]
+    slave_select_deasserted_interrupt_enable,
+
+    /// Slave Select Asserted Interrupt Enable.
+    #[bit(4, RW, rro::SPI_INTEN)]
+    slave_select_asserted_interrupt_enable,
+
+    /// Receive Threshold Interrupt Enable.
+    #[bit(2, RW, rro::SPI_INTEN)]
+    receive_threshold_interrupt_enable,
+
+    /// Transmit Threshold Interrupt Enable.
+    #[bit(0, RW, rro::SPI_INTEN)]
+    transmit_threshold_interrupt_enable,
+
+    /// Busy.
+    /// A transaction is in progress.
+    /// - 0: Idle
+    /// - 1: Busy
+    #[bit(0, RO, rro::SPI_STAT)]
+    busy,
+}
+
+impl Registers {
+    /// # Write FIFO 8
+    /// Push a single byte into the transmit FIFO.
+    ///
+    /// # Safety
+    /// The transmit FIFO must have room for it.
+    pub unsafe fn write_fifo_8(&mut self, value: u8) {
+        core::ptr::write_volatile(self.as_mut_ptr(rro::SPI_FIFO) as *mut u8, value);
+    }
+
+    /// # Write FIFO 16
+    /// Push two bytes, a character of 9 to 16 bits, into the transmit FIFO.
+    ///
+    /// # Safety
+    /// The transmit FIFO must have room for them.
+    pub unsafe fn write_fifo_16(&mut self, value: u16) {
+        core::ptr::write_volatile(self.as_mut_ptr(rro::SPI_FIFO) as *mut u16, value);
+    }
+
+    /// # Read FIFO 8
+    /// Pop a single byte from the receive FIFO.
+    pub fn read_fifo_8(&mut self) -> u8 {
+        unsafe { core::ptr::read_volatile(self.as_ptr(rro::SPI_FIFO) as *const u8) }
+    }
+
+    /// # Read FIFO 16
+    /// Pop two bytes, a character of 9 to 16 bits, from the receive FIFO.
+    pub fn read_fifo_16(&mut self) -> u16 {
+        unsafe { core::ptr::read_volatile(self.as_ptr(rro::SPI_FIFO) as *const u16) }
+    }
+}
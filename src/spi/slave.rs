@@ -0,0 +1,389 @@
+//! # SPI Slave
+//! Let a host MCU drive the SPI, with this controller as the slave, ie. to use the
+//! MAX78000 as a coprocessor. The slave is serviced by the SPI's interrupt: it moves
+//! received bytes from the receive FIFO into a buffer of `N` bytes, and refills the
+//! transmit FIFO from the bytes preloaded for the host to read.
+//!
+//! The host frames transactions with SS0: every byte received while it is asserted
+//! makes up one frame, completed when it is deasserted, and read with `read_frame`.
+//! Preloaded bytes not read by the host by then are dropped, so preload the answer to
+//! each request before the host's next transaction. When the preload runs out, the
+//! host reads whatever the hardware sends on a underrun, counted by `underruns`.
+//!
+//! # Example
+//! ```no_run
+//! use core::cell::RefCell;
+//! use critical_section::Mutex;
+//! use max78000_hal::bind_interrupts;
+//! use max78000_hal::interrupt::typelevel::{self, Handler, Interrupt};
+//! use max78000_hal::spi::slave::SpiSlave;
+//! use max78000_hal::spi::SPI0;
+//! # use max78000_hal::spi::{SpiMode, SPI};
+//! # use max78000_hal::time::Hertz;
+//! # let spi = SPI::port_0_init(SpiMode::Mode0, 8, Hertz::MHz(4))?;
+//! # const STATUS_READY: u8 = 0xA5;
+//! # fn handle(_: &[u8]) {}
+//!
+//! static SLAVE: Mutex<RefCell<Option<SpiSlave<SPI0>>>> = Mutex::new(RefCell::new(None));
+//!
+//! struct Coprocessor;
+//! impl Handler<typelevel::SPI0> for Coprocessor {
+//!     unsafe fn on_interrupt() {
+//!         critical_section::with(|cs| {
+//!             if let Some(slave) = SLAVE.borrow_ref_mut(cs).as_mut() {
+//!                 slave.on_interrupt();
+//!             }
+//!         });
+//!     }
+//! }
+//!
+//! bind_interrupts!(struct Irqs { SPI0 => Coprocessor; });
+//!
+//! let slave = SpiSlave::new(spi)?;
+//! critical_section::with(|cs| SLAVE.replace(cs, Some(slave)));
+//! unsafe { typelevel::SPI0::enable() };
+//!
+//! let mut request = [0; 64];
+//! let count = critical_section::with(|cs| {
+//!     let mut slave = SLAVE.borrow_ref_mut(cs);
+//!     let slave = slave.as_mut()?;
+//!     slave.preload(&[STATUS_READY]);
+//!     slave.read_frame(&mut request)
+//! });
+//! if let Some(count) = count {
+//!     handle(&request[..count]);
+//! }
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::{private, FIFO_DEPTH, SPI};
+use crate::error::{ErrorKind, Result};
+use crate::gpio::{GpioPin, GpioSelect, ResistorStrength};
+
+/// # FIFO Threshold
+/// The receive FIFO level raising the receive interrupt, and the transmit FIFO level it
+/// is refilled at.
+const FIFO_THRESHOLD: u8 = (FIFO_DEPTH / 2) as u8;
+
+/// # SPI Slave
+/// A SPI slave, framing transactions of up to `N` bytes each way.
+pub struct SpiSlave<Port, const N: usize = 64> {
+    spi: SPI<Port>,
+    rx: [u8; N],
+    /// The bytes received in the current transaction.
+    received: usize,
+    /// The length of the last complete transaction, until it is read.
+    frame: Option<usize>,
+    tx: [u8; N],
+    /// The bytes preloaded, and how many of them were moved into the transmit FIFO.
+    queued: usize,
+    sent: usize,
+    selected: bool,
+    dropped: usize,
+    underruns: usize,
+}
+
+// Both buffers live inside the slave and the controller is owned by it, so nothing is
+// left behind when it moves to the context servicing its interrupt
+unsafe impl<Port, const N: usize> Send for SpiSlave<Port, N> {}
+
+impl<Port: private::SPIPortCompatable, const N: usize> SpiSlave<Port, N> {
+    /// # New
+    /// Turn a initialized SPI into a slave, selected by its SS0 pin, keeping its mode
+    /// and word size. The SPI's interrupt must be bound to a handler calling
+    /// `on_interrupt`, and enabled in the NVIC.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` when the word size is above 8 bits.
+    /// - `ErrorKind::Busy` when the SS0 pin is owned.
+    pub fn new(mut spi: SPI<Port>) -> Result<Self> {
+        if spi.word_size() > 8 {
+            return Err(ErrorKind::BadParam);
+        }
+
+        let (pin, function) = crate::gpio::hardware::spi_slave_select_pin(Port::PORT_NUM, 0)
+            .ok_or(ErrorKind::NoDevice)?;
+        spi.disable_slave_select();
+        let gpio = GpioPin::new(GpioSelect::Gpio0, pin).ok_or(ErrorKind::Busy)?;
+        // Keep the slave deselected while the host is not driving SS0
+        gpio.configure_input(ResistorStrength::WeakPullup, function);
+        spi.slave_selects[0] = Some(gpio);
+
+        let reg = &mut spi.reg;
+        unsafe {
+            reg.set_spi_enable(false);
+            reg.set_master_mode_enable(false);
+            reg.set_slave_select_input(true);
+            reg.set_slave_select_stay_asserted(false);
+            reg.activate_transmit_fifo_flush();
+            reg.activate_receive_fifo_flush();
+            reg.set_receive_fifo_threshold(FIFO_THRESHOLD);
+            reg.set_transmit_fifo_threshold(FIFO_THRESHOLD);
+            reg.set_receive_threshold_interrupt_enable(true);
+            reg.set_receive_overrun_interrupt_enable(true);
+            reg.set_transmit_underrun_interrupt_enable(true);
+            reg.set_slave_select_asserted_interrupt_enable(true);
+            reg.set_slave_select_deasserted_interrupt_enable(true);
+            reg.set_spi_enable(true);
+        }
+
+        Ok(Self {
+            spi,
+            rx: [0; N],
+            received: 0,
+            frame: None,
+            tx: [0; N],
+            queued: 0,
+            sent: 0,
+            selected: false,
+            dropped: 0,
+            underruns: 0,
+        })
+    }
+
+    /// # On Interrupt
+    /// Move the received bytes into the receive buffer, refill the transmit FIFO, and
+    /// complete the frame when SS0 was deasserted. Call this from the SPI's interrupt
+    /// handler.
+    pub fn on_interrupt(&mut self) {
+        let reg = &mut self.spi.reg;
+        let asserted = reg.is_slave_select_asserted_flag_active();
+        let deasserted = reg.is_slave_select_deasserted_flag_active();
+        let overrun = reg.is_receive_overrun_flag_active();
+        let underrun = reg.is_transmit_underrun_flag_active();
+
+        unsafe {
+            reg.clear_receive_threshold_flag();
+            reg.clear_transmit_threshold_flag();
+            if asserted {
+                reg.clear_slave_select_asserted_flag();
+            }
+            if deasserted {
+                reg.clear_slave_select_deasserted_flag();
+            }
+            if overrun {
+                reg.clear_receive_overrun_flag();
+            }
+            if underrun {
+                reg.clear_transmit_underrun_flag();
+            }
+        }
+
+        self.selected |= asserted;
+        self.dropped += overrun as usize;
+        self.underruns += underrun as usize;
+
+        // The last bytes of a transaction ending are still drained into its frame
+        self.drain_receive_fifo();
+        self.fill_transmit_fifo();
+        if deasserted {
+            self.complete_frame();
+        }
+    }
+
+    /// # Preload
+    /// Queue as many of `bytes` as the transmit buffer has room for, to be sent as the
+    /// host clocks them out, returning how many. Bytes the current transaction does not
+    /// send are dropped when it ends.
+    pub fn preload(&mut self, bytes: &[u8]) -> usize {
+        self.without_interrupts(|slave| {
+            if slave.sent == slave.queued {
+                slave.sent = 0;
+                slave.queued = 0;
+            }
+
+            let count = bytes.len().min(N - slave.queued);
+            slave.tx[slave.queued..slave.queued + count].copy_from_slice(&bytes[..count]);
+            slave.queued += count;
+            slave.fill_transmit_fifo();
+            count
+        })
+    }
+
+    /// # Read Frame
+    /// Take the bytes of the last complete transaction, returning how many were copied
+    /// into `buffer`, or `None` when no transaction completed since the last call. The
+    /// bytes not fitting `buffer` are lost.
+    pub fn read_frame(&mut self, buffer: &mut [u8]) -> Option<usize> {
+        self.without_interrupts(|slave| {
+            let frame = slave.frame.take()?;
+            let count = frame.min(buffer.len());
+            buffer[..count].copy_from_slice(&slave.rx[..count]);
+            slave.received = 0;
+            Some(count)
+        })
+    }
+
+    /// # Is Selected
+    /// Check if the host is in a transaction with this slave.
+    pub fn is_selected(&self) -> bool {
+        self.selected
+    }
+
+    /// # Dropped
+    /// The received bytes lost since the buffer was full or the last frame was not read
+    /// in time, plus the overruns of the receive FIFO.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// # Underruns
+    /// The times the host read past the preloaded bytes.
+    pub fn underruns(&self) -> usize {
+        self.underruns
+    }
+
+    /// # Free
+    /// Disable the SPI's interrupts, and give it back as a master, without a slave
+    /// select. Bytes still buffered are lost.
+    pub fn free(mut self) -> SPI<Port> {
+        let reg = &mut self.spi.reg;
+        unsafe {
+            reg.set_spi_enable(false);
+            reg.set_receive_threshold_interrupt_enable(false);
+            reg.set_transmit_threshold_interrupt_enable(false);
+            reg.set_receive_overrun_interrupt_enable(false);
+            reg.set_transmit_underrun_interrupt_enable(false);
+            reg.set_slave_select_asserted_interrupt_enable(false);
+            reg.set_slave_select_deasserted_interrupt_enable(false);
+            reg.set_slave_select_input(false);
+            reg.set_master_mode_enable(true);
+            reg.set_spi_enable(true);
+        }
+
+        self.spi.disable_slave_select();
+        self.spi
+    }
+
+    /// # Drain Receive FIFO
+    /// Move the received bytes into the receive buffer. A frame not read yet is dropped
+    /// for the bytes of the next one.
+    fn drain_receive_fifo(&mut self) {
+        for _ in 0..FIFO_DEPTH {
+            if self.spi.reg.get_receive_fifo_level() == 0 {
+                break;
+            }
+
+            if let Some(frame) = self.frame.take() {
+                self.dropped += frame;
+                self.received = 0;
+            }
+
+            let byte = self.spi.reg.read_fifo_8();
+            if self.received < N {
+                self.rx[self.received] = byte;
+                self.received += 1;
+            } else {
+                self.dropped += 1;
+            }
+        }
+    }
+
+    /// # Fill Transmit FIFO
+    /// Move preloaded bytes into the transmit FIFO until it is full, then interrupt when
+    /// it is down to the threshold again while bytes are left.
+    fn fill_transmit_fifo(&mut self) {
+        let reg = &mut self.spi.reg;
+
+        while self.sent < self.queued && (reg.get_transmit_fifo_level() as usize) < FIFO_DEPTH {
+            unsafe { reg.write_fifo_8(self.tx[self.sent]) };
+            self.sent += 1;
+        }
+
+        unsafe { reg.set_transmit_threshold_interrupt_enable(self.sent < self.queued) };
+    }
+
+    /// # Complete Frame
+    /// End the transaction: keep what was received as a frame, and drop the preloaded
+    /// bytes not sent.
+    fn complete_frame(&mut self) {
+        self.selected = false;
+        self.frame = Some(self.received);
+        self.sent = 0;
+        self.queued = 0;
+
+        unsafe {
+            self.spi.reg.activate_transmit_fifo_flush();
+            self.spi.reg.set_transmit_threshold_interrupt_enable(false);
+        }
+    }
+
+    /// # Without Interrupts
+    /// Run `f` with the SPI's interrupts masked, so the buffers are not changed by
+    /// `on_interrupt` meanwhile.
+    fn without_interrupts<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        unsafe {
+            let reg = &mut self.spi.reg;
+            reg.set_receive_threshold_interrupt_enable(false);
+            reg.set_transmit_threshold_interrupt_enable(false);
+            reg.set_slave_select_deasserted_interrupt_enable(false);
+        }
+
+        let result = f(self);
+
+        unsafe {
+            let reg = &mut self.spi.reg;
+            reg.set_receive_threshold_interrupt_enable(true);
+            reg.set_transmit_threshold_interrupt_enable(self.sent < self.queued);
+            reg.set_slave_select_deasserted_interrupt_enable(true);
+        }
+        result
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::memory_map::mmio;
+    use crate::spi::SPI0;
+    use core::mem::ManuallyDrop;
+
+    /// Bit 4 of `SPI_INTFL`, the slave select asserted.
+    const SLAVE_SELECT_ASSERTED: u32 = 1 << 4;
+    /// Bit 5 of `SPI_INTFL`, the slave select deasserted.
+    const SLAVE_SELECT_DEASSERTED: u32 = 1 << 5;
+
+    #[test]
+    fn frames_transactions() {
+        crate::mock::reset();
+        let ctrl0 = mmio::SPI_0 + 0x04;
+        let dma = mmio::SPI_0 + 0x1C;
+        let flags = mmio::SPI_0 + 0x20;
+        let fifo = mmio::SPI_0;
+        let mut spi = ManuallyDrop::into_inner(unsafe { SPI::<SPI0>::steal() });
+        spi.set_word_size(8).unwrap();
+        let mut slave: SpiSlave<SPI0, 64> = SpiSlave::new(spi).unwrap();
+        // Slave, with SS0 as a input
+        assert_eq!(crate::mock::read(ctrl0) & 0b10010, 0b10000);
+
+        assert_eq!(slave.preload(b"abc"), 3);
+        assert_eq!(crate::mock::read(fifo) & 0xFF, b'c' as u32);
+        assert!(!slave.spi.reg.get_transmit_threshold_interrupt_enable());
+
+        crate::mock::write(flags, SLAVE_SELECT_ASSERTED);
+        slave.on_interrupt();
+        assert!(slave.is_selected());
+        assert_eq!(slave.read_frame(&mut [0; 4]), None);
+
+        // The mock FIFO reads back the last byte written, and never empties
+        crate::mock::write(dma, 4 << 24);
+        crate::mock::write(fifo, b'x' as u32);
+        crate::mock::write(flags, SLAVE_SELECT_DEASSERTED);
+        slave.on_interrupt();
+        assert!(!slave.is_selected());
+
+        let mut frame = [0; 8];
+        assert_eq!(slave.read_frame(&mut frame), Some(8));
+        assert_eq!(&frame, b"xxxxxxxx");
+        assert_eq!(slave.read_frame(&mut frame), None);
+
+        // Two transactions without reading the first
+        slave.on_interrupt();
+        slave.on_interrupt();
+        assert_eq!(slave.dropped(), FIFO_DEPTH);
+        assert_eq!(slave.read_frame(&mut [0; 64]), Some(FIFO_DEPTH));
+        assert_eq!(slave.underruns(), 0);
+
+        // Stolen pins, never release them
+        core::mem::forget(slave.free());
+    }
+}
@@ -0,0 +1,383 @@
+//! # Software SPI
+//! A SPI master on any GPIO pins, for boards where a device is wired to pins neither
+//! SPI controller can reach. SCK is toggled by the CPU, waiting out each half period
+//! with a `HalfPeriod`: a `CycleDelay` counting core clock cycles, or a one-shot
+//! timer (see `half_period_timer`). Either gives at most the requested frequency, the
+//! pin writes between waits slow it down a little more.
+//!
+//! It moves the same `u8` and `u16` characters as `SPI`, and with the `eh1` feature
+//! implements `embedded_hal::spi::SpiBus` too. The chip select is left to the caller,
+//! ie. a `SpiDevice` from `embedded-hal-bus`.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::error::ErrorKind;
+//! use max78000_hal::gpio::{GpioPin, GpioSelect};
+//! use max78000_hal::spi::soft::{CycleDelay, SoftSpi};
+//! use max78000_hal::spi::SpiMode;
+//! use max78000_hal::time::Hertz;
+//!
+//! let sck = GpioPin::new(GpioSelect::Gpio2, 3).ok_or(ErrorKind::Busy)?;
+//! let mosi = GpioPin::new(GpioSelect::Gpio2, 4).ok_or(ErrorKind::Busy)?;
+//! let miso = GpioPin::new(GpioSelect::Gpio2, 6).ok_or(ErrorKind::Busy)?;
+//!
+//! let delay = CycleDelay::new(Hertz::kHz(500))?;
+//! let mut spi = SoftSpi::new(sck, Some(mosi), Some(miso), SpiMode::Mode0, 8, delay)?;
+//! let mut id = [0x9F_u8, 0, 0, 0];
+//! spi.transfer_in_place(&mut id)?;
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::{private, SpiMode};
+use crate::delay::Delay;
+use crate::error::{ErrorKind, Result};
+use crate::gpio::{GpioPin, OutputDriveStrength, PinFunction, ResistorStrength, VoltageSelect};
+use crate::time::Hertz;
+use crate::timer::registers::TimerPrescaler;
+use crate::timer::{mode, Timer};
+
+/// # Half Period
+/// Waits out half a SCK period.
+pub trait HalfPeriod {
+    /// Block for half a SCK period.
+    fn half_period(&mut self);
+}
+
+/// No wait, SCK toggles as fast as the pins are written.
+impl HalfPeriod for () {
+    fn half_period(&mut self) {}
+}
+
+/// # Cycle Delay
+/// Half periods counted in core clock cycles, see `Delay`.
+#[derive(Clone, Copy)]
+pub struct CycleDelay {
+    delay: Delay,
+    cycles: u32,
+}
+
+impl CycleDelay {
+    /// # New
+    /// Half periods of `frequency`, at the current core clock.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `frequency` is zero.
+    pub fn new(frequency: Hertz) -> Result<Self> {
+        Ok(Self {
+            delay: Delay::new(),
+            cycles: half_period_ticks(crate::core_clock(), frequency)?,
+        })
+    }
+}
+
+impl HalfPeriod for CycleDelay {
+    fn half_period(&mut self) {
+        self.delay.delay_cycles(self.cycles);
+    }
+}
+
+/// A one-shot timer counting half periods, see `half_period_timer`.
+impl<const PORT: usize> HalfPeriod for Timer<PORT, mode::OneShot> {
+    fn half_period(&mut self) {
+        self.start();
+        self.wait();
+    }
+}
+
+/// # Half Period Timer
+/// Make `timer` a one-shot timer counting half periods of `frequency`, from its own
+/// clock rather than the core's.
+///
+/// # Errors
+/// `ErrorKind::BadParam` when `frequency` is zero.
+pub fn half_period_timer<const PORT: usize>(
+    timer: Timer<PORT, mode::Disabled>,
+    frequency: Hertz,
+) -> Result<Timer<PORT, mode::OneShot>> {
+    let mut timer = timer.into_oneshot_ticks(TimerPrescaler::Div1, 1);
+    let ticks = half_period_ticks(timer.tick_rate(), frequency)?;
+    timer.set_compare(ticks);
+    Ok(timer)
+}
+
+/// # Half Period Ticks
+/// The ticks of `clock` in half a period of `frequency`, rounded up so never faster.
+fn half_period_ticks(clock: Hertz, frequency: Hertz) -> Result<u32> {
+    if frequency.raw() == 0 {
+        return Err(ErrorKind::BadParam);
+    }
+    Ok(clock.raw().div_ceil(2 * frequency.raw()).max(1))
+}
+
+/// # Software SPI
+/// A SPI master clocking its characters out of GPIO pins, most significant bit first.
+/// Without MOSI nothing is sent, without MISO zeros are received.
+pub struct SoftSpi<D = CycleDelay> {
+    sck: GpioPin,
+    mosi: Option<GpioPin>,
+    miso: Option<GpioPin>,
+    mode: SpiMode,
+    word_size: u8,
+    delay: D,
+}
+
+impl<D: HalfPeriod> SoftSpi<D> {
+    /// # New
+    /// Take the pins, making SCK and MOSI outputs (SCK idling as `mode` wants) and
+    /// MISO a input.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `word_size` is not 1 to 16 bits.
+    pub fn new(
+        sck: GpioPin,
+        mosi: Option<GpioPin>,
+        miso: Option<GpioPin>,
+        mode: SpiMode,
+        word_size: u8,
+        delay: D,
+    ) -> Result<Self> {
+        let mut spi = Self {
+            sck,
+            mosi,
+            miso,
+            mode,
+            word_size: 8,
+            delay,
+        };
+        spi.set_word_size(word_size)?;
+
+        let strength = || OutputDriveStrength::Strength0(VoltageSelect::VddIO);
+        spi.sck.set_output(mode.polarity());
+        spi.sck.configure_output(strength(), PinFunction::IO);
+        if let Some(mosi) = &spi.mosi {
+            mosi.set_output(false);
+            mosi.configure_output(strength(), PinFunction::IO);
+        }
+        if let Some(miso) = &spi.miso {
+            miso.configure_input(ResistorStrength::None, PinFunction::IO);
+        }
+        Ok(spi)
+    }
+
+    /// # Set Mode
+    /// Change the clock polarity and phase, moving SCK to its new idle level.
+    pub fn set_mode(&mut self, mode: SpiMode) {
+        self.mode = mode;
+        self.sck.set_output(mode.polarity());
+    }
+
+    /// # Set Word Size
+    /// Change the bits in a character, 1 to 16.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `bits` is out of range.
+    pub fn set_word_size(&mut self, bits: u8) -> Result<()> {
+        if !(1..=16).contains(&bits) {
+            return Err(ErrorKind::BadParam);
+        }
+        self.word_size = bits;
+        Ok(())
+    }
+
+    /// # Word Size
+    /// The bits in a character.
+    pub fn word_size(&self) -> u8 {
+        self.word_size
+    }
+
+    /// # Transfer
+    /// Send `write` while receiving into `read`, see `SPI::transfer`.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `W` does not fit the word size.
+    pub fn transfer<W: private::Word>(&mut self, read: &mut [W], write: &[W]) -> Result<()> {
+        self.check::<W>()?;
+        for index in 0..read.len().max(write.len()) {
+            let word = self.exchange(write.get(index).copied().unwrap_or_default());
+            if let Some(slot) = read.get_mut(index) {
+                *slot = word;
+            }
+        }
+        Ok(())
+    }
+
+    /// # Transfer In Place
+    /// Send `words`, replacing each of them by the character received with it.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `W` does not fit the word size.
+    pub fn transfer_in_place<W: private::Word>(&mut self, words: &mut [W]) -> Result<()> {
+        self.check::<W>()?;
+        for word in words {
+            *word = self.exchange(*word);
+        }
+        Ok(())
+    }
+
+    /// # Write
+    /// Send `words`, dropping what is received.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `W` does not fit the word size.
+    pub fn write<W: private::Word>(&mut self, words: &[W]) -> Result<()> {
+        self.check::<W>()?;
+        for word in words {
+            self.exchange(*word);
+        }
+        Ok(())
+    }
+
+    /// # Read
+    /// Receive into `words`, sending zeros.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `W` does not fit the word size.
+    pub fn read<W: private::Word>(&mut self, words: &mut [W]) -> Result<()> {
+        self.check::<W>()?;
+        for word in words {
+            *word = self.exchange(W::default());
+        }
+        Ok(())
+    }
+
+    /// # Free
+    /// Give back SCK, MOSI, MISO and the delay, as they are.
+    pub fn free(self) -> (GpioPin, Option<GpioPin>, Option<GpioPin>, D) {
+        (self.sck, self.mosi, self.miso, self.delay)
+    }
+
+    /// # Check
+    /// Check if `W` fits the word size, like for `SPI`.
+    fn check<W: private::Word>(&self) -> Result<()> {
+        if (W::BYTES == 1) != (self.word_size <= 8) {
+            return Err(ErrorKind::BadParam);
+        }
+        Ok(())
+    }
+
+    /// # Exchange
+    /// Clock one character out and one in. With CPHA = 0 data is set up before the
+    /// leading edge and sampled on it, with CPHA = 1 set up on the leading edge and
+    /// sampled on the trailing one.
+    fn exchange<W: private::Word>(&mut self, word: W) -> W {
+        let idle = self.mode.polarity();
+        let bits = word.to_bits();
+        let mut received = 0;
+
+        for bit in (0..self.word_size).rev() {
+            let out = bits & (1 << bit) != 0;
+            if self.mode.phase() {
+                self.sck.set_output(!idle);
+                self.send(out);
+                self.delay.half_period();
+                self.sck.set_output(idle);
+                received = received << 1 | self.sample();
+                self.delay.half_period();
+            } else {
+                self.send(out);
+                self.delay.half_period();
+                self.sck.set_output(!idle);
+                received = received << 1 | self.sample();
+                self.delay.half_period();
+                self.sck.set_output(idle);
+            }
+        }
+        W::from_bits(received)
+    }
+
+    /// Drive MOSI, if there is one.
+    fn send(&self, high: bool) {
+        if let Some(mosi) = &self.mosi {
+            mosi.set_output(high);
+        }
+    }
+
+    /// The level of MISO, zero without one.
+    fn sample(&self) -> u16 {
+        self.miso.as_ref().is_some_and(GpioPin::get_input) as u16
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<D: HalfPeriod> embedded_hal::spi::ErrorType for SoftSpi<D> {
+    type Error = crate::Error;
+}
+
+/// `u8` words for sizes of up to 8 bits, `u16` words above, like `SPI`.
+#[cfg(feature = "eh1")]
+impl<D: HalfPeriod, W: private::Word + 'static> embedded_hal::spi::SpiBus<W> for SoftSpi<D> {
+    fn read(&mut self, words: &mut [W]) -> core::result::Result<(), Self::Error> {
+        SoftSpi::read(self, words).map_err(|kind| kind.within(crate::error::Peripheral::Spi))
+    }
+
+    fn write(&mut self, words: &[W]) -> core::result::Result<(), Self::Error> {
+        SoftSpi::write(self, words).map_err(|kind| kind.within(crate::error::Peripheral::Spi))
+    }
+
+    fn transfer(&mut self, read: &mut [W], write: &[W]) -> core::result::Result<(), Self::Error> {
+        SoftSpi::transfer(self, read, write)
+            .map_err(|kind| kind.within(crate::error::Peripheral::Spi))
+    }
+
+    fn transfer_in_place(&mut self, words: &mut [W]) -> core::result::Result<(), Self::Error> {
+        SoftSpi::transfer_in_place(self, words)
+            .map_err(|kind| kind.within(crate::error::Peripheral::Spi))
+    }
+
+    /// Every character is clocked out before returning.
+    fn flush(&mut self) -> core::result::Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn half_periods_are_never_short() {
+        assert_eq!(half_period_ticks(Hertz::MHz(100), Hertz::MHz(1)), Ok(50));
+        assert_eq!(half_period_ticks(Hertz::MHz(100), Hertz::kHz(300)), Ok(167));
+        assert_eq!(half_period_ticks(Hertz::MHz(1), Hertz::MHz(2)), Ok(1));
+        assert_eq!(
+            half_period_ticks(Hertz::MHz(1), Hertz::Hz(0)),
+            Err(ErrorKind::BadParam)
+        );
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn clocks_characters_on_gpio() {
+        use crate::gpio::GpioSelect;
+        use crate::memory_map::mmio;
+
+        crate::gpio::reset_mock();
+        let sck = GpioPin::new(GpioSelect::Gpio2, 3).unwrap();
+        let mosi = GpioPin::new(GpioSelect::Gpio2, 4).unwrap();
+        let miso = GpioPin::new(GpioSelect::Gpio2, 6).unwrap();
+        assert!(SoftSpi::new(sck, None, None, SpiMode::Mode0, 17, ()).is_err());
+
+        let sck = GpioPin::new(GpioSelect::Gpio2, 3).unwrap();
+        let mut spi = SoftSpi::new(sck, Some(mosi), Some(miso), SpiMode::Mode2, 8, ()).unwrap();
+        assert!(spi.sck.get_output());
+
+        // MISO high, every bit is read as one
+        crate::mock::write(mmio::GPIO_PORT_2 + 0x24, 1 << 6);
+        let mut bytes = [0x01_u8, 0x80];
+        spi.transfer_in_place(&mut bytes).unwrap();
+        assert_eq!(bytes, [0xFF, 0xFF]);
+        // The last bit sent was a zero, SCK is back idle
+        assert!(!spi.mosi.as_ref().unwrap().get_output());
+        assert!(spi.sck.get_output());
+
+        spi.set_mode(SpiMode::Mode1);
+        spi.set_word_size(5).unwrap();
+        spi.write(&[0x01_u8]).unwrap();
+        assert!(spi.mosi.as_ref().unwrap().get_output());
+        assert!(!spi.sck.get_output());
+
+        let mut word = [0_u8];
+        spi.read(&mut word).unwrap();
+        assert_eq!(word, [0x1F]);
+        assert_eq!(spi.write(&[0_u16]), Err(ErrorKind::BadParam));
+    }
+}
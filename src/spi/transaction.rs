@@ -0,0 +1,318 @@
+//! # SPI Transactions
+//! Several operations run with the slave select asserted throughout, following the
+//! `embedded_hal::spi::Operation` model, see `SPI::transaction`.
+//!
+//! The controller counts the characters to send and to receive separately, so
+//! consecutive operations are merged into as few hardware transactions as possible: a
+//! command written then a reply read is one transaction, sending the command and
+//! receiving only up to the end of the reply. For dual and quad transfers the
+//! controller sends all of its characters before receiving, so a write followed by a
+//! read is one transaction there too.
+//!
+//! # Example
+//! ```no_run
+//! use embedded_hal::spi::Operation;
+//! use max78000_hal::spi::{SpiMode, SPI};
+//! use max78000_hal::time::Hertz;
+//! # let mut spi = SPI::port_0_init(SpiMode::Mode0, 8, Hertz::MHz(4))?;
+//! # const READ_STATUS: u8 = 0x05;
+//!
+//! let mut status = [0; 2];
+//! spi.transaction(&mut [
+//!     Operation::Write(&[READ_STATUS]),
+//!     Operation::Read(&mut status),
+//! ])?;
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::{private, DataWidth, MAX_CHARACTERS, SPI};
+use crate::error::{ErrorKind, Result};
+use embedded_hal::spi::Operation;
+
+/// # Span
+/// The characters `operation` moves, on the sending side (`sending`) or the receiving
+/// one. Full duplex operations move the same characters on both.
+fn span<W>(operation: &Operation<'_, W>, single: bool, sending: bool) -> usize {
+    match operation {
+        Operation::Read(words) if single || !sending => words.len(),
+        Operation::Write(words) if single || sending => words.len(),
+        Operation::Transfer(read, write) => read.len().max(write.len()),
+        Operation::TransferInPlace(words) => words.len(),
+        _ => 0,
+    }
+}
+
+/// # Sent
+/// The characters `operation` sends out of its span, those after are sent as zero.
+fn sent<W>(operation: &Operation<'_, W>) -> usize {
+    match operation {
+        Operation::Write(words) => words.len(),
+        Operation::Transfer(_, words) => words.len(),
+        Operation::TransferInPlace(words) => words.len(),
+        _ => 0,
+    }
+}
+
+/// # Stored
+/// The characters `operation` keeps out of its span, those after are dropped.
+fn stored<W>(operation: &Operation<'_, W>) -> usize {
+    match operation {
+        Operation::Read(words) | Operation::TransferInPlace(words) => words.len(),
+        Operation::Transfer(read, _) => read.len(),
+        _ => 0,
+    }
+}
+
+/// # Cursor
+/// Finds the operation holding a character, for characters asked for in order.
+#[derive(Default)]
+struct Cursor {
+    operation: usize,
+    start: usize,
+}
+
+impl Cursor {
+    /// The operation of `operations` holding character `index`, and the character's
+    /// position in it.
+    fn seek<W>(
+        &mut self,
+        operations: &[Operation<'_, W>],
+        index: usize,
+        single: bool,
+        sending: bool,
+    ) -> (usize, usize) {
+        while let Some(operation) = operations.get(self.operation) {
+            let span = span(operation, single, sending);
+            if index < self.start + span {
+                break;
+            }
+            self.start += span;
+            self.operation += 1;
+        }
+        (self.operation, index - self.start)
+    }
+}
+
+impl<Port: private::SPIPortCompatable> SPI<Port> {
+    /// # Transaction
+    /// Run `operations` one after the other, with the slave select asserted
+    /// throughout. Unless kept selected (see `set_keep_selected`) it is deasserted
+    /// when the last one ends.
+    ///
+    /// Operations longer than 65535 characters are split, like `transfer`.
+    ///
+    /// # Errors
+    /// Nothing is moved when:
+    /// - `ErrorKind::BadParam`: `W` does not fit the word size, or there is a
+    ///   `Operation::DelayNs`, which needs a delay provider (see `device`).
+    /// - `ErrorKind::BadState`: a dual or quad transaction has a full duplex operation.
+    pub fn transaction<W: private::Word>(
+        &mut self,
+        operations: &mut [Operation<'_, W>],
+    ) -> Result<()> {
+        // Only the word size, the directions are checked by operation
+        self.check::<W>((true, false))?;
+        let single = self.data_width() == DataWidth::Single;
+        for operation in operations.iter() {
+            match operation {
+                Operation::DelayNs(_) => return Err(ErrorKind::BadParam),
+                Operation::Transfer(..) | Operation::TransferInPlace(_) if !single => {
+                    return Err(ErrorKind::BadState)
+                }
+                _ => {}
+            }
+        }
+
+        let keep = self.reg.get_slave_select_stay_asserted();
+        let mut start = 0;
+        while start < operations.len() {
+            let end = start + merged(&operations[start..], single);
+            let last = end == operations.len();
+
+            self.set_keep_selected(keep || !last);
+            let segment = &mut operations[start..end];
+            if end - start == 1 && span(&segment[0], true, true) > MAX_CHARACTERS {
+                self.run_long(&mut segment[0]);
+            } else {
+                self.run(segment, single);
+            }
+            start = end;
+        }
+
+        self.set_keep_selected(keep);
+        Ok(())
+    }
+
+    /// # Run
+    /// Move `operations` in one hardware transaction, see `merged`.
+    fn run<W: private::Word>(&mut self, operations: &mut [Operation<'_, W>], single: bool) {
+        let (transmit, receive) = counts(operations, single);
+        if transmit == 0 && receive == 0 {
+            return;
+        }
+
+        // Only one of the closures runs at a time
+        let operations = core::cell::RefCell::new(operations);
+        let (mut sending, mut receiving) = (Cursor::default(), Cursor::default());
+        self.move_characters(
+            transmit,
+            receive,
+            |index| {
+                let operations = operations.borrow();
+                let (operation, at) = sending.seek(&operations, index, single, true);
+                match operations.get(operation) {
+                    Some(Operation::Write(words) | Operation::Transfer(_, words)) => {
+                        words.get(at).copied().unwrap_or_default()
+                    }
+                    Some(Operation::TransferInPlace(words)) => words[at],
+                    _ => W::default(),
+                }
+            },
+            |index, word| {
+                let mut operations = operations.borrow_mut();
+                let (operation, at) = receiving.seek(&operations, index, single, false);
+                if let Some(
+                    Operation::Read(words)
+                    | Operation::Transfer(words, _)
+                    | Operation::TransferInPlace(words),
+                ) = operations.get_mut(operation)
+                {
+                    if let Some(slot) = words.get_mut(at) {
+                        *slot = word;
+                    }
+                }
+            },
+        );
+    }
+
+    /// # Run Long
+    /// Move a operation too long for a single hardware transaction, split into
+    /// several.
+    fn run_long<W: private::Word>(&mut self, operation: &mut Operation<'_, W>) {
+        // Checked already, so can not fail
+        let _ = match operation {
+            Operation::Read(words) => self.read(words),
+            Operation::Write(words) => self.write(words),
+            Operation::Transfer(read, write) => self.transfer(read, write),
+            Operation::TransferInPlace(words) => self.transfer_in_place(words),
+            Operation::DelayNs(_) => Ok(()),
+        };
+    }
+}
+
+/// # Merged
+/// How many of the leading `operations` fit one hardware transaction: up to
+/// `MAX_CHARACTERS` each way, and for dual and quad transfers no write after a read.
+/// Always at least one.
+fn merged<W>(operations: &[Operation<'_, W>], single: bool) -> usize {
+    let mut reading = false;
+    for count in 1..operations.len() {
+        reading |= matches!(&operations[count - 1], Operation::Read(words) if !words.is_empty());
+        let next = &operations[count];
+        let (transmit, receive) = counts(&operations[..=count], single);
+
+        let turns =
+            !single && reading && matches!(next, Operation::Write(words) if !words.is_empty());
+        if turns || transmit > MAX_CHARACTERS || receive > MAX_CHARACTERS {
+            return count;
+        }
+    }
+    operations.len().max(1)
+}
+
+/// # Counts
+/// The characters to send and to receive to move `operations` in one transaction.
+/// Characters after the last one sent are sent as zero by the hardware, and those
+/// after the last one kept are not received.
+fn counts<W>(operations: &[Operation<'_, W>], single: bool) -> (usize, usize) {
+    if !single {
+        let transmit = operations.iter().map(|op| span(op, false, true)).sum();
+        let receive = operations.iter().map(|op| span(op, false, false)).sum();
+        return (transmit, receive);
+    }
+
+    let (mut start, mut transmit, mut receive) = (0, 0, 0);
+    for operation in operations {
+        if sent(operation) != 0 {
+            transmit = start + sent(operation);
+        }
+        if stored(operation) != 0 {
+            receive = start + stored(operation);
+        }
+        start += span(operation, true, true);
+    }
+    (transmit, receive)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn counts_follow_the_operations() {
+        let (mut status, mut reply) = ([0_u8; 2], [0_u8; 3]);
+        let operations = [
+            Operation::Write(&[0x05_u8]),
+            Operation::Read(&mut status),
+            Operation::Transfer(&mut reply, &[1, 2]),
+            Operation::Write(&[]),
+        ];
+        assert_eq!(counts(&operations, true), (5, 6));
+        assert_eq!(merged(&operations, true), 4);
+
+        // Wide, a read can not be followed by a write
+        let (mut data, mut more) = ([0_u8; 4], [0_u8; 4]);
+        let operations = [
+            Operation::Write(&[0xEB_u8, 0, 0, 0]),
+            Operation::Read(&mut data),
+            Operation::Write(&[0x03]),
+            Operation::Read(&mut more),
+        ];
+        assert_eq!(counts(&operations[..2], false), (4, 4));
+        assert_eq!(merged(&operations, false), 2);
+        assert_eq!(merged(&operations[2..], false), 2);
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn operations_share_a_transaction() {
+        use crate::memory_map::mmio;
+        use crate::spi::SPI0;
+
+        crate::mock::reset();
+        let ctrl0 = mmio::SPI_0 + 0x04;
+        let ctrl1 = mmio::SPI_0 + 0x08;
+        crate::mock::write(mmio::SPI_0 + 0x1C, 2 << 24);
+        crate::mock::write(mmio::SPI_0 + 0x20, 1 << 11);
+        let mut spi = unsafe { SPI::<SPI0>::steal() };
+        spi.set_word_size(8).unwrap();
+
+        // The mock FIFO reads back the last byte written, the command
+        let mut status = [0_u8; 2];
+        spi.transaction(&mut [Operation::Write(&[0x05_u8]), Operation::Read(&mut status)])
+            .unwrap();
+        assert_eq!(crate::mock::read(ctrl1), (3 << 16) | 1);
+        assert_eq!(status, [0x05, 0x05]);
+        assert_eq!(crate::mock::read(ctrl0) >> 8 & 1, 0);
+
+        // Without sending anything past the last write, the receive count follows the
+        // last read
+        let mut reply = [0_u8; 1];
+        spi.transaction(&mut [
+            Operation::Read(&mut reply),
+            Operation::Write(&[0x06_u8, 0x07]),
+        ])
+        .unwrap();
+        assert_eq!(crate::mock::read(ctrl1), (1 << 16) | 3);
+        assert_eq!(reply, [0x07]);
+
+        assert_eq!(
+            spi.transaction(&mut [Operation::<u8>::DelayNs(10)]),
+            Err(ErrorKind::BadParam)
+        );
+        spi.set_data_width(DataWidth::Dual).unwrap();
+        assert_eq!(
+            spi.transaction(&mut [Operation::TransferInPlace(&mut [0_u8])]),
+            Err(ErrorKind::BadState)
+        );
+    }
+}
@@ -0,0 +1,494 @@
+//! # SPI Wait
+//! Transfers without spinning on the FIFOs. The SPI's interrupt wakes the waiting task
+//! when the transmit FIFO has room, the receive FIFO holds characters, or the
+//! transaction is done, so an async executor can run other tasks (or sleep the core)
+//! meanwhile. With the `async` feature a `SPI` also implements
+//! `embedded_hal_async::spi::SpiBus`, for embassy drivers.
+//!
+//! Bind `InterruptHandler` to the interrupts of the SPIs you wait on, the NVIC line is
+//! enabled by the first wait on that SPI. A SPI is either waited on or a slave, so bind
+//! this or a handler calling `SpiSlave::on_interrupt`, not both.
+//!
+//! Dropping a transfer before it completes aborts its transaction.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::bind_interrupts;
+//! use max78000_hal::error::Result;
+//! use max78000_hal::spi::{wait, SPI, SPI0};
+//!
+//! bind_interrupts!(struct Irqs {
+//!     SPI0 => wait::InterruptHandler;
+//! });
+//!
+//! async fn jedec_id(spi: &mut SPI<SPI0>) -> Result<[u8; 4]> {
+//!     let mut id = [0x9F, 0, 0, 0];
+//!     spi.transfer_in_place_async(&mut id).await?;
+//!     Ok(id)
+//! }
+//! ```
+use core::cell::Cell;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use super::{private, registers, FIFO_DEPTH, MAX_CHARACTERS, SPI};
+use crate::error::Result;
+use crate::interrupt::typelevel::{Handler, SPI0, SPI1};
+use crate::interrupt::{Interrupt, InterruptExt};
+use crate::memory_map::mmio;
+
+/// The waker of the task waiting on every SPI. A slot is only written while its SPI's
+/// interrupts are disabled, and only taken by the interrupt handler while they are
+/// enabled.
+static mut WAKERS: [Option<Waker>; 2] = [const { None }; 2];
+
+/// # Transmit Threshold
+/// The transmit FIFO level at or below which a waiting write is woken.
+const TRANSMIT_THRESHOLD: usize = FIFO_DEPTH / 2;
+
+/// # Interrupt Handler
+/// Wakes the task waiting on the SPI. Bind it to `SPI0` and `SPI1`.
+pub struct InterruptHandler;
+
+impl Handler<SPI0> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch(mmio::SPI_0, 0);
+    }
+}
+
+impl Handler<SPI1> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch(mmio::SPI_1, 1);
+    }
+}
+
+/// Disable the interrupts of the SPI at `address`, and wake its task if one of them
+/// fired.
+unsafe fn dispatch(address: usize, index: usize) {
+    let mut reg = registers::Registers::new(address);
+
+    let transmit =
+        reg.get_transmit_threshold_interrupt_enable() && reg.is_transmit_threshold_flag_active();
+    let receive =
+        reg.get_receive_threshold_interrupt_enable() && reg.is_receive_threshold_flag_active();
+    let done = reg.get_master_done_interrupt_enable() && reg.is_master_done_flag_active();
+
+    if transmit || receive || done {
+        enable_events(&mut reg, false, false, false);
+        if let Some(waker) = WAKERS[index].take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Enable/Disable the transmit threshold, receive threshold and master done interrupts
+/// waits use.
+unsafe fn enable_events(reg: &mut registers::Registers, transmit: bool, receive: bool, done: bool) {
+    reg.set_transmit_threshold_interrupt_enable(transmit);
+    reg.set_receive_threshold_interrupt_enable(receive);
+    reg.set_master_done_interrupt_enable(done);
+}
+
+/// The NVIC line of SPI `port`.
+fn spi_interrupt(port: usize) -> Interrupt {
+    match port {
+        0 => Interrupt::SPI0,
+        _ => Interrupt::SPI1,
+    }
+}
+
+/// # Event
+/// What a `EventFuture` waits for.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Event {
+    /// Room for `room` bytes in the transmit FIFO, or `received` bytes in the receive
+    /// FIFO. Either is 0 when not waited for.
+    Fifo { room: usize, received: usize },
+    /// The transaction is done.
+    Done,
+}
+
+/// # Event Future
+/// Completes once `event` happened.
+#[must_use = "futures do nothing unless polled"]
+struct EventFuture<'a, Port: private::SPIPortCompatable> {
+    spi: &'a mut SPI<Port>,
+    event: Event,
+    armed: bool,
+}
+
+impl<Port: private::SPIPortCompatable> EventFuture<'_, Port> {
+    /// Check if the event happened.
+    fn is_ready(&self) -> bool {
+        let reg = &self.spi.reg;
+        match self.event {
+            Event::Fifo { room, received } => {
+                (room != 0 && reg.get_transmit_fifo_level() as usize + room <= FIFO_DEPTH)
+                    || (received != 0 && reg.get_receive_fifo_level() as usize >= received)
+            }
+            Event::Done => reg.is_master_done_flag_active(),
+        }
+    }
+
+    /// Enable/Disable the interrupts of the event. The threshold flags are cleared
+    /// first, they are raised again while the FIFO levels still meet the thresholds.
+    fn listen(&mut self, enable: bool) {
+        let reg = &mut self.spi.reg;
+        let (room, received, done) = match self.event {
+            Event::Fifo { room, received } => (room, received, false),
+            Event::Done => (0, 0, true),
+        };
+        let transmit = enable && room != 0;
+        let receive = enable && received != 0;
+
+        unsafe {
+            if transmit {
+                reg.set_transmit_fifo_threshold(TRANSMIT_THRESHOLD as u8);
+                reg.clear_transmit_threshold_flag();
+            }
+            if receive {
+                // Raised above the threshold
+                reg.set_receive_fifo_threshold((received - 1) as u8);
+                reg.clear_receive_threshold_flag();
+            }
+            enable_events(reg, transmit, receive, enable && done);
+        }
+    }
+}
+
+impl<Port: private::SPIPortCompatable> Future for EventFuture<'_, Port> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.armed {
+            // Stop the handler from touching the waker while it is replaced
+            self.listen(false);
+        } else {
+            unsafe { spi_interrupt(Port::PORT_NUM).enable() };
+            self.armed = true;
+        }
+
+        if self.is_ready() {
+            self.armed = false;
+            return Poll::Ready(());
+        }
+
+        unsafe { WAKERS[Port::PORT_NUM] = Some(cx.waker().clone()) };
+        self.listen(true);
+
+        Poll::Pending
+    }
+}
+
+impl<Port: private::SPIPortCompatable> Drop for EventFuture<'_, Port> {
+    fn drop(&mut self) {
+        if self.armed {
+            self.listen(false);
+            unsafe { WAKERS[Port::PORT_NUM] = None };
+        }
+    }
+}
+
+/// # Abort
+/// Ends the transaction of a transfer dropped before it was done, and puts back the
+/// slave select setting it had on entry.
+struct Abort {
+    reg: registers::Registers,
+    keep: bool,
+    armed: bool,
+}
+
+impl Drop for Abort {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+
+        // Disabling the SPI ends the transaction
+        unsafe {
+            self.reg.set_spi_enable(false);
+            self.reg.activate_transmit_fifo_flush();
+            self.reg.activate_receive_fifo_flush();
+            self.reg.clear_master_done_flag();
+            self.reg.set_spi_enable(true);
+            self.reg.set_slave_select_stay_asserted(self.keep);
+        }
+    }
+}
+
+impl<Port: private::SPIPortCompatable> SPI<Port> {
+    /// The future of `event`.
+    fn wait_for(&mut self, event: Event) -> EventFuture<'_, Port> {
+        EventFuture {
+            spi: self,
+            event,
+            armed: false,
+        }
+    }
+
+    /// # Transfer Async
+    /// `transfer`, waiting on the FIFOs. `InterruptHandler` must be bound to the SPI's
+    /// interrupt.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` when `W` does not fit the word size, see `Word`.
+    /// - `ErrorKind::BadState` for dual and quad transfers, which are half duplex.
+    pub async fn transfer_async<W: private::Word>(
+        &mut self,
+        read: &mut [W],
+        write: &[W],
+    ) -> Result<()> {
+        let count = read.len().max(write.len());
+        self.exchange_async(
+            count,
+            (true, true),
+            |index| write.get(index).copied().unwrap_or_default(),
+            |index, word| {
+                if let Some(slot) = read.get_mut(index) {
+                    *slot = word;
+                }
+            },
+        )
+        .await
+    }
+
+    /// # Transfer In Place Async
+    /// `transfer_in_place`, waiting on the FIFOs.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` when `W` does not fit the word size.
+    /// - `ErrorKind::BadState` for dual and quad transfers.
+    pub async fn transfer_in_place_async<W: private::Word>(
+        &mut self,
+        words: &mut [W],
+    ) -> Result<()> {
+        let words = Cell::from_mut(words).as_slice_of_cells();
+        self.exchange_async(
+            words.len(),
+            (true, true),
+            |index| words[index].get(),
+            |index, word| words[index].set(word),
+        )
+        .await
+    }
+
+    /// # Write Async
+    /// `write`, waiting on the FIFOs.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `W` does not fit the word size.
+    pub async fn write_async<W: private::Word>(&mut self, words: &[W]) -> Result<()> {
+        let lines = self.half_duplex(true, false);
+        self.exchange_async(words.len(), lines, |index| words[index], |_, _| {})
+            .await
+    }
+
+    /// # Read Async
+    /// `read`, waiting on the FIFOs.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `W` does not fit the word size.
+    pub async fn read_async<W: private::Word>(&mut self, words: &mut [W]) -> Result<()> {
+        let lines = self.half_duplex(false, true);
+        self.exchange_async(
+            words.len(),
+            lines,
+            |_| W::default(),
+            |index, word| words[index] = word,
+        )
+        .await
+    }
+
+    /// # Exchange Async
+    /// `exchange`, waiting for room in the transmit FIFO, characters in the receive FIFO
+    /// and the end of each transaction instead of spinning.
+    async fn exchange_async<W: private::Word>(
+        &mut self,
+        count: usize,
+        lines: (bool, bool),
+        mut next: impl FnMut(usize) -> W,
+        mut store: impl FnMut(usize, W),
+    ) -> Result<()> {
+        self.check::<W>(lines)?;
+
+        let keep = self.reg.get_slave_select_stay_asserted();
+        let mut done = 0;
+        while done < count {
+            let chunk = (count - done).min(MAX_CHARACTERS);
+            let transmit = if lines.0 { chunk } else { 0 };
+            let receive = if lines.1 { chunk } else { 0 };
+            // Only the last transaction may deassert the slave select, see `exchange`
+            self.set_keep_selected(keep || done + chunk < count);
+            self.prepare(transmit, receive);
+            let mut abort = Abort {
+                reg: registers::Registers::new(Port::PORT_PTR),
+                keep,
+                armed: true,
+            };
+
+            let (mut sent, mut received) = (0, 0);
+            let mut started = false;
+            loop {
+                while sent < transmit
+                    && self.reg.get_transmit_fifo_level() as usize + W::BYTES <= FIFO_DEPTH
+                {
+                    unsafe { next(done + sent).push(&mut self.reg) };
+                    sent += 1;
+                }
+
+                // Start with the transmit FIFO filled, so it does not run dry right away
+                if !started {
+                    unsafe { self.reg.activate_start_transaction() };
+                    started = true;
+                }
+
+                while received < receive && self.reg.get_receive_fifo_level() as usize >= W::BYTES {
+                    store(done + received, W::pop(&mut self.reg));
+                    received += 1;
+                }
+
+                if sent == transmit && received == receive {
+                    break;
+                }
+
+                // Wake for a whole character, or half a FIFO of them to save interrupts
+                let room = if sent < transmit { W::BYTES } else { 0 };
+                let wanted = ((receive - received) * W::BYTES).min(FIFO_DEPTH / 2);
+                self.wait_for(Event::Fifo {
+                    room,
+                    received: wanted,
+                })
+                .await;
+            }
+
+            self.wait_for(Event::Done).await;
+            abort.armed = false;
+            unsafe { self.reg.clear_master_done_flag() };
+            done += chunk;
+        }
+
+        self.set_keep_selected(keep);
+        Ok(())
+    }
+}
+
+/// `u8` words for sizes of up to 8 bits, `u16` words above, see `Word`.
+#[cfg(feature = "async")]
+impl<Port: private::SPIPortCompatable, W: private::Word + 'static>
+    embedded_hal_async::spi::SpiBus<W> for SPI<Port>
+{
+    async fn read(&mut self, words: &mut [W]) -> core::result::Result<(), Self::Error> {
+        self.read_async(words)
+            .await
+            .map_err(|kind| kind.within(crate::error::Peripheral::Spi))
+    }
+
+    async fn write(&mut self, words: &[W]) -> core::result::Result<(), Self::Error> {
+        self.write_async(words)
+            .await
+            .map_err(|kind| kind.within(crate::error::Peripheral::Spi))
+    }
+
+    async fn transfer(
+        &mut self,
+        read: &mut [W],
+        write: &[W],
+    ) -> core::result::Result<(), Self::Error> {
+        self.transfer_async(read, write)
+            .await
+            .map_err(|kind| kind.within(crate::error::Peripheral::Spi))
+    }
+
+    async fn transfer_in_place(
+        &mut self,
+        words: &mut [W],
+    ) -> core::result::Result<(), Self::Error> {
+        self.transfer_in_place_async(words)
+            .await
+            .map_err(|kind| kind.within(crate::error::Peripheral::Spi))
+    }
+
+    /// Every transfer waits for its transaction to be done.
+    async fn flush(&mut self) -> core::result::Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use core::pin::pin;
+
+    #[test]
+    fn interrupt_wakes_done() {
+        crate::mock::reset();
+        let flags = mmio::SPI_0 + 0x20;
+        let mut spi = unsafe { SPI::<crate::spi::SPI0>::steal() };
+
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut future = spi.wait_for(Event::Done);
+        assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Pending);
+        assert!(Interrupt::SPI0.is_enabled());
+        assert!(future.spi.reg.get_master_done_interrupt_enable());
+
+        // The transaction ends
+        crate::mock::write(flags, 1 << 11);
+        unsafe { <InterruptHandler as Handler<SPI0>>::on_interrupt() };
+        assert!(!future.spi.reg.get_master_done_interrupt_enable());
+        assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Ready(()));
+    }
+
+    #[test]
+    fn transfers_wait_on_the_fifos() {
+        crate::mock::reset();
+        let dma = mmio::SPI_0 + 0x1C;
+        let flags = mmio::SPI_0 + 0x20;
+        let mut spi = unsafe { SPI::<crate::spi::SPI0>::steal() };
+        spi.set_word_size(8).unwrap();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        // Nothing received yet, the transfer waits for the receive FIFO
+        let mut bytes = [1_u8, 2, 3];
+        {
+            let mut transfer = pin!(spi.transfer_in_place_async(&mut bytes));
+            assert_eq!(transfer.as_mut().poll(&mut cx), Poll::Pending);
+            let inten = crate::mock::read(mmio::SPI_0 + 0x24);
+            assert_eq!(inten & 0b101, 0b100);
+            // Raised with all three bytes in
+            assert_eq!(crate::mock::read(dma) >> 16 & 0x1F, 2);
+
+            crate::mock::write(dma, 3 << 24);
+            crate::mock::write(flags, 1 << 11);
+            assert_eq!(transfer.as_mut().poll(&mut cx), Poll::Ready(Ok(())));
+        }
+        // The mock FIFO reads back the last byte written
+        assert_eq!(bytes, [3, 3, 3]);
+
+        let mut words = [0_u16; 2];
+        let read = pin!(spi.read_async(&mut words));
+        assert_eq!(
+            read.poll(&mut cx),
+            Poll::Ready(Err(crate::error::ErrorKind::BadParam))
+        );
+    }
+
+    #[test]
+    fn long_transfers_stay_selected() {
+        crate::mock::reset();
+        let ctrl0 = mmio::SPI_0 + 0x04;
+        let mut spi = unsafe { SPI::<crate::spi::SPI0>::steal() };
+        spi.set_word_size(8).unwrap();
+        let mut cx = Context::from_waker(Waker::noop());
+
+        // The first of two transactions keeps the slave select asserted
+        let mut bytes = [0_u8; MAX_CHARACTERS + 1];
+        {
+            let mut transfer = pin!(spi.transfer_in_place_async(&mut bytes));
+            assert_eq!(transfer.as_mut().poll(&mut cx), Poll::Pending);
+            assert_ne!(crate::mock::read(ctrl0) & (1 << 8), 0);
+        }
+        // Dropped before it was done, the setting is put back
+        assert_eq!(crate::mock::read(ctrl0) & (1 << 8), 0);
+    }
+}
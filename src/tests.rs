@@ -1,10 +1,10 @@
 use hal_macros::{VolatileRead, RW};
 use hal_macros_derive::make_device;
 
-const FIRST_4_BYTES: usize = 0;
-const SECOND_4_BYTES: usize = 1;
-const THREE_4_BYTES: usize = 2;
-const FOUR_4_BYTES: usize = 3;
+const FIRST_4_BYTES: usize = 0x0;
+const SECOND_4_BYTES: usize = 0x4;
+const THREE_4_BYTES: usize = 0x8;
+const FOUR_4_BYTES: usize = 0xC;
 
 make_device! {
     // No need for device ports when testing, since
@@ -127,6 +127,18 @@ make_device! {
 
     #[bit(24..=31, RW, FOUR_4_BYTES)]
     range_6,
+
+    #[bit(0..=1, RW, FOUR_4_BYTES)]
+    #[values(Off = 0, Low = 1, Medium = 2, High = 3)]
+    values_0,
+
+    #[bit(4..=7, RW, FOUR_4_BYTES)]
+    #[values(Divider: Div1 = 0, Div2 = 1, Div4 = 2, Div8 = 3)]
+    values_1,
+
+    #[bit(8..=11, RW, FOUR_4_BYTES)]
+    #[values(Divider: Div1 = 0, Div2 = 1, Div4 = 2, Div8 = 3)]
+    values_2,
 }
 
 macro_rules! single_bit_test {
@@ -298,6 +310,143 @@ fn test_range_u8() {
     }
 }
 
+#[test]
+fn test_values_exhaustive() {
+    #[allow(unused)]
+    let mut fake_device_storage = [0u32; 4];
+    #[allow(unused)]
+    let mut reg = Registers::new(fake_device_storage.as_mut() as *mut [u32] as *mut u32 as usize);
+
+    for value in [Values0::Off, Values0::Low, Values0::Medium, Values0::High] {
+        unsafe { reg.set_values_0(value) };
+        assert_eq!(reg.get_values_0(), value);
+        assert_eq!(reg.four_4_bytes.read(), value.into_bits() as u32);
+    }
+}
+
+#[test]
+fn test_values_shared_enum() {
+    #[allow(unused)]
+    let mut fake_device_storage = [0u32; 4];
+    #[allow(unused)]
+    let mut reg = Registers::new(fake_device_storage.as_mut() as *mut [u32] as *mut u32 as usize);
+
+    unsafe {
+        reg.set_values_1(Divider::Div4);
+        reg.set_values_2(Divider::Div8);
+    }
+    assert_eq!(reg.get_values_1(), Some(Divider::Div4));
+    assert_eq!(reg.get_values_2(), Some(Divider::Div8));
+    assert_eq!(reg.four_4_bytes.read(), (2 << 4) | (3 << 8));
+
+    // Bit patterns without a enumerated value read back as `None`
+    unsafe { reg.set_range_3(0xF0) };
+    assert_eq!(reg.get_values_1(), None);
+    assert_eq!(Divider::from_bits(4), None);
+}
+
+#[test]
+fn test_modify() {
+    #[allow(unused)]
+    let mut fake_device_storage = [0u32; 4];
+    #[allow(unused)]
+    let mut reg = Registers::new(fake_device_storage.as_mut() as *mut [u32] as *mut u32 as usize);
+
+    unsafe {
+        reg.set_range_6(0xAB);
+        reg.modify_four_4_bytes(|r, w| {
+            assert_eq!(r.range_6(), 0xAB);
+            w.values_0(Values0::High)
+                .values_1(Divider::Div2)
+                .range_5(0x12)
+        });
+    }
+    assert_eq!(
+        reg.four_4_bytes.read(),
+        0xAB00_0000 | (0x12 << 16) | (1 << 4) | 3
+    );
+
+    unsafe {
+        reg.modify_first_4_bytes(|_, w| w.bit_0(true).bit_31(true));
+        reg.modify_first_4_bytes(|r, w| w.bit_0(false).bit_1(r.bit_31()));
+    }
+    assert_eq!(reg.first_4_bytes.read(), (1 << 31) | (1 << 1));
+}
+
+#[test]
+fn test_read_all() {
+    #[allow(unused)]
+    let mut fake_device_storage = [0u32; 4];
+    #[allow(unused)]
+    let mut reg = Registers::new(fake_device_storage.as_mut() as *mut [u32] as *mut u32 as usize);
+
+    unsafe {
+        reg.set_range_6(0xAB);
+        reg.set_values_0(Values0::High);
+    }
+
+    let four = reg.read_all_four_4_bytes();
+    assert_eq!(four.range_6(), 0xAB);
+    assert_eq!(four.values_0(), Values0::High);
+    assert_eq!(four.bits(), reg.four_4_bytes.read());
+}
+
+#[test]
+fn test_raw_access() {
+    #[allow(unused)]
+    let mut fake_device_storage = [0u32; 4];
+    #[allow(unused)]
+    let mut reg = Registers::new(fake_device_storage.as_mut() as *mut [u32] as *mut u32 as usize);
+
+    assert_eq!(reg.base_address(), fake_device_storage.as_ptr() as usize);
+
+    unsafe {
+        reg.write_raw(SECOND_4_BYTES, 0xDEAD_BEEF);
+        reg.set_range_6(0xAB);
+        assert_eq!(reg.read_raw(SECOND_4_BYTES), 0xDEAD_BEEF);
+        assert_eq!(reg.read_raw(FOUR_4_BYTES), 0xAB00_0000);
+        assert_eq!(*reg.as_ptr(SECOND_4_BYTES), 0xDEAD_BEEF);
+    }
+}
+
+#[test]
+fn test_layout_consts() {
+    assert_eq!(Registers::BIT_31_OFFSET, FIRST_4_BYTES);
+    assert_eq!(Registers::BIT_31_BIT_MASK, 1 << 31);
+    assert_eq!(Registers::RANGE_6_OFFSET, FOUR_4_BYTES);
+    assert_eq!(Registers::RANGE_6_BIT_MASK, 0xFF00_0000);
+    assert_eq!(Instance::<0x4000_0000>::RANGE_6_ADDRESS, 0x4000_000C);
+}
+
+#[test]
+fn test_instance_is_zero_sized() {
+    assert_eq!(core::mem::size_of::<Instance<0x4000_0000>>(), 0);
+    assert_eq!(Registers::RANGE_3_BIT_MASK, 0xFF);
+}
+
+#[test]
+fn test_snapshot() {
+    #[allow(unused)]
+    let mut fake_device_storage = [0u32; 4];
+    #[allow(unused)]
+    let mut reg = Registers::new(fake_device_storage.as_mut() as *mut [u32] as *mut u32 as usize);
+
+    unsafe {
+        reg.set_bit_3(true);
+        reg.set_range_0(0xDEAD_BEEF);
+        reg.set_range_5(0x42);
+        reg.set_values_1(Divider::Div8);
+    }
+
+    let snapshot = reg.snapshot();
+    assert!(snapshot.bit_3);
+    assert!(!snapshot.bit_4);
+    assert_eq!(snapshot.range_0, 0xDEAD_BEEF);
+    assert_eq!(snapshot.range_5, 0x42);
+    assert_eq!(snapshot.values_1, Some(Divider::Div8));
+    assert_eq!(snapshot, reg.snapshot());
+}
+
 #[test]
 fn test_making_device() {
     #[allow(unused)]
@@ -338,3 +487,289 @@ single_bit_test!(28, test_bit_28, set_bit_28, get_bit_28);
 single_bit_test!(29, test_bit_29, set_bit_29, get_bit_29);
 single_bit_test!(30, test_bit_30, set_bit_30, get_bit_30);
 single_bit_test!(31, test_bit_31, set_bit_31, get_bit_31);
+
+mod array_test {
+    use hal_macros::{VolatileRead, RW};
+    use hal_macros_derive::make_device;
+
+    const CHANNEL_BLOCK: usize = 0x0;
+    const INSTANCE_BLOCK: usize = 0x4;
+    const PIN_MODE: usize = 0x18;
+    const PIN_FLAGS: usize = 0x1C;
+
+    make_device! {
+        device_ports();
+
+        #[bit(0..=7, RW, CHANNEL_BLOCK)]
+        #[array(count = 3, stride = 0x8)]
+        channel_config,
+
+        #[bit(31, RW, CHANNEL_BLOCK)]
+        #[array(count = 3, stride = 0x8)]
+        channel_enable,
+
+        #[bit(0..=15, RW, INSTANCE_BLOCK)]
+        #[array(offsets = [0x0, 0x8, 0x10])]
+        instance_value,
+
+        #[bit(0..=1, RW, PIN_MODE)]
+        #[array(count = 16, bit_stride = 2)]
+        #[values(Off = 0, Rising = 1, Falling = 2, Both = 3)]
+        pin_mode,
+
+        #[bit(0, RW1C, PIN_FLAGS)]
+        #[array(count = 32, bit_stride = 1)]
+        pin_flag,
+    }
+
+    fn fake_registers(storage: &mut [u32; 8]) -> Registers {
+        Registers::new(storage.as_mut() as *mut [u32] as *mut u32 as usize)
+    }
+
+    #[test]
+    fn test_register_array() {
+        let mut storage = [0u32; 8];
+        let mut reg = fake_registers(&mut storage);
+
+        for index in 0..Registers::CHANNEL_CONFIG_COUNT {
+            unsafe {
+                reg.set_channel_config(index, index as u8 + 1);
+                reg.set_channel_enable(index, index != 1);
+            }
+        }
+
+        for index in 0..Registers::CHANNEL_CONFIG_COUNT {
+            assert_eq!(reg.get_channel_config(index), index as u8 + 1);
+            assert_eq!(reg.get_channel_enable(index), index != 1);
+        }
+
+        assert_eq!(storage[0], 0x8000_0001);
+        assert_eq!(storage[2], 0x0000_0002);
+        assert_eq!(storage[4], 0x8000_0003);
+    }
+
+    #[test]
+    fn test_register_array_with_holes() {
+        let mut storage = [0u32; 8];
+        let mut reg = fake_registers(&mut storage);
+
+        unsafe {
+            reg.set_instance_value(0, 0x1111);
+            reg.set_instance_value(1, 0x2222);
+            reg.set_instance_value(2, 0x3333);
+        }
+
+        assert_eq!(reg.get_instance_value(2), 0x3333);
+        assert_eq!(storage, [0, 0x1111, 0, 0x2222, 0, 0x3333, 0, 0]);
+    }
+
+    #[test]
+    fn test_bit_array() {
+        let mut storage = [0u32; 8];
+        let mut reg = fake_registers(&mut storage);
+
+        unsafe {
+            reg.set_pin_mode(0, PinMode::Rising);
+            reg.set_pin_mode(15, PinMode::Both);
+            reg.modify_pin_mode(|_, w| w.pin_mode(1, PinMode::Falling));
+        }
+
+        assert_eq!(reg.get_pin_mode(0), PinMode::Rising);
+        assert_eq!(reg.get_pin_mode(1), PinMode::Falling);
+        assert_eq!(reg.get_pin_mode(2), PinMode::Off);
+        assert_eq!(reg.pin_mode.read(), (0b11 << 30) | (0b10 << 2) | 0b01);
+    }
+
+    #[test]
+    fn test_bit_array_write_one_to_clear() {
+        let mut storage = [0u32; 8];
+        let mut reg = fake_registers(&mut storage);
+
+        unsafe { reg.clear_pin_flag(5) };
+        assert!(reg.is_pin_flag_active(5));
+        assert_eq!(reg.pin_flags.read(), 1 << 5);
+
+        // Since the fake registers are only memory, the flags don't actually
+        // clear. Clearing another flag must not write back a 1 to any other flag.
+        unsafe { reg.clear_pin_flag(6) };
+        assert_eq!(reg.pin_flags.read(), 1 << 6);
+    }
+
+    #[test]
+    fn test_array_snapshot() {
+        let mut storage = [0u32; 8];
+        let mut reg = fake_registers(&mut storage);
+
+        unsafe {
+            reg.set_channel_config(2, 0x7F);
+            reg.set_pin_mode(3, PinMode::Both);
+        }
+
+        let snapshot = reg.snapshot();
+        assert_eq!(snapshot.channel_config, [0, 0, 0x7F]);
+        assert_eq!(snapshot.pin_mode[3], PinMode::Both);
+        assert!(!snapshot.is_pin_flag_active[0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_array_index_out_of_range() {
+        let mut storage = [0u32; 8];
+        let reg = fake_registers(&mut storage);
+
+        reg.get_channel_config(Registers::CHANNEL_CONFIG_COUNT);
+    }
+}
+
+mod access_test {
+    use hal_macros::{VolatileRead, VolatileWrite, RW};
+    use hal_macros_derive::make_device;
+
+    const CONTROL: usize = 0x0;
+    const STATUS: usize = 0x4;
+
+    make_device! {
+        device_ports();
+
+        #[bit(0..=7, RW, CONTROL)]
+        control_value,
+
+        #[bit(8, RW1S, CONTROL)]
+        set_pending,
+
+        #[bit(9, W1O, CONTROL)]
+        start,
+
+        #[bit(16..=19, W1O, CONTROL)]
+        flush,
+
+        #[bit(0, RC, STATUS)]
+        overrun,
+
+        #[bit(8..=15, RC, STATUS)]
+        error_count,
+    }
+
+    fn fake_registers(storage: &mut [u32; 2]) -> Registers {
+        Registers::new(storage.as_mut() as *mut [u32] as *mut u32 as usize)
+    }
+
+    #[test]
+    fn test_write_one_fields_are_not_written_back() {
+        let mut storage = [0u32; 2];
+        let mut reg = fake_registers(&mut storage);
+
+        unsafe { reg.set_set_pending() };
+        assert!(reg.is_set_pending_set());
+
+        // Memory doesn't behave like the hardware, so the set bit is still
+        // there. Writing another field must not write it back as a 1.
+        unsafe { reg.set_control_value(0x5A) };
+        assert_eq!(reg.control.read(), 0x5A);
+
+        unsafe { reg.trigger_start() };
+        assert_eq!(reg.control.read(), 0x5A | (1 << 9));
+
+        unsafe { reg.trigger_flush(0xF) };
+        assert_eq!(reg.control.read(), 0x5A | (0xF << 16));
+
+        unsafe { reg.modify_control(|_, w| w.set_set_pending().control_value(0x01)) };
+        assert_eq!(reg.control.read(), 0x01 | (1 << 8));
+    }
+
+    #[test]
+    fn test_read_to_clear() {
+        let mut storage = [0u32; 2];
+        let mut reg = fake_registers(&mut storage);
+
+        reg.status.write((0x12 << 8) | 1);
+        assert!(reg.take_overrun());
+        assert_eq!(reg.take_error_count(), 0x12);
+    }
+}
+
+mod instances_test {
+    use hal_macros::RW;
+    use hal_macros_derive::make_device;
+
+    const PORT_A: usize = 0x4000_0000;
+    const PORT_B: usize = 0x4000_1000;
+
+    const CONTROL: usize = 0x0;
+
+    make_device! {
+        device_ports(PORT_A, PORT_B);
+
+        #[bit(0..=7, RW, CONTROL)]
+        shared_value,
+
+        #[bit(8, RW, CONTROL)]
+        #[instances(PORT_A)]
+        only_a,
+    }
+
+    #[test]
+    fn test_has_field() {
+        assert!(Instance::<PORT_A>::instance().has_only_a());
+        assert!(!Instance::<PORT_B>::instance().has_only_a());
+    }
+
+    #[test]
+    fn test_snapshot_missing_field() {
+        #[allow(unused)]
+        let mut fake_device_storage = [0u32; 1];
+        #[allow(unused)]
+        let mut reg =
+            Registers::new(fake_device_storage.as_mut() as *mut [u32] as *mut u32 as usize);
+
+        unsafe { reg.set_shared_value(0x42) };
+        let snapshot = reg.snapshot();
+
+        assert_eq!(snapshot.shared_value, 0x42);
+        assert_eq!(snapshot.only_a, None);
+    }
+}
+
+#[cfg(feature = "trace-mmio")]
+mod trace_test {
+    extern crate std;
+
+    use super::*;
+    use crate::trace::{self, AccessKind, Event};
+    use core::cell::RefCell;
+    use std::vec::Vec;
+
+    std::thread_local! {
+        static EVENTS: RefCell<Vec<Event>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn record(event: &Event) {
+        EVENTS.with(|events| events.borrow_mut().push(*event));
+    }
+
+    #[test]
+    fn test_trace_field_names() {
+        #[allow(unused)]
+        let mut fake_device_storage = [0u32; 4];
+        #[allow(unused)]
+        let mut reg =
+            Registers::new(fake_device_storage.as_mut() as *mut [u32] as *mut u32 as usize);
+
+        trace::set_hook(Some(record));
+        unsafe { reg.set_range_6(0xAB) };
+        let _ = reg.get_range_6();
+        trace::set_hook(None);
+
+        let events = EVENTS.with(|events| events.take());
+        let address = reg.base_address() + FOUR_4_BYTES;
+
+        assert_eq!(events.len(), 3);
+        assert!(events.iter().all(|event| event.address == address));
+        assert!(events.iter().all(|event| event.field == Some("range_6")));
+        assert_eq!(
+            events.iter().map(|event| event.kind).collect::<Vec<_>>(),
+            [AccessKind::Read, AccessKind::Write, AccessKind::Read]
+        );
+        assert_eq!(events[1].value, 0xAB00_0000);
+    }
+}
@@ -0,0 +1,43 @@
+//! # Time
+//! The time and frequency types every API in this crate takes, from `fugit`. Using
+//! these instead of bare integers keeps the unit in the type, so a period can not be
+//! passed where a frequency is expected, and milliseconds can not be mixed up with
+//! ticks.
+//!
+//! # Example
+//! ```
+//! use max78000_hal::time::{ExtU32, Hertz, Micros, RateExtU32};
+//!
+//! const BUS_CLOCK: Hertz = Hertz::kHz(400);
+//! let frame_rate: Hertz = 200.Hz();
+//! let timeout: Micros = 5.millis();
+//!
+//! assert_eq!(BUS_CLOCK.to_Hz(), 400_000);
+//! assert_eq!(frame_rate.into_duration::<1, 1_000_000>(), Micros::micros(5_000));
+//! assert_eq!(timeout.to_micros(), 5_000);
+//! ```
+pub use fugit::{ExtU32, ExtU64, RateExtU32};
+
+/// # Hertz
+/// A frequency, ie. a clock or a baud rate.
+pub type Hertz = fugit::HertzU32;
+
+/// # Micros
+/// A duration with microsecond resolution, ie. a delay or a timeout.
+pub type Micros = fugit::MicrosDurationU32;
+
+/// # Millis
+/// A duration with millisecond resolution, ie. a long period.
+pub type Millis = fugit::MillisDurationU32;
+
+/// # Nanos
+/// A duration with nanosecond resolution, ie. a measurement from `profiling`.
+pub type Nanos = fugit::NanosDurationU64;
+
+/// # Micros 64
+/// A duration with microsecond resolution that does not wrap, ie. a uptime.
+pub type Micros64 = fugit::MicrosDurationU64;
+
+/// # Instant
+/// A point in time with microsecond resolution, ie. from `timer::Monotonic::now`.
+pub type Instant = fugit::TimerInstantU64<1_000_000>;
@@ -0,0 +1,235 @@
+//! # Timer Callbacks
+//! Run a function on every timer interrupt event, without writing the interrupt handler.
+//! `InterruptHandler` checks which halves of the timer interrupted, clears their flags,
+//! and calls the function registered for each half. Register one with
+//! `Timer::set_callback`, or `TimerA::set_callback`/`TimerB::set_callback` for the
+//! halves of a split timer.
+//!
+//! Bind `InterruptHandler` to the interrupts of the timers you register callbacks on,
+//! the NVIC line is enabled by the first registration on that timer.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::bind_interrupts;
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::time::ExtU32;
+//! use max78000_hal::timer::callback;
+//! use max78000_hal::timer::registers::TimerPrescaler;
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//! # let timer = Timer::new(TimerInstance::<{ mmio::TIMER_0 }>::instance())?;
+//!
+//! bind_interrupts!(struct Irqs {
+//!     TMR0 => callback::InterruptHandler;
+//! });
+//!
+//! fn blink() {
+//!     // ...
+//! }
+//!
+//! let mut timer = timer.into_continuous(TimerPrescaler::Div1024, 500.millis())?;
+//! timer.set_callback(Some(blink))?;
+//! timer.start();
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::{is_low_power, Timer, TimerInstance};
+use crate::error::{ErrorKind, Result};
+use crate::interrupt::typelevel::{Handler, TMR0, TMR1, TMR2, TMR3, TMR4, TMR5};
+use crate::interrupt::{Interrupt, InterruptExt};
+use crate::memory_map::mmio;
+
+/// # Half
+/// A half of a timer, `A` is also the whole timer when it is not split.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Half {
+    A = 0,
+    B = 1,
+}
+
+/// The callback of a timer half, if it has one.
+type Slot = Option<fn()>;
+
+/// The callback of both halves of every timer, the low power timers only use `A`. A
+/// slot is only written while its half's interrupt is disabled, and only read by the
+/// interrupt handler while it is enabled.
+static mut CALLBACKS: [[Slot; 2]; 6] = [[None; 2]; 6];
+
+/// # Interrupt Handler
+/// Clears the interrupt flags of the timer, and calls the callbacks of the halves that
+/// interrupted. Bind it to `TMR0` to `TMR5`.
+pub struct InterruptHandler;
+
+impl Handler<TMR0> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch::<{ mmio::TIMER_0 }>(0);
+    }
+}
+
+impl Handler<TMR1> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch::<{ mmio::TIMER_1 }>(1);
+    }
+}
+
+impl Handler<TMR2> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch::<{ mmio::TIMER_2 }>(2);
+    }
+}
+
+impl Handler<TMR3> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch::<{ mmio::TIMER_3 }>(3);
+    }
+}
+
+impl Handler<TMR4> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch_low_power::<{ mmio::LOW_POWER_TIMER_0 }>(4);
+    }
+}
+
+impl Handler<TMR5> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch_low_power::<{ mmio::LOW_POWER_TIMER_1 }>(5);
+    }
+}
+
+/// Clear and call back both halves of the timer at `PORT` that interrupted.
+unsafe fn dispatch<const PORT: usize>(index: usize) {
+    let mut timer = TimerInstance::<PORT>::instance();
+
+    if timer.get_timera_interrupt_enable() && timer.is_timera_interrupt_event_active() {
+        timer.clear_timera_interrupt_event();
+        if let Some(callback) = CALLBACKS[index][Half::A as usize] {
+            callback();
+        }
+    }
+
+    if timer.get_timerb_interrupt_enable() && timer.is_timerb_interrupt_event_active() {
+        timer.clear_timerb_interrupt_event();
+        if let Some(callback) = CALLBACKS[index][Half::B as usize] {
+            callback();
+        }
+    }
+}
+
+/// Clear and call back the low power timer at `PORT`, which has no TimerB.
+unsafe fn dispatch_low_power<const PORT: usize>(index: usize) {
+    let mut timer = TimerInstance::<PORT>::instance();
+
+    if timer.get_timera_interrupt_enable() && timer.is_timera_interrupt_event_active() {
+        timer.clear_timera_interrupt_event();
+        if let Some(callback) = CALLBACKS[index][Half::A as usize] {
+            callback();
+        }
+    }
+}
+
+/// # Timer Interrupt
+/// The index (`0` to `5`) and NVIC line of the timer at `port`.
+pub(crate) fn timer_interrupt(port: usize) -> Option<(usize, Interrupt)> {
+    match port {
+        mmio::TIMER_0 => Some((0, Interrupt::TMR0)),
+        mmio::TIMER_1 => Some((1, Interrupt::TMR1)),
+        mmio::TIMER_2 => Some((2, Interrupt::TMR2)),
+        mmio::TIMER_3 => Some((3, Interrupt::TMR3)),
+        mmio::LOW_POWER_TIMER_0 => Some((4, Interrupt::TMR4)),
+        mmio::LOW_POWER_TIMER_1 => Some((5, Interrupt::TMR5)),
+        _ => None,
+    }
+}
+
+/// # Store
+/// Put `callback` in the slot of `half` of the timer at `port`, and enable the
+/// timer's NVIC line when there is one.
+///
+/// # Safety
+/// The interrupt of `half` must be disabled.
+///
+/// # Errors
+/// `ErrorKind::NoDevice` when `half` is `B` of a low power timer.
+pub(crate) unsafe fn store(port: usize, half: Half, callback: Option<fn()>) -> Result<()> {
+    let (index, interrupt) = timer_interrupt(port).ok_or(ErrorKind::NoDevice)?;
+    if is_low_power(port) && half == Half::B {
+        return Err(ErrorKind::NoDevice);
+    }
+
+    CALLBACKS[index][half as usize] = callback;
+    if callback.is_some() {
+        interrupt.enable();
+    }
+    Ok(())
+}
+
+impl<const PORT: usize, MODE> Timer<PORT, MODE> {
+    /// # Set Callback
+    /// Call `callback` from `InterruptHandler` every time the count reaches the compare
+    /// value, enabling the timer's interrupt. `None` disables the interrupt.
+    ///
+    /// # Errors
+    /// `ErrorKind::NoDevice` when `PORT` is not a timer.
+    pub fn set_callback(&mut self, callback: Option<fn()>) -> Result<()> {
+        self.enable_interrupt(false);
+        unsafe { store(PORT, Half::A, callback)? };
+        self.enable_interrupt(callback.is_some());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::super::test_timer;
+    use super::*;
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    static CALLS: AtomicU32 = AtomicU32::new(0);
+
+    fn count_call() {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    static LOW_POWER_CALLS: AtomicU32 = AtomicU32::new(0);
+
+    fn count_low_power_call() {
+        LOW_POWER_CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn dispatches_enabled_halves() {
+        let mut timer: Timer<{ mmio::TIMER_1 }> = test_timer();
+        let intfl = mmio::TIMER_1 + 0x0C;
+        timer.set_callback(Some(count_call)).unwrap();
+        assert!(timer.registers.get_timera_interrupt_enable());
+        assert!(Interrupt::TMR1.is_enabled());
+
+        crate::mock::write(intfl, 0);
+        unsafe { <InterruptHandler as Handler<TMR1>>::on_interrupt() };
+        assert_eq!(CALLS.load(Ordering::Relaxed), 0);
+
+        // TimerB interrupted, but has no interrupt enabled
+        crate::mock::write(intfl, (1 << 16) | 1);
+        unsafe { <InterruptHandler as Handler<TMR1>>::on_interrupt() };
+        assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+
+        timer.set_callback(None).unwrap();
+        assert!(!timer.registers.get_timera_interrupt_enable());
+        unsafe { <InterruptHandler as Handler<TMR1>>::on_interrupt() };
+        assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn low_power_timers_have_one_callback() {
+        let mut timer: Timer<{ mmio::LOW_POWER_TIMER_1 }> = test_timer();
+        timer.set_callback(Some(count_low_power_call)).unwrap();
+        assert!(Interrupt::TMR5.is_enabled());
+
+        crate::mock::write(mmio::LOW_POWER_TIMER_1 + 0x0C, 1);
+        unsafe { <InterruptHandler as Handler<TMR5>>::on_interrupt() };
+        assert_eq!(LOW_POWER_CALLS.load(Ordering::Relaxed), 1);
+
+        assert_eq!(
+            unsafe { store(mmio::LOW_POWER_TIMER_0, Half::B, None) },
+            Err(ErrorKind::NoDevice)
+        );
+    }
+}
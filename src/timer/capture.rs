@@ -0,0 +1,269 @@
+//! # Input Capture
+//! Measure the frequency or pulse width of a signal on a timer input pin. In capture
+//! mode the timer counts freely, and every selected edge of the input copies the count
+//! into the PWM register and sets the timer's interrupt flag. The time between two
+//! captures is the period of the signal.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::timer::capture::Edge;
+//! use max78000_hal::timer::registers::TimerPrescaler;
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//!
+//! let timer = Timer::new(TimerInstance::<{ mmio::TIMER_1 }>::instance())?;
+//! let mut capture = timer.into_capture(TimerPrescaler::Div1, Edge::Rising);
+//! capture.start();
+//!
+//! let frequency = capture.measure_frequency(1_000_000)?;
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::registers::{TimerMode, TimerPrescaler};
+use super::{mode, Timer};
+use crate::error::{ErrorKind, Result};
+use crate::time::{Hertz, Micros};
+
+/// # Edge
+/// Which input edge captures the count.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Edge {
+    Rising,
+    Falling,
+}
+
+impl<const PORT: usize> Timer<PORT, mode::Disabled> {
+    /// # Into Capture
+    /// Capture the count of the peripheral clock divided by `prescaler` on every `edge`
+    /// of the timer input pin, see `InputCapture`. The input pin must be routed to the
+    /// timer.
+    pub fn into_capture(self, prescaler: TimerPrescaler, edge: Edge) -> InputCapture<PORT> {
+        let mut timer: Timer<PORT, mode::Capture> =
+            self.configure(TimerMode::Capture, prescaler, u32::MAX);
+
+        unsafe {
+            // Capture events come from the input pin
+            timer.registers.set_timera_event_capture_selection(0);
+        }
+
+        let mut capture = InputCapture {
+            timer,
+            last: None,
+            period: None,
+        };
+        capture.set_edge(edge);
+        capture
+    }
+}
+
+/// # Input Capture
+/// The timer at `PORT` in capture mode.
+pub struct InputCapture<const PORT: usize> {
+    timer: Timer<PORT, mode::Capture>,
+    last: Option<u32>,
+    period: Option<u32>,
+}
+
+impl<const PORT: usize> InputCapture<PORT> {
+    /// # Start
+    /// Start counting, and forget the last captures.
+    pub fn start(&mut self) {
+        self.last = None;
+        self.period = None;
+        self.timer.clear_interrupt();
+        self.timer.start();
+    }
+
+    /// # Stop
+    /// Stop counting.
+    pub fn stop(&mut self) {
+        self.timer.stop();
+    }
+
+    /// # Set Edge
+    /// Change which input edge captures the count.
+    pub fn set_edge(&mut self, edge: Edge) {
+        let falling = edge == Edge::Falling;
+        unsafe {
+            self.timer.registers.set_timera_polarity(falling);
+            self.timer
+                .registers
+                .set_timera_negative_edge_trigger_for_event(falling);
+        }
+    }
+
+    /// # Enable Interrupt
+    /// Enable/Disable the timer's interrupt on every capture, see `on_interrupt`.
+    pub fn enable_interrupt(&mut self, enable: bool) {
+        self.timer.enable_interrupt(enable);
+    }
+
+    /// # Try Capture
+    /// Take the captured count if there is a new one, updating the period.
+    pub fn try_capture(&mut self) -> Option<u32> {
+        if !self.timer.is_interrupt_pending() {
+            return None;
+        }
+
+        let count = self.timer.registers.get_pwm();
+        self.timer.clear_interrupt();
+
+        if let Some(last) = self.last {
+            self.period = Some(count.wrapping_sub(last));
+        }
+        self.last = Some(count);
+        Some(count)
+    }
+
+    /// # Capture
+    /// Block until the next capture, checking for it at most `polls` times.
+    ///
+    /// # Errors
+    /// `ErrorKind::TimeOut` when there was no capture in time.
+    pub fn capture(&mut self, polls: u32) -> Result<u32> {
+        for _ in 0..polls {
+            if let Some(count) = self.try_capture() {
+                return Ok(count);
+            }
+        }
+
+        Err(ErrorKind::TimeOut)
+    }
+
+    /// # On Interrupt
+    /// Take the captured count, see `try_capture`. Call this from the timer's interrupt
+    /// handler, and read the result with `period` or `frequency`.
+    pub fn on_interrupt(&mut self) -> Option<u32> {
+        self.try_capture()
+    }
+
+    /// # Tick Rate
+    /// The rate the captured counts advance at.
+    pub fn tick_rate(&self) -> Hertz {
+        self.timer.tick_rate()
+    }
+
+    /// # Period
+    /// The ticks between the last two captures.
+    pub fn period(&self) -> Option<u32> {
+        self.period
+    }
+
+    /// # Period Micros
+    /// The time between the last two captures.
+    pub fn period_micros(&self) -> Option<Micros> {
+        self.period
+            .map(|ticks| ticks_to_micros(ticks, self.timer.tick_rate()))
+    }
+
+    /// # Frequency
+    /// The frequency of the signal, from the last two captures.
+    pub fn frequency(&self) -> Option<Hertz> {
+        self.period
+            .and_then(|ticks| ticks_to_frequency(ticks, self.timer.tick_rate()))
+    }
+
+    /// # Measure Frequency
+    /// Block until two captures, and give the frequency of the signal between them.
+    ///
+    /// # Errors
+    /// `ErrorKind::TimeOut` when a capture was not there within `polls` checks.
+    pub fn measure_frequency(&mut self, polls: u32) -> Result<Hertz> {
+        self.last = None;
+        self.capture(polls)?;
+        self.capture(polls)?;
+
+        self.frequency().ok_or(ErrorKind::Overflow)
+    }
+
+    /// # Measure Pulse Width
+    /// Block until a pulse starting with `edge` ends, and give its length. The capture
+    /// edge is left at `edge`.
+    ///
+    /// # Errors
+    /// `ErrorKind::TimeOut` when an edge was not there within `polls` checks.
+    pub fn measure_pulse_width(&mut self, edge: Edge, polls: u32) -> Result<Micros> {
+        let end = match edge {
+            Edge::Rising => Edge::Falling,
+            Edge::Falling => Edge::Rising,
+        };
+
+        self.set_edge(edge);
+        self.timer.clear_interrupt();
+        let start = self.capture(polls)?;
+
+        self.set_edge(end);
+        let result = self.capture(polls);
+        self.set_edge(edge);
+
+        let ticks = result?.wrapping_sub(start);
+        Ok(ticks_to_micros(ticks, self.timer.tick_rate()))
+    }
+
+    /// # Free
+    /// Stop the timer, and give it back disabled.
+    pub fn free(self) -> Timer<PORT, mode::Disabled> {
+        self.timer.into_disabled()
+    }
+}
+
+/// # Ticks To Micros
+/// The length of `ticks` at `tick_rate`, saturating.
+fn ticks_to_micros(ticks: u32, tick_rate: Hertz) -> Micros {
+    let micros = ticks as u64 * 1_000_000 / tick_rate.raw().max(1) as u64;
+    Micros::micros(micros.min(u32::MAX as u64) as u32)
+}
+
+/// # Ticks To Frequency
+/// The frequency with a period of `ticks` at `tick_rate`, `None` for a zero period.
+fn ticks_to_frequency(ticks: u32, tick_rate: Hertz) -> Option<Hertz> {
+    match ticks {
+        0 => None,
+        ticks => Some(Hertz::from_raw(
+            ((tick_rate.raw() as u64 + ticks as u64 / 2) / ticks as u64) as u32,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        let rate = Hertz::MHz(50);
+        assert_eq!(ticks_to_micros(50, rate), Micros::micros(1));
+        assert_eq!(
+            ticks_to_micros(u32::MAX, Hertz::Hz(1)),
+            Micros::micros(u32::MAX)
+        );
+        assert_eq!(ticks_to_frequency(50_000, rate), Some(Hertz::kHz(1)));
+        assert_eq!(ticks_to_frequency(3, rate), Some(Hertz::Hz(16_666_667)));
+        assert_eq!(ticks_to_frequency(0, rate), None);
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn period_from_captures() {
+        use super::super::test_timer;
+        use crate::memory_map::mmio;
+        let timer: Timer<{ mmio::TIMER_0 }> = test_timer();
+        let mut capture = timer.into_capture(TimerPrescaler::Div1, Edge::Falling);
+        assert!(capture.timer.registers.get_timera_polarity());
+
+        let intfl = mmio::TIMER_0 + 0x0C;
+        let pwm = mmio::TIMER_0 + 0x08;
+        crate::mock::write(intfl, 0);
+        assert_eq!(capture.try_capture(), None);
+        assert_eq!(capture.capture(10), Err(ErrorKind::TimeOut));
+
+        // The mock keeps a cleared flag set, so every poll sees a capture
+        crate::mock::write(pwm, 0xFFFF_FF00);
+        crate::mock::write(intfl, 1);
+        assert_eq!(capture.on_interrupt(), Some(0xFFFF_FF00));
+        assert_eq!(capture.period(), None);
+
+        crate::mock::write(pwm, 0x0000_0100);
+        assert_eq!(capture.on_interrupt(), Some(0x100));
+        assert_eq!(capture.period(), Some(0x200));
+    }
+}
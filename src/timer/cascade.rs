@@ -0,0 +1,189 @@
+//! # Cascade Timer
+//! A timer's two 16-bit halves (TimerA, the low half, and TimerB, the high half)
+//! chained into one 32-bit timer. TimerA's settings drive the pair, and TimerB is kept
+//! disabled.
+//!
+//! The high half of the count is write protected by default, and a write to it takes a
+//! few clocks to land. Every write here lifts the protection first and then waits for
+//! both write done flags in `TMR_INTFL`. Reads go again until the high half is stable,
+//! so a carry between the halves never gives a torn count.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::timer::cascade::CascadeTimer32;
+//! use max78000_hal::timer::registers::TimerPrescaler;
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//!
+//! let timer = Timer::new(TimerInstance::<{ mmio::TIMER_0 }>::instance())?;
+//! let mut timer = CascadeTimer32::new(timer, TimerPrescaler::Div1, 100_000_000);
+//! timer.start();
+//! timer.wait();
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::registers::{TimerMode, TimerPrescaler};
+use super::{mode, Timer};
+
+/// # Cascade Timer 32
+/// The timer at `PORT` as one continuous 32-bit timer.
+pub struct CascadeTimer32<const PORT: usize> {
+    timer: Timer<PORT, mode::Continuous>,
+}
+
+impl<const PORT: usize> CascadeTimer32<PORT> {
+    /// # New
+    /// Chain both halves of `timer`, counting the peripheral clock divided by
+    /// `prescaler` up to `compare` over and over. The timer is not started.
+    pub fn new(
+        mut timer: Timer<PORT, mode::Disabled>,
+        prescaler: TimerPrescaler,
+        compare: u32,
+    ) -> Self {
+        unsafe {
+            timer.registers.set_timerb_enable(false);
+            timer.registers.set_bit32_cascade_timer_enable(true);
+        }
+
+        let mut cascade = Self {
+            timer: timer.configure(TimerMode::Continuous, prescaler, 0),
+        };
+
+        cascade.set_compare(compare);
+        cascade.set_count(1);
+        cascade
+    }
+
+    /// # Start
+    /// Start counting.
+    pub fn start(&mut self) {
+        unsafe { self.timer.registers.set_timera_enable(true) };
+    }
+
+    /// # Stop
+    /// Stop counting, keeping the count where it is.
+    pub fn stop(&mut self) {
+        self.timer.stop();
+    }
+
+    /// # Is Running
+    /// Check if the timer is counting.
+    pub fn is_running(&self) -> bool {
+        self.timer.is_running()
+    }
+
+    /// # Count
+    /// The 32-bit count, read again if the high half changed while reading.
+    pub fn count(&self) -> u32 {
+        loop {
+            let first = self.timer.count();
+            let second = self.timer.count();
+            if first >> 16 == second >> 16 {
+                return second;
+            }
+        }
+    }
+
+    /// # Set Count
+    /// Write the 32-bit count, waiting until both halves took it.
+    pub fn set_count(&mut self, count: u32) {
+        self.unprotect();
+        unsafe { self.timer.registers.set_timer_count(count) };
+        self.wait_write_done();
+    }
+
+    /// # Compare
+    /// The 32-bit compare value.
+    pub fn compare(&self) -> u32 {
+        self.timer.compare()
+    }
+
+    /// # Set Compare
+    /// Write the 32-bit compare value.
+    pub fn set_compare(&mut self, compare: u32) {
+        self.unprotect();
+        unsafe { self.timer.registers.set_timer_compare_value(compare) };
+        self.wait_write_done();
+    }
+
+    /// # Enable Interrupt
+    /// Enable/Disable the interrupt when the count reaches the compare value.
+    pub fn enable_interrupt(&mut self, enable: bool) {
+        self.timer.enable_interrupt(enable);
+    }
+
+    /// # Is Interrupt Pending
+    /// Check if the count reached the compare value since the flag was last cleared.
+    pub fn is_interrupt_pending(&self) -> bool {
+        self.timer.is_interrupt_pending()
+    }
+
+    /// # Clear Interrupt
+    /// Clear the flag of the count reaching the compare value.
+    pub fn clear_interrupt(&mut self) {
+        self.timer.clear_interrupt();
+    }
+
+    /// # Wait
+    /// Block until the count reaches the compare value, and clear the flag.
+    pub fn wait(&mut self) {
+        while !self.is_interrupt_pending() {}
+        self.clear_interrupt();
+    }
+
+    /// # Free
+    /// Stop the timer, split the halves again, and give it back disabled.
+    pub fn free(self) -> Timer<PORT, mode::Disabled> {
+        let mut timer = self.timer.into_disabled();
+        unsafe { timer.registers.set_bit32_cascade_timer_enable(false) };
+        timer
+    }
+
+    /// # Unprotect
+    /// Let the next write reach the high half, the hardware protects it by default.
+    fn unprotect(&mut self) {
+        unsafe {
+            self.timer
+                .registers
+                .set_timerb_write_protect_in_dual_timer_mode(true)
+        };
+    }
+
+    /// # Wait Write Done
+    /// Block until both halves took the last write.
+    fn wait_write_done(&self) {
+        while !(self.timer.registers.get_timera_write_done()
+            && self.timer.registers.get_timerb_write_done())
+        {}
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::super::{test_timer, CTRL1_OFFSET};
+    use super::*;
+    use crate::memory_map::mmio;
+
+    #[test]
+    fn chains_halves() {
+        let timer: Timer<{ mmio::TIMER_3 }> = test_timer();
+        let ctrl1 = mmio::TIMER_3 + CTRL1_OFFSET;
+        let intfl = mmio::TIMER_3 + 0x0C;
+
+        // Every write is done
+        crate::mock::write(intfl, (1 << 25) | (1 << 8));
+        crate::mock::write(mmio::TIMER_3 + 0x10, 1 << 31);
+
+        let mut cascade = CascadeTimer32::new(timer, TimerPrescaler::Div4, 0x0012_3456);
+        assert_ne!(crate::mock::read(ctrl1) & (1 << 31), 0);
+        assert!(!cascade.timer.registers.get_timerb_enable());
+        assert_eq!(cascade.compare(), 0x0012_3456);
+        assert_eq!(cascade.count(), 1);
+
+        cascade.set_count(0x0001_FFFF);
+        assert_eq!(cascade.count(), 0x0001_FFFF);
+
+        let timer = cascade.free();
+        assert_eq!(crate::mock::read(ctrl1) & (1 << 31), 0);
+        assert!(!timer.registers.get_timera_enable());
+    }
+}
@@ -0,0 +1,306 @@
+//! # Timer Clock
+//! Pick the clock source and prescaler of a timer from the tick period you want. The
+//! timers can count the peripheral clock (PCLK), or one of the oscillators directly,
+//! which keeps a slow timebase running accurately when PCLK changes.
+//!
+//! The low power timers (TMR4 and TMR5) can only count the IBRO, ERTCO or INRO, which
+//! keep running in the low power modes. `low_power_tick_config` picks from those.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::time::Nanos;
+//! use max78000_hal::timer::clock;
+//! use max78000_hal::timer::registers::TimerPrescaler;
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//! # let timer = Timer::new(TimerInstance::<{ mmio::TIMER_0 }>::instance())?;
+//!
+//! // 1 ms ticks
+//! let config = clock::tick_config(Nanos::millis(1))?;
+//! let mut timer = timer.into_continuous_ticks(TimerPrescaler::Div1, 1_000);
+//! timer.set_clock(config)?;
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::registers::TimerPrescaler;
+use super::{is_low_power, Timer};
+use crate::error::{ErrorKind, Result};
+use crate::time::{Hertz, Nanos};
+
+/// # Clock Source
+/// What a timer counts, see Page 319-321, Table 19-15.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClockSource {
+    /// The peripheral clock, not on the low power timers.
+    PCLK,
+    /// The internal secondary oscillator, 60 MHz, not on the low power timers.
+    ISO,
+    /// The internal baud rate oscillator, 7.3728 MHz.
+    IBRO,
+    /// The external 32.768 kHz RTC oscillator, which must be running.
+    ERTCO,
+    /// The internal nano-ring oscillator, about 8 kHz, only on the low power timers.
+    INRO,
+}
+
+impl ClockSource {
+    /// The sources of TMR0 to TMR3, in the order of their `TMR_CTRL1` bits.
+    const STANDARD: [ClockSource; 4] = [
+        ClockSource::PCLK,
+        ClockSource::ISO,
+        ClockSource::IBRO,
+        ClockSource::ERTCO,
+    ];
+
+    /// The sources of TMR4 and TMR5, in the order of their `TMR_CTRL1` bits.
+    const LOW_POWER: [ClockSource; 3] = [ClockSource::IBRO, ClockSource::ERTCO, ClockSource::INRO];
+
+    /// # From Bits
+    /// The clock source of TMR0 to TMR3 selected by `bits`.
+    pub fn from_bits(bits: u8) -> Self {
+        Self::STANDARD[(bits & 0b11) as usize]
+    }
+
+    /// # From Low Power Bits
+    /// The clock source of TMR4 or TMR5 selected by `bits`, `None` for the reserved
+    /// selection.
+    pub fn from_low_power_bits(bits: u8) -> Option<Self> {
+        Self::LOW_POWER.get((bits & 0b11) as usize).copied()
+    }
+
+    /// # Bits
+    /// The `TMR_CTRL1` clock selection of the source on the timer at `port`, `None`
+    /// when the timer cannot count it.
+    fn bits(self, port: usize) -> Option<u8> {
+        let sources: &[ClockSource] = match is_low_power(port) {
+            true => &Self::LOW_POWER,
+            false => &Self::STANDARD,
+        };
+        sources
+            .iter()
+            .position(|&source| source == self)
+            .map(|bits| bits as u8)
+    }
+
+    /// # Frequency
+    /// How fast the source runs.
+    pub fn frequency(self) -> Hertz {
+        match self {
+            ClockSource::PCLK => crate::core_peripheral_clock(),
+            ClockSource::ISO => Hertz::MHz(60),
+            ClockSource::IBRO => Hertz::Hz(7_372_800),
+            ClockSource::ERTCO => Hertz::Hz(32_768),
+            ClockSource::INRO => Hertz::Hz(8_000),
+        }
+    }
+}
+
+/// # Clock Config
+/// A clock source and prescaler, and the tick period they give.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ClockConfig {
+    /// What the timer counts.
+    pub source: ClockSource,
+    /// How much the source is divided down.
+    pub prescaler: TimerPrescaler,
+    /// The tick period, rounded to the nearest nanosecond.
+    pub period: Nanos,
+    /// How far the tick period is off from the one asked for, in parts per million.
+    pub error_ppm: u32,
+}
+
+/// # Tick Config
+/// The clock source and prescaler with a tick period closest to `period`. On a tie
+/// the source listed first in `ClockSource` wins.
+///
+/// # Errors
+/// `ErrorKind::BadParam` when `period` is zero.
+pub fn tick_config(period: Nanos) -> Result<ClockConfig> {
+    best_config(
+        period,
+        &ClockSource::STANDARD.map(|source| (source, source.frequency())),
+    )
+}
+
+/// # Low Power Tick Config
+/// Like `tick_config`, for the low power timers. The INRO is not trimmed, so expect it
+/// to be off by more than `error_ppm`.
+///
+/// # Errors
+/// `ErrorKind::BadParam` when `period` is zero.
+pub fn low_power_tick_config(period: Nanos) -> Result<ClockConfig> {
+    best_config(
+        period,
+        &ClockSource::LOW_POWER.map(|source| (source, source.frequency())),
+    )
+}
+
+/// # Best Config
+/// `tick_config` from the given source frequencies.
+fn best_config(period: Nanos, sources: &[(ClockSource, Hertz)]) -> Result<ClockConfig> {
+    if period.ticks() == 0 {
+        return Err(ErrorKind::BadParam);
+    }
+
+    sources
+        .iter()
+        .flat_map(|&(source, frequency)| {
+            (0..=12).filter_map(move |shift| config(period, source, frequency, shift))
+        })
+        .min_by_key(|config| config.error_ppm)
+        .ok_or(ErrorKind::BadParam)
+}
+
+/// # Config
+/// The config of `source` running at `frequency` divided by `1 << shift`, compared to
+/// `period`.
+fn config(period: Nanos, source: ClockSource, frequency: Hertz, shift: u8) -> Option<ClockConfig> {
+    let frequency = frequency.raw() as u128;
+    if frequency == 0 {
+        return None;
+    }
+
+    // Both in nanoseconds times the frequency
+    let achieved = (1u128 << shift) * 1_000_000_000;
+    let target = period.ticks() as u128 * frequency;
+    let error_ppm = achieved.abs_diff(target) * 1_000_000 / target;
+
+    Some(ClockConfig {
+        source,
+        prescaler: TimerPrescaler::from_bits(shift)?,
+        period: Nanos::nanos(((achieved + frequency / 2) / frequency) as u64),
+        error_ppm: error_ppm.min(u32::MAX as u128) as u32,
+    })
+}
+
+impl<const PORT: usize, MODE> Timer<PORT, MODE> {
+    /// # Set Clock
+    /// Count `config`'s source divided by its prescaler. The clock is switched with the
+    /// `TMR_CTRL1` clock enable/ready handshake. Changing the timer's mode goes back to
+    /// PCLK, so set the clock after it.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadState` when the timer is running.
+    /// - `ErrorKind::NotSupported` when the timer cannot count `config`'s source.
+    pub fn set_clock(&mut self, config: ClockConfig) -> Result<()> {
+        if self.is_running() {
+            return Err(ErrorKind::BadState);
+        }
+        let bits = config.source.bits(PORT).ok_or(ErrorKind::NotSupported)?;
+
+        unsafe {
+            self.registers.set_timera_clock_enable2(false);
+            self.registers.set_timera_clock_source(bits);
+            self.registers.set_timera_prescaler_select(config.prescaler);
+            self.registers.set_timera_clock_enable2(true);
+        }
+        while !self.registers.get_timera_clock_ready() {}
+
+        self.prescaler = config.prescaler;
+        Ok(())
+    }
+
+    /// # Clock Source
+    /// What the timer counts.
+    pub fn clock_source(&self) -> ClockSource {
+        let bits = self.registers.get_timera_clock_source();
+        match is_low_power(PORT) {
+            true => ClockSource::from_low_power_bits(bits).unwrap_or(ClockSource::IBRO),
+            false => ClockSource::from_bits(bits),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SOURCES: [(ClockSource, Hertz); 4] = [
+        (ClockSource::PCLK, Hertz::MHz(50)),
+        (ClockSource::ISO, Hertz::MHz(60)),
+        (ClockSource::IBRO, Hertz::Hz(7_372_800)),
+        (ClockSource::ERTCO, Hertz::Hz(32_768)),
+    ];
+
+    #[test]
+    fn picks_exact_sources() {
+        let config = best_config(Nanos::nanos(20), &SOURCES).unwrap();
+        assert_eq!(config.source, ClockSource::PCLK);
+        assert_eq!(config.prescaler, TimerPrescaler::Div1);
+        assert_eq!(config.error_ppm, 0);
+
+        // 1/32768 s is 30517.578125 ns
+        let config = best_config(Nanos::nanos(30_518), &SOURCES).unwrap();
+        assert_eq!(config.source, ClockSource::ERTCO);
+        assert_eq!(config.period, Nanos::nanos(30_518));
+        assert_eq!(config.error_ppm, 13);
+
+        let config = best_config(Nanos::nanos(320), &SOURCES).unwrap();
+        assert_eq!(config.source, ClockSource::PCLK);
+        assert_eq!(config.prescaler, TimerPrescaler::Div16);
+    }
+
+    #[test]
+    fn reports_error() {
+        // Nothing divides down to 1 s, the slowest tick is ERTCO / 4096 = 125 ms
+        let config = best_config(Nanos::secs(1), &SOURCES).unwrap();
+        assert_eq!(config.source, ClockSource::ERTCO);
+        assert_eq!(config.prescaler, TimerPrescaler::Div4096);
+        assert_eq!(config.error_ppm, 875_000);
+
+        assert_eq!(
+            best_config(Nanos::nanos(0), &SOURCES),
+            Err(ErrorKind::BadParam)
+        );
+    }
+
+    #[test]
+    fn low_power_sources() {
+        use crate::memory_map::mmio;
+
+        assert_eq!(ClockSource::IBRO.bits(mmio::TIMER_0), Some(2));
+        assert_eq!(ClockSource::IBRO.bits(mmio::LOW_POWER_TIMER_0), Some(0));
+        assert_eq!(ClockSource::INRO.bits(mmio::LOW_POWER_TIMER_1), Some(2));
+        assert_eq!(ClockSource::INRO.bits(mmio::TIMER_3), None);
+        assert_eq!(ClockSource::PCLK.bits(mmio::LOW_POWER_TIMER_0), None);
+        assert_eq!(
+            ClockSource::from_low_power_bits(1),
+            Some(ClockSource::ERTCO)
+        );
+        assert_eq!(ClockSource::from_low_power_bits(3), None);
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn low_power_timer_rejects_pclk() {
+        use super::super::test_timer;
+        use crate::memory_map::mmio;
+
+        let mut timer: Timer<{ mmio::LOW_POWER_TIMER_0 }> = test_timer();
+        assert_eq!(timer.clock_source(), ClockSource::IBRO);
+
+        let config = best_config(Nanos::nanos(20), &SOURCES).unwrap();
+        assert_eq!(timer.set_clock(config), Err(ErrorKind::NotSupported));
+
+        let config = best_config(Nanos::millis(1), &SOURCES[2..]).unwrap();
+        timer.set_clock(config).unwrap();
+        assert_eq!(timer.clock_source(), config.source);
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn switches_clock_while_stopped() {
+        use super::super::test_timer;
+        use crate::memory_map::mmio;
+
+        let mut timer: Timer<{ mmio::TIMER_2 }> = test_timer();
+
+        let config = best_config(Nanos::micros(1), &SOURCES).unwrap();
+        timer.set_clock(config).unwrap();
+        assert_eq!(timer.clock_source(), config.source);
+        assert_eq!(timer.prescaler, config.prescaler);
+        assert!(timer.registers.get_timera_clock_enable2());
+
+        unsafe { timer.registers.set_timera_enable(true) };
+        assert_eq!(timer.set_clock(config), Err(ErrorKind::BadState));
+    }
+}
@@ -0,0 +1,152 @@
+//! # Compare and Capture/Compare Timers
+//! - In compare mode the timer counts freely through the whole 32-bit range. As the count
+//!   passes the compare value the timer output toggles and the interrupt flag is set, so
+//!   moving the compare value ahead schedules the next event without stopping the count.
+//! - In capture/compare mode the timer captures the count into the PWM register on an
+//!   input edge and starts over, like capture mode. When there is no edge before the
+//!   count reaches the compare value, it starts over without a capture, so the compare
+//!   value is a timeout.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::timer::registers::TimerPrescaler;
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//!
+//! # fn main() -> max78000_hal::error::Result<()> {
+//! let timer = Timer::new(TimerInstance::<{ mmio::TIMER_0 }>::instance())?;
+//! let mut compare = timer.into_compare(TimerPrescaler::Div1, 1_000);
+//! compare.start();
+//! loop {
+//!     compare.wait();
+//!     compare.schedule(1_000);
+//! }
+//! # }
+//! ```
+use super::capture::Edge;
+use super::registers::{TimerMode, TimerPrescaler};
+use super::{mode, Timer};
+
+impl<const PORT: usize> Timer<PORT, mode::Disabled> {
+    /// # Into Compare
+    /// Count the peripheral clock divided by `prescaler` freely, toggling the timer
+    /// output as the count passes `compare`. See `Timer::start`.
+    pub fn into_compare(
+        self,
+        prescaler: TimerPrescaler,
+        compare: u32,
+    ) -> Timer<PORT, mode::Compare> {
+        let mut timer: Timer<PORT, mode::Compare> =
+            self.configure(TimerMode::Compare, prescaler, compare);
+        unsafe { timer.registers.set_output_enable(true) };
+        timer
+    }
+
+    /// # Into Capture Compare
+    /// Count the peripheral clock divided by `prescaler`, capturing the count on every
+    /// `edge` of the timer input pin and starting over at it or at `timeout`. The input
+    /// pin must be routed to the timer. See `Timer::start`.
+    pub fn into_capture_compare(
+        self,
+        prescaler: TimerPrescaler,
+        timeout: u32,
+        edge: Edge,
+    ) -> Timer<PORT, mode::CaptureCompare> {
+        let mut timer: Timer<PORT, mode::CaptureCompare> =
+            self.configure(TimerMode::CaptureCompare, prescaler, timeout);
+        unsafe {
+            // Capture events come from the input pin
+            timer.registers.set_timera_event_capture_selection(0);
+        }
+        timer.set_edge(edge);
+        timer
+    }
+}
+
+impl<const PORT: usize> Timer<PORT, mode::Compare> {
+    /// # Schedule
+    /// Move the compare value `ticks` past the current count, and clear the flag.
+    pub fn schedule(&mut self, ticks: u32) {
+        let compare = self.count().wrapping_add(ticks);
+        self.set_compare(compare);
+        self.clear_interrupt();
+    }
+
+    /// # Is Matched
+    /// Check if the count passed the compare value since the flag was last cleared.
+    pub fn is_matched(&self) -> bool {
+        self.is_interrupt_pending()
+    }
+
+    /// # Wait
+    /// Block until the count passes the compare value, and clear the flag.
+    pub fn wait(&mut self) {
+        while !self.is_matched() {}
+        self.clear_interrupt();
+    }
+}
+
+impl<const PORT: usize> Timer<PORT, mode::CaptureCompare> {
+    /// # Set Edge
+    /// Change which input edge captures the count.
+    pub fn set_edge(&mut self, edge: Edge) {
+        let falling = edge == Edge::Falling;
+        unsafe {
+            self.registers.set_timera_polarity(falling);
+            self.registers
+                .set_timera_negative_edge_trigger_for_event(falling);
+        }
+    }
+
+    /// # Take Capture
+    /// Take the captured count if there was a edge since the flag was last cleared,
+    /// and clear the flag.
+    pub fn take_capture(&mut self) -> Option<u32> {
+        if !self.is_interrupt_pending() {
+            return None;
+        }
+
+        let count = self.registers.get_pwm();
+        self.clear_interrupt();
+        Some(count)
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::super::test_timer;
+    use super::*;
+    use crate::memory_map::mmio;
+
+    fn disabled() -> Timer<{ mmio::TIMER_0 }> {
+        test_timer()
+    }
+
+    #[test]
+    fn compare_schedules_ahead() {
+        let mut compare = disabled().into_compare(TimerPrescaler::Div1, 100);
+        assert!(compare.registers.get_output_enable());
+        assert!(matches!(
+            compare.registers.get_timera_mode_select(),
+            Some(TimerMode::Compare)
+        ));
+
+        crate::mock::write(mmio::TIMER_0, u32::MAX - 10);
+        compare.schedule(100);
+        assert_eq!(compare.compare(), 89);
+    }
+
+    #[test]
+    fn capture_compare_takes_captures() {
+        let mut timer = disabled().into_capture_compare(TimerPrescaler::Div1, 5_000, Edge::Falling);
+        assert!(timer.registers.get_timera_negative_edge_trigger_for_event());
+        assert_eq!(timer.compare(), 5_000);
+
+        crate::mock::write(mmio::TIMER_0 + 0x0C, 0);
+        assert_eq!(timer.take_capture(), None);
+
+        crate::mock::write(mmio::TIMER_0 + 0x08, 1_234);
+        crate::mock::write(mmio::TIMER_0 + 0x0C, 1);
+        assert_eq!(timer.take_capture(), Some(1_234));
+    }
+}
@@ -0,0 +1,220 @@
+//! # Event Counter
+//! Count external events, ie. the pulses of a tachometer or a flow meter. In counter
+//! mode the timer counts edges of its input instead of the clock. The edge after the
+//! count reached the rollover value sets it back to `1` with a interrupt, so every
+//! rollover is `rollover` events.
+//!
+//! # Example
+//! ```no_run
+//! use core::cell::RefCell;
+//! use critical_section::Mutex;
+//! use max78000_hal::bind_interrupts;
+//! use max78000_hal::interrupt::typelevel::{Handler, TMR1};
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::timer::capture::Edge;
+//! use max78000_hal::timer::counter::{EventCounter, EventInput};
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//!
+//! static FAN: Mutex<RefCell<Option<EventCounter<{ mmio::TIMER_1 }>>>> =
+//!     Mutex::new(RefCell::new(None));
+//!
+//! struct Rollover;
+//! impl Handler<TMR1> for Rollover {
+//!     unsafe fn on_interrupt() {
+//!         critical_section::with(|cs| {
+//!             if let Some(fan) = FAN.borrow_ref_mut(cs).as_mut() {
+//!                 fan.on_interrupt();
+//!             }
+//!         });
+//!     }
+//! }
+//!
+//! bind_interrupts!(struct Irqs { TMR1 => Rollover; });
+//!
+//! let timer = Timer::new(TimerInstance::<{ mmio::TIMER_1 }>::instance())?;
+//! let mut fan = timer.into_counter(EventInput::Pin, Edge::Rising, 10_000);
+//! fan.enable_interrupt(true);
+//! fan.start();
+//! critical_section::with(|cs| FAN.replace(cs, Some(fan)));
+//! // ...
+//! let pulses = critical_section::with(|cs| FAN.borrow_ref(cs).as_ref().map(|fan| fan.total()));
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::capture::Edge;
+use super::registers::{TimerMode, TimerPrescaler};
+use super::{mode, Timer};
+
+/// # Event Input
+/// Where the events counted come from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EventInput {
+    /// The timer input pin, which must be routed to the timer.
+    Pin,
+    /// One of the internal event sources, by its `TMR_CTRL1` event select value (`1` to
+    /// `7`). See Page 319-321, Table 19-15.
+    Internal(u8),
+}
+
+impl EventInput {
+    pub(crate) fn into_bits(self) -> u8 {
+        match self {
+            EventInput::Pin => 0,
+            EventInput::Internal(select) => select & 0b111,
+        }
+    }
+}
+
+impl<const PORT: usize> Timer<PORT, mode::Disabled> {
+    /// # Into Counter
+    /// Count every `edge` of `input`, rolling over when the count reaches `rollover`,
+    /// see `EventCounter`.
+    pub fn into_counter(self, input: EventInput, edge: Edge, rollover: u32) -> EventCounter<PORT> {
+        let mut timer: Timer<PORT, mode::Counter> =
+            self.configure(TimerMode::Counter, TimerPrescaler::Div1, rollover);
+
+        unsafe {
+            timer
+                .registers
+                .set_timera_event_selection(input.into_bits());
+            timer.registers.set_timera_polarity(edge == Edge::Falling);
+        }
+
+        EventCounter {
+            timer,
+            rollovers: 0,
+            callback: None,
+        }
+    }
+}
+
+/// # Event Counter
+/// The timer at `PORT` in counter mode.
+pub struct EventCounter<const PORT: usize> {
+    timer: Timer<PORT, mode::Counter>,
+    rollovers: u32,
+    callback: Option<fn()>,
+}
+
+// The counter owns its timer outright, and the rollover count is only touched through
+// `&mut self`, so it can move into the cell its rollover interrupt reads
+unsafe impl<const PORT: usize> Send for EventCounter<PORT> {}
+
+impl<const PORT: usize> EventCounter<PORT> {
+    /// # Start
+    /// Start counting from zero.
+    pub fn start(&mut self) {
+        self.rollovers = 0;
+        self.timer.clear_interrupt();
+        self.timer.start();
+    }
+
+    /// # Stop
+    /// Stop counting, keeping the count where it is.
+    pub fn stop(&mut self) {
+        self.timer.stop();
+    }
+
+    /// # Count
+    /// The events counted since the last rollover.
+    pub fn count(&self) -> u32 {
+        self.timer.count().saturating_sub(1)
+    }
+
+    /// # Rollovers
+    /// The number of rollovers seen by `on_interrupt` since `start`.
+    pub fn rollovers(&self) -> u32 {
+        self.rollovers
+    }
+
+    /// # Total
+    /// The events counted since `start`, from the rollovers seen by `on_interrupt`.
+    pub fn total(&self) -> u64 {
+        self.rollovers as u64 * self.rollover() as u64 + self.count() as u64
+    }
+
+    /// # Rollover
+    /// The count the timer rolls over at.
+    pub fn rollover(&self) -> u32 {
+        self.timer.compare()
+    }
+
+    /// # Set Rollover
+    /// Change the count the timer rolls over at.
+    pub fn set_rollover(&mut self, rollover: u32) {
+        self.timer.set_compare(rollover);
+    }
+
+    /// # Set Overflow Callback
+    /// Call `callback` from `on_interrupt` on every rollover, or nothing with `None`.
+    pub fn set_overflow_callback(&mut self, callback: Option<fn()>) {
+        self.callback = callback;
+    }
+
+    /// # Enable Interrupt
+    /// Enable/Disable the timer's interrupt on every rollover, see `on_interrupt`.
+    pub fn enable_interrupt(&mut self, enable: bool) {
+        self.timer.enable_interrupt(enable);
+    }
+
+    /// # On Interrupt
+    /// Count a rollover and call the overflow callback, if the timer rolled over. Call
+    /// this from the timer's interrupt handler, it returns if there was a rollover.
+    pub fn on_interrupt(&mut self) -> bool {
+        if !self.timer.is_interrupt_pending() {
+            return false;
+        }
+        self.timer.clear_interrupt();
+
+        self.rollovers = self.rollovers.wrapping_add(1);
+        if let Some(callback) = self.callback {
+            callback();
+        }
+        true
+    }
+
+    /// # Free
+    /// Stop the timer, and give it back disabled.
+    pub fn free(self) -> Timer<PORT, mode::Disabled> {
+        self.timer.into_disabled()
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::super::test_timer;
+    use super::*;
+    use crate::memory_map::mmio;
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    static CALLS: AtomicU32 = AtomicU32::new(0);
+
+    fn count_call() {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn counts_rollovers() {
+        let timer: Timer<{ mmio::TIMER_1 }> = test_timer();
+        let cnt = mmio::TIMER_1;
+        let intfl = mmio::TIMER_1 + 0x0C;
+        let mut counter = timer.into_counter(EventInput::Internal(2), Edge::Falling, 101);
+        assert_eq!(counter.timer.registers.get_timera_event_selection(), 2);
+        assert!(counter.timer.registers.get_timera_polarity());
+
+        counter.set_overflow_callback(Some(count_call));
+        counter.start();
+        assert_eq!(counter.count(), 0);
+
+        crate::mock::write(intfl, 0);
+        crate::mock::write(cnt, 43);
+        assert!(!counter.on_interrupt());
+        assert_eq!(counter.count(), 42);
+
+        crate::mock::write(intfl, 1);
+        assert!(counter.on_interrupt());
+        assert!(counter.on_interrupt());
+        assert_eq!(counter.rollovers(), 2);
+        assert_eq!(counter.total(), 244);
+        assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,390 @@
+//! # Dual Timer
+//! A timer split into two independent 16-bit timers. `TimerA` counts in the low half of
+//! `TMR_CNT`/`TMR_CMP` and `TimerB` in the high half, each with its own mode,
+//! prescaler, enable and interrupt.
+//!
+//! The high half of the registers is write protected by `TMR_INTFL`, so `TimerA` can
+//! write whole registers without touching `TimerB`. `TimerB` lifts the protection for
+//! its own writes, waits for the write to land, and puts it back.
+//!
+//! Both halves share the control registers, so do not configure one half from a
+//! interrupt that can preempt configuring the other.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::timer::registers::{TimerMode, TimerPrescaler};
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//!
+//! let timer = Timer::new(TimerInstance::<{ mmio::TIMER_0 }>::instance())?;
+//! let (mut a, mut b) = timer.split();
+//!
+//! a.configure(TimerMode::Continuous, TimerPrescaler::Div1, 50_000);
+//! b.configure(TimerMode::OneShot, TimerPrescaler::Div1024, 10_000);
+//! a.start();
+//! b.start();
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use core::marker::PhantomData;
+
+use super::callback::{self, Half};
+use super::registers::{TimerMode, TimerPrescaler};
+use super::{mode, Timer, TimerInstance};
+use crate::error::Result;
+
+impl<const PORT: usize> Timer<PORT, mode::Disabled> {
+    /// # Split
+    /// Split the timer into two independent 16-bit timers, both disabled.
+    pub fn split(mut self) -> (TimerA<PORT>, TimerB<PORT>) {
+        unsafe {
+            self.registers.set_timera_enable(false);
+            self.registers.set_timerb_enable(false);
+            self.registers.set_bit32_cascade_timer_enable(false);
+            self.registers
+                .set_timerb_write_protect_in_dual_timer_mode(false);
+        }
+
+        (
+            TimerA {
+                registers: self.registers,
+            },
+            TimerB {
+                registers: TimerInstance::instance(),
+            },
+        )
+    }
+
+    /// # Join
+    /// Stop both halves, and give back the whole timer.
+    pub fn join(mut a: TimerA<PORT>, mut b: TimerB<PORT>) -> Self {
+        a.stop();
+        b.stop();
+        unsafe { b.registers.set_timerb_clock_enable(false) };
+
+        Timer {
+            registers: a.registers,
+            prescaler: TimerPrescaler::Div1,
+            _mode: PhantomData,
+        }
+    }
+}
+
+/// # Timer A
+/// The low 16-bit half of the timer at `PORT`.
+pub struct TimerA<const PORT: usize> {
+    registers: TimerInstance<PORT>,
+}
+
+impl<const PORT: usize> TimerA<PORT> {
+    /// # Configure
+    /// Stop the timer, and set it to count the peripheral clock divided by `prescaler`
+    /// up to `compare` in `mode`.
+    pub fn configure(&mut self, mode: TimerMode, prescaler: TimerPrescaler, compare: u16) {
+        unsafe {
+            self.registers.set_timera_enable(false);
+            self.registers.set_timera_clock_enable(false);
+
+            self.registers.set_timera_clock_source(0);
+            self.registers.set_timera_mode_select(mode);
+            self.registers.set_timera_prescaler_select(prescaler);
+            self.registers.set_timera_polarity(false);
+            self.set_count(1);
+            self.set_compare(compare);
+            self.registers.clear_timera_interrupt_event();
+
+            self.registers.set_timera_clock_enable(true);
+        }
+        while !self.registers.get_timera_clock_ready() {}
+    }
+
+    /// # Start
+    /// Start counting from the start of a period.
+    pub fn start(&mut self) {
+        self.set_count(1);
+        unsafe { self.registers.set_timera_enable(true) };
+    }
+
+    /// # Stop
+    /// Stop counting, keeping the count where it is.
+    pub fn stop(&mut self) {
+        unsafe { self.registers.set_timera_enable(false) };
+    }
+
+    /// # Is Running
+    /// Check if the timer is counting.
+    pub fn is_running(&self) -> bool {
+        self.registers.get_timera_enable()
+    }
+
+    /// # Count
+    /// The current count of the timer.
+    pub fn count(&self) -> u16 {
+        self.registers.get_timer_count() as u16
+    }
+
+    /// # Set Count
+    /// Set the count of the timer.
+    pub fn set_count(&mut self, count: u16) {
+        let register = low(self.registers.get_timer_count(), count);
+        unsafe { self.registers.set_timer_count(register) };
+    }
+
+    /// # Compare
+    /// The compare value the timer counts up to.
+    pub fn compare(&self) -> u16 {
+        self.registers.get_timer_compare_value() as u16
+    }
+
+    /// # Set Compare
+    /// Set the compare value the timer counts up to.
+    pub fn set_compare(&mut self, compare: u16) {
+        let register = low(self.registers.get_timer_compare_value(), compare);
+        unsafe { self.registers.set_timer_compare_value(register) };
+    }
+
+    /// # Enable Interrupt
+    /// Enable/Disable the interrupt when the count reaches the compare value.
+    pub fn enable_interrupt(&mut self, enable: bool) {
+        unsafe { self.registers.set_timera_interrupt_enable(enable) };
+    }
+
+    /// # Is Interrupt Pending
+    /// Check if the count reached the compare value since the flag was last cleared.
+    pub fn is_interrupt_pending(&self) -> bool {
+        self.registers.is_timera_interrupt_event_active()
+    }
+
+    /// # Clear Interrupt
+    /// Clear the flag of the count reaching the compare value.
+    pub fn clear_interrupt(&mut self) {
+        unsafe { self.registers.clear_timera_interrupt_event() };
+    }
+
+    /// # Set Callback
+    /// Call `callback` from `callback::InterruptHandler` every time the count reaches
+    /// the compare value, enabling the interrupt. `None` disables the interrupt.
+    ///
+    /// # Errors
+    /// `ErrorKind::NoDevice` when `PORT` is a low power timer.
+    pub fn set_callback(&mut self, callback: Option<fn()>) -> Result<()> {
+        self.enable_interrupt(false);
+        unsafe { callback::store(PORT, Half::A, callback)? };
+        self.enable_interrupt(callback.is_some());
+        Ok(())
+    }
+
+    /// # Wait
+    /// Block until the count reaches the compare value, and clear the flag.
+    pub fn wait(&mut self) {
+        while !self.is_interrupt_pending() {}
+        self.clear_interrupt();
+    }
+}
+
+/// # Timer B
+/// The high 16-bit half of the timer at `PORT`.
+pub struct TimerB<const PORT: usize> {
+    registers: TimerInstance<PORT>,
+}
+
+impl<const PORT: usize> TimerB<PORT> {
+    /// # Configure
+    /// Stop the timer, and set it to count the peripheral clock divided by `prescaler`
+    /// up to `compare` in `mode`.
+    pub fn configure(&mut self, mode: TimerMode, prescaler: TimerPrescaler, compare: u16) {
+        unsafe {
+            self.registers.set_timerb_enable(false);
+            self.registers.set_timerb_clock_enable(false);
+
+            self.registers.set_timerb_clock_source(0);
+            self.registers.set_timerb_mode_select(mode);
+            self.registers.set_timerb_prescaler_select(prescaler);
+            self.set_count(1);
+            self.set_compare(compare);
+            self.registers.clear_timerb_interrupt_event();
+
+            self.registers.set_timerb_clock_enable(true);
+        }
+        while !self.registers.get_timerb_clock_ready_status() {}
+    }
+
+    /// # Start
+    /// Start counting from the start of a period.
+    pub fn start(&mut self) {
+        self.set_count(1);
+        unsafe { self.registers.set_timerb_enable(true) };
+    }
+
+    /// # Stop
+    /// Stop counting, keeping the count where it is.
+    pub fn stop(&mut self) {
+        unsafe { self.registers.set_timerb_enable(false) };
+    }
+
+    /// # Is Running
+    /// Check if the timer is counting.
+    pub fn is_running(&self) -> bool {
+        self.registers.get_timerb_enable()
+    }
+
+    /// # Count
+    /// The current count of the timer.
+    pub fn count(&self) -> u16 {
+        (self.registers.get_timer_count() >> 16) as u16
+    }
+
+    /// # Set Count
+    /// Set the count of the timer.
+    pub fn set_count(&mut self, count: u16) {
+        self.write_high(|registers| unsafe {
+            registers.set_timer_count(high(registers.get_timer_count(), count))
+        });
+    }
+
+    /// # Compare
+    /// The compare value the timer counts up to.
+    pub fn compare(&self) -> u16 {
+        (self.registers.get_timer_compare_value() >> 16) as u16
+    }
+
+    /// # Set Compare
+    /// Set the compare value the timer counts up to.
+    pub fn set_compare(&mut self, compare: u16) {
+        self.write_high(|registers| unsafe {
+            registers.set_timer_compare_value(high(registers.get_timer_compare_value(), compare))
+        });
+    }
+
+    /// # Enable Interrupt
+    /// Enable/Disable the interrupt when the count reaches the compare value.
+    pub fn enable_interrupt(&mut self, enable: bool) {
+        unsafe { self.registers.set_timerb_interrupt_enable(enable) };
+    }
+
+    /// # Is Interrupt Pending
+    /// Check if the count reached the compare value since the flag was last cleared.
+    pub fn is_interrupt_pending(&self) -> bool {
+        self.registers.is_timerb_interrupt_event_active()
+    }
+
+    /// # Clear Interrupt
+    /// Clear the flag of the count reaching the compare value.
+    pub fn clear_interrupt(&mut self) {
+        unsafe { self.registers.clear_timerb_interrupt_event() };
+    }
+
+    /// # Set Callback
+    /// Call `callback` from `callback::InterruptHandler` every time the count reaches
+    /// the compare value, enabling the interrupt. `None` disables the interrupt.
+    ///
+    /// # Errors
+    /// `ErrorKind::NoDevice` when `PORT` is a low power timer.
+    pub fn set_callback(&mut self, callback: Option<fn()>) -> Result<()> {
+        self.enable_interrupt(false);
+        unsafe { callback::store(PORT, Half::B, callback)? };
+        self.enable_interrupt(callback.is_some());
+        Ok(())
+    }
+
+    /// # Wait
+    /// Block until the count reaches the compare value, and clear the flag.
+    pub fn wait(&mut self) {
+        while !self.is_interrupt_pending() {}
+        self.clear_interrupt();
+    }
+
+    /// # Enable Wake-Up
+    /// Arm/Disarm the timer as a wake-up source from low power modes.
+    pub fn enable_wakeup(&mut self, enable: bool) {
+        unsafe { self.registers.set_timerb_wakeup_function(enable) };
+    }
+
+    /// # Is Wake-Up Pending
+    /// Check if the timer flagged a wake-up event since it was last cleared.
+    pub fn is_wakeup_pending(&self) -> bool {
+        self.registers.is_timerb_wakeup_event_active()
+    }
+
+    /// # Clear Wake-Up
+    /// Clear the timer's wake-up event flag.
+    pub fn clear_wakeup(&mut self) {
+        unsafe { self.registers.clear_timerb_wakeup_event() };
+    }
+
+    /// # Write High
+    /// Lift the write protection of the high half for `write`, and put it back once
+    /// the write landed.
+    fn write_high(&mut self, write: impl FnOnce(&mut TimerInstance<PORT>)) {
+        unsafe {
+            self.registers
+                .set_timerb_write_protect_in_dual_timer_mode(true)
+        };
+        write(&mut self.registers);
+        while !self.registers.get_timerb_write_done() {}
+        unsafe {
+            self.registers
+                .set_timerb_write_protect_in_dual_timer_mode(false)
+        };
+    }
+}
+
+/// # Low
+/// `register` with its low half replaced by `value`.
+fn low(register: u32, value: u16) -> u32 {
+    (register & 0xFFFF_0000) | value as u32
+}
+
+/// # High
+/// `register` with its high half replaced by `value`.
+fn high(register: u32, value: u16) -> u32 {
+    (register & 0xFFFF) | ((value as u32) << 16)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn halves_of_registers() {
+        assert_eq!(low(0x1234_5678, 0xABCD), 0x1234_ABCD);
+        assert_eq!(high(0x1234_5678, 0xABCD), 0xABCD_5678);
+        assert_eq!(high(0xFFFF_FFFF, 0), 0x0000_FFFF);
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn halves_are_independent() {
+        use super::super::{test_timer, CLOCK_READY, CTRL1_OFFSET};
+        use crate::memory_map::mmio;
+
+        let timer: Timer<{ mmio::TIMER_1 }> = test_timer();
+        let intfl = mmio::TIMER_1 + 0x0C;
+
+        // TimerB's clock is ready too, and every write is done
+        let ctrl1 = mmio::TIMER_1 + CTRL1_OFFSET;
+        crate::mock::write(ctrl1, (1 << 19) | CLOCK_READY);
+        crate::mock::write(intfl, 1 << 25);
+        let (mut a, mut b) = timer.split();
+
+        a.configure(TimerMode::Continuous, TimerPrescaler::Div1, 1_000);
+        b.configure(TimerMode::OneShot, TimerPrescaler::Div64, 2_000);
+        assert_eq!(a.compare(), 1_000);
+        assert_eq!(b.compare(), 2_000);
+        assert!(!b.registers.get_timerb_write_protect_in_dual_timer_mode());
+
+        b.start();
+        assert!(b.is_running());
+        assert!(!a.is_running());
+        assert_eq!(a.count(), 1);
+        assert_eq!(b.count(), 1);
+
+        b.set_count(0x4321);
+        a.set_count(0x8765);
+        assert_eq!(a.count(), 0x8765);
+        assert_eq!(b.count(), 0x4321);
+
+        let timer = Timer::join(a, b);
+        assert!(!timer.registers.get_timerb_enable());
+        assert!(!timer.registers.get_timera_enable());
+    }
+}
@@ -0,0 +1,93 @@
+//! # Gated Timer
+//! A timer that only counts while its input is at the active level, ie. to measure how
+//! long a enable line is asserted for. Like continuous mode, the count starts over with
+//! a interrupt when it reaches the compare value.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::timer::counter::EventInput;
+//! use max78000_hal::timer::registers::TimerPrescaler;
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//!
+//! let timer = Timer::new(TimerInstance::<{ mmio::TIMER_2 }>::instance())?;
+//! let mut gated = timer.into_gated(TimerPrescaler::Div1, u32::MAX, EventInput::Pin, true);
+//! gated.start();
+//! // ...
+//! let active_ticks = gated.count();
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::counter::EventInput;
+use super::registers::{TimerMode, TimerPrescaler};
+use super::{mode, Timer};
+
+impl<const PORT: usize> Timer<PORT, mode::Disabled> {
+    /// # Into Gated
+    /// Count the peripheral clock divided by `prescaler` up to `compare`, but only while
+    /// `input` is high (`active_high`) or low. See `Timer::start`.
+    pub fn into_gated(
+        self,
+        prescaler: TimerPrescaler,
+        compare: u32,
+        input: EventInput,
+        active_high: bool,
+    ) -> Timer<PORT, mode::Gated> {
+        let mut timer: Timer<PORT, mode::Gated> =
+            self.configure(TimerMode::Gated, prescaler, compare);
+        timer.set_input(input);
+        timer.set_active_level(active_high);
+        timer
+    }
+}
+
+impl<const PORT: usize> Timer<PORT, mode::Gated> {
+    /// # Set Input
+    /// Change the input gating the count.
+    pub fn set_input(&mut self, input: EventInput) {
+        unsafe { self.registers.set_timera_event_selection(input.into_bits()) };
+    }
+
+    /// # Set Active Level
+    /// Count while the input is high (`true`) or low (`false`).
+    pub fn set_active_level(&mut self, active_high: bool) {
+        unsafe { self.registers.set_timera_polarity(!active_high) };
+    }
+
+    /// # Take Rollover
+    /// Check if the count reached the compare value and started over, and clear the
+    /// flag.
+    pub fn take_rollover(&mut self) -> bool {
+        let pending = self.is_interrupt_pending();
+        if pending {
+            self.clear_interrupt();
+        }
+        pending
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::super::test_timer;
+    use super::*;
+    use crate::memory_map::mmio;
+
+    #[test]
+    fn configures_gate() {
+        let timer: Timer<{ mmio::TIMER_2 }> = test_timer();
+        let mut gated = timer.into_gated(TimerPrescaler::Div2, 500, EventInput::Pin, false);
+        assert!(matches!(
+            gated.registers.get_timera_mode_select(),
+            Some(TimerMode::Gated)
+        ));
+        assert!(gated.registers.get_timera_polarity());
+        assert_eq!(gated.compare(), 500);
+
+        gated.set_active_level(true);
+        assert!(!gated.registers.get_timera_polarity());
+
+        crate::mock::write(mmio::TIMER_2 + 0x0C, 0);
+        assert!(!gated.take_rollover());
+        crate::mock::write(mmio::TIMER_2 + 0x0C, 1);
+        assert!(gated.take_rollover());
+    }
+}
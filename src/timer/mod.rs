@@ -1 +1,646 @@
+//! # Timer
+//! `Timer` is a driver for TMR0 to TMR3, with the timer's mode in its type. A timer
+//! starts out `Disabled`, and `into_oneshot`, `into_continuous` or `into_pwm` configure
+//! `TMR_CTRL0`/`TMR_CTRL1` for that mode. The hardware can only change mode while
+//! disabled, so a running timer has to go back with `into_disabled` first.
+//!
+//! The low power timers TMR4 and TMR5 (`mmio::LOW_POWER_TIMER_0`/`_1`) use the same
+//! driver, and keep counting in the low power modes. They have no TimerB half, and
+//! cannot count the peripheral clock, so where the docs say peripheral clock they
+//! count the IBRO instead, see `clock` for the other sources.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::time::{ExtU32, RateExtU32};
+//! use max78000_hal::timer::registers::TimerPrescaler;
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//!
+//! let timer = Timer::new(TimerInstance::<{ mmio::TIMER_0 }>::instance())?;
+//!
+//! let mut oneshot = timer.into_oneshot(TimerPrescaler::Div1, 1.millis())?;
+//! oneshot.start();
+//! oneshot.wait();
+//!
+//! let mut pwm = oneshot.into_disabled().into_pwm(TimerPrescaler::Div1, 50.kHz())?;
+//! pwm.set_duty(pwm.max_duty() / 4);
+//! pwm.start();
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use core::marker::PhantomData;
+
+use crate::error::{ErrorKind, Result};
+use crate::gcr::{peripheral_reset, system_clock_enable, HardwareSource};
+use crate::memory_map::mmio;
+use crate::time::{Hertz, Micros};
+
+pub mod callback;
+pub mod capture;
+pub mod cascade;
+pub mod clock;
+pub mod compare;
+pub mod counter;
+pub mod dual;
+pub mod gated;
+pub mod monotonic;
+pub mod pins;
+pub mod pwm;
+pub mod queue;
 pub mod registers;
+#[cfg(feature = "rtic-monotonic")]
+pub mod rtic;
+pub mod wait;
+pub mod wakeup;
+
+use registers::{TimerMode, TimerPrescaler};
+
+/// # Timer Instance
+/// A zero sized handle to the registers of one timer, ie. `TimerInstance<{ mmio::TIMER_0 }>`.
+pub use registers::Instance as TimerInstance;
+
+pub use monotonic::Monotonic;
+pub use queue::SoftTimerQueue;
+
+/// # Hardware Source
+/// The GCR clock and reset of the timer at `port`.
+///
+/// # Errors
+/// `ErrorKind::NoDevice` when `port` is a low power timer.
+pub(crate) fn hardware_source(port: usize) -> Result<HardwareSource> {
+    match port {
+        mmio::TIMER_0 => Ok(HardwareSource::TMR0),
+        mmio::TIMER_1 => Ok(HardwareSource::TMR1),
+        mmio::TIMER_2 => Ok(HardwareSource::TMR2),
+        mmio::TIMER_3 => Ok(HardwareSource::TMR3),
+        _ => Err(ErrorKind::NoDevice),
+    }
+}
+
+/// # Duration Ticks
+/// The ticks at `tick_rate` nearest to `duration`.
+///
+/// # Errors
+/// `ErrorKind::BadParam` when that is zero, or over `u32::MAX`.
+pub(crate) fn duration_ticks(duration: Micros, tick_rate: Hertz) -> Result<u32> {
+    let ticks = (duration.ticks() as u64 * tick_rate.raw() as u64 + 500_000) / 1_000_000;
+    match u32::try_from(ticks) {
+        Ok(0) | Err(_) => Err(ErrorKind::BadParam),
+        Ok(ticks) => Ok(ticks),
+    }
+}
+
+/// # PWM Match
+/// The PWM value keeping the output high for `high` ticks of every `period`, clamped
+/// to the period. The output is low until the PWM value and high after it (see
+/// `mode::Pwm`), so the match is that far from the end of the period.
+pub(crate) const fn pwm_match(high: u32, period: u32) -> u32 {
+    period.saturating_sub(high)
+}
+
+/// # Rate Ticks
+/// The ticks at `tick_rate` nearest to a period of `frequency`.
+///
+/// # Errors
+/// `ErrorKind::BadParam` when `frequency` is zero, or that is zero.
+pub(crate) fn rate_ticks(frequency: Hertz, tick_rate: Hertz) -> Result<u32> {
+    let frequency = frequency.raw();
+    if frequency == 0 {
+        return Err(ErrorKind::BadParam);
+    }
+    match tick_rate.raw().saturating_add(frequency / 2) / frequency {
+        0 => Err(ErrorKind::BadParam),
+        ticks => Ok(ticks),
+    }
+}
+
+/// # Is Low Power
+/// Check if `port` is one of the low power timers, TMR4 and TMR5.
+pub(crate) const fn is_low_power(port: usize) -> bool {
+    matches!(port, mmio::LOW_POWER_TIMER_0 | mmio::LOW_POWER_TIMER_1)
+}
+
+/// # Modes
+/// The modes a `Timer` can be in.
+pub mod mode {
+    /// # Disabled
+    /// Stopped and not configured, the only mode that can go into the others.
+    pub struct Disabled;
+
+    /// # One Shot
+    /// Counts up to the compare value once, then stops.
+    pub struct OneShot;
+
+    /// # Continuous
+    /// Counts up to the compare value, then starts over.
+    pub struct Continuous;
+
+    /// # Counter
+    /// Counts edges of the timer input up to the compare value, then starts over.
+    pub struct Counter;
+
+    /// # PWM
+    /// Counts up to the compare value and starts over, driving the timer output low
+    /// until the PWM value and high after it.
+    pub struct Pwm;
+
+    /// # Capture
+    /// Counts freely, copying the count into the PWM register on every input edge.
+    pub struct Capture;
+
+    /// # Gated
+    /// Counts up to the compare value and starts over, but only while the input is at
+    /// its active level.
+    pub struct Gated;
+
+    /// # Compare
+    /// Counts freely through the whole 32-bit range, toggling the timer output and
+    /// flagging a interrupt as the count passes the compare value.
+    pub struct Compare;
+
+    /// # Capture Compare
+    /// Counts up to the compare value and starts over, copying the count into the PWM
+    /// register and starting over early on every input edge.
+    pub struct CaptureCompare;
+}
+
+/// # Timer
+/// The timer at `PORT`, in `MODE`. See the module level docs.
+pub struct Timer<const PORT: usize, MODE = mode::Disabled> {
+    registers: TimerInstance<PORT>,
+    prescaler: TimerPrescaler,
+    _mode: PhantomData<MODE>,
+}
+
+impl<const PORT: usize> Timer<PORT, mode::Disabled> {
+    /// # New
+    /// Reset the timer, and enable its clock.
+    ///
+    /// # Errors
+    /// `ErrorKind::NoDevice` when `PORT` is not a timer.
+    pub fn new(registers: TimerInstance<PORT>) -> Result<Self> {
+        let source = match PORT {
+            mmio::LOW_POWER_TIMER_0 => HardwareSource::TMR4,
+            mmio::LOW_POWER_TIMER_1 => HardwareSource::TMR5,
+            port => hardware_source(port)?,
+        };
+
+        peripheral_reset(source);
+        system_clock_enable(source, true);
+
+        Ok(Self {
+            registers,
+            prescaler: TimerPrescaler::Div1,
+            _mode: PhantomData,
+        })
+    }
+
+    /// Configure the timer for `mode`, counting the peripheral clock divided by
+    /// `prescaler` up to `compare`. The timer is left disabled.
+    fn configure<NEW>(
+        mut self,
+        mode: TimerMode,
+        prescaler: TimerPrescaler,
+        compare: u32,
+    ) -> Timer<PORT, NEW> {
+        unsafe {
+            // CTRL0 may only be changed with the timer disabled
+            self.registers.set_timera_enable(false);
+            self.registers.set_timera_clock_enable(false);
+
+            self.registers.set_timera_clock_source(0);
+            self.registers.set_timera_mode_select(mode);
+            self.registers.set_timera_prescaler_select(prescaler);
+            self.registers.set_timera_polarity(false);
+            self.registers.set_timer_count(1);
+            self.registers.set_timer_compare_value(compare);
+            self.registers.clear_timera_interrupt_event();
+
+            self.registers.set_timera_clock_enable(true);
+        }
+        while !self.registers.get_timera_clock_ready() {}
+
+        Timer {
+            registers: self.registers,
+            prescaler,
+            _mode: PhantomData,
+        }
+    }
+
+    /// # Into One Shot
+    /// Count `duration` of the peripheral clock divided by `prescaler` once, see
+    /// `Timer::start`.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `duration` is under a tick, or over `u32::MAX` ticks.
+    pub fn into_oneshot(
+        self,
+        prescaler: TimerPrescaler,
+        duration: Micros,
+    ) -> Result<Timer<PORT, mode::OneShot>> {
+        let compare = duration_ticks(duration, Self::configured_tick_rate(prescaler))?;
+        Ok(self.into_oneshot_ticks(prescaler, compare))
+    }
+
+    /// # Into One Shot Ticks
+    /// Count `compare` ticks of the peripheral clock divided by `prescaler` once, see
+    /// `Timer::start`.
+    pub fn into_oneshot_ticks(
+        self,
+        prescaler: TimerPrescaler,
+        compare: u32,
+    ) -> Timer<PORT, mode::OneShot> {
+        self.configure(TimerMode::OneShot, prescaler, compare)
+    }
+
+    /// # Into Continuous
+    /// Count periods of `period` of the peripheral clock divided by `prescaler` over
+    /// and over, see `Timer::start`.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `period` is under a tick, or over `u32::MAX` ticks.
+    pub fn into_continuous(
+        self,
+        prescaler: TimerPrescaler,
+        period: Micros,
+    ) -> Result<Timer<PORT, mode::Continuous>> {
+        let compare = duration_ticks(period, Self::configured_tick_rate(prescaler))?;
+        Ok(self.into_continuous_ticks(prescaler, compare))
+    }
+
+    /// # Into Continuous Ticks
+    /// Count `compare` ticks of the peripheral clock divided by `prescaler` over and
+    /// over, see `Timer::start`.
+    pub fn into_continuous_ticks(
+        self,
+        prescaler: TimerPrescaler,
+        compare: u32,
+    ) -> Timer<PORT, mode::Continuous> {
+        self.configure(TimerMode::Continuous, prescaler, compare)
+    }
+
+    /// # Into PWM
+    /// PWM at `frequency`, counting the peripheral clock divided by `prescaler`, see
+    /// `into_pwm_ticks`. The duties are in ticks, up to `max_duty`.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `frequency` is zero, or faster than the ticks.
+    pub fn into_pwm(
+        self,
+        prescaler: TimerPrescaler,
+        frequency: Hertz,
+    ) -> Result<Timer<PORT, mode::Pwm>> {
+        let period = rate_ticks(frequency, Self::configured_tick_rate(prescaler))?;
+        Ok(self.into_pwm_ticks(prescaler, period))
+    }
+
+    /// # Into PWM Ticks
+    /// PWM with a period of `period` ticks of the peripheral clock divided by
+    /// `prescaler`, starting at a duty of zero. The timer output is enabled, its pin
+    /// must be routed to the timer to see the signal.
+    pub fn into_pwm_ticks(self, prescaler: TimerPrescaler, period: u32) -> Timer<PORT, mode::Pwm> {
+        let mut timer: Timer<PORT, mode::Pwm> = self.configure(TimerMode::Pwm, prescaler, period);
+        unsafe {
+            timer.registers.set_pwm(pwm_match(0, period));
+            timer.registers.set_output_enable(true);
+        }
+        timer
+    }
+
+    /// The tick rate `configure` sets up for `prescaler`.
+    fn configured_tick_rate(prescaler: TimerPrescaler) -> Hertz {
+        let source = match is_low_power(PORT) {
+            true => clock::ClockSource::from_low_power_bits(0).unwrap_or(clock::ClockSource::IBRO),
+            false => clock::ClockSource::from_bits(0),
+        };
+        Hertz::from_raw(source.frequency().raw() >> prescaler as u32)
+    }
+
+    /// # Release
+    /// Give back the timer registers. See `IntoPac::free` to get the PAC peripheral back
+    /// instead.
+    pub fn release(self) -> TimerInstance<PORT> {
+        self.registers
+    }
+}
+
+impl<const PORT: usize, MODE> Timer<PORT, MODE> {
+    /// # Into Disabled
+    /// Stop the timer, turn off its interrupt and output, and go back to `Disabled` so
+    /// it can be put into a other mode.
+    pub fn into_disabled(mut self) -> Timer<PORT, mode::Disabled> {
+        unsafe {
+            self.registers.set_timera_enable(false);
+            self.registers.set_timera_interrupt_enable(false);
+            self.registers.set_output_enable(false);
+            self.registers.clear_timera_interrupt_event();
+        }
+
+        Timer {
+            registers: self.registers,
+            prescaler: self.prescaler,
+            _mode: PhantomData,
+        }
+    }
+
+    /// # Tick Rate
+    /// How fast the timer counts, its clock source divided by the prescaler.
+    pub fn tick_rate(&self) -> Hertz {
+        Hertz::from_raw(self.clock_source().frequency().raw() >> self.prescaler as u32)
+    }
+
+    /// # Count
+    /// The current count of the timer.
+    pub fn count(&self) -> u32 {
+        self.registers.get_timer_count()
+    }
+
+    /// # Compare
+    /// The compare value the timer counts up to.
+    pub fn compare(&self) -> u32 {
+        self.registers.get_timer_compare_value()
+    }
+
+    /// # Enable Interrupt
+    /// Enable/Disable the timer's interrupt when the count reaches the compare value.
+    pub fn enable_interrupt(&mut self, enable: bool) {
+        unsafe { self.registers.set_timera_interrupt_enable(enable) };
+    }
+
+    /// # Is Interrupt Pending
+    /// Check if the count reached the compare value since the flag was last cleared.
+    pub fn is_interrupt_pending(&self) -> bool {
+        self.registers.is_timera_interrupt_event_active()
+    }
+
+    /// # Clear Interrupt
+    /// Clear the flag of the count reaching the compare value.
+    pub fn clear_interrupt(&mut self) {
+        unsafe { self.registers.clear_timera_interrupt_event() };
+    }
+
+    /// # Is Running
+    /// Check if the timer is counting.
+    pub fn is_running(&self) -> bool {
+        self.registers.get_timera_enable()
+    }
+}
+
+/// # Running Mode
+/// The modes a timer can be started in.
+pub trait RunningMode: private::Sealed {}
+
+impl RunningMode for mode::OneShot {}
+impl RunningMode for mode::Continuous {}
+impl RunningMode for mode::Counter {}
+impl RunningMode for mode::Pwm {}
+impl RunningMode for mode::Capture {}
+impl RunningMode for mode::Gated {}
+impl RunningMode for mode::Compare {}
+impl RunningMode for mode::CaptureCompare {}
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for super::mode::OneShot {}
+    impl Sealed for super::mode::Continuous {}
+    impl Sealed for super::mode::Counter {}
+    impl Sealed for super::mode::Pwm {}
+    impl Sealed for super::mode::Capture {}
+    impl Sealed for super::mode::Gated {}
+    impl Sealed for super::mode::Compare {}
+    impl Sealed for super::mode::CaptureCompare {}
+}
+
+impl<const PORT: usize, MODE: RunningMode> Timer<PORT, MODE> {
+    /// # Start
+    /// Start counting from the start of a period.
+    pub fn start(&mut self) {
+        unsafe {
+            self.registers.set_timer_count(1);
+            self.registers.set_timera_enable(true);
+        }
+    }
+
+    /// # Stop
+    /// Stop counting, keeping the count where it is.
+    pub fn stop(&mut self) {
+        unsafe { self.registers.set_timera_enable(false) };
+    }
+
+    /// # Set Compare
+    /// Set the compare value the timer counts up to. Takes effect right away, so a
+    /// running timer whose count is already past it wraps around first.
+    pub fn set_compare(&mut self, compare: u32) {
+        unsafe { self.registers.set_timer_compare_value(compare) };
+    }
+}
+
+impl<const PORT: usize> Timer<PORT, mode::OneShot> {
+    /// # Is Done
+    /// Check if the count reached the compare value.
+    pub fn is_done(&self) -> bool {
+        self.is_interrupt_pending()
+    }
+
+    /// # Wait
+    /// Block until the count reached the compare value, and clear the flag.
+    pub fn wait(&mut self) {
+        while !self.is_done() {}
+        self.clear_interrupt();
+    }
+}
+
+impl<const PORT: usize> Timer<PORT, mode::Pwm> {
+    /// # Set Duty
+    /// Set the number of ticks in every period the output is high for, clamped to the
+    /// period.
+    pub fn set_duty(&mut self, duty: u32) {
+        let compare = self.compare();
+        unsafe { self.registers.set_pwm(pwm_match(duty, compare)) };
+    }
+
+    /// # Get Duty
+    /// Get the number of ticks in every period the output is high for.
+    pub fn get_duty(&self) -> u32 {
+        pwm_match(self.registers.get_pwm(), self.compare())
+    }
+
+    /// # Max Duty
+    /// The duty of a output that is always high, the period.
+    pub fn max_duty(&self) -> u32 {
+        self.compare()
+    }
+}
+
+impl<const PORT: usize> crate::pac::FromPac for Timer<PORT, mode::Disabled> {
+    fn from_pac<P: crate::pac::PacPeripheral>(peripheral: P) -> Result<Self> {
+        crate::pac::check_address::<P>(PORT)?;
+        drop(peripheral);
+
+        Self::new(TimerInstance::instance())
+    }
+}
+
+impl<const PORT: usize, MODE> crate::pac::IntoPac for Timer<PORT, MODE> {
+    fn free<P: crate::pac::PacPeripheral>(self) -> Result<P> {
+        crate::pac::free_into(self, PORT)
+    }
+}
+
+/// # CTRL1 Offset
+/// The offset of the timer configuration register, which holds the clock ready bits.
+#[cfg(all(test, feature = "mock-mmio"))]
+pub(crate) const CTRL1_OFFSET: usize = 0x18;
+
+/// # Clock Ready
+/// TimerA's clock ready bit in CTRL1.
+#[cfg(all(test, feature = "mock-mmio"))]
+pub(crate) const CLOCK_READY: u32 = 1 << 3;
+
+/// # Test Timer
+/// A timer at `PORT` in `MODE` over a freshly reset mock. The mock has no timer, so
+/// this plays the hardware's part and marks TimerA's clock ready.
+#[cfg(all(test, feature = "mock-mmio"))]
+pub(crate) fn test_timer<const PORT: usize, MODE>() -> Timer<PORT, MODE> {
+    crate::mock::reset();
+    crate::mock::write(PORT + CTRL1_OFFSET, CLOCK_READY);
+    Timer {
+        registers: TimerInstance::instance(),
+        prescaler: TimerPrescaler::Div1,
+        _mode: PhantomData,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn configures_modes() {
+        let timer: Timer<{ mmio::TIMER_2 }> = test_timer();
+
+        let mut oneshot = timer.into_oneshot_ticks(TimerPrescaler::Div8, 1_000);
+        assert!(matches!(
+            oneshot.registers.get_timera_mode_select(),
+            Some(TimerMode::OneShot)
+        ));
+        assert_eq!(oneshot.compare(), 1_000);
+        assert!(matches!(oneshot.prescaler, TimerPrescaler::Div8));
+
+        oneshot.start();
+        assert!(oneshot.is_running());
+
+        let mut pwm = oneshot
+            .into_disabled()
+            .into_pwm_ticks(TimerPrescaler::Div1, 100);
+        assert!(!pwm.is_running());
+        assert!(pwm.registers.get_output_enable());
+
+        assert_eq!(pwm.get_duty(), 0);
+        pwm.set_duty(25);
+        assert_eq!(pwm.get_duty(), 25);
+        pwm.set_duty(150);
+        assert_eq!(pwm.get_duty(), pwm.max_duty());
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn pwm_is_high_after_the_match() {
+        let timer: Timer<{ mmio::TIMER_2 }> = test_timer();
+        let pwm_register = mmio::TIMER_2 + 0x08;
+
+        // Low from the start of the period to the PWM value, high from there to the end
+        let mut pwm = timer.into_pwm_ticks(TimerPrescaler::Div1, 100);
+        assert!(!pwm.registers.get_timera_polarity());
+        assert_eq!(crate::mock::read(pwm_register), 100);
+
+        pwm.set_duty(10);
+        assert_eq!(crate::mock::read(pwm_register), 90);
+        pwm.set_duty(pwm.max_duty());
+        assert_eq!(crate::mock::read(pwm_register), 0);
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn oneshot_waits_for_compare() {
+        let timer: Timer<{ mmio::TIMER_2 }> = test_timer();
+
+        let mut oneshot = timer.into_oneshot_ticks(TimerPrescaler::Div1, 10);
+        crate::mock::write(mmio::TIMER_2 + 0x0C, 0);
+        assert!(!oneshot.is_done());
+
+        crate::mock::write(mmio::TIMER_2 + 0x0C, 1);
+        assert!(oneshot.is_done());
+        oneshot.wait();
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn converts_durations_and_rates() {
+        let timer: Timer<{ mmio::TIMER_2 }> = test_timer();
+
+        // The host runs the peripheral clock at 50 MHz
+        let oneshot = timer
+            .into_oneshot(TimerPrescaler::Div8, Micros::millis(1))
+            .unwrap();
+        assert_eq!(oneshot.compare(), 6_250);
+
+        let pwm = oneshot
+            .into_disabled()
+            .into_pwm(TimerPrescaler::Div1, Hertz::kHz(50))
+            .unwrap();
+        assert_eq!(pwm.max_duty(), 1_000);
+
+        let disabled = pwm.into_disabled();
+        assert!(matches!(
+            disabled.into_continuous(TimerPrescaler::Div1, Micros::from_ticks(0)),
+            Err(ErrorKind::BadParam)
+        ));
+    }
+
+    #[test]
+    fn rounds_ticks() {
+        let rate = Hertz::MHz(1);
+        assert_eq!(duration_ticks(Micros::from_ticks(7), rate), Ok(7));
+        assert_eq!(
+            duration_ticks(Micros::from_ticks(1), Hertz::kHz(1)),
+            Err(ErrorKind::BadParam)
+        );
+        assert_eq!(
+            duration_ticks(Micros::secs(100), Hertz::MHz(50)),
+            Err(ErrorKind::BadParam)
+        );
+        assert_eq!(rate_ticks(Hertz::kHz(3), rate), Ok(333));
+        assert_eq!(
+            rate_ticks(Hertz::from_raw(0), rate),
+            Err(ErrorKind::BadParam)
+        );
+        assert_eq!(rate_ticks(Hertz::MHz(4), rate), Err(ErrorKind::BadParam));
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn disabled_timer_frees_into_pac() {
+        use crate::pac::IntoPac;
+
+        struct FakeTmr2;
+
+        impl FakeTmr2 {
+            const PTR: *const u32 = mmio::TIMER_2 as *const u32;
+
+            unsafe fn steal() -> Self {
+                Self
+            }
+        }
+
+        crate::impl_pac_peripheral! {
+            struct PacTmr2(FakeTmr2);
+        }
+
+        let timer: Timer<{ mmio::TIMER_2 }> = test_timer();
+        let PacTmr2(_timer) = timer.free::<PacTmr2>().unwrap();
+        let timer: Timer<{ mmio::TIMER_2 }> = test_timer();
+        let _registers = timer.release();
+    }
+}
@@ -0,0 +1,223 @@
+//! # Monotonic
+//! A free running microsecond clock for timestamps and timeouts, ie. a system timebase.
+//! A `CascadeTimer32` counts the peripheral clock, prescaled down to just above 1 MHz,
+//! through the whole 32-bit range. The timer's interrupt at the end of the range counts
+//! the overflows, which extend the count to 64 bits so the clock never wraps.
+//!
+//! # Example
+//! ```no_run
+//! use core::cell::RefCell;
+//! use critical_section::Mutex;
+//! use max78000_hal::bind_interrupts;
+//! use max78000_hal::interrupt::typelevel::{Handler, TMR0};
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::timer::monotonic::Monotonic;
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//!
+//! static CLOCK: Mutex<RefCell<Option<Monotonic<{ mmio::TIMER_0 }>>>> =
+//!     Mutex::new(RefCell::new(None));
+//!
+//! struct Overflow;
+//! impl Handler<TMR0> for Overflow {
+//!     unsafe fn on_interrupt() {
+//!         critical_section::with(|cs| {
+//!             if let Some(clock) = CLOCK.borrow_ref(cs).as_ref() {
+//!                 clock.on_interrupt();
+//!             }
+//!         });
+//!     }
+//! }
+//!
+//! bind_interrupts!(struct Irqs { TMR0 => Overflow; });
+//!
+//! let clock = Monotonic::new(Timer::new(TimerInstance::instance())?);
+//! critical_section::with(|cs| CLOCK.replace(cs, Some(clock)));
+//!
+//! let now = || critical_section::with(|cs| CLOCK.borrow_ref(cs).as_ref().unwrap().now());
+//! let start = now();
+//! // ...
+//! let took = now() - start;
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use super::cascade::CascadeTimer32;
+use super::registers::TimerPrescaler;
+use super::{mode, Timer, TimerInstance};
+use crate::time::{Hertz, Instant, Micros64};
+
+/// # Monotonic
+/// A microsecond clock counting up from when it was made, on the timer at `PORT`.
+pub struct Monotonic<const PORT: usize> {
+    timer: CascadeTimer32<PORT>,
+    tick_rate: Hertz,
+    overflows: AtomicU32,
+}
+
+// The clock owns its timer, and only reads the count and clears the flag, so it can be
+// handed to the context (ie. a critical section cell) the interrupt handler reaches it in
+unsafe impl<const PORT: usize> Send for Monotonic<PORT> {}
+
+impl<const PORT: usize> Monotonic<PORT> {
+    /// # New
+    /// Chain both halves of `timer` and start it counting from zero. The interrupt of
+    /// the timer must be bound to a handler calling `on_interrupt` and enabled in the
+    /// NVIC, or the clock wraps after the 32-bit count (over an hour).
+    pub fn new(timer: Timer<PORT, mode::Disabled>) -> Self {
+        let clock = crate::core_peripheral_clock();
+        let prescaler = monotonic_prescaler(clock);
+
+        let mut timer = CascadeTimer32::new(timer, prescaler, u32::MAX);
+        timer.enable_interrupt(true);
+        timer.start();
+
+        Self {
+            timer,
+            tick_rate: Hertz::from_raw(clock.raw() >> prescaler as u32),
+            overflows: AtomicU32::new(0),
+        }
+    }
+
+    /// # Tick Rate
+    /// How fast the timer counts, at least 1 MHz unless the peripheral clock is slower.
+    pub fn tick_rate(&self) -> Hertz {
+        self.tick_rate
+    }
+
+    /// # Ticks
+    /// The timer ticks since the clock was made.
+    pub fn ticks(&self) -> u64 {
+        loop {
+            let overflows = self.overflows.load(Ordering::Acquire);
+            let count = self.timer.count();
+            let pending = self.timer.is_interrupt_pending();
+
+            // `on_interrupt` ran in between, so the count may be from either side of it
+            if overflows != self.overflows.load(Ordering::Acquire) {
+                continue;
+            }
+
+            // The count wrapped, but `on_interrupt` did not run yet
+            let overflows = overflows as u64 + (pending && count < u32::MAX / 2) as u64;
+            return overflows * u32::MAX as u64 + count.saturating_sub(1) as u64;
+        }
+    }
+
+    /// # Now
+    /// The time since the clock was made.
+    pub fn now(&self) -> Instant {
+        Instant::from_ticks(ticks_to_micros(self.ticks(), self.tick_rate))
+    }
+
+    /// # Elapsed Since
+    /// The time from `earlier` to now, zero if `earlier` is in the future.
+    pub fn elapsed_since(&self, earlier: Instant) -> Micros64 {
+        self.now()
+            .checked_duration_since(earlier)
+            .unwrap_or(Micros64::from_ticks(0))
+    }
+
+    /// # On Interrupt
+    /// Count a overflow of the timer. Call this from the timer's interrupt handler, it
+    /// only needs a shared reference so the clock can be read meanwhile.
+    pub fn on_interrupt(&self) {
+        if self.timer.is_interrupt_pending() {
+            unsafe { TimerInstance::<PORT>::instance().clear_timera_interrupt_event() };
+            self.overflows.fetch_add(1, Ordering::AcqRel);
+        }
+    }
+
+    /// # Free
+    /// Stop the clock, and give back the timer disabled.
+    pub fn free(self) -> Timer<PORT, mode::Disabled> {
+        self.timer.free()
+    }
+}
+
+/// # Monotonic Prescaler
+/// The largest prescaler that keeps the tick rate from `clock` at 1 MHz or more.
+fn monotonic_prescaler(clock: Hertz) -> TimerPrescaler {
+    (0..=12)
+        .rev()
+        .find(|shift| clock.raw() >> shift >= 1_000_000)
+        .and_then(TimerPrescaler::from_bits)
+        .unwrap_or(TimerPrescaler::Div1)
+}
+
+/// # Ticks To Micros
+/// The microseconds in `ticks` at `tick_rate`, rounded down.
+fn ticks_to_micros(ticks: u64, tick_rate: Hertz) -> u64 {
+    (ticks as u128 * 1_000_000 / tick_rate.raw().max(1) as u128) as u64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn prescaler_values() {
+        assert!(matches!(
+            monotonic_prescaler(Hertz::MHz(50)),
+            TimerPrescaler::Div32
+        ));
+        assert!(matches!(
+            monotonic_prescaler(Hertz::MHz(100)),
+            TimerPrescaler::Div64
+        ));
+        assert!(matches!(
+            monotonic_prescaler(Hertz::kHz(7_372)),
+            TimerPrescaler::Div4
+        ));
+        assert!(matches!(
+            monotonic_prescaler(Hertz::kHz(32)),
+            TimerPrescaler::Div1
+        ));
+    }
+
+    #[test]
+    fn micros_values() {
+        let rate = Hertz::Hz(1_562_500);
+        assert_eq!(ticks_to_micros(1_562_500, rate), 1_000_000);
+        assert_eq!(ticks_to_micros(3, rate), 1);
+        assert_eq!(ticks_to_micros(u64::MAX, rate), 11_805_916_207_174_113_033);
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn extends_to_64_bits() {
+        use super::super::test_timer;
+        use crate::memory_map::mmio;
+        let timer: Timer<{ mmio::TIMER_3 }> = test_timer();
+        let cnt = mmio::TIMER_3;
+        let intfl = mmio::TIMER_3 + 0x0C;
+        // Every write is done
+        crate::mock::write(intfl, (1 << 25) | (1 << 8));
+
+        let clock = Monotonic {
+            timer: CascadeTimer32::new(timer, TimerPrescaler::Div1, u32::MAX),
+            tick_rate: Hertz::MHz(1),
+            overflows: AtomicU32::new(0),
+        };
+
+        crate::mock::write(cnt, 1_001);
+        crate::mock::write(intfl, 0);
+        assert_eq!(clock.ticks(), 1_000);
+        assert_eq!(clock.now(), Instant::from_ticks(1_000));
+
+        // Wrapped, before the interrupt ran
+        crate::mock::write(cnt, 11);
+        crate::mock::write(intfl, 1);
+        assert_eq!(clock.ticks(), u32::MAX as u64 + 10);
+
+        clock.on_interrupt();
+        crate::mock::write(intfl, 0);
+        assert_eq!(clock.ticks(), u32::MAX as u64 + 10);
+
+        let earlier = Instant::from_ticks(u32::MAX as u64);
+        assert_eq!(clock.elapsed_since(earlier), Micros64::micros(10));
+        assert_eq!(
+            clock.elapsed_since(Instant::from_ticks(u32::MAX as u64 + 20)),
+            Micros64::micros(0)
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! # Timer Pins
+//! Route a timer's outputs to its package pins. The timer output only appears on a pin
+//! with the timer's output enabled in `TMR_CTRL1`, and the pin switched to the
+//! alternate function carrying it. These claim the pin from the GPIO ownership, set
+//! its alternate function, and enable the output, in one go.
+//!
+//! | Timer | Output A   | Output B   |
+//! |-------|------------|------------|
+//! | TMR0  | P0.2 AF2   | P0.3 AF2   |
+//! | TMR1  | P0.14 AF2  | P0.15 AF2  |
+//! | TMR2  | P0.26 AF2  | P0.27 AF2  |
+//! | TMR3  | P1.6 AF2   | P1.7 AF2   |
+//! | TMR4  | P2.4 AF1   |            |
+//! | TMR5  | P2.5 AF1   |            |
+//!
+//! Dropping the returned `GpioPin` gives the pin back to the GPIO ownership, but leaves
+//! it on the alternate function.
+//!
+//! With typed pins (see `gpio::pin`) the `_with_pin` versions take the pin already on
+//! its alternate function instead, and only accept the timer's own pin.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::time::RateExtU32;
+//! use max78000_hal::timer::registers::TimerPrescaler;
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//!
+//! let timer = Timer::new(TimerInstance::<{ mmio::TIMER_2 }>::instance())?;
+//! let (mut pwm, _pin) = timer.into_pwm_on_pin(TimerPrescaler::Div1, 50.kHz())?;
+//! pwm.set_duty(pwm.max_duty() / 4);
+//! pwm.start();
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::registers::TimerPrescaler;
+use super::{mode, rate_ticks, Timer};
+use crate::error::{ErrorKind, Result};
+use crate::gpio::pin::{TimerOutput, TimerOutputB};
+use crate::gpio::{GpioPin, GpioSelect, OutputDriveStrength, PinFunction, VoltageSelect};
+use crate::memory_map::mmio;
+use crate::time::Hertz;
+
+/// # Output Pin
+/// The pin and alternate function of output A of the timer at `port`.
+fn output_pin(port: usize) -> Option<(GpioSelect, usize, PinFunction)> {
+    match port {
+        mmio::TIMER_0 => Some((GpioSelect::Gpio0, 2, PinFunction::AF2)),
+        mmio::TIMER_1 => Some((GpioSelect::Gpio0, 14, PinFunction::AF2)),
+        mmio::TIMER_2 => Some((GpioSelect::Gpio0, 26, PinFunction::AF2)),
+        mmio::TIMER_3 => Some((GpioSelect::Gpio1, 6, PinFunction::AF2)),
+        mmio::LOW_POWER_TIMER_0 => Some((GpioSelect::Gpio2, 4, PinFunction::AF1)),
+        mmio::LOW_POWER_TIMER_1 => Some((GpioSelect::Gpio2, 5, PinFunction::AF1)),
+        _ => None,
+    }
+}
+
+/// # Output B Pin
+/// The pin and alternate function of output B of the timer at `port`, the low power
+/// timers have none.
+fn output_b_pin(port: usize) -> Option<(GpioSelect, usize, PinFunction)> {
+    match port {
+        mmio::TIMER_0 => Some((GpioSelect::Gpio0, 3, PinFunction::AF2)),
+        mmio::TIMER_1 => Some((GpioSelect::Gpio0, 15, PinFunction::AF2)),
+        mmio::TIMER_2 => Some((GpioSelect::Gpio0, 27, PinFunction::AF2)),
+        mmio::TIMER_3 => Some((GpioSelect::Gpio1, 7, PinFunction::AF2)),
+        _ => None,
+    }
+}
+
+/// # Claim
+/// Take ownership of the pin at `route`, and switch it to its alternate function as a
+/// output.
+fn claim(route: Option<(GpioSelect, usize, PinFunction)>) -> Result<GpioPin> {
+    let (port, pin, function) = route.ok_or(ErrorKind::NoDevice)?;
+    let pin = GpioPin::new(port, pin).ok_or(ErrorKind::Busy)?;
+
+    pin.configure_output(
+        OutputDriveStrength::Strength0(VoltageSelect::VddIO),
+        function,
+    );
+    Ok(pin)
+}
+
+impl<const PORT: usize> Timer<PORT, mode::Disabled> {
+    /// # Into PWM On Pin
+    /// `into_pwm`, with the timer's output routed to its pin.
+    ///
+    /// # Errors
+    /// `ErrorKind::Busy` when the pin is already owned, or `ErrorKind::BadParam` for a
+    /// `frequency` `into_pwm` can't count.
+    pub fn into_pwm_on_pin(
+        self,
+        prescaler: TimerPrescaler,
+        frequency: Hertz,
+    ) -> Result<(Timer<PORT, mode::Pwm>, GpioPin)> {
+        let period = rate_ticks(frequency, Self::configured_tick_rate(prescaler))?;
+        let pin = claim(output_pin(PORT))?;
+        Ok((self.into_pwm_ticks(prescaler, period), pin))
+    }
+
+    /// # Into Compare On Pin
+    /// `into_compare`, with the timer's output routed to its pin, so it toggles as the
+    /// count passes the compare value.
+    ///
+    /// # Errors
+    /// `ErrorKind::Busy` when the pin is already owned.
+    pub fn into_compare_on_pin(
+        self,
+        prescaler: TimerPrescaler,
+        compare: u32,
+    ) -> Result<(Timer<PORT, mode::Compare>, GpioPin)> {
+        let pin = claim(output_pin(PORT))?;
+        Ok((self.into_compare(prescaler, compare), pin))
+    }
+
+    /// # Into PWM With Pin
+    /// `into_pwm`, driving `pin`. The pin is given back with the timer, keep it as long
+    /// as the PWM runs.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` for a `frequency` `into_pwm` can't count.
+    pub fn into_pwm_with_pin<P: TimerOutput<PORT>>(
+        self,
+        prescaler: TimerPrescaler,
+        frequency: Hertz,
+        pin: P,
+    ) -> Result<(Timer<PORT, mode::Pwm>, P)> {
+        Ok((self.into_pwm(prescaler, frequency)?, pin))
+    }
+
+    /// # Into Compare With Pin
+    /// `into_compare`, toggling `pin` as the count passes the compare value.
+    pub fn into_compare_with_pin<P: TimerOutput<PORT>>(
+        self,
+        prescaler: TimerPrescaler,
+        compare: u32,
+        pin: P,
+    ) -> (Timer<PORT, mode::Compare>, P) {
+        (self.into_compare(prescaler, compare), pin)
+    }
+}
+
+impl<const PORT: usize, MODE> Timer<PORT, MODE> {
+    /// # Enable Output B
+    /// Drive `pin` with output B, the inverted output A in PWM mode. The pin is given
+    /// back, keep it as long as the output is used.
+    pub fn enable_output_b<P: TimerOutputB<PORT>>(&mut self, pin: P) -> P {
+        unsafe { self.registers.set_output_b_enable(true) };
+        pin
+    }
+
+    /// # Route Output
+    /// Claim the pin of output A, switch it to the timer, and enable the output.
+    ///
+    /// # Errors
+    /// `ErrorKind::Busy` when the pin is already owned.
+    pub fn route_output(&mut self) -> Result<GpioPin> {
+        let pin = claim(output_pin(PORT))?;
+        unsafe { self.registers.set_output_enable(true) };
+        Ok(pin)
+    }
+
+    /// # Route Output B
+    /// Claim the pin of output B, the inverted output A in PWM mode, switch it to the
+    /// timer, and enable the output. The low power timers have no output B, so using it
+    /// on them fails to compile.
+    ///
+    /// # Errors
+    /// `ErrorKind::Busy` when the pin is already owned.
+    pub fn route_output_b(&mut self) -> Result<GpioPin> {
+        let pin = claim(output_b_pin(PORT))?;
+        unsafe { self.registers.set_output_b_enable(true) };
+        Ok(pin)
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::super::test_timer;
+    use super::*;
+
+    #[test]
+    fn pwm_claims_its_pin() {
+        let timer: Timer<{ mmio::TIMER_3 }> = test_timer();
+        let (mut pwm, pin) = timer
+            .into_pwm_on_pin(TimerPrescaler::Div1, Hertz::kHz(500))
+            .unwrap();
+        assert_eq!(pin.get_port() as usize, GpioSelect::Gpio1 as usize);
+        assert_eq!(pin.get_pin(), 6);
+        assert!(pwm.registers.get_output_enable());
+        assert_eq!(pwm.route_output().err(), Some(ErrorKind::Busy));
+
+        let pin_b = pwm.route_output_b().unwrap();
+        assert_eq!(pin_b.get_pin(), 7);
+        assert!(pwm.registers.get_output_b_enable());
+
+        // Given back, the pin can be claimed again
+        drop(pin);
+        let pwm = pwm.into_disabled();
+        let (compare, _pin) = pwm.into_compare_on_pin(TimerPrescaler::Div1, 10).unwrap();
+        assert!(compare.registers.get_output_enable());
+    }
+
+    #[test]
+    fn typed_pins_drive_their_timer() {
+        use crate::gpio::pin::{Af2, Gpio0};
+
+        let timer: Timer<{ mmio::TIMER_0 }> = test_timer();
+        let pins = Gpio0::take().unwrap();
+        let output = pins.p0_2.into_alternate::<Af2>();
+        let (mut pwm, _output) = timer
+            .into_pwm_with_pin(TimerPrescaler::Div1, Hertz::kHz(500), output)
+            .unwrap();
+        assert!(pwm.registers.get_output_enable());
+
+        let _output_b = pwm.enable_output_b(pins.p0_3.into_alternate::<Af2>());
+        assert!(pwm.registers.get_output_b_enable());
+    }
+}
@@ -0,0 +1,490 @@
+//! # PWM Channel
+//! PWM set up from a frequency and a duty, instead of register values. `PwmChannel`
+//! works out the prescaler and period of the timer, and keeps the duty as a fraction of
+//! the period so it stays the same when the frequency changes.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::time::RateExtU32;
+//! use max78000_hal::timer::pwm::PwmChannel;
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//!
+//! let timer = Timer::new(TimerInstance::<{ mmio::TIMER_0 }>::instance())?;
+//! let mut led = PwmChannel::new(timer, 1.kHz())?;
+//! led.set_duty_percent(25);
+//! led.enable();
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+//!
+//! `DeadTimePwm` adds the complementary output φA′ to a channel, with a gap (dead time)
+//! between one output going low and the other going high, ie. for a half bridge.
+use super::registers::TimerPrescaler;
+use super::{mode, Timer};
+use crate::error::{ErrorKind, Result};
+use crate::time::{Hertz, Nanos};
+
+/// # Full Duty
+/// The duty fraction of a output that is always high.
+pub const FULL_DUTY: u16 = u16::MAX;
+
+/// # PWM Channel
+/// The output of the timer at `PORT`, at a set frequency and duty.
+pub struct PwmChannel<const PORT: usize> {
+    timer: Timer<PORT, mode::Pwm>,
+    clock: Hertz,
+    duty: u16,
+}
+
+impl<const PORT: usize> PwmChannel<PORT> {
+    /// # New
+    /// Put the timer into PWM at `frequency`, from the peripheral clock, with a duty of
+    /// zero. The output is not enabled, see `enable`.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `frequency` is zero, or too fast to leave a period of
+    /// two clocks.
+    pub fn new(timer: Timer<PORT, mode::Disabled>, frequency: Hertz) -> Result<Self> {
+        let clock = crate::core_peripheral_clock();
+        let (prescaler, period) = pwm_timing(clock, frequency)?;
+
+        Ok(Self {
+            timer: timer.into_pwm_ticks(prescaler, period),
+            clock,
+            duty: 0,
+        })
+    }
+
+    /// # Enable
+    /// Start driving the output.
+    pub fn enable(&mut self) {
+        self.timer.start();
+    }
+
+    /// # Disable
+    /// Stop the timer, the output keeps the level it was at.
+    pub fn disable(&mut self) {
+        self.timer.stop();
+    }
+
+    /// # Set Frequency
+    /// Change the frequency, keeping the duty. The timer restarts from the start of a
+    /// period if it was running.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `frequency` is zero, or too fast to leave a period of
+    /// two clocks.
+    pub fn set_frequency(&mut self, frequency: Hertz) -> Result<()> {
+        let (prescaler, period) = pwm_timing(self.clock, frequency)?;
+        let running = self.timer.is_running();
+
+        let registers = &mut self.timer.registers;
+        unsafe {
+            // The prescaler may only be changed with the timer disabled
+            registers.set_timera_enable(false);
+            registers.set_timera_prescaler_select(prescaler);
+            registers.set_timer_compare_value(period);
+            registers.set_pwm(super::pwm_match(duty_ticks(self.duty, period), period));
+        }
+        self.timer.prescaler = prescaler;
+
+        if running {
+            self.timer.start();
+        }
+        Ok(())
+    }
+
+    /// # Frequency
+    /// The frequency the output runs at, which may be a bit off from the one asked for
+    /// since the period is a whole number of ticks.
+    pub fn frequency(&self) -> Hertz {
+        Hertz::from_raw(self.tick_rate().raw() / self.timer.max_duty())
+    }
+
+    /// # Tick Rate
+    /// How fast the timer counts.
+    pub fn tick_rate(&self) -> Hertz {
+        Hertz::from_raw(self.clock.raw() >> self.timer.prescaler as u32)
+    }
+
+    /// # Set Duty
+    /// Set the duty, the part of every period the output is high for, as a fraction of
+    /// `FULL_DUTY`.
+    pub fn set_duty(&mut self, duty: u16) {
+        self.duty = duty;
+        let ticks = duty_ticks(duty, self.timer.max_duty());
+        self.timer.set_duty(ticks);
+    }
+
+    /// # Get Duty
+    /// Get the duty as a fraction of `FULL_DUTY`.
+    pub fn get_duty(&self) -> u16 {
+        self.duty
+    }
+
+    /// # Set Duty Percent
+    /// Set the duty in percent, clamped to `100`.
+    pub fn set_duty_percent(&mut self, percent: u8) {
+        self.set_duty((percent.min(100) as u32 * FULL_DUTY as u32 / 100) as u16);
+    }
+
+    /// # Free
+    /// Give back the timer, still in PWM mode.
+    pub fn free(self) -> Timer<PORT, mode::Pwm> {
+        self.timer
+    }
+}
+
+/// # Dead Time PWM
+/// A `PwmChannel` driving the complementary pair φA and φA′, which are never high at
+/// the same time. Both dead times are counted in timer ticks, up to 255 of them.
+///
+/// # Example
+/// ```no_run
+/// use max78000_hal::memory_map::mmio;
+/// use max78000_hal::time::{Nanos, RateExtU32};
+/// use max78000_hal::timer::pwm::{DeadTimePwm, PwmChannel};
+/// use max78000_hal::timer::{Timer, TimerInstance};
+///
+/// let timer = Timer::new(TimerInstance::<{ mmio::TIMER_1 }>::instance())?;
+/// let channel = PwmChannel::new(timer, 20.kHz())?;
+/// let mut bridge = DeadTimePwm::new(channel, Nanos::nanos(200), Nanos::nanos(200))?;
+/// bridge.set_duty_percent(40);
+/// bridge.enable();
+/// # Ok::<(), max78000_hal::error::ErrorKind>(())
+/// ```
+pub struct DeadTimePwm<const PORT: usize> {
+    channel: PwmChannel<PORT>,
+    rising: Nanos,
+    falling: Nanos,
+}
+
+impl<const PORT: usize> DeadTimePwm<PORT> {
+    /// # New
+    /// Enable the φA′ output of `channel`. `rising` is the gap from φA′ falling to φA
+    /// rising, and `falling` the gap from φA falling to φA′ rising.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when a dead time is longer than 255 timer ticks.
+    pub fn new(channel: PwmChannel<PORT>, rising: Nanos, falling: Nanos) -> Result<Self> {
+        let mut pwm = Self {
+            channel,
+            rising,
+            falling,
+        };
+
+        pwm.set_dead_time(rising, falling)?;
+        unsafe {
+            let registers = &mut pwm.channel.timer.registers;
+            registers.set_timera_pwm_output_phi_alpha_polarity_bit(false);
+            registers.set_timera_pwm_output_phi_alpha_prime_polarity_bit(false);
+            registers.set_timera_pwm_output_phi_alpha_prime_disable(false);
+        }
+
+        Ok(pwm)
+    }
+
+    /// # Set Dead Time
+    /// Change both dead times, see `new`.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when a dead time is longer than 255 timer ticks.
+    pub fn set_dead_time(&mut self, rising: Nanos, falling: Nanos) -> Result<()> {
+        let tick_rate = self.channel.tick_rate();
+        let high = dead_time_ticks(rising, tick_rate)?;
+        let low = dead_time_ticks(falling, tick_rate)?;
+
+        unsafe {
+            let registers = &mut self.channel.timer.registers;
+            registers.set_timera_non_overlapping_high_compare_0(high);
+            registers.set_timera_non_overlapping_low_compare_0(low);
+        }
+
+        self.rising = rising;
+        self.falling = falling;
+        Ok(())
+    }
+
+    /// # Dead Time
+    /// The `(rising, falling)` dead times.
+    pub fn dead_time(&self) -> (Nanos, Nanos) {
+        (self.rising, self.falling)
+    }
+
+    /// # Set Frequency
+    /// Change the frequency, keeping the duty and the dead times.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `frequency` is zero or too fast, or the dead times no
+    /// longer fit at the new prescaler. The frequency is left unchanged then.
+    pub fn set_frequency(&mut self, frequency: Hertz) -> Result<()> {
+        let (prescaler, _) = pwm_timing(self.channel.clock, frequency)?;
+        let tick_rate = Hertz::from_raw(self.channel.clock.raw() >> prescaler as u32);
+        dead_time_ticks(self.rising, tick_rate)?;
+        dead_time_ticks(self.falling, tick_rate)?;
+
+        self.channel.set_frequency(frequency)?;
+        self.set_dead_time(self.rising, self.falling)
+    }
+
+    /// # Set Duty
+    /// Set the duty of φA as a fraction of `FULL_DUTY`, see `PwmChannel::set_duty`.
+    pub fn set_duty(&mut self, duty: u16) {
+        self.channel.set_duty(duty);
+    }
+
+    /// # Set Duty Percent
+    /// Set the duty of φA in percent, see `PwmChannel::set_duty_percent`.
+    pub fn set_duty_percent(&mut self, percent: u8) {
+        self.channel.set_duty_percent(percent);
+    }
+
+    /// # Enable
+    /// Start driving both outputs.
+    pub fn enable(&mut self) {
+        self.channel.enable();
+    }
+
+    /// # Disable
+    /// Stop the timer.
+    pub fn disable(&mut self) {
+        self.channel.disable();
+    }
+
+    /// # Free
+    /// Disable the φA′ output, and give back the channel.
+    pub fn free(mut self) -> PwmChannel<PORT> {
+        unsafe {
+            let registers = &mut self.channel.timer.registers;
+            registers.set_timera_pwm_output_phi_alpha_prime_disable(true);
+            registers.set_timera_non_overlapping_high_compare_0(0);
+            registers.set_timera_non_overlapping_low_compare_0(0);
+        }
+        self.channel
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl<const PORT: usize> embedded_hal_0_2::PwmPin for PwmChannel<PORT> {
+    type Duty = u16;
+
+    fn disable(&mut self) {
+        PwmChannel::disable(self);
+    }
+
+    fn enable(&mut self) {
+        PwmChannel::enable(self);
+    }
+
+    fn get_duty(&self) -> Self::Duty {
+        PwmChannel::get_duty(self)
+    }
+
+    fn get_max_duty(&self) -> Self::Duty {
+        FULL_DUTY
+    }
+
+    fn set_duty(&mut self, duty: Self::Duty) {
+        PwmChannel::set_duty(self, duty);
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<const PORT: usize> embedded_hal::pwm::ErrorType for PwmChannel<PORT> {
+    type Error = core::convert::Infallible;
+}
+
+#[cfg(feature = "eh1")]
+impl<const PORT: usize> embedded_hal::pwm::SetDutyCycle for PwmChannel<PORT> {
+    fn max_duty_cycle(&self) -> u16 {
+        FULL_DUTY
+    }
+
+    fn set_duty_cycle(&mut self, duty: u16) -> core::result::Result<(), Self::Error> {
+        self.set_duty(duty);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<const PORT: usize> embedded_hal::pwm::ErrorType for DeadTimePwm<PORT> {
+    type Error = core::convert::Infallible;
+}
+
+#[cfg(feature = "eh1")]
+impl<const PORT: usize> embedded_hal::pwm::SetDutyCycle for DeadTimePwm<PORT> {
+    fn max_duty_cycle(&self) -> u16 {
+        FULL_DUTY
+    }
+
+    fn set_duty_cycle(&mut self, duty: u16) -> core::result::Result<(), Self::Error> {
+        self.set_duty(duty);
+        Ok(())
+    }
+}
+
+/// # Dead Time Ticks
+/// The whole number of ticks at `tick_rate` covering at least `time`.
+fn dead_time_ticks(time: Nanos, tick_rate: Hertz) -> Result<u8> {
+    let ticks = (time.ticks() as u128 * tick_rate.raw() as u128).div_ceil(1_000_000_000);
+    u8::try_from(ticks).map_err(|_| ErrorKind::BadParam)
+}
+
+/// # PWM Timing
+/// The smallest prescaler, and the period in ticks at that prescaler, for PWM at
+/// `frequency` from `clock`. A small prescaler gives the finest duty steps.
+fn pwm_timing(clock: Hertz, frequency: Hertz) -> Result<(TimerPrescaler, u32)> {
+    if frequency.raw() == 0 {
+        return Err(ErrorKind::BadParam);
+    }
+
+    // Rounded to the nearest whole period
+    let clocks = (clock.raw() as u64 * 2 / frequency.raw() as u64).div_ceil(2);
+    if clocks < 2 {
+        return Err(ErrorKind::BadParam);
+    }
+
+    (0..=12)
+        .map(|shift| (shift, clocks >> shift))
+        .find(|(_, period)| *period <= u32::MAX as u64)
+        .and_then(|(shift, period)| Some((TimerPrescaler::from_bits(shift)?, period as u32)))
+        .ok_or(ErrorKind::BadParam)
+}
+
+/// # Duty Ticks
+/// The PWM value of `duty` (a fraction of `FULL_DUTY`) in a period of `period` ticks.
+fn duty_ticks(duty: u16, period: u32) -> u32 {
+    (period as u64 * duty as u64 / FULL_DUTY as u64) as u32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A channel on TMR1 with a period of `period` ticks of a 50 MHz clock.
+    #[cfg(feature = "mock-mmio")]
+    fn channel(period: u32) -> PwmChannel<{ crate::memory_map::mmio::TIMER_1 }> {
+        let timer: Timer<{ crate::memory_map::mmio::TIMER_1 }> = super::super::test_timer();
+        PwmChannel {
+            timer: timer.into_pwm_ticks(TimerPrescaler::Div1, period),
+            clock: Hertz::MHz(50),
+            duty: 0,
+        }
+    }
+
+    #[test]
+    fn pwm_timing_values() {
+        let clock = Hertz::MHz(50);
+        assert_eq!(
+            pwm_timing(clock, Hertz::kHz(1)),
+            Ok((TimerPrescaler::Div1, 50_000))
+        );
+        assert_eq!(
+            pwm_timing(clock, Hertz::kHz(30)),
+            Ok((TimerPrescaler::Div1, 1_667))
+        );
+        assert_eq!(pwm_timing(clock, Hertz::Hz(0)), Err(ErrorKind::BadParam));
+        assert_eq!(pwm_timing(clock, Hertz::MHz(40)), Err(ErrorKind::BadParam));
+    }
+
+    #[test]
+    fn duty_ticks_values() {
+        assert_eq!(duty_ticks(0, 1_000), 0);
+        assert_eq!(duty_ticks(FULL_DUTY / 4, 1_000), 249);
+        assert_eq!(duty_ticks(FULL_DUTY, 1_000), 1_000);
+        assert_eq!(duty_ticks(FULL_DUTY, u32::MAX), u32::MAX);
+    }
+
+    #[test]
+    fn dead_time_ticks_values() {
+        let rate = Hertz::MHz(50);
+        assert_eq!(dead_time_ticks(Nanos::nanos(0), rate), Ok(0));
+        assert_eq!(dead_time_ticks(Nanos::nanos(200), rate), Ok(10));
+        assert_eq!(dead_time_ticks(Nanos::nanos(210), rate), Ok(11));
+        assert_eq!(dead_time_ticks(Nanos::nanos(5_100), rate), Ok(255));
+        assert_eq!(
+            dead_time_ticks(Nanos::nanos(5_120), rate),
+            Err(ErrorKind::BadParam)
+        );
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn duty_follows_frequency() {
+        let mut pwm = channel(50_000);
+
+        pwm.set_duty_percent(50);
+        assert_eq!(pwm.timer.get_duty(), 24_999);
+        assert_eq!(pwm.frequency(), Hertz::kHz(1));
+
+        pwm.set_frequency(Hertz::kHz(10)).unwrap();
+        assert_eq!(pwm.timer.max_duty(), 5_000);
+        assert_eq!(pwm.timer.get_duty(), 2_499);
+        assert_eq!(pwm.frequency(), Hertz::kHz(10));
+        assert!(!pwm.timer.is_running());
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn dead_time_programs_nolcmp() {
+        use crate::memory_map::mmio;
+        let channel = channel(2_500);
+
+        let mut bridge = DeadTimePwm::new(channel, Nanos::nanos(100), Nanos::nanos(300)).unwrap();
+        let nolcmp = mmio::TIMER_1 + 0x14;
+        assert_eq!(crate::mock::read(nolcmp), (5 << 8) | 15);
+        assert!(!bridge
+            .channel
+            .timer
+            .registers
+            .get_timera_pwm_output_phi_alpha_prime_disable());
+
+        assert_eq!(
+            bridge.set_dead_time(Nanos::micros(10), Nanos::nanos(0)),
+            Err(ErrorKind::BadParam)
+        );
+        assert_eq!(bridge.dead_time(), (Nanos::nanos(100), Nanos::nanos(300)));
+
+        let channel = bridge.free();
+        assert_eq!(crate::mock::read(nolcmp), 0);
+        assert!(channel
+            .timer
+            .registers
+            .get_timera_pwm_output_phi_alpha_prime_disable());
+    }
+
+    #[cfg(all(feature = "mock-mmio", feature = "eh1"))]
+    #[test]
+    fn set_duty_cycle_trait() {
+        use crate::memory_map::mmio;
+        use embedded_hal::pwm::SetDutyCycle;
+
+        let mut pwm = channel(1_000);
+
+        // High for a quarter of the period, so the match is three quarters in
+        pwm.set_duty_cycle_percent(25).unwrap();
+        assert_eq!(pwm.get_duty(), FULL_DUTY / 4);
+        assert_eq!(pwm.timer.get_duty(), 249);
+        assert_eq!(crate::mock::read(mmio::TIMER_1 + 0x08), 751);
+        pwm.set_duty_cycle_fully_on().unwrap();
+        assert_eq!(pwm.timer.get_duty(), 1_000);
+        assert_eq!(crate::mock::read(mmio::TIMER_1 + 0x08), 0);
+        pwm.set_duty_cycle_fully_off().unwrap();
+        assert_eq!(crate::mock::read(mmio::TIMER_1 + 0x08), 1_000);
+    }
+
+    #[cfg(all(feature = "mock-mmio", feature = "eh0"))]
+    #[test]
+    fn pwm_pin_trait() {
+        use crate::memory_map::mmio;
+        use embedded_hal_0_2::PwmPin;
+
+        let mut pwm = channel(1_000);
+
+        // The duty is the high part of the period, the output going high at the match
+        let max = PwmPin::get_max_duty(&pwm);
+        PwmPin::set_duty(&mut pwm, max / 10);
+        assert_eq!(pwm.timer.get_duty(), 99);
+        assert_eq!(crate::mock::read(mmio::TIMER_1 + 0x08), 901);
+    }
+}
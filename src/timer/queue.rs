@@ -0,0 +1,358 @@
+//! # Software Timer Queue
+//! Many software timers on one hardware timer, for when four are not enough. The timer
+//! counts freely in compare mode, and the compare value is always moved to the nearest
+//! deadline in the queue. At each match `on_interrupt` calls back every timer that is
+//! due, and moves the compare value on to the next one.
+//!
+//! Deadlines are kept as 32-bit counts, so a timeout can be at most `MAX_TICKS` ahead.
+//! The callbacks run in the timer's interrupt, so keep them short.
+//!
+//! # Example
+//! ```no_run
+//! use core::cell::RefCell;
+//! use critical_section::Mutex;
+//! use max78000_hal::bind_interrupts;
+//! use max78000_hal::interrupt::typelevel::{Handler, TMR1};
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::time::ExtU32;
+//! use max78000_hal::timer::queue::SoftTimerQueue;
+//! use max78000_hal::timer::registers::TimerPrescaler;
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//! # let timer = Timer::new(TimerInstance::<{ mmio::TIMER_1 }>::instance())?;
+//! # fn toggle_led() {}
+//! # fn sample() {}
+//!
+//! static QUEUE: Mutex<RefCell<Option<SoftTimerQueue<{ mmio::TIMER_1 }, 8>>>> =
+//!     Mutex::new(RefCell::new(None));
+//!
+//! struct Tick;
+//! impl Handler<TMR1> for Tick {
+//!     unsafe fn on_interrupt() {
+//!         critical_section::with(|cs| {
+//!             if let Some(queue) = QUEUE.borrow_ref_mut(cs).as_mut() {
+//!                 queue.on_interrupt();
+//!             }
+//!         });
+//!     }
+//! }
+//!
+//! bind_interrupts!(struct Irqs { TMR1 => Tick; });
+//!
+//! let mut queue = SoftTimerQueue::new(timer, TimerPrescaler::Div64);
+//! let blink = queue.schedule_periodic(500.millis(), toggle_led)?;
+//! queue.schedule(100.millis(), sample)?;
+//! critical_section::with(|cs| QUEUE.replace(cs, Some(queue)));
+//! // ...
+//! critical_section::with(|cs| QUEUE.borrow_ref_mut(cs).as_mut().map(|queue| queue.cancel(blink)));
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::registers::TimerPrescaler;
+use super::{duration_ticks, mode, Timer};
+use crate::error::{ErrorKind, Result};
+use crate::time::Micros;
+
+/// The furthest a deadline can be ahead of the count, in ticks. Any further and it
+/// could not be told apart from one that passed.
+pub const MAX_TICKS: u32 = i32::MAX as u32;
+
+/// # Soft Timer
+/// The handle of a timer in a `SoftTimerQueue`, to cancel it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SoftTimer(usize);
+
+/// # Entry
+/// A scheduled timer.
+#[derive(Clone, Copy)]
+struct Entry {
+    /// The count to call back at.
+    deadline: u32,
+    /// The ticks to the next deadline, or `0` to run once.
+    period: u32,
+    callback: fn(),
+}
+
+/// # Soft Timer Queue
+/// Up to `N` software timers on the timer at `PORT`.
+pub struct SoftTimerQueue<const PORT: usize, const N: usize> {
+    timer: Timer<PORT, mode::Compare>,
+    entries: [Option<Entry>; N],
+}
+
+// The entries are plain `fn` pointers and counts, and the timer is the queue's alone, so
+// handing the queue to the interrupt that runs it is fine
+unsafe impl<const PORT: usize, const N: usize> Send for SoftTimerQueue<PORT, N> {}
+
+impl<const PORT: usize, const N: usize> SoftTimerQueue<PORT, N> {
+    /// # New
+    /// Start `timer` counting the peripheral clock divided by `prescaler`, with no timers
+    /// scheduled. The interrupt of the timer must be bound to a handler calling
+    /// `on_interrupt` and enabled in the NVIC.
+    pub fn new(timer: Timer<PORT, mode::Disabled>, prescaler: TimerPrescaler) -> Self {
+        let mut timer = timer.into_compare(prescaler, 0);
+        // Only the interrupt is used, not the output toggling at every deadline
+        unsafe { timer.registers.set_output_enable(false) };
+        timer.start();
+
+        Self {
+            timer,
+            entries: [None; N],
+        }
+    }
+
+    /// # Schedule
+    /// Call `callback` once, `delay` from now, rounded to the nearest tick.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` when `delay` is under a tick or over `MAX_TICKS`.
+    /// - `ErrorKind::Overflow` when all `N` timers are scheduled.
+    pub fn schedule(&mut self, delay: Micros, callback: fn()) -> Result<SoftTimer> {
+        let ticks = duration_ticks(delay, self.timer.tick_rate())?;
+        self.schedule_ticks(ticks, callback)
+    }
+
+    /// # Schedule Ticks
+    /// Call `callback` once, `ticks` from now.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` when `ticks` is zero or over `MAX_TICKS`.
+    /// - `ErrorKind::Overflow` when all `N` timers are scheduled.
+    pub fn schedule_ticks(&mut self, ticks: u32, callback: fn()) -> Result<SoftTimer> {
+        self.insert(ticks, 0, callback)
+    }
+
+    /// # Schedule Periodic
+    /// Call `callback` every `period` from now, rounded to the nearest tick, until it is
+    /// cancelled. The period is kept from deadline to deadline, so a late interrupt does
+    /// not add drift.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` when `period` is under a tick or over `MAX_TICKS`.
+    /// - `ErrorKind::Overflow` when all `N` timers are scheduled.
+    pub fn schedule_periodic(&mut self, period: Micros, callback: fn()) -> Result<SoftTimer> {
+        let ticks = duration_ticks(period, self.timer.tick_rate())?;
+        self.schedule_periodic_ticks(ticks, callback)
+    }
+
+    /// # Schedule Periodic Ticks
+    /// Call `callback` every `period` ticks from now, until it is cancelled, see
+    /// `schedule_periodic`.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` when `period` is zero or over `MAX_TICKS`.
+    /// - `ErrorKind::Overflow` when all `N` timers are scheduled.
+    pub fn schedule_periodic_ticks(&mut self, period: u32, callback: fn()) -> Result<SoftTimer> {
+        self.insert(period, period, callback)
+    }
+
+    /// # Cancel
+    /// Remove `timer` from the queue, returning `false` when it already ran (or was
+    /// cancelled). A handle is reused once its timer is gone, so only cancel timers
+    /// that are still pending.
+    pub fn cancel(&mut self, timer: SoftTimer) -> bool {
+        let entry = self.entries.get_mut(timer.0).and_then(Option::take);
+        self.rearm();
+        entry.is_some()
+    }
+
+    /// # Is Pending
+    /// Check if `timer` has not run (or been cancelled) yet.
+    pub fn is_pending(&self, timer: SoftTimer) -> bool {
+        matches!(self.entries.get(timer.0), Some(Some(_)))
+    }
+
+    /// # Len
+    /// The number of timers scheduled.
+    pub fn len(&self) -> usize {
+        self.entries.iter().flatten().count()
+    }
+
+    /// # Is Empty
+    /// Check if no timers are scheduled.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// # Now
+    /// The count of the timer, which deadlines are relative to.
+    pub fn now(&self) -> u32 {
+        self.timer.count()
+    }
+
+    /// # On Interrupt
+    /// Call back the timers that are due, and wait for the next one. Call this from the
+    /// timer's interrupt handler.
+    pub fn on_interrupt(&mut self) {
+        self.timer.clear_interrupt();
+        self.rearm();
+    }
+
+    /// # Free
+    /// Drop every timer, and give back the hardware timer disabled.
+    pub fn free(self) -> Timer<PORT, mode::Disabled> {
+        let mut timer = self.timer;
+        timer.enable_interrupt(false);
+        timer.into_disabled()
+    }
+
+    /// # Insert
+    /// Put a timer in the first free slot, due `ticks` from now.
+    fn insert(&mut self, ticks: u32, period: u32, callback: fn()) -> Result<SoftTimer> {
+        if ticks == 0 || ticks > MAX_TICKS {
+            return Err(ErrorKind::BadParam);
+        }
+
+        let index = self
+            .entries
+            .iter()
+            .position(Option::is_none)
+            .ok_or(ErrorKind::Overflow)?;
+
+        self.entries[index] = Some(Entry {
+            deadline: self.now().wrapping_add(ticks),
+            period,
+            callback,
+        });
+        self.rearm();
+        Ok(SoftTimer(index))
+    }
+
+    /// # Rearm
+    /// Run the timers that are due, and move the compare value to the next deadline. The
+    /// count is checked again after moving it, in case it passed the deadline already.
+    fn rearm(&mut self) {
+        loop {
+            let now = self.now();
+            self.run_due(now);
+
+            let next = match self.next_deadline(now) {
+                Some(next) => next,
+                None => {
+                    self.timer.enable_interrupt(false);
+                    return;
+                }
+            };
+
+            self.timer.set_compare(next);
+            self.timer.clear_interrupt();
+            self.timer.enable_interrupt(true);
+
+            if !is_due(next, self.now()) {
+                return;
+            }
+        }
+    }
+
+    /// # Run Due
+    /// Call back every timer due at `now`, rescheduling the periodic ones.
+    fn run_due(&mut self, now: u32) {
+        for slot in self.entries.iter_mut() {
+            let entry = match slot {
+                Some(entry) if is_due(entry.deadline, now) => entry,
+                _ => continue,
+            };
+
+            let callback = entry.callback;
+            match entry.period {
+                0 => *slot = None,
+                period => entry.deadline = entry.deadline.wrapping_add(period),
+            }
+            callback();
+        }
+    }
+
+    /// # Next Deadline
+    /// The nearest deadline from `now`, which is already due when a periodic timer fell
+    /// more than a period behind.
+    fn next_deadline(&self, now: u32) -> Option<u32> {
+        self.entries
+            .iter()
+            .flatten()
+            .map(|entry| entry.deadline)
+            .min_by_key(|&deadline| match is_due(deadline, now) {
+                true => 0,
+                false => deadline.wrapping_sub(now),
+            })
+    }
+}
+
+/// # Is Due
+/// Check if the count `now` reached `deadline`, across the wrap of the count.
+fn is_due(deadline: u32, now: u32) -> bool {
+    now.wrapping_sub(deadline) <= MAX_TICKS
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn due_across_wrap() {
+        assert!(is_due(100, 100));
+        assert!(is_due(100, 101));
+        assert!(!is_due(100, 99));
+        assert!(is_due(u32::MAX - 5, 10));
+        assert!(!is_due(10, u32::MAX - 5));
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn runs_timers_in_order() {
+        use super::super::test_timer;
+        use crate::memory_map::mmio;
+        use core::sync::atomic::{AtomicU32, Ordering};
+
+        static ONCE: AtomicU32 = AtomicU32::new(0);
+        static PERIODIC: AtomicU32 = AtomicU32::new(0);
+
+        fn once() {
+            ONCE.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn periodic() {
+            PERIODIC.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let timer: Timer<{ mmio::TIMER_2 }> = test_timer();
+        let cnt = mmio::TIMER_2;
+        let cmp = mmio::TIMER_2 + 0x04;
+        let mut queue: SoftTimerQueue<{ mmio::TIMER_2 }, 2> =
+            SoftTimerQueue::new(timer, TimerPrescaler::Div1);
+        assert!(queue.is_empty());
+
+        crate::mock::write(cnt, 1_000);
+        let tick = queue.schedule_periodic_ticks(100, periodic).unwrap();
+        let single = queue.schedule_ticks(250, once).unwrap();
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.schedule_ticks(10, once), Err(ErrorKind::Overflow));
+        assert_eq!(queue.schedule_ticks(0, once), Err(ErrorKind::BadParam));
+        assert_eq!(crate::mock::read(cmp), 1_100);
+        assert!(queue.timer.registers.get_timera_interrupt_enable());
+
+        crate::mock::write(cnt, 1_105);
+        queue.on_interrupt();
+        assert_eq!(PERIODIC.load(Ordering::Relaxed), 1);
+        assert_eq!(crate::mock::read(cmp), 1_200);
+
+        // Late, both are due
+        crate::mock::write(cnt, 1_260);
+        queue.on_interrupt();
+        assert_eq!(PERIODIC.load(Ordering::Relaxed), 2);
+        assert_eq!(ONCE.load(Ordering::Relaxed), 1);
+        assert!(!queue.is_pending(single));
+        assert_eq!(crate::mock::read(cmp), 1_300);
+
+        assert!(queue.cancel(tick));
+        assert!(!queue.cancel(tick));
+        assert!(queue.is_empty());
+        assert!(!queue.timer.registers.get_timera_interrupt_enable());
+
+        // The host runs the peripheral clock at 50 MHz, 50 ticks to the microsecond
+        crate::mock::write(cnt, 2_000);
+        queue.schedule(Micros::from_ticks(3), once).unwrap();
+        assert_eq!(crate::mock::read(cmp), 2_150);
+        assert_eq!(
+            queue.schedule_periodic(Micros::from_ticks(0), periodic),
+            Err(ErrorKind::BadParam)
+        );
+    }
+}
@@ -24,7 +24,14 @@ mod rro {
 }
 
 make_device! {
-    device_ports(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2);
+    device_ports(
+        mmio::TIMER_0,
+        mmio::TIMER_1,
+        mmio::TIMER_2,
+        mmio::TIMER_3,
+        mmio::LOW_POWER_TIMER_0,
+        mmio::LOW_POWER_TIMER_1
+    );
 
     /// Timer Count Register. See Page 315, Table 19-9.
     /// Register holds current value of timer
@@ -59,6 +66,7 @@ make_device! {
     /// - 1: Disabled
     /// Note: always reads as 0 if the timer is currently a 32-bit cascade timer.
     #[bit(24, RW, rro::TMR_INTFL)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_write_protect_in_dual_timer_mode,
 
     /// TimerB Write Done. See Page 315-316, Table 19-12.
@@ -66,6 +74,7 @@ make_device! {
     /// - 0: Currently writing
     /// - 1: Write complete/Not writing
     #[bit(25, RO, rro::TMR_INTFL)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_write_done,
 
     /// TimerB Interrupt Event. See Page 315-316, Table 19-12.
@@ -73,10 +82,12 @@ make_device! {
     /// - 0: No event occurred
     /// - 1: Interrupt occurred (Write 1 to clear)
     #[bit(16, RW1C, rro::TMR_INTFL)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_interrupt_event,
 
     /// TimerB Dual Timer Mode Write Protect. See Page 315-316, Table 19-12.
     #[bit(9, RW, rro::TMR_INTFL)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_dual_timer_mode_write_protect,
 
     /// TimerA Write Done. See Page 315-316, Table 19-12.
@@ -90,22 +101,35 @@ make_device! {
     /// Timer Control 0 Register. See Page 316-319, Table 19-13.
     /// TimerB Enable. See Page 316-319, Table 19-13.
     #[bit(31, RW, rro::TMR_CTRL0)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_enable,
 
     /// TimerB Clock Enable. See Page 316-319, Table 19-13.
     #[bit(30, RW, rro::TMR_CTRL0)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_clock_enable,
 
     /// TimerB Reset. See Page 316-319, Table 19-13.
     #[bit(29, RW1O, rro::TMR_CTRL0)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timeb_reset,
 
     /// TimerB Prescaler Select. See Page 316-319, Table 19-13.
     #[bit(20..=23, RW, rro::TMR_CTRL0)]
+    #[values(
+        TimerPrescaler: Div1 = 0, Div2 = 1, Div4 = 2, Div8 = 3, Div16 = 4, Div32 = 5, Div64 = 6,
+        Div128 = 7, Div256 = 8, Div512 = 9, Div1024 = 10, Div2048 = 11, Div4096 = 12
+    )]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_prescaler_select,
 
     /// TimerB Mode Select. See Page 316-319, Table 19-13.
     #[bit(16..=19, RW, rro::TMR_CTRL0)]
+    #[values(
+        TimerMode: OneShot = 0, Continuous = 1, Counter = 2, Pwm = 3, Capture = 4, Compare = 5,
+        Gated = 6, CaptureCompare = 7, DualEdgeCapture = 8, InactiveGated = 14
+    )]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_mode_select,
 
     /// TimerA Enable. See Page 316-319, Table 19-13.
@@ -142,10 +166,18 @@ make_device! {
 
     /// TimerA Prescaler Select. See Page 316-319, Table 19-13.
     #[bit(4..=7, RW, rro::TMR_CTRL0)]
+    #[values(
+        TimerPrescaler: Div1 = 0, Div2 = 1, Div4 = 2, Div8 = 3, Div16 = 4, Div32 = 5, Div64 = 6,
+        Div128 = 7, Div256 = 8, Div512 = 9, Div1024 = 10, Div2048 = 11, Div4096 = 12
+    )]
     timera_prescaler_select,
 
     /// TimerA Mode Select. See Page 316-319, Table 19-13.
     #[bit(0..=3, RW, rro::TMR_CTRL0)]
+    #[values(
+        TimerMode: OneShot = 0, Continuous = 1, Counter = 2, Pwm = 3, Capture = 4, Compare = 5,
+        Gated = 6, CaptureCompare = 7, DualEdgeCapture = 8, InactiveGated = 14
+    )]
     timera_mode_select,
 
     /// Timer Non-Overlapping Compare Register. See Page 319, Table 19-14.
@@ -168,46 +200,57 @@ make_device! {
     /// Timer Control 1 Register. See Page 319-321, Table 19-15.
     /// 32-bit Cascade Timer Enable. See Page 319-321, Table 19-15.
     #[bit(31, RW, rro::TMR_CTRL1)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     bit32_cascade_timer_enable,
 
     /// TimerB Wake-Up Function. See Page 319-321, Table 19-15.
     #[bit(28, RW, rro::TMR_CTRL1)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_wakeup_function,
 
     /// TimerB Software Event Capture. See Page 319-321, Table 19-15.
     #[bit(27, RW, rro::TMR_CTRL1)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_software_event_capture,
 
     /// TimerB Event Capture Selection. See Page 319-321, Table 19-15.
     #[bit(25..=26, RW, rro::TMR_CTRL1)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_event_capture_selection,
 
     /// TimerB Interrupt Enable. See Page 319-321, Table 19-15.
     #[bit(24, RW, rro::TMR_CTRL1)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_interrupt_enable,
 
     /// TimerB Negative Edge Trigger for Event. See Page 319-321, Table 19-15.
     #[bit(23, RW, rro::TMR_CTRL1)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_negative_edge_trigger_for_event,
 
     /// TimerB Event Selection. See Page 319-321, Table 19-15.
     #[bit(20..=22, RW, rro::TMR_CTRL1)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_event_selection,
 
     /// TimerB Clock Ready Status. See Page 319-321, Table 19-15.
     #[bit(19, RO, rro::TMR_CTRL1)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_clock_ready_status,
 
     /// TimerB Clock Enable Status. See Page 319-321, Table 19-15.
     #[bit(18, RO, rro::TMR_CTRL1)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_clock_enable_status,
 
     /// TimerB Clock Source. See Page 319-321, Table 19-15.
     #[bit(16..=17, RW, rro::TMR_CTRL1)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_clock_source,
 
     /// Output B Enable. See Page 319-321, Table 19-15.
     #[bit(14, RW, rro::TMR_CTRL1)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     output_b_enable,
 
     /// Output Enable. See Page 319-321, Table 19-15.
@@ -253,6 +296,7 @@ make_device! {
     /// Timer Wake-Up Status Register. See Page 321-322, Table 19-16.
     /// TimerB Wake-Up Event. See Page 321-322, Table 19-16.
     #[bit(16, RW1C, rro::TMR_WKFL)]
+    #[instances(mmio::TIMER_0, mmio::TIMER_1, mmio::TIMER_2, mmio::TIMER_3)]
     timerb_wakeup_event,
 
     /// TimerA Wake-Up Event. See Page 321-322, Table 19-16.
@@ -0,0 +1,258 @@
+//! # RTIC Monotonic
+//! A `rtic_time` monotonic on TMR0, so RTIC 2 tasks can `Mono::delay` and time out on
+//! this HAL's timer. Both halves of TMR0 are chained in compare mode, counting freely
+//! through the whole 32-bit range, and rtic-time's half period counter extends the count
+//! to 64 bits. The compare value follows the first wake-up in the timer queue, but never
+//! goes past the end of the current half of the range, so every half is counted.
+//!
+//! The tick rate is `Tmr0Monotonic`'s const generic, and must be the peripheral clock
+//! divided by a power of two (ie. 1.5625 MHz at a 50 MHz peripheral clock). Bind
+//! `InterruptHandler` to `TMR0`, nothing else may use TMR0 or its interrupt. `rtic_time`
+//! needs a `critical-section` implementation, like any RTIC 2 app already has.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::bind_interrupts;
+//! use max78000_hal::time::ExtU64;
+//! use max78000_hal::timer::rtic::{self, Tmr0Monotonic};
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//! use rtic_time::Monotonic;
+//!
+//! type Mono = Tmr0Monotonic<1_562_500>;
+//!
+//! bind_interrupts!(struct Irqs {
+//!     TMR0 => rtic::InterruptHandler;
+//! });
+//!
+//! // In the app's `#[init]`
+//! Mono::start(Timer::new(TimerInstance::instance())?)?;
+//!
+//! // A `#[task]`
+//! async fn blink() {
+//!     loop {
+//!         Mono::delay(500.millis()).await;
+//!     }
+//! }
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use rtic_time::half_period_counter::calculate_now;
+use rtic_time::monotonic::TimerQueueBasedMonotonic;
+use rtic_time::timer_queue::{TimerQueue, TimerQueueBackend};
+
+use super::registers::{TimerMode, TimerPrescaler};
+use super::{mode, Timer, TimerInstance};
+use crate::error::{ErrorKind, Result};
+use crate::interrupt::typelevel::{Handler, Interrupt, TMR0};
+use crate::memory_map::mmio;
+use crate::time::Hertz;
+
+/// The registers of TMR0.
+type Registers = TimerInstance<{ mmio::TIMER_0 }>;
+
+/// The halves of the 32-bit range TMR0 went through, even while the count is in the low
+/// half and odd in the high half. Only written by the interrupt handler.
+static HALF_PERIODS: AtomicU32 = AtomicU32::new(0);
+
+/// The tasks waiting on TMR0, ordered by when they wake up.
+static TIMER_QUEUE: TimerQueue<Tmr0Backend> = TimerQueue::new();
+
+/// # Interrupt Handler
+/// Counts the halves of the range and wakes the tasks that are due. Bind it to `TMR0`.
+pub struct InterruptHandler;
+
+impl Handler<TMR0> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        TIMER_QUEUE.on_monotonic_interrupt();
+    }
+}
+
+/// # TMR0 Monotonic
+/// The RTIC monotonic on TMR0, ticking at `TICK_RATE` Hz. `rtic_time` implements
+/// `Monotonic` for it, import that trait to `delay` and `timeout_after`.
+pub struct Tmr0Monotonic<const TICK_RATE: u32>;
+
+impl<const TICK_RATE: u32> Tmr0Monotonic<TICK_RATE> {
+    /// # Start
+    /// Chain both halves of `timer`, start it counting from zero at `TICK_RATE`, and
+    /// enable its NVIC line. Call this once, before the first delay.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when no prescaler divides the peripheral clock down to
+    /// exactly `TICK_RATE`.
+    pub fn start(timer: Timer<{ mmio::TIMER_0 }, mode::Disabled>) -> Result<()> {
+        let prescaler =
+            tick_prescaler(crate::core_peripheral_clock(), TICK_RATE).ok_or(ErrorKind::BadParam)?;
+        Tmr0Backend::start(timer, prescaler);
+        Ok(())
+    }
+}
+
+impl<const TICK_RATE: u32> TimerQueueBasedMonotonic for Tmr0Monotonic<TICK_RATE> {
+    type Backend = Tmr0Backend;
+    type Instant = fugit::Instant<u64, 1, TICK_RATE>;
+    type Duration = fugit::Duration<u64, 1, TICK_RATE>;
+}
+
+/// # TMR0 Backend
+/// The timer queue backend of `Tmr0Monotonic`, reaching TMR0 through its registers.
+pub struct Tmr0Backend;
+
+impl Tmr0Backend {
+    /// Chain both halves of `timer` in compare mode, and start it from zero with the
+    /// compare value at the end of the low half.
+    fn start(mut timer: Timer<{ mmio::TIMER_0 }, mode::Disabled>, prescaler: TimerPrescaler) {
+        unsafe {
+            timer.registers.set_timerb_enable(false);
+            timer.registers.set_bit32_cascade_timer_enable(true);
+        }
+
+        let mut timer: Timer<{ mmio::TIMER_0 }, mode::Compare> =
+            timer.configure(TimerMode::Compare, prescaler, 0);
+        write_cascaded(|registers| unsafe { registers.set_timer_count(0) });
+        HALF_PERIODS.store(0, Ordering::SeqCst);
+        Self::set_compare(u64::MAX);
+
+        timer.clear_interrupt();
+        timer.enable_interrupt(true);
+        TIMER_QUEUE.initialize(Self);
+        unsafe {
+            TMR0::enable();
+            timer.registers.set_timera_enable(true);
+        }
+    }
+}
+
+impl TimerQueueBackend for Tmr0Backend {
+    type Ticks = u64;
+
+    fn now() -> u64 {
+        calculate_now(|| HALF_PERIODS.load(Ordering::Relaxed), count)
+    }
+
+    fn set_compare(instant: u64) {
+        // The end of the half has to interrupt too, or it would not be counted
+        let half_periods = HALF_PERIODS.load(Ordering::Relaxed);
+        let half_end = (half_periods as u64 + 1) << 31;
+        let compare = instant.min(half_end) as u32;
+        write_cascaded(|registers| unsafe { registers.set_timer_compare_value(compare) });
+
+        // The half ended before the compare value landed, so it will not match
+        if count() >> 31 != half_periods & 1 {
+            Self::pend_interrupt();
+        }
+    }
+
+    fn clear_compare_flag() {
+        unsafe { Registers::instance().clear_timera_interrupt_event() };
+    }
+
+    fn pend_interrupt() {
+        TMR0::pend();
+    }
+
+    fn on_interrupt() {
+        // The compare may have been a wake-up in the same half, or the end of it
+        let half_periods = HALF_PERIODS.load(Ordering::Relaxed);
+        if count() >> 31 != half_periods & 1 {
+            HALF_PERIODS.store(half_periods.wrapping_add(1), Ordering::Relaxed);
+        }
+    }
+
+    fn disable_timer() {
+        // Nothing is waiting, but the timer still has to count the end of the half
+        Self::set_compare(u64::MAX);
+    }
+
+    fn timer_queue() -> &'static TimerQueue<Self> {
+        &TIMER_QUEUE
+    }
+}
+
+/// # Count
+/// The 32-bit count, read again if the high half changed while reading.
+fn count() -> u32 {
+    let registers = Registers::instance();
+    loop {
+        let first = registers.get_timer_count();
+        let second = registers.get_timer_count();
+        if first >> 16 == second >> 16 {
+            return second;
+        }
+    }
+}
+
+/// # Write Cascaded
+/// Lift the write protection of the high half, make the write, and wait until both
+/// halves took it. See `cascade`.
+fn write_cascaded(write: impl FnOnce(&mut Registers)) {
+    let mut registers = Registers::instance();
+    unsafe { registers.set_timerb_write_protect_in_dual_timer_mode(true) };
+    write(&mut registers);
+    while !(registers.get_timera_write_done() && registers.get_timerb_write_done()) {}
+}
+
+/// # Tick Prescaler
+/// The prescaler dividing `clock` down to exactly `tick_rate`, if there is one.
+fn tick_prescaler(clock: Hertz, tick_rate: u32) -> Option<TimerPrescaler> {
+    (0..=12)
+        .find(|shift| (tick_rate as u64) << shift == clock.raw() as u64)
+        .and_then(TimerPrescaler::from_bits)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn prescaler_values() {
+        assert!(matches!(
+            tick_prescaler(Hertz::MHz(50), 1_562_500),
+            Some(TimerPrescaler::Div32)
+        ));
+        assert!(matches!(
+            tick_prescaler(Hertz::MHz(50), 50_000_000),
+            Some(TimerPrescaler::Div1)
+        ));
+        assert!(tick_prescaler(Hertz::MHz(50), 1_000_000).is_none());
+        assert!(tick_prescaler(Hertz::MHz(50), 100_000_000).is_none());
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn counts_every_half() {
+        let cnt = mmio::TIMER_0;
+        let cmp = mmio::TIMER_0 + 0x04;
+
+        crate::mock::reset();
+        // Every write is done
+        crate::mock::write(mmio::TIMER_0 + 0x0C, (1 << 25) | (1 << 8));
+        HALF_PERIODS.store(0, Ordering::Relaxed);
+
+        // A wake-up in the low half is compared on
+        crate::mock::write(cnt, 10);
+        Tmr0Backend::set_compare(1_000);
+        assert_eq!(crate::mock::read(cmp), 1_000);
+
+        // A later one stops at the end of the half first
+        Tmr0Backend::set_compare(5 << 30);
+        assert_eq!(crate::mock::read(cmp), 1 << 31);
+
+        crate::mock::write(cnt, (1 << 31) + 5);
+        Tmr0Backend::on_interrupt();
+        Tmr0Backend::on_interrupt();
+        assert_eq!(HALF_PERIODS.load(Ordering::Relaxed), 1);
+
+        Tmr0Backend::set_compare(3 << 30);
+        assert_eq!(crate::mock::read(cmp), 3 << 30);
+
+        // With nothing waiting, it wakes at the wrap
+        Tmr0Backend::disable_timer();
+        assert_eq!(crate::mock::read(cmp), 0);
+
+        crate::mock::write(cnt, 3);
+        Tmr0Backend::on_interrupt();
+        assert_eq!(HALF_PERIODS.load(Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,252 @@
+//! # Timer Wait
+//! Sleep on a timer without spinning on its count. A one-shot timer's interrupt wakes
+//! the waiting task when the count reaches the compare value, so an async executor can
+//! run other tasks (or sleep the core) meanwhile. With the `async` feature a one-shot
+//! `Timer` also implements `embedded_hal_async::delay::DelayNs`.
+//!
+//! Bind `InterruptHandler` to the interrupts of the timers you wait on, the NVIC line
+//! is enabled by the first wait on that timer. A timer is either waited on or has a
+//! callback, so bind this or `callback::InterruptHandler`, not both.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::bind_interrupts;
+//! use max78000_hal::error::Result;
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::timer::{mode, wait, Timer};
+//! use max78000_hal::uart::{UART, UART0};
+//!
+//! bind_interrupts!(struct Irqs {
+//!     TMR1 => wait::InterruptHandler;
+//! });
+//!
+//! // With `timer.into_oneshot_ticks(TimerPrescaler::Div1, 1)`
+//! async fn exchange(timer: &mut Timer<{ mmio::TIMER_1 }, mode::OneShot>, uart: &mut UART<UART0>) -> Result<()> {
+//!     timer.delay_us(250).await;
+//!     let mut reply = [0; 8];
+//!     let read = timer.timeout(10_000, uart.read_async(&mut reply)).await?;
+//!     Ok(())
+//! }
+//! ```
+use core::future::{poll_fn, Future};
+use core::pin::{pin, Pin};
+use core::task::{Context, Poll, Waker};
+
+use super::callback::timer_interrupt;
+use super::{mode, Timer, TimerInstance};
+use crate::error::{ErrorKind, Result};
+use crate::interrupt::typelevel::{Handler, TMR0, TMR1, TMR2, TMR3, TMR4, TMR5};
+use crate::interrupt::InterruptExt;
+use crate::memory_map::mmio;
+use crate::time::Hertz;
+
+/// The waker of the task waiting on every timer. A slot is only written while its
+/// timer's interrupt is disabled, and only taken by the interrupt handler while it is
+/// enabled.
+static mut WAKERS: [Option<Waker>; 6] = [const { None }; 6];
+
+/// # Interrupt Handler
+/// Wakes the task waiting on the timer. Bind it to `TMR0` to `TMR5`.
+pub struct InterruptHandler;
+
+impl Handler<TMR0> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch::<{ mmio::TIMER_0 }>(0);
+    }
+}
+
+impl Handler<TMR1> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch::<{ mmio::TIMER_1 }>(1);
+    }
+}
+
+impl Handler<TMR2> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch::<{ mmio::TIMER_2 }>(2);
+    }
+}
+
+impl Handler<TMR3> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch::<{ mmio::TIMER_3 }>(3);
+    }
+}
+
+impl Handler<TMR4> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch::<{ mmio::LOW_POWER_TIMER_0 }>(4);
+    }
+}
+
+impl Handler<TMR5> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch::<{ mmio::LOW_POWER_TIMER_1 }>(5);
+    }
+}
+
+/// Disable, clear and wake the timer at `PORT` if it interrupted.
+unsafe fn dispatch<const PORT: usize>(index: usize) {
+    let mut timer = TimerInstance::<PORT>::instance();
+
+    if timer.get_timera_interrupt_enable() && timer.is_timera_interrupt_event_active() {
+        timer.set_timera_interrupt_enable(false);
+        timer.clear_timera_interrupt_event();
+        if let Some(waker) = WAKERS[index].take() {
+            waker.wake();
+        }
+    }
+}
+
+/// # Done Future
+/// Completes once a one-shot timer reached its compare value, see `Timer::done`.
+#[must_use = "futures do nothing unless polled"]
+pub struct DoneFuture<'a, const PORT: usize> {
+    timer: &'a mut Timer<PORT, mode::OneShot>,
+    armed: bool,
+}
+
+impl<const PORT: usize> DoneFuture<'_, PORT> {
+    /// The index of the timer's waker.
+    fn slot() -> usize {
+        timer_interrupt(PORT).map_or(0, |(index, _)| index)
+    }
+}
+
+impl<const PORT: usize> Future for DoneFuture<'_, PORT> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if !self.armed && self.timer.is_done() {
+            self.timer.clear_interrupt();
+            return Poll::Ready(());
+        }
+
+        if self.armed {
+            // Stop the handler from touching the waker while it is replaced
+            let enabled = self.timer.registers.get_timera_interrupt_enable();
+            self.timer.enable_interrupt(false);
+
+            if !enabled || self.timer.is_done() {
+                self.timer.clear_interrupt();
+                self.armed = false;
+                return Poll::Ready(());
+            }
+        } else {
+            if let Some((_, interrupt)) = timer_interrupt(PORT) {
+                unsafe { interrupt.enable() };
+            }
+            self.armed = true;
+        }
+
+        unsafe { WAKERS[Self::slot()] = Some(cx.waker().clone()) };
+        self.timer.enable_interrupt(true);
+
+        Poll::Pending
+    }
+}
+
+impl<const PORT: usize> Drop for DoneFuture<'_, PORT> {
+    fn drop(&mut self) {
+        if self.armed {
+            self.timer.enable_interrupt(false);
+            unsafe { WAKERS[Self::slot()] = None };
+        }
+    }
+}
+
+impl<const PORT: usize> Timer<PORT, mode::OneShot> {
+    /// # Done
+    /// Wait until the count reached the compare value, and clear the flag. The timer
+    /// must be started, and `InterruptHandler` bound to its interrupt.
+    pub fn done(&mut self) -> DoneFuture<'_, PORT> {
+        DoneFuture {
+            timer: self,
+            armed: false,
+        }
+    }
+
+    /// # Delay Ticks
+    /// Count `ticks` (at least one) from the start, and wait until they passed.
+    pub async fn delay_ticks(&mut self, ticks: u32) {
+        self.stop();
+        self.clear_interrupt();
+        self.set_compare(ticks.max(1));
+        self.start();
+        self.done().await;
+    }
+
+    /// # Delay Us
+    /// Wait at least `us` microseconds.
+    pub async fn delay_us(&mut self, us: u32) {
+        let ticks = ticks_in(us as u64 * 1_000, self.tick_rate());
+        self.delay_ticks(ticks).await;
+    }
+
+    /// # Timeout
+    /// Run `future` for at most `us` microseconds.
+    ///
+    /// # Errors
+    /// `ErrorKind::TimeOut` when `future` did not complete in time, it is dropped then.
+    pub async fn timeout<F: Future>(&mut self, us: u32, future: F) -> Result<F::Output> {
+        let mut future = pin!(future);
+        let mut delay = pin!(self.delay_us(us));
+
+        poll_fn(|cx| {
+            if let Poll::Ready(output) = future.as_mut().poll(cx) {
+                return Poll::Ready(Ok(output));
+            }
+            delay.as_mut().poll(cx).map(|_| Err(ErrorKind::TimeOut))
+        })
+        .await
+    }
+}
+
+/// # Ticks In
+/// The ticks at `tick_rate` in `ns` nanoseconds, rounded up and limited to `u32`.
+fn ticks_in(ns: u64, tick_rate: Hertz) -> u32 {
+    let ticks = (ns as u128 * tick_rate.raw() as u128).div_ceil(1_000_000_000);
+    ticks.min(u32::MAX as u128) as u32
+}
+
+#[cfg(feature = "async")]
+impl<const PORT: usize> embedded_hal_async::delay::DelayNs for Timer<PORT, mode::OneShot> {
+    async fn delay_ns(&mut self, ns: u32) {
+        let ticks = ticks_in(ns as u64, self.tick_rate());
+        self.delay_ticks(ticks).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rounds_ticks_up() {
+        assert_eq!(ticks_in(1_000, Hertz::MHz(50)), 50);
+        assert_eq!(ticks_in(1, Hertz::MHz(50)), 1);
+        assert_eq!(ticks_in(30_000, Hertz::Hz(32_768)), 1);
+        assert_eq!(ticks_in(u64::MAX, Hertz::MHz(50)), u32::MAX);
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn interrupt_wakes_done() {
+        use super::super::test_timer;
+        use crate::interrupt::Interrupt;
+
+        let mut timer: Timer<{ mmio::TIMER_2 }, mode::OneShot> = test_timer();
+        let intfl = mmio::TIMER_2 + 0x0C;
+
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut future = timer.done();
+        assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Pending);
+        assert!(Interrupt::TMR2.is_enabled());
+        assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Pending);
+
+        crate::mock::write(intfl, 1);
+        unsafe { <InterruptHandler as Handler<TMR2>>::on_interrupt() };
+        assert!(!future.timer.registers.get_timera_interrupt_enable());
+        assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Ready(()));
+    }
+}
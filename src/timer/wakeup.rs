@@ -0,0 +1,128 @@
+//! # Timer Wake-Up
+//! Timers as wake-up sources for the low power modes. A timer armed with
+//! `enable_wakeup` flags its wake-up event in `TMR_WKFL` when it reaches its compare
+//! value while the core sleeps, see `power::deep_sleep`. After waking, `woken_by` tells
+//! which timer it was.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::power;
+//! use max78000_hal::time::ExtU32;
+//! use max78000_hal::timer::registers::TimerPrescaler;
+//! use max78000_hal::timer::wakeup::woken_by;
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//! # let timer = Timer::new(TimerInstance::<{ mmio::TIMER_1 }>::instance())?;
+//!
+//! let mut timer = timer.into_oneshot(TimerPrescaler::Div4096, 1.secs())?;
+//! timer.enable_interrupt(true);
+//! timer.enable_wakeup(true);
+//! timer.start();
+//!
+//! power::deep_sleep();
+//! if woken_by() == Some(mmio::TIMER_1) {
+//!     timer.clear_wakeup();
+//! }
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::{Timer, TimerInstance};
+use crate::memory_map::mmio;
+
+impl<const PORT: usize, MODE> Timer<PORT, MODE> {
+    /// # Enable Wake-Up
+    /// Arm/Disarm the timer as a wake-up source from low power modes.
+    pub fn enable_wakeup(&mut self, enable: bool) {
+        unsafe { self.registers.set_timera_wakeup_function(enable) };
+    }
+
+    /// # Is Wake-Up Pending
+    /// Check if the timer flagged a wake-up event since it was last cleared.
+    pub fn is_wakeup_pending(&self) -> bool {
+        self.registers.is_timera_wakeup_event_active()
+    }
+
+    /// # Clear Wake-Up
+    /// Clear the timer's wake-up event flag.
+    pub fn clear_wakeup(&mut self) {
+        unsafe { self.registers.clear_timera_wakeup_event() };
+    }
+}
+
+/// # Woken By
+/// The port of the first timer with a wake-up event flagged, ie. `mmio::TIMER_1`, also
+/// checking both halves of dual timers and the low power timers.
+pub fn woken_by() -> Option<usize> {
+    if has_wakeup::<{ mmio::TIMER_0 }>() {
+        Some(mmio::TIMER_0)
+    } else if has_wakeup::<{ mmio::TIMER_1 }>() {
+        Some(mmio::TIMER_1)
+    } else if has_wakeup::<{ mmio::TIMER_2 }>() {
+        Some(mmio::TIMER_2)
+    } else if has_wakeup::<{ mmio::TIMER_3 }>() {
+        Some(mmio::TIMER_3)
+    } else if has_low_power_wakeup::<{ mmio::LOW_POWER_TIMER_0 }>() {
+        Some(mmio::LOW_POWER_TIMER_0)
+    } else if has_low_power_wakeup::<{ mmio::LOW_POWER_TIMER_1 }>() {
+        Some(mmio::LOW_POWER_TIMER_1)
+    } else {
+        None
+    }
+}
+
+/// # Clear Wake-Up Flags
+/// Clear the wake-up event flags of every timer.
+pub fn clear_wakeup_flags() {
+    clear_wakeup::<{ mmio::TIMER_0 }>();
+    clear_wakeup::<{ mmio::TIMER_1 }>();
+    clear_wakeup::<{ mmio::TIMER_2 }>();
+    clear_wakeup::<{ mmio::TIMER_3 }>();
+
+    unsafe {
+        TimerInstance::<{ mmio::LOW_POWER_TIMER_0 }>::instance().clear_timera_wakeup_event();
+        TimerInstance::<{ mmio::LOW_POWER_TIMER_1 }>::instance().clear_timera_wakeup_event();
+    }
+}
+
+/// # Has Wake-Up
+/// Check either half of the timer at `PORT` for a wake-up event.
+fn has_wakeup<const PORT: usize>() -> bool {
+    let timer = TimerInstance::<PORT>::instance();
+    timer.is_timera_wakeup_event_active() || timer.is_timerb_wakeup_event_active()
+}
+
+/// # Has Low Power Wake-Up
+/// Check the low power timer at `PORT`, which has no second half, for a wake-up event.
+fn has_low_power_wakeup<const PORT: usize>() -> bool {
+    TimerInstance::<PORT>::instance().is_timera_wakeup_event_active()
+}
+
+/// # Clear Wake-Up
+/// Clear both halves of the timer at `PORT`.
+fn clear_wakeup<const PORT: usize>() {
+    let mut timer = TimerInstance::<PORT>::instance();
+    unsafe {
+        timer.clear_timera_wakeup_event();
+        timer.clear_timerb_wakeup_event();
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn finds_woken_timer() {
+        crate::mock::reset();
+        assert_eq!(woken_by(), None);
+
+        crate::mock::write(mmio::TIMER_2 + 0x1C, 1 << 16);
+        assert_eq!(woken_by(), Some(mmio::TIMER_2));
+
+        crate::mock::write(mmio::TIMER_2 + 0x1C, 0);
+        crate::mock::write(mmio::LOW_POWER_TIMER_1 + 0x1C, 1);
+        assert_eq!(woken_by(), Some(mmio::LOW_POWER_TIMER_1));
+
+        crate::mock::write(mmio::TIMER_0 + 0x1C, 1);
+        assert_eq!(woken_by(), Some(mmio::TIMER_0));
+    }
+}
@@ -0,0 +1,350 @@
+//! # Capacitive Touch
+//! Simple touch buttons without a touch controller. A pad is a copper area wired to a
+//! timer input pin, with a large resistor (ie. 1 MΩ) to VDD. The pin first discharges
+//! the pad, then lets go, and the timer in capture mode measures how long the pad
+//! takes to charge back up through the resistor. A finger adds capacitance, so a
+//! touched pad takes longer.
+//!
+//! The charge time slowly drifts with temperature and humidity, so every button keeps
+//! a baseline of its untouched charge time and detects a touch as a rise above it.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::gpio::{GpioPin, GpioSelect, OutputDriveStrength, PinFunction, VoltageSelect};
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::timer::TimerInstance;
+//! use max78000_hal::touch::TouchButton;
+//!
+//! # fn main() -> max78000_hal::error::Result<()> {
+//! let led = GpioPin::new(GpioSelect::Gpio2, 0).unwrap();
+//! led.configure_output(OutputDriveStrength::Strength0(VoltageSelect::VddIO), PinFunction::IO);
+//!
+//! let pin = GpioPin::new(GpioSelect::Gpio0, 2).unwrap();
+//! let mut button = TouchButton::new(TimerInstance::<{ mmio::TIMER_1 }>::instance(), pin, PinFunction::AF2)?;
+//! button.calibrate(16)?;
+//!
+//! loop {
+//!     if let Some(touched) = button.poll()? {
+//!         led.set_output(touched);
+//!     }
+//! }
+//! # }
+//! ```
+use crate::error::{ErrorKind, Result};
+use crate::gcr::{peripheral_reset, system_clock_enable};
+use crate::gpio::{GpioPin, OutputDriveStrength, PinFunction, ResistorStrength, VoltageSelect};
+use crate::time::Micros;
+use crate::timer::registers::{TimerMode, TimerPrescaler};
+use crate::timer::{duration_ticks, hardware_source, TimerInstance};
+
+/// # Default Timeout
+/// The longest charge time measured, in timer clocks, before giving up. Long enough for
+/// a 1 MΩ resistor and a large pad at 50 MHz.
+pub const DEFAULT_TIMEOUT: u32 = 0x0001_0000;
+
+/// # Discharge Cycles
+/// How long the pin drives the pad low before a measurement, in busy loops.
+const DISCHARGE_CYCLES: u32 = 64;
+
+/// # Baseline
+/// The untouched charge time of a pad, and the touch detection on top of it.
+///
+/// The baseline follows samples with a exponential moving average (`1 / 2^shift` of the
+/// difference per sample) while the pad is not touched, and is frozen while it is. A
+/// touch starts at `threshold` above the baseline, and ends below half of that.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Baseline {
+    // Fixed point, with `shift` fractional bits
+    average: u32,
+    shift: u8,
+    threshold: u32,
+    touched: bool,
+}
+
+impl Baseline {
+    /// # New
+    /// Start the baseline at `initial`, detecting a touch at `threshold` clocks above it.
+    pub const fn new(initial: u32, threshold: u32, shift: u8) -> Self {
+        Self {
+            average: initial << shift,
+            shift,
+            threshold,
+            touched: false,
+        }
+    }
+
+    /// # Value
+    /// The current baseline, in timer clocks.
+    pub fn value(&self) -> u32 {
+        self.average >> self.shift
+    }
+
+    /// # Threshold
+    /// How far above the baseline a sample has to be to start a touch.
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    /// # Is Touched
+    /// Check if the last sample was a touch.
+    pub fn is_touched(&self) -> bool {
+        self.touched
+    }
+
+    /// # Update
+    /// Feed a new charge time. Returns `Some(touched)` when the touch state changed.
+    pub fn update(&mut self, sample: u32) -> Option<bool> {
+        let baseline = self.value();
+        let rise = sample.saturating_sub(baseline);
+
+        let touched = match self.touched {
+            false => rise >= self.threshold,
+            true => rise >= self.threshold / 2,
+        };
+
+        if !touched {
+            self.average = self.average - (self.average >> self.shift) + sample;
+        }
+
+        match touched == self.touched {
+            true => None,
+            false => {
+                self.touched = touched;
+                Some(touched)
+            }
+        }
+    }
+}
+
+/// # Touch Button
+/// A touch pad on the input pin of the timer at `PORT`.
+pub struct TouchButton<const PORT: usize> {
+    timer: TimerInstance<PORT>,
+    pin: GpioPin,
+    function: PinFunction,
+    timeout: u32,
+    baseline: Baseline,
+}
+
+impl<const PORT: usize> TouchButton<PORT> {
+    /// # New
+    /// Set up the timer for capture mode from the peripheral clock. `function` is the
+    /// alternate function connecting `pin` to the timer input. The button starts
+    /// without a baseline, see `calibrate`.
+    ///
+    /// # Errors
+    /// `ErrorKind::NoDevice` when `PORT` is a low power timer.
+    pub fn new(timer: TimerInstance<PORT>, pin: GpioPin, function: PinFunction) -> Result<Self> {
+        let source = hardware_source(PORT)?;
+
+        peripheral_reset(source);
+        system_clock_enable(source, true);
+
+        let mut button = Self {
+            timer,
+            pin,
+            function,
+            timeout: DEFAULT_TIMEOUT,
+            baseline: Baseline::new(0, 0, 4),
+        };
+
+        unsafe {
+            button.timer.set_timera_enable(false);
+            button.timer.set_timera_clock_source(0);
+            button
+                .timer
+                .set_timera_prescaler_select(TimerPrescaler::Div1);
+            button.timer.set_timera_mode_select(TimerMode::Capture);
+            // Capture the rising edge of the pad charging up
+            button.timer.set_timera_polarity(false);
+            button.timer.set_timer_compare_value(button.timeout);
+            button.timer.set_timera_clock_enable(true);
+        }
+        while !button.timer.get_timera_clock_ready() {}
+
+        Ok(button)
+    }
+
+    /// # Set Timeout
+    /// Set the longest charge time measured, rounded to the nearest timer clock. The
+    /// timer counts the peripheral clock.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when `timeout` is under a clock, or over `u32::MAX` clocks.
+    pub fn set_timeout(&mut self, timeout: Micros) -> Result<()> {
+        let ticks = duration_ticks(timeout, crate::core_peripheral_clock())?;
+        self.set_timeout_ticks(ticks);
+        Ok(())
+    }
+
+    /// # Set Timeout Ticks
+    /// Set the longest charge time measured, in timer clocks.
+    pub fn set_timeout_ticks(&mut self, timeout: u32) {
+        self.timeout = timeout;
+        unsafe { self.timer.set_timer_compare_value(timeout) };
+    }
+
+    /// # Baseline
+    /// The baseline tracking of this button.
+    pub fn baseline(&self) -> &Baseline {
+        &self.baseline
+    }
+
+    /// # Measure
+    /// Discharge the pad and measure how long it takes to charge, in timer clocks.
+    ///
+    /// # Errors
+    /// `ErrorKind::TimeOut` when the pad did not charge within the timeout, ie. the
+    /// resistor is missing.
+    pub fn measure(&mut self) -> Result<u32> {
+        // Drain the pad
+        self.pin.set_output(false);
+        self.pin.configure_output(
+            OutputDriveStrength::Strength0(VoltageSelect::VddIO),
+            PinFunction::IO,
+        );
+        for _ in 0..DISCHARGE_CYCLES {
+            core::hint::spin_loop();
+        }
+
+        unsafe {
+            self.timer.set_timera_enable(false);
+            self.timer.clear_timera_interrupt_event();
+            self.timer.set_timer_count(1);
+            self.timer.set_pwm(0);
+        }
+
+        // Let go of the pad, and count until it crosses the input threshold
+        self.pin
+            .configure_input(ResistorStrength::None, self.function);
+        unsafe { self.timer.set_timera_enable(true) };
+
+        while !self.timer.is_timera_interrupt_event_active() {}
+
+        unsafe {
+            self.timer.set_timera_enable(false);
+            self.timer.clear_timera_interrupt_event();
+        }
+
+        // The count is captured into the PWM register, it stays zero when the timer
+        // rolled over at the timeout instead
+        match self.timer.get_pwm() {
+            0 => Err(ErrorKind::TimeOut),
+            count => Ok(count),
+        }
+    }
+
+    /// # Calibrate
+    /// Set the baseline to the average of `samples` measurements of the untouched pad,
+    /// with a threshold of one eighth of it.
+    ///
+    /// # Errors
+    /// - `ErrorKind::BadParam` when `samples` is zero.
+    /// - `ErrorKind::TimeOut` when a measurement timed out, see `measure`.
+    pub fn calibrate(&mut self, samples: u32) -> Result<()> {
+        if samples == 0 {
+            return Err(ErrorKind::BadParam);
+        }
+
+        let mut total = 0u64;
+        for _ in 0..samples {
+            total += self.measure()? as u64;
+        }
+
+        let average = (total / samples as u64) as u32;
+        self.baseline = Baseline::new(average, (average / 8).max(1), 4);
+        Ok(())
+    }
+
+    /// # Set Baseline
+    /// Replace the baseline tracking, ie. with a tuned threshold.
+    pub fn set_baseline(&mut self, baseline: Baseline) {
+        self.baseline = baseline;
+    }
+
+    /// # Poll
+    /// Take a measurement and update the baseline. Returns `Some(touched)` when the
+    /// touch state changed.
+    ///
+    /// # Errors
+    /// `ErrorKind::TimeOut` when the measurement timed out, see `measure`.
+    pub fn poll(&mut self) -> Result<Option<bool>> {
+        let sample = self.measure()?;
+        Ok(self.baseline.update(sample))
+    }
+
+    /// # Is Touched
+    /// Check if the pad was touched at the last `poll`.
+    pub fn is_touched(&self) -> bool {
+        self.baseline.is_touched()
+    }
+
+    /// # Free
+    /// Stop the timer, and give back the timer and pin.
+    pub fn free(mut self) -> (TimerInstance<PORT>, GpioPin) {
+        unsafe { self.timer.set_timera_enable(false) };
+        (self.timer, self.pin)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn baseline_tracks_drift() {
+        let mut baseline = Baseline::new(1_000, 100, 2);
+
+        // Slow drift up is followed
+        for _ in 0..32 {
+            assert_eq!(baseline.update(1_040), None);
+        }
+        assert!((1_035..=1_040).contains(&baseline.value()));
+    }
+
+    #[test]
+    fn baseline_detects_touch_with_hysteresis() {
+        let mut baseline = Baseline::new(1_000, 100, 2);
+
+        assert_eq!(baseline.update(1_099), None);
+        let value = baseline.value();
+
+        assert_eq!(baseline.update(value + 100), Some(true));
+        assert!(baseline.is_touched());
+
+        // Frozen while touched, and held until below half the threshold
+        assert_eq!(baseline.update(value + 60), None);
+        assert_eq!(baseline.value(), value);
+        assert_eq!(baseline.update(value + 49), Some(false));
+        assert!(!baseline.is_touched());
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn measure_reads_capture() {
+        use crate::gpio::GpioSelect;
+        use crate::memory_map::mmio;
+
+        crate::mock::reset();
+
+        let mut button = TouchButton {
+            timer: TimerInstance::<{ mmio::TIMER_1 }>::instance(),
+            pin: GpioPin::new(GpioSelect::Gpio0, 2).unwrap(),
+            function: PinFunction::AF2,
+            timeout: DEFAULT_TIMEOUT,
+            baseline: Baseline::new(0, 0, 4),
+        };
+
+        // The mock has no timer, clearing the interrupt event leaves it set and the
+        // zeroed PWM register reads as a timeout
+        assert_eq!(button.measure(), Err(ErrorKind::TimeOut));
+
+        // The host runs the peripheral clock at 50 MHz
+        button.set_timeout(Micros::millis(2)).unwrap();
+        assert_eq!(crate::mock::read(mmio::TIMER_1 + 0x04), 100_000);
+        assert_eq!(
+            button.set_timeout(Micros::from_ticks(0)),
+            Err(ErrorKind::BadParam)
+        );
+    }
+}
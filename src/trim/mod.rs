@@ -0,0 +1,144 @@
+use crate::fcr::registers::Instance as FcrInstance;
+use crate::memory_map::mmio;
+
+pub mod registers;
+
+pub use registers::InroFrequency;
+
+type Trimsir = registers::Instance<{ mmio::TRIM_SYSTEM_INITIALIZATION }>;
+
+/// # Factory Trims
+/// The trims the boot ROM loaded from the info block into the TRIMSIR shadow registers.
+/// Mostly useful for diagnostics, ie. comparing two boards that behave differently.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Trims {
+    /// Capacitor trim of the 32 kHz crystal's X1 pin.
+    pub rtc_x1: u8,
+    /// Capacitor trim of the 32 kHz crystal's X2 pin.
+    pub rtc_x2: u8,
+    /// If the RTC trims are locked.
+    pub rtc_locked: bool,
+    /// The lowest trim the IPO auto-calibration should use.
+    pub ipo_limit_low: u16,
+    /// The highest trim the IPO auto-calibration should use.
+    pub ipo_limit_high: u16,
+    /// The INRO trim.
+    pub inro_trim: u8,
+    /// The INRO trim for the 16 kHz setting.
+    pub inro_trim_16k: u8,
+    /// The INRO trim for the 30 kHz setting.
+    pub inro_trim_30k: u8,
+    /// The frequency the INRO was trimmed for, `None` if the field holds a reserved value.
+    pub inro_frequency: Option<InroFrequency>,
+    /// The VDDA low limit trim.
+    pub vdda_limit_low: u8,
+    /// The VDDA high limit trim.
+    pub vdda_limit_high: u8,
+}
+
+impl Trims {
+    /// # IPO Trim Midpoint
+    /// The trim halfway between the factory IPO limits, where the auto-calibration
+    /// should start from.
+    pub fn ipo_trim_midpoint(&self) -> u16 {
+        let (low, high) = if self.ipo_limit_low <= self.ipo_limit_high {
+            (self.ipo_limit_low, self.ipo_limit_high)
+        } else {
+            (self.ipo_limit_high, self.ipo_limit_low)
+        };
+
+        low + (high - low) / 2
+    }
+}
+
+/// # Read
+/// Read the factory trims out of the TRIMSIR shadow registers.
+pub fn read() -> Trims {
+    let trimsir = Trimsir::instance();
+
+    Trims {
+        rtc_x1: trimsir.get_rtc_x1_trim(),
+        rtc_x2: trimsir.get_rtc_x2_trim(),
+        rtc_locked: trimsir.get_rtc_trim_lock(),
+        ipo_limit_low: u16::from(trimsir.get_ipo_limit_low()),
+        ipo_limit_high: trimsir.get_ipo_limit_high(),
+        inro_trim: trimsir.get_inro_trim(),
+        inro_trim_16k: trimsir.get_inro_trim_16k(),
+        inro_trim_30k: trimsir.get_inro_trim_30k(),
+        inro_frequency: InroFrequency::from_bits(trimsir.get_inro_select()),
+        vdda_limit_low: trimsir.get_vdda_limit_low(),
+        vdda_limit_high: trimsir.get_vdda_limit_high(),
+    }
+}
+
+/// # Apply
+/// Apply the factory trims the clocks need, and return them for diagnostics.
+///
+/// - The IPO auto-calibration is loaded with the midpoint of the factory IPO limits, so
+///   a later calibration starts close to where it will settle.
+/// - The INRO low power clock is switched to the frequency it was trimmed for.
+///
+/// Called once by `gcr::init_global_control_register`, calling it again is harmless.
+pub fn apply() -> Trims {
+    let trims = read();
+
+    let mut fcr = FcrInstance::<{ mmio::FUNCTION_CONTROL }>::instance();
+    unsafe {
+        fcr.set_ipo_initial_trim(trims.ipo_trim_midpoint());
+        fcr.activate_ipo_load_trim();
+    }
+
+    if let Some(frequency) = trims.inro_frequency {
+        let mut trimsir = Trimsir::instance();
+        unsafe { trimsir.set_inro_low_power_clock_select(frequency) };
+    }
+
+    trims
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn trims(ipo_limit_low: u16, ipo_limit_high: u16) -> Trims {
+        Trims {
+            rtc_x1: 0,
+            rtc_x2: 0,
+            rtc_locked: false,
+            ipo_limit_low,
+            ipo_limit_high,
+            inro_trim: 0,
+            inro_trim_16k: 0,
+            inro_trim_30k: 0,
+            inro_frequency: None,
+            vdda_limit_low: 0,
+            vdda_limit_high: 0,
+        }
+    }
+
+    #[test]
+    fn ipo_trim_midpoint() {
+        assert_eq!(trims(0x40, 0x140).ipo_trim_midpoint(), 0xC0);
+        assert_eq!(trims(0x140, 0x40).ipo_trim_midpoint(), 0xC0);
+        assert_eq!(trims(0x1FF, 0x1FF).ipo_trim_midpoint(), 0x1FF);
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn apply_loads_ipo_midpoint() {
+        use crate::mock;
+
+        mock::reset();
+        mock::write(mmio::TRIM_SYSTEM_INITIALIZATION + 0x3C, 0x40);
+        mock::write(
+            mmio::TRIM_SYSTEM_INITIALIZATION + 0x40,
+            (0x140 << 15) | (2 << 24),
+        );
+
+        let trims = apply();
+        assert_eq!(trims.inro_frequency, Some(InroFrequency::Khz30));
+        assert_eq!(mock::read(mmio::FUNCTION_CONTROL + 0x08) & 0x1FF, 0xC0);
+        assert_eq!(mock::read(mmio::TRIM_SYSTEM_INITIALIZATION + 0x44) >> 6, 2);
+    }
+}
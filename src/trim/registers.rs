@@ -0,0 +1,86 @@
+use hal_macros::RW;
+use hal_macros_derive::make_device;
+
+use crate::memory_map::mmio;
+
+/// # Relative Register Offsets
+/// These are the offsets for the TRIMSIR registers that the
+/// Maxim Integrated - spec shows. Found on page 90.
+mod rro {
+    /// # RTC Trim Register
+    pub const TRIMSIR_RTC: usize = 0x0008;
+    /// # SIMO Trim Register
+    pub const TRIMSIR_SIMO: usize = 0x0034;
+    /// # IPO Low Trim Register
+    pub const TRIMSIR_IPOLO: usize = 0x003C;
+    /// # Control Trim Register
+    pub const TRIMSIR_CTRL: usize = 0x0040;
+    /// # INRO Trim Register
+    pub const TRIMSIR_INRO: usize = 0x0044;
+}
+
+make_device! {
+    device_ports(mmio::TRIM_SYSTEM_INITIALIZATION);
+
+    /// RTC X1 Trim.
+    /// Capacitor trim of the 32 kHz crystal's X1 pin.
+    #[bit(16..=20, RW, rro::TRIMSIR_RTC)]
+    rtc_x1_trim,
+
+    /// RTC X2 Trim.
+    /// Capacitor trim of the 32 kHz crystal's X2 pin.
+    #[bit(21..=25, RW, rro::TRIMSIR_RTC)]
+    rtc_x2_trim,
+
+    /// RTC Trim Lock.
+    /// When set, the RTC trims can no longer be changed.
+    #[bit(31, RO, rro::TRIMSIR_RTC)]
+    rtc_trim_lock,
+
+    /// SIMO Clock Scaling Trim.
+    #[bit(0..=2, RO, rro::TRIMSIR_SIMO)]
+    simo_clock_scaling_trim,
+
+    /// IPO Low Limit.
+    /// The lowest trim the IPO auto-calibration should use.
+    #[bit(0..=7, RO, rro::TRIMSIR_IPOLO)]
+    ipo_limit_low,
+
+    /// VDDA Low Limit Trim.
+    #[bit(0..=6, RO, rro::TRIMSIR_CTRL)]
+    vdda_limit_low,
+
+    /// VDDA High Limit Trim.
+    #[bit(8..=14, RO, rro::TRIMSIR_CTRL)]
+    vdda_limit_high,
+
+    /// IPO High Limit.
+    /// The highest trim the IPO auto-calibration should use.
+    #[bit(15..=23, RO, rro::TRIMSIR_CTRL)]
+    ipo_limit_high,
+
+    /// INRO Select.
+    /// The factory trimmed frequency of the internal nano-ring oscillator.
+    #[bit(24..=25, RO, rro::TRIMSIR_CTRL)]
+    inro_select,
+
+    /// INRO Trim.
+    #[bit(29..=31, RO, rro::TRIMSIR_CTRL)]
+    inro_trim,
+
+    /// INRO 16 kHz Trim.
+    #[bit(0..=2, RW, rro::TRIMSIR_INRO)]
+    inro_trim_16k,
+
+    /// INRO 30 kHz Trim.
+    #[bit(3..=5, RW, rro::TRIMSIR_INRO)]
+    inro_trim_30k,
+
+    /// INRO Low Power Clock Select.
+    /// - 0: 8 kHz
+    /// - 1: 16 kHz
+    /// - 2: 30 kHz
+    #[bit(6..=7, RW, rro::TRIMSIR_INRO)]
+    #[values(InroFrequency: Khz8 = 0, Khz16 = 1, Khz30 = 2)]
+    inro_low_power_clock_select,
+}
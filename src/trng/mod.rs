@@ -3,12 +3,12 @@ pub mod registers;
 use crate::gcr::HardwareSource;
 use crate::gcr::{peripheral_reset, system_clock_enable};
 use crate::memory_map::mmio;
-use registers::Registers;
+use registers::Instance;
 
 /// A wrapper around the TRNG register. Used to allow the borrow checker to keep
 /// track of who can mutate the state of TRNG.
 pub struct TRNG {
-    registers: Registers,
+    registers: Instance<{ mmio::TRNG }>,
 }
 
 impl TRNG {
@@ -20,11 +20,23 @@ impl TRNG {
         peripheral_reset(HardwareSource::TRNG);
         system_clock_enable(HardwareSource::TRNG, true);
 
-        let mut registers = Registers::new(mmio::TRNG);
+        let mut registers = Instance::<{ mmio::TRNG }>::instance();
         unsafe { registers.set_trng_control_register(0) };
         Self { registers }
     }
 
+    /// Conjure a handle to TRNG without resetting it or enabling its clock. Meant for
+    /// interrupt handlers and panic paths that need TRNG after it was moved elsewhere.
+    ///
+    /// # Safety
+    /// TRNG must already have been initialized with `init`, and the caller must ensure
+    /// the stolen handle does not race with the original one.
+    pub unsafe fn steal() -> Self {
+        Self {
+            registers: Instance::<{ mmio::TRNG }>::instance(),
+        }
+    }
+
     /// Get a random number from TRNG.
     pub fn get_trng_data(&mut self) -> u32 {
         while !self.registers.get_random_number_ready() {}
@@ -36,3 +48,55 @@ impl TRNG {
         self.registers.get_random_number_ready()
     }
 }
+
+impl crate::pac::FromPac for TRNG {
+    fn from_pac<P: crate::pac::PacPeripheral>(peripheral: P) -> crate::error::Result<Self> {
+        crate::pac::check_address::<P>(mmio::TRNG)?;
+        drop(peripheral);
+
+        Ok(Self::init())
+    }
+}
+
+impl crate::pac::IntoPac for TRNG {
+    fn free<P: crate::pac::PacPeripheral>(self) -> crate::error::Result<P> {
+        crate::pac::free_into(self, mmio::TRNG)
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::mock;
+
+    #[test]
+    fn waits_for_ready() {
+        mock::reset();
+        let mut trng = TRNG {
+            registers: Instance::instance(),
+        };
+
+        assert!(!trng.ready());
+
+        mock::write(mmio::TRNG + 0x8, 0x1234_5678);
+        mock::write(mmio::TRNG + 0x4, 1);
+
+        assert!(trng.ready());
+        assert_eq!(trng.get_trng_data(), 0x1234_5678);
+    }
+
+    #[test]
+    fn steal_shares_registers() {
+        mock::reset();
+        let trng = TRNG {
+            registers: Instance::instance(),
+        };
+        let mut stolen = unsafe { TRNG::steal() };
+
+        mock::write(mmio::TRNG + 0x8, 0xCAFE);
+        mock::write(mmio::TRNG + 0x4, 1);
+
+        assert!(trng.ready());
+        assert_eq!(stolen.get_trng_data(), 0xCAFE);
+    }
+}
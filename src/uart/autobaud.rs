@@ -0,0 +1,155 @@
+//! # Baud Rate Detection
+//! Find the baud rate of a peer by timing the edges on its TX line. The shortest time
+//! between two edges is one bit, so once the peer sent a character with a lone 0 or 1
+//! bit (ie. `U`, `0x55`, is only lone bits) the bit time, and with it the baud rate,
+//! is known. The measured rate is snapped to the nearest of `BaudRates`.
+//!
+//! The UART can not time its RX line, so this uses a timer in capture mode. Connect the
+//! RX line to the timer's input pin as well, and route that pin to the timer. The
+//! edges are polled, so the core has to be fast enough to switch edges within a bit;
+//! this is easily done at 115200 baud and a 50 MHz timer clock.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::memory_map::mmio;
+//! use max78000_hal::timer::capture::Edge;
+//! use max78000_hal::timer::registers::TimerPrescaler;
+//! use max78000_hal::timer::{Timer, TimerInstance};
+//! use max78000_hal::uart::ClockSources;
+//! # use max78000_hal::uart::{BaudRates, CharacterLength, Parity, ParityValueSelect, StopBits, UART};
+//! # let mut uart = UART::port_0_init(BaudRates::Baud115200, CharacterLength::EightBits, StopBits::OneBit, false, Parity::Odd, ParityValueSelect::OneBased, false)?;
+//!
+//! let timer = Timer::new(TimerInstance::<{ mmio::TIMER_1 }>::instance())?;
+//! let mut capture = timer.into_capture(TimerPrescaler::Div1, Edge::Falling);
+//!
+//! // The peer sends `UUUU` after reset
+//! let rate = uart.auto_baud(&mut capture, 16, 1_000_000, ClockSources::PCLK)?;
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::{private, BaudRates, ClockSources, UART};
+use crate::error::{ErrorKind, Result};
+use crate::time::Hertz;
+use crate::timer::capture::{Edge, InputCapture};
+
+/// # Tolerance Percent
+/// How far the measured rate may be off a standard rate and still be taken as it.
+const TOLERANCE_PERCENT: u32 = 4;
+
+/// # Standard Rates
+/// The baud rates a measured rate is snapped to.
+const STANDARD_RATES: [BaudRates; 8] = [
+    BaudRates::Baud1200,
+    BaudRates::Baud2400,
+    BaudRates::Baud4800,
+    BaudRates::Baud9600,
+    BaudRates::Baud19200,
+    BaudRates::Baud38400,
+    BaudRates::Baud57600,
+    BaudRates::Baud115200,
+];
+
+/// # Measure Bit Ticks
+/// Capture `edges` edges of the line, starting with the falling edge of a start bit,
+/// and give the shortest capture ticks between two of them. Each capture is checked
+/// for at most `polls` times, see `InputCapture::capture`.
+///
+/// # Errors
+/// - `ErrorKind::BadParam` when `edges` is less than two.
+/// - `ErrorKind::TimeOut` when the line stopped changing.
+pub fn measure_bit_ticks<const TIMER: usize>(
+    capture: &mut InputCapture<TIMER>,
+    edges: usize,
+    polls: u32,
+) -> Result<u32> {
+    if edges < 2 {
+        return Err(ErrorKind::BadParam);
+    }
+
+    capture.set_edge(Edge::Falling);
+    capture.start();
+    let mut last = capture.capture(polls)?;
+    let mut edge = Edge::Rising;
+    let mut shortest = u32::MAX;
+
+    for _ in 1..edges {
+        capture.set_edge(edge);
+        let count = capture.capture(polls);
+        // Edges alternate, so the next one is the other way
+        edge = match edge {
+            Edge::Rising => Edge::Falling,
+            Edge::Falling => Edge::Rising,
+        };
+
+        let count = match count {
+            Ok(count) => count,
+            Err(error) => {
+                capture.stop();
+                return Err(error);
+            }
+        };
+        shortest = shortest.min(count.wrapping_sub(last));
+        last = count;
+    }
+
+    capture.stop();
+    Ok(shortest)
+}
+
+/// # Nearest Baud Rate
+/// The standard baud rate with a bit of `bit_ticks` at `tick_rate`, if one is within
+/// the tolerance.
+fn nearest_baud_rate(bit_ticks: u32, tick_rate: Hertz) -> Option<BaudRates> {
+    if bit_ticks == 0 {
+        return None;
+    }
+
+    let measured = (tick_rate.raw() as u64 + bit_ticks as u64 / 2) / bit_ticks as u64;
+    STANDARD_RATES.into_iter().find(|rate| {
+        let rate = *rate as u64;
+        measured.abs_diff(rate) * 100 <= rate * TOLERANCE_PERCENT as u64
+    })
+}
+
+impl<Port: private::UARTPortCompatable> UART<Port> {
+    /// # Auto Baud
+    /// Measure the peer's baud rate over `edges` edges, see `measure_bit_ticks`, and
+    /// switch this UART to it, clocked from `source`. What was received meanwhile is
+    /// flushed, since it was received at the wrong rate.
+    ///
+    /// # Errors
+    /// - The errors of `measure_bit_ticks`.
+    /// - `ErrorKind::Invalid` when the measured rate is not a standard one, ie. the
+    ///   peer never sent a lone bit.
+    /// - The errors of `set_baud_rate`.
+    pub fn auto_baud<const TIMER: usize>(
+        &mut self,
+        capture: &mut InputCapture<TIMER>,
+        edges: usize,
+        polls: u32,
+        source: ClockSources,
+    ) -> Result<BaudRates> {
+        let bit_ticks = measure_bit_ticks(capture, edges, polls)?;
+        let rate = nearest_baud_rate(bit_ticks, capture.tick_rate()).ok_or(ErrorKind::Invalid)?;
+
+        self.set_baud_rate(rate.rate(), source)?;
+        self.clear_rx_fifo();
+        Ok(rate)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn snaps_to_standard_rates() {
+        let clock = Hertz::MHz(50);
+        assert_eq!(nearest_baud_rate(434, clock), Some(BaudRates::Baud115200));
+        assert_eq!(nearest_baud_rate(5208, clock), Some(BaudRates::Baud9600));
+        // 3% slow still counts, 10% does not
+        assert_eq!(nearest_baud_rate(5365, clock), Some(BaudRates::Baud9600));
+        assert_eq!(nearest_baud_rate(5787, clock), None);
+        assert_eq!(nearest_baud_rate(0, clock), None);
+        assert_eq!(nearest_baud_rate(u32::MAX, clock), None);
+    }
+}
@@ -0,0 +1,311 @@
+//! # Buffered UART
+//! A UART serviced by its interrupt, so bytes are received and sent while the program
+//! does something else. The interrupt moves received bytes from the receive FIFO into a
+//! ring buffer of `RX` bytes, and refills the transmit FIFO from a ring buffer of `TX`
+//! bytes, so `read` and `write` never wait.
+//!
+//! # Example
+//! ```no_run
+//! use core::cell::RefCell;
+//! use critical_section::Mutex;
+//! use max78000_hal::bind_interrupts;
+//! use max78000_hal::interrupt::typelevel::{self, Handler, Interrupt};
+//! use max78000_hal::uart::buffered::BufferedUart;
+//! use max78000_hal::uart::UART0;
+//! # use max78000_hal::uart::{BaudRates, CharacterLength, Parity, ParityValueSelect, StopBits, UART};
+//!
+//! static SERIAL: Mutex<RefCell<Option<BufferedUart<UART0, 128, 128>>>> =
+//!     Mutex::new(RefCell::new(None));
+//!
+//! struct Serial;
+//! impl Handler<typelevel::UART0> for Serial {
+//!     unsafe fn on_interrupt() {
+//!         critical_section::with(|cs| {
+//!             if let Some(serial) = SERIAL.borrow_ref_mut(cs).as_mut() {
+//!                 serial.on_interrupt();
+//!             }
+//!         });
+//!     }
+//! }
+//!
+//! bind_interrupts!(struct Irqs { UART0 => Serial; });
+//!
+//! # let uart = UART::port_0_init(
+//! #     BaudRates::Baud115200,
+//! #     CharacterLength::EightBits,
+//! #     StopBits::OneBit,
+//! #     false,
+//! #     Parity::Odd,
+//! #     ParityValueSelect::OneBased,
+//! #     false,
+//! # ).unwrap();
+//! critical_section::with(|cs| SERIAL.replace(cs, Some(BufferedUart::new(uart))));
+//! unsafe { typelevel::UART0::enable() };
+//!
+//! let mut line = [0; 16];
+//! let count = critical_section::with(|cs| {
+//!     let mut serial = SERIAL.borrow_ref_mut(cs);
+//!     let serial = serial.as_mut().unwrap();
+//!     serial.write(b"hello");
+//!     serial.read(&mut line)
+//! });
+//! ```
+use super::{private, UART};
+
+/// # FIFO Depth
+/// The number of bytes the receive and transmit FIFOs each hold.
+const FIFO_DEPTH: usize = 8;
+
+/// # Ring Buffer
+/// A queue of up to `N` bytes.
+struct RingBuffer<const N: usize> {
+    bytes: [u8; N],
+    head: usize,
+    len: usize,
+}
+
+impl<const N: usize> RingBuffer<N> {
+    const fn new() -> Self {
+        Self {
+            bytes: [0; N],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Queue `byte`, returning false when full.
+    fn push(&mut self, byte: u8) -> bool {
+        if self.len == N {
+            return false;
+        }
+
+        self.bytes[(self.head + self.len) % N] = byte;
+        self.len += 1;
+        true
+    }
+
+    /// Take the oldest byte.
+    fn pop(&mut self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let byte = self.bytes[self.head];
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        Some(byte)
+    }
+}
+
+/// # Buffered UART
+/// A UART with a receive buffer of `RX` bytes and a transmit buffer of `TX` bytes.
+pub struct BufferedUart<Port, const RX: usize = 64, const TX: usize = 64> {
+    uart: UART<Port>,
+    rx: RingBuffer<RX>,
+    tx: RingBuffer<TX>,
+    dropped: usize,
+}
+
+// The UART is owned, and the buffers only change through `&mut self`, so the driver can
+// move into the cell the program and the interrupt handler share it through
+unsafe impl<Port, const RX: usize, const TX: usize> Send for BufferedUart<Port, RX, TX> {}
+
+impl<Port: private::UARTPortCompatable, const RX: usize, const TX: usize>
+    BufferedUart<Port, RX, TX>
+{
+    /// # New
+    /// Buffer `uart`, enabling its receive interrupt. The interrupt of the UART must be
+    /// bound to a handler calling `on_interrupt`, and enabled in the NVIC.
+    pub fn new(mut uart: UART<Port>) -> Self {
+        unsafe {
+            uart.reg.set_recieve_fifo_threshold(1);
+            uart.reg.set_receive_fifo_thershold_event(true);
+            uart.reg.set_receive_fifo_overrun_event(true);
+        }
+
+        Self {
+            uart,
+            rx: RingBuffer::new(),
+            tx: RingBuffer::new(),
+            dropped: 0,
+        }
+    }
+
+    /// # On Interrupt
+    /// Move the received bytes into the receive buffer, and refill the transmit FIFO.
+    /// Call this from the UART's interrupt handler.
+    pub fn on_interrupt(&mut self) {
+        let reg = &mut self.uart.reg;
+
+        unsafe {
+            reg.clear_receive_fifo_threshold_interrupt_flag();
+            if reg.is_receive_fifo_overrun_interrupt_flag_active() {
+                reg.clear_receive_fifo_overrun_interrupt_flag();
+                self.dropped += 1;
+            }
+        }
+
+        for _ in 0..FIFO_DEPTH {
+            if reg.get_receive_fifo_empty() {
+                break;
+            }
+            if !self.rx.push(reg.get_fifo_data()) {
+                self.dropped += 1;
+            }
+        }
+
+        unsafe { reg.clear_transmit_fifo_half_empty_interrupt_flag() };
+        self.fill_transmit_fifo();
+    }
+
+    /// # Read
+    /// Take up to `buffer.len()` received bytes, returning how many. Never waits, so it
+    /// returns zero when nothing was received.
+    pub fn read(&mut self, buffer: &mut [u8]) -> usize {
+        self.without_interrupts(|uart| {
+            let mut count = 0;
+            for byte in buffer.iter_mut() {
+                match uart.rx.pop() {
+                    Some(data) => *byte = data,
+                    None => break,
+                }
+                count += 1;
+            }
+            count
+        })
+    }
+
+    /// # Write
+    /// Queue as many of `bytes` as the transmit buffer has room for, returning how many.
+    /// Never waits, so it returns zero when the buffer is full.
+    pub fn write(&mut self, bytes: &[u8]) -> usize {
+        self.without_interrupts(|uart| {
+            let count = bytes.iter().take_while(|byte| uart.tx.push(**byte)).count();
+            uart.fill_transmit_fifo();
+            count
+        })
+    }
+
+    /// # Len
+    /// The number of received bytes waiting to be read.
+    pub fn len(&self) -> usize {
+        self.rx.len
+    }
+
+    /// # Is Empty
+    /// Check if no received bytes are waiting to be read.
+    pub fn is_empty(&self) -> bool {
+        self.rx.len == 0
+    }
+
+    /// # Transmit Len
+    /// The number of bytes queued but not yet in the transmit FIFO.
+    pub fn transmit_len(&self) -> usize {
+        self.tx.len
+    }
+
+    /// # Dropped
+    /// The received bytes lost since the receive buffer or FIFO was full, read more
+    /// often (or use a bigger `RX`) when this grows.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// # Free
+    /// Disable the UART's interrupts, and give it back. Bytes still buffered are lost.
+    pub fn free(mut self) -> UART<Port> {
+        unsafe {
+            self.uart.reg.set_receive_fifo_thershold_event(false);
+            self.uart.reg.set_receive_fifo_overrun_event(false);
+            self.uart.reg.set_transmit_fifo_half_empty_event(false);
+        }
+        self.uart
+    }
+
+    /// # Fill Transmit FIFO
+    /// Move queued bytes into the transmit FIFO until it is full, then interrupt when it
+    /// is half empty again while bytes are left.
+    fn fill_transmit_fifo(&mut self) {
+        let reg = &mut self.uart.reg;
+
+        while !reg.get_transmit_fifo_full() {
+            match self.tx.pop() {
+                Some(byte) => unsafe { reg.set_fifo_data(byte) },
+                None => break,
+            }
+        }
+
+        unsafe { reg.set_transmit_fifo_half_empty_event(self.tx.len != 0) };
+    }
+
+    /// # Without Interrupts
+    /// Run `f` with the UART's interrupts masked, so the buffers are not changed by
+    /// `on_interrupt` meanwhile.
+    fn without_interrupts<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        unsafe {
+            let reg = &mut self.uart.reg;
+            reg.set_receive_fifo_thershold_event(false);
+            reg.set_receive_fifo_overrun_event(false);
+            reg.set_transmit_fifo_half_empty_event(false);
+        }
+
+        let result = f(self);
+
+        unsafe {
+            let reg = &mut self.uart.reg;
+            reg.set_receive_fifo_thershold_event(true);
+            reg.set_receive_fifo_overrun_event(true);
+            reg.set_transmit_fifo_half_empty_event(self.tx.len != 0);
+        }
+        result
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::memory_map::mmio;
+    use crate::uart::UART0;
+    use core::mem::ManuallyDrop;
+
+    /// Bit 4 of `UART_STATUS`, the receive FIFO empty.
+    const RECEIVE_FIFO_EMPTY: u32 = 1 << 4;
+    /// Bit 7 of `UART_STATUS`, the transmit FIFO full.
+    const TRANSMIT_FIFO_FULL: u32 = 1 << 7;
+
+    #[test]
+    fn buffers_both_ways() {
+        crate::mock::reset();
+        let uart = ManuallyDrop::into_inner(unsafe { UART::<UART0>::steal() });
+        let mut serial: BufferedUart<UART0, 4, 4> = BufferedUart::new(uart);
+        let status = mmio::UART_0 + 0x04;
+        let fifo = mmio::UART_0 + 0x20;
+
+        // Eight bytes in the FIFO, four fit the buffer
+        crate::mock::write(fifo, b'a' as u32);
+        serial.on_interrupt();
+        assert_eq!(serial.len(), 4);
+        assert_eq!(serial.dropped(), 4);
+
+        let mut buffer = [0; 3];
+        assert_eq!(serial.read(&mut buffer), 3);
+        assert_eq!(&buffer, b"aaa");
+        assert_eq!(serial.read(&mut buffer), 1);
+        assert!(serial.is_empty());
+
+        // The FIFO full, everything waits in the buffer for the interrupt
+        crate::mock::write(status, RECEIVE_FIFO_EMPTY | TRANSMIT_FIFO_FULL);
+        assert_eq!(serial.write(b"hello"), 4);
+        assert_eq!(serial.transmit_len(), 4);
+        assert!(serial.uart.reg.get_transmit_fifo_half_empty_event());
+
+        crate::mock::write(status, RECEIVE_FIFO_EMPTY);
+        serial.on_interrupt();
+        assert_eq!(serial.transmit_len(), 0);
+        assert_eq!(crate::mock::read(fifo), b'l' as u32);
+        assert!(!serial.uart.reg.get_transmit_fifo_half_empty_event());
+
+        // Stolen pins, never release them
+        core::mem::forget(serial.free());
+    }
+}
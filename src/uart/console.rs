@@ -0,0 +1,160 @@
+//! # UART Console
+//! A UART for text output. Terminals expect a carriage return before every line feed,
+//! so `Console` sends `\n` as `\r\n`, and `write!`/`writeln!` output lines up without
+//! configuring the terminal. With the `console` feature it can be registered as the
+//! global console, after which `print!`/`println!` (and `uprint!`/`uprintln!`) write
+//! into it from anywhere.
+//!
+//! # Example
+//! ```no_run
+//! use core::fmt::Write;
+//! use max78000_hal::uart::console::Console;
+//! # use max78000_hal::uart::{BaudRates, CharacterLength, Parity, ParityValueSelect, StopBits, UART};
+//! # let uart = UART::port_0_init(
+//! #     BaudRates::Baud115200,
+//! #     CharacterLength::EightBits,
+//! #     StopBits::OneBit,
+//! #     false,
+//! #     Parity::Odd,
+//! #     ParityValueSelect::OneBased,
+//! #     false,
+//! # )?;
+//! # let value = 0;
+//!
+//! let mut console = Console::new(uart);
+//! writeln!(console, "sensor = {}", value).unwrap();
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use core::fmt;
+
+use super::{private, UART};
+
+/// # Console
+/// A UART sending `\n` as `\r\n`.
+pub struct Console<Port> {
+    uart: UART<Port>,
+}
+
+impl<Port: private::UARTPortCompatable> Console<Port> {
+    /// # New
+    /// Wrap a initialized UART.
+    pub fn new(uart: UART<Port>) -> Self {
+        Self { uart }
+    }
+
+    /// # Register
+    /// Make this the global console that `print!`/`println!` write into, replacing the
+    /// one installed before. See `console::install`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use max78000_hal::uart::console::Console;
+    /// use max78000_hal::uart::UART0;
+    /// # use max78000_hal::uart::{BaudRates, CharacterLength, Parity, ParityValueSelect, StopBits, UART};
+    /// # let uart = UART::port_0_init(
+    /// #     BaudRates::Baud115200,
+    /// #     CharacterLength::EightBits,
+    /// #     StopBits::OneBit,
+    /// #     false,
+    /// #     Parity::Odd,
+    /// #     ParityValueSelect::OneBased,
+    /// #     false,
+    /// # )?;
+    /// # let value = 0;
+    ///
+    /// static mut CONSOLE: Option<Console<UART0>> = None;
+    ///
+    /// // Only ever borrowed here, so the console holds the one reference to it
+    /// unsafe { (*core::ptr::addr_of_mut!(CONSOLE)).insert(Console::new(uart)) }.register();
+    ///
+    /// max78000_hal::println!("sensor = {}", value);
+    /// # Ok::<(), max78000_hal::error::ErrorKind>(())
+    /// ```
+    #[cfg(feature = "console")]
+    pub fn register(&'static mut self) {
+        crate::console::install(self);
+    }
+
+    /// # Write Bytes
+    /// Send `bytes`, inserting a `\r` before every `\n`.
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        translate_line_feeds(bytes, |chunk| self.uart.write_all(chunk));
+    }
+
+    /// # Free
+    /// Give back the UART.
+    pub fn free(self) -> UART<Port> {
+        self.uart
+    }
+}
+
+/// Pass `bytes` to `emit` in chunks, with `\n` replaced by `\r\n`.
+fn translate_line_feeds(bytes: &[u8], mut emit: impl FnMut(&[u8])) {
+    for line in bytes.split_inclusive(|byte| *byte == b'\n') {
+        match line.split_last() {
+            Some((b'\n', text)) => {
+                emit(text);
+                emit(b"\r\n");
+            }
+            _ => emit(line),
+        }
+    }
+}
+
+impl<Port: private::UARTPortCompatable> fmt::Write for Console<Port> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_bytes(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<Port: private::UARTPortCompatable> embedded_io::ErrorType for Console<Port> {
+    type Error = crate::Error;
+}
+
+#[cfg(feature = "embedded-io")]
+impl<Port: private::UARTPortCompatable> embedded_io::Write for Console<Port> {
+    fn write(&mut self, buf: &[u8]) -> core::result::Result<usize, Self::Error> {
+        self.write_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> core::result::Result<(), Self::Error> {
+        self.uart.flush_transmit();
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::memory_map::mmio;
+    use crate::uart::UART1;
+    use core::fmt::Write;
+    use core::mem::ManuallyDrop;
+
+    #[test]
+    fn line_feeds_get_carriage_returns() {
+        let mut sent = [0; 16];
+        let mut len = 0;
+        translate_line_feeds(b"a\n\nb", |chunk| {
+            sent[len..len + chunk.len()].copy_from_slice(chunk);
+            len += chunk.len();
+        });
+        assert_eq!(&sent[..len], b"a\r\n\r\nb");
+
+        crate::mock::reset();
+        let fifo = mmio::UART_1 + 0x20;
+        let uart = ManuallyDrop::into_inner(unsafe { UART::<UART1>::steal() });
+        let mut console = Console::new(uart);
+
+        write!(console, "{}", 42).unwrap();
+        assert_eq!(crate::mock::read(fifo), b'2' as u32);
+        writeln!(console).unwrap();
+        assert_eq!(crate::mock::read(fifo), b'\n' as u32);
+
+        // Stolen pins, never release them
+        core::mem::forget(console.free());
+    }
+}
@@ -0,0 +1,147 @@
+//! # Half-Duplex UART
+//! Talk over a single wire shared by both ends, like a smart card's I/O line or a
+//! Dynamixel servo bus. The UART has no single-wire mode, so tie its TX and RX pins
+//! together onto the bus. The TX pin only drives the line while sending, between
+//! frames it floats with a weak pull-up so the other end can answer.
+//!
+//! The RX pin hears everything sent, so every byte sent is read back and compared. A
+//! byte that does not come back as it was sent means someone else drove the line
+//! meanwhile, or the pins are not tied together.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::uart::half_duplex::HalfDuplex;
+//! # use max78000_hal::uart::{BaudRates, CharacterLength, Parity, ParityValueSelect, StopBits, UART};
+//! # let mut uart = UART::port_0_init(BaudRates::Baud115200, CharacterLength::EightBits, StopBits::OneBit, false, Parity::Odd, ParityValueSelect::OneBased, false)?;
+//!
+//! let mut bus = HalfDuplex::new(uart);
+//!
+//! bus.write(&[0xFF, 0xFF, 0x01, 0x02, 0x01, 0xFB])?;
+//! let mut status = [0; 6];
+//! bus.read_exact(&mut status);
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::{private, UartError, UART};
+use crate::error::{ErrorKind, Result};
+use crate::gpio::{OutputDriveStrength, PinFunction, ResistorStrength, VoltageSelect};
+
+/// # Half-Duplex
+/// A UART sharing one wire between sending and receiving.
+pub struct HalfDuplex<Port> {
+    uart: UART<Port>,
+}
+
+impl<Port: private::UARTPortCompatable> HalfDuplex<Port> {
+    /// # New
+    /// Wrap a initialized UART whose TX and RX pins are tied together, releasing the
+    /// line.
+    pub fn new(uart: UART<Port>) -> Self {
+        let bus = Self { uart };
+        bus.release_line();
+        bus
+    }
+
+    /// # Write
+    /// Drive the line, send `bytes`, and release it again once the last one was sent.
+    /// Whatever was received before is flushed.
+    ///
+    /// # Errors
+    /// `ErrorKind::ComError` when a byte did not read back as it was sent.
+    pub fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        self.uart.clear_rx_fifo();
+        self.drive_line();
+
+        let mut echoed = 0;
+        let mut collided = false;
+        for (sent, byte) in bytes.iter().enumerate() {
+            while self.uart.reg.get_transmit_fifo_full() {}
+            unsafe { self.uart.reg.set_fifo_data(*byte) };
+
+            // Read the echoes as they come, so the receive FIFO never overruns
+            while echoed <= sent && !self.uart.reg.get_receive_fifo_empty() {
+                collided |= self.uart.reg.get_fifo_data() != bytes[echoed];
+                echoed += 1;
+            }
+        }
+
+        self.uart.flush_transmit();
+        while echoed < bytes.len() && !self.uart.reg.get_receive_fifo_empty() {
+            collided |= self.uart.reg.get_fifo_data() != bytes[echoed];
+            echoed += 1;
+        }
+        self.release_line();
+
+        if collided || echoed < bytes.len() {
+            return Err(ErrorKind::ComError);
+        }
+        Ok(())
+    }
+
+    /// # Read Exact
+    /// Read exactly `buffer.len()` bytes from the line, waiting for each of them.
+    pub fn read_exact(&mut self, buffer: &mut [u8]) {
+        self.uart.read_exact(buffer);
+    }
+
+    /// # Read Checked
+    /// Read a byte from the line, or the receive error flagged with it, see
+    /// `UART::read_checked`.
+    pub fn read_checked(&mut self) -> Option<core::result::Result<u8, UartError>> {
+        self.uart.read_checked()
+    }
+
+    /// # Free
+    /// Give back the UART, with its TX pin driving again.
+    pub fn free(self) -> UART<Port> {
+        self.drive_line();
+        self.uart
+    }
+
+    /// # Drive Line
+    /// Give the TX pin back to the UART, driving the line.
+    fn drive_line(&self) {
+        self.uart._gpio[1].configure_output(
+            OutputDriveStrength::Strength0(VoltageSelect::VddIO),
+            PinFunction::AF1,
+        );
+    }
+
+    /// # Release Line
+    /// Float the TX pin, with a weak pull-up keeping the idle line high.
+    fn release_line(&self) {
+        self.uart._gpio[1].configure_input(ResistorStrength::WeakPullup, PinFunction::IO);
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::memory_map::mmio;
+    use crate::uart::UART2;
+    use core::mem::ManuallyDrop;
+
+    /// Bit 4 of `UART_STATUS`, the receive FIFO empty.
+    const RECEIVE_FIFO_EMPTY: u32 = 1 << 4;
+    /// Bit 6 of `UART_STATUS`, the transmit FIFO empty.
+    const TRANSMIT_FIFO_EMPTY: u32 = 1 << 6;
+
+    #[test]
+    fn writes_check_their_echo() {
+        crate::mock::reset();
+        let status = mmio::UART_2 + 0x04;
+        let uart = ManuallyDrop::into_inner(unsafe { UART::<UART2>::steal() });
+        let mut bus = HalfDuplex::new(uart);
+
+        // The mock FIFO reads back what was written, like a tied RX pin
+        crate::mock::write(status, TRANSMIT_FIFO_EMPTY);
+        bus.write(b"ping").unwrap();
+
+        // Nothing comes back
+        crate::mock::write(status, TRANSMIT_FIFO_EMPTY | RECEIVE_FIFO_EMPTY);
+        assert_eq!(bus.write(b"ping"), Err(ErrorKind::ComError));
+        assert_eq!(bus.write(b""), Ok(()));
+
+        // Stolen pins, never release them
+        core::mem::forget(bus.free());
+    }
+}
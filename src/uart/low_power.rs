@@ -0,0 +1,104 @@
+//! # Low Power UART
+//! The low power UART (UART 3) keeps receiving in the low power modes, clocked from the
+//! IBRO or the ERTCO instead of the system clock. Its receive FIFO can wake the chip,
+//! so serial traffic wakes it up: arm `WakeUpEvent::ReceiveNotEmpty` to wake on the
+//! first byte received. The ERTCO only runs while the 32 kHz oscillator is enabled, and
+//! only suits low baud rates.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::power;
+//! use max78000_hal::uart::low_power::WakeUpEvent;
+//! use max78000_hal::uart::{BaudRates, CharacterLength, Parity, ParityValueSelect, StopBits, UART};
+//!
+//! let mut uart = UART::port_3_init(
+//!     BaudRates::Baud9600,
+//!     CharacterLength::EightBits,
+//!     StopBits::OneBit,
+//!     false,
+//!     Parity::Odd,
+//!     ParityValueSelect::OneBased,
+//!     false,
+//! )?;
+//! uart.enable_wakeup(WakeUpEvent::ReceiveNotEmpty, true);
+//!
+//! power::deep_sleep();
+//! if uart.is_wakeup_pending() {
+//!     uart.clear_wakeup();
+//! }
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::{UART, UART3};
+
+/// # Wake-Up Event
+/// A receive FIFO event that can wake the chip.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WakeUpEvent {
+    /// A byte was received.
+    ReceiveNotEmpty,
+    /// The receive FIFO reached its threshold.
+    ReceiveThreshold,
+    /// The receive FIFO is full.
+    ReceiveFull,
+}
+
+impl UART<UART3> {
+    /// # Enable Wake-Up
+    /// Allow/Stop `event` waking the chip from the low power modes.
+    pub fn enable_wakeup(&mut self, event: WakeUpEvent, enable: bool) {
+        unsafe {
+            match event {
+                WakeUpEvent::ReceiveNotEmpty => self
+                    .reg
+                    .set_receive_fifo_not_empty_wakeup_event_enable(enable),
+                WakeUpEvent::ReceiveThreshold => self
+                    .reg
+                    .set_receive_fifo_threshold_wakeup_event_enable(enable),
+                WakeUpEvent::ReceiveFull => {
+                    self.reg.set_receive_fifo_full_wakeup_event_enable(enable)
+                }
+            }
+        }
+    }
+
+    /// # Is Wake-Up Pending
+    /// Check if any receive FIFO event flagged a wake-up since it was last cleared.
+    pub fn is_wakeup_pending(&self) -> bool {
+        self.reg.get_receive_fifo_not_empty_wakeup_event()
+            || self.reg.get_receive_fifo_threshold_wakeup_event()
+            || self.reg.get_receive_fifo_full_wakeup_event()
+    }
+
+    /// # Clear Wake-Up
+    /// Clear every wake-up event flag.
+    pub fn clear_wakeup(&mut self) {
+        unsafe {
+            self.reg.set_receive_fifo_not_empty_wakeup_event(false);
+            self.reg.set_receive_fifo_threshold_wakeup_event(false);
+            self.reg.set_receive_fifo_full_wakeup_event(false);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::memory_map::mmio;
+
+    #[test]
+    fn arms_and_clears_wakeup() {
+        crate::mock::reset();
+        let mut uart = unsafe { UART::<UART3>::steal() };
+
+        uart.enable_wakeup(WakeUpEvent::ReceiveNotEmpty, true);
+        uart.enable_wakeup(WakeUpEvent::ReceiveFull, true);
+        uart.enable_wakeup(WakeUpEvent::ReceiveFull, false);
+        assert_eq!(crate::mock::read(mmio::LOW_POWER_UART_0 + 0x34), 1);
+
+        assert!(!uart.is_wakeup_pending());
+        crate::mock::write(mmio::LOW_POWER_UART_0 + 0x38, 1);
+        assert!(uart.is_wakeup_pending());
+        uart.clear_wakeup();
+        assert!(!uart.is_wakeup_pending());
+    }
+}
@@ -1,12 +1,22 @@
 use crate::error::{ErrorKind, Result};
 use crate::gcr::{peripheral_reset, system_clock_enable};
-use crate::gpio::GpioPin;
+use crate::gpio::{GpioPin, OutputDriveStrength, PinFunction, ResistorStrength, VoltageSelect};
 use crate::memory_map::mmio;
+use crate::time::{Hertz, Micros};
 use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
 
+pub mod autobaud;
+pub mod buffered;
+pub mod console;
+pub mod half_duplex;
+pub mod low_power;
+pub mod multidrop;
 pub mod registers;
+pub mod rs485;
+pub mod wait;
 
-mod private {
+pub(crate) mod private {
     pub trait UARTPortCompatable {
         const PORT_PTR: usize;
         const PORT_NUM: usize;
@@ -17,6 +27,8 @@ pub struct NoPort {}
 pub struct UART0 {}
 pub struct UART1 {}
 pub struct UART2 {}
+/// The low power UART, LPUART0.
+pub struct UART3 {}
 
 impl private::UARTPortCompatable for UART0 {
     const PORT_PTR: usize = mmio::UART_0;
@@ -30,11 +42,17 @@ impl private::UARTPortCompatable for UART2 {
     const PORT_PTR: usize = mmio::UART_2;
     const PORT_NUM: usize = 2;
 }
+impl private::UARTPortCompatable for UART3 {
+    const PORT_PTR: usize = mmio::LOW_POWER_UART_0;
+    const PORT_NUM: usize = 3;
+}
 
 pub struct UART<Port = NoPort> {
     reg: registers::Registers,
     _ph: PhantomData<Port>,
     _gpio: [GpioPin; 2],
+    /// The CTS and RTS pins, while hardware flow control is enabled.
+    flow: Option<[GpioPin; 2]>,
 }
 
 #[allow(unused)]
@@ -172,8 +190,42 @@ impl UART<NoPort> {
             hfc,
         )
     }
+
+    /// # Port 3 Init
+    /// Initializes the low power UART (UART 3), on `P2.6` (Rx) and `P2.7` (Tx). It
+    /// starts clocked from the IBRO, see `set_baud_rate` to clock it from the ERTCO,
+    /// and `low_power` to have it wake the chip.
+    /// # Arguments
+    /// * `baud_rate` - The baud rate that the UART will use
+    /// * `character_length` - The number of data bits that will be transferred in a frame
+    /// * `stop_bits` - The number of stop bits that will be used
+    /// * `transmit_parity` - Enables the generation of the parity bit
+    /// * `parity` - Specifies whether to use odd, or even parity
+    /// * `hfc` - Enables the use of hardware flow control
+    pub fn port_3_init(
+        baud_rate: BaudRates,
+        character_length: CharacterLength,
+        stop_bits: StopBits,
+        transmit_parity: bool,
+        parity: Parity,
+        parity_value: ParityValueSelect,
+        hfc: bool,
+    ) -> Result<UART<UART3>> {
+        peripheral_reset(crate::gcr::HardwareSource::UART3);
+        system_clock_enable(crate::gcr::HardwareSource::UART3, true);
+        UART::<UART3>::init(
+            baud_rate,
+            character_length,
+            stop_bits,
+            transmit_parity,
+            parity,
+            parity_value,
+            hfc,
+        )
+    }
 }
 #[repr(u32)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum BaudRates {
     Baud1200 = 1200,
     Baud2400 = 2400,
@@ -185,6 +237,14 @@ pub enum BaudRates {
     Baud115200 = 115200,
 }
 
+impl BaudRates {
+    /// # Rate
+    /// The baud rate as a frequency.
+    pub const fn rate(self) -> Hertz {
+        Hertz::Hz(self as u32)
+    }
+}
+
 /// # Character Length
 /// The number of data bits in a UART frame.
 #[repr(u8)]
@@ -195,12 +255,99 @@ pub enum CharacterLength {
     EightBits = 3,
 }
 
+/// # IBRO Frequency
+/// The frequency of the internal baud rate oscillator.
+const IBRO_FREQUENCY: Hertz = Hertz::Hz(7_372_800);
+
+/// # Max Baud Divisor
+/// The largest divisor of the 20-bit `UART_CLKDIV` register.
+const MAX_BAUD_DIVISOR: u32 = 0xF_FFFF;
+
+/// # Baud Clock
+/// The baud generator settings of a UART, see `UART::switch_to_ibro`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BaudClock {
+    source: u8,
+    divisor: u32,
+}
+
+/// # ERTCO Frequency
+/// The frequency of the 32 kHz external RTC oscillator.
+const ERTCO_FREQUENCY: Hertz = Hertz::Hz(32_768);
+
 /// # Clock Sources
-/// The clock source to use for UART
-#[repr(u8)]
+/// The clock source to use for UART. UART 0 to 2 run from `PCLK` or `IBRO`, the low
+/// power UART (UART 3) from `IBRO` or `ERTCO`, which keep running in the low power
+/// modes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum ClockSources {
-    PCLK = 0,
-    IBRO = 2,
+    /// The peripheral clock.
+    PCLK,
+    /// The internal baud rate oscillator, 7.3728 MHz.
+    IBRO,
+    /// The external RTC oscillator, 32.768 kHz, only for low baud rates.
+    ERTCO,
+}
+
+impl ClockSources {
+    /// # Bits
+    /// The baud clock source field value of this source on UART `port`, `None` when
+    /// the port can not use it.
+    const fn bits(self, port: usize) -> Option<u8> {
+        match (self, port) {
+            (ClockSources::PCLK, 0..=2) => Some(0),
+            (ClockSources::IBRO, 0..=2) => Some(2),
+            (ClockSources::IBRO, 3) => Some(0),
+            (ClockSources::ERTCO, 3) => Some(1),
+            _ => None,
+        }
+    }
+
+    /// # Frequency
+    /// The frequency the source runs at now.
+    fn frequency(self) -> Hertz {
+        match self {
+            ClockSources::PCLK => crate::core_peripheral_clock(),
+            ClockSources::IBRO => IBRO_FREQUENCY,
+            ClockSources::ERTCO => ERTCO_FREQUENCY,
+        }
+    }
+}
+
+/// # UART Error
+/// A receive error, reported instead of the byte it happened on, or a read that timed
+/// out.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UartError {
+    /// The line was held low for longer than a frame, received as a zero byte
+    /// without a stop bit.
+    Break,
+    /// The stop bit was missing.
+    Framing,
+    /// The parity bit did not match.
+    Parity,
+    /// The receive FIFO was full, so received bytes were lost.
+    Overrun,
+    /// Nothing was received in time.
+    Timeout,
+}
+
+impl From<UartError> for ErrorKind {
+    fn from(value: UartError) -> ErrorKind {
+        match value {
+            UartError::Overrun => ErrorKind::Overflow,
+            UartError::Timeout => ErrorKind::TimeOut,
+            UartError::Break | UartError::Framing | UartError::Parity => ErrorKind::ComError,
+        }
+    }
+}
+
+impl From<UartError> for crate::Error {
+    fn from(value: UartError) -> crate::Error {
+        ErrorKind::from(value).within(crate::error::Peripheral::Uart)
+    }
 }
 
 /// # Stop Bits
@@ -212,9 +359,9 @@ pub enum StopBits {
     TwoBits,
 }
 
-impl Into<bool> for StopBits {
-    fn into(self) -> bool {
-        match self {
+impl From<StopBits> for bool {
+    fn from(value: StopBits) -> bool {
+        match value {
             StopBits::OneBit => false,
             StopBits::TwoBits => true,
         }
@@ -224,18 +371,29 @@ impl Into<bool> for StopBits {
 /// # Hardware Flow Control Deassert Condition
 /// When to deassert the hardware flow control
 pub enum HFCDeassertCondition {
+    /// When the receive FIFO is full.
     EqualsFIFODepth,
+    /// When the receive FIFO reaches its threshold, see `UART::set_receive_threshold`.
     ExceedsRxThreshold,
 }
 
+impl From<HFCDeassertCondition> for bool {
+    fn from(value: HFCDeassertCondition) -> bool {
+        match value {
+            HFCDeassertCondition::EqualsFIFODepth => false,
+            HFCDeassertCondition::ExceedsRxThreshold => true,
+        }
+    }
+}
+
 pub enum ParityValueSelect {
     OneBased,
     ZeroBased,
 }
 
-impl Into<bool> for ParityValueSelect {
-    fn into(self) -> bool {
-        match self {
+impl From<ParityValueSelect> for bool {
+    fn from(value: ParityValueSelect) -> bool {
+        match value {
             ParityValueSelect::OneBased => false,
             ParityValueSelect::ZeroBased => true,
         }
@@ -249,9 +407,9 @@ pub enum Parity {
     Even,
 }
 
-impl Into<bool> for Parity {
-    fn into(self) -> bool {
-        match self {
+impl From<Parity> for bool {
+    fn from(value: Parity) -> bool {
+        match value {
             Parity::Odd => false,
             Parity::Even => true,
         }
@@ -259,6 +417,27 @@ impl Into<bool> for Parity {
 }
 
 impl<Port: private::UARTPortCompatable> UART<Port> {
+    /// # Steal
+    /// Conjure a handle to a already initialized UART port, without resetting it or
+    /// taking ownership of its pins. Useful for printing from a interrupt handler or
+    /// panic path after the original handle was moved elsewhere.
+    ///
+    /// The handle is wrapped in `ManuallyDrop`, so dropping it never releases the pins
+    /// owned by the original handle.
+    ///
+    /// # Safety
+    /// The port must have been initialized with one of the `port_n_init` functions,
+    /// and the caller must ensure the stolen handle does not race with the original
+    /// one, ie. it is only used from a interrupt handler or panic path that preempts it.
+    pub unsafe fn steal() -> ManuallyDrop<Self> {
+        ManuallyDrop::new(Self {
+            reg: registers::Registers::new(Port::PORT_PTR),
+            _gpio: crate::gpio::hardware::steal_uart_n(Port::PORT_NUM),
+            flow: None,
+            _ph: PhantomData,
+        })
+    }
+
     fn init(
         baud_rate: BaudRates,
         character_length: CharacterLength,
@@ -271,6 +450,7 @@ impl<Port: private::UARTPortCompatable> UART<Port> {
         let mut uart = Self {
             reg: registers::Registers::new(Port::PORT_PTR),
             _gpio: crate::gpio::hardware::uart_n(Port::PORT_NUM).ok_or(ErrorKind::Busy)?,
+            flow: None,
             _ph: PhantomData,
         };
 
@@ -292,9 +472,10 @@ impl<Port: private::UARTPortCompatable> UART<Port> {
             // Set the parity
             uart.reg.set_parity_odd_even(parity.into());
             // Set the clock source to IBRO
-            uart.reg.set_baud_clock_source(ClockSources::IBRO as u8);
+            let ibro = ClockSources::IBRO.bits(Port::PORT_NUM).unwrap_or_default();
+            uart.reg.set_baud_clock_source(ibro);
             // Set the clock divisor to 7.3728 Mhz / baud rate
-            let divisor = 7372800u32 / baud_rate as u32;
+            let divisor = IBRO_FREQUENCY / baud_rate.rate();
             uart.reg.set_baud_rate_divisor(divisor);
             // Set the Hardware Flow Control
             uart.reg.set_hardware_flow_control(hfc);
@@ -365,6 +546,313 @@ impl<Port: private::UARTPortCompatable> UART<Port> {
         }
     }
 
+    /// # Flush Transmit
+    /// Wait until every byte in the transmit FIFO has been shifted out.
+    pub fn flush_transmit(&mut self) {
+        while !self.reg.get_transmit_fifo_empty() || self.reg.get_transmit_busy() {}
+    }
+
+    /// # Baud Clock
+    /// Get the current baud generator settings.
+    pub fn baud_clock(&self) -> BaudClock {
+        BaudClock {
+            source: self.reg.get_baud_clock_source(),
+            divisor: self.reg.get_baud_rate_divisor(),
+        }
+    }
+
+    /// # Set Baud Clock
+    /// Restore baud generator settings, ie. the ones `switch_to_ibro` returned.
+    pub fn set_baud_clock(&mut self, clock: BaudClock) {
+        unsafe {
+            self.reg.set_baud_clock_enable(false);
+            self.reg.set_baud_clock_source(clock.source);
+            self.reg.set_baud_rate_divisor(clock.divisor);
+            self.reg.set_baud_clock_enable(true);
+        }
+        while !self.reg.get_baud_clock_ready() {}
+    }
+
+    /// # Switch To IBRO
+    /// Flush the transmit FIFO, then clock the baud generator from the IBRO at the same
+    /// baud rate, so the UART keeps working while the system clock is slowed or stopped.
+    /// Returns the previous settings, for `set_baud_clock`. The low power UART never
+    /// runs from the system clock, so it is left as is.
+    pub fn switch_to_ibro(&mut self) -> BaudClock {
+        self.flush_transmit();
+
+        let previous = self.baud_clock();
+        let pclk = ClockSources::PCLK.bits(Port::PORT_NUM);
+        if pclk == Some(previous.source) && previous.divisor != 0 {
+            let baud_rate = crate::core_peripheral_clock() / previous.divisor;
+            self.set_baud_clock(BaudClock {
+                source: ClockSources::IBRO.bits(Port::PORT_NUM).unwrap_or_default(),
+                divisor: IBRO_FREQUENCY / baud_rate.max(Hertz::Hz(1)),
+            });
+        }
+
+        previous
+    }
+
+    /// # Set Baud Rate
+    /// Clock the baud generator from `source`, dividing it down to `baud_rate`. A
+    /// `PCLK` source follows the peripheral clock as it is now, so set it again after
+    /// changing the system clock.
+    ///
+    /// # Errors
+    /// `ErrorKind::BadParam` when this UART can not use `source`, `baud_rate` is zero,
+    /// or the divisor it needs does not fit the 20-bit divisor register.
+    pub fn set_baud_rate(&mut self, baud_rate: Hertz, source: ClockSources) -> Result<()> {
+        let bits = source.bits(Port::PORT_NUM).ok_or(ErrorKind::BadParam)?;
+        let divisor = baud_divisor(source.frequency(), baud_rate)?;
+
+        self.flush_transmit();
+        self.set_baud_clock(BaudClock {
+            source: bits,
+            divisor,
+        });
+        Ok(())
+    }
+
+    /// # Write All
+    /// Write every byte of `bytes`, waiting for room in the transmit FIFO. Returns once
+    /// the last byte is queued, see `flush_transmit` to wait until it was sent.
+    pub fn write_all(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            while self.reg.get_transmit_fifo_full() {}
+            unsafe { self.reg.set_fifo_data(*byte) }
+        }
+    }
+
+    /// # Read Exact
+    /// Fill `buffer` from the receive FIFO, waiting for every byte.
+    pub fn read_exact(&mut self, buffer: &mut [u8]) {
+        for byte in buffer {
+            while self.reg.get_receive_fifo_empty() {}
+            *byte = self.reg.get_fifo_data();
+        }
+    }
+
+    /// # Enable Flow Control
+    /// Take the CTS and RTS pins and enable hardware flow control. The UART only sends
+    /// while CTS is asserted (low), and deasserts RTS on `rts_deassert` so the other
+    /// end stops sending before the receive FIFO overruns. Calling it again only
+    /// changes `rts_deassert`.
+    ///
+    /// # Errors
+    /// - `ErrorKind::NoDevice` when this UART has no CTS and RTS pins, only UART 0
+    ///   (`P0.2`, `P0.3`) and UART 1 (`P0.14`, `P0.15`) have them.
+    /// - `ErrorKind::Busy` when the pins are already owned.
+    pub fn enable_flow_control(&mut self, rts_deassert: HFCDeassertCondition) -> Result<()> {
+        if self.flow.is_none() {
+            if Port::PORT_NUM > 1 {
+                return Err(ErrorKind::NoDevice);
+            }
+            let pins = crate::gpio::hardware::uart_flow_n(Port::PORT_NUM);
+            self.flow = Some(pins.ok_or(ErrorKind::Busy)?);
+        }
+
+        unsafe {
+            self.reg
+                .set_hardware_flow_rts_deassert_condition(rts_deassert.into());
+            self.reg.set_cts_sampling_disable(false);
+            self.reg.set_hardware_flow_control(true);
+        }
+        Ok(())
+    }
+
+    /// # Disable Flow Control
+    /// Disable hardware flow control, and release the CTS and RTS pins.
+    pub fn disable_flow_control(&mut self) {
+        unsafe { self.reg.set_hardware_flow_control(false) };
+        self.flow = None;
+    }
+
+    /// # Is Clear To Send
+    /// Check if the other end asserts CTS (low), allowing this UART to send.
+    pub fn is_clear_to_send(&self) -> bool {
+        !self.reg.get_cts_pin_state()
+    }
+
+    /// # Set Receive Threshold
+    /// Set the receive FIFO level (1 to 8 bytes, clamped) that raises the receive
+    /// threshold interrupt. With `HFCDeassertCondition::ExceedsRxThreshold` it also
+    /// deasserts RTS.
+    pub fn set_receive_threshold(&mut self, threshold: u8) {
+        unsafe { self.reg.set_recieve_fifo_threshold(threshold.clamp(1, 8)) };
+    }
+
+    /// # Send Break
+    /// Hold the TX line low for `duration`, after the transmit FIFO was sent. The
+    /// receiver sees a break when `duration` is longer than a frame.
+    pub fn send_break(&mut self, duration: Micros) {
+        self.flush_transmit();
+
+        let tx = &self._gpio[1];
+        tx.set_output(false);
+        tx.configure_output(
+            OutputDriveStrength::Strength0(VoltageSelect::VddIO),
+            PinFunction::IO,
+        );
+        crate::delay::Delay::new().delay(duration);
+        tx.configure_input(ResistorStrength::None, PinFunction::AF1);
+    }
+
+    /// # Take Error
+    /// Take the receive error flagged since this was last called, clearing its flag.
+    /// A framing error is reported as is, see `read_checked` to tell breaks apart.
+    pub fn take_error(&mut self) -> Option<UartError> {
+        let error = self.pending_error()?;
+        self.clear_error(error);
+        Some(error)
+    }
+
+    /// # Read Checked
+    /// Read a byte from the receive FIFO, or the receive error flagged with it. A
+    /// framing error on a zero byte is a `UartError::Break`. The error flags are shared
+    /// by the whole FIFO, so a error is reported with the next byte read, which is
+    /// dropped. Returns `None` when the FIFO is empty, and no error is flagged.
+    pub fn read_checked(&mut self) -> Option<core::result::Result<u8, UartError>> {
+        if self.reg.is_receive_fifo_overrun_interrupt_flag_active() {
+            self.clear_error(UartError::Overrun);
+            return Some(Err(UartError::Overrun));
+        }
+        if self.reg.get_receive_fifo_empty() {
+            return None;
+        }
+
+        let byte = self.reg.get_fifo_data();
+        Some(match self.pending_error() {
+            Some(UartError::Framing) => {
+                self.clear_error(UartError::Framing);
+                Err(if byte == 0 {
+                    UartError::Break
+                } else {
+                    UartError::Framing
+                })
+            }
+            Some(error) => {
+                self.clear_error(error);
+                Err(error)
+            }
+            None => Ok(byte),
+        })
+    }
+
+    /// # Read Frame
+    /// Wait up to `first_byte` (forever when `None`) for a byte, then read the bytes
+    /// following it into `buffer` until the line was idle for `gap`, returning how many
+    /// were read. Frames packets delimited by silence, ie. Modbus RTU (a gap of 3.5
+    /// characters) or GPS modules sending a burst of sentences every second. Both are
+    /// timed with the cycle counter (see `profiling`), which is started if it is not
+    /// running.
+    ///
+    /// # Errors
+    /// - `UartError::Timeout` when no byte arrived within `first_byte`.
+    /// - The first receive error in the frame, or `UartError::Overrun` when the frame
+    ///   did not fit `buffer`. The rest of the frame is still read (and dropped), so the
+    ///   next call starts on the next frame.
+    pub fn read_frame(
+        &mut self,
+        buffer: &mut [u8],
+        gap: Micros,
+        first_byte: Option<Micros>,
+    ) -> core::result::Result<usize, UartError> {
+        if !crate::profiling::is_enabled() {
+            crate::profiling::enable();
+        }
+
+        let cycles_per_micro = crate::core_clock().to_MHz().max(1);
+        self.read_frame_cycles(
+            buffer,
+            gap.to_micros().saturating_mul(cycles_per_micro),
+            first_byte.map(|timeout| timeout.to_micros() as u64 * cycles_per_micro as u64),
+        )
+    }
+
+    /// # Read Frame Cycles
+    /// `read_frame`, with the gap and the first byte timeout in core clock cycles.
+    fn read_frame_cycles(
+        &mut self,
+        buffer: &mut [u8],
+        gap: u32,
+        first_byte: Option<u64>,
+    ) -> core::result::Result<usize, UartError> {
+        let mut count = 0;
+        let mut error = None;
+        let mut store = |byte: core::result::Result<u8, UartError>| match byte {
+            Ok(byte) if count < buffer.len() => {
+                buffer[count] = byte;
+                count += 1;
+            }
+            Ok(_) => {
+                error.get_or_insert(UartError::Overrun);
+            }
+            Err(first) => {
+                error.get_or_insert(first);
+            }
+        };
+
+        // Summed up, so a timeout longer than the cycle counter's range does not wrap
+        let mut waited = 0_u64;
+        let mut last = crate::profiling::now();
+        let mut last = loop {
+            if let Some(byte) = self.read_checked() {
+                store(byte);
+                break crate::profiling::now();
+            }
+            if let Some(timeout) = first_byte {
+                let now = crate::profiling::now();
+                waited += now.wrapping_sub(last) as u64;
+                last = now;
+                if waited >= timeout {
+                    return Err(UartError::Timeout);
+                }
+            }
+        };
+        while crate::profiling::now().wrapping_sub(last) < gap {
+            if let Some(byte) = self.read_checked() {
+                store(byte);
+                last = crate::profiling::now();
+            }
+        }
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(count),
+        }
+    }
+
+    /// # Pending Error
+    /// The receive error flagged, if any, the overrun first.
+    fn pending_error(&self) -> Option<UartError> {
+        if self.reg.is_receive_fifo_overrun_interrupt_flag_active() {
+            Some(UartError::Overrun)
+        } else if self.reg.is_receive_frame_error_interrupt_flag_active() {
+            Some(UartError::Framing)
+        } else if self.reg.is_receive_parity_error_interrupt_flag_active() {
+            Some(UartError::Parity)
+        } else {
+            None
+        }
+    }
+
+    /// # Clear Error
+    /// Clear the flag of `error`.
+    fn clear_error(&mut self, error: UartError) {
+        unsafe {
+            match error {
+                UartError::Overrun => self.reg.clear_receive_fifo_overrun_interrupt_flag(),
+                UartError::Break | UartError::Framing => {
+                    self.reg.clear_receive_frame_error_interrupt_flag()
+                }
+                UartError::Parity => self.reg.clear_receive_parity_error_interrupt_flag(),
+                // Not flagged by the hardware
+                UartError::Timeout => {}
+            }
+        }
+    }
+
     /// # Read Receive FIFO
     /// Reads from the receive FIFO if possible
     pub fn read_receive_fifo(&mut self) -> Result<u8> {
@@ -376,9 +864,321 @@ impl<Port: private::UARTPortCompatable> UART<Port> {
     }
 }
 
+/// # Baud Divisor
+/// The divisor of a baud generator clocked at `clock`, for `baud_rate`.
+fn baud_divisor(clock: Hertz, baud_rate: Hertz) -> Result<u32> {
+    if baud_rate.raw() == 0 {
+        return Err(ErrorKind::BadParam);
+    }
+
+    match clock / baud_rate {
+        0 => Err(ErrorKind::BadParam),
+        divisor if divisor > MAX_BAUD_DIVISOR => Err(ErrorKind::BadParam),
+        divisor => Ok(divisor),
+    }
+}
+
+impl<Port: private::UARTPortCompatable> crate::pac::IntoPac for UART<Port> {
+    fn free<P: crate::pac::PacPeripheral>(self) -> Result<P> {
+        crate::pac::free_into(self, Port::PORT_PTR)
+    }
+}
+
 impl<Port: private::UARTPortCompatable> core::fmt::Write for UART<Port> {
     fn write_str(&mut self, s: &str) -> core::fmt::Result {
         self.print_string(s);
         Ok(())
     }
 }
+
+#[cfg(feature = "embedded-io")]
+impl<Port: private::UARTPortCompatable> embedded_io::ErrorType for UART<Port> {
+    type Error = crate::Error;
+}
+
+#[cfg(feature = "embedded-io")]
+impl<Port: private::UARTPortCompatable> embedded_io::Write for UART<Port> {
+    fn write(&mut self, buf: &[u8]) -> core::result::Result<usize, Self::Error> {
+        self.write_all(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> core::result::Result<(), Self::Error> {
+        self.flush_transmit();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<Port: private::UARTPortCompatable> embedded_io::WriteReady for UART<Port> {
+    fn write_ready(&mut self) -> core::result::Result<bool, Self::Error> {
+        Ok(!self.reg.get_transmit_fifo_full())
+    }
+}
+
+/// Waits for the first byte, then reads what else the receive FIFO already holds.
+#[cfg(feature = "embedded-io")]
+impl<Port: private::UARTPortCompatable> embedded_io::Read for UART<Port> {
+    fn read(&mut self, buf: &mut [u8]) -> core::result::Result<usize, Self::Error> {
+        let Some((first, rest)) = buf.split_first_mut() else {
+            return Ok(0);
+        };
+
+        *first = loop {
+            if let Some(byte) = self.read_checked() {
+                break byte?;
+            }
+        };
+
+        // Stop before a error, so the next call reports it
+        let mut count = 1;
+        for byte in rest {
+            if self.pending_error().is_some() {
+                break;
+            }
+            match self.read_receive_fifo() {
+                Ok(data) => *byte = data,
+                Err(_) => break,
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<Port: private::UARTPortCompatable> embedded_io::ReadReady for UART<Port> {
+    fn read_ready(&mut self) -> core::result::Result<bool, Self::Error> {
+        Ok(!self.reg.get_receive_fifo_empty())
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl<Port: private::UARTPortCompatable> embedded_hal_0_2::serial::Read<u8> for UART<Port> {
+    type Error = crate::Error;
+
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        match self.read_checked() {
+            Some(byte) => byte.map_err(|error| nb::Error::Other(error.into())),
+            None => Err(nb::Error::WouldBlock),
+        }
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl<Port: private::UARTPortCompatable> embedded_hal_0_2::serial::Write<u8> for UART<Port> {
+    type Error = crate::Error;
+
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        self.write_transmit_fifo(word)
+            .map_err(|_| nb::Error::WouldBlock)
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        if self.reg.get_transmit_fifo_empty() && !self.reg.get_transmit_busy() {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+#[cfg(feature = "eh0")]
+impl<Port: private::UARTPortCompatable> embedded_hal_0_2::blocking::serial::write::Default<u8>
+    for UART<Port>
+{
+}
+
+#[cfg(feature = "eh1")]
+impl<Port: private::UARTPortCompatable> embedded_hal_nb::serial::ErrorType for UART<Port> {
+    type Error = crate::Error;
+}
+
+#[cfg(feature = "eh1")]
+impl<Port: private::UARTPortCompatable> embedded_hal_nb::serial::Read<u8> for UART<Port> {
+    fn read(&mut self) -> nb::Result<u8, Self::Error> {
+        match self.read_checked() {
+            Some(byte) => byte.map_err(|error| nb::Error::Other(error.into())),
+            None => Err(nb::Error::WouldBlock),
+        }
+    }
+}
+
+#[cfg(feature = "eh1")]
+impl<Port: private::UARTPortCompatable> embedded_hal_nb::serial::Write<u8> for UART<Port> {
+    fn write(&mut self, word: u8) -> nb::Result<(), Self::Error> {
+        self.write_transmit_fifo(word)
+            .map_err(|_| nb::Error::WouldBlock)
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        if self.reg.get_transmit_fifo_empty() && !self.reg.get_transmit_busy() {
+            Ok(())
+        } else {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn baud_divisors() {
+        assert_eq!(baud_divisor(IBRO_FREQUENCY, Hertz::Hz(115_200)), Ok(64));
+        assert_eq!(baud_divisor(Hertz::MHz(50), Hertz::Hz(9_600)), Ok(5_208));
+        assert_eq!(
+            baud_divisor(IBRO_FREQUENCY, Hertz::Hz(0)),
+            Err(ErrorKind::BadParam)
+        );
+        assert_eq!(
+            baud_divisor(Hertz::Hz(1_200), Hertz::Hz(9_600)),
+            Err(ErrorKind::BadParam)
+        );
+        assert_eq!(
+            baud_divisor(Hertz::MHz(100), Hertz::Hz(1)),
+            Err(ErrorKind::BadParam)
+        );
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn flow_control_takes_pins() {
+        crate::mock::reset();
+        let mut uart = unsafe { UART::<UART1>::steal() };
+
+        uart.enable_flow_control(HFCDeassertCondition::ExceedsRxThreshold)
+            .unwrap();
+        assert!(crate::gpio::GpioPin::new(crate::gpio::GpioSelect::Gpio0, 15).is_none());
+        assert!(uart.reg.get_hardware_flow_control());
+        assert!(uart.is_clear_to_send());
+
+        uart.set_receive_threshold(12);
+        assert_eq!(uart.reg.get_recieve_fifo_threshold(), 8);
+
+        uart.disable_flow_control();
+        assert!(!uart.reg.get_hardware_flow_control());
+        assert!(crate::gpio::GpioPin::new(crate::gpio::GpioSelect::Gpio0, 15).is_some());
+
+        let mut uart = unsafe { UART::<UART2>::steal() };
+        assert_eq!(
+            uart.enable_flow_control(HFCDeassertCondition::EqualsFIFODepth),
+            Err(ErrorKind::NoDevice)
+        );
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn receive_errors_are_reported() {
+        crate::mock::reset();
+        let mut uart = unsafe { UART::<UART0>::steal() };
+        let flags = mmio::UART_0 + 0x0C;
+        let fifo = mmio::UART_0 + 0x20;
+
+        crate::mock::write(fifo, 0x55);
+        assert_eq!(uart.read_checked(), Some(Ok(0x55)));
+
+        // A framing error on a zero byte is a break, on any other a framing error
+        crate::mock::write(flags, 1 << 0);
+        crate::mock::write(fifo, 0);
+        assert_eq!(uart.read_checked(), Some(Err(UartError::Break)));
+        crate::mock::write(fifo, 0x55);
+        crate::mock::write(flags, 1 << 0);
+        assert_eq!(uart.read_checked(), Some(Err(UartError::Framing)));
+
+        crate::mock::write(flags, (1 << 3) | (1 << 1));
+        assert_eq!(uart.take_error(), Some(UartError::Overrun));
+        assert_eq!(crate::mock::read(flags) & (1 << 3), 1 << 3);
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn frames_end_on_idle() {
+        crate::mock::reset();
+        let mut uart = unsafe { UART::<UART0>::steal() };
+        let fifo = mmio::UART_0 + 0x20;
+
+        // The cycle counter stands still in the mock, so only a zero gap ends
+        crate::mock::write(fifo, 0x01);
+        let mut frame = [0; 4];
+        assert_eq!(uart.read_frame_cycles(&mut frame, 0, None), Ok(1));
+        assert_eq!(frame[0], 0x01);
+        assert_eq!(
+            uart.read_frame_cycles(&mut [], 0, None),
+            Err(UartError::Overrun)
+        );
+
+        crate::mock::write(mmio::UART_0 + 0x0C, 1 << 1);
+        assert_eq!(
+            uart.read_frame_cycles(&mut frame, 0, None),
+            Err(UartError::Parity)
+        );
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn frames_time_out_on_silence() {
+        crate::mock::reset();
+        let mut uart = unsafe { UART::<UART0>::steal() };
+
+        // Nothing ever arrives, the receive FIFO stays empty
+        crate::mock::write(mmio::UART_0 + 0x04, 1 << 4);
+        let mut frame = [0; 4];
+        assert_eq!(
+            uart.read_frame_cycles(&mut frame, 0, Some(0)),
+            Err(UartError::Timeout)
+        );
+
+        assert_eq!(ErrorKind::from(UartError::Timeout), ErrorKind::TimeOut);
+    }
+
+    #[test]
+    fn clock_sources_per_port() {
+        assert_eq!(ClockSources::PCLK.bits(0), Some(0));
+        assert_eq!(ClockSources::IBRO.bits(2), Some(2));
+        assert_eq!(ClockSources::ERTCO.bits(1), None);
+        // The low power UART numbers its sources differently, and has no PCLK
+        assert_eq!(ClockSources::IBRO.bits(3), Some(0));
+        assert_eq!(ClockSources::ERTCO.bits(3), Some(1));
+        assert_eq!(ClockSources::PCLK.bits(3), None);
+        assert_eq!(baud_divisor(ERTCO_FREQUENCY, Hertz::Hz(2_400)), Ok(13));
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn blocking_transfers() {
+        crate::mock::reset();
+        let mut uart = unsafe { UART::<UART1>::steal() };
+
+        uart.write_all(b"hi");
+        assert_eq!(crate::mock::read(mmio::UART_1 + 0x20), b'i' as u32);
+
+        // The receive FIFO reads as not empty, holding one byte
+        crate::mock::write(mmio::UART_1 + 0x20, b'x' as u32);
+        let mut buffer = [0; 2];
+        uart.read_exact(&mut buffer);
+        assert_eq!(&buffer, b"xx");
+    }
+
+    #[cfg(all(feature = "mock-mmio", feature = "embedded-io"))]
+    #[test]
+    fn embedded_io_traits() {
+        use embedded_io::{Read, ReadReady, Write, WriteReady};
+
+        crate::mock::reset();
+        let mut uart = unsafe { UART::<UART2>::steal() };
+        assert!(uart.write_ready().unwrap());
+        assert_eq!(Write::write(&mut *uart, b"ok").unwrap(), 2);
+
+        crate::mock::write(mmio::UART_2 + 0x20, b'y' as u32);
+        assert!(uart.read_ready().unwrap());
+        let mut buffer = [0; 3];
+        assert_eq!(uart.read(&mut buffer).unwrap(), 3);
+        assert_eq!(uart.read(&mut []).unwrap(), 0);
+
+        // Status bit 4, the receive FIFO empty
+        crate::mock::write(mmio::UART_2 + 0x04, 1 << 4);
+        assert!(!uart.read_ready().unwrap());
+    }
+}
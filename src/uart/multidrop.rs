@@ -0,0 +1,207 @@
+//! # Multidrop UART
+//! Share a RS-485 bus between many nodes, addressing them with 9-bit characters: a
+//! character with its ninth bit set is a address, the data characters after it are
+//! for the node with that address only.
+//!
+//! The UART has no 9-bit mode, so the parity bit carries the ninth bit. To send it the
+//! parity is switched between odd and even to make the parity bit come out as the
+//! ninth bit. On receive the parity is even, so a parity error means the ninth bit
+//! differs from the even parity bit. Address matching is done in software by a
+//! `AddressFilter`, so every character still raises the receive interrupt.
+//!
+//! The parity error flag is shared by the whole receive FIFO, so read every character
+//! as soon as it arrives, ie. from the receive interrupt with a threshold of one.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::uart::multidrop::AddressFilter;
+//! # use max78000_hal::uart::{BaudRates, CharacterLength, Parity, ParityValueSelect, StopBits, UART};
+//! # let mut uart = UART::port_0_init(BaudRates::Baud115200, CharacterLength::EightBits, StopBits::OneBit, false, Parity::Odd, ParityValueSelect::OneBased, false)?;
+//! # fn handle(_: u8) {}
+//!
+//! uart.enable_multidrop();
+//! let mut filter = AddressFilter::new(0x12);
+//!
+//! // In the receive interrupt
+//! while let Some(word) = uart.read_nine_bit() {
+//!     if let Some(byte) = filter.accept(word) {
+//!         handle(byte);
+//!     }
+//! }
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::{private, UART};
+
+/// # Address Bit
+/// The ninth bit of a character, set for addresses.
+pub const ADDRESS_BIT: u16 = 1 << 8;
+
+/// # Broadcast Address
+/// The address every `AddressFilter` accepts.
+pub const BROADCAST_ADDRESS: u8 = 0xFF;
+
+/// # Even Parity Bit
+/// The parity bit even parity (counting ones) sends with `byte`.
+const fn even_parity_bit(byte: u8) -> bool {
+    byte.count_ones() % 2 == 1
+}
+
+impl<Port: private::UARTPortCompatable> UART<Port> {
+    /// # Enable Multidrop
+    /// Send and receive 9-bit characters, with the parity bit as the ninth bit. The
+    /// UART must use eight data bits.
+    pub fn enable_multidrop(&mut self) {
+        self.flush_transmit();
+        unsafe {
+            self.reg.set_transmit_parity_generation_enable(true);
+            self.reg.set_parity_value(false);
+            self.reg.set_parity_odd_even(false);
+            self.reg.clear_receive_parity_error_interrupt_flag();
+        }
+    }
+
+    /// # Write Nine Bit
+    /// Send the low nine bits of `word`, waiting for the characters before it to be
+    /// sent when the parity has to change.
+    pub fn write_nine_bit(&mut self, word: u16) {
+        let byte = word as u8;
+        let odd = (word & ADDRESS_BIT != 0) != even_parity_bit(byte);
+
+        if self.reg.get_parity_odd_even() != odd {
+            self.flush_transmit();
+            unsafe { self.reg.set_parity_odd_even(odd) };
+        }
+        while self.reg.get_transmit_fifo_full() {}
+        unsafe { self.reg.set_fifo_data(byte) };
+    }
+
+    /// # Write Address
+    /// Select the node at `address`, see `AddressFilter`.
+    pub fn write_address(&mut self, address: u8) {
+        self.write_nine_bit(ADDRESS_BIT | address as u16);
+    }
+
+    /// # Write Data
+    /// Send `bytes` to the selected node.
+    pub fn write_data(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.write_nine_bit(*byte as u16);
+        }
+    }
+
+    /// # Read Nine Bit
+    /// Read a 9-bit character, or `None` when the receive FIFO is empty. Sending leaves
+    /// the parity odd after some characters, so wait for them to be sent (ie. with
+    /// `flush_transmit`) before receiving.
+    pub fn read_nine_bit(&mut self) -> Option<u16> {
+        if self.reg.get_receive_fifo_empty() {
+            return None;
+        }
+
+        let byte = self.reg.get_fifo_data();
+        let mismatch = self.reg.is_receive_parity_error_interrupt_flag_active();
+        if mismatch {
+            unsafe { self.reg.clear_receive_parity_error_interrupt_flag() };
+        }
+
+        // The sender chose the parity bit, so a error only says it differs from even
+        let parity_bit = even_parity_bit(byte) != self.reg.get_parity_odd_even();
+        let ninth = parity_bit != mismatch;
+        Some(byte as u16 | if ninth { ADDRESS_BIT } else { 0 })
+    }
+}
+
+/// # Address Filter
+/// Keeps the data characters sent to one node, dropping the rest.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AddressFilter {
+    address: u8,
+    selected: bool,
+}
+
+impl AddressFilter {
+    /// # New
+    /// Keep the data sent to `address` (or `BROADCAST_ADDRESS`).
+    pub const fn new(address: u8) -> Self {
+        Self {
+            address,
+            selected: false,
+        }
+    }
+
+    /// # Accept
+    /// Take a received 9-bit character, and give back its data if it is for this node.
+    /// Addresses are taken, but never given back.
+    pub fn accept(&mut self, word: u16) -> Option<u8> {
+        let byte = word as u8;
+        if word & ADDRESS_BIT != 0 {
+            self.selected = byte == self.address || byte == BROADCAST_ADDRESS;
+            None
+        } else if self.selected {
+            Some(byte)
+        } else {
+            None
+        }
+    }
+
+    /// # Is Selected
+    /// Check if the last address was this node's.
+    pub fn is_selected(&self) -> bool {
+        self.selected
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn filters_by_address() {
+        let mut filter = AddressFilter::new(0x12);
+        assert_eq!(filter.accept(0x41), None);
+        assert_eq!(filter.accept(ADDRESS_BIT | 0x12), None);
+        assert!(filter.is_selected());
+        assert_eq!(filter.accept(0x41), Some(0x41));
+        assert_eq!(filter.accept(ADDRESS_BIT | 0x13), None);
+        assert_eq!(filter.accept(0x42), None);
+        assert_eq!(filter.accept(ADDRESS_BIT | BROADCAST_ADDRESS as u16), None);
+        assert_eq!(filter.accept(0x43), Some(0x43));
+    }
+
+    #[cfg(feature = "mock-mmio")]
+    #[test]
+    fn ninth_bit_rides_the_parity() {
+        use crate::memory_map::mmio;
+        use crate::uart::UART1;
+        use core::mem::ManuallyDrop;
+
+        crate::mock::reset();
+        let status = mmio::UART_1 + 0x04;
+        let flags = mmio::UART_1 + 0x0C;
+        let fifo = mmio::UART_1 + 0x20;
+        let mut uart = ManuallyDrop::into_inner(unsafe { UART::<UART1>::steal() });
+        // The transmit FIFO empty, so parity changes do not wait
+        crate::mock::write(status, 1 << 6);
+        uart.enable_multidrop();
+
+        // 0x12 has an even number of ones, the address bit needs odd parity
+        uart.write_address(0x12);
+        assert!(uart.reg.get_parity_odd_even());
+        uart.write_data(&[0x12]);
+        assert!(!uart.reg.get_parity_odd_even());
+        assert_eq!(crate::mock::read(fifo), 0x12);
+
+        // Received with even parity, a parity error marks the ninth bit. The mock
+        // keeps cleared flags set, so play the hardware's part.
+        crate::mock::write(flags, 0);
+        assert_eq!(uart.read_nine_bit(), Some(0x12));
+        crate::mock::write(flags, 1 << 1);
+        assert_eq!(uart.read_nine_bit(), Some(ADDRESS_BIT | 0x12));
+
+        crate::mock::write(status, 1 << 4);
+        assert_eq!(uart.read_nine_bit(), None);
+
+        // Stolen pins, never release them
+        core::mem::forget(uart);
+    }
+}
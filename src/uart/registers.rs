@@ -25,14 +25,19 @@ mod rro {
     pub const UART_FIFO: usize = 0x0020;
     /// # UART DMA Control Register
     pub const UART_DMA: usize = 0x0030;
-    /// # UART Wakeup Interrupt Enable Register
+    /// # UART Wakeup Interrupt Enable Register (LPUART only)
     pub const UART_WKEN: usize = 0x0034;
-    /// # UART Wakeup Interrupt Flag Register
+    /// # UART Wakeup Interrupt Flag Register (LPUART only)
     pub const UART_WKFL: usize = 0x0038;
 }
 
 make_device! {
-    device_ports(mmio::UART_0, mmio::UART_1, mmio::UART_2);
+    device_ports(
+        mmio::UART_0,
+        mmio::UART_1,
+        mmio::UART_2,
+        mmio::LOW_POWER_UART_0
+    );
     /// Receive Dual Edge Sampling. See Page 180, Table 12-8.
     /// This feature can **only** be used with `LPUART`
     /// Can choose to sample only on the rising edge, or both the rising and falling edges.
@@ -441,6 +446,7 @@ make_device! {
     /// - 0: Disabled
     /// - 1: Enabled
     #[bit(2, RW, rro::UART_WKEN)]
+    #[instances(mmio::LOW_POWER_UART_0)]
     receive_fifo_threshold_wakeup_event_enable,
 
     /// Receive FIFO Full Wake-up Event Enable. See Page 186, Table 12-18.
@@ -448,6 +454,7 @@ make_device! {
     /// - 0: Disabled
     /// - 1: Enabled
     #[bit(1, RW, rro::UART_WKEN)]
+    #[instances(mmio::LOW_POWER_UART_0)]
     receive_fifo_full_wakeup_event_enable,
 
     /// Receive FIFO Not Empty Wake-up Event Enable. See Page 186, Table 12-18.
@@ -455,6 +462,7 @@ make_device! {
     /// - 0: Disabled
     /// - 1: Enabled
     #[bit(0, RW, rro::UART_WKEN)]
+    #[instances(mmio::LOW_POWER_UART_0)]
     receive_fifo_not_empty_wakeup_event_enable,
 
     /// The UART Wakeup Flag register. See Page 186, Table 12-19.
@@ -463,6 +471,7 @@ make_device! {
     /// - 0: Disabled
     /// - 1: Enabled
     #[bit(2, RW, rro::UART_WKFL)]
+    #[instances(mmio::LOW_POWER_UART_0)]
     receive_fifo_threshold_wakeup_event,
 
     /// Receive FIFO Full Wake-up Event. See Page 186, Table 12-19.
@@ -470,6 +479,7 @@ make_device! {
     /// - 0: Disabled
     /// - 1: Enabled
     #[bit(1, RW, rro::UART_WKFL)]
+    #[instances(mmio::LOW_POWER_UART_0)]
     receive_fifo_full_wakeup_event,
 
     /// Receive FIFO Not Empty Wake-up Event. See Page 186, Table 12-19.
@@ -477,5 +487,6 @@ make_device! {
     /// - 0: Disabled
     /// - 1: Enabled
     #[bit(0, RW, rro::UART_WKFL)]
+    #[instances(mmio::LOW_POWER_UART_0)]
     receive_fifo_not_empty_wakeup_event,
 }
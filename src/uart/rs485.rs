@@ -0,0 +1,142 @@
+//! # RS-485
+//! Drive a RS-485 transceiver's direction from a GPIO pin. The transceiver only drives
+//! the bus while its driver enable (DE) input is asserted, and only receives while its
+//! receiver enable (RE) input is not, so the two are usually tied together onto one
+//! pin. `Rs485` asserts it before sending, and releases it once the last stop bit left
+//! the UART, so the bus is free for the answer right away.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::error::ErrorKind;
+//! use max78000_hal::gpio::{GpioPin, GpioSelect};
+//! use max78000_hal::uart::rs485::Rs485;
+//! # use max78000_hal::uart::{BaudRates, CharacterLength, Parity, ParityValueSelect, StopBits, UART};
+//! # let mut uart = UART::port_0_init(BaudRates::Baud115200, CharacterLength::EightBits, StopBits::OneBit, false, Parity::Odd, ParityValueSelect::OneBased, false)?;
+//!
+//! let de = GpioPin::new(GpioSelect::Gpio0, 19).ok_or(ErrorKind::Busy)?;
+//! let mut bus = Rs485::new(uart, de, false);
+//!
+//! bus.write(b"\x01\x03\x00\x00\x00\x01");
+//! let mut reply = [0; 7];
+//! bus.read_exact(&mut reply);
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+use super::{private, UART};
+use crate::gpio::{GpioPin, OutputDriveStrength, PinFunction, VoltageSelect};
+
+/// # RS-485
+/// A UART with a transceiver direction pin.
+pub struct Rs485<Port> {
+    uart: UART<Port>,
+    pin: GpioPin,
+    active_low: bool,
+}
+
+impl<Port: private::UARTPortCompatable> Rs485<Port> {
+    /// # New
+    /// Configure `pin` as the DE/RE output of the transceiver, starting out receiving.
+    /// With `active_low` the pin is driven low to send.
+    pub fn new(uart: UART<Port>, pin: GpioPin, active_low: bool) -> Self {
+        pin.configure_output(
+            OutputDriveStrength::Strength0(VoltageSelect::VddIO),
+            PinFunction::IO,
+        );
+
+        let mut bus = Self {
+            uart,
+            pin,
+            active_low,
+        };
+        bus.set_driving(false);
+        bus
+    }
+
+    /// # Write
+    /// Enable the driver, send `bytes`, and wait until the last of them was sent before
+    /// switching back to receiving. Whatever the transceiver echoed meanwhile is
+    /// flushed.
+    pub fn write(&mut self, bytes: &[u8]) {
+        self.set_driving(true);
+        self.uart.write_all(bytes);
+        self.uart.flush_transmit();
+        self.set_driving(false);
+        self.uart.clear_rx_fifo();
+    }
+
+    /// # Read Exact
+    /// Read exactly `buffer.len()` bytes from the bus, waiting for each of them.
+    pub fn read_exact(&mut self, buffer: &mut [u8]) {
+        self.uart.read_exact(buffer);
+    }
+
+    /// # Is Driving
+    /// Check if the transceiver is currently driving the bus.
+    pub fn is_driving(&self) -> bool {
+        self.pin.get_output() != self.active_low
+    }
+
+    /// # Free
+    /// Switch the transceiver to receiving, and give back the UART and the pin.
+    pub fn free(mut self) -> (UART<Port>, GpioPin) {
+        self.set_driving(false);
+        (self.uart, self.pin)
+    }
+
+    /// # Set Driving
+    /// Switch the transceiver to sending (`true`) or receiving.
+    fn set_driving(&mut self, driving: bool) {
+        self.pin.set_output(driving != self.active_low);
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<Port: private::UARTPortCompatable> embedded_io::ErrorType for Rs485<Port> {
+    type Error = crate::Error;
+}
+
+#[cfg(feature = "embedded-io")]
+impl<Port: private::UARTPortCompatable> embedded_io::Write for Rs485<Port> {
+    fn write(&mut self, buf: &[u8]) -> core::result::Result<usize, Self::Error> {
+        Rs485::write(self, buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> core::result::Result<(), Self::Error> {
+        // `write` only returns once everything was sent
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+    use crate::gpio::GpioSelect;
+    use crate::memory_map::mmio;
+    use crate::uart::UART0;
+    use core::mem::ManuallyDrop;
+
+    #[test]
+    fn direction_follows_writes() {
+        crate::gpio::reset_mock();
+        // The transmit FIFO empty, so writes finish right away
+        crate::mock::write(mmio::UART_0 + 0x04, 1 << 6);
+        let uart = ManuallyDrop::into_inner(unsafe { UART::<UART0>::steal() });
+        let pin = GpioPin::new(GpioSelect::Gpio0, 19).unwrap();
+
+        let mut bus = Rs485::new(uart, pin, true);
+        assert!(!bus.is_driving());
+        assert!(bus.pin.get_output());
+
+        bus.write(b"hi");
+        assert_eq!(crate::mock::read(mmio::UART_0 + 0x20), b'i' as u32);
+        assert!(!bus.is_driving());
+
+        bus.set_driving(true);
+        assert!(!bus.pin.get_output());
+
+        // Stolen pins, never release them
+        let (uart, pin) = bus.free();
+        assert!(pin.get_output());
+        core::mem::forget(uart);
+    }
+}
@@ -0,0 +1,297 @@
+//! # UART Wait
+//! Send and receive without spinning on the FIFOs. The UART's interrupt wakes the
+//! waiting task when a byte was received, or the transmit FIFO has room again, so an
+//! async executor can run other tasks (or sleep the core) meanwhile. With the `async`
+//! feature a `UART` also implements `embedded_io_async::{Read, Write}`.
+//!
+//! Bind `InterruptHandler` to the interrupts of the UARTs you wait on, the NVIC line is
+//! enabled by the first wait on that UART. A UART is either waited on or buffered, so
+//! bind this or a handler calling `BufferedUart::on_interrupt`, not both.
+//!
+//! # Example
+//! ```no_run
+//! use max78000_hal::bind_interrupts;
+//! use max78000_hal::uart::{wait, UartError, UART, UART0};
+//!
+//! bind_interrupts!(struct Irqs {
+//!     UART0 => wait::InterruptHandler;
+//! });
+//!
+//! async fn ping(uart: &mut UART<UART0>) -> Result<usize, UartError> {
+//!     uart.write_async(b"ping\r\n").await;
+//!     let mut reply = [0; 16];
+//!     let count = uart.read_async(&mut reply).await?;
+//!     Ok(count)
+//! }
+//! ```
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+use super::{private, registers, UartError, UART};
+use crate::interrupt::typelevel::{Handler, UART0, UART1, UART2, UART3};
+use crate::interrupt::{Interrupt, InterruptExt};
+use crate::memory_map::mmio;
+
+/// The waker of the task waiting on every UART. A slot is only written while its UART's
+/// interrupts are disabled, and only taken by the interrupt handler while they are
+/// enabled.
+static mut WAKERS: [Option<Waker>; 4] = [const { None }; 4];
+
+/// # Interrupt Handler
+/// Wakes the task waiting on the UART. Bind it to `UART0` to `UART3`.
+pub struct InterruptHandler;
+
+impl Handler<UART0> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch(mmio::UART_0, 0);
+    }
+}
+
+impl Handler<UART1> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch(mmio::UART_1, 1);
+    }
+}
+
+impl Handler<UART2> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch(mmio::UART_2, 2);
+    }
+}
+
+impl Handler<UART3> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        dispatch(mmio::LOW_POWER_UART_0, 3);
+    }
+}
+
+/// Disable the interrupts of the UART at `address`, and wake its task if one of them
+/// fired.
+unsafe fn dispatch(address: usize, index: usize) {
+    let mut reg = registers::Registers::new(address);
+
+    let receive = reg.get_receive_fifo_thershold_event()
+        && (reg.is_receive_fifo_threshold_interrupt_flag_active()
+            || reg.is_receive_fifo_overrun_interrupt_flag_active());
+    let transmit = reg.get_transmit_fifo_half_empty_event()
+        && reg.is_transmit_fifo_half_empty_interrupt_flag_active();
+
+    if receive || transmit {
+        enable_events(&mut reg, false, false);
+        reg.clear_receive_fifo_threshold_interrupt_flag();
+        reg.clear_transmit_fifo_half_empty_interrupt_flag();
+        if let Some(waker) = WAKERS[index].take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Enable/Disable the receive and transmit interrupts waits use.
+unsafe fn enable_events(reg: &mut registers::Registers, receive: bool, transmit: bool) {
+    reg.set_receive_fifo_thershold_event(receive);
+    reg.set_receive_fifo_overrun_event(receive);
+    reg.set_transmit_fifo_half_empty_event(transmit);
+}
+
+/// The NVIC line of UART `port`.
+fn uart_interrupt(port: usize) -> Interrupt {
+    match port {
+        0 => Interrupt::UART0,
+        1 => Interrupt::UART1,
+        2 => Interrupt::UART2,
+        _ => Interrupt::UART3,
+    }
+}
+
+/// # Event
+/// What a `EventFuture` waits for.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Event {
+    /// A byte, or a error, was received.
+    Receive,
+    /// The transmit FIFO has room.
+    Transmit,
+    /// The transmit FIFO is empty.
+    TransmitEmpty,
+}
+
+/// # Event Future
+/// Completes once `event` happened.
+#[must_use = "futures do nothing unless polled"]
+struct EventFuture<'a, Port: private::UARTPortCompatable> {
+    uart: &'a mut UART<Port>,
+    event: Event,
+    armed: bool,
+}
+
+impl<Port: private::UARTPortCompatable> EventFuture<'_, Port> {
+    /// Check if the event happened.
+    fn is_ready(&self) -> bool {
+        let reg = &self.uart.reg;
+        match self.event {
+            Event::Receive => !reg.get_receive_fifo_empty() || self.uart.pending_error().is_some(),
+            Event::Transmit => !reg.get_transmit_fifo_full(),
+            Event::TransmitEmpty => reg.get_transmit_fifo_empty(),
+        }
+    }
+
+    /// Enable/Disable the interrupt of the event.
+    fn listen(&mut self, enable: bool) {
+        let receive = enable && self.event == Event::Receive;
+        let transmit = enable && self.event != Event::Receive;
+        unsafe { enable_events(&mut self.uart.reg, receive, transmit) };
+    }
+}
+
+impl<Port: private::UARTPortCompatable> Future for EventFuture<'_, Port> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.armed {
+            // Stop the handler from touching the waker while it is replaced
+            self.listen(false);
+        } else {
+            unsafe { uart_interrupt(Port::PORT_NUM).enable() };
+            self.armed = true;
+        }
+
+        if self.is_ready() {
+            self.armed = false;
+            return Poll::Ready(());
+        }
+
+        unsafe { WAKERS[Port::PORT_NUM] = Some(cx.waker().clone()) };
+        self.listen(true);
+
+        Poll::Pending
+    }
+}
+
+impl<Port: private::UARTPortCompatable> Drop for EventFuture<'_, Port> {
+    fn drop(&mut self) {
+        if self.armed {
+            self.listen(false);
+            unsafe { WAKERS[Port::PORT_NUM] = None };
+        }
+    }
+}
+
+impl<Port: private::UARTPortCompatable> UART<Port> {
+    /// The future of `event`.
+    fn wait_for(&mut self, event: Event) -> EventFuture<'_, Port> {
+        EventFuture {
+            uart: self,
+            event,
+            armed: false,
+        }
+    }
+
+    /// # Read Async
+    /// Wait for a byte, then read what else the receive FIFO already holds into
+    /// `buffer`, returning how many were read. `InterruptHandler` must be bound to the
+    /// UART's interrupt.
+    ///
+    /// # Errors
+    /// The receive error flagged with the first byte, see `read_checked`.
+    pub async fn read_async(
+        &mut self,
+        buffer: &mut [u8],
+    ) -> core::result::Result<usize, UartError> {
+        let Some((first, rest)) = buffer.split_first_mut() else {
+            return Ok(0);
+        };
+
+        *first = loop {
+            if let Some(byte) = self.read_checked() {
+                break byte?;
+            }
+            self.wait_for(Event::Receive).await;
+        };
+
+        // Stop before a error, so the next call reports it
+        let mut count = 1;
+        for byte in rest {
+            if self.pending_error().is_some() {
+                break;
+            }
+            match self.read_receive_fifo() {
+                Ok(data) => *byte = data,
+                Err(_) => break,
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// # Write Async
+    /// Write every byte of `bytes`, waiting for room in the transmit FIFO. Returns once
+    /// the last byte is queued. `InterruptHandler` must be bound to the UART's
+    /// interrupt.
+    pub async fn write_async(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            if self.reg.get_transmit_fifo_full() {
+                self.wait_for(Event::Transmit).await;
+            }
+            unsafe { self.reg.set_fifo_data(*byte) };
+        }
+    }
+
+    /// # Flush Async
+    /// Wait until the transmit FIFO is empty, then spin while its last byte is shifted
+    /// out.
+    pub async fn flush_async(&mut self) {
+        self.wait_for(Event::TransmitEmpty).await;
+        self.flush_transmit();
+    }
+}
+
+#[cfg(feature = "async")]
+impl<Port: private::UARTPortCompatable> embedded_io_async::Read for UART<Port> {
+    async fn read(&mut self, buf: &mut [u8]) -> core::result::Result<usize, Self::Error> {
+        self.read_async(buf).await.map_err(Into::into)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<Port: private::UARTPortCompatable> embedded_io_async::Write for UART<Port> {
+    async fn write(&mut self, buf: &[u8]) -> core::result::Result<usize, Self::Error> {
+        self.write_async(buf).await;
+        Ok(buf.len())
+    }
+
+    async fn flush(&mut self) -> core::result::Result<(), Self::Error> {
+        self.flush_async().await;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "mock-mmio"))]
+mod test {
+    use super::*;
+
+    /// Bit 4 of `UART_STATUS`, the receive FIFO empty.
+    const RECEIVE_FIFO_EMPTY: u32 = 1 << 4;
+
+    #[test]
+    fn interrupt_wakes_receive() {
+        crate::mock::reset();
+        let status = mmio::UART_2 + 0x04;
+        let flags = mmio::UART_2 + 0x0C;
+        let mut uart = unsafe { UART::<crate::uart::UART2>::steal() };
+
+        crate::mock::write(status, RECEIVE_FIFO_EMPTY);
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut future = uart.wait_for(Event::Receive);
+        assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Pending);
+        assert!(Interrupt::UART2.is_enabled());
+        assert!(future.uart.reg.get_receive_fifo_thershold_event());
+
+        // A byte arrives
+        crate::mock::write(status, 0);
+        crate::mock::write(flags, 1 << 4);
+        unsafe { <InterruptHandler as Handler<UART2>>::on_interrupt() };
+        assert!(!future.uart.reg.get_receive_fifo_thershold_event());
+        assert_eq!(Pin::new(&mut future).poll(&mut cx), Poll::Ready(()));
+    }
+}
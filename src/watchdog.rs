@@ -0,0 +1,38 @@
+//! # Watchdog
+//! Helpers for keeping a watchdog fed during legitimately long operations.
+//!
+//! Drivers with operations that can outlast a watchdog period (ie. erasing or writing
+//! a whole flash image) take a `&mut dyn Feed`, and call it at points where the
+//! hardware is idle and it is safe to do so. A closure works as a `Feed`:
+//!
+//! ```no_run
+//! # use max78000_hal::flc::Flc;
+//! # struct Watchdog;
+//! # impl Watchdog { fn feed(&mut self) {} }
+//! # const IMAGE_START: usize = 0x1004_0000;
+//! # let (mut flc, mut wdt, image) = (Flc::init(), Watchdog, [0_u8; 16]);
+//! unsafe { flc.write_feeding(IMAGE_START, &image, &mut || wdt.feed())? };
+//! # Ok::<(), max78000_hal::error::ErrorKind>(())
+//! ```
+
+/// # Feed
+/// Something to call periodically during a long operation, ie. to feed a watchdog.
+pub trait Feed {
+    /// # Feed
+    /// Called between steps of a long operation.
+    fn feed(&mut self);
+}
+
+impl<F: FnMut()> Feed for F {
+    fn feed(&mut self) {
+        self()
+    }
+}
+
+/// # No Feed
+/// A `Feed` that does nothing, for when no watchdog is running.
+pub struct NoFeed;
+
+impl Feed for NoFeed {
+    fn feed(&mut self) {}
+}